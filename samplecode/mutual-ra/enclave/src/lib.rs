@@ -35,12 +35,8 @@ extern crate webpki;
 extern crate itertools;
 extern crate base64;
 extern crate httparse;
-extern crate yasna;
-extern crate bit_vec;
-extern crate num_bigint;
-extern crate serde_json;
-extern crate chrono;
 extern crate webpki_roots;
+extern crate sgx_tra_tls;
 
 use std::backtrace::{self, PrintFormat};
 use sgx_types::*;
@@ -60,14 +56,13 @@ use std::io::{Write, Read};
 use std::untrusted::fs;
 use std::vec::Vec;
 use itertools::Itertools;
+use sgx_tra_tls::{AttestationEvidence, AttestationPolicy, QuoteStatus, RaTlsVerifier};
 
-mod cert;
 mod hex;
 
 pub const DEV_HOSTNAME:&'static str = "api.trustedservices.intel.com";
 pub const SIGRL_SUFFIX:&'static str = "/sgx/dev/attestation/v3/sigrl/";
 pub const REPORT_SUFFIX:&'static str = "/sgx/dev/attestation/v3/report";
-pub const CERTEXPIRYDAYS: i64 = 90i64;
 
 extern "C" {
     pub fn ocall_sgx_init_quote ( ret_val : *mut sgx_status_t,
@@ -89,67 +84,6 @@ extern "C" {
 }
 
 
-fn parse_response_attn_report(resp : &[u8]) -> (String, String, String){
-    println!("parse_response_attn_report");
-    let mut headers = [httparse::EMPTY_HEADER; 16];
-    let mut respp   = httparse::Response::new(&mut headers);
-    let result = respp.parse(resp);
-    println!("parse result {:?}", result);
-
-    let msg : &'static str;
-
-    match respp.code {
-        Some(200) => msg = "OK Operation Successful",
-        Some(401) => msg = "Unauthorized Failed to authenticate or authorize request.",
-        Some(404) => msg = "Not Found GID does not refer to a valid EPID group ID.",
-        Some(500) => msg = "Internal error occurred",
-        Some(503) => msg = "Service is currently not able to process the request (due to
-            a temporary overloading or maintenance). This is a
-            temporary state – the same request can be repeated after
-            some time. ",
-        _ => {println!("DBG:{}", respp.code.unwrap()); msg = "Unknown error occured"},
-    }
-
-    println!("{}", msg);
-    let mut len_num : u32 = 0;
-
-    let mut sig = String::new();
-    let mut cert = String::new();
-    let mut attn_report = String::new();
-
-    for i in 0..respp.headers.len() {
-        let h = respp.headers[i];
-        //println!("{} : {}", h.name, str::from_utf8(h.value).unwrap());
-        match h.name{
-            "Content-Length" => {
-                let len_str = String::from_utf8(h.value.to_vec()).unwrap();
-                len_num = len_str.parse::<u32>().unwrap();
-                println!("content length = {}", len_num);
-            }
-            "X-IASReport-Signature" => sig = str::from_utf8(h.value).unwrap().to_string(),
-            "X-IASReport-Signing-Certificate" => cert = str::from_utf8(h.value).unwrap().to_string(),
-            _ => (),
-        }
-    }
-
-    // Remove %0A from cert, and only obtain the signing cert
-    cert = cert.replace("%0A", "");
-    cert = cert::percent_decode(cert);
-    let v: Vec<&str> = cert.split("-----").collect();
-    let sig_cert = v[2].to_string();
-
-    if len_num != 0 {
-        let header_len = result.unwrap().unwrap();
-        let resp_body = &resp[header_len..];
-        attn_report = str::from_utf8(resp_body).unwrap().to_string();
-        println!("Attestation report: {}", attn_report);
-    }
-
-    // len_num == 0
-    (attn_report, sig, sig_cert)
-}
-
-
 fn parse_response_sigrl(resp : &[u8]) -> Vec<u8> {
     println!("parse_response_sigrl");
     let mut headers = [httparse::EMPTY_HEADER; 16];
@@ -245,20 +179,13 @@ pub fn get_sigrl_from_intel(fd : c_int, gid : u32) -> Vec<u8> {
 }
 
 // TODO: support pse
-pub fn get_report_from_intel(fd : c_int, quote : Vec<u8>) -> (String, String, String) {
+pub fn get_report_from_intel(fd : c_int, quote : Vec<u8>) -> Result<AttestationEvidence, sgx_status_t> {
     println!("get_report_from_intel fd = {:?}", fd);
     let config = make_ias_client_config();
-    let encoded_quote = base64::encode(&quote[..]);
-    let encoded_json = format!("{{\"isvEnclaveQuote\":\"{}\"}}\r\n", encoded_quote);
-
     let ias_key = get_ias_api_key();
 
-    let req = format!("POST {} HTTP/1.1\r\nHOST: {}\r\nOcp-Apim-Subscription-Key:{}\r\nContent-Length:{}\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n{}",
-                           REPORT_SUFFIX,
-                           DEV_HOSTNAME,
-                           ias_key,
-                           encoded_json.len(),
-                           encoded_json);
+    let req = sgx_tra_tls::ias::report_request(DEV_HOSTNAME, REPORT_SUFFIX, &ias_key, &quote, None)
+        .map_err(|_| sgx_status_t::SGX_ERROR_UNEXPECTED)?;
     println!("{}", req);
     let dns_name = webpki::DNSNameRef::try_from_ascii_str(DEV_HOSTNAME).unwrap();
     let mut sess = rustls::ClientSession::new(&Arc::new(config), dns_name);
@@ -272,13 +199,17 @@ pub fn get_report_from_intel(fd : c_int, quote : Vec<u8>) -> (String, String, St
 
     tls.read_to_end(&mut plaintext).unwrap();
     println!("read_to_end complete");
-    let resp_string = String::from_utf8(plaintext.clone()).unwrap();
-
-    println!("resp_string = {}", resp_string);
 
-    let (attn_report, sig, cert) = parse_response_attn_report(&plaintext);
-
-    (attn_report, sig, cert)
+    match sgx_tra_tls::ias::parse_report_response(&plaintext) {
+        Ok(evidence) => {
+            println!("Attestation report: {}", String::from_utf8_lossy(&evidence.report));
+            Ok(evidence)
+        },
+        Err(e) => {
+            println!("Error in parse_report_response: {}", e);
+            Err(e.into())
+        }
+    }
 }
 
 fn as_u32_le(array: &[u8; 4]) -> u32 {
@@ -289,7 +220,7 @@ fn as_u32_le(array: &[u8; 4]) -> u32 {
 }
 
 #[allow(const_err)]
-pub fn create_attestation_report(pub_k: &sgx_ec256_public_t, sign_type: sgx_quote_sign_type_t) -> Result<(String, String, String), sgx_status_t> {
+pub fn create_attestation_report(pub_k: &sgx_ec256_public_t, sign_type: sgx_quote_sign_type_t) -> Result<AttestationEvidence, sgx_status_t> {
     // Workflow:
     // (1) ocall to get the target_info structure (ti) and epid group id (eg)
     // (1.5) get sigrl
@@ -484,8 +415,7 @@ pub fn create_attestation_report(pub_k: &sgx_ec256_public_t, sign_type: sgx_quot
         return Err(rt);
     }
 
-    let (attn_report, sig, cert) = get_report_from_intel(ias_sock, quote_vec);
-    Ok((attn_report, sig, cert))
+    get_report_from_intel(ias_sock, quote_vec)
 }
 
 fn load_spid(filename: &str) -> sgx_spid_t {
@@ -504,79 +434,21 @@ fn get_ias_api_key() -> String {
     key.trim_end().to_owned()
 }
 
-struct ClientAuth {
-    outdated_ok: bool,
-}
-
-impl ClientAuth {
-    fn new(outdated_ok: bool) -> ClientAuth {
-        ClientAuth{ outdated_ok : outdated_ok }
-    }
-}
-
-impl rustls::ClientCertVerifier for ClientAuth {
-    fn client_auth_root_subjects(&self) -> rustls::DistinguishedNames {
-    rustls::DistinguishedNames::new()
-    }
-
-    fn verify_client_cert(&self, _certs: &[rustls::Certificate])
-    -> Result<rustls::ClientCertVerified, rustls::TLSError> {
-        println!("client cert: {:?}", _certs);
-            // This call will automatically verify cert is properly signed
-            match cert::verify_mra_cert(&_certs[0].0) {
-                Ok(()) => {
-                    return Ok(rustls::ClientCertVerified::assertion());
-                }
-                Err(sgx_status_t::SGX_ERROR_UPDATE_NEEDED) => {
-                    if self.outdated_ok {
-                        println!("outdated_ok is set, overriding outdated error");
-                        return Ok(rustls::ClientCertVerified::assertion());
-                    } else {
-                        return Err(rustls::TLSError::WebPKIError(webpki::Error::ExtensionValueInvalid));
-                    }
-                }
-                Err(_) => {
-                    return Err(rustls::TLSError::WebPKIError(webpki::Error::ExtensionValueInvalid));
-                }
-            }
-    }
-}
-
-struct ServerAuth {
-    outdated_ok: bool
-}
-
-impl ServerAuth {
-    fn new(outdated_ok: bool) -> ServerAuth {
-        ServerAuth{ outdated_ok : outdated_ok }
-    }
-}
-
-impl rustls::ServerCertVerifier for ServerAuth {
-    fn verify_server_cert(&self,
-              _roots: &rustls::RootCertStore,
-              _certs: &[rustls::Certificate],
-              _hostname: webpki::DNSNameRef,
-              _ocsp: &[u8]) -> Result<rustls::ServerCertVerified, rustls::TLSError> {
-    println!("server cert: {:?}", _certs);
-        // This call will automatically verify cert is properly signed
-        match cert::verify_mra_cert(&_certs[0].0) {
-            Ok(()) => {
-                return Ok(rustls::ServerCertVerified::assertion());
-            }
-            Err(sgx_status_t::SGX_ERROR_UPDATE_NEEDED) => {
-                if self.outdated_ok {
-                    println!("outdated_ok is set, overriding outdated error");
-                    return Ok(rustls::ServerCertVerified::assertion());
-                } else {
-                    return Err(rustls::TLSError::WebPKIError(webpki::Error::ExtensionValueInvalid));
-                }
-            }
-            Err(_) => {
-                return Err(rustls::TLSError::WebPKIError(webpki::Error::ExtensionValueInvalid));
-            }
-        }
+// Both ends of this sample run in enclaves the app creates with
+// `debug = 1`. Accepting debug peers is for development only.
+const ALLOW_DEBUG_ENCLAVES: bool = true;
+
+// Peers are accepted if IAS reports their quote as OK or, with
+// `outdated_ok`, if their platform only needs an update.
+fn ra_tls_verifier(outdated_ok: bool) -> Arc<RaTlsVerifier> {
+    let mut policy = AttestationPolicy::new().allow_debug(ALLOW_DEBUG_ENCLAVES);
+    if outdated_ok {
+        policy = policy.accept_status(QuoteStatus::GroupOutOfDate)
+                       .accept_status(QuoteStatus::ConfigurationNeeded)
+                       .accept_status(QuoteStatus::SwHardeningNeeded)
+                       .accept_status(QuoteStatus::ConfigurationAndSwHardeningNeeded);
     }
+    Arc::new(RaTlsVerifier::new(policy))
 }
 
 #[no_mangle]
@@ -588,26 +460,24 @@ pub extern "C" fn run_server(socket_fd : c_int, sign_type: sgx_quote_sign_type_t
     let _result = ecc_handle.open();
     let (prv_k, pub_k) = ecc_handle.create_key_pair().unwrap();
 
-    let (attn_report, sig, cert) = match create_attestation_report(&pub_k, sign_type) {
+    let evidence = match create_attestation_report(&pub_k, sign_type) {
         Ok(r) => r,
         Err(e) => {
             println!("Error in create_attestation_report: {:?}", e);
             return e;
         }
     };
-
-    let payload = attn_report + "|" + &sig + "|" + &cert;
-    let (key_der, cert_der) = match cert::gen_ecc_cert(payload, &prv_k, &pub_k, &ecc_handle) {
+    let (key_der, cert_der) = match sgx_tra_tls::gen_ecc_cert(&evidence, &prv_k, &pub_k, &ecc_handle) {
         Ok(r) => r,
         Err(e) => {
-            println!("Error in gen_ecc_cert: {:?}", e);
-            return e;
+            println!("Error in gen_ecc_cert: {}", e);
+            return e.into();
         }
     };
     let _result = ecc_handle.close();
 
 
-    let mut cfg = rustls::ServerConfig::new(Arc::new(ClientAuth::new(true)));
+    let mut cfg = rustls::ServerConfig::new(ra_tls_verifier(true));
     let mut certs = Vec::new();
     certs.push(rustls::Certificate(cert_der));
     let privkey = rustls::PrivateKey(key_der);
@@ -642,21 +512,18 @@ pub extern "C" fn run_client(socket_fd : c_int, sign_type: sgx_quote_sign_type_t
     ecc_handle.open().unwrap();
     let (prv_k, pub_k) = ecc_handle.create_key_pair().unwrap();
 
-    let (attn_report, sig, cert) = match create_attestation_report(&pub_k, sign_type) {
+    let evidence = match create_attestation_report(&pub_k, sign_type) {
         Ok(r) => r,
         Err(e) => {
             println!("Error in create_attestation_report: {:?}", e);
             return e;
         }
     };
-
-    let payload = attn_report + "|" + &sig + "|" + &cert;
-
-    let (key_der, cert_der) = match cert::gen_ecc_cert(payload, &prv_k, &pub_k, &ecc_handle) {
+    let (key_der, cert_der) = match sgx_tra_tls::gen_ecc_cert(&evidence, &prv_k, &pub_k, &ecc_handle) {
         Ok(r) => r,
         Err(e) => {
-            println!("Error in gen_ecc_cert: {:?}", e);
-            return e;
+            println!("Error in gen_ecc_cert: {}", e);
+            return e.into();
         }
     };
     ecc_handle.close().unwrap();
@@ -668,7 +535,7 @@ pub extern "C" fn run_client(socket_fd : c_int, sign_type: sgx_quote_sign_type_t
     let privkey = rustls::PrivateKey(key_der);
 
     cfg.set_single_client_cert(certs, privkey);
-    cfg.dangerous().set_certificate_verifier(Arc::new(ServerAuth::new(true)));
+    cfg.dangerous().set_certificate_verifier(ra_tls_verifier(true));
     cfg.versions.clear();
     cfg.versions.push(rustls::ProtocolVersion::TLSv1_2);
 
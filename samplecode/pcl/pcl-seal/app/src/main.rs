@@ -23,11 +23,13 @@ extern crate sgx_urts;
 extern crate itertools;
 use sgx_types::*;
 use sgx_urts::SgxEnclave;
+use sgx_urts::quote::{EpidQuoter, Quoter};
 
 use std::os::unix::io::{IntoRawFd, AsRawFd};
 use std::env;
 use std::net::{TcpListener, TcpStream, SocketAddr};
 use std::str;
+use std::{ptr, slice};
 use itertools::Itertools;
 
 const PCL_SEALED_KEY_SIZE: usize = SGX_AESGCM_KEY_SIZE + SGX_PCL_GUID_SIZE;
@@ -54,7 +56,16 @@ pub extern "C"
 fn ocall_sgx_init_quote(ret_ti: *mut sgx_target_info_t,
                         ret_gid : *mut sgx_epid_group_id_t) -> sgx_status_t {
     println!("Entering ocall_sgx_init_quote");
-    unsafe {sgx_init_quote(ret_ti, ret_gid)}
+    match EpidQuoter::init() {
+        Ok((ti, gid)) => {
+            unsafe {
+                *ret_ti = ti;
+                *ret_gid = gid;
+            }
+            sgx_status_t::SGX_SUCCESS
+        },
+        Err(e) => e.into(),
+    }
 }
 
 pub fn lookup_ipv4(host: &str, port: u16) -> SocketAddr {
@@ -94,43 +105,51 @@ fn ocall_get_quote (p_sigrl            : *const u8,
                     p_nonce            : *const sgx_quote_nonce_t,
                     p_qe_report        : *mut sgx_report_t,
                     p_quote            : *mut u8,
-                    _maxlen             : u32,
+                    maxlen             : u32,
                     p_quote_len        : *mut u32) -> sgx_status_t {
     println!("Entering ocall_get_quote");
 
-    let mut real_quote_len : u32 = 0;
+    // As with sgx_get_quote, the nonce and the QE report go together.
+    if p_report.is_null() || p_spid.is_null() || p_quote.is_null() || p_quote_len.is_null() ||
+       (sigrl_len != 0 && p_sigrl.is_null()) || p_nonce.is_null() != p_qe_report.is_null() {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+
+    let mut quoter = EpidQuoter::new(unsafe { *p_spid }, quote_type);
+    if sigrl_len != 0 {
+        let sigrl = unsafe { slice::from_raw_parts(p_sigrl, sigrl_len as usize) };
+        quoter.set_sigrl(sigrl.to_vec());
+    }
 
-    let ret = unsafe {
-        sgx_calc_quote_size(p_sigrl, sigrl_len, &mut real_quote_len as *mut u32)
+    let report = unsafe { &*p_report };
+    let quote = if p_nonce.is_null() {
+        quoter.quote(report)
+    } else {
+        quoter.quote_with_nonce(report, unsafe { &*p_nonce })
+              .map(|(quote, qe_report)| {
+                  unsafe { *p_qe_report = qe_report; }
+                  quote
+              })
     };
 
-    if ret != sgx_status_t::SGX_SUCCESS {
-        println!("sgx_calc_quote_size returned {}", ret);
-        return ret;
-    }
+    let quote = match quote {
+        Ok(quote) => quote,
+        Err(e) => {
+            println!("get quote failed: {}", e);
+            return e.into();
+        },
+    };
 
-    println!("quote size = {}", real_quote_len);
-    unsafe { *p_quote_len = real_quote_len; }
-
-    let ret = unsafe {
-        sgx_get_quote(p_report,
-                      quote_type,
-                      p_spid,
-                      p_nonce,
-                      p_sigrl,
-                      sigrl_len,
-                      p_qe_report,
-                      p_quote as *mut sgx_quote_t,
-                      real_quote_len)
-        };
-
-    if ret != sgx_status_t::SGX_SUCCESS {
-        println!("sgx_calc_quote_size returned {}", ret);
-        return ret;
+    println!("quote size = {}", quote.len());
+    if quote.len() > maxlen as usize {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    unsafe {
+        ptr::copy_nonoverlapping(quote.as_ptr(), p_quote, quote.len());
+        *p_quote_len = quote.len() as u32;
     }
 
-    println!("sgx_calc_quote_size returned {}", ret);
-    ret
+    sgx_status_t::SGX_SUCCESS
 }
 
 #[no_mangle]
@@ -1,38 +1,48 @@
-extern crate chrono;
 extern crate webpki;
 extern crate rustls;
-extern crate base64;
 extern crate itertools;
-extern crate serde_json;
-extern crate num_bigint;
-extern crate bit_vec;
-extern crate hex;
-extern crate sgx_types;
+extern crate sgx_ra_tls;
 
-use sgx_types::*;
+use sgx_ra_tls::{AttestationPolicy, QuoteStatus, RaTlsVerifier};
+use itertools::Itertools;
 
-use std::fs;
-use std::io::{self, Write, Read, BufReader};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write, Read, BufRead, BufReader};
 use std::sync::Arc;
 use std::str;
 use std::net::TcpStream;
 
-mod cert;
-mod pib;
-
 const SERVERADDR: &str = "localhost:3443";
 const VERIFYMSADDR: &str = "localhost:3444";
 const MSFILE: &str = "./measurement.txt";
 
+// tr-mpc-server creates its enclave with `debug = 1`. Accepting debug
+// enclaves is for development only; turn this off for release builds.
+const ALLOW_DEBUG_ENCLAVES: bool = true;
+
+// Servers are accepted if IAS reports their quote as OK or, with
+// `outdated_ok`, if their platform only needs an update.
+fn attestation_policy(outdated_ok: bool) -> AttestationPolicy {
+    let policy = AttestationPolicy::new().allow_debug(ALLOW_DEBUG_ENCLAVES);
+    if outdated_ok {
+        policy.accept_status(QuoteStatus::GroupOutOfDate)
+              .accept_status(QuoteStatus::ConfigurationNeeded)
+              .accept_status(QuoteStatus::SwHardeningNeeded)
+              .accept_status(QuoteStatus::ConfigurationAndSwHardeningNeeded)
+    } else {
+        policy
+    }
+}
+
 struct ServerAuth {
-    outdated_ok: bool,
+    verifier: RaTlsVerifier,
     //whether to verify mr_enclave
     mr_enclave_flag: bool
 }
 
 impl ServerAuth {
     fn new(outdated_ok: bool, mr_enclave_flag: bool) -> ServerAuth {
-        ServerAuth{ outdated_ok, mr_enclave_flag}
+        ServerAuth{ verifier: RaTlsVerifier::new(attestation_policy(outdated_ok)), mr_enclave_flag }
     }
 }
 
@@ -43,23 +53,50 @@ impl rustls::ServerCertVerifier for ServerAuth {
                           _hostname: webpki::DNSNameRef,
                           _ocsp: &[u8]) -> Result<rustls::ServerCertVerified, rustls::TLSError> {
         println!("--received-server cert: {:?}", _certs);
-        // This call will automatically verify cert is properly signed
-        match cert::verify_mra_cert(&_certs[0].0, self.mr_enclave_flag) {
-            Ok(()) => {
-                Ok(rustls::ServerCertVerified::assertion())
-            }
-            Err(sgx_status_t::SGX_ERROR_UPDATE_NEEDED) => {
-                if self.outdated_ok {
-                    println!("outdated_ok is set, overriding outdated error");
-                    Ok(rustls::ServerCertVerified::assertion())
-                } else {
-                    Err(rustls::TLSError::WebPKIError(webpki::Error::ExtensionValueInvalid))
-                }
-            }
-            Err(_) => {
-                Err(rustls::TLSError::WebPKIError(webpki::Error::ExtensionValueInvalid))
+        let cert = _certs.first().ok_or(rustls::TLSError::NoCertificatesPresented)?;
+        let report = self.verifier.verify_cert(&cert.0)
+            .map_err(|e| rustls::TLSError::General(e.to_string()))?;
+
+        let mut output:File = OpenOptions::new().append(true).create(true).open(MSFILE).unwrap();
+        let _ = write!(output, "{}\n", report.report_body.mr_enclave.m.iter().format(""));
+
+        if self.mr_enclave_flag {
+            // we are writing the mr_enclave into file
+            if !verify_mr_enclave() {
+                return Err(rustls::TLSError::General("mr_enclave mismatch".to_string()));
             }
+            println!("tr mpc done!");
+        } else {
+            println!("success to get mr_enclavement\n\n\n");
         }
+        Ok(rustls::ServerCertVerified::assertion())
+    }
+}
+
+fn verify_mr_enclave() -> bool {
+    //we are trying to vefity the measurement of remote server
+    let file = File::open(MSFILE).unwrap();
+    let fin = BufReader::new(file);
+    let mut i = 0;
+
+    let mut ms_str_server = String::new();
+    let mut ms_str_verifyserver = String::new();
+
+    for line in fin.lines() {
+        if i==0 {
+            ms_str_server = line.unwrap()
+        }else{
+            ms_str_verifyserver = line.unwrap()
+        }
+        i = i+1;
+    }
+
+    if ms_str_server == ms_str_verifyserver {
+        println!("verify successd");
+        true
+    }else{
+        println!("failed to verify mr_enclave");
+        false
     }
 }
 
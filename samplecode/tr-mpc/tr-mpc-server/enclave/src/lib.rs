@@ -37,10 +37,7 @@ extern crate webpki_roots;
 extern crate itertools;
 extern crate base64;
 extern crate httparse;
-extern crate yasna;
-extern crate bit_vec;
-extern crate num_bigint;
-extern crate chrono;
+extern crate sgx_tra_tls;
 
 use sgx_types::*;
 use sgx_tse::*;
@@ -59,14 +56,13 @@ use std::vec::Vec;
 use itertools::Itertools;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
+use sgx_tra_tls::AttestationEvidence;
 
-mod cert;
 mod hex;
 
 pub const DEV_HOSTNAME:&'static str = "api.trustedservices.intel.com";
 pub const SIGRL_SUFFIX:&'static str = "/sgx/dev/attestation/v3/sigrl/";
 pub const REPORT_SUFFIX:&'static str = "/sgx/dev/attestation/v3/report";
-pub const CERTEXPIRYDAYS: i64 = 90i64;
 
 extern "C" {
     pub fn ocall_sgx_init_quote ( ret_val : *mut sgx_status_t,
@@ -88,6 +84,19 @@ extern "C" {
 }
 
 
+fn percent_decode(orig: String) -> String {
+    let v:Vec<&str> = orig.split("%").collect();
+    let mut ret = String::new();
+    ret.push_str(v[0]);
+    if v.len() > 1 {
+        for s in v[1..].iter() {
+            ret.push(u8::from_str_radix(&s[0..2], 16).unwrap() as char);
+            ret.push_str(&s[2..]);
+        }
+    }
+    ret
+}
+
 fn parse_response_attn_report(resp : &[u8]) -> (String, String, String){
     println!("parse_response_attn_report");
     let mut headers = [httparse::EMPTY_HEADER; 16];
@@ -133,7 +142,7 @@ fn parse_response_attn_report(resp : &[u8]) -> (String, String, String){
 
     // Remove %0A from cert, and only obtain the signing cert
     cert = cert.replace("%0A", "");
-    cert = cert::percent_decode(cert);
+    cert = percent_decode(cert);
     let v: Vec<&str> = cert.split("-----").collect();
     let sig_cert = v[2].to_string();
 
@@ -500,6 +509,12 @@ fn get_ias_api_key() -> String {
     key.trim_end().to_owned()
 }
 
+fn attestation_evidence(attn_report: String, sig: String, cert: String) -> Result<AttestationEvidence, sgx_status_t> {
+    let sig = base64::decode(&sig).map_err(|_| sgx_status_t::SGX_ERROR_UNEXPECTED)?;
+    let cert = base64::decode(&cert).map_err(|_| sgx_status_t::SGX_ERROR_UNEXPECTED)?;
+    Ok(AttestationEvidence::new(attn_report.into_bytes(), sig, cert))
+}
+
 #[no_mangle]
 pub extern "C" fn run_server(socket_fd : c_int, sign_type: sgx_quote_sign_type_t) -> sgx_status_t {
     // Generate Keypair
@@ -515,14 +530,20 @@ pub extern "C" fn run_server(socket_fd : c_int, sign_type: sgx_quote_sign_type_t
         }
     };
 
-    let payload = attn_report + "|" + &sig + "|" + &cert;
-    let (key_der, cert_der) = match cert::gen_ecc_cert(payload, &prv_k, &pub_k, &ecc_handle) {
+    let evidence = match attestation_evidence(attn_report, sig, cert) {
         Ok(r) => r,
         Err(e) => {
-            println!("Error in gen_ecc_cert: {:?}", e);
+            println!("Error in attestation_evidence: {:?}", e);
             return e;
         }
     };
+    let (key_der, cert_der) = match sgx_tra_tls::gen_ecc_cert(&evidence, &prv_k, &pub_k, &ecc_handle) {
+        Ok(r) => r,
+        Err(e) => {
+            println!("Error in gen_ecc_cert: {}", e);
+            return e.into();
+        }
+    };
     let _result = ecc_handle.close();
 
     let root_ca_bin = include_bytes!("../../../cert/ca.crt");
@@ -1,60 +1,31 @@
-extern crate chrono;
 extern crate webpki;
 extern crate rustls;
-extern crate base64;
-extern crate itertools;
-extern crate serde_json;
-extern crate num_bigint;
-extern crate bit_vec;
-extern crate hex;
-extern crate sgx_types;
+extern crate sgx_ra_tls;
 
-use sgx_types::*;
+use sgx_ra_tls::{AttestationPolicy, QuoteStatus, RaTlsVerifier};
 
 use std::io::{self, Write, Read, BufReader};
 use std::sync::Arc;
 use std::str;
 use std::net::TcpStream;
 
-mod cert;
-mod pib;
-
 const SERVERADDR: &str = "localhost:3443";
 
-struct ServerAuth {
-    outdated_ok: bool
-}
-
-impl ServerAuth {
-    fn new(outdated_ok: bool) -> ServerAuth {
-        ServerAuth{ outdated_ok }
-    }
-}
-
-impl rustls::ServerCertVerifier for ServerAuth {
-    fn verify_server_cert(&self,
-              _roots: &rustls::RootCertStore,
-              _certs: &[rustls::Certificate],
-              _hostname: webpki::DNSNameRef,
-              _ocsp: &[u8]) -> Result<rustls::ServerCertVerified, rustls::TLSError> {
-        println!("--received-server cert: {:?}", _certs);
-        // This call will automatically verify cert is properly signed
-        match cert::verify_mra_cert(&_certs[0].0) {
-            Ok(()) => {
-                Ok(rustls::ServerCertVerified::assertion())
-            }
-            Err(sgx_status_t::SGX_ERROR_UPDATE_NEEDED) => {
-                if self.outdated_ok {
-                    println!("outdated_ok is set, overriding outdated error");
-                    Ok(rustls::ServerCertVerified::assertion())
-                } else {
-                    Err(rustls::TLSError::WebPKIError(webpki::Error::ExtensionValueInvalid))
-                }
-            }
-            Err(_) => {
-                Err(rustls::TLSError::WebPKIError(webpki::Error::ExtensionValueInvalid))
-            }
-        }
+// ue-ra-server creates its enclave with `debug = 1`. Accepting debug
+// enclaves is for development only; turn this off for release builds.
+const ALLOW_DEBUG_ENCLAVES: bool = true;
+
+// Servers are accepted if IAS reports their quote as OK or, with
+// `outdated_ok`, if their platform only needs an update.
+fn attestation_policy(outdated_ok: bool) -> AttestationPolicy {
+    let policy = AttestationPolicy::new().allow_debug(ALLOW_DEBUG_ENCLAVES);
+    if outdated_ok {
+        policy.accept_status(QuoteStatus::GroupOutOfDate)
+              .accept_status(QuoteStatus::ConfigurationNeeded)
+              .accept_status(QuoteStatus::SwHardeningNeeded)
+              .accept_status(QuoteStatus::ConfigurationAndSwHardeningNeeded)
+    } else {
+        policy
     }
 }
 
@@ -72,7 +43,7 @@ fn make_config() -> rustls::ClientConfig {
 
     config.set_single_client_cert(certs, privk.unwrap()[0].clone());
 
-    config.dangerous().set_certificate_verifier(Arc::new(ServerAuth::new(true)));
+    config.dangerous().set_certificate_verifier(Arc::new(RaTlsVerifier::new(attestation_policy(true))));
     config.versions.clear();
     config.versions.push(rustls::ProtocolVersion::TLSv1_2);
 
@@ -41,6 +41,7 @@ extern crate rand;
 #[macro_use]
 extern crate memoffset;
 extern crate sgx_tse;
+extern crate sgx_tservice;
 
 extern crate sgx_serialize;
 pub use sgx_serialize::*;
@@ -80,6 +81,15 @@ use test_file::*;
 mod test_time;
 use test_time::*;
 
+mod test_service;
+use test_service::*;
+
+mod test_executor;
+use test_executor::*;
+
+mod test_poll;
+use test_poll::*;
+
 mod test_rand_cratesio;
 use test_rand_cratesio::*;
 
@@ -108,6 +118,22 @@ fn test_main_entrance() -> size_t {
                     // tcrypto
                     test_rsgx_sha256_slice,
                     test_rsgx_sha256_handle,
+                    test_rsgx_sha384_slice,
+                    test_rsgx_sha512_handle,
+                    test_rsgx_rijndael256GCM,
+                    test_rsgx_aes256_handle,
+                    test_rsgx_chacha20_poly1305,
+                    test_rsgx_xchacha20_poly1305,
+                    test_rsgx_chacha20_poly1305_handle,
+                    test_rsgx_hkdf_sha256,
+                    test_rsgx_cmac_kdf_counter,
+                    test_rsgx_ed25519,
+                    test_rsgx_x25519,
+                    test_rsgx_ec256_codec,
+                    test_rsgx_rsa_codec,
+                    test_rsgx_rsa_padding,
+                    test_rsgx_secret_key,
+                    test_rsgx_aes_gcm_siv,
                     // assert
                     foo_panic,
                     foo_should,
@@ -145,6 +171,10 @@ fn test_main_entrance() -> size_t {
                     test_array_sealing,         // Thanks to @silvanegli
                     test_mac_aadata_slice,
                     test_mac_aadata_number,
+                    test_seal_migrate,
+                    test_seal_envelope,
+                    test_sealed_blob,
+                    test_seal_rollback,
                     // rand
                     test_rand_os_sgxrng,
                     test_rand_distributions,
@@ -155,19 +185,35 @@ fn test_main_entrance() -> size_t {
                     test_serialize_base,
                     test_serialize_struct,
                     test_serialize_enum,
+                    test_serialize_seal,
                     // std::sgxfs
                     test_sgxfs,
+                    test_sgxfs_dir,
+                    test_sgxfs_rekey,
+                    test_sgxfs_integrity,
                     // std::fs
                     test_fs,
                     // std::fs untrusted mode
                     test_fs_untrusted_fs_feature_enabled,
                     // std::time
                     test_std_time,
+                    // sgx_tservice
+                    test_trusted_service,
+                    // std::executor
+                    test_executor,
+                    test_executor_block_on_keeps_woken_tasks,
+                    test_executor_file_io,
+                    // std::net::Poll
+                    test_poll_round_trip,
+                    test_poll_event_count_is_bounded,
+                    test_poll_drops_unregistered_fds,
                     // rand
                     test_rand_cratesio,
                     // types
                     check_metadata_size,
                     check_version,
+                    test_quote_view_epid,
+                    test_quote_view_ecdsa,
                     // env
                     test_env_vars_os,
                     test_env_self_exe_path,
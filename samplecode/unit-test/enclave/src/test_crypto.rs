@@ -18,6 +18,9 @@
 use utils::*;
 use std::string::String;
 use sgx_tcrypto::*;
+use sgx_types::*;
+use sgx_tse::alignbox::AlignBox;
+use std::vec::Vec;
 
 static HASH_TEST_VEC: &'static [&'static str] = &[
     &"abc",
@@ -53,3 +56,490 @@ pub fn test_rsgx_sha256_handle(){
     }
 }
 
+
+static HASH_SHA384_TRUTH: &'static [&'static str] = &[
+    &"cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed8086072ba1e7cc2358baeca134c825a7",
+    &"3391fdddfc8dc7393707a65b1b4709397cf8b1d162af05abfe8f450de5f36bc6b0455a8520bc4e6f5fe95b1fe3c8452b",
+    &"09330c33f71147e83d192fc782cd1b4753111b173b3b05d22fa08086e3b0f712fcc7c71a557e2db966c3e9fa91746039",
+];
+
+static HASH_SHA512_TRUTH: &'static [&'static str] = &[
+    &"ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+    &"204a8fc6dda82f0a0ced7beb8e08a41657c16ef468b228a8279be331a703c33596fd15c13b1b07f9aa1d3bea57789ca031ad85c7a71dd70354ec631238ca3445",
+    &"8e959b75dae313da8cf4f72814fc143f8f7779c6eb9f7fa17299aeadb6889018501d289e4900f7e4331b99dec4b5433ac7d329eeb6dd26545e96e55b874be909",
+];
+
+pub fn test_rsgx_sha384_slice() {
+    let test_size = HASH_TEST_VEC.len();
+    for i in 0..test_size {
+        let input_str = String::from(HASH_TEST_VEC[i]);
+        let hash = rsgx_sha384_slice(input_str.as_bytes()).unwrap();
+        assert_eq!(hex_to_bytes(HASH_SHA384_TRUTH[i]), &hash[..]);
+    }
+}
+
+pub fn test_rsgx_sha512_handle() {
+    let test_size = HASH_TEST_VEC.len();
+    for i in 0..test_size {
+        let input_str = String::from(HASH_TEST_VEC[i]);
+        let shah = SgxSha512Handle::new();
+        shah.init().unwrap();
+        for chunk in input_str.as_bytes().chunks(7) {
+            shah.update_slice(chunk).unwrap();
+        }
+        let hash = shah.get_hash().unwrap();
+        shah.close().unwrap();
+        assert_eq!(hex_to_bytes(HASH_SHA512_TRUTH[i]), &hash[..]);
+    }
+}
+
+// NIST SP 800-38D test case 16
+static AES256_GCM_KEY: &'static str = "feffe9928665731c6d6a8f9467308308feffe9928665731c6d6a8f9467308308";
+static AES256_GCM_IV: &'static str = "cafebabefacedbaddecaf888";
+static AES256_GCM_AAD: &'static str = "feedfacedeadbeeffeedfacedeadbeefabaddad2";
+static AES256_GCM_PT: &'static str = "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a721c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39";
+static AES256_GCM_CT: &'static str = "522dc1f099567d07f47f37a32a84427d643a8cdcbfe5c0c97598a2bd2555d1aa8cb08e48590dbb3da7b08b1056828838c5f61e6393ba7a0abcc9f662";
+static AES256_GCM_TAG: &'static str = "76fc6ece0f4e1768cddf8853bb2d551b";
+
+pub fn test_rsgx_rijndael256GCM() {
+    let mut key = sgx_aes_gcm_256bit_key_t::default();
+    key.copy_from_slice(&hex_to_bytes(AES256_GCM_KEY));
+    let iv = hex_to_bytes(AES256_GCM_IV);
+    let aad = hex_to_bytes(AES256_GCM_AAD);
+    let pt = hex_to_bytes(AES256_GCM_PT);
+
+    let mut ct = vec![0_u8; pt.len()];
+    let mut mac = sgx_aes_gcm_128bit_tag_t::default();
+    rsgx_rijndael256GCM_encrypt(&key, &pt, &iv, &aad, &mut ct, &mut mac).unwrap();
+    assert_eq!(hex_to_bytes(AES256_GCM_CT), ct);
+    assert_eq!(hex_to_bytes(AES256_GCM_TAG), mac);
+
+    let mut dec = vec![0_u8; ct.len()];
+    rsgx_rijndael256GCM_decrypt(&key, &ct, &iv, &aad, &mac, &mut dec).unwrap();
+    assert_eq!(pt, dec);
+
+    mac[0] ^= 1;
+    assert_eq!(rsgx_rijndael256GCM_decrypt(&key, &ct, &iv, &aad, &mac, &mut dec),
+               Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH));
+}
+
+pub fn test_rsgx_aes256_handle() {
+    let mut key = sgx_aes_gcm_256bit_key_t::default();
+    key.copy_from_slice(&hex_to_bytes(AES256_GCM_KEY));
+    let iv = hex_to_bytes(AES256_GCM_IV);
+    let aad = hex_to_bytes(AES256_GCM_AAD);
+    let pt = hex_to_bytes(AES256_GCM_PT);
+
+    let aesh = SgxAes256Handle::new();
+    aesh.init(&key, &iv, &aad).unwrap();
+    let mut ct = vec![0_u8; pt.len()];
+    let mut offset = 0;
+    for chunk in pt.chunks(7) {
+        aesh.update(chunk, &mut ct[offset..offset + chunk.len()]).unwrap();
+        offset += chunk.len();
+    }
+    let mac = aesh.get_mac().unwrap();
+    aesh.close().unwrap();
+    assert_eq!(hex_to_bytes(AES256_GCM_CT), ct);
+    assert_eq!(hex_to_bytes(AES256_GCM_TAG), mac);
+}
+
+// RFC 8439, section 2.8.2 and draft-irtf-cfrg-xchacha, appendix A.3.1
+static CHACHA20_KEY: &'static str = "808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f";
+static CHACHA20_NONCE: &'static str = "070000004041424344454647";
+static XCHACHA20_NONCE: &'static str = "404142434445464748494a4b4c4d4e4f5051525354555657";
+static CHACHA20_AAD: &'static str = "50515253c0c1c2c3c4c5c6c7";
+static CHACHA20_PT: &'static str = "Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
+static CHACHA20_CT: &'static str = "d31a8d34648e60db7b86afbc53ef7ec2a4aded51296e08fea9e2b5a736ee62d63dbea45e8ca9671282fafb69da92728b1a71de0a9e060b2905d6a5b67ecd3b3692ddbd7f2d778b8c9803aee328091b58fab324e4fad675945585808b4831d7bc3ff4def08e4b7a9de576d26586cec64b6116";
+static CHACHA20_TAG: &'static str = "1ae10b594f09e26a7e902ecbd0600691";
+static XCHACHA20_CT: &'static str = "bd6d179d3e83d43b9576579493c0e939572a1700252bfaccbed2902c21396cbb731c7f1b0b4aa6440bf3a82f4eda7e39ae64c6708c54c216cb96b72e1213b4522f8c9ba40db5d945b11b69b982c1bb9e3f3fac2bc369488f76b2383565d3fff921f9664c97637da9768812f615c68b13b52e";
+static XCHACHA20_TAG: &'static str = "c0875924c1c7987947deafd8780acf49";
+
+pub fn test_rsgx_chacha20_poly1305() {
+    let mut key = sgx_chacha20_256bit_key_t::default();
+    key.copy_from_slice(&hex_to_bytes(CHACHA20_KEY));
+    let nonce = hex_to_bytes(CHACHA20_NONCE);
+    let aad = hex_to_bytes(CHACHA20_AAD);
+    let pt = CHACHA20_PT.as_bytes();
+
+    let mut ct = vec![0_u8; pt.len()];
+    let mut mac = sgx_poly1305_128bit_tag_t::default();
+    rsgx_chacha20_poly1305_encrypt(&key, pt, &nonce, &aad, &mut ct, &mut mac).unwrap();
+    assert_eq!(hex_to_bytes(CHACHA20_CT), ct);
+    assert_eq!(hex_to_bytes(CHACHA20_TAG), mac);
+
+    let mut dec = vec![0_u8; ct.len()];
+    rsgx_chacha20_poly1305_decrypt(&key, &ct, &nonce, &aad, &mac, &mut dec).unwrap();
+    assert_eq!(pt, &dec[..]);
+
+    mac[15] ^= 0x80;
+    assert_eq!(rsgx_chacha20_poly1305_decrypt(&key, &ct, &nonce, &aad, &mac, &mut dec),
+               Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH));
+}
+
+pub fn test_rsgx_xchacha20_poly1305() {
+    let mut key = sgx_chacha20_256bit_key_t::default();
+    key.copy_from_slice(&hex_to_bytes(CHACHA20_KEY));
+    let nonce = hex_to_bytes(XCHACHA20_NONCE);
+    let aad = hex_to_bytes(CHACHA20_AAD);
+    let pt = CHACHA20_PT.as_bytes();
+
+    let mut ct = vec![0_u8; pt.len()];
+    let mut mac = sgx_poly1305_128bit_tag_t::default();
+    rsgx_xchacha20_poly1305_encrypt(&key, pt, &nonce, &aad, &mut ct, &mut mac).unwrap();
+    assert_eq!(hex_to_bytes(XCHACHA20_CT), ct);
+    assert_eq!(hex_to_bytes(XCHACHA20_TAG), mac);
+
+    let mut dec = vec![0_u8; ct.len()];
+    rsgx_xchacha20_poly1305_decrypt(&key, &ct, &nonce, &aad, &mac, &mut dec).unwrap();
+    assert_eq!(pt, &dec[..]);
+}
+
+pub fn test_rsgx_chacha20_poly1305_handle() {
+    let mut key = sgx_chacha20_256bit_key_t::default();
+    key.copy_from_slice(&hex_to_bytes(CHACHA20_KEY));
+    let nonce = hex_to_bytes(CHACHA20_NONCE);
+    let aad = hex_to_bytes(CHACHA20_AAD);
+    let pt = CHACHA20_PT.as_bytes();
+
+    let handle = SgxChaCha20Poly1305Handle::new();
+    handle.init(&key, &nonce, &aad).unwrap();
+    let mut ct = vec![0_u8; pt.len()];
+    let mut offset = 0;
+    for chunk in pt.chunks(13) {
+        handle.update(chunk, &mut ct[offset..offset + chunk.len()]).unwrap();
+        offset += chunk.len();
+    }
+    let mac = handle.get_mac().unwrap();
+    handle.close().unwrap();
+    assert_eq!(hex_to_bytes(CHACHA20_CT), ct);
+    assert_eq!(hex_to_bytes(CHACHA20_TAG), mac);
+}
+
+// RFC 5869, appendix A.1
+static HKDF_IKM: &'static str = "0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b";
+static HKDF_SALT: &'static str = "000102030405060708090a0b0c";
+static HKDF_INFO: &'static str = "f0f1f2f3f4f5f6f7f8f9";
+static HKDF_PRK: &'static str = "077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5";
+static HKDF_OKM: &'static str = "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865";
+
+pub fn test_rsgx_hkdf_sha256() {
+    let ikm = hex_to_bytes(HKDF_IKM);
+    let salt = hex_to_bytes(HKDF_SALT);
+    let info = hex_to_bytes(HKDF_INFO);
+
+    let prk = rsgx_hkdf_sha256_extract(&salt, &ikm).unwrap();
+    assert_eq!(hex_to_bytes(HKDF_PRK), prk);
+
+    let mut okm = vec![0_u8; 42];
+    rsgx_hkdf_sha256_expand(&prk, &info, &mut okm[..]).unwrap();
+    assert_eq!(hex_to_bytes(HKDF_OKM), okm);
+
+    let mut align_key = AlignBox::<sgx_align_key_128bit_t>::new().unwrap();
+    rsgx_hkdf_sha256(&salt, &ikm, &info, &mut *align_key).unwrap();
+    assert_eq!(&hex_to_bytes(HKDF_OKM)[..16], &align_key.key[..]);
+
+    let mut too_long = vec![0_u8; SGX_HKDF_SHA256_MAX_OUTPUT_SIZE + 1];
+    assert_eq!(rsgx_hkdf_sha256(&salt, &ikm, &info, &mut too_long[..]),
+               Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER));
+}
+
+pub fn test_rsgx_cmac_kdf_counter() {
+    let kdk: sgx_cmac_128bit_key_t = [0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6,
+                                      0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c];
+
+    // The SMK derivation of the SGX key exchange: 0x01 || "SMK" || 0x00 || 0x0080.
+    let derivation_buffer: [u8; 7] = [0x01, 0x53, 0x4d, 0x4b, 0x00, 0x80, 0x00];
+    let smk = rsgx_rijndael128_cmac_slice(&kdk, &derivation_buffer).unwrap();
+
+    let mut align_key = sgx_align_key_128bit_t::default();
+    rsgx_cmac_kdf_counter(&kdk, b"SMK", &[], &mut align_key).unwrap();
+    assert_eq!(smk, align_key.key);
+
+    // L is mixed into every block, so a shorter output is not a prefix of a longer one.
+    let mut okm32 = [0_u8; 32];
+    rsgx_cmac_kdf_counter(&kdk, b"label", b"context", &mut okm32).unwrap();
+    let mut okm16 = [0_u8; 16];
+    rsgx_cmac_kdf_counter(&kdk, b"label", b"context", &mut okm16).unwrap();
+    assert_ne!(&okm32[..16], &okm16[..]);
+}
+
+// RFC 8032, section 7.1, test 2
+static ED25519_SEED: &'static str = "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb";
+static ED25519_PUBLIC: &'static str = "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c";
+static ED25519_MSG: &'static str = "72";
+static ED25519_SIG: &'static str = "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00";
+
+pub fn test_rsgx_ed25519() {
+    let mut private = sgx_ed25519_private_t::default();
+    private.seed.copy_from_slice(&hex_to_bytes(ED25519_SEED));
+    let public = rsgx_ed25519_public_from_private(&private).unwrap();
+    assert_eq!(hex_to_bytes(ED25519_PUBLIC), public.key);
+
+    let msg = hex_to_bytes(ED25519_MSG);
+    let signature = rsgx_ed25519_sign_slice(&msg, &private).unwrap();
+    let expected = hex_to_bytes(ED25519_SIG);
+    assert_eq!(&expected[..32], &signature.r[..]);
+    assert_eq!(&expected[32..], &signature.s[..]);
+    assert!(rsgx_ed25519_verify_slice(&msg, &public, &signature).unwrap());
+
+    let mut bad_signature = signature;
+    bad_signature.s[0] ^= 1;
+    assert!(!rsgx_ed25519_verify_slice(&msg, &public, &bad_signature).unwrap());
+
+    let (align_private, public) = rsgx_align_ed25519_create_key_pair().unwrap();
+    let signature = rsgx_ed25519_sign_msg(&public, &align_private.key).unwrap();
+    assert!(rsgx_ed25519_verify_msg(&public, &public, &signature).unwrap());
+}
+
+// RFC 7748, section 6.1
+static X25519_ALICE_PRIVATE: &'static str = "77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a";
+static X25519_ALICE_PUBLIC: &'static str = "8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a";
+static X25519_BOB_PUBLIC: &'static str = "de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f";
+static X25519_SHARED: &'static str = "4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742";
+
+pub fn test_rsgx_x25519() {
+    let mut alice = sgx_x25519_private_t::default();
+    alice.scalar.copy_from_slice(&hex_to_bytes(X25519_ALICE_PRIVATE));
+    let alice_public = rsgx_x25519_public_from_private(&alice).unwrap();
+    assert_eq!(hex_to_bytes(X25519_ALICE_PUBLIC), alice_public.key);
+
+    let mut bob_public = sgx_x25519_public_t::default();
+    bob_public.key.copy_from_slice(&hex_to_bytes(X25519_BOB_PUBLIC));
+    let shared = rsgx_x25519_compute_shared_dhkey(&alice, &bob_public).unwrap();
+    assert_eq!(hex_to_bytes(X25519_SHARED), shared.s);
+
+    let (private_a, public_a) = rsgx_align_x25519_create_key_pair().unwrap();
+    let (private_b, public_b) = rsgx_x25519_create_key_pair().unwrap();
+    let shared_a = rsgx_x25519_compute_align_shared_dhkey(&private_a.key, &public_b).unwrap();
+    let shared_b = rsgx_x25519_compute_shared_dhkey(&private_b, &public_a).unwrap();
+    assert_eq!(shared_a.key.s, shared_b.s);
+
+    let low_order = sgx_x25519_public_t::default();
+    assert!(rsgx_x25519_compute_shared_dhkey(&private_b, &low_order).is_err());
+}
+
+pub fn test_rsgx_ec256_codec() {
+    let ecc_handle = SgxEccHandle::new();
+    ecc_handle.open().unwrap();
+    let (private, public) = ecc_handle.create_key_pair().unwrap();
+
+    let point = rsgx_ec256_public_to_sec1(&public);
+    assert_eq!(point[0], 0x04);
+    let decoded = rsgx_ec256_public_from_sec1(&point).unwrap();
+    assert_eq!(decoded.gx, public.gx);
+    assert_eq!(decoded.gy, public.gy);
+
+    let spki = rsgx_ec256_public_to_spki_der(&public);
+    let decoded = rsgx_ec256_public_from_spki_der(&spki).unwrap();
+    assert_eq!(decoded.gx, public.gx);
+    assert_eq!(decoded.gy, public.gy);
+
+    let mut pkcs8 = [0_u8; SGX_EC256_PKCS8_DER_MAX_SIZE];
+    let len = rsgx_ec256_private_to_pkcs8_der(&private, Some(&public), &mut pkcs8).unwrap();
+    let mut pem = vec![0_u8; rsgx_pem_encoded_len(SGX_PEM_PRIVATE_KEY_LABEL, len)];
+    rsgx_pem_encode(SGX_PEM_PRIVATE_KEY_LABEL, &pkcs8[..len], &mut pem).unwrap();
+    let mut der = [0_u8; SGX_EC256_PKCS8_DER_MAX_SIZE];
+    let der_len = rsgx_pem_decode(SGX_PEM_PRIVATE_KEY_LABEL, &pem, &mut der).unwrap();
+    assert_eq!(&pkcs8[..len], &der[..der_len]);
+    let decoded = rsgx_ec256_private_from_pkcs8_der(&der[..der_len]).unwrap();
+    assert_eq!(decoded.r, private.r);
+
+    let data = [0x5a_u8; 64];
+    let signature = ecc_handle.ecdsa_sign_slice(&data, &private).unwrap();
+    let mut sig_der = [0_u8; SGX_EC256_SIGNATURE_DER_MAX_SIZE];
+    let len = rsgx_ec256_signature_to_der(&signature, &mut sig_der).unwrap();
+    let decoded = rsgx_ec256_signature_from_der(&sig_der[..len]).unwrap();
+    assert!(ecc_handle.ecdsa_verify_slice(&data, &public, &decoded).unwrap());
+
+    assert!(rsgx_ec256_signature_from_der(&sig_der[..len - 1]).is_err());
+    ecc_handle.close().unwrap();
+}
+
+pub fn test_rsgx_rsa_codec() {
+    let mut n = [0_u8; SGX_RSA3072_KEY_SIZE];
+    let mut d = [0_u8; SGX_RSA3072_PRI_EXP_SIZE];
+    let mut e: [u8; SGX_RSA3072_PUB_EXP_SIZE] = [1, 0, 1, 0];
+    let mut p = [0_u8; SGX_RSA3072_KEY_SIZE / 2];
+    let mut q = [0_u8; SGX_RSA3072_KEY_SIZE / 2];
+    let mut dmp1 = [0_u8; SGX_RSA3072_KEY_SIZE / 2];
+    let mut dmq1 = [0_u8; SGX_RSA3072_KEY_SIZE / 2];
+    let mut iqmp = [0_u8; SGX_RSA3072_KEY_SIZE / 2];
+    rsgx_create_rsa_key_pair(SGX_RSA3072_KEY_SIZE as i32,
+                             SGX_RSA3072_PUB_EXP_SIZE as i32,
+                             &mut n, &mut d, &mut e, &mut p, &mut q,
+                             &mut dmp1, &mut dmq1, &mut iqmp).unwrap();
+
+    let mut public = sgx_rsa3072_public_key_t::default();
+    public.modulus = n;
+    public.exponent = e;
+    let mut spki = vec![0_u8; 512];
+    let len = rsgx_rsa3072_public_to_spki_der(&public, &mut spki).unwrap();
+    let decoded = rsgx_rsa3072_public_from_spki_der(&spki[..len]).unwrap();
+    assert_eq!(&decoded.modulus[..], &n[..]);
+    assert_eq!(decoded.exponent, e);
+
+    let mut pkcs8 = vec![0_u8; 2048];
+    let len = rsgx_rsa_key_pair_to_pkcs8_der(&n, &d, &e, &p, &q, &dmp1, &dmq1, &iqmp, &mut pkcs8).unwrap();
+    let key = rsgx_rsa3072_key_from_pkcs8_der(&pkcs8[..len]).unwrap();
+    assert_eq!(&key.modulus[..], &n[..]);
+    assert_eq!(&key.d[..], &d[..]);
+    assert_eq!(key.e, e);
+
+    let mut n2 = [0_u8; SGX_RSA3072_KEY_SIZE];
+    let mut d2 = [0_u8; SGX_RSA3072_PRI_EXP_SIZE];
+    let mut e2 = [0_u8; SGX_RSA3072_PUB_EXP_SIZE];
+    let mut p2 = [0_u8; SGX_RSA3072_KEY_SIZE / 2];
+    let mut q2 = [0_u8; SGX_RSA3072_KEY_SIZE / 2];
+    let mut dmp12 = [0_u8; SGX_RSA3072_KEY_SIZE / 2];
+    let mut dmq12 = [0_u8; SGX_RSA3072_KEY_SIZE / 2];
+    let mut iqmp2 = [0_u8; SGX_RSA3072_KEY_SIZE / 2];
+    rsgx_rsa_key_pair_from_pkcs8_der(&pkcs8[..len], &mut n2, &mut d2, &mut e2, &mut p2, &mut q2,
+                                     &mut dmp12, &mut dmq12, &mut iqmp2).unwrap();
+    assert_eq!(&p2[..], &p[..]);
+    assert_eq!(&q2[..], &q[..]);
+    assert_eq!(&iqmp2[..], &iqmp[..]);
+}
+
+pub fn test_rsgx_rsa_padding() {
+    let mod_size: i32 = 256;
+    let exp_size: i32 = 4;
+    let mut n = vec![0_u8; mod_size as usize];
+    let mut d = vec![0_u8; mod_size as usize];
+    let mut e: Vec<u8> = vec![1, 0, 1, 0];
+    let mut p = vec![0_u8; mod_size as usize / 2];
+    let mut q = vec![0_u8; mod_size as usize / 2];
+    let mut dmp1 = vec![0_u8; mod_size as usize / 2];
+    let mut dmq1 = vec![0_u8; mod_size as usize / 2];
+    let mut iqmp = vec![0_u8; mod_size as usize / 2];
+    rsgx_create_rsa_key_pair(mod_size, exp_size,
+                             &mut n, &mut d, &mut e, &mut p, &mut q,
+                             &mut dmp1, &mut dmq1, &mut iqmp).unwrap();
+
+    let pubkey = SgxRsaPubKey::new();
+    pubkey.create(mod_size, exp_size, &n, &e).unwrap();
+    let privkey = SgxRsaPrivKey::new();
+    privkey.create(mod_size, exp_size, &e, &p, &q, &dmp1, &dmq1, &iqmp).unwrap();
+    let privkey1 = SgxRsaPrivKey::new();
+    privkey1.create1(mod_size, exp_size, mod_size, &n, &e, &d).unwrap();
+
+    let msg = b"rsa padding test message";
+    let hashes = [sgx_rsa_hash_t::SGX_RSA_HASH_SHA1,
+                  sgx_rsa_hash_t::SGX_RSA_HASH_SHA256,
+                  sgx_rsa_hash_t::SGX_RSA_HASH_SHA384];
+    for hash in hashes.iter() {
+        let mut sig = vec![0_u8; mod_size as usize];
+        let mut sig1 = vec![0_u8; mod_size as usize];
+        privkey.sign_pkcs1_v15(*hash, msg, &mut sig).unwrap();
+        privkey1.sign_pkcs1_v15(*hash, msg, &mut sig1).unwrap();
+        assert_eq!(sig, sig1);
+        assert_eq!(pubkey.verify_pkcs1_v15(*hash, msg, &sig), Ok(true));
+        assert_eq!(pubkey.verify_pkcs1_v15(*hash, &msg[1..], &sig), Ok(false));
+
+        privkey.sign_pss(*hash, msg, &mut sig).unwrap();
+        assert_eq!(pubkey.verify_pss(*hash, msg, &sig), Ok(true));
+        sig[0] ^= 1;
+        assert_eq!(pubkey.verify_pss(*hash, msg, &sig), Ok(false));
+
+        let mut ciphertext = vec![0_u8; mod_size as usize];
+        let mut ciphertext_len = ciphertext.len();
+        pubkey.encrypt_oaep(*hash, &mut ciphertext, &mut ciphertext_len, msg).unwrap();
+        let mut plaintext = vec![0_u8; mod_size as usize];
+        let mut plaintext_len = plaintext.len();
+        privkey1.decrypt_oaep(*hash, &mut plaintext, &mut plaintext_len, &ciphertext).unwrap();
+        assert_eq!(&plaintext[..plaintext_len], &msg[..]);
+    }
+
+    // OAEP with SHA-256 interoperates with decrypt_sha256.
+    let mut ciphertext = vec![0_u8; mod_size as usize];
+    let mut ciphertext_len = ciphertext.len();
+    pubkey.encrypt_oaep(sgx_rsa_hash_t::SGX_RSA_HASH_SHA256, &mut ciphertext, &mut ciphertext_len, msg).unwrap();
+    let mut plaintext = vec![0_u8; mod_size as usize];
+    let mut plaintext_len = plaintext.len();
+    privkey.decrypt_sha256(&mut plaintext, &mut plaintext_len, &ciphertext).unwrap();
+    assert_eq!(&plaintext[..plaintext_len], &msg[..]);
+}
+
+pub fn test_rsgx_secret_key() {
+    let key = SecretKey128::random().unwrap();
+    let nonce = AesGcmNonce::random().unwrap();
+    let msg = b"secret key wrapper";
+    let mut ciphertext = [0_u8; 18];
+    let mut mac = sgx_aes_gcm_128bit_tag_t::default();
+    rsgx_rijndael128GCM_encrypt(&key, msg, &nonce[..], &[], &mut ciphertext, &mut mac).unwrap();
+
+    let mut raw = *key;
+    let moved = SecretKey128::take(&mut raw).unwrap();
+    assert_eq!(raw, [0_u8; 16]);
+    let mut plaintext = [0_u8; 18];
+    rsgx_rijndael128GCM_decrypt(&moved, &ciphertext, &nonce[..], &[], &mac, &mut plaintext).unwrap();
+    assert_eq!(&plaintext, msg);
+
+    let mut okm = SecretKey256::new().unwrap();
+    rsgx_hkdf_sha256(b"salt", b"ikm", b"info", &mut okm).unwrap();
+    let mut expected = [0_u8; 32];
+    rsgx_hkdf_sha256(b"salt", b"ikm", b"info", &mut expected).unwrap();
+    assert_eq!(okm.as_bytes(), &expected[..]);
+    assert_eq!(SecretKey256::from_slice(&expected[1..]).err(),
+               Some(sgx_status_t::SGX_ERROR_INVALID_PARAMETER));
+
+    let ecc_handle = SgxEccHandle::new();
+    ecc_handle.open().unwrap();
+    let (priv_a, pub_a) = ecc_handle.create_secret_key_pair().unwrap();
+    let (priv_b, pub_b) = ecc_handle.create_secret_key_pair().unwrap();
+    let shared_a = ecc_handle.compute_secret_shared_dhkey(&priv_a, &pub_b).unwrap();
+    let shared_b = ecc_handle.compute_secret_shared_dhkey(&priv_b, &pub_a).unwrap();
+    assert_eq!(shared_a.as_bytes(), shared_b.as_bytes());
+    ecc_handle.close().unwrap();
+}
+
+pub fn test_rsgx_aes_gcm_siv() {
+    // RFC 8452, Appendix C.1
+    let key: sgx_aes_gcm_128bit_key_t = [1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    let nonce = hex_to_bytes("030000000000000000000000");
+    let plaintext = hex_to_bytes("0100000000000000");
+    let mut ciphertext = [0_u8; 8];
+    let mut mac = sgx_aes_gcm_128bit_tag_t::default();
+    rsgx_rijndael128GCMSIV_encrypt(&key, &plaintext, &nonce, &[], &mut ciphertext, &mut mac).unwrap();
+    assert_eq!(ciphertext.to_vec(), hex_to_bytes("b5d839330ac7b786"));
+    assert_eq!(mac.to_vec(), hex_to_bytes("578782fff6013b815b287c22493a364c"));
+
+    let mut decrypted = [0_u8; 8];
+    rsgx_rijndael128GCMSIV_decrypt(&key, &ciphertext, &nonce, &[], &mac, &mut decrypted).unwrap();
+    assert_eq!(decrypted.to_vec(), plaintext);
+    mac[0] ^= 1;
+    assert_eq!(rsgx_rijndael128GCMSIV_decrypt(&key, &ciphertext, &nonce, &[], &mac, &mut decrypted),
+               Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH));
+    assert_eq!(decrypted, [0_u8; 8]);
+
+    let msg = b"aead sealer test message";
+    let sealer = AeadSealer::aes256_gcm_siv(SecretKey256::random().unwrap()).with_message_limit(2);
+    let mut sealed1 = vec![0_u8; AeadSealer::sealed_len(msg.len()).unwrap()];
+    let mut sealed2 = sealed1.clone();
+    assert_eq!(sealer.seal(msg, b"aad", &mut sealed1), Ok(msg.len() + AEAD_SEALER_OVERHEAD));
+    sealer.seal(msg, b"aad", &mut sealed2).unwrap();
+    assert_ne!(sealed1[..AEAD_SEALER_NONCE_SIZE], sealed2[..AEAD_SEALER_NONCE_SIZE]);
+    assert_eq!(sealer.seal(msg, b"aad", &mut sealed2), Err(sgx_status_t::SGX_ERROR_INVALID_STATE));
+
+    let mut opened = vec![0_u8; msg.len()];
+    assert_eq!(sealer.open(&sealed1, b"aad", &mut opened), Ok(msg.len()));
+    assert_eq!(&opened[..], &msg[..]);
+    assert_eq!(sealer.open(&sealed1, b"bad", &mut opened), Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH));
+
+    let sealer = AeadSealer::aes128_gcm(SecretKey128::random().unwrap());
+    sealer.seal(msg, &[], &mut sealed1).unwrap();
+    assert_eq!(sealer.open(&sealed1, &[], &mut opened), Ok(msg.len()));
+    assert_eq!(&opened[..], &msg[..]);
+
+    // A failed seal does not count against the limit.
+    let sealer = AeadSealer::aes128_gcm(SecretKey128::random().unwrap()).with_message_limit(1);
+    let mut empty = [0_u8; AEAD_SEALER_OVERHEAD];
+    assert_eq!(sealer.seal(&[], &[], &mut empty), Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER));
+    assert_eq!(sealer.sealed_count(), 0);
+    sealer.seal(msg, &[], &mut sealed1).unwrap();
+    assert_eq!(sealer.remaining(), 0);
+
+    let sealer = AeadSealer::aes128_gcm_siv(SecretKey128::random().unwrap()).with_sealed_count(5).unwrap();
+    sealer.seal(msg, &[], &mut sealed1).unwrap();
+    assert_eq!(sealer.with_sealed_count(5).err(), Some(sgx_status_t::SGX_ERROR_INVALID_PARAMETER));
+}
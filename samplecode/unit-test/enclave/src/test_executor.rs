@@ -0,0 +1,108 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+use std::executor::{self, LocalExecutor};
+use std::io::{AsyncReadExt, AsyncWriteExt};
+use std::sgxfs::{self, SgxFile};
+use std::untrusted::fs::{File, remove_file};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::vec::Vec;
+
+// Completes after being polled `remaining` more times, waking itself each time.
+struct CountDown {
+    remaining: u32,
+    id: u32,
+    log: Rc<RefCell<Vec<u32>>>,
+}
+
+impl Future for CountDown {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        let this = self.get_mut();
+        if this.remaining == 0 {
+            this.log.borrow_mut().push(this.id);
+            Poll::Ready(())
+        } else {
+            this.remaining -= 1;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+pub fn test_executor() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let executor = LocalExecutor::new();
+    for id in 0..3 {
+        executor.spawn(CountDown { remaining: 3 - id, id: id, log: log.clone() });
+    }
+    executor.run();
+    assert_eq!(*log.borrow(), vec![2, 1, 0]);
+
+    executor.spawn(CountDown { remaining: 10, id: 7, log: log.clone() });
+    executor.block_on(CountDown { remaining: 1, id: 8, log: log.clone() });
+    assert_eq!(*log.borrow(), vec![2, 1, 0, 8]);
+    executor.run();
+    assert_eq!(*log.borrow(), vec![2, 1, 0, 8, 7]);
+}
+
+pub fn test_executor_block_on_keeps_woken_tasks() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let executor = LocalExecutor::new();
+    // The spawned task is scheduled in the same batch in which the main
+    // future completes; its wakeup must survive until the next `run`.
+    executor.spawn(CountDown { remaining: 1, id: 5, log: log.clone() });
+    executor.block_on(CountDown { remaining: 0, id: 6, log: log.clone() });
+    assert_eq!(*log.borrow(), vec![6]);
+    executor.run();
+    assert_eq!(*log.borrow(), vec![6, 5]);
+}
+
+pub fn test_executor_file_io() {
+    let data = b"asynchronous enclave file io";
+    {
+        let mut file = SgxFile::create("sgx_async_file").unwrap();
+        executor::block_on(file.write_all(data)).unwrap();
+        executor::block_on(file.close()).unwrap();
+    }
+    {
+        let mut file = SgxFile::open("sgx_async_file").unwrap();
+        let mut buf = [0_u8; 28];
+        executor::block_on(file.read_exact(&mut buf)).unwrap();
+        assert_eq!(&buf[..], &data[..]);
+        assert_eq!(executor::block_on(file.read(&mut buf)).unwrap(), 0);
+    }
+    assert_eq!(sgxfs::remove("sgx_async_file").is_ok(), true);
+
+    {
+        let mut file = File::create("untrusted_async_file").unwrap();
+        executor::block_on(file.write_all(data)).unwrap();
+    }
+    {
+        let mut file = File::open("untrusted_async_file").unwrap();
+        let mut buf = [0_u8; 28];
+        executor::block_on(file.read_exact(&mut buf)).unwrap();
+        assert_eq!(&buf[..], &data[..]);
+    }
+    assert_eq!(remove_file("untrusted_async_file").is_ok(), true);
+}
@@ -16,11 +16,13 @@
 // under the License..
 
 use sgx_rand::{Rng, StdRng};
-use std::sgxfs::{self, SgxFile};
+use std::sgxfs::{self, SgxFile, SgxDir, SgxIntegrityFile, OpenOptions};
 use std::untrusted::fs::File;
-use std::untrusted::fs::remove_file;
-use std::io::{Read, Write};
+use std::untrusted::fs::{self as untrusted_fs, remove_file, remove_dir_all, read_dir};
+use std::io::{ErrorKind, Read, Write, Seek, SeekFrom};
 use std::string::*;
+use std::path::PathBuf;
+use std::vec::Vec;
 
 pub fn test_sgxfs() {
 
@@ -86,6 +88,138 @@ pub fn test_sgxfs() {
     }
 }
 
+pub fn test_sgxfs_dir() {
+    let key: [u8; 16] = [0x5a; 16];
+    {
+        let dir = SgxDir::create_ex("sgx_dir", &key).unwrap();
+        dir.write("docs/a.txt", b"hello").unwrap();
+        dir.write("docs/sub/b.txt", b"world!").unwrap();
+        dir.write("top", b"1").unwrap();
+        assert_eq!(dir.write("docs", b"x").is_err(), true);
+        assert_eq!(dir.write("top/x", b"x").is_err(), true);
+
+        let root: Vec<(String, bool)> = dir.read_dir("").unwrap().iter()
+            .map(|e| (e.file_name().to_string(), e.metadata().is_dir()))
+            .collect();
+        assert_eq!(root, vec![("docs".to_string(), true), ("top".to_string(), false)]);
+        let docs: Vec<PathBuf> = dir.read_dir("docs").unwrap().iter().map(|e| e.path().to_path_buf()).collect();
+        assert_eq!(docs, vec![PathBuf::from("docs/a.txt"), PathBuf::from("docs/sub")]);
+
+        let meta = dir.metadata("docs/sub/b.txt").unwrap();
+        assert_eq!(meta.is_file(), true);
+        assert_eq!(meta.len(), 6);
+        assert_eq!(dir.metadata("docs").unwrap().is_dir(), true);
+        assert_eq!(dir.metadata("missing").is_err(), true);
+
+        {
+            let mut file = dir.open_file_with("top", OpenOptions::new().append(true)).unwrap();
+            file.write_all(b"23").unwrap();
+        }
+        assert_eq!(dir.metadata("top").unwrap().len(), 3);
+
+        dir.rename("docs", "renamed").unwrap();
+        assert_eq!(dir.read("renamed/sub/b.txt").unwrap(), b"world!");
+        assert_eq!(dir.read("docs/a.txt").is_err(), true);
+        assert_eq!(dir.rename("renamed", "renamed/inner").is_err(), true);
+        dir.rename("top", "renamed/a.txt").unwrap();
+        assert_eq!(dir.read("renamed/a.txt").unwrap(), b"123");
+    }
+    {
+        assert_eq!(SgxDir::open("sgx_dir").is_err(), true);
+        let dir = SgxDir::open_ex("sgx_dir", &key).unwrap();
+        assert_eq!(dir.read("renamed/a.txt").unwrap(), b"123");
+        dir.remove_file("renamed/a.txt").unwrap();
+        assert_eq!(dir.read_dir("renamed").unwrap().len(), 1);
+
+        // The host only sees the two index slots and one randomly named file.
+        let names: Vec<String> = read_dir("sgx_dir").unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        assert_eq!(names.len(), 3);
+        assert_eq!(names.iter().any(|n| n.contains("sub") || n.contains("b.txt")), false);
+    }
+    assert_eq!(remove_dir_all("sgx_dir").is_ok(), true);
+}
+
+pub fn test_sgxfs_rekey() {
+    let old_key: [u8; 16] = [0x11; 16];
+    let new_key: [u8; 16] = [0x22; 16];
+    let mut data = vec![0_u8; 10000];
+    let mut rand = StdRng::new().unwrap();
+    rand.fill_bytes(&mut data);
+
+    {
+        let mut file = SgxFile::create("sgx_rekey_file").unwrap();
+        file.write_all(&data).unwrap();
+    }
+    sgxfs::rekey_from_auto_key("sgx_rekey_file", &old_key).unwrap();
+    assert_eq!(SgxFile::open("sgx_rekey_file").is_err(), true);
+
+    assert_eq!(sgxfs::rekey("sgx_rekey_file", &new_key, &old_key).is_err(), true);
+    sgxfs::rekey("sgx_rekey_file", &old_key, &new_key).unwrap();
+    assert_eq!(SgxFile::open_ex("sgx_rekey_file", &old_key).is_err(), true);
+
+    let mut read_data = Vec::new();
+    SgxFile::open_ex("sgx_rekey_file", &new_key).unwrap().read_to_end(&mut read_data).unwrap();
+    assert_eq!(read_data, data);
+
+    let leftovers = read_dir(".").unwrap()
+        .filter(|e| e.as_ref().unwrap().file_name().to_string_lossy().contains(".rekey-"))
+        .count();
+    assert_eq!(leftovers, 0);
+    assert_eq!(sgxfs::remove("sgx_rekey_file").is_ok(), true);
+}
+
+pub fn test_sgxfs_integrity() {
+    let key: [u8; 16] = [0x33; 16];
+    let mut data = vec![0_u8; 3 * 4096 + 100];
+    let mut rand = StdRng::new().unwrap();
+    rand.fill_bytes(&mut data);
+
+    {
+        let mut file = OpenOptions::new().write(true).open_integrity("sgx_integrity_file", &key).unwrap();
+        file.write_all(&data).unwrap();
+        file.finish().unwrap();
+    }
+    // The data itself stays readable by the host.
+    assert_eq!(untrusted_fs::read("sgx_integrity_file").unwrap(), data);
+
+    {
+        let mut file = SgxIntegrityFile::open("sgx_integrity_file", &key).unwrap();
+        assert_eq!(file.len(), data.len() as u64);
+        let mut buf = [0_u8; 200];
+        assert_eq!(file.read_at(&mut buf, 4000).unwrap(), 200);
+        assert_eq!(&buf[..], &data[4000..4200]);
+        file.seek(SeekFrom::End(-50)).unwrap();
+        let mut tail = Vec::new();
+        file.read_to_end(&mut tail).unwrap();
+        assert_eq!(&tail[..], &data[data.len() - 50..]);
+        assert_eq!(file.write(b"x").is_err(), true);
+    }
+    assert_eq!(SgxIntegrityFile::open("sgx_integrity_file", &[0x44; 16]).is_err(), true);
+    assert_eq!(OpenOptions::new().read(true).write(true).open_integrity("sgx_integrity_file", &key).is_err(), true);
+
+    {
+        let mut tampered = data.clone();
+        tampered[2 * 4096] ^= 1;
+        untrusted_fs::write("sgx_integrity_file", &tampered).unwrap();
+        let mut file = SgxIntegrityFile::open("sgx_integrity_file", &key).unwrap();
+        let mut buf = [0_u8; 16];
+        assert_eq!(file.read_at(&mut buf, 0).is_ok(), true);
+        assert_eq!(file.read_at(&mut buf, 2 * 4096).is_err(), true);
+    }
+    {
+        // A data length that overflows the block count is rejected.
+        let mut macs = untrusted_fs::read("sgx_integrity_file.mac").unwrap();
+        macs[16..24].copy_from_slice(&u64::max_value().to_le_bytes());
+        untrusted_fs::write("sgx_integrity_file.mac", &macs).unwrap();
+        let err = SgxIntegrityFile::open("sgx_integrity_file", &key).err().unwrap();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+    assert_eq!(remove_file("sgx_integrity_file").is_ok(), true);
+    assert_eq!(remove_file("sgx_integrity_file.mac").is_ok(), true);
+}
+
 pub fn test_fs () {
     {
         let f = File::create("foo.txt");
@@ -0,0 +1,101 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+use std::io::ErrorKind;
+use std::net::{Events, Interest, Poll, TcpListener, TcpStream, Token, UdpSocket};
+use std::os::unix::io::AsRawFd;
+use std::time::Duration;
+use sgx_trts::libc::{self, c_int, epoll_event};
+
+fn raw(fd: u64, events: c_int) -> epoll_event {
+    epoll_event { events: events as u32, u64: fd }
+}
+
+pub fn test_poll_round_trip() {
+    let mut poll = Poll::new().unwrap();
+    let mut events = Events::with_capacity(8);
+    let mut listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.set_nonblocking(true).unwrap();
+    poll.registry().register(&mut listener, Token(1), Interest::READABLE).unwrap();
+    assert_eq!(poll.registry().register(&mut listener, Token(2), Interest::READABLE).unwrap_err().kind(),
+               ErrorKind::AlreadyExists);
+
+    poll.poll(&mut events, Some(Duration::from_millis(10))).unwrap();
+    assert!(events.is_empty());
+
+    let _client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    poll.poll(&mut events, Some(Duration::from_secs(5))).unwrap();
+    assert_eq!(events.iter().count(), 1);
+    let event = events.iter().next().unwrap();
+    assert_eq!(event.token(), Token(1));
+    assert!(event.is_readable());
+    listener.accept().unwrap();
+
+    poll.registry().deregister(&mut listener).unwrap();
+    assert_eq!(poll.registry().deregister(&mut listener).unwrap_err().kind(), ErrorKind::NotFound);
+    assert_eq!(poll.registry().reregister(&mut listener, Token(1), Interest::READABLE).unwrap_err().kind(),
+               ErrorKind::NotFound);
+}
+
+pub fn test_poll_event_count_is_bounded() {
+    let poll = Poll::new().unwrap();
+    let mut events = Events::with_capacity(2);
+    let mut socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    poll.registry().register(&mut socket, Token(5), Interest::READABLE | Interest::WRITABLE).unwrap();
+    let fd = socket.as_raw_fd() as u64;
+
+    let buffer = [raw(fd, libc::EPOLLIN), raw(fd, libc::EPOLLOUT)];
+    poll.translate_raw_events(&buffer, 2, &mut events).unwrap();
+    assert_eq!(events.iter().count(), 2);
+
+    for &n in &[3, c_int::max_value(), -2, c_int::min_value()] {
+        let err = poll.translate_raw_events(&buffer, n, &mut events).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(events.is_empty());
+    }
+    poll.registry().deregister(&mut socket).unwrap();
+}
+
+pub fn test_poll_drops_unregistered_fds() {
+    let poll = Poll::new().unwrap();
+    let mut events = Events::with_capacity(4);
+    let mut socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    poll.registry().register(&mut socket, Token(5), Interest::READABLE).unwrap();
+    let fd = socket.as_raw_fd() as u64;
+
+    let buffer = [
+        raw(fd + 1, libc::EPOLLIN),
+        raw(u64::from(u32::max_value()) + 1 + fd, libc::EPOLLIN),
+        raw(fd, libc::EPOLLIN),
+    ];
+    poll.translate_raw_events(&buffer, 3, &mut events).unwrap();
+    assert_eq!(events.iter().count(), 1);
+    let event = events.iter().next().unwrap();
+    assert_eq!(event.token(), Token(5));
+    assert!(event.is_readable());
+
+    // Bits the source was not registered for are dropped too.
+    let buffer = [raw(fd, libc::EPOLLOUT)];
+    poll.translate_raw_events(&buffer, 1, &mut events).unwrap();
+    assert!(events.is_empty());
+
+    poll.registry().deregister(&mut socket).unwrap();
+    let buffer = [raw(fd, libc::EPOLLIN)];
+    poll.translate_raw_events(&buffer, 1, &mut events).unwrap();
+    assert!(events.is_empty());
+}
@@ -100,3 +100,180 @@ pub fn test_mac_aadata_slice() {
     let inner_slice = unsafe {slice::from_raw_parts(inner as *mut u8, 10)};
     assert_eq!(inner_slice, aad_data);
 }
+
+pub fn test_seal_migrate() {
+    let data: [u8; 10] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+    let aad: [u8; 3] = [7, 8, 9];
+    let sealed_data = SgxSealedData::<[u8]>::seal_data(&aad, &data).expect("error while sealing array");
+    assert_eq!(sealed_data.get_seal_status(), SgxSealStatus::Current);
+
+    let migrated = sealed_data.migrate_data().expect("error while migrating array");
+    assert_eq!(migrated.get_seal_status(), SgxSealStatus::Current);
+    assert_eq!(migrated.get_key_request().key_policy, sealed_data.get_key_request().key_policy);
+    assert_ne!(migrated.get_key_request().key_id.id, sealed_data.get_key_request().key_id.id);
+    let unsealed_data = migrated.unseal_data().expect("error while unsealing array");
+    assert_eq!(unsealed_data.get_decrypt_txt(), data);
+    assert_eq!(unsealed_data.get_additional_txt(), aad);
+
+    let resealed = sealed_data.reseal_data(&SgxSealPolicy::mrenclave()).expect("error while resealing array");
+    assert_eq!(resealed.get_key_request().key_policy & SGX_KEYPOLICY_MRENCLAVE, SGX_KEYPOLICY_MRENCLAVE);
+    assert_eq!(resealed.unseal_data().unwrap().get_decrypt_txt(), data);
+
+    let mmac = SgxMacAadata::<[u8]>::mac_aadata(&data).expect("error while mac data");
+    let migrated_mac = mmac.migrate_data().expect("error while migrating mac data");
+    assert_eq!(&*migrated_mac.unmac_aadata().unwrap(), &data[..]);
+}
+
+pub fn test_seal_envelope() {
+    let data: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+    let aad: [u8; 4] = [0xaa, 0xbb, 0xcc, 0xdd];
+    let envelope = SgxSealedEnvelope::seal(&SgxSealPolicy::mrsigner(), 1, &aad, &data).unwrap();
+    assert_eq!(envelope.get_format_version(), Some(SGX_SEAL_ENVELOPE_VERSION));
+    assert_eq!(envelope.get_schema_version(), Some(1));
+    assert_eq!(envelope.get_additional_txt(), aad);
+    assert_eq!(envelope.needs_upgrade(1), false);
+    assert_eq!(envelope.needs_upgrade(2), true);
+
+    let size = SgxSealedEnvelope::calc_raw_sealed_data_size(aad.len() as u32, data.len() as u32);
+    assert_eq!(size, envelope.get_raw_sealed_data_size());
+    let mut sealed_log = vec![0_u8; size as usize];
+    let p = sealed_log.as_mut_ptr() as * mut sgx_sealed_data_t;
+    assert!(unsafe { envelope.to_raw_sealed_data_t(p, size) }.is_some());
+    let envelope = unsafe { SgxSealedEnvelope::from_raw_sealed_data_t(p, size) }.unwrap();
+
+    let unsealed = envelope.unseal().unwrap();
+    assert_eq!(unsealed.get_schema_version(), 1);
+    assert_eq!(unsealed.get_decrypt_txt(), data);
+    assert_eq!(unsealed.get_additional_txt(), aad);
+
+    // A plain sealed blob is not an envelope.
+    let sealed_data = SgxSealedData::<[u8]>::seal_data(&aad, &data).unwrap();
+    let plain_size = SgxSealedData::<[u8]>::calc_raw_sealed_data_size(aad.len() as u32, data.len() as u32);
+    let mut plain_log = vec![0_u8; plain_size as usize];
+    let plain_p = plain_log.as_mut_ptr() as * mut sgx_sealed_data_t;
+    assert!(unsafe { sealed_data.to_raw_sealed_data_t(plain_p, plain_size) }.is_some());
+    assert!(unsafe { SgxSealedEnvelope::from_raw_sealed_data_t(plain_p, plain_size) }.is_none());
+
+    let upgraded = envelope.upgrade(2, |schema, old| {
+        assert_eq!(schema, 1);
+        let mut new = old.to_vec();
+        new.push(9);
+        Ok(new)
+    }).unwrap();
+    assert_eq!(upgraded.get_schema_version(), Some(2));
+    let unsealed = upgraded.unseal().unwrap();
+    assert_eq!(unsealed.get_decrypt_txt(), &[1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    assert_eq!(unsealed.get_additional_txt(), aad);
+
+    assert_eq!(upgraded.upgrade(1, |_, old| Ok(old.to_vec())).err(),
+               Some(sgx_status_t::SGX_ERROR_INVALID_VERSION));
+    let same = upgraded.upgrade(2, |_, _| Err(sgx_status_t::SGX_ERROR_UNEXPECTED)).unwrap();
+    assert_eq!(same.get_key_request().key_id.id, upgraded.get_key_request().key_id.id);
+}
+
+pub fn test_sealed_blob() {
+    let data = [0x5a_u8; 32];
+    let aad = b"sealed blob";
+
+    let blob = SealedBlob::seal_data(aad, &data).unwrap();
+    let bytes = blob.to_bytes();
+    assert_eq!(bytes.len() as u32, blob.get_raw_sealed_data_size());
+
+    // The bytes match the raw sgx_sealed_data_t layout.
+    let sealed_data = SealedBlob::from_bytes(&bytes).unwrap().into_sealed_data::<[u8; 32]>();
+    let unsealed = sealed_data.unseal_data().unwrap();
+    assert_eq!(unsealed.get_decrypt_txt(), &data);
+    let mut raw_log = vec![0_u64; (bytes.len() + 7) / 8];
+    let raw_p = raw_log.as_mut_ptr() as * mut sgx_sealed_data_t;
+    assert!(unsafe { sealed_data.to_raw_sealed_data_t(raw_p, bytes.len() as u32) }.is_some());
+    let raw_bytes = unsafe { std::slice::from_raw_parts(raw_p as * const u8, bytes.len()) };
+    assert_eq!(SealedBlob::from_bytes(raw_bytes).unwrap().to_bytes(), bytes);
+
+    let unsealed = SealedBlob::from_bytes(&bytes).unwrap().unseal_data().unwrap();
+    assert_eq!(unsealed.get_decrypt_txt(), &data[..]);
+    assert_eq!(unsealed.get_additional_txt(), aad);
+
+    let sealed_data = SgxSealedData::<[u8]>::seal_data(aad, &data).unwrap();
+    let blob = SealedBlob::from(sealed_data);
+    assert_eq!(blob.get_encrypt_txt().len(), data.len());
+    assert_eq!(blob.get_additional_txt(), aad);
+
+    // Truncated, padded or inconsistent buffers are rejected before anything is read.
+    let invalid = Some(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    assert_eq!(SealedBlob::from_bytes(&bytes[..100]).err(), invalid);
+    assert_eq!(SealedBlob::from_bytes(&bytes[..bytes.len() - 1]).err(), invalid);
+    let mut padded = bytes.clone();
+    padded.push(0);
+    assert_eq!(SealedBlob::from_bytes(&padded).err(), invalid);
+    let offset = std::mem::size_of::<sgx_key_request_t>();
+    let mut bad_offset = bytes.clone();
+    bad_offset[offset..offset + 4].copy_from_slice(&u32::max_value().to_le_bytes());
+    assert_eq!(SealedBlob::from_bytes(&bad_offset).err(), invalid);
+
+    let mut tampered = bytes.clone();
+    let last = tampered.len() - 1;
+    tampered[last] ^= 0x01;
+    assert_eq!(SealedBlob::from_bytes(&tampered).unwrap().unseal_data().err(),
+               Some(sgx_status_t::SGX_ERROR_MAC_MISMATCH));
+}
+
+struct TestCounter {
+    value: std::cell::Cell<u32>,
+}
+
+impl CounterBackend for TestCounter {
+    fn counter_id(&self) -> SgxResult<[u8; SGX_COUNTER_ID_SIZE]> {
+        Ok([0x11; SGX_COUNTER_ID_SIZE])
+    }
+
+    fn read(&self) -> SgxResult<u32> {
+        Ok(self.value.get())
+    }
+
+    fn increment(&self) -> SgxResult<u32> {
+        self.value.set(self.value.get() + 1);
+        Ok(self.value.get())
+    }
+}
+
+pub fn test_seal_rollback() {
+    let store = RollbackProtectedStore::new(TestCounter { value: std::cell::Cell::new(7) });
+
+    let v1 = store.seal(b"state", b"version 1").unwrap();
+    assert_eq!(store.get_backend().read().unwrap(), 8);
+    let unsealed = store.unseal(&v1).unwrap();
+    assert_eq!(unsealed.get_decrypt_txt(), b"version 1");
+    assert_eq!(unsealed.get_additional_txt(), b"state");
+
+    // The host replays the first version after a second one has been sealed.
+    let v2 = store.seal(b"state", b"version 2").unwrap();
+    assert!(store.is_current(&v2).unwrap());
+    assert!(!store.is_current(&v1).unwrap());
+    assert_eq!(store.unseal(&v1).err(), Some(sgx_status_t::SGX_ERROR_MC_NOT_FOUND));
+    assert_eq!(store.unseal(&v2).unwrap().get_decrypt_txt(), b"version 2");
+
+    // A blob that was not sealed by a store, and a blob with a forged binding.
+    let plain = SealedBlob::seal_data(b"state", b"version 2").unwrap();
+    assert_eq!(store.unseal(&plain).err(), Some(sgx_status_t::SGX_ERROR_MAC_MISMATCH));
+    let mut forged = v1.to_bytes();
+    let offset = forged.len() - b"state".len() - 8;
+    forged[offset..offset + 4].copy_from_slice(&9_u32.to_le_bytes());
+    let forged = SealedBlob::from_bytes(&forged).unwrap();
+    assert_eq!(store.unseal(&forged).err(), Some(sgx_status_t::SGX_ERROR_MAC_MISMATCH));
+
+    let store = RollbackProtectedStore::new(TestCounter { value: std::cell::Cell::new(u32::max_value()) });
+    assert_eq!(store.seal(&[], b"data").err(), Some(sgx_status_t::SGX_ERROR_MC_USED_UP));
+
+    // The file-backed stand-in for simulation mode.
+    let counter = std::sgxfs::SgxFileCounter::create("sgx_rollback_counter").unwrap();
+    let store = RollbackProtectedStore::new(counter);
+    let v1 = store.seal(&[], b"file 1").unwrap();
+    let v2 = store.seal(&[], b"file 2").unwrap();
+    assert_eq!(store.unseal(&v1).err(), Some(sgx_status_t::SGX_ERROR_MC_NOT_FOUND));
+    assert_eq!(store.unseal(&v2).unwrap().get_decrypt_txt(), b"file 2");
+    let counter = store.into_backend();
+    let reopened = std::sgxfs::SgxFileCounter::open(counter.path()).unwrap();
+    assert_eq!(reopened.read().unwrap(), 2);
+    assert_eq!(reopened.counter_id().unwrap(), counter.counter_id().unwrap());
+    assert!(std::sgxfs::remove("sgx_rollback_counter").is_ok());
+}
@@ -2,6 +2,9 @@ use std::vec::Vec;
 use std::fmt::Debug;
 use std::string::{ToString, String};
 use sgx_serialize::{Serializable, DeSerializable, SerializeHelper, DeSerializeHelper};
+use sgx_serialize::{seal_object, seal_object_ex, unseal_object, SgxUnsealedObject};
+use sgx_tseal::SgxSealPolicy;
+use sgx_types::*;
 
 fn test_serialize_internal<T: Serializable + DeSerializable>(target: &T) -> Option<T>{
     let helper = SerializeHelper::new();
@@ -340,4 +343,40 @@ pub fn test_serialize_base() {
     test_sequence();
     test_hash_map();
     test_tuples();
-}
\ No newline at end of file
+}
+
+pub fn test_serialize_seal() {
+    use std::collections::HashMap;
+
+    #[derive(Serializable, DeSerializable, PartialEq, Debug)]
+    struct Account {
+        name: String,
+        keys: Vec<u8>,
+        limits: HashMap<String, u64>,
+    }
+
+    let mut limits = HashMap::new();
+    limits.insert("daily".to_string(), 1000u64);
+    limits.insert("monthly".to_string(), 20000u64);
+    let account = Account {
+        name: "alice".to_string(),
+        keys: vec![1, 2, 3, 4, 5, 6, 7, 8],
+        limits: limits,
+    };
+
+    let sealed = seal_object(b"account v1", &account).unwrap();
+    let unsealed: SgxUnsealedObject<Account> = unseal_object(&sealed).unwrap();
+    assert_eq!(unsealed.get_additional_txt(), b"account v1");
+    assert_eq!(unsealed.get_object(), &account);
+
+    let sealed = seal_object_ex(&SgxSealPolicy::mrenclave(), &[], &account).unwrap();
+    let unsealed = unseal_object::<Account>(&sealed).unwrap();
+    assert_eq!(unsealed.get_additional_txt().len(), 0);
+    assert_eq!(unsealed.into_object(), account);
+
+    let mut tampered = sealed.clone();
+    let last = tampered.len() - 1;
+    tampered[last] ^= 0x01;
+    assert_eq!(unseal_object::<Account>(&tampered).err(), Some(sgx_status_t::SGX_ERROR_MAC_MISMATCH));
+    assert_eq!(unseal_object::<Account>(&sealed[..16]).err(), Some(sgx_status_t::SGX_ERROR_INVALID_PARAMETER));
+}
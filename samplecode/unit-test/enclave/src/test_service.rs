@@ -0,0 +1,157 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+use sgx_types::*;
+use sgx_tservice::sgxtime::*;
+use sgx_tservice::sgxcounter::*;
+use sgx_tservice::remote::*;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::vec::Vec;
+use std::boxed::Box;
+
+// Stands in for the remote time and counter service, speaking the same protocol.
+struct MockServer {
+    key: sgx_cmac_128bit_key_t,
+    time: Cell<u64>,
+    counters: RefCell<Vec<(sgx_mc_uuid_t, u32)>>,
+    next_id: Cell<u8>,
+    last_response: RefCell<Vec<u8>>,
+}
+
+impl MockServer {
+    fn handle(&self, request: &ServiceRequest) -> ServiceResponse {
+        let mut counters = self.counters.borrow_mut();
+        let find = |uuid: &sgx_mc_uuid_t| counters.iter().position(|(u, _)| u.counter_id == uuid.counter_id && u.nonce == uuid.nonce);
+        match *request {
+            ServiceRequest::GetTrustedTime => {
+                self.time.set(self.time.get() + 1);
+                ServiceResponse::TrustedTime { timestamp: self.time.get(), source_nonce: [7; 32] }
+            },
+            ServiceRequest::CreateCounter { owner_policy, .. } => {
+                if owner_policy != SGX_MC_POLICY_SIGNER { return ServiceResponse::Error(sgx_status_t::SGX_ERROR_MC_NO_ACCESS_RIGHT); }
+                let mut uuid = sgx_mc_uuid_t::default();
+                self.next_id.set(self.next_id.get() + 1);
+                uuid.counter_id[0] = self.next_id.get();
+                counters.push((uuid, 0));
+                ServiceResponse::CounterCreated { counter_uuid: uuid, counter_value: 0 }
+            },
+            ServiceRequest::DestroyCounter(ref uuid) => match find(uuid) {
+                Some(i) => { counters.remove(i); ServiceResponse::CounterDestroyed },
+                None => ServiceResponse::Error(sgx_status_t::SGX_ERROR_MC_NOT_FOUND),
+            },
+            ServiceRequest::IncrementCounter(ref uuid) => match find(uuid) {
+                Some(i) => { counters[i].1 += 1; ServiceResponse::CounterValue(counters[i].1) },
+                None => ServiceResponse::Error(sgx_status_t::SGX_ERROR_MC_NOT_FOUND),
+            },
+            ServiceRequest::ReadCounter(ref uuid) => match find(uuid) {
+                Some(i) => ServiceResponse::CounterValue(counters[i].1),
+                None => ServiceResponse::Error(sgx_status_t::SGX_ERROR_MC_NOT_FOUND),
+            },
+        }
+    }
+}
+
+struct MockTransport(Rc<MockServer>, Cell<bool>);
+
+impl ServiceTransport for MockTransport {
+    fn call(&self, frame: &[u8]) -> SgxResult<Vec<u8>> {
+        let server = &self.0;
+        if self.1.get() {
+            return Ok(server.last_response.borrow().clone());
+        }
+        let (nonce, request) = ServiceRequest::decode(&server.key, frame)?;
+        let response = server.handle(&request).encode(&server.key, &request, &nonce)?;
+        *server.last_response.borrow_mut() = response.clone();
+        Ok(response)
+    }
+}
+
+struct FixedTimeSource;
+
+impl TrustedTimeSource for FixedTimeSource {
+    fn get_trusted_time(&self) -> SgxResult<(sgx_time_t, sgx_time_source_nonce_t)> {
+        Ok((42, [1; 32]))
+    }
+}
+
+static FIXED_TIME_SOURCE: FixedTimeSource = FixedTimeSource;
+
+pub fn test_trusted_service() {
+    let key = [3_u8; 16];
+    let server = Rc::new(MockServer { key, time: Cell::new(1000), counters: RefCell::new(Vec::new()), next_id: Cell::new(0), last_response: RefCell::new(Vec::new()) });
+    let service: &'static RemoteTrustedService<MockTransport> =
+        Box::leak(Box::new(RemoteTrustedService::new(MockTransport(server.clone(), Cell::new(false)), &key)));
+
+    let t1 = SgxTime::now_from(service).ok().unwrap();
+    let t2 = SgxTime::now_from(service).ok().unwrap();
+    assert_eq!(t2.duration_since(&t1).ok(), Some(1));
+    assert_eq!(t1.get_source_nonce(), [7; 32]);
+
+    let mut value = 99;
+    let counter = SgxMonotonicCounter::new_with_source(service, SGX_MC_POLICY_SIGNER, &SGX_MC_DEFAULT_OWNER_ATTRIBUTE_MASK, &mut value).unwrap();
+    assert_eq!(value, 0);
+    assert_eq!(counter.increment().unwrap(), 1);
+    assert_eq!(counter.increment().unwrap(), 2);
+    assert_eq!(counter.read().unwrap(), 2);
+    let uuid = counter.into_uuid().unwrap();
+    assert_eq!(server.counters.borrow().len(), 1);
+    let counter = SgxMonotonicCounter::from_uuid_with_source(service, uuid);
+    assert_eq!(counter.read().unwrap(), 2);
+    counter.destory().unwrap();
+    assert_eq!(counter.read().err(), Some(sgx_status_t::SGX_ERROR_MC_NOT_FOUND));
+    let stale = SgxMonotonicCounter::from_uuid_with_source(service, uuid);
+    assert_eq!(stale.read().err(), Some(sgx_status_t::SGX_ERROR_MC_NOT_FOUND));
+    std::mem::forget(stale);
+    assert_eq!(SgxMonotonicCounter::new_with_source(service, SGX_MC_POLICY_ENCLAVE, &SGX_MC_DEFAULT_OWNER_ATTRIBUTE_MASK, &mut value).err(),
+               Some(sgx_status_t::SGX_ERROR_MC_NO_ACCESS_RIGHT));
+
+    // Replayed response.
+    service.get_transport().1.set(true);
+    assert_eq!(service.get_trusted_time().err(), Some(sgx_status_t::SGX_ERROR_MAC_MISMATCH));
+    service.get_transport().1.set(false);
+
+    // Wrong key.
+    let other = RemoteTrustedService::new(MockTransport(server.clone(), Cell::new(false)), &[4; 16]);
+    assert_eq!(other.get_trusted_time().err(), Some(sgx_status_t::SGX_ERROR_MAC_MISMATCH));
+
+    // Frame codec.
+    let nonce = [9_u8; 16];
+    let req = ServiceRequest::ReadCounter(uuid);
+    let frame = req.encode(&key, &nonce).unwrap();
+    assert_eq!(frame.len(), 28 + 16 + 16);
+    let mut bad = frame.clone(); bad[30] ^= 1;
+    assert_eq!(ServiceRequest::decode(&key, &bad).err(), Some(sgx_status_t::SGX_ERROR_MAC_MISMATCH));
+    assert_eq!(ServiceRequest::decode(&key, &frame[..50]).err(), Some(sgx_status_t::SGX_ERROR_MAC_MISMATCH));
+    assert_eq!(ServiceRequest::decode(&key, &frame[..10]).err(), Some(sgx_status_t::SGX_ERROR_INVALID_PARAMETER));
+    let resp = ServiceResponse::CounterValue(5).encode(&key, &req, &nonce).unwrap();
+    assert!(ServiceRequest::decode(&key, &resp).is_err());
+    match ServiceResponse::decode(&key, &req, &nonce, &resp).unwrap() { ServiceResponse::CounterValue(5) => {}, _ => panic!() }
+    assert_eq!(ServiceResponse::decode(&key, &ServiceRequest::IncrementCounter(uuid), &nonce, &resp).err(), Some(sgx_status_t::SGX_ERROR_MAC_MISMATCH));
+    assert_eq!(ServiceResponse::decode(&key, &req, &[8; 16], &resp).err(), Some(sgx_status_t::SGX_ERROR_MAC_MISMATCH));
+    assert_eq!(ServiceResponse::CounterDestroyed.encode(&key, &req, &nonce).err(), Some(sgx_status_t::SGX_ERROR_INVALID_PARAMETER));
+    let err = ServiceResponse::Error(sgx_status_t::SGX_ERROR_BUSY).encode(&key, &req, &nonce).unwrap();
+    match ServiceResponse::decode(&key, &req, &nonce, &err).unwrap() { ServiceResponse::Error(sgx_status_t::SGX_ERROR_BUSY) => {}, _ => panic!() }
+
+    // SgxTime::now() reads the registered source.
+    assert!(rsgx_set_trusted_time_source(&FIXED_TIME_SOURCE).is_ok());
+    assert_eq!(rsgx_set_trusted_time_source(&FIXED_TIME_SOURCE).err(), Some(sgx_status_t::SGX_ERROR_INVALID_STATE));
+    let now = SgxTime::now().ok().unwrap();
+    assert_eq!(now.get_secs(), 42);
+    assert_eq!(now.get_source_nonce(), [1; 32]);
+}
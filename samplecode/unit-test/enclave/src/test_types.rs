@@ -1,5 +1,7 @@
 use sgx_types::metadata::*;
 use sgx_types::*;
+use sgx_types::quote::*;
+use std::vec::Vec;
 
 use core::mem;
 
@@ -22,3 +24,89 @@ pub fn check_version() {
     assert_eq!(major_version_of_metadata!(curr_version), MAJOR_VERSION as u64);
     assert_eq!(minor_version_of_metadata!(curr_version), MINOR_VERSION as u64);
 }
+
+fn quote_bytes(version: u16, key_type: u16, sig: &[u8]) -> Vec<u8> {
+    let mut quote = vec![0_u8; QUOTE_BODY_SIZE];
+    quote[0..2].copy_from_slice(&version.to_le_bytes());
+    quote[2..4].copy_from_slice(&key_type.to_le_bytes());
+    quote[8..10].copy_from_slice(&7_u16.to_le_bytes());
+    // report body: mr_enclave, mr_signer, isv_prod_id, isv_svn, report_data
+    quote[48 + 48..48 + 56].copy_from_slice(&0x05_u64.to_le_bytes());
+    quote[48 + 64..48 + 96].copy_from_slice(&[0xaa; 32]);
+    quote[48 + 128..48 + 160].copy_from_slice(&[0xbb; 32]);
+    quote[48 + 256..48 + 258].copy_from_slice(&0x1234_u16.to_le_bytes());
+    quote[48 + 258..48 + 260].copy_from_slice(&3_u16.to_le_bytes());
+    quote[48 + 320..48 + 384].copy_from_slice(&[0xcc; 64]);
+    quote.extend_from_slice(&(sig.len() as u32).to_le_bytes());
+    quote.extend_from_slice(sig);
+    quote
+}
+
+fn ecdsa_sig_data(auth: &[u8], cert: &[u8]) -> Vec<u8> {
+    let mut sig = vec![0x11_u8; 576];
+    sig[128 + 256..128 + 258].copy_from_slice(&1_u16.to_le_bytes());
+    sig.extend_from_slice(&(auth.len() as u16).to_le_bytes());
+    sig.extend_from_slice(auth);
+    sig.extend_from_slice(&5_u16.to_le_bytes());
+    sig.extend_from_slice(&(cert.len() as u32).to_le_bytes());
+    sig.extend_from_slice(cert);
+    sig
+}
+
+pub fn test_quote_view_epid() {
+    let quote = quote_bytes(2, 1, &[0x42; 10]);
+    let view = QuoteView::parse(&quote).unwrap();
+    assert_eq!(view.version(), 2);
+    assert_eq!(view.sign_type(), Some(1));
+    assert_eq!(view.qe_svn(), 7);
+    let body = view.report_body();
+    assert_eq!(body.mr_enclave().m, [0xaa; 32]);
+    assert_eq!(body.mr_signer().m, [0xbb; 32]);
+    assert_eq!(body.isv_prod_id(), 0x1234);
+    assert_eq!(body.isv_svn(), 3);
+    assert_eq!(body.attributes().flags, 5);
+    assert_eq!(body.report_data(), &[0xcc; 64][..]);
+    assert_eq!(body.to_report_body().isv_svn, 3);
+    match view.signature() {
+        QuoteSignature::Epid(sig) => assert_eq!(sig, &[0x42; 10][..]),
+        _ => panic!("expected an EPID signature"),
+    }
+
+    let body_only = QuoteView::parse_body(&quote[..QUOTE_BODY_SIZE]).unwrap();
+    assert_eq!(body_only.report_body().mr_enclave().m, [0xaa; 32]);
+    assert!(QuoteView::parse_body(&quote).is_err());
+
+    assert_eq!(QuoteView::parse(&quote[..quote.len() - 1]).unwrap_err(), QuoteParseError::LengthMismatch);
+    assert_eq!(QuoteView::parse(&quote[..QUOTE_BODY_SIZE]).unwrap_err(), QuoteParseError::Truncated);
+    assert_eq!(QuoteView::parse(&quote[..10]).unwrap_err(), QuoteParseError::Truncated);
+    assert_eq!(QuoteView::parse(&quote_bytes(4, 0, &[])).unwrap_err(), QuoteParseError::UnsupportedVersion(4));
+}
+
+pub fn test_quote_view_ecdsa() {
+    let quote = quote_bytes(3, SGX_QL_ALG_ECDSA_P256, &ecdsa_sig_data(&[1, 2, 3], b"-----BEGIN CERTIFICATE-----"));
+    let view = QuoteView::parse(&quote).unwrap();
+    assert_eq!(view.att_key_type(), Some(SGX_QL_ALG_ECDSA_P256));
+    assert_eq!(view.sign_type(), None);
+    assert_eq!(view.report_body().isv_prod_id(), 0x1234);
+    let sig = match view.signature() {
+        QuoteSignature::Ecdsa(sig) => sig,
+        _ => panic!("expected an ECDSA signature"),
+    };
+    assert_eq!(sig.signature(), &[0x11; 64][..]);
+    assert_eq!(sig.qe_report().isv_prod_id(), 1);
+    assert_eq!(sig.qe_auth_data(), &[1, 2, 3][..]);
+    assert_eq!(sig.certification_data().cert_key_type(), 5);
+    assert_eq!(sig.certification_data().data(), &b"-----BEGIN CERTIFICATE-----"[..]);
+
+    // certification data length running past the signature data
+    let mut bad = ecdsa_sig_data(&[1, 2, 3], b"cert");
+    bad.pop();
+    assert_eq!(QuoteView::parse(&quote_bytes(3, SGX_QL_ALG_ECDSA_P256, &bad)).unwrap_err(),
+               QuoteParseError::LengthMismatch);
+    // auth data length running past the signature data
+    let mut bad = vec![0_u8; 576];
+    bad.extend_from_slice(&100_u16.to_le_bytes());
+    assert_eq!(QuoteView::parse(&quote_bytes(3, SGX_QL_ALG_ECDSA_P256, &bad)).unwrap_err(),
+               QuoteParseError::Truncated);
+    assert_eq!(QuoteView::parse(&quote_bytes(3, 3, &[])).unwrap_err(), QuoteParseError::UnsupportedKeyType(3));
+}
@@ -29,6 +29,9 @@
 #[macro_use]
 extern crate alloc;
 
+#[cfg(test)]
+extern crate std;
+
 #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
 extern crate sgx_types;
 
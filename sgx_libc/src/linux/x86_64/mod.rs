@@ -38,7 +38,7 @@ extern {
     pub fn memalign(align: size_t, size: size_t) -> *mut c_void;
 }
 
-#[link(name = "sgx_tstdc")]
+#[cfg_attr(not(test), link(name = "sgx_tstdc"))]
 extern {
     //pub fn memchr(s: * const c_void, c: c_int, n: size_t) -> *mut c_void;
     //pub fn memrchr(cx: *const c_void, c: c_int, n: size_t) -> *mut c_void;
@@ -46,7 +46,7 @@ extern {
     pub fn malloc_usable_size(ptr: * const c_void) -> size_t;
 }
 
-#[link(name = "sgx_tstdc")]
+#[cfg_attr(not(test), link(name = "sgx_tstdc"))]
 extern {
     #[cfg_attr(target_os = "linux", link_name = "__errno_location")]
     fn errno_location() -> * mut c_int;
@@ -1289,6 +1289,7 @@ pub const ERFKILL: int32_t            = 132;
 pub const EHWPOISON: int32_t          = 133;
 pub const ENOTSUP: int32_t            = EOPNOTSUPP;
 pub const ESGX: int32_t               = 0x0000_FFFF;
+pub const EIAGO: int32_t              = 0x0000_FFFE;
 
 
 pub unsafe fn FD_CLR(fd: c_int, set: *mut fd_set) -> () {
@@ -1318,3 +1319,4 @@ pub unsafe fn FD_ZERO(set: *mut fd_set) -> () {
 }
 
 pub mod ocall;
+pub mod net_switchless;
@@ -0,0 +1,586 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//!
+//! Socket OCALLs from `sgx_net_switchless.edl`.
+//!
+//! These functions take the same arguments as their counterparts in `ocall`,
+//! but the OCALLs are declared `transition_using_threads`: when the enclave
+//! was created with untrusted switchless workers, a worker thread performs the
+//! call while the enclave thread stays inside the enclave. Without workers,
+//! or when all of them are busy, the SGX runtime falls back to a regular OCALL.
+//!
+
+use sgx_types::sgx_status_t;
+use super::*;
+use super::ocall;
+
+extern "C" {
+    pub fn u_net_socket_ocall(result: * mut c_int, error: * mut c_int, domain: c_int, ty: c_int, protocol: c_int) -> sgx_status_t;
+    pub fn u_net_socketpair_ocall(result: * mut c_int,
+                                  error: * mut c_int,
+                                  domain: c_int,
+                                  ty: c_int,
+                                  protocol: c_int,
+                                  sv: * mut c_int) -> sgx_status_t;
+    pub fn u_net_bind_ocall(result: * mut c_int,
+                            error: * mut c_int,
+                            sockfd: c_int,
+                            address: * const sockaddr,
+                            addrlen: socklen_t) -> sgx_status_t;
+    pub fn u_net_listen_ocall(result: * mut c_int, error: * mut c_int, sockfd: c_int, backlog: c_int) -> sgx_status_t;
+    pub fn u_net_accept4_ocall(result: * mut c_int,
+                               error: * mut c_int,
+                               sockfd: c_int,
+                               addr: * mut sockaddr,
+                               addrlen_in: socklen_t,
+                               addrlen_out: * mut socklen_t,
+                               flags: c_int) -> sgx_status_t;
+    pub fn u_net_connect_ocall(result: * mut c_int,
+                               error: * mut c_int,
+                               sockfd: c_int,
+                               address: * const sockaddr,
+                               addrlen: socklen_t) -> sgx_status_t;
+    pub fn u_net_recv_ocall(result: * mut ssize_t,
+                            error: * mut c_int,
+                            sockfd: c_int,
+                            buf: * mut c_void,
+                            len: size_t,
+                            flags: c_int) -> sgx_status_t;
+    pub fn u_net_recvfrom_ocall(result: * mut ssize_t,
+                                error: * mut c_int,
+                                sockfd: c_int,
+                                buf: * mut c_void,
+                                len: size_t,
+                                flags: c_int,
+                                addr: * mut sockaddr,
+                                addrlen_in: socklen_t,
+                                addrlen_out: * mut socklen_t) -> sgx_status_t;
+    pub fn u_net_recvmsg_ocall(result: * mut ssize_t,
+                               error: * mut c_int,
+                               sockfd: c_int,
+                               msg: * mut msghdr,
+                               flags: c_int) -> sgx_status_t;
+    pub fn u_net_send_ocall(result: * mut ssize_t,
+                            error: * mut c_int,
+                            sockfd: c_int,
+                            buf: * const c_void,
+                            len: size_t,
+                            flags: c_int) -> sgx_status_t;
+    pub fn u_net_sendto_ocall(result: * mut ssize_t,
+                              error: * mut c_int,
+                              sockfd: c_int,
+                              buf: * const c_void,
+                              len: size_t,
+                              flags: c_int,
+                              addr: * const sockaddr,
+                              addrlen: socklen_t) -> sgx_status_t;
+    pub fn u_net_sendmsg_ocall(result: * mut ssize_t,
+                               error: * mut c_int,
+                               sockfd: c_int,
+                               msg: * const msghdr,
+                               flags: c_int) -> sgx_status_t;
+    pub fn u_net_getsockopt_ocall(result: * mut c_int,
+                                  error: * mut c_int,
+                                  sockfd: c_int,
+                                  level: c_int,
+                                  optname: c_int,
+                                  optval: * mut c_void,
+                                  optlen_in: socklen_t,
+                                  optlen_out: * mut socklen_t) -> sgx_status_t;
+    pub fn u_net_setsockopt_ocall(result: * mut c_int,
+                                  error: * mut c_int,
+                                  sockfd: c_int,
+                                  level: c_int,
+                                  optname: c_int,
+                                  optval: * const c_void,
+                                  optlen: socklen_t) -> sgx_status_t;
+    pub fn u_net_getsockname_ocall(result: * mut c_int,
+                                   error: * mut c_int,
+                                   sockfd: c_int,
+                                   address: * mut sockaddr,
+                                   addrlen_in: socklen_t,
+                                   addrlen_out: * mut socklen_t) -> sgx_status_t;
+    pub fn u_net_getpeername_ocall(result: * mut c_int,
+                                   error: * mut c_int,
+                                   sockfd: c_int,
+                                   address: * mut sockaddr,
+                                   addrlen_in: socklen_t,
+                                   addrlen_out: * mut socklen_t) -> sgx_status_t;
+    pub fn u_net_shutdown_ocall(result: * mut c_int, error: * mut c_int, sockfd: c_int, how: c_int) -> sgx_status_t;
+    pub fn u_net_ioctl_ocall(result: * mut c_int,
+                             error: * mut c_int,
+                             fd: c_int,
+                             request: c_int,
+                             arg: * mut c_int) -> sgx_status_t;
+    pub fn u_net_poll_ocall(result: * mut c_int,
+                            error: * mut c_int,
+                            fds: * mut pollfd,
+                            nfds: nfds_t,
+                            timeout: c_int) -> sgx_status_t;
+}
+
+pub unsafe fn socket(domain: c_int, ty: c_int, protocol: c_int) -> c_int {
+    let mut result: c_int = 0;
+    let mut error: c_int = 0;
+    let status = u_net_socket_ocall(&mut result as * mut c_int,
+                                    &mut error as * mut c_int,
+                                    domain,
+                                    ty,
+                                    protocol);
+
+    if status == sgx_status_t::SGX_SUCCESS {
+        if result == -1 {
+            set_errno(error);
+        } else if result < 0 {
+            set_errno(EIAGO);
+            result = -1;
+        }
+    } else {
+        set_errno(ESGX);
+        result = -1;
+    }
+    result
+}
+
+pub unsafe fn socketpair(domain: c_int, ty: c_int, protocol: c_int, sv: * mut c_int) -> c_int {
+    let mut result: c_int = 0;
+    let mut error: c_int = 0;
+    let status = u_net_socketpair_ocall(&mut result as * mut c_int,
+                                        &mut error as * mut c_int,
+                                        domain,
+                                        ty,
+                                        protocol,
+                                        sv);
+
+    if status == sgx_status_t::SGX_SUCCESS {
+        if result == -1 {
+            set_errno(error);
+        } else if result != 0 || (!sv.is_null() && (*sv < 0 || *sv.add(1) < 0)) {
+            set_errno(EIAGO);
+            result = -1;
+        }
+    } else {
+        set_errno(ESGX);
+        result = -1;
+    }
+    result
+}
+
+pub unsafe fn bind(sockfd: c_int, address: * const sockaddr, addrlen: socklen_t) -> c_int {
+    let mut result: c_int = 0;
+    let mut error: c_int = 0;
+    let status = u_net_bind_ocall(&mut result as * mut c_int,
+                                  &mut error as * mut c_int,
+                                  sockfd,
+                                  address,
+                                  addrlen);
+
+    if status == sgx_status_t::SGX_SUCCESS {
+        if result == -1 {
+            set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
+        }
+    } else {
+        set_errno(ESGX);
+        result = -1;
+    }
+    result
+}
+
+pub unsafe fn listen(sockfd: c_int, backlog: c_int) -> c_int {
+    let mut result: c_int = 0;
+    let mut error: c_int = 0;
+    let status = u_net_listen_ocall(&mut result as * mut c_int,
+                                    &mut error as * mut c_int,
+                                    sockfd,
+                                    backlog);
+
+    if status == sgx_status_t::SGX_SUCCESS {
+        if result == -1 {
+            set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
+        }
+    } else {
+        set_errno(ESGX);
+        result = -1;
+    }
+    result
+}
+
+pub unsafe fn accept4(sockfd: c_int, addr: * mut sockaddr, addrlen: * mut socklen_t, flags: c_int) -> c_int {
+    let mut result: c_int = 0;
+    let mut error: c_int = 0;
+    let len_in: socklen_t = if !addrlen.is_null() { *addrlen } else { 0 };
+    let mut len_out: socklen_t = 0 as socklen_t;
+    let status = u_net_accept4_ocall(&mut result as * mut c_int,
+                                     &mut error as * mut c_int,
+                                     sockfd,
+                                     addr,
+                                     len_in,
+                                     &mut len_out as * mut socklen_t,
+                                     flags);
+
+    if status == sgx_status_t::SGX_SUCCESS {
+        if result == -1 {
+            set_errno(error);
+        } else if result < 0 || !ocall::is_valid_addrlen(len_in, len_out) {
+            set_errno(EIAGO);
+            result = -1;
+            len_out = 0;
+        }
+    } else {
+        set_errno(ESGX);
+        result = -1;
+    }
+
+    if !addrlen.is_null() {
+        *addrlen = len_out;
+    }
+    result
+}
+
+pub unsafe fn connect(sockfd: c_int, address: * const sockaddr, addrlen: socklen_t) -> c_int {
+    let mut result: c_int = 0;
+    let mut error: c_int = 0;
+    let status = u_net_connect_ocall(&mut result as * mut c_int,
+                                     &mut error as * mut c_int,
+                                     sockfd,
+                                     address,
+                                     addrlen);
+
+    if status == sgx_status_t::SGX_SUCCESS {
+        if result == -1 {
+            set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
+        }
+    } else {
+        set_errno(ESGX);
+        result = -1;
+    }
+    result
+}
+
+pub unsafe fn recv(sockfd: c_int, buf: * mut c_void, len: size_t, flags: c_int) -> ssize_t {
+    let mut result: ssize_t = 0;
+    let mut error: c_int = 0;
+    let status = u_net_recv_ocall(&mut result as * mut ssize_t,
+                                  &mut error as * mut c_int,
+                                  sockfd,
+                                  buf,
+                                  len,
+                                  flags);
+
+    if status == sgx_status_t::SGX_SUCCESS {
+        if result == -1 {
+            set_errno(error);
+        } else if result < 0 || result as size_t > len {
+            set_errno(EIAGO);
+            result = -1;
+        }
+    } else {
+        set_errno(ESGX);
+        result = -1;
+    }
+    result
+}
+
+pub unsafe fn recvfrom(sockfd: c_int, buf: * mut c_void, len: size_t, flags: c_int, addr: * mut sockaddr, addrlen: * mut socklen_t) -> ssize_t {
+    let mut result: ssize_t = 0;
+    let mut error: c_int = 0;
+    let len_in: socklen_t = if !addrlen.is_null() { *addrlen } else { 0 };
+    let mut len_out: socklen_t = 0 as socklen_t;
+    let status = u_net_recvfrom_ocall(&mut result as * mut ssize_t,
+                                      &mut error as * mut c_int,
+                                      sockfd,
+                                      buf,
+                                      len,
+                                      flags,
+                                      addr,
+                                      len_in,
+                                      &mut len_out as * mut socklen_t);
+
+    if status == sgx_status_t::SGX_SUCCESS {
+        if result == -1 {
+            set_errno(error);
+        } else if result < 0 || result as size_t > len || !ocall::is_valid_addrlen(len_in, len_out) {
+            set_errno(EIAGO);
+            result = -1;
+            len_out = 0;
+        }
+    } else {
+        set_errno(ESGX);
+        result = -1;
+    }
+
+    if !addrlen.is_null() {
+        *addrlen = len_out;
+    }
+    result
+}
+
+pub unsafe fn send(sockfd: c_int, buf: * const c_void, len: size_t, flags: c_int) -> ssize_t {
+    let mut result: ssize_t = 0;
+    let mut error: c_int = 0;
+    let status = u_net_send_ocall(&mut result as * mut ssize_t,
+                                  &mut error as * mut c_int,
+                                  sockfd,
+                                  buf,
+                                  len,
+                                  flags);
+
+    if status == sgx_status_t::SGX_SUCCESS {
+        if result == -1 {
+            set_errno(error);
+        } else if result < 0 || result as size_t > len {
+            set_errno(EIAGO);
+            result = -1;
+        }
+    } else {
+        set_errno(ESGX);
+        result = -1;
+    }
+    result
+}
+
+pub unsafe fn sendto(sockfd: c_int, buf: * const c_void, len: size_t, flags: c_int, addr: * const sockaddr, addrlen: socklen_t) -> ssize_t {
+    let mut result: ssize_t = 0;
+    let mut error: c_int = 0;
+    let status = u_net_sendto_ocall(&mut result as * mut ssize_t,
+                                    &mut error as * mut c_int,
+                                    sockfd,
+                                    buf,
+                                    len,
+                                    flags,
+                                    addr,
+                                    addrlen);
+
+    if status == sgx_status_t::SGX_SUCCESS {
+        if result == -1 {
+            set_errno(error);
+        } else if result < 0 || result as size_t > len {
+            set_errno(EIAGO);
+            result = -1;
+        }
+    } else {
+        set_errno(ESGX);
+        result = -1;
+    }
+    result
+}
+
+pub unsafe fn setsockopt(sockfd: c_int, level: c_int, optname: c_int, optval: * const c_void, optlen: socklen_t) -> c_int {
+    let mut result: c_int = 0;
+    let mut error: c_int = 0;
+    let status = u_net_setsockopt_ocall(&mut result as * mut c_int,
+                                        &mut error as * mut c_int,
+                                        sockfd,
+                                        level,
+                                        optname,
+                                        optval,
+                                        optlen);
+
+    if status == sgx_status_t::SGX_SUCCESS {
+        if result == -1 {
+            set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
+        }
+    } else {
+        set_errno(ESGX);
+        result = -1;
+    }
+    result
+}
+
+pub unsafe fn getsockopt(sockfd: c_int, level: c_int, optname: c_int, optval: * mut c_void, optlen: * mut socklen_t) -> c_int {
+    let mut result: c_int = 0;
+    let mut error: c_int = 0;
+    let len_in: socklen_t = if !optlen.is_null() { *optlen } else { 0 };
+    let mut len_out: socklen_t = 0 as socklen_t;
+    let status = u_net_getsockopt_ocall(&mut result as * mut c_int,
+                                        &mut error as * mut c_int,
+                                        sockfd,
+                                        level,
+                                        optname,
+                                        optval,
+                                        len_in,
+                                        &mut len_out as * mut socklen_t);
+
+    if status == sgx_status_t::SGX_SUCCESS {
+        if result == -1 {
+            set_errno(error);
+        } else if result != 0 || len_out > len_in {
+            set_errno(EIAGO);
+            result = -1;
+            len_out = 0;
+        }
+    } else {
+        set_errno(ESGX);
+        result = -1;
+    }
+
+    if !optlen.is_null() {
+        *optlen = len_out;
+    }
+    result
+}
+
+pub unsafe fn getpeername(sockfd: c_int, address: * mut sockaddr, addrlen: * mut socklen_t) -> c_int {
+    let mut result: c_int = 0;
+    let mut error: c_int = 0;
+    let len_in: socklen_t = if !addrlen.is_null() { *addrlen } else { 0 };
+    let mut len_out: socklen_t = 0 as socklen_t;
+    let status = u_net_getpeername_ocall(&mut result as * mut c_int,
+                                         &mut error as * mut c_int,
+                                         sockfd,
+                                         address,
+                                         len_in,
+                                         &mut len_out as * mut socklen_t);
+
+    if status == sgx_status_t::SGX_SUCCESS {
+        if result == -1 {
+            set_errno(error);
+        } else if result != 0 || !ocall::is_valid_addrlen(len_in, len_out) {
+            set_errno(EIAGO);
+            result = -1;
+            len_out = 0;
+        }
+    } else {
+        set_errno(ESGX);
+        result = -1;
+    }
+
+    if !addrlen.is_null() {
+        *addrlen = len_out;
+    }
+    result
+}
+
+pub unsafe fn getsockname(sockfd: c_int, address: * mut sockaddr, addrlen: * mut socklen_t) -> c_int {
+    let mut result: c_int = 0;
+    let mut error: c_int = 0;
+    let len_in: socklen_t = if !addrlen.is_null() { *addrlen } else { 0 };
+    let mut len_out: socklen_t = 0 as socklen_t;
+    let status = u_net_getsockname_ocall(&mut result as * mut c_int,
+                                         &mut error as * mut c_int,
+                                         sockfd,
+                                         address,
+                                         len_in,
+                                         &mut len_out as * mut socklen_t);
+
+    if status == sgx_status_t::SGX_SUCCESS {
+        if result == -1 {
+            set_errno(error);
+        } else if result != 0 || !ocall::is_valid_addrlen(len_in, len_out) {
+            set_errno(EIAGO);
+            result = -1;
+            len_out = 0;
+        }
+    } else {
+        set_errno(ESGX);
+        result = -1;
+    }
+
+    if !addrlen.is_null() {
+        *addrlen = len_out;
+    }
+    result
+}
+
+pub unsafe fn shutdown(sockfd: c_int, how: c_int) -> c_int {
+    let mut result: c_int = 0;
+    let mut error: c_int = 0;
+    let status = u_net_shutdown_ocall(&mut result as * mut c_int,
+                                      &mut error as * mut c_int,
+                                      sockfd,
+                                      how);
+
+    if status == sgx_status_t::SGX_SUCCESS {
+        if result == -1 {
+            set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
+        }
+    } else {
+        set_errno(ESGX);
+        result = -1;
+    }
+    result
+}
+
+pub unsafe fn ioctl_arg1(fd: c_int, request: c_int, arg: * const c_int) -> c_int {
+    let mut result: c_int = 0;
+    let mut error: c_int = 0;
+    let status = u_net_ioctl_ocall(&mut result as * mut c_int,
+                                   &mut error as * mut c_int,
+                                   fd,
+                                   request,
+                                   arg as * mut c_int);
+
+    if status == sgx_status_t::SGX_SUCCESS {
+        if result == -1 {
+            set_errno(error);
+        } else if result < 0 {
+            set_errno(EIAGO);
+            result = -1;
+        }
+    } else {
+        set_errno(ESGX);
+        result = -1;
+    }
+    result
+}
+
+pub unsafe fn poll(fds: * mut pollfd, nfds: nfds_t, timeout: c_int) -> c_int {
+    let mut result: c_int = 0;
+    let mut error: c_int = 0;
+    let status = u_net_poll_ocall(&mut result as * mut c_int,
+                                  &mut error as * mut c_int,
+                                  fds,
+                                  nfds,
+                                  timeout);
+
+    if status == sgx_status_t::SGX_SUCCESS {
+        if result == -1 {
+            set_errno(error);
+        } else if result < 0 || result as nfds_t > nfds {
+            set_errno(EIAGO);
+            result = -1;
+        }
+    } else {
+        set_errno(ESGX);
+        result = -1;
+    }
+    result
+}
+
+pub unsafe fn sendmsg(sockfd: c_int, msg: * const msghdr, flags: c_int) -> ssize_t {
+    ocall::sendmsg_with(u_net_sendmsg_ocall, sockfd, msg, flags)
+}
+
+pub unsafe fn recvmsg(sockfd: c_int, msg: * mut msghdr, flags: c_int) -> ssize_t {
+    ocall::recvmsg_with(u_net_recvmsg_ocall, sockfd, msg, flags)
+}
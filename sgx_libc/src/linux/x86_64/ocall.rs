@@ -445,6 +445,54 @@ extern "C" {
                               rmtp: *mut timespec) -> sgx_status_t;
 }
 
+// Whether `s` is a NUL-terminated string lying entirely outside the enclave.
+unsafe fn is_untrusted_str(s: * const c_char) -> bool {
+    if sgx_types::sgx_is_outside_enclave(s as * const c_void, 1) == 0 {
+        return false;
+    }
+    let len = strlen(s);
+    sgx_types::sgx_is_outside_enclave(s as * const c_void, len + 1) != 0
+}
+
+// Whether `arr` is a NULL-terminated array of untrusted strings lying
+// entirely outside the enclave.
+unsafe fn is_untrusted_str_array(arr: * const * const c_char) -> bool {
+    let mut p = arr;
+    loop {
+        if sgx_types::sgx_is_outside_enclave(p as * const c_void, mem::size_of::<* const c_char>()) == 0 {
+            return false;
+        }
+        if (*p).is_null() {
+            return true;
+        }
+        if !is_untrusted_str(*p) {
+            return false;
+        }
+        p = p.add(1);
+    }
+}
+
+// Whether `len_out` is a plausible address length for a buffer of `len_in`
+// bytes. Only `len_in` bytes of the address are copied back, but like the
+// kernel the host reports the full length when the address was truncated.
+// No socket address is larger than `sockaddr_storage`.
+pub(crate) fn is_valid_addrlen(len_in: socklen_t, len_out: socklen_t) -> bool {
+    len_out <= len_in || len_out as usize <= mem::size_of::<sockaddr_storage>()
+}
+
+// Resolves a `passwd` string field that the host returned as an offset
+// into `buf`, checking that it names a NUL-terminated string within it.
+unsafe fn passwd_field(field: * mut c_char, buf: * mut c_char, buflen: size_t) -> Option<* mut c_char> {
+    let offset = field as usize;
+    if offset == -1_isize as usize {
+        Some(ptr::null_mut())
+    } else if offset < buflen && !memchr(buf.add(offset) as * const u8, 0, buflen - offset).is_null() {
+        Some(buf.add(offset))
+    } else {
+        None
+    }
+}
+
 pub unsafe fn malloc(size: size_t) -> * mut c_void {
     let mut result: * mut c_void = ptr::null_mut();
     let mut error: c_int = 0;
@@ -455,6 +503,9 @@ pub unsafe fn malloc(size: size_t) -> * mut c_void {
     if status == sgx_status_t::SGX_SUCCESS {
         if result.is_null() {
             set_errno(error);
+        } else if sgx_types::sgx_is_outside_enclave(result, size) == 0 {
+            set_errno(EIAGO);
+            result = ptr::null_mut();
         }
     } else {
         set_errno(ESGX);
@@ -482,6 +533,9 @@ pub unsafe fn mmap(start: * mut c_void, length: size_t, prot: c_int, flags: c_in
     if status == sgx_status_t::SGX_SUCCESS {
         if result as isize == -1 {
             set_errno(error);
+        } else if sgx_types::sgx_is_outside_enclave(result, length) == 0 {
+            set_errno(EIAGO);
+            result = -1 as isize as * mut c_void;
         }
     } else {
         set_errno(ESGX);
@@ -501,6 +555,9 @@ pub unsafe fn munmap(start: * mut c_void, length: size_t) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -521,6 +578,9 @@ pub unsafe fn msync(addr: * mut c_void, length: size_t, flags: c_int) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -541,6 +601,9 @@ pub unsafe fn mprotect(addr: * mut c_void, length: size_t, prot: c_int) -> c_int
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -564,6 +627,9 @@ pub unsafe fn environ() -> * const * const c_char {
 
     if status != sgx_status_t::SGX_SUCCESS {
         result = ptr::null();
+    } else if !result.is_null() && !is_untrusted_str_array(result) {
+        set_errno(EIAGO);
+        result = ptr::null();
     }
     result
 }
@@ -574,6 +640,9 @@ pub unsafe fn getenv(name: * const c_char) -> * const c_char {
 
     if status != sgx_status_t::SGX_SUCCESS {
         result = ptr::null();
+    } else if !result.is_null() && !is_untrusted_str(result) {
+        set_errno(EIAGO);
+        result = ptr::null();
     }
     result
 }
@@ -590,6 +659,9 @@ pub unsafe fn setenv(name: * const c_char, value: * const c_char, overwrite: c_i
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -608,6 +680,9 @@ pub unsafe fn unsetenv(name: * const c_char) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -623,6 +698,9 @@ pub unsafe fn getcwd(buf: * mut c_char, size: size_t) -> * mut c_char {
     if status == sgx_status_t::SGX_SUCCESS {
         if result.is_null() {
             set_errno(error);
+        } else if memchr(buf as * const u8, 0, size).is_null() {
+            set_errno(EIAGO);
+            result = ptr::null_mut();
         } else {
             result = buf;
         }
@@ -640,6 +718,9 @@ pub unsafe fn chdir(dir: * const c_char) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -663,38 +744,23 @@ pub unsafe fn getpwuid_r(uid: uid_t,
     if status == sgx_status_t::SGX_SUCCESS && result == 0 {
         let pwd_ret = *passwd_result;
         if !pwd_ret.is_null() {
-            let mut temp_pwd = &mut *pwd;
-            let mut temp_buf = buf;
-            if temp_pwd.pw_name as usize != -1_isize as usize {
-                temp_pwd.pw_name = temp_buf.offset(temp_pwd.pw_name as isize);
-            } else {
-                temp_pwd.pw_name = ptr::null_mut();
-            }
-            temp_buf = buf;
-            if temp_pwd.pw_passwd as usize != -1_isize as usize {
-                temp_pwd.pw_passwd = temp_buf.offset(temp_pwd.pw_passwd as isize);
-            } else {
-                temp_pwd.pw_passwd = ptr::null_mut();
-            }
-            temp_buf = buf;
-            if temp_pwd.pw_gecos as usize != -1_isize as usize {
-                temp_pwd.pw_gecos = temp_buf.offset(temp_pwd.pw_gecos as isize);
-            } else {
-                temp_pwd.pw_gecos = ptr::null_mut();
-            }
-            temp_buf = buf;
-            if temp_pwd.pw_dir as usize != -1_isize as usize {
-                temp_pwd.pw_dir = temp_buf.offset(temp_pwd.pw_dir as isize);
+            let temp_pwd = &mut *pwd;
+            let fields = (passwd_field(temp_pwd.pw_name, buf, buflen),
+                          passwd_field(temp_pwd.pw_passwd, buf, buflen),
+                          passwd_field(temp_pwd.pw_gecos, buf, buflen),
+                          passwd_field(temp_pwd.pw_dir, buf, buflen),
+                          passwd_field(temp_pwd.pw_shell, buf, buflen));
+            if let (Some(name), Some(passwd), Some(gecos), Some(dir), Some(shell)) = fields {
+                temp_pwd.pw_name = name;
+                temp_pwd.pw_passwd = passwd;
+                temp_pwd.pw_gecos = gecos;
+                temp_pwd.pw_dir = dir;
+                temp_pwd.pw_shell = shell;
+                *passwd_result = pwd;
             } else {
-                temp_pwd.pw_dir = ptr::null_mut();
+                *passwd_result = ptr::null_mut();
+                result = EIAGO;
             }
-            temp_buf = buf;
-            if temp_pwd.pw_shell as usize != -1_isize as usize {
-                temp_pwd.pw_shell = temp_buf.offset(temp_pwd.pw_shell as isize);
-            } else {
-                temp_pwd.pw_shell = ptr::null_mut();
-            }
-            *passwd_result = pwd;
         }
     } else {
         *passwd_result = ptr::null_mut();
@@ -716,6 +782,9 @@ pub unsafe fn open(path: * const c_char, flags: c_int) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -736,6 +805,9 @@ pub unsafe fn open64(path: * const c_char, oflag: c_int, mode: c_int) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -755,6 +827,9 @@ pub unsafe fn fstat(fd: c_int, buf: * mut stat) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -774,6 +849,9 @@ pub unsafe fn fstat64(fd: c_int, buf: * mut stat64) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -793,6 +871,9 @@ pub unsafe fn stat(path: * const c_char, buf: * mut stat) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -812,6 +893,9 @@ pub unsafe fn stat64(path: * const c_char, buf: * mut stat64) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -831,6 +915,9 @@ pub unsafe fn lstat(path: * const c_char, buf: * mut stat) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -850,6 +937,9 @@ pub unsafe fn lstat64(path: * const c_char, buf: * mut stat64) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -870,6 +960,9 @@ pub unsafe fn lseek(fd: c_int, offset: off_t, whence: c_int) -> off_t {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -890,6 +983,9 @@ pub unsafe fn lseek64(fd: c_int, offset: off64_t, whence: c_int) -> off64_t {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -909,6 +1005,9 @@ pub unsafe fn ftruncate(fd: c_int, length: off_t) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -928,6 +1027,9 @@ pub unsafe fn ftruncate64(fd: c_int, length: off64_t) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -947,6 +1049,9 @@ pub unsafe fn truncate(path: * const c_char, length: off_t) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -966,6 +1071,9 @@ pub unsafe fn truncate64(path: * const c_char, length: off64_t) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -984,6 +1092,9 @@ pub unsafe fn fsync(fd: c_int) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1002,6 +1113,9 @@ pub unsafe fn fdatasync(fd: c_int) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1021,6 +1135,9 @@ pub unsafe fn fchmod(fd: c_int, mode: mode_t) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1039,6 +1156,9 @@ pub unsafe fn unlink(pathname: * const c_char) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1058,6 +1178,9 @@ pub unsafe fn link(oldpath: * const c_char, newpath: * const c_char) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1077,6 +1200,9 @@ pub unsafe fn rename(oldpath: * const c_char, newpath: * const c_char) -> c_int
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1096,6 +1222,9 @@ pub unsafe fn chmod(path: * const c_char, mode: mode_t) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1116,6 +1245,9 @@ pub unsafe fn readlink(path: * const c_char, buf: * mut c_char, bufsz: size_t) -
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 || result as size_t > bufsz {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1135,6 +1267,9 @@ pub unsafe fn symlink(path1: * const c_char, path2: * const c_char) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1153,6 +1288,9 @@ pub unsafe fn realpath(pathname: * const c_char) -> * mut c_char {
     if status == sgx_status_t::SGX_SUCCESS {
         if result.is_null() {
             set_errno(error);
+        } else if !is_untrusted_str(result) {
+            set_errno(EIAGO);
+            result = ptr::null_mut();
         }
     } else {
         set_errno(ESGX);
@@ -1172,6 +1310,9 @@ pub unsafe fn mkdir(pathname: * const c_char, mode: mode_t) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1190,6 +1331,9 @@ pub unsafe fn rmdir(pathname: * const c_char) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1208,6 +1352,9 @@ pub unsafe fn opendir(pathname: * const c_char) -> * mut DIR {
     if status == sgx_status_t::SGX_SUCCESS {
         if result.is_null() {
             set_errno(error);
+        } else if sgx_types::sgx_is_outside_enclave(result as * const c_void, 1) == 0 {
+            set_errno(EIAGO);
+            result = ptr::null_mut();
         }
     } else {
         set_errno(ESGX);
@@ -1227,7 +1374,13 @@ pub unsafe fn readdir64_r(dirp: * mut DIR,
     if status == sgx_status_t::SGX_SUCCESS && result == 0 {
         let dir_ret = *dirresult;
         if !dir_ret.is_null() {
-            *dirresult = entry;
+            let name = &(*entry).d_name;
+            if memchr(name.as_ptr() as * const u8, 0, name.len()).is_null() {
+                *dirresult = ptr::null_mut();
+                result = EIAGO;
+            } else {
+                *dirresult = entry;
+            }
         }
     } else {
         *dirresult = ptr::null_mut();
@@ -1248,6 +1401,9 @@ pub unsafe fn closedir(dirp: * mut DIR) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1266,6 +1422,9 @@ pub unsafe fn dirfd(dirp: * mut DIR) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1290,6 +1449,9 @@ pub unsafe fn fstatat64(dirfd: c_int,
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1310,6 +1472,9 @@ pub unsafe fn read(fd: c_int, buf: * mut c_void, count: size_t) -> ssize_t {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 || result as size_t > count {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1331,6 +1496,9 @@ pub unsafe fn pread64(fd: c_int, buf: * mut c_void, count: size_t, offset: off64
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 || result as size_t > count {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1387,6 +1555,9 @@ pub unsafe fn readv(fd: c_int, iov: * const iovec, iovcnt: c_int) -> ssize_t {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 || result as usize > iosize {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1452,6 +1623,9 @@ pub unsafe fn preadv64(fd: c_int, iov: * const iovec, iovcnt: c_int, offset: off
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 || result as usize > iosize {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1480,6 +1654,9 @@ pub unsafe fn write(fd: c_int, buf: * const c_void, count: size_t) -> ssize_t {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 || result as size_t > count {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1501,6 +1678,9 @@ pub unsafe fn pwrite64(fd: c_int, buf: * const c_void, count: size_t, offset: of
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 || result as size_t > count {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1558,6 +1738,9 @@ pub unsafe fn writev(fd: c_int, iov: * const iovec, iovcnt: c_int) -> ssize_t {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 || result as usize > iosize {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1618,6 +1801,9 @@ pub unsafe fn pwritev64(fd: c_int, iov: * const iovec, iovcnt: c_int, offset: of
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 || result as usize > iosize {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1639,6 +1825,9 @@ pub unsafe fn fcntl_arg0(fd: c_int, cmd: c_int) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1659,6 +1848,9 @@ pub unsafe fn fcntl_arg1(fd: c_int, cmd: c_int, arg: c_int) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1678,6 +1870,9 @@ pub unsafe fn ioctl_arg0(fd: c_int, request: c_int) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1697,6 +1892,9 @@ pub unsafe fn ioctl_arg1(fd: c_int, request: c_int, arg: * const c_int) -> c_int
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1715,6 +1913,9 @@ pub unsafe fn close(fd: c_int) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1734,6 +1935,9 @@ pub unsafe fn clock_gettime(clk_id: clockid_t, tp: * mut timespec) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 || (!tp.is_null() && ((*tp).tv_nsec < 0 || (*tp).tv_nsec >= 1_000_000_000)) {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1755,6 +1959,9 @@ pub unsafe fn socket(domain: c_int, ty: c_int, protocol: c_int) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1776,6 +1983,9 @@ pub unsafe fn socketpair(domain: c_int, ty: c_int, protocol: c_int, sv: *mut c_i
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 || (!sv.is_null() && (*sv < 0 || *sv.add(1) < 0)) {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1796,6 +2006,9 @@ pub unsafe fn bind(sockfd: c_int, address: * const sockaddr, addrlen: socklen_t)
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1815,6 +2028,9 @@ pub unsafe fn listen(sockfd: c_int, backlog: c_int) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1838,6 +2054,10 @@ pub unsafe fn accept(sockfd: c_int, addr: *mut sockaddr, addrlen: *mut socklen_t
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 || !is_valid_addrlen(len_in, len_out) {
+            set_errno(EIAGO);
+            result = -1;
+            len_out = 0;
         }
     } else {
         set_errno(ESGX);
@@ -1866,6 +2086,10 @@ pub unsafe fn accept4(sockfd: c_int, addr: *mut sockaddr, addrlen: *mut socklen_
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 || !is_valid_addrlen(len_in, len_out) {
+            set_errno(EIAGO);
+            result = -1;
+            len_out = 0;
         }
     } else {
         set_errno(ESGX);
@@ -1890,6 +2114,9 @@ pub unsafe fn connect(sockfd: c_int, address: * const sockaddr, addrlen: socklen
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1911,6 +2138,9 @@ pub unsafe fn send(sockfd: c_int, buf: * const c_void, len: size_t, flags: c_int
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 || result as size_t > len {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1939,6 +2169,9 @@ pub unsafe fn sendto(sockfd: c_int,
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 || result as size_t > len {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -1948,9 +2181,18 @@ pub unsafe fn sendto(sockfd: c_int,
 }
 
 pub unsafe fn sendmsg(sockfd: c_int, msg: * const msghdr, flags: c_int) -> ssize_t {
+    sendmsg_with(u_sendmsg_ocall, sockfd, msg, flags)
+}
+
+pub(crate) type SendmsgOcall = unsafe extern "C" fn(* mut ssize_t, * mut c_int, c_int, * const msghdr, c_int) -> sgx_status_t;
+pub(crate) type RecvmsgOcall = unsafe extern "C" fn(* mut ssize_t, * mut c_int, c_int, * mut msghdr, c_int) -> sgx_status_t;
+
+// Marshals `msg` into untrusted memory and sends it through `ocall`.
+pub(crate) unsafe fn sendmsg_with(ocall: SendmsgOcall, sockfd: c_int, msg: * const msghdr, flags: c_int) -> ssize_t {
     let mut result: ssize_t = 0;
     let mut error: c_int = 0;
     let mut hdrsize: usize = 0;
+    let mut iolen: usize = 0;
     let mut ptr: * mut u8 = ptr::null_mut();
 
     if msg.is_null() || sgx_types::sgx_is_within_enclave(msg as * const c_void, mem::size_of::<msghdr>()) == 0 {
@@ -1973,6 +2215,7 @@ pub unsafe fn sendmsg(sockfd: c_int, msg: * const msghdr, flags: c_int) -> ssize
             if !io.iov_base.is_null() && io.iov_len > 0 &&
                 sgx_types::sgx_is_within_enclave(io.iov_base, io.iov_len as usize) != 0 {
                 hdrsize += io.iov_len as usize;
+                iolen += io.iov_len as usize;
             } else {
                 set_errno(EINVAL);
                 return -1;
@@ -2003,6 +2246,8 @@ pub unsafe fn sendmsg(sockfd: c_int, msg: * const msghdr, flags: c_int) -> ssize
 
     if !mhdr.msg_iov.is_null() && mhdr.msg_iovlen > 0 {
         let tmpiov = slice::from_raw_parts_mut(ptr as * mut iovec, mhdr.msg_iovlen as usize);
+        tmpmsg.msg_iov = tmpiov.as_mut_ptr();
+        tmpmsg.msg_iovlen = mhdr.msg_iovlen;
         ptr = ptr.add(mhdr.msg_iovlen as usize * mem::size_of::<iovec>());
 
         let v = slice::from_raw_parts(mhdr.msg_iov, mhdr.msg_iovlen as usize);
@@ -2020,15 +2265,18 @@ pub unsafe fn sendmsg(sockfd: c_int, msg: * const msghdr, flags: c_int) -> ssize
         ptr::copy_nonoverlapping(mhdr.msg_control as * mut u8, tmpmsg.msg_control as * mut u8, mhdr.msg_controllen as usize);
     }
 
-    let status = u_sendmsg_ocall(&mut result as * mut ssize_t,
-                                 &mut error as * mut c_int,
-                                 sockfd,
-                                 &tmpmsg as * const msghdr,
-                                 flags);
+    let status = ocall(&mut result as * mut ssize_t,
+                       &mut error as * mut c_int,
+                       sockfd,
+                       &tmpmsg as * const msghdr,
+                       flags);
 
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 || result as usize > iolen {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -2052,6 +2300,9 @@ pub unsafe fn recv(sockfd: c_int, buf: * mut c_void, len: size_t, flags: c_int)
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 || result as size_t > len {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -2083,6 +2334,10 @@ pub unsafe fn recvfrom(sockfd: c_int,
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 || result as size_t > len || !is_valid_addrlen(len_in, len_out) {
+            set_errno(EIAGO);
+            result = -1;
+            len_out = 0;
         }
     } else {
         set_errno(ESGX);
@@ -2096,9 +2351,15 @@ pub unsafe fn recvfrom(sockfd: c_int,
 }
 
 pub unsafe fn recvmsg(sockfd: c_int, msg: * mut msghdr, flags: c_int) -> ssize_t {
+    recvmsg_with(u_recvmsg_ocall, sockfd, msg, flags)
+}
+
+// Receives through `ocall` into untrusted memory and copies the result into `msg`.
+pub(crate) unsafe fn recvmsg_with(ocall: RecvmsgOcall, sockfd: c_int, msg: * mut msghdr, flags: c_int) -> ssize_t {
     let mut result: ssize_t = 0;
     let mut error: c_int = 0;
     let mut hdrsize: usize = 0;
+    let mut iolen: usize = 0;
     let mut ptr: * mut u8 = ptr::null_mut();
 
     if msg.is_null() || sgx_types::sgx_is_within_enclave(msg as * const c_void, mem::size_of::<msghdr>()) == 0 {
@@ -2121,6 +2382,7 @@ pub unsafe fn recvmsg(sockfd: c_int, msg: * mut msghdr, flags: c_int) -> ssize_t
             if !io.iov_base.is_null() && io.iov_len > 0 &&
                 sgx_types::sgx_is_within_enclave(io.iov_base, io.iov_len as usize) != 0 {
                 hdrsize += io.iov_len as usize;
+                iolen += io.iov_len as usize;
             } else {
                 set_errno(EINVAL);
                 return -1;
@@ -2151,6 +2413,8 @@ pub unsafe fn recvmsg(sockfd: c_int, msg: * mut msghdr, flags: c_int) -> ssize_t
 
     if !mhdr.msg_iov.is_null() && mhdr.msg_iovlen > 0 {
         let tmpiov = slice::from_raw_parts_mut(ptr as * mut iovec, mhdr.msg_iovlen as usize);
+        tmpmsg.msg_iov = tmpiov.as_mut_ptr();
+        tmpmsg.msg_iovlen = mhdr.msg_iovlen;
         ptr = ptr.add(mhdr.msg_iovlen as usize * mem::size_of::<iovec>());
 
         let v = slice::from_raw_parts(mhdr.msg_iov, mhdr.msg_iovlen as usize);
@@ -2167,39 +2431,47 @@ pub unsafe fn recvmsg(sockfd: c_int, msg: * mut msghdr, flags: c_int) -> ssize_t
         ptr::copy_nonoverlapping(mhdr.msg_control as * mut u8, tmpmsg.msg_control as * mut u8, mhdr.msg_controllen as usize);
     }
 
-    let status = u_recvmsg_ocall(&mut result as * mut ssize_t,
-                                 &mut error as * mut c_int,
-                                 sockfd,
-                                 &mut tmpmsg as * mut msghdr,
-                                 flags);
+    let status = ocall(&mut result as * mut ssize_t,
+                       &mut error as * mut c_int,
+                       sockfd,
+                       &mut tmpmsg as * mut msghdr,
+                       flags);
 
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 || result as usize > iolen ||
+                  tmpmsg.msg_namelen > mhdr.msg_namelen ||
+                  tmpmsg.msg_controllen > mhdr.msg_controllen {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
         result = -1;
     }
 
-    ptr = hdrbase;
-    if !mhdr.msg_name.is_null() && mhdr.msg_namelen > 0 {
-        ptr = ptr.add(mhdr.msg_namelen as usize);
-    }
+    // The host may have rewritten the pointers in the untrusted copy of the
+    // header, so the data is located from `hdrbase` instead.
+    if result != -1 {
+        ptr = hdrbase;
+        if !mhdr.msg_name.is_null() && mhdr.msg_namelen > 0 {
+            ptr = ptr.add(mhdr.msg_namelen as usize);
+        }
 
-    if !mhdr.msg_iov.is_null() && mhdr.msg_iovlen > 0 {
-        let tmpiov = slice::from_raw_parts_mut(ptr as * mut iovec, mhdr.msg_iovlen as usize);
-        ptr = ptr.add(mhdr.msg_iovlen as usize * mem::size_of::<iovec>());
+        if !mhdr.msg_iov.is_null() && mhdr.msg_iovlen > 0 {
+            ptr = ptr.add(mhdr.msg_iovlen as usize * mem::size_of::<iovec>());
 
-        let v = slice::from_raw_parts(mhdr.msg_iov, mhdr.msg_iovlen as usize);
-        for i in 0..v.len() {
-            ptr::copy_nonoverlapping(tmpiov[i].iov_base as * mut u8, v[i].iov_base as * mut u8, v[i].iov_len as usize);
-            ptr = ptr.add(v[i].iov_len as usize);
+            let v = slice::from_raw_parts(mhdr.msg_iov, mhdr.msg_iovlen as usize);
+            for io in v {
+                ptr::copy_nonoverlapping(ptr, io.iov_base as * mut u8, io.iov_len as usize);
+                ptr = ptr.add(io.iov_len as usize);
+            }
         }
-    }
 
-    if !mhdr.msg_control.is_null() &&  mhdr.msg_controllen > 0 {
-        ptr::copy_nonoverlapping(tmpmsg.msg_control as * mut u8, mhdr.msg_control as * mut u8, mhdr.msg_controllen as usize);
+        if !mhdr.msg_control.is_null() &&  mhdr.msg_controllen > 0 {
+            ptr::copy_nonoverlapping(ptr, mhdr.msg_control as * mut u8, mhdr.msg_controllen as usize);
+        }
     }
 
     free(hdrbase as * mut c_void);
@@ -2224,6 +2496,9 @@ pub unsafe fn setsockopt(sockfd: c_int,
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -2254,6 +2529,10 @@ pub unsafe fn getsockopt(sockfd: c_int,
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 || len_out > len_in {
+            set_errno(EIAGO);
+            result = -1;
+            len_out = 0;
         }
     } else {
         set_errno(ESGX);
@@ -2281,6 +2560,10 @@ pub unsafe fn getpeername(sockfd: c_int, address: * mut sockaddr, addrlen: * mut
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 || !is_valid_addrlen(len_in, len_out) {
+            set_errno(EIAGO);
+            result = -1;
+            len_out = 0;
         }
     } else {
         set_errno(ESGX);
@@ -2308,6 +2591,10 @@ pub unsafe fn getsockname(sockfd: c_int, address: * mut sockaddr, addrlen: * mut
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 || !is_valid_addrlen(len_in, len_out) {
+            set_errno(EIAGO);
+            result = -1;
+            len_out = 0;
         }
     } else {
         set_errno(ESGX);
@@ -2331,6 +2618,9 @@ pub unsafe fn shutdown(sockfd: c_int, how: c_int) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -2365,8 +2655,20 @@ pub unsafe fn getaddrinfo(node: * const c_char, service: * const c_char, hints:
             *res = ptr::null_mut();
             let mut cur_ptr: * mut addrinfo = ret_res;
             let mut addrinfo_vec: Vec<Box<addrinfo>> = Vec::new();
+            let mut iago = false;
             while cur_ptr != ptr::null_mut() {
-                let cur: &addrinfo = &*cur_ptr;
+                if sgx_types::sgx_is_outside_enclave(cur_ptr as * const c_void, mem::size_of::<addrinfo>()) == 0 {
+                    iago = true;
+                    break;
+                }
+                let cur: addrinfo = ptr::read(cur_ptr);
+                if (!cur.ai_addr.is_null() &&
+                    (cur.ai_addrlen as usize > mem::size_of::<sockaddr_storage>() ||
+                     sgx_types::sgx_is_outside_enclave(cur.ai_addr as * const c_void, cur.ai_addrlen as usize) == 0)) ||
+                   (!cur.ai_canonname.is_null() && !is_untrusted_str(cur.ai_canonname)) {
+                    iago = true;
+                    break;
+                }
                 let mut info = addrinfo {
                     ai_flags: cur.ai_flags,
                     ai_family: cur.ai_family,
@@ -2378,26 +2680,31 @@ pub unsafe fn getaddrinfo(node: * const c_char, service: * const c_char, hints:
                     ai_next: ptr::null_mut(),
                 };
 
-                if !cur.ai_addr.is_null() && cur.ai_addrlen > 0 {
-                    let mut addr_vec = vec![0u8; cur.ai_addrlen as usize];
-                    let addr_slice: &[u8] = slice::from_raw_parts(cur.ai_addr as * const u8, cur.ai_addrlen as usize);
-                    addr_vec.copy_from_slice(addr_slice);
-                    addr_vec.shrink_to_fit();
-                    info.ai_addrlen = cur.ai_addrlen;
-                    info.ai_addr = addr_vec.as_mut_ptr() as * mut sockaddr;
-                    mem::forget(addr_vec);
-                }
-
                 if !cur.ai_canonname.is_null() {
                     let len: usize = strlen(cur.ai_canonname) + 1;
                     let mut name_vec = vec![0u8; len];
                     let name_slice: &[u8] = slice::from_raw_parts(cur.ai_canonname as * const u8, len);
                     name_vec.copy_from_slice(name_slice);
+                    name_vec[len - 1] = 0;
+                    if !memchr(name_vec.as_ptr(), 0, len - 1).is_null() {
+                        iago = true;
+                        break;
+                    }
                     name_vec.shrink_to_fit();
                     info.ai_canonname = name_vec.as_mut_ptr() as * mut c_char;
                     mem::forget(name_vec);
                 }
 
+                if !cur.ai_addr.is_null() && cur.ai_addrlen > 0 {
+                    let mut addr_vec = vec![0u8; cur.ai_addrlen as usize];
+                    let addr_slice: &[u8] = slice::from_raw_parts(cur.ai_addr as * const u8, cur.ai_addrlen as usize);
+                    addr_vec.copy_from_slice(addr_slice);
+                    addr_vec.shrink_to_fit();
+                    info.ai_addrlen = cur.ai_addrlen;
+                    info.ai_addr = addr_vec.as_mut_ptr() as * mut sockaddr;
+                    mem::forget(addr_vec);
+                }
+
                 addrinfo_vec.push(Box::new(info));
                 cur_ptr = cur.ai_next;
             }
@@ -2414,6 +2721,13 @@ pub unsafe fn getaddrinfo(node: * const c_char, service: * const c_char, hints:
             }
             let _ = u_freeaddrinfo_ocall(ret_res);
 
+            if iago {
+                freeaddrinfo(*res);
+                *res = ptr::null_mut();
+                set_errno(EIAGO);
+                result = EAI_SYSTEM;
+            }
+
         } else if result == EAI_SYSTEM {
             set_errno(error);
         }
@@ -2449,6 +2763,10 @@ pub unsafe fn gai_strerror(errcode: c_int) -> * const c_char {
     let status = u_gai_strerror_ocall(&mut result as * mut * const c_char, errcode);
     if status != sgx_status_t::SGX_SUCCESS {
         set_errno(ESGX);
+        result = ptr::null();
+    } else if !result.is_null() && !is_untrusted_str(result) {
+        set_errno(EIAGO);
+        result = ptr::null();
     }
     result
 }
@@ -2465,6 +2783,9 @@ pub unsafe fn poll(fds: * mut pollfd, nfds: nfds_t, timeout: c_int) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 || result as nfds_t > nfds {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -2482,6 +2803,9 @@ pub unsafe fn epoll_create1(flags: c_int) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -2502,6 +2826,9 @@ pub unsafe fn epoll_ctl(epfd: c_int, op: c_int, fd: c_int, event: * mut epoll_ev
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -2513,6 +2840,10 @@ pub unsafe fn epoll_ctl(epfd: c_int, op: c_int, fd: c_int, event: * mut epoll_ev
 pub unsafe fn epoll_wait(epfd: c_int, events: * mut epoll_event, maxevents: c_int, timeout: c_int) -> c_int {
     let mut result: c_int = 0;
     let mut error: c_int = 0;
+    if maxevents <= 0 {
+        set_errno(EINVAL);
+        return -1;
+    }
     let status = u_epoll_wait_ocall(&mut result as * mut c_int,
                                     &mut error as * mut c_int,
                                     epfd,
@@ -2522,6 +2853,9 @@ pub unsafe fn epoll_wait(epfd: c_int, events: * mut epoll_event, maxevents: c_in
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 || result > maxevents {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -2539,6 +2873,9 @@ pub unsafe fn sysconf(name: c_int) -> c_long {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -2564,6 +2901,9 @@ pub unsafe fn prctl(option: c_int,
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result < 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -2582,6 +2922,9 @@ pub unsafe fn pipe(fds: * mut c_int) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 || (!fds.is_null() && (*fds < 0 || *fds.add(1) < 0)) {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -2600,6 +2943,9 @@ pub unsafe fn pipe2(fds: * mut c_int, flags: c_int) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 || (!fds.is_null() && (*fds < 0 || *fds.add(1) < 0)) {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -2654,6 +3000,9 @@ pub unsafe fn sched_yield() -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
@@ -2672,10 +3021,784 @@ pub unsafe fn nanosleep(rqtp: * const timespec, rmtp: * mut timespec) -> c_int {
     if status == sgx_status_t::SGX_SUCCESS {
         if result == -1 {
             set_errno(error);
+        } else if result != 0 {
+            set_errno(EIAGO);
+            result = -1;
         }
     } else {
         set_errno(ESGX);
         result = -1;
     }
     result
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::vec::Vec;
+
+    // The mocks below play a malicious host: every OCALL reports success and
+    // hands back whatever the running test configured, regardless of what it
+    // was asked for. Memory registered through `untrusted` is treated as
+    // outside the enclave; everything else counts as enclave memory.
+    std::thread_local! {
+        static RESULT: Cell<i64> = Cell::new(0);
+        static PTR: Cell<usize> = Cell::new(0);
+        static LEN_OUT: Cell<socklen_t> = Cell::new(0);
+        static FDS: Cell<(c_int, c_int)> = Cell::new((3, 4));
+        static NSEC: Cell<c_long> = Cell::new(0);
+        static FILL: Cell<u8> = Cell::new(0);
+        static REDIRECT: Cell<usize> = Cell::new(0);
+        static ARENA: Cell<(usize, usize)> = Cell::new((0, 0));
+    }
+
+    const HOST_ERRNO: c_int = EIO;
+
+    fn untrusted(size: usize) -> Vec<u64> {
+        let v = vec![0_u64; size / 8];
+        ARENA.with(|a| a.set((v.as_ptr() as usize, size)));
+        PTR.with(|p| p.set(v.as_ptr() as usize));
+        v
+    }
+
+    fn in_arena(addr: usize, size: usize) -> bool {
+        let (base, len) = ARENA.with(Cell::get);
+        addr >= base && addr.checked_add(size).map_or(false, |end| end <= base + len)
+    }
+
+    fn host(result: i64) {
+        RESULT.with(|r| r.set(result));
+    }
+
+    fn host_ptr(p: usize) {
+        PTR.with(|c| c.set(p));
+    }
+
+    fn assert_iago(ret: i64) {
+        assert_eq!(ret, -1);
+        assert_eq!(errno(), EIAGO);
+    }
+
+    #[no_mangle]
+    pub extern "C" fn sgx_is_within_enclave(addr: * const c_void, size: size_t) -> i32 {
+        let (base, len) = ARENA.with(Cell::get);
+        let addr = addr as usize;
+        (addr + size <= base || addr >= base + len) as i32
+    }
+
+    #[no_mangle]
+    pub extern "C" fn sgx_is_outside_enclave(addr: * const c_void, size: size_t) -> i32 {
+        in_arena(addr as usize, size) as i32
+    }
+
+    macro_rules! mock {
+        ($($name:ident($($ty:ty),*) -> $res:ty;)*) => {$(
+            #[no_mangle]
+            pub extern "C" fn $name(result: * mut $res, error: * mut c_int, $(_: $ty),*) -> sgx_status_t {
+                unsafe {
+                    *result = RESULT.with(Cell::get) as $res;
+                    *error = HOST_ERRNO;
+                }
+                sgx_status_t::SGX_SUCCESS
+            }
+        )*}
+    }
+
+    mock! {
+        u_munmap_ocall(* mut c_void, size_t) -> c_int;
+        u_msync_ocall(* mut c_void, size_t, c_int) -> c_int;
+        u_mprotect_ocall(* mut c_void, size_t, c_int) -> c_int;
+        u_setenv_ocall(* const c_char, * const c_char, c_int) -> c_int;
+        u_unsetenv_ocall(* const c_char) -> c_int;
+        u_chdir_ocall(* const c_char) -> c_int;
+        u_open_ocall(* const c_char, c_int) -> c_int;
+        u_open64_ocall(* const c_char, c_int, c_int) -> c_int;
+        u_fstat_ocall(c_int, * mut stat) -> c_int;
+        u_fstat64_ocall(c_int, * mut stat64) -> c_int;
+        u_stat_ocall(* const c_char, * mut stat) -> c_int;
+        u_stat64_ocall(* const c_char, * mut stat64) -> c_int;
+        u_lstat_ocall(* const c_char, * mut stat) -> c_int;
+        u_lstat64_ocall(* const c_char, * mut stat64) -> c_int;
+        u_lseek_ocall(c_int, off_t, c_int) -> off_t;
+        u_lseek64_ocall(c_int, off64_t, c_int) -> off64_t;
+        u_ftruncate_ocall(c_int, off_t) -> c_int;
+        u_ftruncate64_ocall(c_int, off64_t) -> c_int;
+        u_truncate_ocall(* const c_char, off_t) -> c_int;
+        u_truncate64_ocall(* const c_char, off64_t) -> c_int;
+        u_fsync_ocall(c_int) -> c_int;
+        u_fdatasync_ocall(c_int) -> c_int;
+        u_fchmod_ocall(c_int, mode_t) -> c_int;
+        u_unlink_ocall(* const c_char) -> c_int;
+        u_link_ocall(* const c_char, * const c_char) -> c_int;
+        u_rename_ocall(* const c_char, * const c_char) -> c_int;
+        u_chmod_ocall(* const c_char, mode_t) -> c_int;
+        u_readlink_ocall(* const c_char, * mut c_char, size_t) -> ssize_t;
+        u_symlink_ocall(* const c_char, * const c_char) -> c_int;
+        u_mkdir_ocall(* const c_char, mode_t) -> c_int;
+        u_rmdir_ocall(* const c_char) -> c_int;
+        u_closedir_ocall(* mut DIR) -> c_int;
+        u_dirfd_ocall(* mut DIR) -> c_int;
+        u_fstatat64_ocall(c_int, * const c_char, * mut stat64, c_int) -> c_int;
+        u_read_ocall(c_int, * mut c_void, size_t) -> ssize_t;
+        u_pread64_ocall(c_int, * mut c_void, size_t, off64_t) -> ssize_t;
+        u_readv_ocall(c_int, * const iovec, c_int) -> ssize_t;
+        u_preadv64_ocall(c_int, * const iovec, c_int, off64_t) -> ssize_t;
+        u_write_ocall(c_int, * const c_void, size_t) -> ssize_t;
+        u_pwrite64_ocall(c_int, * const c_void, size_t, off64_t) -> ssize_t;
+        u_writev_ocall(c_int, * const iovec, c_int) -> ssize_t;
+        u_pwritev64_ocall(c_int, * const iovec, c_int, off64_t) -> ssize_t;
+        u_fcntl_arg0_ocall(c_int, c_int) -> c_int;
+        u_fcntl_arg1_ocall(c_int, c_int, c_int) -> c_int;
+        u_ioctl_arg0_ocall(c_int, c_int) -> c_int;
+        u_ioctl_arg1_ocall(c_int, c_int, * const c_int) -> c_int;
+        u_close_ocall(c_int) -> c_int;
+        u_socket_ocall(c_int, c_int, c_int) -> c_int;
+        u_bind_ocall(c_int, * const sockaddr, socklen_t) -> c_int;
+        u_listen_ocall(c_int, c_int) -> c_int;
+        u_connect_ocall(c_int, * const sockaddr, socklen_t) -> c_int;
+        u_send_ocall(c_int, * const c_void, size_t, c_int) -> ssize_t;
+        u_sendto_ocall(c_int, * const c_void, size_t, c_int, * const sockaddr, socklen_t) -> ssize_t;
+        u_sendmsg_ocall(c_int, * const msghdr, c_int) -> ssize_t;
+        u_recv_ocall(c_int, * mut c_void, size_t, c_int) -> ssize_t;
+        u_setsockopt_ocall(c_int, c_int, c_int, * const c_void, socklen_t) -> c_int;
+        u_shutdown_ocall(c_int, c_int) -> c_int;
+        u_poll_ocall(* mut pollfd, nfds_t, c_int) -> c_int;
+        u_epoll_create1_ocall(c_int) -> c_int;
+        u_epoll_ctl_ocall(c_int, c_int, c_int, * mut epoll_event) -> c_int;
+        u_epoll_wait_ocall(c_int, * mut epoll_event, c_int, c_int) -> c_int;
+        u_sysconf_ocall(c_int) -> c_long;
+        u_prctl_ocall(c_int, c_ulong, c_ulong, c_ulong, c_ulong) -> c_int;
+        u_sched_yield_ocall() -> c_int;
+        u_nanosleep_ocall(* const timespec, * mut timespec) -> c_int;
+    }
+
+    macro_rules! mock_len_out {
+        ($($name:ident($($ty:ty),*) -> $res:ty;)*) => {$(
+            #[no_mangle]
+            pub extern "C" fn $name(result: * mut $res,
+                                    error: * mut c_int,
+                                    $(_: $ty,)*
+                                    _len_in: socklen_t,
+                                    len_out: * mut socklen_t) -> sgx_status_t {
+                unsafe {
+                    *result = RESULT.with(Cell::get) as $res;
+                    *error = HOST_ERRNO;
+                    *len_out = LEN_OUT.with(Cell::get);
+                }
+                sgx_status_t::SGX_SUCCESS
+            }
+        )*}
+    }
+
+    mock_len_out! {
+        u_accept_ocall(c_int, * mut sockaddr) -> c_int;
+        u_recvfrom_ocall(c_int, * mut c_void, size_t, c_int, * mut sockaddr) -> ssize_t;
+        u_getsockopt_ocall(c_int, c_int, c_int, * mut c_void) -> c_int;
+        u_getpeername_ocall(c_int, * mut sockaddr) -> c_int;
+        u_getsockname_ocall(c_int, * mut sockaddr) -> c_int;
+    }
+
+    #[no_mangle]
+    pub extern "C" fn u_accept4_ocall(result: * mut c_int,
+                                      error: * mut c_int,
+                                      _sockfd: c_int,
+                                      _addr: * mut sockaddr,
+                                      _len_in: socklen_t,
+                                      len_out: * mut socklen_t,
+                                      _flags: c_int) -> sgx_status_t {
+        unsafe {
+            *result = RESULT.with(Cell::get) as c_int;
+            *error = HOST_ERRNO;
+            *len_out = LEN_OUT.with(Cell::get);
+        }
+        sgx_status_t::SGX_SUCCESS
+    }
+
+    macro_rules! mock_fd_pair {
+        ($($name:ident($($ty:ty),*);)*) => {$(
+            #[no_mangle]
+            pub extern "C" fn $name(result: * mut c_int, error: * mut c_int, $(_: $ty,)* fds: * mut c_int) -> sgx_status_t {
+                unsafe {
+                    *result = RESULT.with(Cell::get) as c_int;
+                    *error = HOST_ERRNO;
+                    let (a, b) = FDS.with(Cell::get);
+                    *fds = a;
+                    *fds.add(1) = b;
+                }
+                sgx_status_t::SGX_SUCCESS
+            }
+        )*}
+    }
+
+    mock_fd_pair! {
+        u_socketpair_ocall(c_int, c_int, c_int);
+        u_pipe_ocall();
+    }
+
+    #[no_mangle]
+    pub extern "C" fn u_pipe2_ocall(result: * mut c_int, error: * mut c_int, fds: * mut c_int, _flags: c_int) -> sgx_status_t {
+        u_pipe_ocall(result, error, fds)
+    }
+
+    macro_rules! mock_ptr {
+        ($($name:ident($($ty:ty),*) -> $res:ty;)*) => {$(
+            #[no_mangle]
+            pub extern "C" fn $name(result: * mut $res, error: * mut c_int, $(_: $ty),*) -> sgx_status_t {
+                unsafe {
+                    *result = PTR.with(Cell::get) as $res;
+                    *error = HOST_ERRNO;
+                }
+                sgx_status_t::SGX_SUCCESS
+            }
+        )*}
+    }
+
+    mock_ptr! {
+        u_malloc_ocall(size_t) -> * mut c_void;
+        u_mmap_ocall(* mut c_void, size_t, c_int, c_int, c_int, off_t) -> * mut c_void;
+        u_realpath_ocall(* const c_char) -> * mut c_char;
+        u_opendir_ocall(* const c_char) -> * mut DIR;
+    }
+
+    #[no_mangle]
+    pub extern "C" fn u_free_ocall(_p: * mut c_void) -> sgx_status_t {
+        sgx_status_t::SGX_SUCCESS
+    }
+
+    #[no_mangle]
+    pub extern "C" fn u_environ_ocall(result: * mut * const * const c_char) -> sgx_status_t {
+        unsafe { *result = PTR.with(Cell::get) as * const * const c_char; }
+        sgx_status_t::SGX_SUCCESS
+    }
+
+    #[no_mangle]
+    pub extern "C" fn u_getenv_ocall(result: * mut * const c_char, _name: * const c_char) -> sgx_status_t {
+        unsafe { *result = PTR.with(Cell::get) as * const c_char; }
+        sgx_status_t::SGX_SUCCESS
+    }
+
+    #[no_mangle]
+    pub extern "C" fn u_gai_strerror_ocall(result: * mut * const c_char, _errcode: c_int) -> sgx_status_t {
+        unsafe { *result = PTR.with(Cell::get) as * const c_char; }
+        sgx_status_t::SGX_SUCCESS
+    }
+
+    // Fills `len` bytes at `p` with the configured byte, or with a short
+    // NUL-terminated string when that byte is zero.
+    unsafe fn fill(p: * mut u8, len: usize) {
+        match FILL.with(Cell::get) {
+            0 => ptr::copy_nonoverlapping(b"/ok\0".as_ptr(), p, 4),
+            b => p.write_bytes(b, len),
+        }
+    }
+
+    #[no_mangle]
+    pub extern "C" fn u_getcwd_ocall(result: * mut * mut c_char,
+                                     error: * mut c_int,
+                                     buf: * mut c_char,
+                                     size: size_t) -> sgx_status_t {
+        unsafe {
+            fill(buf as * mut u8, size);
+            *result = buf;
+            *error = HOST_ERRNO;
+        }
+        sgx_status_t::SGX_SUCCESS
+    }
+
+    #[no_mangle]
+    pub extern "C" fn u_readdir64_r_ocall(result: * mut c_int,
+                                          _dirp: * mut DIR,
+                                          entry: * mut dirent64,
+                                          dirresult: * mut * mut dirent64) -> sgx_status_t {
+        unsafe {
+            let name = &mut (*entry).d_name;
+            fill(name.as_mut_ptr() as * mut u8, name.len());
+            *dirresult = PTR.with(Cell::get) as * mut dirent64;
+            *result = 0;
+        }
+        sgx_status_t::SGX_SUCCESS
+    }
+
+    #[no_mangle]
+    pub extern "C" fn u_getpwuid_r_ocall(result: * mut c_int,
+                                         _uid: uid_t,
+                                         pwd: * mut passwd,
+                                         buf: * mut c_char,
+                                         buflen: size_t,
+                                         passwd_result: * mut * mut passwd) -> sgx_status_t {
+        unsafe {
+            fill(buf as * mut u8, buflen);
+            let offset = PTR.with(Cell::get) as * mut c_char;
+            (*pwd).pw_name = offset;
+            (*pwd).pw_passwd = -1_isize as * mut c_char;
+            (*pwd).pw_gecos = offset;
+            (*pwd).pw_dir = offset;
+            (*pwd).pw_shell = offset;
+            *passwd_result = pwd;
+            *result = 0;
+        }
+        sgx_status_t::SGX_SUCCESS
+    }
+
+    #[no_mangle]
+    pub extern "C" fn u_clock_gettime_ocall(result: * mut c_int,
+                                            error: * mut c_int,
+                                            _clk_id: clockid_t,
+                                            tp: * mut timespec) -> sgx_status_t {
+        unsafe {
+            (*tp).tv_sec = 1;
+            (*tp).tv_nsec = NSEC.with(Cell::get);
+            *result = 0;
+            *error = HOST_ERRNO;
+        }
+        sgx_status_t::SGX_SUCCESS
+    }
+
+    #[no_mangle]
+    pub extern "C" fn u_recvmsg_ocall(result: * mut ssize_t,
+                                      error: * mut c_int,
+                                      _sockfd: c_int,
+                                      msg: * mut msghdr,
+                                      _flags: c_int) -> sgx_status_t {
+        unsafe {
+            let msg = &mut *msg;
+            let iov = &mut *msg.msg_iov;
+            (iov.iov_base as * mut u8).write_bytes(b'h', iov.iov_len);
+            let target = REDIRECT.with(Cell::get);
+            if target != 0 {
+                iov.iov_base = target as * mut c_void;
+                msg.msg_control = target as * mut c_void;
+            }
+            msg.msg_controllen = LEN_OUT.with(Cell::get);
+            *result = RESULT.with(Cell::get) as ssize_t;
+            *error = HOST_ERRNO;
+        }
+        sgx_status_t::SGX_SUCCESS
+    }
+
+    #[no_mangle]
+    pub extern "C" fn u_getaddrinfo_ocall(result: * mut c_int,
+                                          error: * mut c_int,
+                                          _node: * const c_char,
+                                          _service: * const c_char,
+                                          _hints: * const addrinfo,
+                                          res: * mut * mut addrinfo) -> sgx_status_t {
+        unsafe {
+            *res = PTR.with(Cell::get) as * mut addrinfo;
+            *result = 0;
+            *error = HOST_ERRNO;
+        }
+        sgx_status_t::SGX_SUCCESS
+    }
+
+    #[no_mangle]
+    pub extern "C" fn u_freeaddrinfo_ocall(_res: * mut addrinfo) -> sgx_status_t {
+        sgx_status_t::SGX_SUCCESS
+    }
+
+    #[test]
+    fn zero_result_wrappers() {
+        let calls: &[fn() -> i64] = &[
+            || unsafe { munmap(ptr::null_mut(), 0) as i64 },
+            || unsafe { msync(ptr::null_mut(), 0, 0) as i64 },
+            || unsafe { mprotect(ptr::null_mut(), 0, 0) as i64 },
+            || unsafe { setenv(b"k\0".as_ptr() as _, b"v\0".as_ptr() as _, 1) as i64 },
+            || unsafe { unsetenv(b"k\0".as_ptr() as _) as i64 },
+            || unsafe { chdir(b"/\0".as_ptr() as _) as i64 },
+            || unsafe { fstat(0, ptr::null_mut()) as i64 },
+            || unsafe { fstat64(0, ptr::null_mut()) as i64 },
+            || unsafe { stat(b"/\0".as_ptr() as _, ptr::null_mut()) as i64 },
+            || unsafe { stat64(b"/\0".as_ptr() as _, ptr::null_mut()) as i64 },
+            || unsafe { lstat(b"/\0".as_ptr() as _, ptr::null_mut()) as i64 },
+            || unsafe { lstat64(b"/\0".as_ptr() as _, ptr::null_mut()) as i64 },
+            || unsafe { ftruncate(0, 0) as i64 },
+            || unsafe { ftruncate64(0, 0) as i64 },
+            || unsafe { truncate(b"/\0".as_ptr() as _, 0) as i64 },
+            || unsafe { truncate64(b"/\0".as_ptr() as _, 0) as i64 },
+            || unsafe { fsync(0) as i64 },
+            || unsafe { fdatasync(0) as i64 },
+            || unsafe { fchmod(0, 0) as i64 },
+            || unsafe { unlink(b"/\0".as_ptr() as _) as i64 },
+            || unsafe { link(b"/\0".as_ptr() as _, b"/\0".as_ptr() as _) as i64 },
+            || unsafe { rename(b"/\0".as_ptr() as _, b"/\0".as_ptr() as _) as i64 },
+            || unsafe { chmod(b"/\0".as_ptr() as _, 0) as i64 },
+            || unsafe { symlink(b"/\0".as_ptr() as _, b"/\0".as_ptr() as _) as i64 },
+            || unsafe { mkdir(b"/\0".as_ptr() as _, 0) as i64 },
+            || unsafe { rmdir(b"/\0".as_ptr() as _) as i64 },
+            || unsafe { closedir(ptr::null_mut()) as i64 },
+            || unsafe { fstatat64(0, b"/\0".as_ptr() as _, ptr::null_mut(), 0) as i64 },
+            || unsafe { close(0) as i64 },
+            || unsafe { bind(0, ptr::null(), 0) as i64 },
+            || unsafe { listen(0, 0) as i64 },
+            || unsafe { connect(0, ptr::null(), 0) as i64 },
+            || unsafe { setsockopt(0, 0, 0, ptr::null(), 0) as i64 },
+            || unsafe { shutdown(0, 0) as i64 },
+            || unsafe { epoll_ctl(0, 0, 0, ptr::null_mut()) as i64 },
+            || unsafe { sched_yield() as i64 },
+            || unsafe { nanosleep(ptr::null(), ptr::null_mut()) as i64 },
+        ];
+        for call in calls {
+            host(0);
+            assert_eq!(call(), 0);
+            host(-1);
+            assert_eq!(call(), -1);
+            assert_eq!(errno(), HOST_ERRNO);
+            host(1);
+            assert_iago(call());
+            host(-7);
+            assert_iago(call());
+        }
+    }
+
+    #[test]
+    fn non_negative_result_wrappers() {
+        let calls: &[fn() -> i64] = &[
+            || unsafe { open(b"/\0".as_ptr() as _, 0) as i64 },
+            || unsafe { open64(b"/\0".as_ptr() as _, 0, 0) as i64 },
+            || unsafe { lseek(0, 0, 0) as i64 },
+            || unsafe { lseek64(0, 0, 0) as i64 },
+            || unsafe { dirfd(ptr::null_mut()) as i64 },
+            || unsafe { fcntl_arg0(0, 0) as i64 },
+            || unsafe { fcntl_arg1(0, 0, 0) as i64 },
+            || unsafe { ioctl_arg0(0, 0) as i64 },
+            || unsafe { ioctl_arg1(0, 0, ptr::null()) as i64 },
+            || unsafe { socket(0, 0, 0) as i64 },
+            || unsafe { epoll_create1(0) as i64 },
+            || unsafe { sysconf(0) as i64 },
+            || unsafe { prctl(0, 0, 0, 0, 0) as i64 },
+        ];
+        for call in calls {
+            host(5);
+            assert_eq!(call(), 5);
+            host(-1);
+            assert_eq!(call(), -1);
+            assert_eq!(errno(), HOST_ERRNO);
+            host(-2);
+            assert_iago(call());
+        }
+    }
+
+    #[test]
+    fn length_bounded_wrappers() {
+        let mut buf = [0_u8; 16];
+        let p = buf.as_mut_ptr() as * mut c_void;
+        let calls: &[(usize, &dyn Fn() -> i64)] = &[
+            (16, &|| unsafe { read(0, p, 16) as i64 }),
+            (16, &|| unsafe { pread64(0, p, 16, 0) as i64 }),
+            (16, &|| unsafe { write(0, p, 16) as i64 }),
+            (16, &|| unsafe { pwrite64(0, p, 16, 0) as i64 }),
+            (16, &|| unsafe { readlink(b"/\0".as_ptr() as _, p as _, 16) as i64 }),
+            (16, &|| unsafe { recv(0, p, 16, 0) as i64 }),
+            (16, &|| unsafe { send(0, p, 16, 0) as i64 }),
+            (16, &|| unsafe { sendto(0, p, 16, 0, ptr::null(), 0) as i64 }),
+            (16, &|| unsafe { recvfrom(0, p, 16, 0, ptr::null_mut(), ptr::null_mut()) as i64 }),
+        ];
+        for (max, call) in calls {
+            host(*max as i64);
+            assert_eq!(call(), *max as i64);
+            host(*max as i64 + 1);
+            assert_iago(call());
+            host(-3);
+            assert_iago(call());
+        }
+    }
+
+    #[test]
+    fn event_counts_are_bounded() {
+        let mut fds: [pollfd; 2] = unsafe { mem::zeroed() };
+        host(2);
+        assert_eq!(unsafe { poll(fds.as_mut_ptr(), 2, 0) }, 2);
+        host(3);
+        assert_iago(unsafe { poll(fds.as_mut_ptr(), 2, 0) } as i64);
+
+        let mut events: [epoll_event; 4] = unsafe { mem::zeroed() };
+        host(4);
+        assert_eq!(unsafe { epoll_wait(0, events.as_mut_ptr(), 4, 0) }, 4);
+        host(5);
+        assert_iago(unsafe { epoll_wait(0, events.as_mut_ptr(), 4, 0) } as i64);
+        assert_eq!(unsafe { epoll_wait(0, events.as_mut_ptr(), 0, 0) }, -1);
+        assert_eq!(errno(), EINVAL);
+    }
+
+    #[test]
+    fn returned_lengths_are_bounded() {
+        let mut addr: sockaddr_storage = unsafe { mem::zeroed() };
+        let ap = &mut addr as * mut _ as * mut sockaddr;
+        let size = mem::size_of::<sockaddr_storage>() as socklen_t;
+        let calls: &[&dyn Fn(* mut socklen_t) -> i64] = &[
+            &|len| unsafe { accept(0, ap, len) as i64 },
+            &|len| unsafe { accept4(0, ap, len, 0) as i64 },
+            &|len| unsafe { recvfrom(0, ptr::null_mut(), 0, 0, ap, len) as i64 },
+            &|len| unsafe { getpeername(0, ap, len) as i64 },
+            &|len| unsafe { getsockname(0, ap, len) as i64 },
+            &|len| unsafe { getsockopt(0, 0, 0, ap as _, len) as i64 },
+        ];
+        host(0);
+        for call in calls {
+            let mut len = size;
+            LEN_OUT.with(|l| l.set(16));
+            assert_eq!(call(&mut len), 0);
+            assert_eq!(len, 16);
+
+            let mut len = size;
+            LEN_OUT.with(|l| l.set(size + 1));
+            assert_iago(call(&mut len));
+            assert_eq!(len, 0);
+        }
+
+        // A truncated address reports its full length, as on Linux, while
+        // option values are never longer than the buffer.
+        let (getsockopt_call, address_calls) = calls.split_last().unwrap();
+        LEN_OUT.with(|l| l.set(16));
+        for call in address_calls {
+            let mut len = 8;
+            assert_eq!(call(&mut len), 0);
+            assert_eq!(len, 16);
+        }
+        let mut len = 8;
+        assert_iago(getsockopt_call(&mut len));
+        assert_eq!(len, 0);
+    }
+
+    #[test]
+    fn returned_fds_are_checked() {
+        let calls: &[fn(* mut c_int) -> i64] = &[
+            |fds| unsafe { socketpair(0, 0, 0, fds) as i64 },
+            |fds| unsafe { pipe(fds) as i64 },
+            |fds| unsafe { pipe2(fds, 0) as i64 },
+        ];
+        host(0);
+        for call in calls {
+            let mut fds = [0; 2];
+            FDS.with(|f| f.set((3, 4)));
+            assert_eq!(call(fds.as_mut_ptr()), 0);
+            assert_eq!(fds, [3, 4]);
+            FDS.with(|f| f.set((3, -9)));
+            assert_iago(call(fds.as_mut_ptr()));
+        }
+    }
+
+    #[test]
+    fn clock_gettime_checks_nanoseconds() {
+        let mut tp: timespec = unsafe { mem::zeroed() };
+        NSEC.with(|n| n.set(999_999_999));
+        assert_eq!(unsafe { clock_gettime(0, &mut tp) }, 0);
+        NSEC.with(|n| n.set(1_000_000_000));
+        assert_iago(unsafe { clock_gettime(0, &mut tp) } as i64);
+        NSEC.with(|n| n.set(-1));
+        assert_iago(unsafe { clock_gettime(0, &mut tp) } as i64);
+    }
+
+    #[test]
+    fn returned_pointers_must_be_untrusted() {
+        let mut secret = [0_u8; 64];
+        let arena = untrusted(64);
+        let outside = arena.as_ptr() as usize;
+        let inside = secret.as_mut_ptr() as usize;
+
+        host_ptr(outside);
+        assert_eq!(unsafe { malloc(64) } as usize, outside);
+        assert!(unsafe { malloc(65) }.is_null());
+        assert_eq!(errno(), EIAGO);
+        host_ptr(inside);
+        assert!(unsafe { malloc(8) }.is_null());
+        assert_eq!(errno(), EIAGO);
+
+        host_ptr(outside);
+        assert_eq!(unsafe { mmap(ptr::null_mut(), 64, 0, 0, 0, 0) } as usize, outside);
+        host_ptr(inside);
+        assert_eq!(unsafe { mmap(ptr::null_mut(), 64, 0, 0, 0, 0) } as isize, -1);
+        assert_eq!(errno(), EIAGO);
+
+        host_ptr(outside);
+        assert_eq!(unsafe { opendir(b"/\0".as_ptr() as _) } as usize, outside);
+        host_ptr(inside);
+        assert!(unsafe { opendir(b"/\0".as_ptr() as _) }.is_null());
+        assert_eq!(errno(), EIAGO);
+    }
+
+    #[test]
+    fn returned_strings_must_be_untrusted_and_terminated() {
+        let mut secret = *b"secret\0";
+        let mut arena = untrusted(32);
+        unsafe { ptr::copy_nonoverlapping(b"abc\0".as_ptr(), arena.as_mut_ptr() as * mut u8, 4); }
+        let outside = arena.as_ptr() as usize;
+        let inside = secret.as_mut_ptr() as usize;
+
+        let calls: &[fn() -> * const c_char] = &[
+            || unsafe { getenv(b"k\0".as_ptr() as _) },
+            || unsafe { realpath(b"/\0".as_ptr() as _) },
+            || unsafe { gai_strerror(0) },
+        ];
+        for call in calls {
+            host_ptr(outside);
+            assert_eq!(call() as usize, outside);
+            host_ptr(inside);
+            assert!(call().is_null());
+            assert_eq!(errno(), EIAGO);
+        }
+    }
+
+    #[test]
+    fn environ_must_be_untrusted() {
+        let mut arena = untrusted(64);
+        let base = arena.as_mut_ptr();
+        unsafe {
+            ptr::copy_nonoverlapping(b"A=1\0".as_ptr(), base.add(4) as * mut u8, 4);
+            *base = base.add(4) as u64;
+            *base.add(1) = 0;
+        }
+        host_ptr(base as usize);
+        assert_eq!(unsafe { environ() } as usize, base as usize);
+
+        let secret = *b"S=1\0";
+        unsafe { *base = secret.as_ptr() as u64; }
+        assert!(unsafe { environ() }.is_null());
+        assert_eq!(errno(), EIAGO);
+    }
+
+    #[test]
+    fn getcwd_requires_terminator() {
+        let mut buf = [0 as c_char; 16];
+        FILL.with(|f| f.set(0));
+        assert_eq!(unsafe { getcwd(buf.as_mut_ptr(), 16) }, buf.as_mut_ptr());
+        FILL.with(|f| f.set(b'a'));
+        assert!(unsafe { getcwd(buf.as_mut_ptr(), 16) }.is_null());
+        assert_eq!(errno(), EIAGO);
+    }
+
+    #[test]
+    fn readdir_requires_terminated_name() {
+        let mut entry: dirent64 = unsafe { mem::zeroed() };
+        let mut out: * mut dirent64 = ptr::null_mut();
+        host_ptr(1);
+        FILL.with(|f| f.set(0));
+        assert_eq!(unsafe { readdir64_r(ptr::null_mut(), &mut entry, &mut out) }, 0);
+        assert_eq!(out, &mut entry as * mut dirent64);
+        FILL.with(|f| f.set(b'a'));
+        assert_eq!(unsafe { readdir64_r(ptr::null_mut(), &mut entry, &mut out) }, EIAGO);
+        assert!(out.is_null());
+    }
+
+    #[test]
+    fn getpwuid_offsets_stay_in_buffer() {
+        let mut pwd: passwd = unsafe { mem::zeroed() };
+        let mut buf = [0 as c_char; 32];
+        let mut out: * mut passwd = ptr::null_mut();
+        FILL.with(|f| f.set(0));
+        host_ptr(1);
+        assert_eq!(unsafe { getpwuid_r(0, &mut pwd, buf.as_mut_ptr(), 32, &mut out) }, 0);
+        assert_eq!(out, &mut pwd as * mut passwd);
+        assert_eq!(pwd.pw_name, unsafe { buf.as_mut_ptr().add(1) });
+        assert!(pwd.pw_passwd.is_null());
+
+        host_ptr(32);
+        assert_eq!(unsafe { getpwuid_r(0, &mut pwd, buf.as_mut_ptr(), 32, &mut out) }, EIAGO);
+        assert!(out.is_null());
+
+        FILL.with(|f| f.set(b'a'));
+        host_ptr(0);
+        assert_eq!(unsafe { getpwuid_r(0, &mut pwd, buf.as_mut_ptr(), 32, &mut out) }, EIAGO);
+        assert!(out.is_null());
+    }
+
+    #[test]
+    fn vectored_io_is_bounded() {
+        let _arena = untrusted(64);
+        let mut a = [0_u8; 4];
+        let mut b = [0_u8; 4];
+        let iov = [iovec { iov_base: a.as_mut_ptr() as _, iov_len: 4 },
+                   iovec { iov_base: b.as_mut_ptr() as _, iov_len: 4 }];
+        let calls: &[&dyn Fn() -> i64] = &[
+            &|| unsafe { readv(0, iov.as_ptr(), 2) as i64 },
+            &|| unsafe { preadv64(0, iov.as_ptr(), 2, 0) as i64 },
+            &|| unsafe { writev(0, iov.as_ptr(), 2) as i64 },
+            &|| unsafe { pwritev64(0, iov.as_ptr(), 2, 0) as i64 },
+        ];
+        for call in calls {
+            host(8);
+            assert_eq!(call(), 8);
+            host(9);
+            assert_iago(call());
+        }
+    }
+
+    #[test]
+    fn sendmsg_is_bounded() {
+        let _arena = untrusted(128);
+        let mut data = [0_u8; 8];
+        let mut iov = iovec { iov_base: data.as_mut_ptr() as _, iov_len: 8 };
+        let mut msg: msghdr = unsafe { mem::zeroed() };
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        host(8);
+        assert_eq!(unsafe { sendmsg(0, &msg, 0) }, 8);
+        host(9);
+        assert_iago(unsafe { sendmsg(0, &msg, 0) } as i64);
+    }
+
+    #[test]
+    fn recvmsg_ignores_rewritten_pointers() {
+        let _arena = untrusted(128);
+        let mut secret = [b's'; 8];
+        let mut data = [0_u8; 8];
+        let mut control = [0_u8; 8];
+        let mut iov = iovec { iov_base: data.as_mut_ptr() as _, iov_len: 8 };
+        let mut msg: msghdr = unsafe { mem::zeroed() };
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr() as _;
+        msg.msg_controllen = 8;
+
+        host(8);
+        LEN_OUT.with(|l| l.set(8));
+        REDIRECT.with(|r| r.set(secret.as_mut_ptr() as usize));
+        assert_eq!(unsafe { recvmsg(0, &mut msg, 0) }, 8);
+        assert_eq!(data, [b'h'; 8]);
+        assert_eq!(control, [0; 8]);
+
+        host(9);
+        assert_iago(unsafe { recvmsg(0, &mut msg, 0) } as i64);
+
+        host(8);
+        LEN_OUT.with(|l| l.set(9));
+        assert_iago(unsafe { recvmsg(0, &mut msg, 0) } as i64);
+    }
+
+    #[test]
+    fn getaddrinfo_rejects_enclave_nodes() {
+        let mut arena = untrusted(256);
+        let base = arena.as_mut_ptr();
+        let hints: addrinfo = unsafe { mem::zeroed() };
+        let mut res: * mut addrinfo = ptr::null_mut();
+        unsafe {
+            let node = base as * mut addrinfo;
+            let addr = base.add(8);
+            let name = base.add(24) as * mut u8;
+            ptr::write(node, mem::zeroed());
+            (*node).ai_family = AF_INET;
+            (*node).ai_addrlen = mem::size_of::<sockaddr_in>() as socklen_t;
+            (*node).ai_addr = addr as * mut sockaddr;
+            ptr::copy_nonoverlapping(b"host\0".as_ptr(), name, 5);
+            (*node).ai_canonname = name as * mut c_char;
+        }
+
+        host_ptr(base as usize);
+        assert_eq!(unsafe { getaddrinfo(ptr::null(), ptr::null(), &hints, &mut res) }, 0);
+        assert!(!res.is_null());
+        assert_eq!(unsafe { (*res).ai_family }, AF_INET);
+        unsafe { freeaddrinfo(res); }
+
+        let mut secret: sockaddr_storage = unsafe { mem::zeroed() };
+        unsafe { (*(base as * mut addrinfo)).ai_addr = &mut secret as * mut _ as * mut sockaddr; }
+        assert_eq!(unsafe { getaddrinfo(ptr::null(), ptr::null(), &hints, &mut res) }, EAI_SYSTEM);
+        assert_eq!(errno(), EIAGO);
+        assert!(res.is_null());
+
+        let mut local: addrinfo = unsafe { mem::zeroed() };
+        host_ptr(&mut local as * mut addrinfo as usize);
+        assert_eq!(unsafe { getaddrinfo(ptr::null(), ptr::null(), &hints, &mut res) }, EAI_SYSTEM);
+        assert_eq!(errno(), EIAGO);
+    }
+}
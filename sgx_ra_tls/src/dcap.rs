@@ -0,0 +1,886 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! DCAP quote verification with collateral supplied by the host.
+//!
+//! Inside an enclave there is no QvE, and the result of a host-side
+//! `sgx_qv_verify_quote` cannot be trusted. `DcapVerifier` does the checks
+//! itself: it verifies the PCK certificate chain of a version 3 quote
+//! against a trusted root, checks the chain and the collateral signing
+//! chain against the CRLs, verifies the TCB info and QE identity
+//! signatures, matches the QE report against the QE identity and evaluates
+//! the platform TCB level. The host only supplies the collateral, e.g. from
+//! `sgx_ql_get_quote_verification_collateral`.
+//!
+
+use std::prelude::v1::*;
+use std::str;
+use sgx_types::*;
+use sgx_types::quote::{QuoteSignature, QuoteView, ReportBodyView};
+use crypto::*;
+use serde_json::Value;
+use crate::der::*;
+use crate::error::RaTlsError;
+use crate::ias::decode_hex;
+use crate::policy::{AttestationPolicy, QuoteStatus};
+use crate::time;
+use crate::x509::{self, CertInfo, CrlInfo};
+
+// 1.2.840.113741.1.13.1, the SGX extension of PCK certificates, and its
+// PPID, TCB, PCE-ID and FMSPC entries.
+const OID_SGX_EXTENSION: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf8, 0x4d, 0x01, 0x0d, 0x01];
+const SGX_EXT_TCB: u8 = 2;
+const SGX_EXT_PCE_ID: u8 = 3;
+const SGX_EXT_FMSPC: u8 = 4;
+const SGX_TCB_PCESVN: u8 = 17;
+
+const TCB_COMPONENTS: usize = 16;
+const FMSPC_SIZE: usize = 6;
+const PCE_ID_SIZE: usize = 2;
+const ATTRIBUTES_SIZE: usize = 16;
+
+// Certification data type of a PEM PCK certificate chain.
+const PCK_CERT_CHAIN: u16 = 5;
+
+// Subject common name of the certificate that signs TCB info and QE identity.
+const TCB_SIGNING_CN: &[u8] = b"Intel SGX TCB Signing";
+
+/// TCB status of a TCB info or QE identity level.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TcbStatus {
+    UpToDate,
+    SwHardeningNeeded,
+    ConfigurationNeeded,
+    ConfigurationAndSwHardeningNeeded,
+    OutOfDate,
+    OutOfDateConfigurationNeeded,
+    Revoked,
+}
+
+impl TcbStatus {
+    pub fn parse(s: &str) -> Option<TcbStatus> {
+        match s {
+            "UpToDate" => Some(TcbStatus::UpToDate),
+            "SWHardeningNeeded" => Some(TcbStatus::SwHardeningNeeded),
+            "ConfigurationNeeded" => Some(TcbStatus::ConfigurationNeeded),
+            "ConfigurationAndSWHardeningNeeded" => Some(TcbStatus::ConfigurationAndSwHardeningNeeded),
+            "OutOfDate" => Some(TcbStatus::OutOfDate),
+            "OutOfDateConfigurationNeeded" => Some(TcbStatus::OutOfDateConfigurationNeeded),
+            "Revoked" => Some(TcbStatus::Revoked),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            TcbStatus::UpToDate => "UpToDate",
+            TcbStatus::SwHardeningNeeded => "SWHardeningNeeded",
+            TcbStatus::ConfigurationNeeded => "ConfigurationNeeded",
+            TcbStatus::ConfigurationAndSwHardeningNeeded => "ConfigurationAndSWHardeningNeeded",
+            TcbStatus::OutOfDate => "OutOfDate",
+            TcbStatus::OutOfDateConfigurationNeeded => "OutOfDateConfigurationNeeded",
+            TcbStatus::Revoked => "Revoked",
+        }
+    }
+
+    /// The result `sgx_qv_verify_quote` reports for this status.
+    pub fn qv_result(&self) -> sgx_ql_qv_result_t {
+        match *self {
+            TcbStatus::UpToDate => sgx_ql_qv_result_t::SGX_QL_QV_RESULT_OK,
+            TcbStatus::SwHardeningNeeded => sgx_ql_qv_result_t::SGX_QL_QV_RESULT_SW_HARDENING_NEEDED,
+            TcbStatus::ConfigurationNeeded => sgx_ql_qv_result_t::SGX_QL_QV_RESULT_CONFIG_NEEDED,
+            TcbStatus::ConfigurationAndSwHardeningNeeded => sgx_ql_qv_result_t::SGX_QL_QV_RESULT_CONFIG_AND_SW_HARDENING_NEEDED,
+            TcbStatus::OutOfDate => sgx_ql_qv_result_t::SGX_QL_QV_RESULT_OUT_OF_DATE,
+            TcbStatus::OutOfDateConfigurationNeeded => sgx_ql_qv_result_t::SGX_QL_QV_RESULT_OUT_OF_DATE_CONFIG_NEEDED,
+            TcbStatus::Revoked => sgx_ql_qv_result_t::SGX_QL_QV_RESULT_REVOKED,
+        }
+    }
+
+    /// The quote statuses an `AttestationPolicy` must accept for this TCB
+    /// status. An out-of-date platform that also needs configuration must
+    /// be accepted for both.
+    pub fn quote_statuses(&self) -> &'static [QuoteStatus] {
+        match *self {
+            TcbStatus::UpToDate => &[QuoteStatus::Ok],
+            TcbStatus::SwHardeningNeeded => &[QuoteStatus::SwHardeningNeeded],
+            TcbStatus::ConfigurationNeeded => &[QuoteStatus::ConfigurationNeeded],
+            TcbStatus::ConfigurationAndSwHardeningNeeded => &[QuoteStatus::ConfigurationAndSwHardeningNeeded],
+            TcbStatus::OutOfDate => &[QuoteStatus::GroupOutOfDate],
+            TcbStatus::OutOfDateConfigurationNeeded => &[QuoteStatus::GroupOutOfDate, QuoteStatus::ConfigurationNeeded],
+            TcbStatus::Revoked => &[QuoteStatus::KeyRevoked],
+        }
+    }
+
+    // Folds the QE TCB status into the platform's, as the QvL does.
+    fn converge(self, qe: TcbStatus) -> TcbStatus {
+        match (qe, self) {
+            (TcbStatus::Revoked, _) => TcbStatus::Revoked,
+            (TcbStatus::OutOfDate, TcbStatus::UpToDate) |
+            (TcbStatus::OutOfDate, TcbStatus::SwHardeningNeeded) => TcbStatus::OutOfDate,
+            (TcbStatus::OutOfDate, TcbStatus::ConfigurationNeeded) |
+            (TcbStatus::OutOfDate, TcbStatus::ConfigurationAndSwHardeningNeeded) => TcbStatus::OutOfDateConfigurationNeeded,
+            _ => self,
+        }
+    }
+}
+
+/// Quote verification collateral, as in `sgx_ql_qve_collateral_t`.
+///
+/// Certificate chains are PEM. CRLs may be DER, PEM or hex-encoded DER, as
+/// different PCCS versions return them. TCB info and QE identity are the
+/// signed JSON documents of the Intel PCS. Trailing NULs are ignored.
+#[derive(Clone, Debug, Default)]
+pub struct QuoteCollateral {
+    pub pck_crl_issuer_chain: Vec<u8>,
+    pub root_ca_crl: Vec<u8>,
+    pub pck_crl: Vec<u8>,
+    pub tcb_info_issuer_chain: Vec<u8>,
+    pub tcb_info: Vec<u8>,
+    pub qe_identity_issuer_chain: Vec<u8>,
+    pub qe_identity: Vec<u8>,
+}
+
+impl QuoteCollateral {
+    /// Copies the collateral out of a `sgx_ql_qve_collateral_t`.
+    ///
+    /// # Safety
+    ///
+    /// Every pointer must be valid for its size, or null with size 0.
+    pub unsafe fn from_raw(raw: &sgx_ql_qve_collateral_t) -> QuoteCollateral {
+        let copy = |ptr: *const u8, size: uint32_t| -> Vec<u8> {
+            if ptr.is_null() || size == 0 {
+                Vec::new()
+            } else {
+                std::slice::from_raw_parts(ptr, size as usize).to_vec()
+            }
+        };
+        QuoteCollateral {
+            pck_crl_issuer_chain: copy(raw.pck_crl_issuer_chain as *const u8, raw.pck_crl_issuer_chain_size),
+            root_ca_crl: copy(raw.root_ca_crl as *const u8, raw.root_ca_crl_size),
+            pck_crl: copy(raw.pck_crl as *const u8, raw.pck_crl_size),
+            tcb_info_issuer_chain: copy(raw.tcb_info_issuer_chain as *const u8, raw.tcb_info_issuer_chain_size),
+            tcb_info: copy(raw.tcb_info as *const u8, raw.tcb_info_size),
+            qe_identity_issuer_chain: copy(raw.qe_identity_issuer_chain as *const u8, raw.qe_identity_issuer_chain_size),
+            qe_identity: copy(raw.qe_identity as *const u8, raw.qe_identity_size),
+        }
+    }
+}
+
+/// The attested facts of a quote that passed verification.
+#[derive(Clone)]
+pub struct VerifiedQuote {
+    /// The platform TCB status, with the QE's folded in.
+    pub tcb_status: TcbStatus,
+    /// Security advisories of the matching TCB level (TCB info version 3).
+    pub advisory_ids: Vec<String>,
+    pub fmspc: [u8; FMSPC_SIZE],
+    pub report_body: sgx_report_body_t,
+}
+
+/// Verifies DCAP quotes against an `AttestationPolicy`.
+///
+/// The policy's accepted statuses apply to `TcbStatus::quote_statuses`;
+/// its maximum report age does not apply, since collateral carries its own
+/// next update.
+///
+/// ```ignore
+/// let verifier = DcapVerifier::new(policy, sgx_root_ca_der);
+/// let verified = verifier.verify(&quote, &collateral)?;
+/// ```
+pub struct DcapVerifier {
+    policy: AttestationPolicy,
+    root_ca: Vec<u8>,
+}
+
+impl DcapVerifier {
+    /// Creates a verifier that trusts `root_ca`, the DER Intel SGX Root CA
+    /// certificate.
+    pub fn new(policy: AttestationPolicy, root_ca: Vec<u8>) -> DcapVerifier {
+        DcapVerifier { policy, root_ca }
+    }
+
+    pub fn policy(&self) -> &AttestationPolicy {
+        &self.policy
+    }
+
+    /// Verifies a quote at the current time.
+    pub fn verify(&self, quote: &[u8], collateral: &QuoteCollateral) -> Result<VerifiedQuote, RaTlsError> {
+        self.verify_at(quote, collateral, time::now_secs())
+    }
+
+    /// Verifies a quote at `now`, in seconds since the Unix epoch.
+    pub fn verify_at(&self, quote: &[u8], collateral: &QuoteCollateral, now: u64) -> Result<VerifiedQuote, RaTlsError> {
+        let quote = QuoteView::parse(quote).map_err(|_| RaTlsError::BadQuote)?;
+        let sig = match quote.signature() {
+            QuoteSignature::Ecdsa(sig) => sig,
+            _ => return Err(RaTlsError::BadQuote),
+        };
+        let cert_data = sig.certification_data();
+        if cert_data.cert_key_type() != PCK_CERT_CHAIN {
+            return Err(RaTlsError::BadQuote);
+        }
+        let chain = x509::pem_certificates(cert_data.data()).ok_or(RaTlsError::BadQuote)?;
+        if chain.len() < 2 {
+            return Err(RaTlsError::BadPckCert);
+        }
+
+        // The PCK chain, up to the trusted root. A root in the quote is
+        // ignored.
+        let root = CertInfo::parse(&self.root_ca).ok_or(RaTlsError::BadPckCert)?;
+        let pck = CertInfo::parse(&chain[0]).ok_or(RaTlsError::BadPckCert)?;
+        let pck_ca = CertInfo::parse(&chain[1]).ok_or(RaTlsError::BadPckCert)?;
+        if !pck_ca.is_valid_at(now) || !pck_ca.is_ecdsa_signed_by(&root)?
+            || !pck.is_valid_at(now) || !pck.is_ecdsa_signed_by(&pck_ca)? {
+            return Err(RaTlsError::BadPckCert);
+        }
+        let root_crl_der = decode_crl(&collateral.root_ca_crl).ok_or(RaTlsError::BadCollateral)?;
+        let root_crl = checked_crl(&root_crl_der, &root, now)?;
+        // The PCK CRL is checked against the CA from the quote; its issuer
+        // chain in the collateral adds nothing once that CA is verified.
+        let pck_crl_der = decode_crl(&collateral.pck_crl).ok_or(RaTlsError::BadCollateral)?;
+        let pck_crl = checked_crl(&pck_crl_der, &pck_ca, now)?;
+        if root_crl.is_revoked(&pck_ca) || pck_crl.is_revoked(&pck) {
+            return Err(RaTlsError::CertRevoked);
+        }
+
+        // The QE report, signed by the PCK, binds the attestation key,
+        // which signs the quote.
+        let pck_public = x509::spki_public(pck.spki).ok_or(RaTlsError::BadPckCert)?;
+        let qe_report = sig.qe_report();
+        let qe_report_sig = x509::raw_signature(sig.qe_report_signature()).ok_or(RaTlsError::BadQuoteSignature)?;
+        if !x509::ecdsa_verify(&pck_public, qe_report.as_bytes(), &qe_report_sig)? {
+            return Err(RaTlsError::BadQuoteSignature);
+        }
+        let mut key_hash_input = sig.attest_pub_key().to_vec();
+        key_hash_input.extend_from_slice(sig.qe_auth_data());
+        let key_hash = rsgx_sha256_slice(&key_hash_input).map_err(RaTlsError::Crypto)?;
+        let report_data = qe_report.report_data();
+        if report_data[..32] != key_hash[..] || report_data[32..].iter().any(|b| *b != 0) {
+            return Err(RaTlsError::BadQuoteSignature);
+        }
+        let attest_key = x509::raw_public(sig.attest_pub_key()).ok_or(RaTlsError::BadQuoteSignature)?;
+        let quote_sig = x509::raw_signature(sig.signature()).ok_or(RaTlsError::BadQuoteSignature)?;
+        if !x509::ecdsa_verify(&attest_key, quote.signed_bytes(), &quote_sig)? {
+            return Err(RaTlsError::BadQuoteSignature);
+        }
+
+        let tcb_body = verify_signed_json(&collateral.tcb_info, "tcbInfo",
+                                          &collateral.tcb_info_issuer_chain, &root, &root_crl, now)?;
+        let tcb_info = TcbInfo::parse(tcb_body).ok_or(RaTlsError::BadCollateral)?;
+        let qe_body = verify_signed_json(&collateral.qe_identity, "enclaveIdentity",
+                                         &collateral.qe_identity_issuer_chain, &root, &root_crl, now)?;
+        let qe_identity = QeIdentity::parse(qe_body).ok_or(RaTlsError::BadCollateral)?;
+        if now >= tcb_info.next_update || now >= qe_identity.next_update {
+            return Err(RaTlsError::CollateralExpired);
+        }
+
+        let pck_tcb = PckTcb::parse(&pck).ok_or(RaTlsError::BadPckCert)?;
+        if pck_tcb.fmspc[..] != tcb_info.fmspc[..] || pck_tcb.pce_id[..] != tcb_info.pce_id[..] {
+            return Err(RaTlsError::TcbInfoMismatch);
+        }
+        let level = tcb_info.levels.iter()
+            .find(|level| level.matches(&pck_tcb))
+            .ok_or(RaTlsError::UnsupportedTcb)?;
+        let qe_status = qe_identity.check(&qe_report)?;
+        let tcb_status = level.status.converge(qe_status);
+        if tcb_status == TcbStatus::Revoked {
+            return Err(RaTlsError::TcbRevoked);
+        }
+
+        for status in tcb_status.quote_statuses() {
+            self.policy.check_status(*status)?;
+        }
+        let body = quote.report_body();
+        self.policy.check_identity(&body)?;
+
+        Ok(VerifiedQuote {
+            tcb_status,
+            advisory_ids: level.advisory_ids.clone(),
+            fmspc: pck_tcb.fmspc,
+            report_body: body.to_report_body(),
+        })
+    }
+}
+
+// Parses a CRL and checks that `issuer` signed it and that it is current.
+fn checked_crl<'a>(der: &'a [u8], issuer: &CertInfo, now: u64) -> Result<CrlInfo<'a>, RaTlsError> {
+    let crl = CrlInfo::parse(der).ok_or(RaTlsError::BadCollateral)?;
+    if !crl.is_ecdsa_signed_by(issuer)? {
+        return Err(RaTlsError::BadCollateralSignature);
+    }
+    if !crl.is_current_at(now) {
+        return Err(RaTlsError::CollateralExpired);
+    }
+    Ok(crl)
+}
+
+// Verifies a PCS document `{"<name>":<body>,"signature":"<hex r || s>"}`
+// with the first certificate of `issuer_chain`, which must be issued by
+// `root` and not revoked, and returns the body.
+//
+// The signature covers the body exactly as sent, so it is cut out of the
+// document rather than re-serialized.
+fn verify_signed_json<'a>(document: &'a [u8],
+                          name: &str,
+                          issuer_chain: &[u8],
+                          root: &CertInfo,
+                          root_crl: &CrlInfo,
+                          now: u64) -> Result<&'a [u8], RaTlsError> {
+    const SIGNATURE: &[u8] = b",\"signature\":\"";
+    let document = trim_collateral(document);
+    let prefix = format!("{{\"{}\":", name);
+    if !document.starts_with(prefix.as_bytes()) || !document.ends_with(b"\"}") {
+        return Err(RaTlsError::BadCollateral);
+    }
+    let sig_start = document.windows(SIGNATURE.len())
+        .rposition(|w| w == SIGNATURE)
+        .ok_or(RaTlsError::BadCollateral)?;
+    // The closing quote of the signature key may double as the final one.
+    if sig_start < prefix.len() || sig_start + SIGNATURE.len() > document.len() - 2 {
+        return Err(RaTlsError::BadCollateral);
+    }
+    let body = &document[prefix.len()..sig_start];
+    let signature = str::from_utf8(&document[sig_start + SIGNATURE.len()..document.len() - 2]).ok()
+        .and_then(decode_hex)
+        .and_then(|rs| x509::raw_signature(&rs))
+        .ok_or(RaTlsError::BadCollateral)?;
+
+    let chain = x509::pem_certificates(trim_collateral(issuer_chain)).ok_or(RaTlsError::BadCollateral)?;
+    let signer = CertInfo::parse(&chain[0]).ok_or(RaTlsError::BadCollateral)?;
+    if signer.common_name() != Some(TCB_SIGNING_CN)
+        || !signer.is_valid_at(now)
+        || !signer.is_ecdsa_signed_by(root)? {
+        return Err(RaTlsError::BadCollateralSignature);
+    }
+    if root_crl.is_revoked(&signer) {
+        return Err(RaTlsError::CertRevoked);
+    }
+    let public = x509::spki_public(signer.spki).ok_or(RaTlsError::BadCollateral)?;
+    if !x509::ecdsa_verify(&public, body, &signature)? {
+        return Err(RaTlsError::BadCollateralSignature);
+    }
+    Ok(body)
+}
+
+fn trim_collateral(data: &[u8]) -> &[u8] {
+    let end = data.iter().rposition(|b| *b != 0 && !b.is_ascii_whitespace()).map_or(0, |i| i + 1);
+    &data[..end]
+}
+
+fn decode_crl(data: &[u8]) -> Option<Vec<u8>> {
+    let data = trim_collateral(data);
+    if data.first() == Some(&TAG_SEQUENCE) {
+        Some(data.to_vec())
+    } else if data.starts_with(b"-----BEGIN X509 CRL-----") {
+        let mut der = vec![0_u8; data.len()];
+        let len = rsgx_pem_decode("X509 CRL", data, &mut der).ok()?;
+        der.truncate(len);
+        Some(der)
+    } else {
+        decode_hex(str::from_utf8(data).ok()?)
+    }
+}
+
+fn hex_field(value: &Value, key: &str, len: usize) -> Option<Vec<u8>> {
+    let bytes = decode_hex(value[key].as_str()?)?;
+    if bytes.len() == len { Some(bytes) } else { None }
+}
+
+fn u16_field(value: &Value, key: &str) -> Option<u16> {
+    let n = value[key].as_u64()?;
+    if n <= u64::from(u16::max_value()) { Some(n as u16) } else { None }
+}
+
+fn time_field(value: &Value, key: &str) -> Option<u64> {
+    value[key].as_str().and_then(time::parse_ias_timestamp)
+}
+
+// The TCB info parts used for TCB evaluation.
+struct TcbInfo {
+    fmspc: Vec<u8>,
+    pce_id: Vec<u8>,
+    next_update: u64,
+    levels: Vec<TcbLevel>,
+}
+
+struct TcbLevel {
+    components: [u8; TCB_COMPONENTS],
+    pce_svn: u16,
+    status: TcbStatus,
+    advisory_ids: Vec<String>,
+}
+
+impl TcbInfo {
+    // Accepts version 2, with `sgxtcbcompNNsvn` fields, and version 3 for
+    // SGX, with a `sgxtcbcomponents` array.
+    fn parse(body: &[u8]) -> Option<TcbInfo> {
+        let info: Value = serde_json::from_slice(body).ok()?;
+        let version = info["version"].as_u64()?;
+        if version != 2 && !(version == 3 && info["id"] == "SGX") {
+            return None;
+        }
+        time_field(&info, "issueDate")?;
+        let levels = info["tcbLevels"].as_array()?.iter()
+            .map(|level| TcbLevel::parse(level, version))
+            .collect::<Option<Vec<_>>>()?;
+        Some(TcbInfo {
+            fmspc: hex_field(&info, "fmspc", FMSPC_SIZE)?,
+            pce_id: hex_field(&info, "pceId", PCE_ID_SIZE)?,
+            next_update: time_field(&info, "nextUpdate")?,
+            levels,
+        })
+    }
+}
+
+impl TcbLevel {
+    fn parse(level: &Value, version: u64) -> Option<TcbLevel> {
+        let tcb = &level["tcb"];
+        let mut components = [0_u8; TCB_COMPONENTS];
+        for (i, svn) in components.iter_mut().enumerate() {
+            let value = if version == 2 {
+                &tcb[&format!("sgxtcbcomp{:02}svn", i + 1)]
+            } else {
+                &tcb["sgxtcbcomponents"][i]["svn"]
+            };
+            let n = value.as_u64()?;
+            if n > 255 {
+                return None;
+            }
+            *svn = n as u8;
+        }
+        let advisory_ids = match level.get("advisoryIDs") {
+            Some(ids) => ids.as_array()?.iter()
+                .map(|id| id.as_str().map(str::to_string))
+                .collect::<Option<Vec<_>>>()?,
+            None => Vec::new(),
+        };
+        Some(TcbLevel {
+            components,
+            pce_svn: u16_field(tcb, "pcesvn")?,
+            status: TcbStatus::parse(level["tcbStatus"].as_str()?)?,
+            advisory_ids,
+        })
+    }
+
+    fn matches(&self, tcb: &PckTcb) -> bool {
+        tcb.components.iter().zip(self.components.iter()).all(|(have, need)| have >= need)
+            && tcb.pce_svn >= self.pce_svn
+    }
+}
+
+// The QE identity parts used to check the QE report.
+struct QeIdentity {
+    next_update: u64,
+    misc_select: u32,
+    misc_select_mask: u32,
+    attributes: Vec<u8>,
+    attributes_mask: Vec<u8>,
+    mr_signer: Vec<u8>,
+    isv_prod_id: u16,
+    levels: Vec<(u16, TcbStatus)>,
+}
+
+impl QeIdentity {
+    fn parse(body: &[u8]) -> Option<QeIdentity> {
+        let identity: Value = serde_json::from_slice(body).ok()?;
+        if identity["version"].as_u64()? != 2 || identity["id"] != "QE" {
+            return None;
+        }
+        let be_u32 = |key: &str| hex_field(&identity, key, 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]));
+        let levels = identity["tcbLevels"].as_array()?.iter()
+            .map(|level| Some((u16_field(&level["tcb"], "isvsvn")?,
+                               TcbStatus::parse(level["tcbStatus"].as_str()?)?)))
+            .collect::<Option<Vec<_>>>()?;
+        Some(QeIdentity {
+            next_update: time_field(&identity, "nextUpdate")?,
+            misc_select: be_u32("miscselect")?,
+            misc_select_mask: be_u32("miscselectMask")?,
+            attributes: hex_field(&identity, "attributes", ATTRIBUTES_SIZE)?,
+            attributes_mask: hex_field(&identity, "attributesMask", ATTRIBUTES_SIZE)?,
+            mr_signer: hex_field(&identity, "mrsigner", SGX_HASH_SIZE)?,
+            isv_prod_id: u16_field(&identity, "isvprodid")?,
+            levels,
+        })
+    }
+
+    // Matches the QE report and returns the status of its ISVSVN.
+    fn check(&self, report: &ReportBodyView) -> Result<TcbStatus, RaTlsError> {
+        let attributes = &report.as_bytes()[48..64];
+        if report.misc_select() & self.misc_select_mask != self.misc_select
+            || attributes.iter().zip(self.attributes_mask.iter()).map(|(a, m)| a & m).ne(self.attributes.iter().cloned())
+            || report.mr_signer().m[..] != self.mr_signer[..]
+            || report.isv_prod_id() != self.isv_prod_id {
+            return Err(RaTlsError::QeIdentityMismatch);
+        }
+        self.levels.iter()
+            .find(|(isv_svn, _)| report.isv_svn() >= *isv_svn)
+            .map(|(_, status)| *status)
+            .ok_or(RaTlsError::UnsupportedTcb)
+    }
+}
+
+// The TCB of a PCK certificate, from its SGX extension.
+struct PckTcb {
+    components: [u8; TCB_COMPONENTS],
+    pce_svn: u16,
+    pce_id: [u8; PCE_ID_SIZE],
+    fmspc: [u8; FMSPC_SIZE],
+}
+
+impl PckTcb {
+    fn parse(pck: &CertInfo) -> Option<PckTcb> {
+        let mut tcb = PckTcb {
+            components: [0; TCB_COMPONENTS],
+            pce_svn: 0,
+            pce_id: [0; PCE_ID_SIZE],
+            fmspc: [0; FMSPC_SIZE],
+        };
+        let (mut has_tcb, mut has_pce_id, mut has_fmspc) = (false, false, false);
+        let mut entries = DerReader::new(pck.extension(OID_SGX_EXTENSION)?).read_sequence()?;
+        while !entries.is_empty() {
+            let mut entry = entries.read_sequence()?;
+            let oid = entry.read(TAG_OID)?;
+            match sgx_oid_arcs(oid) {
+                Some([SGX_EXT_TCB]) => {
+                    let mut svns = entry.read_sequence()?;
+                    let mut seen = 0_u32;
+                    while !svns.is_empty() {
+                        let mut svn = svns.read_sequence()?;
+                        let oid = svn.read(TAG_OID)?;
+                        match sgx_oid_arcs(oid) {
+                            Some([SGX_EXT_TCB, n]) if *n >= 1 && usize::from(*n) <= TCB_COMPONENTS => {
+                                let value = read_uint(&mut svn)?;
+                                if value > 255 {
+                                    return None;
+                                }
+                                tcb.components[usize::from(*n) - 1] = value as u8;
+                                seen |= 1 << *n;
+                            },
+                            Some([SGX_EXT_TCB, SGX_TCB_PCESVN]) => {
+                                let value = read_uint(&mut svn)?;
+                                if value > u32::from(u16::max_value()) {
+                                    return None;
+                                }
+                                tcb.pce_svn = value as u16;
+                                seen |= 1 << SGX_TCB_PCESVN;
+                            },
+                            _ => (),
+                        }
+                    }
+                    // All 16 components and the PCESVN.
+                    has_tcb = seen == 0x3_fffe;
+                },
+                Some([SGX_EXT_PCE_ID]) => {
+                    let value = entry.read(TAG_OCTET_STRING)?;
+                    if value.len() != PCE_ID_SIZE {
+                        return None;
+                    }
+                    tcb.pce_id.copy_from_slice(value);
+                    has_pce_id = true;
+                },
+                Some([SGX_EXT_FMSPC]) => {
+                    let value = entry.read(TAG_OCTET_STRING)?;
+                    if value.len() != FMSPC_SIZE {
+                        return None;
+                    }
+                    tcb.fmspc.copy_from_slice(value);
+                    has_fmspc = true;
+                },
+                _ => (),
+            }
+        }
+        if has_tcb && has_pce_id && has_fmspc { Some(tcb) } else { None }
+    }
+}
+
+// Returns the arcs below the SGX extension OID, all of which are single
+// bytes.
+fn sgx_oid_arcs(oid: &[u8]) -> Option<&[u8]> {
+    if oid.starts_with(OID_SGX_EXTENSION) {
+        Some(&oid[OID_SGX_EXTENSION.len()..])
+    } else {
+        None
+    }
+}
+
+fn read_uint(reader: &mut DerReader) -> Option<u32> {
+    let content = reader.read(TAG_INTEGER)?;
+    if content.is_empty() || content[0] & 0x80 != 0 {
+        return None;
+    }
+    let content = if content.len() > 1 && content[0] == 0 { &content[1..] } else { content };
+    if content.len() > 3 {
+        return None;
+    }
+    Some(content.iter().fold(0, |acc, b| (acc << 8) | u32::from(*b)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sgx_types::quote::EcdsaSignatureView;
+
+    // See testdata/gen_dcap.sh. The collateral is current from 2020-06-01
+    // through 2020-06-30.
+    const QUOTE: &[u8] = include_bytes!("../testdata/dcap_quote.bin");
+    const ROOT_CA: &[u8] = include_bytes!("../testdata/dcap_root_ca.der");
+    const PCK_CRL_CHAIN: &[u8] = include_bytes!("../testdata/dcap_pck_crl_chain.pem");
+    const ROOT_CA_CRL: &[u8] = include_bytes!("../testdata/dcap_root_ca_crl.der");
+    const PCK_CRL: &[u8] = include_bytes!("../testdata/dcap_pck_crl.der");
+    const PCK_CRL_REVOKED: &[u8] = include_bytes!("../testdata/dcap_pck_crl_revoked.der");
+    const TCB_CHAIN: &[u8] = include_bytes!("../testdata/dcap_tcb_chain.pem");
+    const TCB_INFO: &[u8] = include_bytes!("../testdata/dcap_tcb_info.json");
+    const QE_IDENTITY: &[u8] = include_bytes!("../testdata/dcap_qe_identity.json");
+    const NOW: u64 = 1_592_179_200;
+
+    fn collateral() -> QuoteCollateral {
+        QuoteCollateral {
+            pck_crl_issuer_chain: PCK_CRL_CHAIN.to_vec(),
+            root_ca_crl: ROOT_CA_CRL.to_vec(),
+            pck_crl: PCK_CRL.to_vec(),
+            tcb_info_issuer_chain: TCB_CHAIN.to_vec(),
+            tcb_info: TCB_INFO.to_vec(),
+            qe_identity_issuer_chain: TCB_CHAIN.to_vec(),
+            qe_identity: QE_IDENTITY.to_vec(),
+        }
+    }
+
+    fn test_verifier(policy: AttestationPolicy) -> DcapVerifier {
+        DcapVerifier::new(policy.accept_status(QuoteStatus::SwHardeningNeeded), ROOT_CA.to_vec())
+    }
+
+    fn verify(quote: &[u8], collateral: &QuoteCollateral) -> Result<VerifiedQuote, RaTlsError> {
+        test_verifier(AttestationPolicy::new()).verify_at(quote, collateral, NOW)
+    }
+
+    fn signature_view(quote: &[u8]) -> EcdsaSignatureView<'_> {
+        match QuoteView::parse(quote).unwrap().signature() {
+            QuoteSignature::Ecdsa(sig) => sig,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn verify_fixture() {
+        let verified = verify(QUOTE, &collateral()).ok().unwrap();
+        assert_eq!(verified.tcb_status, TcbStatus::SwHardeningNeeded);
+        assert_eq!(verified.tcb_status.qv_result(), sgx_ql_qv_result_t::SGX_QL_QV_RESULT_SW_HARDENING_NEEDED);
+        assert!(verified.advisory_ids.is_empty());
+        assert_eq!(verified.fmspc, [0x00, 0x90, 0x6e, 0xa1, 0x00, 0x00]);
+        assert_eq!(verified.report_body.mr_enclave.m[0], 0x20);
+        assert_eq!(verified.report_body.mr_signer.m, [0x5a; 32]);
+        assert_eq!(verified.report_body.isv_svn, 3);
+        assert_eq!(verified.report_body.report_data.d[..], (0x40..0x80).collect::<Vec<u8>>()[..]);
+    }
+
+    #[test]
+    fn reject_by_policy() {
+        let verifier = DcapVerifier::new(AttestationPolicy::new(), ROOT_CA.to_vec());
+        assert_eq!(verifier.verify_at(QUOTE, &collateral(), NOW).err(),
+                   Some(RaTlsError::QuoteStatus(QuoteStatus::SwHardeningNeeded)));
+
+        let other = sgx_measurement_t { m: [0x5b; 32] };
+        let verifier = test_verifier(AttestationPolicy::new().allow_mr_signer(&other));
+        assert_eq!(verifier.verify_at(QUOTE, &collateral(), NOW).err(), Some(RaTlsError::MrSignerMismatch));
+    }
+
+    #[test]
+    fn reject_untrusted_root() {
+        let root = include_bytes!("../testdata/ias_root_ca.der").to_vec();
+        let verifier = DcapVerifier::new(AttestationPolicy::new(), root);
+        assert_eq!(verifier.verify_at(QUOTE, &collateral(), NOW).err(), Some(RaTlsError::BadPckCert));
+    }
+
+    #[test]
+    fn reject_revoked_pck() {
+        let mut collateral = collateral();
+        collateral.pck_crl = PCK_CRL_REVOKED.to_vec();
+        assert_eq!(verify(QUOTE, &collateral).err(), Some(RaTlsError::CertRevoked));
+    }
+
+    #[test]
+    fn reject_tampered_quote() {
+        let original: &[u8] = QUOTE;
+        let sig = signature_view(original);
+        let offset = |part: &[u8]| part.as_ptr() as usize - original.as_ptr() as usize;
+        // MRENCLAVE, the QE report, the QE authentication data and the
+        // attestation key.
+        for pos in [48 + 64, offset(sig.qe_report().as_bytes()) + 100,
+                    offset(sig.qe_auth_data()), offset(sig.attest_pub_key()) + 10].iter() {
+            let mut quote = original.to_vec();
+            quote[*pos] ^= 1;
+            assert_eq!(verify(&quote, &collateral()).err(), Some(RaTlsError::BadQuoteSignature), "{}", pos);
+        }
+
+        assert_eq!(verify(&QUOTE[..QUOTE.len() - 1], &collateral()).err(), Some(RaTlsError::BadQuote));
+        let mut epid = QUOTE.to_vec();
+        epid[0] = 2;
+        assert_eq!(verify(&epid, &collateral()).err(), Some(RaTlsError::BadQuote));
+    }
+
+    #[test]
+    fn reject_tampered_collateral() {
+        let mut collateral = collateral();
+        let tcb_info = str::from_utf8(TCB_INFO).unwrap();
+        collateral.tcb_info = tcb_info.replacen("\"tcbStatus\":\"SWHardeningNeeded\"", "\"tcbStatus\":\"UpToDate\"", 1).into_bytes();
+        assert_eq!(verify(QUOTE, &collateral).err(), Some(RaTlsError::BadCollateralSignature));
+
+        // The PCK platform CA is issued by the root but is not a TCB signer,
+        // even for a document it signed itself.
+        let mut collateral = self::collateral();
+        collateral.qe_identity_issuer_chain = PCK_CRL_CHAIN.to_vec();
+        assert_eq!(verify(QUOTE, &collateral).err(), Some(RaTlsError::BadCollateralSignature));
+        let chain = x509::pem_certificates(PCK_CRL_CHAIN).unwrap();
+        let platform = CertInfo::parse(&chain[0]).unwrap();
+        assert_eq!(platform.common_name(), Some(&b"Test SGX PCK Platform CA"[..]));
+
+        // The closing quote of the signature key is also the final one.
+        let mut collateral = self::collateral();
+        collateral.tcb_info = b"{\"tcbInfo\":,\"signature\":\"}".to_vec();
+        assert_eq!(verify(QUOTE, &collateral).err(), Some(RaTlsError::BadCollateral));
+
+        let mut collateral = self::collateral();
+        collateral.pck_crl = ROOT_CA_CRL.to_vec();
+        assert_eq!(verify(QUOTE, &collateral).err(), Some(RaTlsError::BadCollateralSignature));
+
+        let mut collateral = self::collateral();
+        collateral.tcb_info = tcb_info.replacen("{\"tcbInfo\":", "{\"tcbInfo\" :", 1).into_bytes();
+        assert_eq!(verify(QUOTE, &collateral).err(), Some(RaTlsError::BadCollateral));
+
+        let mut collateral = self::collateral();
+        collateral.root_ca_crl.truncate(10);
+        assert_eq!(verify(QUOTE, &collateral).err(), Some(RaTlsError::BadCollateral));
+    }
+
+    #[test]
+    fn collateral_expiry() {
+        let verifier = test_verifier(AttestationPolicy::new());
+        let june_1 = 1_590_969_600;
+        let july_1 = 1_593_561_600;
+        assert!(verifier.verify_at(QUOTE, &collateral(), june_1).is_ok());
+        assert_eq!(verifier.verify_at(QUOTE, &collateral(), june_1 - 1).err(), Some(RaTlsError::CollateralExpired));
+        assert_eq!(verifier.verify_at(QUOTE, &collateral(), july_1).err(), Some(RaTlsError::CollateralExpired));
+        // The certificates expire at the end of 2049.
+        assert_eq!(verifier.verify_at(QUOTE, &collateral(), 2_524_608_000).err(), Some(RaTlsError::BadPckCert));
+    }
+
+    #[test]
+    fn collateral_encodings() {
+        let mut pem = vec![0_u8; 512];
+        let len = rsgx_pem_encode("X509 CRL", PCK_CRL, &mut pem).unwrap();
+        pem.truncate(len);
+        let hex: String = ROOT_CA_CRL.iter().map(|b| format!("{:02x}", b)).collect();
+
+        let mut collateral = collateral();
+        collateral.pck_crl = pem;
+        collateral.root_ca_crl = hex.into_bytes();
+        collateral.root_ca_crl.push(0);
+        collateral.tcb_info.extend_from_slice(b"\n\0");
+        assert!(verify(QUOTE, &collateral).is_ok());
+    }
+
+    #[test]
+    fn pck_tcb() {
+        let chain = x509::pem_certificates(signature_view(QUOTE).certification_data().data()).unwrap();
+        assert_eq!(chain.len(), 3);
+        let tcb = PckTcb::parse(&CertInfo::parse(&chain[0]).unwrap()).unwrap();
+        assert_eq!(tcb.components, [14, 14, 2, 2, 255, 128, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(tcb.pce_svn, 10);
+        assert_eq!(tcb.pce_id, [0, 0]);
+        assert_eq!(tcb.fmspc, [0x00, 0x90, 0x6e, 0xa1, 0x00, 0x00]);
+        assert!(PckTcb::parse(&CertInfo::parse(&chain[1]).unwrap()).is_none());
+    }
+
+    #[test]
+    fn tcb_levels() {
+        let body = &TCB_INFO[b"{\"tcbInfo\":".len()..TCB_INFO.windows(13).rposition(|w| w == b",\"signature\":").unwrap()];
+        let info = TcbInfo::parse(body).unwrap();
+        assert_eq!(info.levels.len(), 4);
+        let status = |components: [u8; 16], pce_svn| {
+            let tcb = PckTcb { components, pce_svn, pce_id: [0; 2], fmspc: [0; 6] };
+            info.levels.iter().find(|level| level.matches(&tcb)).map(|level| level.status)
+        };
+        let mut comps = [15, 15, 2, 2, 255, 128, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert_eq!(status(comps, 11), Some(TcbStatus::UpToDate));
+        assert_eq!(status(comps, 10), Some(TcbStatus::SwHardeningNeeded));
+        comps[15] = 1;
+        assert_eq!(status(comps, 12), Some(TcbStatus::UpToDate));
+        comps[0] = 13;
+        assert_eq!(status(comps, 11), Some(TcbStatus::OutOfDate));
+        comps[6] = 0;
+        assert_eq!(status(comps, 11), Some(TcbStatus::Revoked));
+        assert_eq!(status(comps, 4), None);
+    }
+
+    #[test]
+    fn tcb_info_v3() {
+        let level = |status: &str| format!(
+            "{{\"tcb\":{{\"sgxtcbcomponents\":[{}],\"pcesvn\":11}},\"tcbDate\":\"2020-05-13T00:00:00Z\",\
+             \"tcbStatus\":\"{}\",\"advisoryIDs\":[\"INTEL-SA-00334\",\"INTEL-SA-00615\"]}}",
+            (1..=16).map(|svn| format!("{{\"svn\":{}}}", svn)).collect::<Vec<_>>().join(","), status);
+        let info = format!(
+            "{{\"id\":\"SGX\",\"version\":3,\"issueDate\":\"2020-06-01T00:00:00Z\",\"nextUpdate\":\"2020-07-01T00:00:00Z\",\
+             \"fmspc\":\"00906ea10000\",\"pceId\":\"0000\",\"tcbType\":0,\"tcbEvaluationDataNumber\":8,\
+             \"tcbLevels\":[{}]}}", level("SWHardeningNeeded"));
+        let parsed = TcbInfo::parse(info.as_bytes()).unwrap();
+        assert_eq!(parsed.levels[0].components[15], 16);
+        assert_eq!(parsed.levels[0].status, TcbStatus::SwHardeningNeeded);
+        assert_eq!(parsed.levels[0].advisory_ids, vec!["INTEL-SA-00334".to_string(), "INTEL-SA-00615".to_string()]);
+
+        assert!(TcbInfo::parse(info.replace("\"SGX\"", "\"TDX\"").as_bytes()).is_none());
+        assert!(TcbInfo::parse(info.replace("{\"svn\":16}", "{\"svn\":256}").as_bytes()).is_none());
+        assert!(TcbInfo::parse(info.replace("SWHardeningNeeded", "Unknown").as_bytes()).is_none());
+    }
+
+    #[test]
+    fn qe_identity() {
+        let body = &QE_IDENTITY[b"{\"enclaveIdentity\":".len()..QE_IDENTITY.windows(13).rposition(|w| w == b",\"signature\":").unwrap()];
+        let qe_report = signature_view(QUOTE).qe_report();
+
+        let mut identity = QeIdentity::parse(body).unwrap();
+        assert_eq!(identity.check(&qe_report), Ok(TcbStatus::UpToDate));
+        identity.levels = vec![(6, TcbStatus::UpToDate), (5, TcbStatus::OutOfDate)];
+        assert_eq!(identity.check(&qe_report), Ok(TcbStatus::OutOfDate));
+        identity.levels = vec![(6, TcbStatus::UpToDate)];
+        assert_eq!(identity.check(&qe_report), Err(RaTlsError::UnsupportedTcb));
+
+        let mut identity = QeIdentity::parse(body).unwrap();
+        identity.attributes_mask[0] = 0xff;
+        assert_eq!(identity.check(&qe_report), Err(RaTlsError::QeIdentityMismatch));
+        let mut identity = QeIdentity::parse(body).unwrap();
+        identity.mr_signer[0] ^= 1;
+        assert_eq!(identity.check(&qe_report), Err(RaTlsError::QeIdentityMismatch));
+        let mut identity = QeIdentity::parse(body).unwrap();
+        identity.isv_prod_id = 2;
+        assert_eq!(identity.check(&qe_report), Err(RaTlsError::QeIdentityMismatch));
+    }
+
+    #[test]
+    fn tcb_status() {
+        assert_eq!(TcbStatus::parse("ConfigurationAndSWHardeningNeeded"), Some(TcbStatus::ConfigurationAndSwHardeningNeeded));
+        assert_eq!(TcbStatus::OutOfDateConfigurationNeeded.as_str(), "OutOfDateConfigurationNeeded");
+        assert!(TcbStatus::parse("UpToDate ").is_none());
+        assert_eq!(TcbStatus::UpToDate.converge(TcbStatus::OutOfDate), TcbStatus::OutOfDate);
+        assert_eq!(TcbStatus::ConfigurationNeeded.converge(TcbStatus::OutOfDate), TcbStatus::OutOfDateConfigurationNeeded);
+        assert_eq!(TcbStatus::OutOfDate.converge(TcbStatus::UpToDate), TcbStatus::OutOfDate);
+        assert_eq!(TcbStatus::UpToDate.converge(TcbStatus::Revoked), TcbStatus::Revoked);
+        assert_eq!(TcbStatus::OutOfDateConfigurationNeeded.quote_statuses(),
+                   &[QuoteStatus::GroupOutOfDate, QuoteStatus::ConfigurationNeeded]);
+    }
+}
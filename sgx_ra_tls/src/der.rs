@@ -0,0 +1,156 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! Just enough DER to build and take apart RA-TLS certificates.
+//!
+//! The reader accepts definite, minimally encoded lengths only. Unlike the
+//! codec in sgx_tcrypto, the writer builds into a `Vec`, since certificates
+//! have no small fixed upper bound.
+//!
+
+use std::prelude::v1::*;
+
+pub(crate) const TAG_BOOLEAN: u8 = 0x01;
+pub(crate) const TAG_INTEGER: u8 = 0x02;
+pub(crate) const TAG_BIT_STRING: u8 = 0x03;
+pub(crate) const TAG_OCTET_STRING: u8 = 0x04;
+pub(crate) const TAG_OID: u8 = 0x06;
+pub(crate) const TAG_UTF8_STRING: u8 = 0x0c;
+pub(crate) const TAG_UTC_TIME: u8 = 0x17;
+pub(crate) const TAG_GENERALIZED_TIME: u8 = 0x18;
+pub(crate) const TAG_SEQUENCE: u8 = 0x30;
+pub(crate) const TAG_SET: u8 = 0x31;
+pub(crate) const TAG_CONTEXT_0: u8 = 0xa0;
+pub(crate) const TAG_CONTEXT_3: u8 = 0xa3;
+
+#[derive(Clone, Copy)]
+pub(crate) struct DerReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> DerReader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> DerReader<'a> {
+        DerReader { data }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub(crate) fn peek_tag(&self) -> Option<u8> {
+        self.data.first().copied()
+    }
+
+    ///
+    /// Reads any TLV and returns its tag, content and full encoding.
+    ///
+    pub(crate) fn read_any(&mut self) -> Option<(u8, &'a [u8], &'a [u8])> {
+        if self.data.len() < 2 {
+            return None;
+        }
+        let first = self.data[1] as usize;
+        let (len, header) = if first < 0x80 {
+            (first, 2)
+        } else {
+            let n = first & 0x7f;
+            if n == 0 || n > 3 || self.data.len() < 2 + n || self.data[2] == 0 {
+                return None;
+            }
+            let len = self.data[2..2 + n].iter().fold(0_usize, |acc, b| (acc << 8) | *b as usize);
+            if len < 0x80 {
+                return None;
+            }
+            (len, 2 + n)
+        };
+        if self.data.len() - header < len {
+            return None;
+        }
+        let tag = self.data[0];
+        let (tlv, rest) = self.data.split_at(header + len);
+        self.data = rest;
+        Some((tag, &tlv[header..], tlv))
+    }
+
+    ///
+    /// Reads a TLV with the expected tag and returns its content.
+    ///
+    pub(crate) fn read(&mut self, tag: u8) -> Option<&'a [u8]> {
+        self.read_with_encoding(tag).map(|(content, _)| content)
+    }
+
+    ///
+    /// Reads a TLV with the expected tag and returns its content and full encoding.
+    ///
+    pub(crate) fn read_with_encoding(&mut self, tag: u8) -> Option<(&'a [u8], &'a [u8])> {
+        if self.peek_tag() != Some(tag) {
+            return None;
+        }
+        self.read_any().map(|(_, content, tlv)| (content, tlv))
+    }
+
+    pub(crate) fn read_sequence(&mut self) -> Option<DerReader<'a>> {
+        self.read(TAG_SEQUENCE).map(DerReader::new)
+    }
+
+    ///
+    /// Skips a TLV if it has the given tag.
+    ///
+    pub(crate) fn skip_optional(&mut self, tag: u8) -> Option<()> {
+        if self.peek_tag() == Some(tag) {
+            self.read_any()?;
+        }
+        Some(())
+    }
+
+    pub(crate) fn expect_empty(&self) -> Option<()> {
+        if self.data.is_empty() { Some(()) } else { None }
+    }
+}
+
+///
+/// Appends a TLV with the given content.
+///
+pub(crate) fn write_tlv(out: &mut Vec<u8>, tag: u8, content: &[u8]) {
+    out.push(tag);
+    let len = content.len();
+    match len {
+        0..=0x7f => out.push(len as u8),
+        0x80..=0xff => out.extend_from_slice(&[0x81, len as u8]),
+        0x100..=0xffff => out.extend_from_slice(&[0x82, (len >> 8) as u8, len as u8]),
+        _ => out.extend_from_slice(&[0x83, (len >> 16) as u8, (len >> 8) as u8, len as u8]),
+    }
+    out.extend_from_slice(content);
+}
+
+///
+/// Builds the content with `f` and appends it as a TLV.
+///
+pub(crate) fn write_nested<F>(out: &mut Vec<u8>, tag: u8, f: F)
+    where F: FnOnce(&mut Vec<u8>) {
+    let mut content = Vec::new();
+    f(&mut content);
+    write_tlv(out, tag, &content);
+}
+
+pub(crate) fn write_bit_string(out: &mut Vec<u8>, content: &[u8]) {
+    write_nested(out, TAG_BIT_STRING, |out| {
+        out.push(0);
+        out.extend_from_slice(content);
+    });
+}
@@ -0,0 +1,140 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+use std::fmt;
+use sgx_types::sgx_status_t;
+use crate::policy::QuoteStatus;
+
+/// The error type for RA-TLS certificate generation and verification, and
+/// for IAS report and DCAP quote verification.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RaTlsError {
+    /// The certificate is malformed, has no P-256 key or no attestation
+    /// extension.
+    BadCertificate,
+    /// The attestation extension does not hold report, signature and
+    /// signing certificate.
+    BadEvidence,
+    /// The report signing certificate does not chain to the trusted root.
+    BadSigningCert,
+    /// The report signature does not verify with the signing certificate.
+    BadReportSignature,
+    /// The attestation report or its quote body is malformed.
+    BadReport,
+    /// The report is older than the policy allows.
+    StaleReport,
+    /// The report does not carry the expected nonce.
+    NonceMismatch,
+    /// IAS answered with an HTTP status other than 200.
+    IasStatus(u16),
+    /// The nonce for an IAS report request is longer than 32 characters or
+    /// not alphanumeric.
+    BadNonce,
+    /// The IAS response is not a complete HTTP response with the report
+    /// signature headers.
+    BadIasResponse,
+    /// The DCAP quote is malformed, not an ECDSA quote, or does not carry a
+    /// PCK certificate chain.
+    BadQuote,
+    /// The PCK certificate is malformed, expired or does not chain to the
+    /// trusted root.
+    BadPckCert,
+    /// The QE report, attestation key or quote signature does not verify.
+    BadQuoteSignature,
+    /// The DCAP collateral is malformed.
+    BadCollateral,
+    /// A CRL, TCB info or QE identity is not signed under the trusted root.
+    BadCollateralSignature,
+    /// A CRL, TCB info or QE identity has passed its next update.
+    CollateralExpired,
+    /// A certificate of the PCK or collateral signing chain is revoked.
+    CertRevoked,
+    /// The TCB info is for another FMSPC or PCE.
+    TcbInfoMismatch,
+    /// The QE report does not match the QE identity.
+    QeIdentityMismatch,
+    /// No TCB level of the collateral matches the platform or the QE.
+    UnsupportedTcb,
+    /// The platform or QE TCB is revoked.
+    TcbRevoked,
+    /// The quote status is not accepted by the policy.
+    QuoteStatus(QuoteStatus),
+    /// The report data does not bind the certificate's public key.
+    KeyMismatch,
+    MrEnclaveMismatch,
+    MrSignerMismatch,
+    IsvProdIdMismatch,
+    IsvSvnTooLow,
+    /// The enclave runs in debug mode and the policy forbids it.
+    DebugEnclave,
+    /// A crypto primitive failed while building a certificate.
+    Crypto(sgx_status_t),
+}
+
+impl fmt::Display for RaTlsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RaTlsError::BadCertificate => write!(f, "malformed RA-TLS certificate"),
+            RaTlsError::BadEvidence => write!(f, "malformed attestation evidence"),
+            RaTlsError::BadSigningCert => write!(f, "untrusted report signing certificate"),
+            RaTlsError::BadReportSignature => write!(f, "invalid attestation report signature"),
+            RaTlsError::BadReport => write!(f, "malformed attestation report"),
+            RaTlsError::StaleReport => write!(f, "attestation report is too old"),
+            RaTlsError::NonceMismatch => write!(f, "attestation report nonce does not match"),
+            RaTlsError::IasStatus(status) => write!(f, "IAS returned HTTP status {}", status),
+            RaTlsError::BadNonce => write!(f, "invalid IAS request nonce"),
+            RaTlsError::BadIasResponse => write!(f, "malformed IAS response"),
+            RaTlsError::BadQuote => write!(f, "malformed DCAP quote"),
+            RaTlsError::BadPckCert => write!(f, "untrusted PCK certificate"),
+            RaTlsError::BadQuoteSignature => write!(f, "invalid quote signature"),
+            RaTlsError::BadCollateral => write!(f, "malformed DCAP collateral"),
+            RaTlsError::BadCollateralSignature => write!(f, "invalid DCAP collateral signature"),
+            RaTlsError::CollateralExpired => write!(f, "DCAP collateral has expired"),
+            RaTlsError::CertRevoked => write!(f, "certificate is revoked"),
+            RaTlsError::TcbInfoMismatch => write!(f, "TCB info does not match the PCK certificate"),
+            RaTlsError::QeIdentityMismatch => write!(f, "QE report does not match the QE identity"),
+            RaTlsError::UnsupportedTcb => write!(f, "no matching TCB level"),
+            RaTlsError::TcbRevoked => write!(f, "TCB is revoked"),
+            RaTlsError::QuoteStatus(status) => write!(f, "quote status {} is not accepted", status.as_str()),
+            RaTlsError::KeyMismatch => write!(f, "report data does not match the certificate key"),
+            RaTlsError::MrEnclaveMismatch => write!(f, "MRENCLAVE is not allowed"),
+            RaTlsError::MrSignerMismatch => write!(f, "MRSIGNER is not allowed"),
+            RaTlsError::IsvProdIdMismatch => write!(f, "ISVPRODID is not allowed"),
+            RaTlsError::IsvSvnTooLow => write!(f, "ISVSVN is below the minimum"),
+            RaTlsError::DebugEnclave => write!(f, "debug enclaves are not allowed"),
+            RaTlsError::Crypto(status) => write!(f, "crypto error: {}", status),
+        }
+    }
+}
+
+impl std::error::Error for RaTlsError {}
+
+impl From<RaTlsError> for sgx_status_t {
+    fn from(err: RaTlsError) -> sgx_status_t {
+        match err {
+            RaTlsError::Crypto(status) => status,
+            RaTlsError::BadReportSignature |
+            RaTlsError::BadSigningCert |
+            RaTlsError::BadPckCert |
+            RaTlsError::BadQuoteSignature |
+            RaTlsError::BadCollateralSignature => sgx_status_t::SGX_ERROR_INVALID_SIGNATURE,
+            RaTlsError::QuoteStatus(status) if status.needs_update() => sgx_status_t::SGX_ERROR_UPDATE_NEEDED,
+            _ => sgx_status_t::SGX_ERROR_UNEXPECTED,
+        }
+    }
+}
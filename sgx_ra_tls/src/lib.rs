@@ -0,0 +1,72 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//! # Remote Attestation TLS
+//!
+//! This crate binds TLS to SGX remote attestation. An enclave generates an
+//! ECDSA key pair, puts the public key into the report data of its quote,
+//! has IAS verify the quote, and embeds the signed attestation report into a
+//! self-signed X.509 certificate. A peer that checks the certificate with
+//! `RaTlsVerifier` knows it is talking to an enclave satisfying its
+//! `AttestationPolicy`.
+//!
+//! The `ias` and `dcap` modules verify IAS attestation reports and DCAP
+//! quotes on their own, for protocols other than RA-TLS.
+//!
+//! With the `mesalock_sgx` feature the crate is built for enclaves on top of
+//! sgx_tstd and sgx_tcrypto, otherwise for untrusted applications on top of
+//! std and sgx_ucrypto. The source codes of sgx_tra_tls and sgx_ra_tls are
+//! the same.
+//!
+
+#![cfg_attr(all(feature = "mesalock_sgx", not(target_env = "sgx")), no_std)]
+#![cfg_attr(target_env = "sgx", feature(rustc_private))]
+
+#[cfg(all(feature = "mesalock_sgx", not(target_env = "sgx")))]
+#[macro_use]
+extern crate sgx_tstd as std;
+
+extern crate sgx_types;
+#[cfg(any(feature = "mesalock_sgx", target_env = "sgx"))]
+extern crate sgx_tcrypto as crypto;
+#[cfg(not(any(feature = "mesalock_sgx", target_env = "sgx")))]
+extern crate sgx_ucrypto as crypto;
+
+extern crate base64;
+extern crate rustls;
+extern crate serde_json;
+extern crate webpki;
+
+mod der;
+mod time;
+mod x509;
+
+mod error;
+pub use self::error::*;
+
+mod policy;
+pub use self::policy::*;
+
+pub mod ias;
+pub mod dcap;
+
+mod cert;
+pub use self::cert::*;
+
+mod verify;
+pub use self::verify::*;
@@ -0,0 +1,159 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! Conversions between Unix time and the civil UTC dates used by IAS reports
+//! and X.509 validity periods.
+//!
+
+use std::prelude::v1::*;
+use std::time::{SystemTime, UNIX_EPOCH};
+#[cfg(all(feature = "mesalock_sgx", not(target_env = "sgx")))]
+use std::untrusted::time::SystemTimeEx;
+use crate::der::{TAG_GENERALIZED_TIME, TAG_UTC_TIME};
+
+const SECS_PER_DAY: u64 = 86_400;
+
+///
+/// The current time in seconds since the Unix epoch.
+///
+/// Inside an enclave this is the time reported by the host.
+///
+pub(crate) fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// Howard Hinnant's days_from_civil and civil_from_days, for the proleptic
+// Gregorian calendar.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = (if year >= 0 { year } else { year - 399 }) / 400;
+    let yoe = year - era * 400;
+    let month = i64::from(month);
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = (if days >= 0 { days } else { days - 146_096 }) / 146_097;
+    let doe = days - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = yoe + era * 400 + (if month <= 2 { 1 } else { 0 });
+    (year, month, day)
+}
+
+fn is_leap(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn parse_digits(s: &[u8]) -> Option<u32> {
+    if s.is_empty() || !s.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    Some(s.iter().fold(0, |acc, b| acc * 10 + u32::from(b - b'0')))
+}
+
+// Validates a UTC date and time and converts it to seconds since the epoch.
+fn civil_to_secs(year: i64, month: u32, day: u32, hour: u32, min: u32, sec: u32) -> Option<u64> {
+    if year < 1970 || month == 0 || month > 12 || day == 0 || day > days_in_month(year, month)
+        || hour > 23 || min > 59 || sec > 59 {
+        return None;
+    }
+    let days = days_from_civil(year, month, day) as u64;
+    Some(days * SECS_PER_DAY + u64::from(hour) * 3600 + u64::from(min) * 60 + u64::from(sec))
+}
+
+///
+/// Parses an IAS timestamp, `YYYY-MM-DDThh:mm:ss` with optional fractional
+/// seconds, in UTC. Fractional seconds are truncated.
+///
+pub(crate) fn parse_ias_timestamp(s: &str) -> Option<u64> {
+    let s = s.as_bytes();
+    let s = if s.last() == Some(&b'Z') { &s[..s.len() - 1] } else { s };
+    if s.len() < 19 || s[4] != b'-' || s[7] != b'-' || s[10] != b'T' || s[13] != b':' || s[16] != b':' {
+        return None;
+    }
+    if s.len() > 19 && (s[19] != b'.' || parse_digits(&s[20..]).is_none()) {
+        return None;
+    }
+    let year = i64::from(parse_digits(&s[0..4])?);
+    let month = parse_digits(&s[5..7])?;
+    let day = parse_digits(&s[8..10])?;
+    let hour = parse_digits(&s[11..13])?;
+    let min = parse_digits(&s[14..16])?;
+    let sec = parse_digits(&s[17..19])?;
+    civil_to_secs(year, month, day, hour, min, sec)
+}
+
+///
+/// Parses an X.509 validity time: UTCTime `YYMMDDhhmmssZ`, with years 50-99
+/// in the 1900s, or GeneralizedTime `YYYYMMDDhhmmssZ`.
+///
+pub(crate) fn parse_x509_time(tag: u8, content: &[u8]) -> Option<u64> {
+    let (year, rest) = match (tag, content.len()) {
+        (TAG_UTC_TIME, 13) => {
+            let yy = i64::from(parse_digits(&content[..2])?);
+            (if yy >= 50 { 1900 + yy } else { 2000 + yy }, &content[2..])
+        },
+        (TAG_GENERALIZED_TIME, 15) => (i64::from(parse_digits(&content[..4])?), &content[4..]),
+        _ => return None,
+    };
+    if rest[10] != b'Z' {
+        return None;
+    }
+    let month = parse_digits(&rest[0..2])?;
+    let day = parse_digits(&rest[2..4])?;
+    let hour = parse_digits(&rest[4..6])?;
+    let min = parse_digits(&rest[6..8])?;
+    let sec = parse_digits(&rest[8..10])?;
+    civil_to_secs(year, month, day, hour, min, sec)
+}
+
+///
+/// Encodes an X.509 validity time: UTCTime through 2049, GeneralizedTime after.
+///
+pub(crate) fn x509_time(secs: u64) -> (u8, Vec<u8>) {
+    let (year, month, day) = civil_from_days((secs / SECS_PER_DAY) as i64);
+    let rem = secs % SECS_PER_DAY;
+    let (hour, min, sec) = (rem / 3600, rem % 3600 / 60, rem % 60);
+    if year < 2050 {
+        let s = format!("{:02}{:02}{:02}{:02}{:02}{:02}Z", year % 100, month, day, hour, min, sec);
+        (TAG_UTC_TIME, s.into_bytes())
+    } else {
+        let s = format!("{:04}{:02}{:02}{:02}{:02}{:02}Z", year, month, day, hour, min, sec);
+        (TAG_GENERALIZED_TIME, s.into_bytes())
+    }
+}
@@ -0,0 +1,259 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! Verification of RA-TLS certificates, standalone and as rustls verifiers.
+//!
+
+use std::prelude::v1::*;
+use sgx_types::*;
+use crypto::*;
+use crate::cert::{extract_evidence, key_report_data, AttestationEvidence};
+use crate::error::RaTlsError;
+use crate::ias::{self, IasReport};
+use crate::policy::{AttestationPolicy, QuoteStatus};
+use crate::time;
+
+/// The IAS report signing root CA, PEM encoded.
+pub const IAS_REPORT_CA: &[u8] = include_bytes!("../AttestationReportSigningCACert.pem");
+
+/// The attested facts of a certificate that passed verification.
+#[derive(Clone)]
+pub struct VerifiedReport {
+    pub quote_status: QuoteStatus,
+    /// The IAS timestamp, in seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub report_body: sgx_report_body_t,
+    /// The full report, with platform info blob and advisories.
+    pub ias_report: IasReport,
+}
+
+/// Verifies RA-TLS certificates against an `AttestationPolicy`.
+///
+/// A certificate is accepted when its report signing certificate chains to
+/// the IAS root, the report signature is valid, the report binds the
+/// certificate key, and the quote status and enclave identity satisfy the
+/// policy.
+///
+/// The verifier can be handed to rustls directly, as the server certificate
+/// verifier of a client or the client certificate verifier of a server:
+///
+/// ```ignore
+/// let verifier = Arc::new(RaTlsVerifier::new(policy));
+/// let mut client_cfg = rustls::ClientConfig::new();
+/// client_cfg.dangerous().set_certificate_verifier(verifier.clone());
+/// let server_cfg = rustls::ServerConfig::new(verifier);
+/// ```
+///
+/// Inside an enclave the current time comes from the host, so an untrusted
+/// host can make an expired signing certificate or a stale report look
+/// current.
+pub struct RaTlsVerifier {
+    policy: AttestationPolicy,
+    root_ca: Vec<u8>,
+}
+
+impl RaTlsVerifier {
+    /// Creates a verifier that trusts the pinned IAS report signing root.
+    pub fn new(policy: AttestationPolicy) -> RaTlsVerifier {
+        let mut root_ca = vec![0_u8; IAS_REPORT_CA.len()];
+        // The pinned PEM is well formed.
+        let len = rsgx_pem_decode("CERTIFICATE", IAS_REPORT_CA, &mut root_ca).unwrap();
+        root_ca.truncate(len);
+        RaTlsVerifier { policy, root_ca }
+    }
+
+    /// Creates a verifier that trusts another root, given in DER.
+    pub fn with_root_ca(policy: AttestationPolicy, root_ca: Vec<u8>) -> RaTlsVerifier {
+        RaTlsVerifier { policy, root_ca }
+    }
+
+    pub fn policy(&self) -> &AttestationPolicy {
+        &self.policy
+    }
+
+    /// Verifies an RA-TLS certificate at the current time.
+    pub fn verify_cert(&self, cert_der: &[u8]) -> Result<VerifiedReport, RaTlsError> {
+        self.verify_cert_at(cert_der, time::now_secs())
+    }
+
+    /// Verifies an RA-TLS certificate at `now`, in seconds since the Unix epoch.
+    pub fn verify_cert_at(&self, cert_der: &[u8], now: u64) -> Result<VerifiedReport, RaTlsError> {
+        let (public, evidence) = extract_evidence(cert_der)?;
+        self.verify_evidence_at(&evidence, &public, now)
+    }
+
+    /// Verifies attestation evidence for `public` at `now`, in seconds since
+    /// the Unix epoch.
+    pub fn verify_evidence_at(&self,
+                              evidence: &AttestationEvidence,
+                              public: &sgx_ec256_public_t,
+                              now: u64) -> Result<VerifiedReport, RaTlsError> {
+        ias::verify_report_signature(evidence, &self.root_ca, now)?;
+        let report = IasReport::parse(&evidence.report)?;
+        if let Some(max_age) = self.policy.max_report_age() {
+            report.check_freshness(now, max_age)?;
+        }
+
+        let report_body = {
+            let quote = report.quote();
+            let body = quote.report_body();
+            if body.report_data() != &key_report_data(public).d[..] {
+                return Err(RaTlsError::KeyMismatch);
+            }
+            self.policy.check(report.quote_status(), &body)?;
+            body.to_report_body()
+        };
+
+        Ok(VerifiedReport {
+            quote_status: report.quote_status(),
+            timestamp: report.timestamp(),
+            report_body,
+            ias_report: report,
+        })
+    }
+
+    fn verify_presented(&self, certs: &[rustls::Certificate]) -> Result<(), rustls::TLSError> {
+        let cert = certs.first().ok_or(rustls::TLSError::NoCertificatesPresented)?;
+        self.verify_cert(&cert.0)
+            .map(|_| ())
+            .map_err(|e| rustls::TLSError::General(e.to_string()))
+    }
+}
+
+impl rustls::ServerCertVerifier for RaTlsVerifier {
+    fn verify_server_cert(&self,
+                          _roots: &rustls::RootCertStore,
+                          presented_certs: &[rustls::Certificate],
+                          _dns_name: webpki::DNSNameRef,
+                          _ocsp_response: &[u8]) -> Result<rustls::ServerCertVerified, rustls::TLSError> {
+        self.verify_presented(presented_certs).map(|_| rustls::ServerCertVerified::assertion())
+    }
+}
+
+impl rustls::ClientCertVerifier for RaTlsVerifier {
+    fn client_auth_root_subjects(&self) -> rustls::DistinguishedNames {
+        rustls::DistinguishedNames::new()
+    }
+
+    fn verify_client_cert(&self, presented_certs: &[rustls::Certificate])
+                          -> Result<rustls::ClientCertVerified, rustls::TLSError> {
+        self.verify_presented(presented_certs).map(|_| rustls::ClientCertVerified::assertion())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // See testdata/gen.sh. The report was issued at 2020-06-01T12:00:00.
+    const TEST_ROOT_CA: &[u8] = include_bytes!("../testdata/ias_root_ca.der");
+    const RATLS_CERT: &[u8] = include_bytes!("../testdata/ratls_cert.der");
+    const ISSUED_AT: u64 = 1_591_012_800;
+    const NOW: u64 = ISSUED_AT + 3600;
+
+    fn mr_enclave() -> sgx_measurement_t {
+        let mut m = sgx_measurement_t::default();
+        for (i, b) in m.m.iter_mut().enumerate() {
+            *b = 0x20 + i as u8;
+        }
+        m
+    }
+
+    fn test_verifier(policy: AttestationPolicy) -> RaTlsVerifier {
+        RaTlsVerifier::with_root_ca(policy.accept_status(QuoteStatus::SwHardeningNeeded), TEST_ROOT_CA.to_vec())
+    }
+
+    #[test]
+    fn verify_fixture() {
+        let report = test_verifier(AttestationPolicy::new()).verify_cert_at(RATLS_CERT, NOW).unwrap();
+        assert_eq!(report.quote_status, QuoteStatus::SwHardeningNeeded);
+        assert_eq!(report.timestamp, ISSUED_AT);
+        assert_eq!(report.report_body.mr_enclave.m, mr_enclave().m);
+        assert_eq!(report.report_body.mr_signer.m, [0x5a; 32]);
+        assert_eq!(report.report_body.isv_prod_id, 1);
+        assert_eq!(report.report_body.isv_svn, 3);
+    }
+
+    #[test]
+    fn reject_status() {
+        let verifier = RaTlsVerifier::with_root_ca(AttestationPolicy::new(), TEST_ROOT_CA.to_vec());
+        assert_eq!(verifier.verify_cert_at(RATLS_CERT, NOW).err(),
+                   Some(RaTlsError::QuoteStatus(QuoteStatus::SwHardeningNeeded)));
+    }
+
+    #[test]
+    fn reject_untrusted_root() {
+        let verifier = RaTlsVerifier::new(AttestationPolicy::new().accept_status(QuoteStatus::SwHardeningNeeded));
+        assert_eq!(verifier.verify_cert_at(RATLS_CERT, NOW).err(), Some(RaTlsError::BadSigningCert));
+        // The signing certificate expires at the end of 2049.
+        assert_eq!(test_verifier(AttestationPolicy::new()).verify_cert_at(RATLS_CERT, 2_524_608_000).err(),
+                   Some(RaTlsError::BadSigningCert));
+    }
+
+    #[test]
+    fn reject_tampered_evidence() {
+        let verifier = test_verifier(AttestationPolicy::new());
+        let (public, evidence) = extract_evidence(RATLS_CERT).unwrap();
+
+        let mut tampered = evidence.clone();
+        let pos = tampered.report.len() - 2;
+        tampered.report[pos] ^= 1;
+        assert_eq!(verifier.verify_evidence_at(&tampered, &public, NOW).err(), Some(RaTlsError::BadReportSignature));
+
+        let mut tampered = evidence.clone();
+        tampered.signature[0] ^= 1;
+        assert_eq!(verifier.verify_evidence_at(&tampered, &public, NOW).err(), Some(RaTlsError::BadReportSignature));
+
+        let mut other = public;
+        other.gx[0] ^= 1;
+        assert_eq!(verifier.verify_evidence_at(&evidence, &other, NOW).err(), Some(RaTlsError::KeyMismatch));
+    }
+
+    #[test]
+    fn report_age() {
+        let verifier = test_verifier(AttestationPolicy::new().with_max_report_age(7200));
+        assert!(verifier.verify_cert_at(RATLS_CERT, NOW).is_ok());
+        assert_eq!(verifier.verify_cert_at(RATLS_CERT, ISSUED_AT + 7201).err(), Some(RaTlsError::StaleReport));
+    }
+
+    #[test]
+    fn enclave_identity() {
+        let other = sgx_measurement_t { m: [0x5a; 32] };
+        let cases = vec![
+            (AttestationPolicy::new().allow_mr_enclave(&mr_enclave()), Ok(())),
+            (AttestationPolicy::new().allow_mr_enclave(&other), Err(RaTlsError::MrEnclaveMismatch)),
+            (AttestationPolicy::new().allow_mr_signer(&other), Ok(())),
+            (AttestationPolicy::new().allow_mr_signer(&mr_enclave()), Err(RaTlsError::MrSignerMismatch)),
+            (AttestationPolicy::new().with_isv_prod_id(2), Err(RaTlsError::IsvProdIdMismatch)),
+            (AttestationPolicy::new().with_min_isv_svn(3), Ok(())),
+            (AttestationPolicy::new().with_min_isv_svn(4), Err(RaTlsError::IsvSvnTooLow)),
+        ];
+        for (policy, expected) in cases {
+            assert_eq!(test_verifier(policy).verify_cert_at(RATLS_CERT, NOW).map(|_| ()), expected);
+        }
+    }
+
+    #[test]
+    fn parse_timestamp() {
+        assert_eq!(time::parse_ias_timestamp("2020-06-01T12:00:00.123456"), Some(ISSUED_AT));
+        assert_eq!(time::parse_ias_timestamp("2020-06-01T12:00:00Z"), Some(ISSUED_AT));
+        assert_eq!(time::parse_ias_timestamp("2020-02-30T12:00:00"), None);
+        assert_eq!(time::x509_time(ISSUED_AT), (crate::der::TAG_UTC_TIME, b"200601120000Z".to_vec()));
+    }
+}
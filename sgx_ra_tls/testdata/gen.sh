@@ -0,0 +1,108 @@
+#!/bin/bash
+#
+# Regenerates the RA-TLS test fixtures. The IAS root and report signing
+# certificate are test stand-ins; nothing here is signed by Intel.
+#
+# ias_root_ca.der    test IAS report signing root
+# ratls_cert.der     RA-TLS certificate whose report is signed under it, with
+#                    quote status SW_HARDENING_NEEDED and timestamp
+#                    2020-06-01T12:00:00.123456
+# ias_response.http  report API response for the same quote with status
+#                    GROUP_OUT_OF_DATE, a platform info blob and a nonce,
+#                    timestamp 2020-06-01T12:30:00.000000
+#
+set -e
+cd "$(dirname "$0")"
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+
+validity="-not_before 20200101000000Z -not_after 20491231235959Z"
+
+openssl req -x509 -newkey rsa:3072 -nodes -sha256 -keyout "$tmp/root.key" -out "$tmp/root.pem" \
+    -subj "/CN=Test IAS Report Signing CA" $validity \
+    -addext "basicConstraints=critical,CA:TRUE" -addext "keyUsage=critical,keyCertSign,cRLSign"
+openssl req -new -newkey rsa:2048 -nodes -keyout "$tmp/sign.key" -out "$tmp/sign.csr" \
+    -subj "/CN=Test IAS Report Signing"
+printf "basicConstraints=critical,CA:FALSE\nkeyUsage=critical,digitalSignature,nonRepudiation\n" > "$tmp/sign.ext"
+openssl x509 -req -sha256 -in "$tmp/sign.csr" -CA "$tmp/root.pem" -CAkey "$tmp/root.key" -set_serial 2 \
+    $validity -extfile "$tmp/sign.ext" -outform DER -out "$tmp/sign.der"
+openssl x509 -in "$tmp/root.pem" -outform DER -out ias_root_ca.der
+
+openssl ecparam -name prime256v1 -genkey -noout -out "$tmp/ec.key"
+openssl ec -in "$tmp/ec.key" -pubout -outform DER -out "$tmp/ec_pub.der" 2>/dev/null
+
+python3 - "$tmp" <<'PY'
+import base64, json, struct, sys
+tmp = sys.argv[1]
+point = open(tmp + "/ec_pub.der", "rb").read()[-64:]
+body = bytearray(384)
+body[48:56] = struct.pack("<Q", 0x05)          # attributes.flags: INIT | MODE64BIT
+body[56:64] = struct.pack("<Q", 0x03)          # attributes.xfrm
+body[64:96] = bytes(range(0x20, 0x40))         # mr_enclave
+body[128:160] = bytes([0x5a] * 32)             # mr_signer
+body[256:258] = struct.pack("<H", 1)           # isv_prod_id
+body[258:260] = struct.pack("<H", 3)           # isv_svn
+body[320:384] = point                          # report_data
+header = bytearray(48)
+header[0:2] = struct.pack("<H", 2)             # version
+header[2:4] = struct.pack("<H", 1)             # linkable
+header[4:8] = bytes([0x0b, 0x0c, 0, 0])        # epid group id
+report = {
+    "id": "131402127283658917512346497442431582233",
+    "timestamp": "2020-06-01T12:00:00.123456",
+    "version": 4,
+    "epidPseudonym": base64.b64encode(bytes(64)).decode(),
+    "advisoryURL": "https://security-center.intel.com",
+    "advisoryIDs": ["INTEL-SA-00334"],
+    "isvEnclaveQuoteStatus": "SW_HARDENING_NEEDED",
+    "isvEnclaveQuoteBody": base64.b64encode(bytes(header + body)).decode(),
+}
+open(tmp + "/report.json", "w").write(json.dumps(report, separators=(",", ":")))
+
+pib = bytes([0x15, 0x02, 0x00, 0x65])          # TLV header
+pib += bytes([0x04])                           # QE_EPID_GROUP_OUT_OF_DATE
+pib += bytes([0x00, 0x03])                     # CPUSVN and QE ISVSVN out of date
+pib += bytes([0x00, 0x00])
+pib += bytes([0x0f, 0x0f, 0x02, 0x02, 0xff, 0x80, 0x01] + [0] * 9 + [0x00, 0x0b])
+pib += bytes([0x00, 0x0b])
+pib += bytes([0x00, 0x00, 0x00, 0x02])
+pib += bytes([0x00, 0x00, 0x00, 0x00])         # xeid
+pib += bytes([0x00, 0x00, 0x0b, 0x0c])         # gid
+pib += bytes(range(0x40, 0x80))                # signature
+assert len(pib) == 105
+report.update({
+    "timestamp": "2020-06-01T12:30:00.000000",
+    "isvEnclaveQuoteStatus": "GROUP_OUT_OF_DATE",
+    "platformInfoBlob": pib.hex().upper(),
+    "advisoryIDs": ["INTEL-SA-00219", "INTEL-SA-00334"],
+    "nonce": "0123456789abcdef",
+})
+open(tmp + "/report2.json", "w").write(json.dumps(report, separators=(",", ":")))
+PY
+
+openssl dgst -sha256 -sign "$tmp/sign.key" -out "$tmp/report.sig" "$tmp/report.json"
+openssl dgst -sha256 -sign "$tmp/sign.key" -out "$tmp/report2.sig" "$tmp/report2.json"
+openssl x509 -inform DER -in "$tmp/sign.der" -out "$tmp/sign.pem"
+python3 - "$tmp" <<'PY'
+import base64, sys, urllib.parse
+tmp = sys.argv[1]
+body = open(tmp + "/report2.json", "rb").read()
+chain = open(tmp + "/sign.pem").read() + open(tmp + "/root.pem").read()
+headers = [
+    "HTTP/1.1 200 OK",
+    "Content-Length: %d" % len(body),
+    "Content-Type: application/json",
+    "Request-ID: 8b4e7c1a92f34c7d8e0f5b6a3c2d1e0f",
+    "X-IASReport-Signature: " + base64.b64encode(open(tmp + "/report2.sig", "rb").read()).decode(),
+    "X-IASReport-Signing-Certificate: " + urllib.parse.quote(chain, safe=""),
+    "Date: Mon, 01 Jun 2020 12:30:00 GMT",
+]
+open("ias_response.http", "wb").write(("\r\n".join(headers) + "\r\n\r\n").encode() + body)
+PY
+
+payload=$( (cat "$tmp/report.json"; printf '|'; base64 -w0 "$tmp/report.sig"; printf '|'; base64 -w0 "$tmp/sign.der") | xxd -p | tr -d '\n')
+printf "[ext]\n2.16.840.1.113730.1.13=DER:%s\n" "$payload" > "$tmp/ratls.ext"
+openssl req -new -x509 -sha256 -key "$tmp/ec.key" -subj "/CN=Teaclave RA-TLS" -set_serial 1 $validity \
+    -config /dev/null -extensions ext -extfile "$tmp/ratls.ext" -outform DER -out ratls_cert.der 2>/dev/null || \
+openssl req -new -x509 -sha256 -key "$tmp/ec.key" -subj "/CN=Teaclave RA-TLS" -set_serial 1 $validity \
+    -extensions ext -config "$tmp/ratls.ext" -outform DER -out ratls_cert.der
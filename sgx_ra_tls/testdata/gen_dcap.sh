@@ -0,0 +1,215 @@
+#!/bin/bash
+#
+# Regenerates the DCAP test fixtures. The root, PCK and TCB signing
+# certificates are test stand-ins; nothing here is signed by Intel. The TCB
+# signing certificate carries the common name the verifier requires.
+#
+# dcap_root_ca.der          test SGX root CA
+# dcap_quote.bin            version 3 quote whose PCK certificate chains to it,
+#                           with the TCB of the second TCB info level
+# dcap_pck_crl_chain.pem    PCK platform CA and root, issuer of dcap_pck_crl.der
+# dcap_root_ca_crl.der      root CA CRL, revoking serial 0x99
+# dcap_pck_crl.der          PCK CRL, revoking serial 0x98
+# dcap_pck_crl_revoked.der  PCK CRL that also revokes the PCK certificate
+# dcap_tcb_chain.pem        TCB signing certificate and root, issuer of the
+#                           TCB info and QE identity
+# dcap_tcb_info.json        TCB info for the quote's FMSPC
+# dcap_qe_identity.json     QE identity matching the quote's QE report
+#
+# All CRLs and collateral are issued 2020-06-01 and expire 2020-07-01.
+#
+set -e
+cd "$(dirname "$0")"
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+
+validity="-not_before 20200101000000Z -not_after 20491231235959Z"
+ec="-newkey ec -pkeyopt ec_paramgen_curve:prime256v1 -nodes"
+
+openssl req -x509 $ec -sha256 -keyout "$tmp/root.key" -out "$tmp/root.pem" \
+    -subj "/CN=Test SGX Root CA/O=Teaclave/C=US" $validity \
+    -addext "basicConstraints=critical,CA:TRUE,pathlen:1" -addext "keyUsage=critical,keyCertSign,cRLSign"
+openssl x509 -in "$tmp/root.pem" -outform DER -out dcap_root_ca.der
+
+printf "basicConstraints=critical,CA:TRUE,pathlen:0\nkeyUsage=critical,keyCertSign,cRLSign\n" > "$tmp/ca.ext"
+openssl req -new $ec -keyout "$tmp/platform.key" -out "$tmp/platform.csr" \
+    -subj "/CN=Test SGX PCK Platform CA/O=Teaclave/C=US"
+openssl x509 -req -sha256 -in "$tmp/platform.csr" -CA "$tmp/root.pem" -CAkey "$tmp/root.key" -set_serial 2 \
+    $validity -extfile "$tmp/ca.ext" -out "$tmp/platform.pem"
+
+printf "basicConstraints=critical,CA:FALSE\nkeyUsage=critical,digitalSignature,nonRepudiation\n" > "$tmp/signing.ext"
+openssl req -new $ec -keyout "$tmp/tcb.key" -out "$tmp/tcb.csr" \
+    -subj "/CN=Intel SGX TCB Signing/O=Teaclave/C=US"
+openssl x509 -req -sha256 -in "$tmp/tcb.csr" -CA "$tmp/root.pem" -CAkey "$tmp/root.key" -set_serial 3 \
+    $validity -extfile "$tmp/signing.ext" -out "$tmp/tcb.pem"
+
+# The SGX extension: PPID, TCB component SVNs 14 14 2 2 255 128 1 0..., PCESVN
+# 10, CPUSVN, PCE ID 0, FMSPC 00906ea10000 and SGX type Standard.
+python3 - > "$tmp/sgx_ext.hex" <<'PY'
+def tlv(tag, content):
+    n = len(content)
+    if n < 0x80:
+        head = bytes([tag, n])
+    elif n < 0x100:
+        head = bytes([tag, 0x81, n])
+    else:
+        head = bytes([tag, 0x82, n >> 8, n & 0xff])
+    return head + content
+def oid(*arcs):
+    out = bytes([40 * arcs[0] + arcs[1]])
+    for arc in arcs[2:]:
+        enc = [arc & 0x7f]
+        arc >>= 7
+        while arc:
+            enc.insert(0, 0x80 | (arc & 0x7f))
+            arc >>= 7
+        out += bytes(enc)
+    return tlv(0x06, out)
+def integer(v):
+    b = v.to_bytes(2, "big").lstrip(b"\0") or b"\0"
+    if b[0] & 0x80:
+        b = b"\0" + b
+    return tlv(0x02, b)
+SGX = (1, 2, 840, 113741, 1, 13, 1)
+comps = [14, 14, 2, 2, 255, 128, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+tcb = b"".join(tlv(0x30, oid(*SGX, 2, i + 1) + integer(svn)) for i, svn in enumerate(comps))
+tcb += tlv(0x30, oid(*SGX, 2, 17) + integer(10))
+tcb += tlv(0x30, oid(*SGX, 2, 18) + tlv(0x04, bytes(comps)))
+ext = tlv(0x30, oid(*SGX, 1) + tlv(0x04, bytes(range(0xa0, 0xb0))))
+ext += tlv(0x30, oid(*SGX, 2) + tlv(0x30, tcb))
+ext += tlv(0x30, oid(*SGX, 3) + tlv(0x04, bytes(2)))
+ext += tlv(0x30, oid(*SGX, 4) + tlv(0x04, bytes.fromhex("00906ea10000")))
+ext += tlv(0x30, oid(*SGX, 5) + tlv(0x0a, b"\0"))
+print(tlv(0x30, ext).hex())
+PY
+printf "basicConstraints=critical,CA:FALSE\nkeyUsage=critical,digitalSignature,nonRepudiation\n1.2.840.113741.1.13.1=DER:%s\n" \
+    "$(cat "$tmp/sgx_ext.hex")" > "$tmp/pck.ext"
+openssl req -new $ec -keyout "$tmp/pck.key" -out "$tmp/pck.csr" -subj "/CN=Test SGX PCK Certificate/O=Teaclave/C=US"
+openssl x509 -req -sha256 -in "$tmp/pck.csr" -CA "$tmp/platform.pem" -CAkey "$tmp/platform.key" -set_serial 0x42 \
+    $validity -extfile "$tmp/pck.ext" -out "$tmp/pck.pem"
+
+cat "$tmp/platform.pem" "$tmp/root.pem" > dcap_pck_crl_chain.pem
+cat "$tmp/tcb.pem" "$tmp/root.pem" > dcap_tcb_chain.pem
+
+# gen_crl <ca> <out> <serial>...
+gen_crl() {
+    local ca=$1 out=$2 db="$tmp/crl_db"
+    shift 2
+    rm -rf "$db" && mkdir "$db" && : > "$db/index.txt" && echo 01 > "$db/crlnumber"
+    for serial in "$@"; do
+        printf "R\t491231235959Z\t200515000000Z\t%s\tunknown\t/CN=Revoked\n" "$serial" >> "$db/index.txt"
+    done
+    printf "[ca]\ndefault_ca=ca\n[ca]\ndatabase=%s/index.txt\ncrlnumber=%s/crlnumber\ndefault_md=sha256\n" \
+        "$db" "$db" > "$db/ca.cnf"
+    openssl ca -gencrl -config "$db/ca.cnf" -cert "$tmp/$ca.pem" -keyfile "$tmp/$ca.key" \
+        -crl_lastupdate 20200601000000Z -crl_nextupdate 20200701000000Z -out "$db/crl.pem" 2>/dev/null
+    openssl crl -in "$db/crl.pem" -outform DER -out "$out"
+}
+gen_crl root dcap_root_ca_crl.der 99
+gen_crl platform dcap_pck_crl.der 98
+gen_crl platform dcap_pck_crl_revoked.der 98 42
+
+openssl ec -in "$tmp/pck.key" -pubout -outform DER -out "$tmp/pck_pub.der" 2>/dev/null
+openssl ecparam -name prime256v1 -genkey -noout -out "$tmp/attest.key"
+openssl ec -in "$tmp/attest.key" -pubout -outform DER -out "$tmp/attest_pub.der" 2>/dev/null
+
+python3 - "$tmp" <<'PY'
+import hashlib, json, struct, subprocess, sys
+tmp = sys.argv[1]
+
+def sign(key, data):
+    open(tmp + "/tbs", "wb").write(data)
+    der = subprocess.check_output(["openssl", "dgst", "-sha256", "-sign", tmp + "/" + key, tmp + "/tbs"])
+    # ECDSA-Sig-Value to r || s
+    assert der[0] == 0x30
+    pos, out = 2, b""
+    for _ in range(2):
+        assert der[pos] == 0x02
+        n = der[pos + 1]
+        out += int.from_bytes(der[pos + 2:pos + 2 + n], "big").to_bytes(32, "big")
+        pos += 2 + n
+    return out
+
+def signed_json(name, key, body):
+    body = json.dumps(body, separators=(",", ":"))
+    sig = sign(key, body.encode())
+    return '{"%s":%s,"signature":"%s"}' % (name, body, sig.hex())
+
+issued = {"issueDate": "2020-06-01T00:00:00Z", "nextUpdate": "2020-07-01T00:00:00Z"}
+
+def level(comps, pcesvn, status):
+    tcb = {"sgxtcbcomp%02dsvn" % (i + 1): svn for i, svn in enumerate(comps)}
+    tcb["pcesvn"] = pcesvn
+    return {"tcb": tcb, "tcbDate": "2020-05-13T00:00:00Z", "tcbStatus": status}
+
+tcb_info = {"version": 2}
+tcb_info.update(issued)
+tcb_info.update({
+    "fmspc": "00906ea10000",
+    "pceId": "0000",
+    "tcbType": 0,
+    "tcbEvaluationDataNumber": 8,
+    "tcbLevels": [
+        level([15, 15, 2, 2, 255, 128, 1] + [0] * 9, 11, "UpToDate"),
+        level([14, 14, 2, 2, 255, 128, 1] + [0] * 9, 10, "SWHardeningNeeded"),
+        level([13, 13, 2, 2, 255, 128, 1] + [0] * 9, 9, "OutOfDate"),
+        level([5, 5, 2, 2, 255, 128, 0] + [0] * 9, 5, "Revoked"),
+    ],
+})
+open("dcap_tcb_info.json", "w").write(signed_json("tcbInfo", "tcb.key", tcb_info))
+
+qe_mr_signer = bytes(range(0x80, 0xa0))
+qe_identity = {"id": "QE", "version": 2}
+qe_identity.update(issued)
+qe_identity.update({
+    "tcbEvaluationDataNumber": 8,
+    "miscselect": "00000000",
+    "miscselectMask": "FFFFFFFF",
+    "attributes": "11000000000000000000000000000000",
+    "attributesMask": "FBFFFFFFFFFFFFFF0000000000000000",
+    "mrsigner": qe_mr_signer.hex().upper(),
+    "isvprodid": 1,
+    "tcbLevels": [
+        {"tcb": {"isvsvn": 5}, "tcbDate": "2020-05-13T00:00:00Z", "tcbStatus": "UpToDate"},
+        {"tcb": {"isvsvn": 2}, "tcbDate": "2019-11-13T00:00:00Z", "tcbStatus": "OutOfDate"},
+    ],
+})
+open("dcap_qe_identity.json", "w").write(signed_json("enclaveIdentity", "tcb.key", qe_identity))
+
+attest_key = open(tmp + "/attest_pub.der", "rb").read()[-64:]
+auth_data = bytes(range(32))
+
+header = bytearray(48)
+header[0:2] = struct.pack("<H", 3)             # version
+header[2:4] = struct.pack("<H", 2)             # ECDSA-256 with P-256
+header[8:10] = struct.pack("<H", 5)            # qe_svn
+header[10:12] = struct.pack("<H", 10)          # pce_svn
+header[12:28] = bytes.fromhex("939a7233f79c4ca9940a0db3957f0607")
+body = bytearray(384)
+body[0:16] = bytes([14, 14, 2, 2, 255, 128, 1] + [0] * 9)
+body[48:56] = struct.pack("<Q", 0x05)          # attributes.flags: INIT | MODE64BIT
+body[56:64] = struct.pack("<Q", 0x03)          # attributes.xfrm
+body[64:96] = bytes(range(0x20, 0x40))         # mr_enclave
+body[128:160] = bytes([0x5a] * 32)             # mr_signer
+body[256:258] = struct.pack("<H", 1)           # isv_prod_id
+body[258:260] = struct.pack("<H", 3)           # isv_svn
+body[320:384] = bytes(range(0x40, 0x80))       # report_data
+
+qe_report = bytearray(384)
+qe_report[0:16] = body[0:16]
+qe_report[48:56] = struct.pack("<Q", 0x15)     # INIT | MODE64BIT | PROVISIONKEY
+qe_report[56:64] = struct.pack("<Q", 0xe7)
+qe_report[64:96] = bytes([0x77] * 32)
+qe_report[128:160] = qe_mr_signer
+qe_report[256:258] = struct.pack("<H", 1)
+qe_report[258:260] = struct.pack("<H", 5)
+qe_report[320:352] = hashlib.sha256(attest_key + auth_data).digest()
+
+chain = b"".join(open(tmp + "/" + n + ".pem", "rb").read() for n in ("pck", "platform", "root"))
+sig_data = sign("attest.key", bytes(header + body))
+sig_data += attest_key + bytes(qe_report) + sign("pck.key", bytes(qe_report))
+sig_data += struct.pack("<H", len(auth_data)) + auth_data
+sig_data += struct.pack("<HI", 5, len(chain)) + chain
+quote = bytes(header + body) + struct.pack("<I", len(sig_data)) + sig_data
+open("dcap_quote.bin", "wb").write(quote)
+PY
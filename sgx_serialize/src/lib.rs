@@ -25,11 +25,25 @@ Core encoding and decoding interfaces.
 #![cfg_attr(target_env = "sgx", feature(rustc_private))]
 
 #[cfg(not(target_env = "sgx"))]
+#[macro_use]
 extern crate sgx_tstd as std;
 
+extern crate sgx_types;
+extern crate sgx_tseal;
+
+#[cfg(feature = "with_serde")]
+extern crate serde;
+#[cfg(feature = "with_serde")]
+extern crate serde_cbor;
+
 mod serialize;
 pub use self::serialize::{Decoder, Encoder, DeSerializable, Serializable, SerializeHelper, DeSerializeHelper};
 
 mod opaque;
 mod leb128;
 
+mod seal;
+pub use self::seal::{SgxUnsealedObject, seal_object, seal_object_ex, unseal_object};
+#[cfg(feature = "with_serde")]
+pub use self::seal::{seal_serde, seal_serde_ex, unseal_serde};
+
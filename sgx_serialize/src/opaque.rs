@@ -20,6 +20,7 @@
 //!
 
 use crate::leb128::{read_signed_leb128, read_unsigned_leb128, write_signed_leb128, write_unsigned_leb128};
+use crate::leb128::{write_signed_leb128_to, write_unsigned_leb128_to};
 use std::vec::Vec;
 use std::string::String;
 use std::string::ToString;
@@ -143,6 +144,122 @@ impl<'a> serialize::Encoder for Encoder<'a> {
 }
 
 
+// -----------------------------------------------------------------------------
+// SizeEncoder
+// -----------------------------------------------------------------------------
+
+/// Counts the bytes that `Encoder` would write, so that the output buffer can
+/// be allocated once and never reallocated.
+pub struct SizeEncoder {
+    size: usize,
+}
+
+impl SizeEncoder {
+    pub fn new() -> SizeEncoder {
+        SizeEncoder { size: 0 }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+macro_rules! size_uleb128 {
+    ($enc:expr, $value:expr) => {{
+        $enc.size += write_unsigned_leb128_to($value as u128, |_, _| {});
+        Ok(())
+    }}
+}
+
+macro_rules! size_sleb128 {
+    ($enc:expr, $value:expr) => {{
+        $enc.size += write_signed_leb128_to($value as i128, |_, _| {});
+        Ok(())
+    }}
+}
+
+impl serialize::Encoder for SizeEncoder {
+    type Error = ();
+
+    fn emit_nil(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn emit_usize(&mut self, v: usize) -> Result<(), Self::Error> {
+        size_uleb128!(self, v)
+    }
+
+    fn emit_u128(&mut self, v: u128) -> Result<(), Self::Error> {
+        size_uleb128!(self, v)
+    }
+
+    fn emit_u64(&mut self, v: u64) -> Result<(), Self::Error> {
+        size_uleb128!(self, v)
+    }
+
+    fn emit_u32(&mut self, v: u32) -> Result<(), Self::Error> {
+        size_uleb128!(self, v)
+    }
+
+    fn emit_u16(&mut self, v: u16) -> Result<(), Self::Error> {
+        size_uleb128!(self, v)
+    }
+
+    fn emit_u8(&mut self, _v: u8) -> Result<(), Self::Error> {
+        self.size += 1;
+        Ok(())
+    }
+
+    fn emit_isize(&mut self, v: isize) -> Result<(), Self::Error> {
+        size_sleb128!(self, v)
+    }
+
+    fn emit_i128(&mut self, v: i128) -> Result<(), Self::Error> {
+        size_sleb128!(self, v)
+    }
+
+    fn emit_i64(&mut self, v: i64) -> Result<(), Self::Error> {
+        size_sleb128!(self, v)
+    }
+
+    fn emit_i32(&mut self, v: i32) -> Result<(), Self::Error> {
+        size_sleb128!(self, v)
+    }
+
+    fn emit_i16(&mut self, v: i16) -> Result<(), Self::Error> {
+        size_sleb128!(self, v)
+    }
+
+    fn emit_i8(&mut self, _v: i8) -> Result<(), Self::Error> {
+        self.size += 1;
+        Ok(())
+    }
+
+    fn emit_bool(&mut self, _v: bool) -> Result<(), Self::Error> {
+        self.size += 1;
+        Ok(())
+    }
+
+    fn emit_f64(&mut self, v: f64) -> Result<(), Self::Error> {
+        self.emit_u64(v.to_bits())
+    }
+
+    fn emit_f32(&mut self, v: f32) -> Result<(), Self::Error> {
+        self.emit_u32(v.to_bits())
+    }
+
+    fn emit_char(&mut self, v: char) -> Result<(), Self::Error> {
+        self.emit_u32(v as u32)
+    }
+
+    fn emit_str(&mut self, v: &str) -> Result<(), Self::Error> {
+        self.emit_usize(v.len())?;
+        self.size += v.len();
+        Ok(())
+    }
+}
+
+
 // -----------------------------------------------------------------------------
 // Decoder
 // -----------------------------------------------------------------------------
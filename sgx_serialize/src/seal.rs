@@ -0,0 +1,279 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! Sealing of serializable objects
+//!
+//! `SgxSealedData` can only seal types that are `Copy + ContiguousMemory`, or
+//! slices of them. The functions here seal any `Serializable` value, such as a
+//! struct holding `String`s or a `HashMap`, and restore it with `unseal_object`.
+//! With the `with_serde` feature, `seal_serde` and `unseal_serde` do the same for
+//! serde types, encoded as CBOR.
+//!
+//! The value is first measured, then serialized into a buffer of exactly that
+//! size, so the plaintext never gets reallocated and left behind, and the buffer
+//! is wiped once it has been sealed with `seal_data_ex`. Decrypted plaintext is
+//! wiped as soon as it has been decoded.
+//!
+//! A sealed object is returned as the bytes of an `sgx_sealed_data_t`, exactly as
+//! `SgxSealedData::to_raw_sealed_data_t` would write it, so it can be stored as is
+//! and passed back to `unseal_object`.
+//!
+//! These helpers are part of sgx_serialize rather than sgx_tseal because sgx_tstd,
+//! on which sgx_serialize is built, itself depends on sgx_tseal.
+//!
+
+use std::vec::Vec;
+use std::boxed::Box;
+use std::io::Cursor;
+use std::mem;
+use std::ptr;
+use std::slice;
+use sgx_types::*;
+use sgx_tseal::{SgxSealedData, SgxUnsealedData, SgxSealPolicy};
+use crate::serialize::{Serializable, DeSerializable};
+use crate::opaque::{Encoder as DataEncoder, Decoder as DataDecoder, SizeEncoder};
+
+/// An object restored by `unseal_object`, with the additional text it was sealed with.
+pub struct SgxUnsealedObject<T> {
+    object: T,
+    additional: Box<[u8]>,
+}
+
+impl<T> SgxUnsealedObject<T> {
+    ///
+    /// Get the unsealed object.
+    ///
+    pub fn get_object(&self) -> &T {
+        &self.object
+    }
+    ///
+    /// Take the unsealed object.
+    ///
+    pub fn into_object(self) -> T {
+        self.object
+    }
+    ///
+    /// Get the additional text the object was sealed with.
+    ///
+    pub fn get_additional_txt(&self) -> &[u8] {
+        &*self.additional
+    }
+}
+
+fn clear_buffer(buf: &mut [u8]) {
+    for b in buf.iter_mut() {
+        unsafe { ptr::write_volatile(b, 0) };
+    }
+}
+
+// sgx_sealed_data_t needs 4-byte alignment, which a byte slice does not guarantee.
+fn aligned_buffer(len: usize) -> Vec<u64> {
+    vec![0_u64; (len + mem::size_of::<u64>() - 1) / mem::size_of::<u64>()]
+}
+
+fn seal_bytes(policy: &SgxSealPolicy, additional_text: &[u8], encrypt_text: &[u8]) -> SgxResult<Vec<u8>> {
+
+    let sealed_data = SgxSealedData::<[u8]>::seal_data_ex(policy.key_policy,
+                                                          policy.attribute_mask,
+                                                          policy.misc_mask,
+                                                          additional_text,
+                                                          encrypt_text)?;
+    let size = SgxSealedData::<[u8]>::calc_raw_sealed_data_size(sealed_data.get_add_mac_txt_len(),
+                                                                sealed_data.get_encrypt_txt_len());
+    if size == u32::max_value() {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+
+    let mut raw = aligned_buffer(size as usize);
+    let p = raw.as_mut_ptr() as * mut sgx_sealed_data_t;
+    unsafe {
+        sealed_data.to_raw_sealed_data_t(p, size).ok_or(sgx_status_t::SGX_ERROR_UNEXPECTED)?;
+        Ok(slice::from_raw_parts(p as * const u8, size as usize).to_vec())
+    }
+}
+
+fn unseal_bytes<'a>(sealed: &[u8]) -> SgxResult<SgxUnsealedData<'a, [u8]>> {
+
+    if sealed.len() > u32::max_value() as usize {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    let mut raw = aligned_buffer(sealed.len());
+    let p = raw.as_mut_ptr() as * mut sgx_sealed_data_t;
+    let sealed_data = unsafe {
+        ptr::copy_nonoverlapping(sealed.as_ptr(), p as * mut u8, sealed.len());
+        SgxSealedData::<[u8]>::from_raw_sealed_data_t(p, sealed.len() as u32)
+    }.ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+    sealed_data.unseal_data()
+}
+
+///
+/// Serialize and seal an object with the default MRSIGNER policy of `SgxSealedData::seal_data`.
+///
+/// See `seal_object_ex`.
+///
+pub fn seal_object<T: Serializable + ?Sized>(additional_text: &[u8], object: &T) -> SgxResult<Vec<u8>> {
+    seal_object_ex(&SgxSealPolicy::mrsigner(), additional_text, object)
+}
+
+///
+/// Serialize and seal an object.
+///
+/// # Parameters
+///
+/// **policy**
+///
+/// The key policy, attribute mask and misc mask passed to `seal_data_ex`.
+///
+/// **additional_text**
+///
+/// Additional MAC data, stored in clear. This additional data is optional and no data is necessary.
+///
+/// **object**
+///
+/// The object to be sealed.
+///
+/// # Return value
+///
+/// The sealed object in the layout of `sgx_sealed_data_t`.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The object fails to serialize or serializes to no bytes at all, or the parameters
+/// are not accepted by `seal_data_ex`.
+///
+/// The other errors of `seal_data_ex` are passed through.
+///
+pub fn seal_object_ex<T: Serializable + ?Sized>(policy: &SgxSealPolicy,
+                                                additional_text: &[u8],
+                                                object: &T) -> SgxResult<Vec<u8>> {
+
+    let mut sizer = SizeEncoder::new();
+    object.encode(&mut sizer).map_err(|_| sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+
+    let mut cursor = Cursor::new(Vec::with_capacity(sizer.size()));
+    let encoded = {
+        let mut encoder = DataEncoder::new(&mut cursor);
+        object.encode(&mut encoder)
+    };
+    let result = match encoded {
+        Ok(()) => seal_bytes(policy, additional_text, cursor.get_ref()),
+        Err(_) => Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+    };
+    clear_buffer(cursor.get_mut());
+    result
+}
+
+///
+/// Unseal and deserialize an object produced by `seal_object` or `seal_object_ex`.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The buffer is not a well-formed `sgx_sealed_data_t`, or `T::decode` returns an error.
+///
+/// The errors of `unseal_data`, such as **SGX_ERROR_MAC_MISMATCH**, are passed through.
+///
+/// # Panics
+///
+/// The payload is authenticated before it is decoded, so it was sealed by this enclave
+/// or one sharing its sealing key. As with `DeSerializeHelper`, decoding it as a type
+/// other than the one that was sealed may panic.
+///
+pub fn unseal_object<T: DeSerializable>(sealed: &[u8]) -> SgxResult<SgxUnsealedObject<T>> {
+
+    let mut unsealed_data = unseal_bytes(sealed)?;
+    let decoded = {
+        let mut decoder = DataDecoder::new(unsealed_data.get_decrypt_txt(), 0);
+        T::decode(&mut decoder)
+    };
+    clear_buffer(&mut unsealed_data.decrypt);
+
+    let object = decoded.map_err(|_| sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+    Ok(SgxUnsealedObject {
+        object,
+        additional: unsealed_data.additional,
+    })
+}
+
+#[cfg(feature = "with_serde")]
+struct SizeWriter(usize);
+
+#[cfg(feature = "with_serde")]
+impl std::io::Write for SizeWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+///
+/// Encode a serde object as CBOR and seal it with the default MRSIGNER policy.
+///
+/// See `seal_serde_ex`.
+///
+#[cfg(feature = "with_serde")]
+pub fn seal_serde<T: serde::Serialize>(additional_text: &[u8], object: &T) -> SgxResult<Vec<u8>> {
+    seal_serde_ex(&SgxSealPolicy::mrsigner(), additional_text, object)
+}
+
+///
+/// Encode a serde object as CBOR and seal it. The parameters, return value and errors
+/// are those of `seal_object_ex`.
+///
+#[cfg(feature = "with_serde")]
+pub fn seal_serde_ex<T: serde::Serialize>(policy: &SgxSealPolicy,
+                                          additional_text: &[u8],
+                                          object: &T) -> SgxResult<Vec<u8>> {
+
+    let mut sizer = SizeWriter(0);
+    serde_cbor::to_writer(&mut sizer, object).map_err(|_| sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+
+    let mut buffer: Vec<u8> = Vec::with_capacity(sizer.0);
+    let result = match serde_cbor::to_writer(&mut buffer, object) {
+        Ok(()) => seal_bytes(policy, additional_text, &buffer),
+        Err(_) => Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+    };
+    clear_buffer(&mut buffer);
+    result
+}
+
+///
+/// Unseal and decode a serde object produced by `seal_serde` or `seal_serde_ex`. The
+/// errors are those of `unseal_object`.
+///
+#[cfg(feature = "with_serde")]
+pub fn unseal_serde<T: serde::de::DeserializeOwned>(sealed: &[u8]) -> SgxResult<SgxUnsealedObject<T>> {
+
+    let mut unsealed_data = unseal_bytes(sealed)?;
+    let decoded = serde_cbor::from_slice::<T>(unsealed_data.get_decrypt_txt());
+    clear_buffer(&mut unsealed_data.decrypt);
+
+    let object = decoded.map_err(|_| sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+    Ok(SgxUnsealedObject {
+        object,
+        additional: unsealed_data.additional,
+    })
+}
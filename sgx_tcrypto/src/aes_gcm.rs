@@ -0,0 +1,426 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! AES-256-GCM Functions
+//!
+//! libsgx_tcrypto.a only supports 128-bit AES-GCM keys. The functions in this
+//! module provide the 256-bit variant with the same calling conventions as
+//! `rsgx_rijndael128GCM_encrypt`, `rsgx_rijndael128GCM_decrypt` and
+//! `SgxAesHandle`.
+//!
+use sgx_types::*;
+use core::cell::{Cell, RefCell};
+use crate::aes::{Aes, AES_BLOCK_SIZE};
+use crate::ghash::{Ghash, GHASH_BLOCK_SIZE};
+use crate::internal::{zeroize, consttime_eq};
+
+// NIST SP 800-38D limits the plaintext to 2^39 - 256 bits per invocation.
+const GCM_MAX_TEXT_LEN: u64 = (1_u64 << 36) - 32;
+
+pub(crate) struct GcmContext {
+    cipher: Aes,
+    ghash: Ghash,
+    j0: [u8; AES_BLOCK_SIZE],
+    counter: [u8; AES_BLOCK_SIZE],
+    keystream: [u8; AES_BLOCK_SIZE],
+    ks_used: usize,
+    pending: [u8; GHASH_BLOCK_SIZE],
+    pending_len: usize,
+    aad_len: u64,
+    text_len: u64,
+}
+
+impl GcmContext {
+    pub(crate) fn new(key: &[u8], iv: &[u8], aad: &[u8]) -> SgxResult<GcmContext> {
+        if iv.len() != SGX_AESGCM_IV_SIZE {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let cipher = Aes::new(key).ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+
+        let mut h = [0_u8; AES_BLOCK_SIZE];
+        cipher.encrypt_block(&mut h);
+        let mut ghash = Ghash::new(&h);
+        zeroize(&mut h);
+        ghash.update_padded(aad);
+
+        let mut j0 = [0_u8; AES_BLOCK_SIZE];
+        j0[..SGX_AESGCM_IV_SIZE].copy_from_slice(iv);
+        j0[AES_BLOCK_SIZE - 1] = 1;
+
+        Ok(GcmContext {
+            cipher,
+            ghash,
+            j0,
+            counter: j0,
+            keystream: [0_u8; AES_BLOCK_SIZE],
+            ks_used: AES_BLOCK_SIZE,
+            pending: [0_u8; GHASH_BLOCK_SIZE],
+            pending_len: 0,
+            aad_len: aad.len() as u64,
+            text_len: 0,
+        })
+    }
+
+    fn next_keystream(&mut self) {
+        let ctr = u32::from_be_bytes([self.counter[12], self.counter[13], self.counter[14], self.counter[15]]);
+        self.counter[12..].copy_from_slice(&ctr.wrapping_add(1).to_be_bytes());
+        self.keystream = self.counter;
+        self.cipher.encrypt_block(&mut self.keystream);
+        self.ks_used = 0;
+    }
+
+    fn apply_keystream(&mut self, src: &[u8], dst: &mut [u8]) {
+        for (s, d) in src.iter().zip(dst.iter_mut()) {
+            if self.ks_used == AES_BLOCK_SIZE {
+                self.next_keystream();
+            }
+            *d = *s ^ self.keystream[self.ks_used];
+            self.ks_used += 1;
+        }
+    }
+
+    fn absorb_ciphertext(&mut self, mut data: &[u8]) {
+        if self.pending_len > 0 {
+            let n = core::cmp::min(GHASH_BLOCK_SIZE - self.pending_len, data.len());
+            self.pending[self.pending_len..self.pending_len + n].copy_from_slice(&data[..n]);
+            self.pending_len += n;
+            data = &data[n..];
+            if self.pending_len < GHASH_BLOCK_SIZE {
+                return;
+            }
+            let block = self.pending;
+            self.ghash.update_block(&block);
+            self.pending_len = 0;
+        }
+        let full = data.len() - data.len() % GHASH_BLOCK_SIZE;
+        self.ghash.update_padded(&data[..full]);
+        let rest = &data[full..];
+        self.pending[..rest.len()].copy_from_slice(rest);
+        self.pending_len = rest.len();
+    }
+
+    fn reserve(&mut self, len: usize) -> SgxError {
+        let total = self.text_len.checked_add(len as u64)
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+        if total > GCM_MAX_TEXT_LEN {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        self.text_len = total;
+        Ok(())
+    }
+
+    pub(crate) fn encrypt_update(&mut self, src: &[u8], dst: &mut [u8]) -> SgxError {
+        if dst.len() < src.len() {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        self.reserve(src.len())?;
+        let dst = &mut dst[..src.len()];
+        self.apply_keystream(src, dst);
+        self.absorb_ciphertext(dst);
+        Ok(())
+    }
+
+    ///
+    /// Computes the tag over everything absorbed so far without consuming the context.
+    ///
+    pub(crate) fn tag(&self) -> sgx_aes_gcm_128bit_tag_t {
+        let mut ghash = self.ghash.clone();
+        if self.pending_len > 0 {
+            ghash.update_padded(&self.pending[..self.pending_len]);
+        }
+        let mut len_block = [0_u8; GHASH_BLOCK_SIZE];
+        len_block[..8].copy_from_slice(&(self.aad_len << 3).to_be_bytes());
+        len_block[8..].copy_from_slice(&(self.text_len << 3).to_be_bytes());
+        ghash.update_block(&len_block);
+
+        let mut tag = self.j0;
+        self.cipher.encrypt_block(&mut tag);
+        for (t, s) in tag.iter_mut().zip(ghash.finalize().iter()) {
+            *t ^= *s;
+        }
+        tag
+    }
+}
+
+impl Drop for GcmContext {
+    fn drop(&mut self) {
+        zeroize(&mut self.j0);
+        zeroize(&mut self.counter);
+        zeroize(&mut self.keystream);
+        zeroize(&mut self.pending);
+    }
+}
+
+fn check_gcm_params(src: &[u8], iv: &[u8], aad: &[u8], dst: &[u8]) -> SgxError {
+    if src.len() > u32::max_value() as usize {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    if iv.len() != SGX_AESGCM_IV_SIZE {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    if aad.len() > u32::max_value() as usize {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    if dst.len() > u32::max_value() as usize {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    if dst.len() < src.len() {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    if src.is_empty() && aad.is_empty() {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    Ok(())
+}
+
+///
+/// rsgx_rijndael256GCM_encrypt performs a Rijndael AES-GCM encryption operation with a 256-bit key.
+///
+/// # Description
+///
+/// This is the 256-bit key counterpart of rsgx_rijndael128GCM_encrypt. The IV, AAD, output
+/// buffer and MAC conventions are identical.
+///
+/// # Parameters
+///
+/// **key**
+///
+/// A pointer to key to be used in the AES-GCM encryption operation. The size must be 256 bits.
+///
+/// **src**
+///
+/// A pointer to the input data stream to be encrypted. Buffer content could be empty if there is AAD text.
+///
+/// **iv**
+///
+/// A pointer to the initialization vector to be used in the AES-GCM calculation. The size must be 96 bits (12 bytes).
+///
+/// **aad**
+///
+/// A pointer to an optional additional authentication data buffer which is used in the GCM MAC calculation.
+/// The data in this buffer will not be encrypted. The field is optional and content could be empty.
+///
+/// **dst**
+///
+/// A pointer to the output encrypted data buffer. This buffer should be allocated by the calling code.
+///
+/// **mac**
+///
+/// This is the output GCM MAC performed over the input data buffer (data to be encrypted) as well as
+/// the additional authentication data (this is optional data). The calling code should allocate this buffer.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// If both source buffer and AAD buffer content are empty.
+///
+/// If IV Length is not equal to 12 (bytes).
+///
+/// If the destination buffer is shorter than the source buffer.
+///
+pub fn rsgx_rijndael256GCM_encrypt(key: &sgx_aes_gcm_256bit_key_t,
+                                   src: &[u8],
+                                   iv: &[u8],
+                                   aad: &[u8],
+                                   dst: &mut [u8],
+                                   mac: &mut sgx_aes_gcm_128bit_tag_t) -> SgxError {
+
+    check_gcm_params(src, iv, aad, dst)?;
+
+    let mut ctx = GcmContext::new(key, iv, aad)?;
+    ctx.encrypt_update(src, dst)?;
+    *mac = ctx.tag();
+    Ok(())
+}
+
+///
+/// rsgx_rijndael256GCM_decrypt performs a Rijndael AES-GCM decryption operation with a 256-bit key.
+///
+/// # Description
+///
+/// This is the 256-bit key counterpart of rsgx_rijndael128GCM_decrypt. The MAC is verified
+/// before any plaintext is written to the destination buffer.
+///
+/// # Parameters
+///
+/// **key**
+///
+/// A pointer to key to be used in the AES-GCM decryption operation. The size must be 256 bits.
+///
+/// **src**
+///
+/// A pointer to the input data stream to be decrypted. Buffer content could be empty if there is AAD text.
+///
+/// **iv**
+///
+/// A pointer to the initialization vector to be used in the AES-GCM calculation. The size must be 96 bits (12 bytes).
+///
+/// **aad**
+///
+/// A pointer to an optional additional authentication data buffer which is provided for the GCM MAC calculation
+/// when encrypting. The data in this buffer was not encrypted. The field is optional and content could be empty.
+///
+/// **mac**
+///
+/// This is the GCM MAC that was produced by rsgx_rijndael256GCM_encrypt.
+///
+/// **dst**
+///
+/// A pointer to the output decrypted data buffer. This buffer should be allocated by the calling code.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// If both source buffer and AAD buffer content are empty.
+///
+/// If IV Length is not equal to 12 (bytes).
+///
+/// If the destination buffer is shorter than the source buffer.
+///
+/// **SGX_ERROR_MAC_MISMATCH**
+///
+/// The input MAC does not match the MAC calculated.
+///
+pub fn rsgx_rijndael256GCM_decrypt(key: &sgx_aes_gcm_256bit_key_t,
+                                   src: &[u8],
+                                   iv: &[u8],
+                                   aad: &[u8],
+                                   mac: &sgx_aes_gcm_128bit_tag_t,
+                                   dst: &mut [u8]) -> SgxError {
+
+    check_gcm_params(src, iv, aad, dst)?;
+
+    let mut ctx = GcmContext::new(key, iv, aad)?;
+    ctx.reserve(src.len())?;
+    ctx.absorb_ciphertext(src);
+    if !consttime_eq(&ctx.tag(), mac) {
+        return Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH);
+    }
+    ctx.apply_keystream(src, &mut dst[..src.len()]);
+    Ok(())
+}
+
+///
+/// AES-256-GCM encryption context state.
+///
+/// This is the 256-bit key counterpart of `SgxAesHandle`. The expanded key and
+/// intermediate GHASH state are wiped on close and on drop.
+///
+pub struct SgxAes256Handle {
+    state: RefCell<Option<GcmContext>>,
+    initflag: Cell<bool>,
+}
+
+impl SgxAes256Handle {
+
+    pub fn new() -> Self {
+        SgxAes256Handle {
+            state: RefCell::new(None),
+            initflag: Cell::new(false),
+        }
+    }
+
+    ///
+    /// init sets up the key, IV and additional authentication data for an
+    /// iterative AES-256-GCM encryption.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// If IV Length is not equal to 12 (bytes), or the AAD is larger than 4 GB.
+    ///
+    pub fn init(&self, key: &sgx_aes_gcm_256bit_key_t, iv: &[u8], aad: &[u8]) -> SgxError {
+        if self.initflag.get() {
+            return Ok(());
+        }
+        if aad.len() > u32::max_value() as usize {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let ctx = GcmContext::new(key, iv, aad)?;
+        *self.state.borrow_mut() = Some(ctx);
+        self.initflag.set(true);
+        Ok(())
+    }
+
+    ///
+    /// update encrypts the next chunk of plaintext into dst.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The source is empty or larger than 4 GB, or the destination is shorter than the source.
+    ///
+    /// **SGX_ERROR_INVALID_STATE**
+    ///
+    /// The handle is not initialized.
+    ///
+    pub fn update(&self, src: &[u8], dst: &mut [u8]) -> SgxError {
+        if !self.initflag.get() {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_STATE);
+        }
+        if src.is_empty() || src.len() > u32::max_value() as usize {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        match self.state.borrow_mut().as_mut() {
+            Some(ctx) => ctx.encrypt_update(src, dst),
+            None => Err(sgx_status_t::SGX_ERROR_INVALID_STATE),
+        }
+    }
+
+    pub fn get_mac(&self) -> SgxResult<sgx_aes_gcm_128bit_tag_t> {
+        if !self.initflag.get() {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_STATE);
+        }
+        match self.state.borrow().as_ref() {
+            Some(ctx) => Ok(ctx.tag()),
+            None => Err(sgx_status_t::SGX_ERROR_INVALID_STATE),
+        }
+    }
+
+    pub fn get_align_mac(&self) -> SgxResult<sgx_align_mac_128bit_t> {
+        let mut align_mac = sgx_align_mac_128bit_t::default();
+        align_mac.mac = self.get_mac()?;
+        Ok(align_mac)
+    }
+
+    pub fn close(&self) -> SgxError {
+        if !self.initflag.get() {
+            return Ok(());
+        }
+        *self.state.borrow_mut() = None;
+        self.initflag.set(false);
+        Ok(())
+    }
+}
+
+impl Default for SgxAes256Handle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for SgxAes256Handle {
+    fn drop(&mut self) {
+       let _ = self.close();
+    }
+}
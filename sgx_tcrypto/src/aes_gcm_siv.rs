@@ -0,0 +1,318 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! AES-GCM-SIV Functions
+//!
+//! AES-GCM-SIV (RFC 8452) is a nonce-misuse-resistant AEAD. Repeating a nonce
+//! under the same key only reveals whether two messages with the same AAD were
+//! identical, instead of exposing the keystream and the authentication key as
+//! it does with AES-GCM. Both 128-bit and 256-bit keys are supported; the IV,
+//! AAD, output buffer and MAC conventions follow `rsgx_rijndael128GCM_encrypt`.
+//!
+use sgx_types::*;
+use crate::aes::{Aes, AES_BLOCK_SIZE};
+use crate::ghash::{Polyval, GHASH_BLOCK_SIZE};
+use crate::internal::{zeroize, consttime_eq};
+
+struct SivKeys {
+    auth: [u8; GHASH_BLOCK_SIZE],
+    enc: Aes,
+}
+
+impl Drop for SivKeys {
+    fn drop(&mut self) {
+        zeroize(&mut self.auth);
+    }
+}
+
+///
+/// Derives the per-nonce message authentication and encryption keys (RFC 8452, Section 4).
+///
+fn derive_keys(key: &[u8], nonce: &[u8]) -> SgxResult<SivKeys> {
+    let kdf = Aes::new(key).ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+    let mut material = [0_u8; 48];
+    let blocks = 2 + key.len() / 8;
+    for (i, out) in material.chunks_mut(8).take(blocks).enumerate() {
+        let mut block = [0_u8; AES_BLOCK_SIZE];
+        block[..4].copy_from_slice(&(i as u32).to_le_bytes());
+        block[4..].copy_from_slice(nonce);
+        kdf.encrypt_block(&mut block);
+        out.copy_from_slice(&block[..8]);
+        zeroize(&mut block);
+    }
+
+    let mut auth = [0_u8; GHASH_BLOCK_SIZE];
+    auth.copy_from_slice(&material[..GHASH_BLOCK_SIZE]);
+    let enc = Aes::new(&material[GHASH_BLOCK_SIZE..blocks * 8]);
+    zeroize(&mut material);
+    match enc {
+        Some(enc) => Ok(SivKeys { auth, enc }),
+        None => {
+            zeroize(&mut auth);
+            Err(sgx_status_t::SGX_ERROR_UNEXPECTED)
+        },
+    }
+}
+
+fn compute_tag(keys: &SivKeys, nonce: &[u8], aad: &[u8], text: &[u8]) -> [u8; AES_BLOCK_SIZE] {
+    let mut polyval = Polyval::new(&keys.auth);
+    polyval.update_padded(aad);
+    polyval.update_padded(text);
+    let mut len_block = [0_u8; GHASH_BLOCK_SIZE];
+    len_block[..8].copy_from_slice(&((aad.len() as u64) << 3).to_le_bytes());
+    len_block[8..].copy_from_slice(&((text.len() as u64) << 3).to_le_bytes());
+    polyval.update_block(&len_block);
+
+    let mut tag = polyval.finalize();
+    for (t, n) in tag.iter_mut().zip(nonce.iter()) {
+        *t ^= *n;
+    }
+    tag[AES_BLOCK_SIZE - 1] &= 0x7f;
+    keys.enc.encrypt_block(&mut tag);
+    tag
+}
+
+///
+/// AES-CTR with the tag as initial counter block and a 32-bit little-endian
+/// counter in the first four bytes.
+///
+fn apply_ctr(enc: &Aes, tag: &[u8; AES_BLOCK_SIZE], src: &[u8], dst: &mut [u8]) {
+    let mut counter = *tag;
+    counter[AES_BLOCK_SIZE - 1] |= 0x80;
+    let mut keystream = [0_u8; AES_BLOCK_SIZE];
+    for (s, d) in src.chunks(AES_BLOCK_SIZE).zip(dst.chunks_mut(AES_BLOCK_SIZE)) {
+        keystream = counter;
+        enc.encrypt_block(&mut keystream);
+        for ((d, s), k) in d.iter_mut().zip(s.iter()).zip(keystream.iter()) {
+            *d = *s ^ *k;
+        }
+        let ctr = u32::from_le_bytes([counter[0], counter[1], counter[2], counter[3]]);
+        counter[..4].copy_from_slice(&ctr.wrapping_add(1).to_le_bytes());
+    }
+    zeroize(&mut keystream);
+}
+
+fn check_siv_params(src: &[u8], nonce: &[u8], aad: &[u8], dst: &[u8]) -> SgxError {
+    if src.len() > u32::max_value() as usize {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    if nonce.len() != SGX_AESGCMSIV_NONCE_SIZE {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    if aad.len() > u32::max_value() as usize {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    if dst.len() < src.len() {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    Ok(())
+}
+
+fn siv_encrypt(key: &[u8], src: &[u8], nonce: &[u8], aad: &[u8], dst: &mut [u8], mac: &mut sgx_aes_gcm_128bit_tag_t) -> SgxError {
+    check_siv_params(src, nonce, aad, dst)?;
+    let keys = derive_keys(key, nonce)?;
+    let tag = compute_tag(&keys, nonce, aad, src);
+    apply_ctr(&keys.enc, &tag, src, &mut dst[..src.len()]);
+    *mac = tag;
+    Ok(())
+}
+
+fn siv_decrypt(key: &[u8], src: &[u8], nonce: &[u8], aad: &[u8], mac: &sgx_aes_gcm_128bit_tag_t, dst: &mut [u8]) -> SgxError {
+    check_siv_params(src, nonce, aad, dst)?;
+    let keys = derive_keys(key, nonce)?;
+    let dst = &mut dst[..src.len()];
+    apply_ctr(&keys.enc, mac, src, dst);
+    let tag = compute_tag(&keys, nonce, aad, dst);
+    if !consttime_eq(&tag, mac) {
+        zeroize(dst);
+        return Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH);
+    }
+    Ok(())
+}
+
+///
+/// rsgx_rijndael128GCMSIV_encrypt performs an AES-GCM-SIV encryption operation with a 128-bit key.
+///
+/// # Description
+///
+/// The MAC is a synthetic IV computed over the AAD and the plaintext, and it is used as the
+/// initial counter for encryption. Unlike AES-GCM, both source and AAD may be empty.
+///
+/// # Parameters
+///
+/// **key**
+///
+/// A pointer to key to be used in the AES-GCM-SIV encryption operation. The size must be 128 bits.
+///
+/// **src**
+///
+/// A pointer to the input data stream to be encrypted. Buffer content could be empty.
+///
+/// **nonce**
+///
+/// A pointer to the nonce to be used in the AES-GCM-SIV calculation. The size must be 96 bits (12 bytes).
+///
+/// **aad**
+///
+/// A pointer to an optional additional authentication data buffer which is used in the MAC calculation.
+/// The data in this buffer will not be encrypted. The field is optional and content could be empty.
+///
+/// **dst**
+///
+/// A pointer to the output encrypted data buffer. This buffer should be allocated by the calling code.
+///
+/// **mac**
+///
+/// This is the output MAC performed over the input data buffer (data to be encrypted) as well as
+/// the additional authentication data (this is optional data). The calling code should allocate this buffer.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// If nonce length is not equal to 12 (bytes).
+///
+/// If the source or AAD buffer is larger than 4 GB.
+///
+/// If the destination buffer is shorter than the source buffer.
+///
+pub fn rsgx_rijndael128GCMSIV_encrypt(key: &sgx_aes_gcm_128bit_key_t,
+                                      src: &[u8],
+                                      nonce: &[u8],
+                                      aad: &[u8],
+                                      dst: &mut [u8],
+                                      mac: &mut sgx_aes_gcm_128bit_tag_t) -> SgxError {
+    siv_encrypt(key, src, nonce, aad, dst, mac)
+}
+
+///
+/// rsgx_rijndael128GCMSIV_decrypt performs an AES-GCM-SIV decryption operation with a 128-bit key.
+///
+/// # Description
+///
+/// The plaintext is recovered into the destination buffer and then authenticated. If the MAC
+/// does not match, the destination buffer is zeroed before the error is returned.
+///
+/// # Parameters
+///
+/// **key**
+///
+/// A pointer to key to be used in the AES-GCM-SIV decryption operation. The size must be 128 bits.
+///
+/// **src**
+///
+/// A pointer to the input data stream to be decrypted. Buffer content could be empty.
+///
+/// **nonce**
+///
+/// A pointer to the nonce to be used in the AES-GCM-SIV calculation. The size must be 96 bits (12 bytes).
+///
+/// **aad**
+///
+/// A pointer to an optional additional authentication data buffer which is provided for the MAC calculation
+/// when encrypting. The data in this buffer was not encrypted. The field is optional and content could be empty.
+///
+/// **mac**
+///
+/// This is the MAC that was produced by rsgx_rijndael128GCMSIV_encrypt.
+///
+/// **dst**
+///
+/// A pointer to the output decrypted data buffer. This buffer should be allocated by the calling code.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// If nonce length is not equal to 12 (bytes).
+///
+/// If the source or AAD buffer is larger than 4 GB.
+///
+/// If the destination buffer is shorter than the source buffer.
+///
+/// **SGX_ERROR_MAC_MISMATCH**
+///
+/// The input MAC does not match the MAC calculated.
+///
+pub fn rsgx_rijndael128GCMSIV_decrypt(key: &sgx_aes_gcm_128bit_key_t,
+                                      src: &[u8],
+                                      nonce: &[u8],
+                                      aad: &[u8],
+                                      mac: &sgx_aes_gcm_128bit_tag_t,
+                                      dst: &mut [u8]) -> SgxError {
+    siv_decrypt(key, src, nonce, aad, mac, dst)
+}
+
+///
+/// rsgx_rijndael256GCMSIV_encrypt performs an AES-GCM-SIV encryption operation with a 256-bit key.
+///
+/// # Description
+///
+/// This is the 256-bit key counterpart of rsgx_rijndael128GCMSIV_encrypt. The nonce, AAD,
+/// output buffer and MAC conventions are identical.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// If nonce length is not equal to 12 (bytes).
+///
+/// If the source or AAD buffer is larger than 4 GB.
+///
+/// If the destination buffer is shorter than the source buffer.
+///
+pub fn rsgx_rijndael256GCMSIV_encrypt(key: &sgx_aes_gcm_256bit_key_t,
+                                      src: &[u8],
+                                      nonce: &[u8],
+                                      aad: &[u8],
+                                      dst: &mut [u8],
+                                      mac: &mut sgx_aes_gcm_128bit_tag_t) -> SgxError {
+    siv_encrypt(key, src, nonce, aad, dst, mac)
+}
+
+///
+/// rsgx_rijndael256GCMSIV_decrypt performs an AES-GCM-SIV decryption operation with a 256-bit key.
+///
+/// # Description
+///
+/// This is the 256-bit key counterpart of rsgx_rijndael128GCMSIV_decrypt. The destination
+/// buffer is zeroed if the MAC does not match.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// If nonce length is not equal to 12 (bytes).
+///
+/// If the source or AAD buffer is larger than 4 GB.
+///
+/// If the destination buffer is shorter than the source buffer.
+///
+/// **SGX_ERROR_MAC_MISMATCH**
+///
+/// The input MAC does not match the MAC calculated.
+///
+pub fn rsgx_rijndael256GCMSIV_decrypt(key: &sgx_aes_gcm_256bit_key_t,
+                                      src: &[u8],
+                                      nonce: &[u8],
+                                      aad: &[u8],
+                                      mac: &sgx_aes_gcm_128bit_tag_t,
+                                      dst: &mut [u8]) -> SgxError {
+    siv_decrypt(key, src, nonce, aad, mac, dst)
+}
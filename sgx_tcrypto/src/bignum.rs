@@ -0,0 +1,399 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! Fixed-capacity multi-precision arithmetic for the RSA operations in rsa.rs.
+//!
+//! Numbers are little-endian arrays of 64-bit limbs, at most 4096 bits wide.
+//! Reduction, Montgomery multiplication and `Mont::exp` run in time that depends
+//! only on operand lengths, so they are safe to use on private key material.
+//! Functions marked vartime must only see public values.
+//!
+use crate::internal::zeroize;
+use core::cmp::Ordering;
+
+pub(crate) const BN_MAX_LIMBS: usize = 64;
+
+pub(crate) type Limbs = [u64; BN_MAX_LIMBS];
+
+#[inline]
+fn mac(acc: u64, a: u64, b: u64, carry: u64) -> (u64, u64) {
+    let t = u128::from(acc) + u128::from(a) * u128::from(b) + u128::from(carry);
+    (t as u64, (t >> 64) as u64)
+}
+
+#[inline]
+fn adc(a: u64, b: u64, carry: u64) -> (u64, u64) {
+    let t = u128::from(a) + u128::from(b) + u128::from(carry);
+    (t as u64, (t >> 64) as u64)
+}
+
+#[inline]
+fn sbb(a: u64, b: u64, borrow: u64) -> (u64, u64) {
+    let t = u128::from(a).wrapping_sub(u128::from(b) + u128::from(borrow));
+    (t as u64, (t >> 127) as u64)
+}
+
+// All-ones if a == b, zero otherwise.
+#[inline]
+fn eq_mask(a: u64, b: u64) -> u64 {
+    let x = a ^ b;
+    ((x | x.wrapping_neg()) >> 63).wrapping_sub(1)
+}
+
+///
+/// Loads a little-endian byte string into `out`, which is zeroed first.
+/// Fails if the value does not fit.
+///
+pub(crate) fn load_le(out: &mut [u64], bytes: &[u8]) -> bool {
+    for v in out.iter_mut() {
+        *v = 0;
+    }
+    let mut overflow = 0_u8;
+    for (i, b) in bytes.iter().enumerate() {
+        if i / 8 < out.len() {
+            out[i / 8] |= u64::from(*b) << ((i % 8) * 8);
+        } else {
+            overflow |= *b;
+        }
+    }
+    overflow == 0
+}
+
+///
+/// Loads a big-endian byte string into `out`, which is zeroed first.
+/// Fails if the value does not fit.
+///
+pub(crate) fn load_be(out: &mut [u64], bytes: &[u8]) -> bool {
+    for v in out.iter_mut() {
+        *v = 0;
+    }
+    let mut overflow = 0_u8;
+    for (i, b) in bytes.iter().rev().enumerate() {
+        if i / 8 < out.len() {
+            out[i / 8] |= u64::from(*b) << ((i % 8) * 8);
+        } else {
+            overflow |= *b;
+        }
+    }
+    overflow == 0
+}
+
+///
+/// Stores `x` as a big-endian byte string filling all of `out`.
+/// Fails if the value does not fit.
+///
+pub(crate) fn store_be(x: &[u64], out: &mut [u8]) -> bool {
+    let mut overflow = 0_u64;
+    for (i, v) in x.iter().enumerate() {
+        if i * 8 >= out.len() {
+            overflow |= *v;
+        } else if out.len() - i * 8 < 8 {
+            overflow |= *v >> ((out.len() - i * 8) * 8);
+        }
+    }
+    let len = out.len();
+    for (i, b) in out.iter_mut().enumerate() {
+        let pos = len - 1 - i;
+        *b = if pos / 8 < x.len() { (x[pos / 8] >> ((pos % 8) * 8)) as u8 } else { 0 };
+    }
+    overflow == 0
+}
+
+/// Number of limbs up to and including the most significant non-zero one (vartime).
+pub(crate) fn significant_limbs(x: &[u64]) -> usize {
+    x.iter().rposition(|v| *v != 0).map_or(0, |i| i + 1)
+}
+
+/// Bit length of `x` (vartime).
+pub(crate) fn bit_len(x: &[u64]) -> usize {
+    match x.iter().rposition(|v| *v != 0) {
+        Some(i) => i * 64 + 64 - x[i].leading_zeros() as usize,
+        None => 0,
+    }
+}
+
+/// Compares two numbers of possibly different lengths (vartime).
+pub(crate) fn cmp_vartime(a: &[u64], b: &[u64]) -> Ordering {
+    let len = core::cmp::max(a.len(), b.len());
+    for i in (0..len).rev() {
+        let x = if i < a.len() { a[i] } else { 0 };
+        let y = if i < b.len() { b[i] } else { 0 };
+        match x.cmp(&y) {
+            Ordering::Equal => continue,
+            other => return other,
+        }
+    }
+    Ordering::Equal
+}
+
+/// `out = a - b` over equal lengths, returning the borrow.
+pub(crate) fn sub(a: &[u64], b: &[u64], out: &mut [u64]) -> u64 {
+    let mut borrow = 0;
+    for i in 0..out.len() {
+        let (v, br) = sbb(a[i], b[i], borrow);
+        out[i] = v;
+        borrow = br;
+    }
+    borrow
+}
+
+/// `out = a + b` over equal lengths, returning the carry.
+pub(crate) fn add(a: &[u64], b: &[u64], out: &mut [u64]) -> u64 {
+    let mut carry = 0;
+    for i in 0..out.len() {
+        let (v, c) = adc(a[i], b[i], carry);
+        out[i] = v;
+        carry = c;
+    }
+    carry
+}
+
+/// `out = if mask is all-ones { a } else { b }`.
+pub(crate) fn select(mask: u64, a: &[u64], b: &[u64], out: &mut [u64]) {
+    for i in 0..out.len() {
+        out[i] = (a[i] & mask) | (b[i] & !mask);
+    }
+}
+
+/// Schoolbook product; `out` must hold `a.len() + b.len()` limbs.
+pub(crate) fn mul(a: &[u64], b: &[u64], out: &mut [u64]) {
+    for v in out.iter_mut() {
+        *v = 0;
+    }
+    for (i, x) in a.iter().enumerate() {
+        let mut carry = 0;
+        for (j, y) in b.iter().enumerate() {
+            let (v, c) = mac(out[i + j], *x, *y, carry);
+            out[i + j] = v;
+            carry = c;
+        }
+        out[i + b.len()] = carry;
+    }
+}
+
+// r = 2 * r + bit (mod m), for r < m.
+fn double_mod(r: &mut [u64], bit: u64, m: &[u64]) {
+    let len = m.len();
+    let mut shifted = [0_u64; BN_MAX_LIMBS];
+    let mut carry = bit;
+    for i in 0..len {
+        shifted[i] = (r[i] << 1) | carry;
+        carry = r[i] >> 63;
+    }
+    let mut diff = [0_u64; BN_MAX_LIMBS];
+    let borrow = sub(&shifted[..len], m, &mut diff[..len]);
+    let use_diff = (carry | (borrow ^ 1)).wrapping_neg();
+    select(use_diff, &diff[..len], &shifted[..len], r);
+    zeroize(&mut shifted);
+    zeroize(&mut diff);
+}
+
+///
+/// `out = x mod m`, shifting `x` in one bit at a time. `out` has `m.len()` limbs
+/// and `m` must be non-zero.
+///
+pub(crate) fn reduce(x: &[u64], m: &[u64], out: &mut [u64]) {
+    for v in out.iter_mut() {
+        *v = 0;
+    }
+    for i in (0..x.len() * 64).rev() {
+        double_mod(out, (x[i / 64] >> (i % 64)) & 1, m);
+    }
+}
+
+///
+/// Montgomery context for an odd modulus.
+///
+pub(crate) struct Mont {
+    m: Limbs,
+    rr: Limbs,
+    n0: u64,
+    len: usize,
+}
+
+impl Mont {
+    ///
+    /// Builds a context for `m`, which must be odd, greater than one and at most
+    /// `BN_MAX_LIMBS` limbs long once leading zero limbs are dropped.
+    ///
+    pub(crate) fn new(m: &[u64]) -> Option<Mont> {
+        let len = significant_limbs(m);
+        if len == 0 || m[0] & 1 == 0 || (len == 1 && m[0] == 1) {
+            return None;
+        }
+
+        let mut mont = Mont {
+            m: [0_u64; BN_MAX_LIMBS],
+            rr: [0_u64; BN_MAX_LIMBS],
+            n0: 0,
+            len,
+        };
+        mont.m[..len].copy_from_slice(&m[..len]);
+
+        // Newton iteration for m[0]^-1 mod 2^64, then negate.
+        let mut inv: u64 = 1;
+        for _ in 0..6 {
+            inv = inv.wrapping_mul(2_u64.wrapping_sub(m[0].wrapping_mul(inv)));
+        }
+        mont.n0 = inv.wrapping_neg();
+
+        // R^2 mod m, with R = 2^(64 * len).
+        mont.rr[0] = 1;
+        for _ in 0..128 * len {
+            double_mod(&mut mont.rr[..len], 0, &mont.m[..len]);
+        }
+        Some(mont)
+    }
+
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub(crate) fn modulus(&self) -> &[u64] {
+        &self.m[..self.len]
+    }
+
+    // out = a * b / R mod m, for a, b < m.
+    #[allow(clippy::many_single_char_names)]
+    fn mont_mul(&self, a: &[u64], b: &[u64], out: &mut [u64]) {
+        let len = self.len;
+        let m = &self.m[..len];
+        let mut t = [0_u64; BN_MAX_LIMBS + 2];
+        for ai in a[..len].iter() {
+            let mut carry = 0;
+            for j in 0..len {
+                let (v, c) = mac(t[j], *ai, b[j], carry);
+                t[j] = v;
+                carry = c;
+            }
+            let (v, c) = adc(t[len], carry, 0);
+            t[len] = v;
+            t[len + 1] = c;
+
+            let u = t[0].wrapping_mul(self.n0);
+            let (_, mut carry) = mac(t[0], u, m[0], 0);
+            for j in 1..len {
+                let (v, c) = mac(t[j], u, m[j], carry);
+                t[j - 1] = v;
+                carry = c;
+            }
+            let (v, c) = adc(t[len], carry, 0);
+            t[len - 1] = v;
+            t[len] = t[len + 1] + c;
+        }
+
+        let mut diff = [0_u64; BN_MAX_LIMBS];
+        let borrow = sub(&t[..len], m, &mut diff[..len]);
+        let use_diff = (t[len] | (borrow ^ 1)).wrapping_neg();
+        select(use_diff, &diff[..len], &t[..len], &mut out[..len]);
+        zeroize(&mut t);
+        zeroize(&mut diff);
+    }
+
+    ///
+    /// `out = a * b mod m`, for `a, b < m`.
+    ///
+    pub(crate) fn mul_mod(&self, a: &[u64], b: &[u64], out: &mut [u64]) {
+        let len = self.len;
+        let mut t = [0_u64; BN_MAX_LIMBS];
+        self.mont_mul(a, b, &mut t[..len]);
+        self.mont_mul(&t[..len], &self.rr[..len], out);
+        zeroize(&mut t);
+    }
+
+    ///
+    /// `out = base ^ exp mod m` for `base < m`, in constant time with a fixed
+    /// 4-bit window. Every limb of `exp` is processed, so only its length leaks.
+    ///
+    pub(crate) fn exp(&self, base: &[u64], exp: &[u64], out: &mut [u64]) {
+        let len = self.len;
+        let mut one = [0_u64; BN_MAX_LIMBS];
+        one[0] = 1;
+
+        let mut table = [[0_u64; BN_MAX_LIMBS]; 16];
+        let mut acc = [0_u64; BN_MAX_LIMBS];
+        let mut tmp = [0_u64; BN_MAX_LIMBS];
+        self.mont_mul(&one[..len], &self.rr[..len], &mut acc[..len]);
+        table[0] = acc;
+        self.mont_mul(&base[..len], &self.rr[..len], &mut tmp[..len]);
+        table[1] = tmp;
+        for i in 2..16 {
+            self.mont_mul(&table[i - 1][..len], &table[1][..len], &mut tmp[..len]);
+            table[i] = tmp;
+        }
+
+        for w in (0..exp.len() * 16).rev() {
+            for _ in 0..4 {
+                self.mont_mul(&acc[..len], &acc[..len], &mut tmp[..len]);
+                acc = tmp;
+            }
+            let index = (exp[w / 16] >> ((w % 16) * 4)) & 0xf;
+            let mut entry = [0_u64; BN_MAX_LIMBS];
+            for (i, row) in table.iter().enumerate() {
+                let mask = eq_mask(i as u64, index);
+                for j in 0..len {
+                    entry[j] |= row[j] & mask;
+                }
+            }
+            self.mont_mul(&acc[..len], &entry[..len], &mut tmp[..len]);
+            acc = tmp;
+            zeroize(&mut entry);
+        }
+
+        self.mont_mul(&acc[..len], &one[..len], out);
+        for row in table.iter_mut() {
+            zeroize(row);
+        }
+        zeroize(&mut acc);
+        zeroize(&mut tmp);
+    }
+
+    ///
+    /// `out = base ^ exp mod m` for `base < m` by square-and-multiply (vartime).
+    ///
+    pub(crate) fn exp_vartime(&self, base: &[u64], exp: &[u64], out: &mut [u64]) {
+        let len = self.len;
+        let mut one = [0_u64; BN_MAX_LIMBS];
+        one[0] = 1;
+        let mut b = [0_u64; BN_MAX_LIMBS];
+        let mut acc = [0_u64; BN_MAX_LIMBS];
+        let mut tmp = [0_u64; BN_MAX_LIMBS];
+        self.mont_mul(&base[..len], &self.rr[..len], &mut b[..len]);
+        self.mont_mul(&one[..len], &self.rr[..len], &mut acc[..len]);
+
+        for i in (0..bit_len(exp)).rev() {
+            self.mont_mul(&acc[..len], &acc[..len], &mut tmp[..len]);
+            acc = tmp;
+            if (exp[i / 64] >> (i % 64)) & 1 == 1 {
+                self.mont_mul(&acc[..len], &b[..len], &mut tmp[..len]);
+                acc = tmp;
+            }
+        }
+        self.mont_mul(&acc[..len], &one[..len], out);
+    }
+}
+
+impl Drop for Mont {
+    fn drop(&mut self) {
+        zeroize(&mut self.m);
+        zeroize(&mut self.rr);
+    }
+}
@@ -0,0 +1,133 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! ChaCha20 stream cipher (RFC 8439) and the HChaCha20 subkey derivation
+//! used by XChaCha20.
+//!
+use crate::internal::zeroize;
+
+pub(crate) const CHACHA20_KEY_SIZE: usize = 32;
+pub(crate) const CHACHA20_NONCE_SIZE: usize = 12;
+pub(crate) const CHACHA20_BLOCK_SIZE: usize = 64;
+pub(crate) const HCHACHA20_NONCE_SIZE: usize = 16;
+
+const SIGMA: [u32; 4] = [0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574];
+
+#[inline]
+#[allow(clippy::many_single_char_names)]
+fn quarter_round(s: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(16);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(12);
+    s[a] = s[a].wrapping_add(s[b]);
+    s[d] = (s[d] ^ s[a]).rotate_left(8);
+    s[c] = s[c].wrapping_add(s[d]);
+    s[b] = (s[b] ^ s[c]).rotate_left(7);
+}
+
+fn double_rounds(s: &mut [u32; 16]) {
+    for _ in 0..10 {
+        quarter_round(s, 0, 4, 8, 12);
+        quarter_round(s, 1, 5, 9, 13);
+        quarter_round(s, 2, 6, 10, 14);
+        quarter_round(s, 3, 7, 11, 15);
+        quarter_round(s, 0, 5, 10, 15);
+        quarter_round(s, 1, 6, 11, 12);
+        quarter_round(s, 2, 7, 8, 13);
+        quarter_round(s, 3, 4, 9, 14);
+    }
+}
+
+fn load_words(dst: &mut [u32], src: &[u8]) {
+    for (w, chunk) in dst.iter_mut().zip(src.chunks(4)) {
+        *w = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+}
+
+///
+/// Derives an XChaCha20 subkey from the key and the first 16 bytes of the nonce.
+///
+pub(crate) fn hchacha20(key: &[u8; CHACHA20_KEY_SIZE], nonce: &[u8]) -> [u8; CHACHA20_KEY_SIZE] {
+    let mut s = [0_u32; 16];
+    s[..4].copy_from_slice(&SIGMA);
+    load_words(&mut s[4..12], key);
+    load_words(&mut s[12..16], &nonce[..HCHACHA20_NONCE_SIZE]);
+    double_rounds(&mut s);
+
+    let mut out = [0_u8; CHACHA20_KEY_SIZE];
+    for (chunk, w) in out[..16].chunks_mut(4).zip(s[..4].iter()) {
+        chunk.copy_from_slice(&w.to_le_bytes());
+    }
+    for (chunk, w) in out[16..].chunks_mut(4).zip(s[12..].iter()) {
+        chunk.copy_from_slice(&w.to_le_bytes());
+    }
+    zeroize(&mut s);
+    out
+}
+
+#[derive(Clone)]
+pub(crate) struct ChaCha20 {
+    state: [u32; 16],
+    keystream: [u8; CHACHA20_BLOCK_SIZE],
+    ks_used: usize,
+}
+
+impl ChaCha20 {
+    pub(crate) fn new(key: &[u8; CHACHA20_KEY_SIZE], nonce: &[u8; CHACHA20_NONCE_SIZE], counter: u32) -> ChaCha20 {
+        let mut state = [0_u32; 16];
+        state[..4].copy_from_slice(&SIGMA);
+        load_words(&mut state[4..12], key);
+        state[12] = counter;
+        load_words(&mut state[13..16], nonce);
+        ChaCha20 {
+            state,
+            keystream: [0_u8; CHACHA20_BLOCK_SIZE],
+            ks_used: CHACHA20_BLOCK_SIZE,
+        }
+    }
+
+    fn next_block(&mut self) {
+        let mut x = self.state;
+        double_rounds(&mut x);
+        for (i, chunk) in self.keystream.chunks_mut(4).enumerate() {
+            chunk.copy_from_slice(&x[i].wrapping_add(self.state[i]).to_le_bytes());
+        }
+        zeroize(&mut x);
+        self.state[12] = self.state[12].wrapping_add(1);
+        self.ks_used = 0;
+    }
+
+    pub(crate) fn apply_keystream(&mut self, src: &[u8], dst: &mut [u8]) {
+        for (s, d) in src.iter().zip(dst.iter_mut()) {
+            if self.ks_used == CHACHA20_BLOCK_SIZE {
+                self.next_block();
+            }
+            *d = *s ^ self.keystream[self.ks_used];
+            self.ks_used += 1;
+        }
+    }
+}
+
+impl Drop for ChaCha20 {
+    fn drop(&mut self) {
+        zeroize(&mut self.state);
+        zeroize(&mut self.keystream);
+    }
+}
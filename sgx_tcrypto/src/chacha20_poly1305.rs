@@ -0,0 +1,421 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! ChaCha20-Poly1305 and XChaCha20-Poly1305 AEAD Functions
+//!
+//! ChaCha20-Poly1305 (RFC 8439) only uses additions, rotations and XORs, so it
+//! runs in constant time without relying on AES-NI. XChaCha20-Poly1305 extends
+//! the nonce to 192 bits, which makes randomly generated nonces safe to use.
+//!
+//! The calling conventions follow `rsgx_rijndael128GCM_encrypt`,
+//! `rsgx_rijndael128GCM_decrypt` and `SgxAesHandle`.
+//!
+use sgx_types::*;
+use core::cell::{Cell, RefCell};
+use crate::chacha20::{ChaCha20, hchacha20, CHACHA20_NONCE_SIZE, HCHACHA20_NONCE_SIZE};
+use crate::poly1305::{Poly1305, POLY1305_KEY_SIZE};
+use crate::internal::{zeroize, consttime_eq};
+
+// The 32-bit block counter starts at 1 for the payload.
+const CHACHA20_MAX_TEXT_LEN: u64 = ((1_u64 << 32) - 1) * 64;
+
+pub(crate) struct ChaChaPolyContext {
+    cipher: ChaCha20,
+    mac: Poly1305,
+    aad_len: u64,
+    text_len: u64,
+}
+
+impl ChaChaPolyContext {
+    ///
+    /// Sets up ChaCha20-Poly1305 for a 12-byte nonce, or XChaCha20-Poly1305 for a 24-byte nonce.
+    ///
+    pub(crate) fn new(key: &sgx_chacha20_256bit_key_t, nonce: &[u8], aad: &[u8]) -> SgxResult<ChaChaPolyContext> {
+        let mut subkey = *key;
+        let mut chacha_nonce = [0_u8; CHACHA20_NONCE_SIZE];
+        match nonce.len() {
+            SGX_CHACHA20POLY1305_NONCE_SIZE => chacha_nonce.copy_from_slice(nonce),
+            SGX_XCHACHA20POLY1305_NONCE_SIZE => {
+                subkey = hchacha20(key, &nonce[..HCHACHA20_NONCE_SIZE]);
+                chacha_nonce[4..].copy_from_slice(&nonce[HCHACHA20_NONCE_SIZE..]);
+            },
+            _ => return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+        }
+
+        let mut otk = [0_u8; POLY1305_KEY_SIZE];
+        let mut block0 = ChaCha20::new(&subkey, &chacha_nonce, 0);
+        block0.apply_keystream(&[0_u8; POLY1305_KEY_SIZE], &mut otk);
+        let mut mac = Poly1305::new(&otk);
+        zeroize(&mut otk);
+        mac.update(aad);
+        mac.pad16();
+
+        let cipher = ChaCha20::new(&subkey, &chacha_nonce, 1);
+        zeroize(&mut subkey);
+
+        Ok(ChaChaPolyContext {
+            cipher,
+            mac,
+            aad_len: aad.len() as u64,
+            text_len: 0,
+        })
+    }
+
+    fn reserve(&mut self, len: usize) -> SgxError {
+        let total = self.text_len.checked_add(len as u64)
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+        if total > CHACHA20_MAX_TEXT_LEN {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        self.text_len = total;
+        Ok(())
+    }
+
+    pub(crate) fn encrypt_update(&mut self, src: &[u8], dst: &mut [u8]) -> SgxError {
+        if dst.len() < src.len() {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        self.reserve(src.len())?;
+        let dst = &mut dst[..src.len()];
+        self.cipher.apply_keystream(src, dst);
+        self.mac.update(dst);
+        Ok(())
+    }
+
+    ///
+    /// Computes the tag over everything absorbed so far without consuming the context.
+    ///
+    pub(crate) fn tag(&self) -> sgx_poly1305_128bit_tag_t {
+        let mut mac = self.mac.clone();
+        mac.pad16();
+        mac.update(&self.aad_len.to_le_bytes());
+        mac.update(&self.text_len.to_le_bytes());
+        mac.finalize()
+    }
+}
+
+fn check_aead_params(src: &[u8], aad: &[u8], dst: &[u8]) -> SgxError {
+    if src.len() > u32::max_value() as usize {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    if aad.len() > u32::max_value() as usize {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    if dst.len() > u32::max_value() as usize {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    if dst.len() < src.len() {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    if src.is_empty() && aad.is_empty() {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    Ok(())
+}
+
+fn chacha20_poly1305_seal(key: &sgx_chacha20_256bit_key_t,
+                          src: &[u8],
+                          nonce: &[u8],
+                          aad: &[u8],
+                          dst: &mut [u8],
+                          mac: &mut sgx_poly1305_128bit_tag_t) -> SgxError {
+
+    check_aead_params(src, aad, dst)?;
+
+    let mut ctx = ChaChaPolyContext::new(key, nonce, aad)?;
+    ctx.encrypt_update(src, dst)?;
+    *mac = ctx.tag();
+    Ok(())
+}
+
+fn chacha20_poly1305_open(key: &sgx_chacha20_256bit_key_t,
+                          src: &[u8],
+                          nonce: &[u8],
+                          aad: &[u8],
+                          mac: &sgx_poly1305_128bit_tag_t,
+                          dst: &mut [u8]) -> SgxError {
+
+    check_aead_params(src, aad, dst)?;
+
+    let mut ctx = ChaChaPolyContext::new(key, nonce, aad)?;
+    ctx.reserve(src.len())?;
+    ctx.mac.update(src);
+    if !consttime_eq(&ctx.tag(), mac) {
+        return Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH);
+    }
+    ctx.cipher.apply_keystream(src, &mut dst[..src.len()]);
+    Ok(())
+}
+
+///
+/// rsgx_chacha20_poly1305_encrypt performs a ChaCha20-Poly1305 (RFC 8439) encryption operation.
+///
+/// # Parameters
+///
+/// **key**
+///
+/// A pointer to key to be used in the encryption operation. The size must be 256 bits.
+///
+/// **src**
+///
+/// A pointer to the input data stream to be encrypted. Buffer content could be empty if there is AAD text.
+///
+/// **nonce**
+///
+/// A pointer to the nonce. The size must be 96 bits (12 bytes). A nonce must never be reused with the same key.
+///
+/// **aad**
+///
+/// A pointer to an optional additional authentication data buffer which is used in the MAC calculation.
+/// The data in this buffer will not be encrypted. The field is optional and content could be empty.
+///
+/// **dst**
+///
+/// A pointer to the output encrypted data buffer. This buffer should be allocated by the calling code.
+///
+/// **mac**
+///
+/// This is the output Poly1305 MAC performed over the ciphertext as well as the additional
+/// authentication data. The calling code should allocate this buffer.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// If both source buffer and AAD buffer content are empty.
+///
+/// If the nonce length is not equal to 12 (bytes).
+///
+/// If the destination buffer is shorter than the source buffer.
+///
+pub fn rsgx_chacha20_poly1305_encrypt(key: &sgx_chacha20_256bit_key_t,
+                                      src: &[u8],
+                                      nonce: &[u8],
+                                      aad: &[u8],
+                                      dst: &mut [u8],
+                                      mac: &mut sgx_poly1305_128bit_tag_t) -> SgxError {
+
+    if nonce.len() != SGX_CHACHA20POLY1305_NONCE_SIZE {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    chacha20_poly1305_seal(key, src, nonce, aad, dst, mac)
+}
+
+///
+/// rsgx_chacha20_poly1305_decrypt performs a ChaCha20-Poly1305 (RFC 8439) decryption operation.
+///
+/// The MAC is verified before any plaintext is written to the destination buffer.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// If both source buffer and AAD buffer content are empty.
+///
+/// If the nonce length is not equal to 12 (bytes).
+///
+/// If the destination buffer is shorter than the source buffer.
+///
+/// **SGX_ERROR_MAC_MISMATCH**
+///
+/// The input MAC does not match the MAC calculated.
+///
+pub fn rsgx_chacha20_poly1305_decrypt(key: &sgx_chacha20_256bit_key_t,
+                                      src: &[u8],
+                                      nonce: &[u8],
+                                      aad: &[u8],
+                                      mac: &sgx_poly1305_128bit_tag_t,
+                                      dst: &mut [u8]) -> SgxError {
+
+    if nonce.len() != SGX_CHACHA20POLY1305_NONCE_SIZE {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    chacha20_poly1305_open(key, src, nonce, aad, mac, dst)
+}
+
+///
+/// rsgx_xchacha20_poly1305_encrypt performs an XChaCha20-Poly1305 encryption operation.
+///
+/// # Description
+///
+/// XChaCha20-Poly1305 takes a 192-bit (24 bytes) nonce, which is large enough to be
+/// generated with rsgx_read_rand for every message. The other parameters are the same
+/// as rsgx_chacha20_poly1305_encrypt.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// If both source buffer and AAD buffer content are empty.
+///
+/// If the nonce length is not equal to 24 (bytes).
+///
+/// If the destination buffer is shorter than the source buffer.
+///
+pub fn rsgx_xchacha20_poly1305_encrypt(key: &sgx_chacha20_256bit_key_t,
+                                       src: &[u8],
+                                       nonce: &[u8],
+                                       aad: &[u8],
+                                       dst: &mut [u8],
+                                       mac: &mut sgx_poly1305_128bit_tag_t) -> SgxError {
+
+    if nonce.len() != SGX_XCHACHA20POLY1305_NONCE_SIZE {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    chacha20_poly1305_seal(key, src, nonce, aad, dst, mac)
+}
+
+///
+/// rsgx_xchacha20_poly1305_decrypt performs an XChaCha20-Poly1305 decryption operation.
+///
+/// The MAC is verified before any plaintext is written to the destination buffer.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// If both source buffer and AAD buffer content are empty.
+///
+/// If the nonce length is not equal to 24 (bytes).
+///
+/// If the destination buffer is shorter than the source buffer.
+///
+/// **SGX_ERROR_MAC_MISMATCH**
+///
+/// The input MAC does not match the MAC calculated.
+///
+pub fn rsgx_xchacha20_poly1305_decrypt(key: &sgx_chacha20_256bit_key_t,
+                                       src: &[u8],
+                                       nonce: &[u8],
+                                       aad: &[u8],
+                                       mac: &sgx_poly1305_128bit_tag_t,
+                                       dst: &mut [u8]) -> SgxError {
+
+    if nonce.len() != SGX_XCHACHA20POLY1305_NONCE_SIZE {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    chacha20_poly1305_open(key, src, nonce, aad, mac, dst)
+}
+
+///
+/// ChaCha20-Poly1305 encryption context state.
+///
+/// The handle follows `SgxAesHandle`: init, update … update, get_mac, close.
+/// A 12-byte nonce selects ChaCha20-Poly1305 and a 24-byte nonce selects
+/// XChaCha20-Poly1305. The cipher and MAC state are wiped on close and on drop.
+///
+pub struct SgxChaCha20Poly1305Handle {
+    state: RefCell<Option<ChaChaPolyContext>>,
+    initflag: Cell<bool>,
+}
+
+impl SgxChaCha20Poly1305Handle {
+
+    pub fn new() -> Self {
+        SgxChaCha20Poly1305Handle {
+            state: RefCell::new(None),
+            initflag: Cell::new(false),
+        }
+    }
+
+    ///
+    /// init sets up the key, nonce and additional authentication data.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// If the nonce length is neither 12 nor 24 (bytes), or the AAD is larger than 4 GB.
+    ///
+    pub fn init(&self, key: &sgx_chacha20_256bit_key_t, nonce: &[u8], aad: &[u8]) -> SgxError {
+        if self.initflag.get() {
+            return Ok(());
+        }
+        if aad.len() > u32::max_value() as usize {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let ctx = ChaChaPolyContext::new(key, nonce, aad)?;
+        *self.state.borrow_mut() = Some(ctx);
+        self.initflag.set(true);
+        Ok(())
+    }
+
+    ///
+    /// update encrypts the next chunk of plaintext into dst.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The source is empty or larger than 4 GB, or the destination is shorter than the source.
+    ///
+    /// **SGX_ERROR_INVALID_STATE**
+    ///
+    /// The handle is not initialized.
+    ///
+    pub fn update(&self, src: &[u8], dst: &mut [u8]) -> SgxError {
+        if !self.initflag.get() {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_STATE);
+        }
+        if src.is_empty() || src.len() > u32::max_value() as usize {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        match self.state.borrow_mut().as_mut() {
+            Some(ctx) => ctx.encrypt_update(src, dst),
+            None => Err(sgx_status_t::SGX_ERROR_INVALID_STATE),
+        }
+    }
+
+    pub fn get_mac(&self) -> SgxResult<sgx_poly1305_128bit_tag_t> {
+        if !self.initflag.get() {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_STATE);
+        }
+        match self.state.borrow().as_ref() {
+            Some(ctx) => Ok(ctx.tag()),
+            None => Err(sgx_status_t::SGX_ERROR_INVALID_STATE),
+        }
+    }
+
+    pub fn get_align_mac(&self) -> SgxResult<sgx_align_mac_128bit_t> {
+        let mut align_mac = sgx_align_mac_128bit_t::default();
+        align_mac.mac = self.get_mac()?;
+        Ok(align_mac)
+    }
+
+    pub fn close(&self) -> SgxError {
+        if !self.initflag.get() {
+            return Ok(());
+        }
+        *self.state.borrow_mut() = None;
+        self.initflag.set(false);
+        Ok(())
+    }
+}
+
+impl Default for SgxChaCha20Poly1305Handle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for SgxChaCha20Poly1305Handle {
+    fn drop(&mut self) {
+       let _ = self.close();
+    }
+}
@@ -0,0 +1,868 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! Key and Signature Encoding Functions
+//!
+//! The SGX key and signature types store integers and coordinates in
+//! little-endian order. The functions here convert them to and from the
+//! standard encodings used by X.509 libraries and tools such as OpenSSL:
+//!
+//! * SEC1 uncompressed points (`04 || X || Y`, big-endian)
+//! * DER `ECDSA-Sig-Value`
+//! * DER `SubjectPublicKeyInfo` (SPKI) for P-256 and RSA public keys
+//! * DER PKCS#8 `PrivateKeyInfo` for P-256 and RSA private keys
+//! * PEM armoring of any of the DER encodings
+//!
+//! This crate does not allocate, so variable-length encodings are written into
+//! a caller-provided buffer and the encoded length is returned.
+//!
+use sgx_types::*;
+use crate::der::*;
+use crate::internal::zeroize;
+
+/// The size of a SEC1 uncompressed P-256 point.
+pub const SGX_EC256_SEC1_POINT_SIZE: size_t = 1 + 2 * SGX_ECP256_KEY_SIZE;
+/// The size of a DER SubjectPublicKeyInfo holding a P-256 public key.
+pub const SGX_EC256_SPKI_DER_SIZE: size_t = 91;
+/// The largest DER ECDSA-Sig-Value for a P-256 signature.
+pub const SGX_EC256_SIGNATURE_DER_MAX_SIZE: size_t = 72;
+/// The largest DER PKCS#8 encoding of a P-256 private key, which is the size when the
+/// public key is included.
+pub const SGX_EC256_PKCS8_DER_MAX_SIZE: size_t = 138;
+
+pub const SGX_PEM_PUBLIC_KEY_LABEL: &str = "PUBLIC KEY";
+pub const SGX_PEM_PRIVATE_KEY_LABEL: &str = "PRIVATE KEY";
+
+// 1.2.840.10045.2.1
+const OID_EC_PUBLIC_KEY: [u8; 7] = [0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+// 1.2.840.10045.3.1.7
+const OID_PRIME256V1: [u8; 8] = [0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+// 1.2.840.113549.1.1.1
+const OID_RSA_ENCRYPTION: [u8; 9] = [0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+
+// The P-256 field prime, big-endian.
+const P256_P: [u8; SGX_ECP256_KEY_SIZE] = [
+    0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+];
+
+const EC_ALGORITHM_CONTENT_SIZE: usize = 2 + OID_EC_PUBLIC_KEY.len() + 2 + OID_PRIME256V1.len();
+const RSA_ALGORITHM_CONTENT_SIZE: usize = 2 + OID_RSA_ENCRYPTION.len() + 2;
+
+fn invalid<T>() -> SgxResult<T> {
+    Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+}
+
+// Copies a big-endian magnitude into a little-endian buffer, zero-filling the top.
+fn copy_be_to_le(src_be: &[u8], dst_le: &mut [u8]) -> SgxError {
+    if src_be.len() > dst_le.len() {
+        return invalid();
+    }
+    for (d, s) in dst_le.iter_mut().zip(src_be.iter().rev()) {
+        *d = *s;
+    }
+    for d in dst_le[src_be.len()..].iter_mut() {
+        *d = 0;
+    }
+    Ok(())
+}
+
+fn copy_le_to_be(src_le: &[u8], dst_be: &mut [u8]) {
+    for (d, s) in dst_be.iter_mut().zip(src_le.iter().rev()) {
+        *d = *s;
+    }
+}
+
+fn write_ec_algorithm(w: &mut DerWriter) -> SgxError {
+    w.write_header(TAG_SEQUENCE, EC_ALGORITHM_CONTENT_SIZE)?;
+    w.write_tlv(TAG_OID, &OID_EC_PUBLIC_KEY)?;
+    w.write_tlv(TAG_OID, &OID_PRIME256V1)
+}
+
+fn read_ec_algorithm(r: &mut DerReader) -> SgxError {
+    let mut alg = r.read_sequence()?;
+    if alg.read_tlv(TAG_OID)? != OID_EC_PUBLIC_KEY || alg.read_tlv(TAG_OID)? != OID_PRIME256V1 {
+        return invalid();
+    }
+    alg.expect_empty()
+}
+
+fn write_rsa_algorithm(w: &mut DerWriter) -> SgxError {
+    w.write_header(TAG_SEQUENCE, RSA_ALGORITHM_CONTENT_SIZE)?;
+    w.write_tlv(TAG_OID, &OID_RSA_ENCRYPTION)?;
+    w.write_tlv(TAG_NULL, &[])
+}
+
+fn read_rsa_algorithm(r: &mut DerReader) -> SgxError {
+    let mut alg = r.read_sequence()?;
+    if alg.read_tlv(TAG_OID)? != OID_RSA_ENCRYPTION {
+        return invalid();
+    }
+    // The parameters must be NULL, but some encoders omit them.
+    if !alg.is_empty() && !alg.read_tlv(TAG_NULL)?.is_empty() {
+        return invalid();
+    }
+    alg.expect_empty()
+}
+
+///
+/// rsgx_ec256_public_to_sec1 encodes a P-256 public key as a SEC1 uncompressed point,
+/// `04 || X || Y` with big-endian coordinates.
+///
+pub fn rsgx_ec256_public_to_sec1(public: &sgx_ec256_public_t) -> [u8; SGX_EC256_SEC1_POINT_SIZE] {
+    let mut point = [0_u8; SGX_EC256_SEC1_POINT_SIZE];
+    point[0] = 0x04;
+    let (x, y) = point[1..].split_at_mut(SGX_ECP256_KEY_SIZE);
+    copy_le_to_be(&public.gx, x);
+    copy_le_to_be(&public.gy, y);
+    point
+}
+
+///
+/// rsgx_ec256_public_from_sec1 decodes a SEC1 uncompressed P-256 point.
+///
+/// # Description
+///
+/// Only the uncompressed form is accepted, and both coordinates must be less than the
+/// field prime. Whether the point lies on the curve is not checked here; use
+/// `SgxEccHandle::check_point` for that before trusting a key from a peer.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The input is not a valid uncompressed point encoding.
+///
+pub fn rsgx_ec256_public_from_sec1(point: &[u8]) -> SgxResult<sgx_ec256_public_t> {
+    if point.len() != SGX_EC256_SEC1_POINT_SIZE || point[0] != 0x04 {
+        return invalid();
+    }
+    let (x, y) = point[1..].split_at(SGX_ECP256_KEY_SIZE);
+    if *x >= P256_P[..] || *y >= P256_P[..] {
+        return invalid();
+    }
+
+    let mut public = sgx_ec256_public_t::default();
+    copy_be_to_le(x, &mut public.gx)?;
+    copy_be_to_le(y, &mut public.gy)?;
+    Ok(public)
+}
+
+fn signature_component_le(words: &[uint32_t; SGX_NISTP_ECP256_KEY_SIZE]) -> [u8; SGX_ECP256_KEY_SIZE] {
+    let mut bytes = [0_u8; SGX_ECP256_KEY_SIZE];
+    for (chunk, w) in bytes.chunks_mut(4).zip(words.iter()) {
+        chunk.copy_from_slice(&w.to_le_bytes());
+    }
+    bytes
+}
+
+fn signature_component_from_be(src_be: &[u8], words: &mut [uint32_t; SGX_NISTP_ECP256_KEY_SIZE]) -> SgxError {
+    if src_be.is_empty() {
+        return invalid();
+    }
+    let mut bytes = [0_u8; SGX_ECP256_KEY_SIZE];
+    copy_be_to_le(src_be, &mut bytes)?;
+    for (w, chunk) in words.iter_mut().zip(bytes.chunks(4)) {
+        let mut b = [0_u8; 4];
+        b.copy_from_slice(chunk);
+        *w = u32::from_le_bytes(b);
+    }
+    Ok(())
+}
+
+///
+/// rsgx_ec256_signature_to_der encodes an ECDSA signature as a DER ECDSA-Sig-Value,
+/// `SEQUENCE { r INTEGER, s INTEGER }`.
+///
+/// # Parameters
+///
+/// **signature**
+///
+/// The signature, as returned by `SgxEccHandle::ecdsa_sign_msg`.
+///
+/// **der**
+///
+/// The output buffer. SGX_EC256_SIGNATURE_DER_MAX_SIZE bytes are always enough.
+///
+/// # Return value
+///
+/// The number of bytes written.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The output buffer is too small.
+///
+pub fn rsgx_ec256_signature_to_der(signature: &sgx_ec256_signature_t, der: &mut [u8]) -> SgxResult<usize> {
+    let r = signature_component_le(&signature.x);
+    let s = signature_component_le(&signature.y);
+    let content_size = tlv_size(uint_le_content_size(&r)) + tlv_size(uint_le_content_size(&s));
+
+    let mut w = DerWriter::new(der);
+    w.write_header(TAG_SEQUENCE, content_size)?;
+    w.write_uint_le(&r)?;
+    w.write_uint_le(&s)?;
+    Ok(w.position())
+}
+
+///
+/// rsgx_ec256_signature_from_der decodes a DER ECDSA-Sig-Value into an ECDSA signature
+/// that `SgxEccHandle::ecdsa_verify_msg` accepts.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The input is not valid DER, has trailing data, or r or s is zero or longer than 256 bits.
+///
+pub fn rsgx_ec256_signature_from_der(der: &[u8]) -> SgxResult<sgx_ec256_signature_t> {
+    let mut outer = DerReader::new(der);
+    let mut seq = outer.read_sequence()?;
+    outer.expect_empty()?;
+
+    let mut signature = sgx_ec256_signature_t::default();
+    signature_component_from_be(seq.read_uint()?, &mut signature.x)?;
+    signature_component_from_be(seq.read_uint()?, &mut signature.y)?;
+    seq.expect_empty()?;
+    Ok(signature)
+}
+
+///
+/// rsgx_ec256_public_to_spki_der encodes a P-256 public key as a DER SubjectPublicKeyInfo
+/// with the id-ecPublicKey algorithm and the prime256v1 named curve.
+///
+pub fn rsgx_ec256_public_to_spki_der(public: &sgx_ec256_public_t) -> [u8; SGX_EC256_SPKI_DER_SIZE] {
+    let mut der = [0_u8; SGX_EC256_SPKI_DER_SIZE];
+    let point = rsgx_ec256_public_to_sec1(public);
+    let mut w = DerWriter::new(&mut der);
+    // The buffer has exactly the right size, so none of the writes can fail.
+    let _ = w.write_header(TAG_SEQUENCE, tlv_size(EC_ALGORITHM_CONTENT_SIZE) + tlv_size(point.len() + 1))
+        .and_then(|_| write_ec_algorithm(&mut w))
+        .and_then(|_| w.write_bit_string(&point));
+    der
+}
+
+///
+/// rsgx_ec256_public_from_spki_der decodes a DER SubjectPublicKeyInfo holding a P-256
+/// public key. See rsgx_ec256_public_from_sec1 for the checks applied to the point.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The input is not valid DER, is not a P-256 key, or has trailing data.
+///
+pub fn rsgx_ec256_public_from_spki_der(der: &[u8]) -> SgxResult<sgx_ec256_public_t> {
+    let mut outer = DerReader::new(der);
+    let mut spki = outer.read_sequence()?;
+    outer.expect_empty()?;
+
+    read_ec_algorithm(&mut spki)?;
+    let point = spki.read_bit_string()?;
+    spki.expect_empty()?;
+    rsgx_ec256_public_from_sec1(point)
+}
+
+///
+/// rsgx_ec256_private_to_pkcs8_der encodes a P-256 private key as a DER PKCS#8
+/// PrivateKeyInfo wrapping a SEC1 ECPrivateKey.
+///
+/// # Parameters
+///
+/// **private**
+///
+/// The private key.
+///
+/// **public**
+///
+/// The matching public key. It is optional in the encoding, but most tools expect it.
+///
+/// **der**
+///
+/// The output buffer. SGX_EC256_PKCS8_DER_MAX_SIZE bytes are always enough.
+///
+/// # Return value
+///
+/// The number of bytes written.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The output buffer is too small. Whatever was written to it is zeroed.
+///
+pub fn rsgx_ec256_private_to_pkcs8_der(private: &sgx_ec256_private_t,
+                                       public: Option<&sgx_ec256_public_t>,
+                                       der: &mut [u8]) -> SgxResult<usize> {
+
+    let point = public.map(rsgx_ec256_public_to_sec1);
+    let public_size = point.map_or(0, |p| tlv_size(tlv_size(p.len() + 1)));
+    let ec_key_size = tlv_size(1) + tlv_size(SGX_ECP256_KEY_SIZE) + public_size;
+    let content_size = tlv_size(1) + tlv_size(EC_ALGORITHM_CONTENT_SIZE) + tlv_size(tlv_size(ec_key_size));
+
+    let mut scalar = [0_u8; SGX_ECP256_KEY_SIZE];
+    copy_le_to_be(&private.r, &mut scalar);
+
+    let mut w = DerWriter::new(der);
+    let result = (|| {
+        w.write_header(TAG_SEQUENCE, content_size)?;
+        w.write_tlv(TAG_INTEGER, &[0])?;
+        write_ec_algorithm(&mut w)?;
+        w.write_header(TAG_OCTET_STRING, tlv_size(ec_key_size))?;
+        w.write_header(TAG_SEQUENCE, ec_key_size)?;
+        w.write_tlv(TAG_INTEGER, &[1])?;
+        w.write_tlv(TAG_OCTET_STRING, &scalar)?;
+        if let Some(p) = point {
+            w.write_header(TAG_CONTEXT_1, tlv_size(p.len() + 1))?;
+            w.write_bit_string(&p)?;
+        }
+        Ok(w.position())
+    })();
+    zeroize(&mut scalar);
+    if result.is_err() {
+        let written = w.position();
+        drop(w);
+        zeroize(&mut der[..written]);
+    }
+    result
+}
+
+///
+/// rsgx_ec256_private_from_pkcs8_der decodes a DER PKCS#8 PrivateKeyInfo holding a P-256
+/// private key. An embedded public key, if any, is ignored.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The input is not valid DER, is not a P-256 key, or has trailing data.
+///
+pub fn rsgx_ec256_private_from_pkcs8_der(der: &[u8]) -> SgxResult<sgx_ec256_private_t> {
+    let mut outer = DerReader::new(der);
+    let mut info = outer.read_sequence()?;
+    outer.expect_empty()?;
+
+    if info.read_small_uint()? != 0 {
+        return invalid();
+    }
+    read_ec_algorithm(&mut info)?;
+    let mut key_der = DerReader::new(info.read_tlv(TAG_OCTET_STRING)?);
+    // Attributes may follow the key; they are not needed.
+
+    let mut ec_key = key_der.read_sequence()?;
+    key_der.expect_empty()?;
+    if ec_key.read_small_uint()? != 1 {
+        return invalid();
+    }
+    let scalar = ec_key.read_tlv(TAG_OCTET_STRING)?;
+    if scalar.len() != SGX_ECP256_KEY_SIZE {
+        return invalid();
+    }
+    if ec_key.peek_tag() == Some(TAG_CONTEXT_0) {
+        let mut params = DerReader::new(ec_key.read_tlv(TAG_CONTEXT_0)?);
+        if params.read_tlv(TAG_OID)? != OID_PRIME256V1 {
+            return invalid();
+        }
+    }
+    if ec_key.peek_tag() == Some(TAG_CONTEXT_1) {
+        ec_key.read_tlv(TAG_CONTEXT_1)?;
+    }
+    ec_key.expect_empty()?;
+
+    let mut private = sgx_ec256_private_t::default();
+    copy_be_to_le(scalar, &mut private.r)?;
+    Ok(private)
+}
+
+///
+/// rsgx_align_ec256_private_from_pkcs8_der is the same as rsgx_ec256_private_from_pkcs8_der,
+/// but returns the private key in an aligned structure.
+///
+pub fn rsgx_align_ec256_private_from_pkcs8_der(der: &[u8]) -> SgxResult<sgx_align_ec256_private_t> {
+    let mut align_private = sgx_align_ec256_private_t::default();
+    align_private.key = rsgx_ec256_private_from_pkcs8_der(der)?;
+    Ok(align_private)
+}
+
+fn rsa_public_key_size(n: &[u8], e: &[u8]) -> usize {
+    tlv_size(uint_le_content_size(n)) + tlv_size(uint_le_content_size(e))
+}
+
+///
+/// rsgx_rsa_public_to_spki_der encodes an RSA public key of any size as a DER
+/// SubjectPublicKeyInfo with the rsaEncryption algorithm.
+///
+/// # Parameters
+///
+/// **n**
+///
+/// The modulus, little-endian, as produced by rsgx_create_rsa_key_pair.
+///
+/// **e**
+///
+/// The public exponent, little-endian.
+///
+/// **der**
+///
+/// The output buffer.
+///
+/// # Return value
+///
+/// The number of bytes written.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The output buffer is too small.
+///
+pub fn rsgx_rsa_public_to_spki_der(n: &[u8], e: &[u8], der: &mut [u8]) -> SgxResult<usize> {
+    let key_size = rsa_public_key_size(n, e);
+    let content_size = tlv_size(RSA_ALGORITHM_CONTENT_SIZE) + tlv_size(tlv_size(key_size) + 1);
+
+    let mut w = DerWriter::new(der);
+    w.write_header(TAG_SEQUENCE, content_size)?;
+    write_rsa_algorithm(&mut w)?;
+    w.write_header(TAG_BIT_STRING, tlv_size(key_size) + 1)?;
+    w.write_raw(&[0])?;
+    w.write_header(TAG_SEQUENCE, key_size)?;
+    w.write_uint_le(n)?;
+    w.write_uint_le(e)?;
+    Ok(w.position())
+}
+
+///
+/// rsgx_rsa_public_from_spki_der decodes a DER SubjectPublicKeyInfo holding an RSA public key.
+///
+/// # Parameters
+///
+/// **der**
+///
+/// The encoded key.
+///
+/// **n**
+///
+/// Receives the modulus, little-endian and zero-padded. It must be large enough.
+///
+/// **e**
+///
+/// Receives the public exponent, little-endian and zero-padded. It must be large enough.
+///
+/// # Return value
+///
+/// The significant lengths of the modulus and of the exponent, in bytes.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The input is not valid DER, is not an RSA key, or an output buffer is too small.
+///
+pub fn rsgx_rsa_public_from_spki_der(der: &[u8], n: &mut [u8], e: &mut [u8]) -> SgxResult<(usize, usize)> {
+    let mut outer = DerReader::new(der);
+    let mut spki = outer.read_sequence()?;
+    outer.expect_empty()?;
+
+    read_rsa_algorithm(&mut spki)?;
+    let mut key_der = DerReader::new(spki.read_bit_string()?);
+    spki.expect_empty()?;
+    let mut key = key_der.read_sequence()?;
+    key_der.expect_empty()?;
+
+    let modulus = key.read_uint()?;
+    let exponent = key.read_uint()?;
+    key.expect_empty()?;
+    if modulus.is_empty() || exponent.is_empty() {
+        return invalid();
+    }
+    copy_be_to_le(modulus, n)?;
+    copy_be_to_le(exponent, e)?;
+    Ok((modulus.len(), exponent.len()))
+}
+
+///
+/// rsgx_rsa3072_public_to_spki_der encodes a 3072-bit RSA public key as a DER
+/// SubjectPublicKeyInfo. See rsgx_rsa_public_to_spki_der.
+///
+pub fn rsgx_rsa3072_public_to_spki_der(public: &sgx_rsa3072_public_key_t, der: &mut [u8]) -> SgxResult<usize> {
+    rsgx_rsa_public_to_spki_der(&public.modulus, &public.exponent, der)
+}
+
+///
+/// rsgx_rsa3072_public_from_spki_der decodes a DER SubjectPublicKeyInfo holding an RSA
+/// public key with a modulus of exactly 3072 bits.
+///
+pub fn rsgx_rsa3072_public_from_spki_der(der: &[u8]) -> SgxResult<sgx_rsa3072_public_key_t> {
+    let mut public = sgx_rsa3072_public_key_t::default();
+    let (n_len, _) = rsgx_rsa_public_from_spki_der(der, &mut public.modulus, &mut public.exponent)?;
+    if n_len != SGX_RSA3072_KEY_SIZE {
+        return invalid();
+    }
+    Ok(public)
+}
+
+///
+/// rsgx_rsa_key_pair_to_pkcs8_der encodes an RSA private key as a DER PKCS#8
+/// PrivateKeyInfo wrapping a PKCS#1 RSAPrivateKey.
+///
+/// # Description
+///
+/// The RSAPrivateKey structure requires the CRT components, so this takes the full
+/// output of rsgx_create_rsa_key_pair. All components are little-endian. An
+/// `sgx_rsa3072_key_t` alone cannot be exported because it lacks them.
+///
+/// # Return value
+///
+/// The number of bytes written.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The output buffer is too small. Whatever was written to it is zeroed.
+///
+#[allow(clippy::many_single_char_names)]
+pub fn rsgx_rsa_key_pair_to_pkcs8_der(n: &[u8],
+                                      d: &[u8],
+                                      e: &[u8],
+                                      p: &[u8],
+                                      q: &[u8],
+                                      dmp1: &[u8],
+                                      dmq1: &[u8],
+                                      iqmp: &[u8],
+                                      der: &mut [u8]) -> SgxResult<usize> {
+
+    let components = [n, e, d, p, q, dmp1, dmq1, iqmp];
+    let key_size = tlv_size(1) + components.iter().map(|c| tlv_size(uint_le_content_size(c))).sum::<usize>();
+    let content_size = tlv_size(1) + tlv_size(RSA_ALGORITHM_CONTENT_SIZE) + tlv_size(tlv_size(key_size));
+
+    let mut w = DerWriter::new(der);
+    let result = (|| {
+        w.write_header(TAG_SEQUENCE, content_size)?;
+        w.write_tlv(TAG_INTEGER, &[0])?;
+        write_rsa_algorithm(&mut w)?;
+        w.write_header(TAG_OCTET_STRING, tlv_size(key_size))?;
+        w.write_header(TAG_SEQUENCE, key_size)?;
+        w.write_tlv(TAG_INTEGER, &[0])?;
+        for c in components.iter() {
+            w.write_uint_le(c)?;
+        }
+        Ok(w.position())
+    })();
+    if result.is_err() {
+        let written = w.position();
+        drop(w);
+        zeroize(&mut der[..written]);
+    }
+    result
+}
+
+///
+/// rsgx_rsa_key_pair_from_pkcs8_der decodes a DER PKCS#8 PrivateKeyInfo holding an RSA
+/// private key into little-endian, zero-padded component buffers, laid out like the
+/// output of rsgx_create_rsa_key_pair.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The input is not valid DER, is not a two-prime RSA key, or a component does not fit its
+/// buffer. The buffers are zeroed on failure.
+///
+#[allow(clippy::many_single_char_names)]
+pub fn rsgx_rsa_key_pair_from_pkcs8_der(der: &[u8],
+                                        n: &mut [u8],
+                                        d: &mut [u8],
+                                        e: &mut [u8],
+                                        p: &mut [u8],
+                                        q: &mut [u8],
+                                        dmp1: &mut [u8],
+                                        dmq1: &mut [u8],
+                                        iqmp: &mut [u8]) -> SgxError {
+
+    let mut outputs = [n, e, d, p, q, dmp1, dmq1, iqmp];
+    let result = (|| {
+        let mut key = read_rsa_pkcs8(der)?;
+        for out in outputs.iter_mut() {
+            copy_be_to_le(key.read_uint()?, out)?;
+        }
+        key.expect_empty()
+    })();
+    if result.is_err() {
+        for out in outputs.iter_mut() {
+            zeroize(out);
+        }
+    }
+    result
+}
+
+// Returns a reader positioned after the version of the inner RSAPrivateKey.
+fn read_rsa_pkcs8(der: &[u8]) -> SgxResult<DerReader> {
+    let mut outer = DerReader::new(der);
+    let mut info = outer.read_sequence()?;
+    outer.expect_empty()?;
+
+    if info.read_small_uint()? != 0 {
+        return invalid();
+    }
+    read_rsa_algorithm(&mut info)?;
+    let mut key_der = DerReader::new(info.read_tlv(TAG_OCTET_STRING)?);
+    let mut key = key_der.read_sequence()?;
+    key_der.expect_empty()?;
+    // Version 1 would mean a multi-prime key, which the SGX types cannot hold.
+    if key.read_small_uint()? != 0 {
+        return invalid();
+    }
+    Ok(key)
+}
+
+///
+/// rsgx_rsa3072_key_from_pkcs8_der decodes a DER PKCS#8 PrivateKeyInfo holding a 3072-bit
+/// RSA private key, keeping the modulus and both exponents.
+///
+pub fn rsgx_rsa3072_key_from_pkcs8_der(der: &[u8]) -> SgxResult<sgx_rsa3072_key_t> {
+    let mut key = sgx_rsa3072_key_t::default();
+    let result = (|| {
+        let mut reader = read_rsa_pkcs8(der)?;
+        let modulus = reader.read_uint()?;
+        if modulus.len() != SGX_RSA3072_KEY_SIZE {
+            return invalid();
+        }
+        copy_be_to_le(modulus, &mut key.modulus)?;
+        copy_be_to_le(reader.read_uint()?, &mut key.e)?;
+        copy_be_to_le(reader.read_uint()?, &mut key.d)?;
+        for _ in 0..5 {
+            reader.read_uint()?;
+        }
+        reader.expect_empty()
+    })();
+    match result {
+        Ok(_) => Ok(key),
+        Err(err) => {
+            zeroize(&mut key.d);
+            Err(err)
+        },
+    }
+}
+
+const PEM_BEGIN: &[u8] = b"-----BEGIN ";
+const PEM_END: &[u8] = b"-----END ";
+const PEM_DASHES: &[u8] = b"-----";
+const PEM_LINE_SIZE: usize = 64;
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+///
+/// rsgx_pem_encoded_len returns the size of the PEM encoding of der_len bytes of DER
+/// under the given label.
+///
+pub fn rsgx_pem_encoded_len(label: &str, der_len: usize) -> usize {
+    let base64_len = (der_len + 2) / 3 * 4;
+    let lines = (base64_len + PEM_LINE_SIZE - 1) / PEM_LINE_SIZE;
+    PEM_BEGIN.len() + label.len() + PEM_DASHES.len() + 1
+        + base64_len + lines
+        + PEM_END.len() + label.len() + PEM_DASHES.len() + 1
+}
+
+///
+/// rsgx_pem_encode wraps DER in PEM armor, with 64-character base64 lines.
+///
+/// # Parameters
+///
+/// **label**
+///
+/// The PEM label, such as SGX_PEM_PUBLIC_KEY_LABEL or SGX_PEM_PRIVATE_KEY_LABEL.
+///
+/// **der**
+///
+/// The DER encoding.
+///
+/// **pem**
+///
+/// The output buffer, at least rsgx_pem_encoded_len(label, der.len()) bytes.
+///
+/// # Return value
+///
+/// The number of bytes written.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The output buffer is too small.
+///
+pub fn rsgx_pem_encode(label: &str, der: &[u8], pem: &mut [u8]) -> SgxResult<usize> {
+    let total = rsgx_pem_encoded_len(label, der.len());
+    if pem.len() < total {
+        return invalid();
+    }
+
+    let mut pos = 0;
+    let mut put = |bytes: &[u8]| {
+        pem[pos..pos + bytes.len()].copy_from_slice(bytes);
+        pos += bytes.len();
+    };
+    put(PEM_BEGIN);
+    put(label.as_bytes());
+    put(PEM_DASHES);
+    put(b"\n");
+
+    let mut column = 0;
+    for chunk in der.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let quad = [BASE64_ALPHABET[(b[0] >> 2) as usize],
+                    BASE64_ALPHABET[(((b[0] & 0x03) << 4) | (b[1] >> 4)) as usize],
+                    if chunk.len() > 1 { BASE64_ALPHABET[(((b[1] & 0x0f) << 2) | (b[2] >> 6)) as usize] } else { b'=' },
+                    if chunk.len() > 2 { BASE64_ALPHABET[(b[2] & 0x3f) as usize] } else { b'=' }];
+        put(&quad);
+        column += 4;
+        if column == PEM_LINE_SIZE {
+            put(b"\n");
+            column = 0;
+        }
+    }
+    if column != 0 {
+        put(b"\n");
+    }
+
+    put(PEM_END);
+    put(label.as_bytes());
+    put(PEM_DASHES);
+    put(b"\n");
+    Ok(total)
+}
+
+fn base64_value(c: u8) -> Option<u8> {
+    match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+// Returns the base64 body between the BEGIN and END lines of the first block with the label.
+fn pem_body<'a>(label: &str, pem: &'a [u8]) -> SgxResult<&'a [u8]> {
+    let label = label.as_bytes();
+    let mut rest = pem;
+    loop {
+        let start = find(rest, PEM_BEGIN).ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+        rest = &rest[start + PEM_BEGIN.len()..];
+        if rest.starts_with(label) && rest[label.len()..].starts_with(PEM_DASHES) {
+            rest = &rest[label.len() + PEM_DASHES.len()..];
+            break;
+        }
+    }
+    let end = find(rest, PEM_END).ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+    let trailer = &rest[end + PEM_END.len()..];
+    if !(trailer.starts_with(label) && trailer[label.len()..].starts_with(PEM_DASHES)) {
+        return invalid();
+    }
+    Ok(&rest[..end])
+}
+
+///
+/// rsgx_pem_decode extracts the DER from the first PEM block with the given label.
+///
+/// # Description
+///
+/// Text before the BEGIN line is ignored, so a file holding several blocks can be searched
+/// by label. Whitespace inside the base64 body is skipped; anything else that is not
+/// base64 is rejected.
+///
+/// # Parameters
+///
+/// **label**
+///
+/// The PEM label to look for.
+///
+/// **pem**
+///
+/// The PEM text.
+///
+/// **der**
+///
+/// The output buffer.
+///
+/// # Return value
+///
+/// The number of bytes written.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// No block with the label was found, the base64 is malformed, or the output buffer is
+/// too small.
+///
+pub fn rsgx_pem_decode(label: &str, pem: &[u8], der: &mut [u8]) -> SgxResult<usize> {
+    let body = pem_body(label, pem)?;
+
+    let mut quad = [0_u8; 4];
+    let mut filled = 0;
+    let mut padding = 0;
+    let mut pos = 0;
+    for &c in body.iter() {
+        if c == b' ' || c == b'\t' || c == b'\r' || c == b'\n' {
+            continue;
+        }
+        if c == b'=' {
+            padding += 1;
+            if filled + padding > 4 || filled < 2 {
+                return invalid();
+            }
+            continue;
+        }
+        // No data may follow padding.
+        if padding != 0 {
+            return invalid();
+        }
+        quad[filled] = base64_value(c).ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+        filled += 1;
+        if filled == 4 {
+            let bytes = [(quad[0] << 2) | (quad[1] >> 4), (quad[1] << 4) | (quad[2] >> 2), (quad[2] << 6) | quad[3]];
+            if pos + 3 > der.len() {
+                return invalid();
+            }
+            der[pos..pos + 3].copy_from_slice(&bytes);
+            pos += 3;
+            filled = 0;
+        }
+    }
+
+    if filled == 0 && padding == 0 {
+        return Ok(pos);
+    }
+    if filled + padding != 4 {
+        return invalid();
+    }
+    let bytes = [(quad[0] << 2) | (quad[1] >> 4), (quad[1] << 4) | (quad[2] >> 2)];
+    let n = filled - 1;
+    if pos + n > der.len() {
+        return invalid();
+    }
+    der[pos..pos + n].copy_from_slice(&bytes[..n]);
+    Ok(pos + n)
+}
@@ -24,6 +24,8 @@ use core::ops::{Drop, DerefMut};
 use core::ptr;
 use core::mem;
 use core::cell::{Cell, RefCell};
+use crate::rsa::{RsaPrivateKey, RsaPublicKey};
+use crate::secret::{EcPrivateKey, EcSharedKey};
 
 ///
 /// The rsgx_sha256_msg function performs a standard SHA256 hash over the input data buffer.
@@ -1409,6 +1411,17 @@ fn rsgx_hmac256_init(key: &sgx_hmac_256bit_key_t, hmac_handle: &mut sgx_hmac_sta
     }
 }
 
+fn rsgx_hmac256_init_slice(key: &[u8], hmac_handle: &mut sgx_hmac_state_handle_t) -> sgx_status_t {
+    if key.is_empty() || key.len() > i32::max_value() as usize {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    unsafe {
+        sgx_hmac256_init(key.as_ptr(),
+                         key.len() as i32,
+                         hmac_handle as * mut sgx_hmac_state_handle_t)
+    }
+}
+
 fn rsgx_hmac256_update_msg<T>(src: &T, hmac_handle: sgx_hmac_state_handle_t) -> sgx_status_t
     where T: Copy + ContiguousMemory {
 
@@ -1480,6 +1493,24 @@ impl SgxHmacHandle {
         }
     }
 
+    ///
+    /// Same as init, but accepts a key of any non-zero length, as HMAC itself does.
+    ///
+    pub(crate) fn init_slice(&self, key: &[u8]) -> SgxError {
+        if self.initflag.get() {
+            return Ok(());
+        }
+
+        let ret = rsgx_hmac256_init_slice(key, self.handle.borrow_mut().deref_mut());
+        match ret {
+            sgx_status_t::SGX_SUCCESS => {
+                self.initflag.set(true);
+                Ok(())
+            },
+            _ => Err(ret),
+        }
+    }
+
     pub fn update_msg<T>(&self, src: &T) -> SgxError
         where T: Copy + ContiguousMemory {
 
@@ -2126,6 +2157,25 @@ impl SgxEccHandle {
         }
     }
 
+    ///
+    /// create_secret_key_pair is the same as create_key_pair, but generates the private key
+    /// directly inside a zeroizing EcPrivateKey, so no copy of it is left on the stack.
+    ///
+    pub fn create_secret_key_pair(&self) -> SgxResult<(EcPrivateKey, sgx_ec256_public_t)> {
+        if !self.initflag.get() {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_STATE);
+        }
+
+        let mut private = EcPrivateKey::new()?;
+        let mut public = sgx_ec256_public_t::default();
+        let ret = rsgx_ecc256_create_key_pair(&mut private, &mut public, *self.handle.borrow());
+
+        match ret {
+            sgx_status_t::SGX_SUCCESS => Ok((private, public)),
+            _ => Err(ret),
+        }
+    }
+
     ///
     /// check_point checks whether the input point is a valid point on the ECC curve for the given cryptographic system.
     ///
@@ -2308,6 +2358,23 @@ impl SgxEccHandle {
         }
     }
 
+    ///
+    /// compute_secret_shared_dhkey is the same as compute_shared_dhkey, but writes the shared
+    /// secret directly into a zeroizing EcSharedKey.
+    ///
+    pub fn compute_secret_shared_dhkey(&self, private_b: &sgx_ec256_private_t, public_ga: &sgx_ec256_public_t) -> SgxResult<EcSharedKey> {
+        if !self.initflag.get() {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_STATE);
+        }
+
+        let mut shared_key = EcSharedKey::new()?;
+        let ret = rsgx_ecc256_compute_shared_dhkey(private_b, public_ga, &mut shared_key, *self.handle.borrow());
+        match ret {
+            sgx_status_t::SGX_SUCCESS => Ok(shared_key),
+            _ => Err(ret),
+        }
+    }
+
     /* delete (intel sgx sdk 2.0)
     pub fn compute_shared_dhkey512(&self, private_b: &sgx_ec256_private_t, public_ga: &sgx_ec256_public_t) -> SgxResult<sgx_ec256_dh_shared512_t> {
 
@@ -3044,8 +3111,22 @@ fn rsgx_rsa_pub_encrypt_sha256(rsa_key: sgx_rsa_key_t,
     }
 }
 
+// Handles keep the key components for the padding modes implemented in rsa.rs
+// only when the sizes are valid and the modulus fits SGX_RSA_MAX_MOD_SIZE.
+fn rsa_keeps_components(mod_size: i32, exp_size: i32, priv_exp_size: i32) -> bool {
+    mod_size > 0 && exp_size > 0 && priv_exp_size > 0 && mod_size as usize <= SGX_RSA_MAX_MOD_SIZE
+}
+
+///
+/// RSA private key handle.
+///
+/// Besides the libsgx_tcrypto.a key used by `decrypt_sha256`, a handle whose modulus is at
+/// most SGX_RSA_MAX_MOD_SIZE bytes keeps the key components for RSASSA-PSS and
+/// RSASSA-PKCS1-v1_5 signing and RSAES-OAEP decryption with SHA-1, SHA-256 or SHA-384.
+///
 pub struct SgxRsaPrivKey {
     key: RefCell<sgx_rsa_key_t>,
+    rsa: RefCell<Option<RsaPrivateKey>>,
     mod_size: Cell<i32>,
     exp_size: Cell<i32>,
     createflag: Cell<bool>,
@@ -3056,6 +3137,7 @@ impl SgxRsaPrivKey {
     pub fn new() -> Self {
         SgxRsaPrivKey {
             key: RefCell::new(ptr::null_mut() as sgx_rsa_key_t),
+            rsa: RefCell::new(None),
             mod_size: Cell::new(0),
             exp_size: Cell::new(0),
             createflag: Cell::new(false),
@@ -3088,6 +3170,12 @@ impl SgxRsaPrivKey {
             return Ok(());
         }
 
+        let rsa = if rsa_keeps_components(mod_size, exp_size, exp_size) {
+            Some(RsaPrivateKey::from_crt(mod_size as usize, exp_size as usize, e, p, q, dmp1, dmq1, iqmp)?)
+        } else {
+            None
+        };
+
         let ret = rsgx_create_rsa_priv2_key(mod_size,
                                             exp_size,
                                             e,
@@ -3099,6 +3187,7 @@ impl SgxRsaPrivKey {
                                             self.key.borrow_mut().deref_mut());
         match ret {
             sgx_status_t::SGX_SUCCESS => {
+                *self.rsa.borrow_mut() = rsa;
                 self.mod_size.set(mod_size);
                 self.exp_size.set(exp_size);
                 self.createflag.set(true);
@@ -3119,6 +3208,17 @@ impl SgxRsaPrivKey {
             return Ok(());
         }
 
+        let rsa = if rsa_keeps_components(mod_size, exp_size, priv_exp_size) {
+            Some(RsaPrivateKey::from_exponent(mod_size as usize,
+                                              exp_size as usize,
+                                              priv_exp_size as usize,
+                                              n,
+                                              e,
+                                              d)?)
+        } else {
+            None
+        };
+
         let ret = rsgx_create_rsa_priv1_key(mod_size,
                                             exp_size,
                                             priv_exp_size,
@@ -3128,6 +3228,7 @@ impl SgxRsaPrivKey {
                                             self.key.borrow_mut().deref_mut());
         match ret {
             sgx_status_t::SGX_SUCCESS => {
+                *self.rsa.borrow_mut() = rsa;
                 self.mod_size.set(mod_size);
                 self.exp_size.set(exp_size);
                 self.createflag.set(true);
@@ -3157,6 +3258,184 @@ impl SgxRsaPrivKey {
         }
     }
 
+    ///
+    /// sign_pkcs1_v15 computes an RSASSA-PKCS1-v1_5 signature over the input data.
+    ///
+    /// # Description
+    ///
+    /// The data is hashed with the selected algorithm and encoded as described in
+    /// PKCS #1 v2.2 (RFC 8017), section 9.2. The private key operation is checked
+    /// against the public exponent before the signature is released.
+    ///
+    /// # Parameters
+    ///
+    /// **hash**
+    ///
+    /// The hash algorithm: SGX_RSA_HASH_SHA1, SGX_RSA_HASH_SHA256 or SGX_RSA_HASH_SHA384.
+    ///
+    /// **data**
+    ///
+    /// The data to be signed. It may be empty.
+    ///
+    /// **signature**
+    ///
+    /// The buffer receiving the signature. Its length must equal the modulus size.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_STATE**
+    ///
+    /// The key has not been created.
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The signature buffer has the wrong size, the modulus is too small for the encoding,
+    /// or the modulus is larger than SGX_RSA_MAX_MOD_SIZE.
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// The signature does not verify under the public exponent.
+    ///
+    pub fn sign_pkcs1_v15(&self, hash: sgx_rsa_hash_t, data: &[u8], signature: &mut [u8]) -> SgxError {
+        if !self.createflag.get() {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_STATE);
+        }
+
+        match *self.rsa.borrow() {
+            Some(ref rsa) => rsa.sign_pkcs1_v15(hash, data, signature),
+            None => Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+        }
+    }
+
+    ///
+    /// sign_pss computes an RSASSA-PSS signature over the input data.
+    ///
+    /// # Description
+    ///
+    /// The data is hashed with the selected algorithm and encoded as described in
+    /// PKCS #1 v2.2 (RFC 8017), section 9.1, using MGF1 with the same hash and a
+    /// random salt as long as the digest.
+    ///
+    /// # Parameters
+    ///
+    /// **hash**
+    ///
+    /// The hash algorithm: SGX_RSA_HASH_SHA1, SGX_RSA_HASH_SHA256 or SGX_RSA_HASH_SHA384.
+    ///
+    /// **data**
+    ///
+    /// The data to be signed. It may be empty.
+    ///
+    /// **signature**
+    ///
+    /// The buffer receiving the signature. Its length must equal the modulus size.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a, libsgx_trts.a
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_STATE**
+    ///
+    /// The key has not been created.
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The signature buffer has the wrong size, the modulus is too small for the encoding,
+    /// or the modulus is larger than SGX_RSA_MAX_MOD_SIZE.
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// Random number generation failed, or the signature does not verify under the public exponent.
+    ///
+    pub fn sign_pss(&self, hash: sgx_rsa_hash_t, data: &[u8], signature: &mut [u8]) -> SgxError {
+        if !self.createflag.get() {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_STATE);
+        }
+
+        match *self.rsa.borrow() {
+            Some(ref rsa) => rsa.sign_pss(hash, data, signature),
+            None => Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+        }
+    }
+
+    ///
+    /// decrypt_oaep decrypts an RSAES-OAEP ciphertext with the selected hash and an empty label.
+    ///
+    /// # Description
+    ///
+    /// The semantics of out_len follow decrypt_sha256. If out_len is 0, it is set to the
+    /// largest message the key can carry and nothing is decrypted. Otherwise out_data.len()
+    /// must equal out_len, and on success out_len is set to the length of the message.
+    /// The padding is checked in constant time and all padding failures are reported
+    /// alike.
+    ///
+    /// # Parameters
+    ///
+    /// **hash**
+    ///
+    /// The hash algorithm used for OAEP and MGF1.
+    ///
+    /// **out_data**
+    ///
+    /// The buffer receiving the message.
+    ///
+    /// **out_len**
+    ///
+    /// The size of out_data on input, the length of the message on output.
+    ///
+    /// **in_data**
+    ///
+    /// The ciphertext, which must be as long as the modulus.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_STATE**
+    ///
+    /// The key has not been created.
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The buffers have the wrong size, or the modulus is larger than SGX_RSA_MAX_MOD_SIZE.
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// The ciphertext does not decrypt to a valid OAEP encoding.
+    ///
+    pub fn decrypt_oaep(&self,
+                        hash: sgx_rsa_hash_t,
+                        out_data: &mut [u8],
+                        out_len: &mut usize,
+                        in_data: &[u8]) -> SgxError {
+        if !self.createflag.get() {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_STATE);
+        }
+        if *out_len != 0 && out_data.len() != *out_len {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        match *self.rsa.borrow() {
+            Some(ref rsa) => {
+                if *out_len == 0 {
+                    *out_len = rsa.oaep_max_message_size(hash)?;
+                    return Ok(());
+                }
+                *out_len = rsa.decrypt_oaep(hash, in_data, out_data)?;
+                Ok(())
+            },
+            None => Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+        }
+    }
+
     pub fn free(&self) -> SgxError {
         if !self.createflag.get() {
             return Ok(());
@@ -3178,6 +3457,7 @@ impl SgxRsaPrivKey {
             sgx_status_t::SGX_SUCCESS => {
                 self.createflag.set(false);
                 *self.key.borrow_mut() = ptr::null_mut();
+                *self.rsa.borrow_mut() = None;
                 Ok(())
             },
             _ => Err(ret),
@@ -3197,8 +3477,16 @@ impl Drop for SgxRsaPrivKey {
     }
 }
 
+///
+/// RSA public key handle.
+///
+/// Besides the libsgx_tcrypto.a key used by `encrypt_sha256`, a handle whose modulus is at
+/// most SGX_RSA_MAX_MOD_SIZE bytes keeps the modulus and exponent for RSASSA-PSS and
+/// RSASSA-PKCS1-v1_5 verification and RSAES-OAEP encryption with SHA-1, SHA-256 or SHA-384.
+///
 pub struct SgxRsaPubKey {
     key: RefCell<sgx_rsa_key_t>,
+    rsa: RefCell<Option<RsaPublicKey>>,
     mod_size: Cell<i32>,
     exp_size: Cell<i32>,
     createflag: Cell<bool>,
@@ -3209,6 +3497,7 @@ impl SgxRsaPubKey {
     pub fn new() -> Self {
         SgxRsaPubKey {
             key: RefCell::new(ptr::null_mut() as sgx_rsa_key_t),
+            rsa: RefCell::new(None),
             mod_size: Cell::new(0),
             exp_size: Cell::new(0),
             createflag: Cell::new(false),
@@ -3224,6 +3513,12 @@ impl SgxRsaPubKey {
             return Ok(());
         }
 
+        let rsa = if rsa_keeps_components(mod_size, exp_size, exp_size) {
+            Some(RsaPublicKey::new(mod_size as usize, exp_size as usize, n, e)?)
+        } else {
+            None
+        };
+
         let ret = rsgx_create_rsa_pub1_key(mod_size,
                                            exp_size,
                                            n,
@@ -3231,6 +3526,7 @@ impl SgxRsaPubKey {
                                            self.key.borrow_mut().deref_mut());
         match ret {
             sgx_status_t::SGX_SUCCESS => {
+                *self.rsa.borrow_mut() = rsa;
                 self.mod_size.set(mod_size);
                 self.exp_size.set(exp_size);
                 self.createflag.set(true);
@@ -3260,6 +3556,192 @@ impl SgxRsaPubKey {
         }
     }
 
+    ///
+    /// verify_pkcs1_v15 verifies an RSASSA-PKCS1-v1_5 signature over the input data.
+    ///
+    /// # Description
+    ///
+    /// The expected encoding is rebuilt from the data and compared with the whole
+    /// recovered block, so no parsing of attacker-controlled DigestInfo takes place.
+    ///
+    /// # Parameters
+    ///
+    /// **hash**
+    ///
+    /// The hash algorithm: SGX_RSA_HASH_SHA1, SGX_RSA_HASH_SHA256 or SGX_RSA_HASH_SHA384.
+    ///
+    /// **data**
+    ///
+    /// The signed data.
+    ///
+    /// **signature**
+    ///
+    /// The signature to check.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Return value
+    ///
+    /// **true**
+    ///
+    /// The signature is valid.
+    ///
+    /// **false**
+    ///
+    /// The signature is not valid.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_STATE**
+    ///
+    /// The key has not been created.
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The modulus is too small for the encoding or larger than SGX_RSA_MAX_MOD_SIZE.
+    ///
+    pub fn verify_pkcs1_v15(&self, hash: sgx_rsa_hash_t, data: &[u8], signature: &[u8]) -> SgxResult<bool> {
+        if !self.createflag.get() {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_STATE);
+        }
+
+        match *self.rsa.borrow() {
+            Some(ref rsa) => rsa.verify_pkcs1_v15(hash, data, signature),
+            None => Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+        }
+    }
+
+    ///
+    /// verify_pss verifies an RSASSA-PSS signature over the input data.
+    ///
+    /// # Description
+    ///
+    /// MGF1 uses the same hash as the message digest. The salt length is taken from the
+    /// encoding, so signatures made with any salt length are accepted.
+    ///
+    /// # Parameters
+    ///
+    /// **hash**
+    ///
+    /// The hash algorithm: SGX_RSA_HASH_SHA1, SGX_RSA_HASH_SHA256 or SGX_RSA_HASH_SHA384.
+    ///
+    /// **data**
+    ///
+    /// The signed data.
+    ///
+    /// **signature**
+    ///
+    /// The signature to check.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a
+    ///
+    /// # Return value
+    ///
+    /// **true**
+    ///
+    /// The signature is valid.
+    ///
+    /// **false**
+    ///
+    /// The signature is not valid.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_STATE**
+    ///
+    /// The key has not been created.
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The modulus is too small for the encoding or larger than SGX_RSA_MAX_MOD_SIZE.
+    ///
+    pub fn verify_pss(&self, hash: sgx_rsa_hash_t, data: &[u8], signature: &[u8]) -> SgxResult<bool> {
+        if !self.createflag.get() {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_STATE);
+        }
+
+        match *self.rsa.borrow() {
+            Some(ref rsa) => rsa.verify_pss(hash, data, signature),
+            None => Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+        }
+    }
+
+    ///
+    /// encrypt_oaep encrypts data with RSAES-OAEP using the selected hash and an empty label.
+    ///
+    /// # Description
+    ///
+    /// The semantics of out_len follow encrypt_sha256. If out_len is 0, it is set to the
+    /// modulus size and nothing is encrypted. Otherwise out_data.len() must equal out_len and
+    /// be at least the modulus size, and on success out_len is set to the modulus size.
+    ///
+    /// # Parameters
+    ///
+    /// **hash**
+    ///
+    /// The hash algorithm used for OAEP and MGF1.
+    ///
+    /// **out_data**
+    ///
+    /// The buffer receiving the ciphertext.
+    ///
+    /// **out_len**
+    ///
+    /// The size of out_data on input, the length of the ciphertext on output.
+    ///
+    /// **in_data**
+    ///
+    /// The message. It may be at most the modulus size minus twice the digest size minus 2 bytes.
+    ///
+    /// # Requirements
+    ///
+    /// Library: libsgx_tcrypto.a, libsgx_trts.a
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_STATE**
+    ///
+    /// The key has not been created.
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The buffers have the wrong size, the message is too long, or the modulus is larger
+    /// than SGX_RSA_MAX_MOD_SIZE.
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// Random number generation failed.
+    ///
+    pub fn encrypt_oaep(&self,
+                        hash: sgx_rsa_hash_t,
+                        out_data: &mut [u8],
+                        out_len: &mut usize,
+                        in_data: &[u8]) -> SgxError {
+        if !self.createflag.get() {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_STATE);
+        }
+        if *out_len != 0 && out_data.len() != *out_len {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        match *self.rsa.borrow() {
+            Some(ref rsa) => {
+                if *out_len == 0 {
+                    *out_len = rsa.size();
+                    return Ok(());
+                }
+                rsa.encrypt_oaep(hash, in_data, out_data)?;
+                *out_len = rsa.size();
+                Ok(())
+            },
+            None => Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+        }
+    }
+
     pub fn free(&self) -> SgxError {
         if !self.createflag.get() {
             return Ok(());
@@ -3281,6 +3763,7 @@ impl SgxRsaPubKey {
             sgx_status_t::SGX_SUCCESS => {
                 self.createflag.set(false);
                 *self.key.borrow_mut() = ptr::null_mut();
+                *self.rsa.borrow_mut() = None;
                 Ok(())
             },
             _ => Err(ret),
@@ -0,0 +1,277 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! Ed25519 Signature Functions
+//!
+//! Ed25519 (RFC 8032) signing and verification. libsgx_tcrypto.a only offers
+//! NIST P-256 through `SgxEccHandle`, so the curve arithmetic is implemented
+//! here in portable, constant-time Rust. Ed25519 needs no context, so these are
+//! free functions rather than a handle.
+//!
+use sgx_types::*;
+use sgx_types::marker::ContiguousMemory;
+use crate::curve25519::{EdwardsPoint, scalar_reduce, scalar_muladd, scalar_is_canonical};
+use crate::sha2::Sha512Context;
+use crate::internal::{zeroize, read_rand, msg_bytes, slice_bytes};
+use core::mem;
+
+// Splits SHA-512(seed) into the clamped secret scalar and the nonce prefix.
+fn expand_seed(private: &sgx_ed25519_private_t) -> ([u8; 32], [u8; 32]) {
+    let mut ctx = Sha512Context::new_sha512();
+    ctx.update(&private.seed);
+    let mut h = ctx.finalize();
+
+    let mut scalar = [0_u8; 32];
+    let mut prefix = [0_u8; 32];
+    scalar.copy_from_slice(&h[..32]);
+    prefix.copy_from_slice(&h[32..]);
+    scalar[0] &= 248;
+    scalar[31] &= 127;
+    scalar[31] |= 64;
+    zeroize(&mut h);
+    (scalar, prefix)
+}
+
+fn public_from_scalar(scalar: &[u8; 32]) -> sgx_ed25519_public_t {
+    let mut point = EdwardsPoint::mul_base(scalar);
+    let public = sgx_ed25519_public_t { key: point.compress() };
+    point.clear();
+    public
+}
+
+fn hash_to_scalar(parts: &[&[u8]]) -> [u8; 32] {
+    let mut ctx = Sha512Context::new_sha512();
+    for part in parts {
+        ctx.update(part);
+    }
+    let mut h = ctx.finalize();
+    let scalar = scalar_reduce(&h);
+    zeroize(&mut h);
+    scalar
+}
+
+fn check_data_size(size: usize) -> SgxError {
+    if size > u32::max_value() as usize {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    Ok(())
+}
+
+fn ed25519_sign(data: &[u8], private: &sgx_ed25519_private_t) -> sgx_ed25519_signature_t {
+    let (mut scalar, mut prefix) = expand_seed(private);
+    let public = public_from_scalar(&scalar);
+
+    let mut r = hash_to_scalar(&[&prefix, data]);
+    let mut point_r = EdwardsPoint::mul_base(&r);
+    let encoded_r = point_r.compress();
+    let k = hash_to_scalar(&[&encoded_r, &public.key, data]);
+    let s = scalar_muladd(&k, &scalar, &r);
+
+    zeroize(&mut scalar);
+    zeroize(&mut prefix);
+    zeroize(&mut r);
+    point_r.clear();
+    sgx_ed25519_signature_t { r: encoded_r, s }
+}
+
+fn ed25519_verify(data: &[u8], public: &sgx_ed25519_public_t, signature: &sgx_ed25519_signature_t) -> bool {
+    if !scalar_is_canonical(&signature.s) {
+        return false;
+    }
+    let point_a = match EdwardsPoint::decompress(&public.key) {
+        Some(point) => point,
+        None => return false,
+    };
+
+    // Check that [S]B - [k]A encodes to R.
+    let k = hash_to_scalar(&[&signature.r, &public.key, data]);
+    let check = EdwardsPoint::mul_base(&signature.s).add(&point_a.neg().mul(&k));
+    check.compress() == signature.r
+}
+
+///
+/// rsgx_ed25519_create_key_pair generates an Ed25519 key pair.
+///
+/// # Description
+///
+/// The 32-byte private key (the seed of RFC 8032) is drawn from sgx_read_rand and the
+/// public key is derived from it.
+///
+/// # Requirements
+///
+/// Library: libsgx_tcrypto.a, libsgx_trts.a
+///
+/// # Return value
+///
+/// The private key and the public key.
+///
+/// # Errors
+///
+/// **SGX_ERROR_UNEXPECTED**
+///
+/// Random number generation failed.
+///
+pub fn rsgx_ed25519_create_key_pair() -> SgxResult<(sgx_ed25519_private_t, sgx_ed25519_public_t)> {
+    let mut private = sgx_ed25519_private_t::default();
+    read_rand(&mut private.seed)?;
+    let public = rsgx_ed25519_public_from_private(&private)?;
+    Ok((private, public))
+}
+
+///
+/// rsgx_align_ed25519_create_key_pair is the same as rsgx_ed25519_create_key_pair, but
+/// returns the private key in an aligned structure.
+///
+pub fn rsgx_align_ed25519_create_key_pair() -> SgxResult<(sgx_align_ed25519_private_t, sgx_ed25519_public_t)> {
+    let mut private = sgx_align_ed25519_private_t::default();
+    read_rand(&mut private.key.seed)?;
+    let public = rsgx_ed25519_public_from_private(&private.key)?;
+    Ok((private, public))
+}
+
+///
+/// rsgx_ed25519_public_from_private derives the public key that belongs to an Ed25519 private key.
+///
+pub fn rsgx_ed25519_public_from_private(private: &sgx_ed25519_private_t) -> SgxResult<sgx_ed25519_public_t> {
+    let (mut scalar, mut prefix) = expand_seed(private);
+    let public = public_from_scalar(&scalar);
+    zeroize(&mut scalar);
+    zeroize(&mut prefix);
+    Ok(public)
+}
+
+///
+/// rsgx_ed25519_sign_msg computes an Ed25519 signature over the input dataset.
+///
+/// # Description
+///
+/// Ed25519 signatures are deterministic; signing the same data with the same key always
+/// produces the same signature. The public key is recomputed from the private key, so a
+/// mismatched public key can never be used to leak the private key.
+///
+/// # Parameters
+///
+/// **data**
+///
+/// The data to be signed.
+///
+/// **private**
+///
+/// The private key.
+///
+/// # Requirements
+///
+/// Library: libsgx_tcrypto.a
+///
+/// # Return value
+///
+/// The 64-byte signature, R || S.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The data is larger than 4GB.
+///
+pub fn rsgx_ed25519_sign_msg<T>(data: &T, private: &sgx_ed25519_private_t) -> SgxResult<sgx_ed25519_signature_t>
+    where T: Copy + ContiguousMemory {
+
+    let size = mem::size_of::<T>();
+    check_data_size(size)?;
+    Ok(ed25519_sign(msg_bytes(data, size), private))
+}
+
+///
+/// rsgx_ed25519_sign_slice computes an Ed25519 signature over the input dataset.
+///
+/// Unlike most functions in this crate, an empty dataset is accepted, since Ed25519 defines
+/// signatures over the empty message.
+///
+pub fn rsgx_ed25519_sign_slice<T>(data: &[T], private: &sgx_ed25519_private_t) -> SgxResult<sgx_ed25519_signature_t>
+    where T: Copy + ContiguousMemory {
+
+    let size = mem::size_of_val(data);
+    check_data_size(size)?;
+    Ok(ed25519_sign(slice_bytes(data, size), private))
+}
+
+///
+/// rsgx_ed25519_verify_msg verifies an Ed25519 signature over the input dataset.
+///
+/// # Description
+///
+/// Verification follows RFC 8032: the public key and R must be valid encodings, S must be
+/// reduced modulo the group order, and [S]B = R + [k]A must hold.
+///
+/// # Parameters
+///
+/// **data**
+///
+/// The signed data.
+///
+/// **public**
+///
+/// The public key of the signer.
+///
+/// **signature**
+///
+/// The signature to be verified.
+///
+/// # Requirements
+///
+/// Library: libsgx_tcrypto.a
+///
+/// # Return value
+///
+/// **true**
+///
+/// The signature is valid.
+///
+/// **false**
+///
+/// The signature is invalid.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The data is larger than 4GB.
+///
+pub fn rsgx_ed25519_verify_msg<T>(data: &T,
+                                  public: &sgx_ed25519_public_t,
+                                  signature: &sgx_ed25519_signature_t) -> SgxResult<bool>
+    where T: Copy + ContiguousMemory {
+
+    let size = mem::size_of::<T>();
+    check_data_size(size)?;
+    Ok(ed25519_verify(msg_bytes(data, size), public, signature))
+}
+
+///
+/// rsgx_ed25519_verify_slice verifies an Ed25519 signature over the input dataset.
+///
+pub fn rsgx_ed25519_verify_slice<T>(data: &[T],
+                                    public: &sgx_ed25519_public_t,
+                                    signature: &sgx_ed25519_signature_t) -> SgxResult<bool>
+    where T: Copy + ContiguousMemory {
+
+    let size = mem::size_of_val(data);
+    check_data_size(size)?;
+    Ok(ed25519_verify(slice_bytes(data, size), public, signature))
+}
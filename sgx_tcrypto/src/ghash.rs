@@ -0,0 +1,201 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! Constant-time GHASH and POLYVAL universal hash functions.
+//!
+//! Field multiplication uses integer multiplications with "holes" so that no
+//! carry-less multiply instruction or secret-indexed table is needed. GHASH is
+//! derived from POLYVAL following RFC 8452, Appendix A.
+//!
+use core::slice;
+use crate::internal::zeroize;
+
+pub(crate) const GHASH_BLOCK_SIZE: usize = 16;
+
+#[inline]
+fn bmul64(x: u64, y: u64) -> u64 {
+    let x0 = x & 0x1111_1111_1111_1111;
+    let x1 = x & 0x2222_2222_2222_2222;
+    let x2 = x & 0x4444_4444_4444_4444;
+    let x3 = x & 0x8888_8888_8888_8888;
+    let y0 = y & 0x1111_1111_1111_1111;
+    let y1 = y & 0x2222_2222_2222_2222;
+    let y2 = y & 0x4444_4444_4444_4444;
+    let y3 = y & 0x8888_8888_8888_8888;
+
+    let mut z0 = x0.wrapping_mul(y0) ^ x1.wrapping_mul(y3) ^ x2.wrapping_mul(y2) ^ x3.wrapping_mul(y1);
+    let mut z1 = x0.wrapping_mul(y1) ^ x1.wrapping_mul(y0) ^ x2.wrapping_mul(y3) ^ x3.wrapping_mul(y2);
+    let mut z2 = x0.wrapping_mul(y2) ^ x1.wrapping_mul(y1) ^ x2.wrapping_mul(y0) ^ x3.wrapping_mul(y3);
+    let mut z3 = x0.wrapping_mul(y3) ^ x1.wrapping_mul(y2) ^ x2.wrapping_mul(y1) ^ x3.wrapping_mul(y0);
+
+    z0 &= 0x1111_1111_1111_1111;
+    z1 &= 0x2222_2222_2222_2222;
+    z2 &= 0x4444_4444_4444_4444;
+    z3 &= 0x8888_8888_8888_8888;
+    z0 | z1 | z2 | z3
+}
+
+///
+/// Multiplies two elements in POLYVAL's field: returns a * b * x^-128.
+///
+fn polyval_mul(a: (u64, u64), b: (u64, u64)) -> (u64, u64) {
+    let (h0, h1) = a;
+    let h0r = h0.reverse_bits();
+    let h1r = h1.reverse_bits();
+    let h2 = h0 ^ h1;
+    let h2r = h0r ^ h1r;
+
+    let (y0, y1) = b;
+    let y0r = y0.reverse_bits();
+    let y1r = y1.reverse_bits();
+    let y2 = y0 ^ y1;
+    let y2r = y0r ^ y1r;
+
+    // Karatsuba over the low and high halves, using bit reversal to
+    // recover the upper 64 bits of each carry-less product.
+    let z0 = bmul64(y0, h0);
+    let z1 = bmul64(y1, h1);
+    let mut z2 = bmul64(y2, h2);
+    let mut z0h = bmul64(y0r, h0r);
+    let mut z1h = bmul64(y1r, h1r);
+    let mut z2h = bmul64(y2r, h2r);
+
+    z2 ^= z0 ^ z1;
+    z2h ^= z0h ^ z1h;
+    z0h = z0h.reverse_bits() >> 1;
+    z1h = z1h.reverse_bits() >> 1;
+    z2h = z2h.reverse_bits() >> 1;
+
+    let v0 = z0;
+    let mut v1 = z0h ^ z2;
+    let mut v2 = z1 ^ z2h;
+    let mut v3 = z1h;
+
+    // Montgomery reduction modulo x^128 + x^127 + x^126 + x^121 + 1.
+    v2 ^= v0 ^ (v0 >> 1) ^ (v0 >> 2) ^ (v0 >> 7);
+    v1 ^= (v0 << 63) ^ (v0 << 62) ^ (v0 << 57);
+    v3 ^= v1 ^ (v1 >> 1) ^ (v1 >> 2) ^ (v1 >> 7);
+    v2 ^= (v1 << 63) ^ (v1 << 62) ^ (v1 << 57);
+
+    (v2, v3)
+}
+
+fn load_le(block: &[u8]) -> (u64, u64) {
+    let mut lo = [0_u8; 8];
+    let mut hi = [0_u8; 8];
+    lo.copy_from_slice(&block[..8]);
+    hi.copy_from_slice(&block[8..16]);
+    (u64::from_le_bytes(lo), u64::from_le_bytes(hi))
+}
+
+fn store_le(v: (u64, u64)) -> [u8; GHASH_BLOCK_SIZE] {
+    let mut out = [0_u8; GHASH_BLOCK_SIZE];
+    out[..8].copy_from_slice(&v.0.to_le_bytes());
+    out[8..].copy_from_slice(&v.1.to_le_bytes());
+    out
+}
+
+#[derive(Clone)]
+pub(crate) struct Polyval {
+    h: (u64, u64),
+    s: (u64, u64),
+}
+
+impl Polyval {
+    pub(crate) fn new(key: &[u8; GHASH_BLOCK_SIZE]) -> Polyval {
+        Polyval::from_element(load_le(key))
+    }
+
+    fn from_element(h: (u64, u64)) -> Polyval {
+        Polyval { h, s: (0, 0) }
+    }
+
+    pub(crate) fn update_block(&mut self, block: &[u8; GHASH_BLOCK_SIZE]) {
+        let x = load_le(block);
+        self.s = polyval_mul((self.s.0 ^ x.0, self.s.1 ^ x.1), self.h);
+    }
+
+    ///
+    /// Absorbs arbitrary data, zero-padding the final partial block.
+    ///
+    pub(crate) fn update_padded(&mut self, data: &[u8]) {
+        for chunk in data.chunks(GHASH_BLOCK_SIZE) {
+            let mut block = [0_u8; GHASH_BLOCK_SIZE];
+            block[..chunk.len()].copy_from_slice(chunk);
+            self.update_block(&block);
+        }
+    }
+
+    pub(crate) fn finalize(&self) -> [u8; GHASH_BLOCK_SIZE] {
+        store_le(self.s)
+    }
+}
+
+impl Drop for Polyval {
+    fn drop(&mut self) {
+        zeroize(slice::from_mut(&mut self.h));
+        zeroize(slice::from_mut(&mut self.s));
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Ghash {
+    inner: Polyval,
+}
+
+impl Ghash {
+    pub(crate) fn new(key: &[u8; GHASH_BLOCK_SIZE]) -> Ghash {
+        let mut h = *key;
+        h.reverse();
+        let (lo, hi) = load_le(&h);
+        zeroize(&mut h);
+
+        // mulX_POLYVAL(ByteReverse(H))
+        let carry = hi >> 63;
+        let mut lo2 = lo << 1;
+        let mut hi2 = (hi << 1) | (lo >> 63);
+        lo2 ^= carry;
+        hi2 ^= carry.wrapping_neg() & 0xc200_0000_0000_0000;
+
+        Ghash { inner: Polyval::from_element((lo2, hi2)) }
+    }
+
+    pub(crate) fn update_block(&mut self, block: &[u8; GHASH_BLOCK_SIZE]) {
+        let mut x = *block;
+        x.reverse();
+        self.inner.update_block(&x);
+    }
+
+    ///
+    /// Absorbs arbitrary data, zero-padding the final partial block.
+    ///
+    pub(crate) fn update_padded(&mut self, data: &[u8]) {
+        for chunk in data.chunks(GHASH_BLOCK_SIZE) {
+            let mut block = [0_u8; GHASH_BLOCK_SIZE];
+            block[..chunk.len()].copy_from_slice(chunk);
+            self.update_block(&block);
+        }
+    }
+
+    pub(crate) fn finalize(&self) -> [u8; GHASH_BLOCK_SIZE] {
+        let mut out = self.inner.finalize();
+        out.reverse();
+        out
+    }
+}
@@ -0,0 +1,91 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+use sgx_types::*;
+use sgx_types::marker::ContiguousMemory;
+use core::ptr;
+use core::mem;
+use core::slice;
+use core::sync::atomic::{compiler_fence, Ordering};
+
+///
+/// Overwrites the buffer with zeros in a way the optimizer cannot elide.
+///
+pub(crate) fn zeroize<T: Copy + Default>(buf: &mut [T]) {
+    for v in buf.iter_mut() {
+        unsafe { ptr::write_volatile(v, T::default()); }
+    }
+    compiler_fence(Ordering::SeqCst);
+}
+
+///
+/// Compares two buffers in constant time. The lengths are not secret.
+///
+pub(crate) fn consttime_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut diff: u8 = 0;
+    for (x, y) in a.iter().zip(b.iter()) {
+        diff |= x ^ y;
+    }
+    unsafe { ptr::read_volatile(&diff) == 0 }
+}
+
+pub(crate) fn check_msg_size<T>() -> SgxResult<usize> {
+    let size = mem::size_of::<T>();
+    if size == 0 {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    if size > u32::max_value() as usize {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    Ok(size)
+}
+
+pub(crate) fn check_slice_size<T>(src: &[T]) -> SgxResult<usize> {
+    let size = mem::size_of_val(src);
+    if size == 0 {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    if size > u32::max_value() as usize {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    Ok(size)
+}
+
+pub(crate) fn msg_bytes<T>(src: &T, size: usize) -> &[u8]
+    where T: Copy + ContiguousMemory {
+    unsafe { slice::from_raw_parts(src as * const _ as * const u8, size) }
+}
+
+pub(crate) fn slice_bytes<T>(src: &[T], size: usize) -> &[u8]
+    where T: Copy + ContiguousMemory {
+    unsafe { slice::from_raw_parts(src.as_ptr() as * const u8, size) }
+}
+
+///
+/// Fills the buffer from the SGX random number generator.
+///
+pub(crate) fn read_rand(buf: &mut [u8]) -> SgxError {
+    let ret = unsafe { sgx_read_rand(buf.as_mut_ptr(), buf.len()) };
+    match ret {
+        sgx_status_t::SGX_SUCCESS => Ok(()),
+        _ => Err(ret),
+    }
+}
@@ -0,0 +1,333 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! Key Derivation Functions
+//!
+//! HKDF-SHA256 (RFC 5869) built on `SgxHmacHandle`, and the NIST SP800-108
+//! counter mode KDF with AES-CMAC as the PRF. The latter is the derivation the
+//! SGX key exchange libraries use to turn a key derivation key (KDK) into the
+//! SMK, SK, MK and VK session keys.
+//!
+//! The output is written through `KdfOutput`, so a derived key can be placed
+//! straight into the `key` field of an `sgx_align_key_128bit_t` (or of one held
+//! in an `AlignBox`) without passing through an unaligned temporary, as the
+//! Intel-SA-00219 mitigation requires.
+//!
+use sgx_types::*;
+use crate::crypto::{SgxHmacHandle, SgxCmacHandle};
+use crate::internal::zeroize;
+use crate::secret::{SecretKey, SecretMaterial};
+
+/// The longest output HKDF-SHA256 can produce, 255 hash blocks.
+pub const SGX_HKDF_SHA256_MAX_OUTPUT_SIZE: size_t = 255 * SGX_SHA256_HASH_SIZE;
+/// The longest output of rsgx_cmac_kdf_counter, bounded by its 8-bit counter.
+pub const SGX_CMAC_KDF_MAX_OUTPUT_SIZE: size_t = 255 * SGX_CMAC_MAC_SIZE;
+
+///
+/// A destination for derived key material.
+///
+/// For the aligned key types only the `key` field is written; the padding that
+/// provides the alignment is left untouched.
+///
+pub trait KdfOutput {
+    fn kdf_bytes_mut(&mut self) -> &mut [u8];
+}
+
+impl KdfOutput for [u8] {
+    fn kdf_bytes_mut(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+impl KdfOutput for sgx_key_128bit_t {
+    fn kdf_bytes_mut(&mut self) -> &mut [u8] {
+        &mut self[..]
+    }
+}
+
+impl KdfOutput for sgx_key_256bit_t {
+    fn kdf_bytes_mut(&mut self) -> &mut [u8] {
+        &mut self[..]
+    }
+}
+
+impl KdfOutput for sgx_align_key_128bit_t {
+    fn kdf_bytes_mut(&mut self) -> &mut [u8] {
+        &mut self.key[..]
+    }
+}
+
+impl KdfOutput for sgx_align_key_256bit_t {
+    fn kdf_bytes_mut(&mut self) -> &mut [u8] {
+        &mut self.key[..]
+    }
+}
+
+impl<T: SecretMaterial + KdfOutput> KdfOutput for SecretKey<T> {
+    fn kdf_bytes_mut(&mut self) -> &mut [u8] {
+        (**self).kdf_bytes_mut()
+    }
+}
+
+///
+/// rsgx_hkdf_sha256_extract performs the HKDF-Extract step of RFC 5869 with SHA256.
+///
+/// # Parameters
+///
+/// **salt**
+///
+/// The optional salt. An empty salt is replaced by 32 zero bytes, as the RFC specifies.
+///
+/// **ikm**
+///
+/// The input keying material.
+///
+/// # Requirements
+///
+/// Library: libsgx_tcrypto.a
+///
+/// # Return value
+///
+/// The pseudorandom key (PRK), to be passed to rsgx_hkdf_sha256_expand.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The input is too large.
+///
+/// **SGX_ERROR_OUT_OF_MEMORY**
+///
+/// Not enough memory is available to complete this operation.
+///
+/// **SGX_ERROR_UNEXPECTED**
+///
+/// An internal cryptography library failure occurred.
+///
+pub fn rsgx_hkdf_sha256_extract(salt: &[u8], ikm: &[u8]) -> SgxResult<sgx_hmac_256bit_tag_t> {
+    let zero_salt = [0_u8; SGX_HMAC256_KEY_SIZE];
+    let salt = if salt.is_empty() { &zero_salt[..] } else { salt };
+
+    let handle = SgxHmacHandle::new();
+    handle.init_slice(salt)?;
+    if !ikm.is_empty() {
+        handle.update_slice(ikm)?;
+    }
+    let prk = handle.get_hash()?;
+    handle.close()?;
+    Ok(prk)
+}
+
+///
+/// rsgx_align_hkdf_sha256_extract is the same as rsgx_hkdf_sha256_extract, but returns
+/// the PRK in an aligned structure.
+///
+pub fn rsgx_align_hkdf_sha256_extract(salt: &[u8], ikm: &[u8]) -> SgxResult<sgx_align_mac_256bit_t> {
+    let mut align_prk = sgx_align_mac_256bit_t::default();
+    let mut prk = rsgx_hkdf_sha256_extract(salt, ikm)?;
+    align_prk.mac = prk;
+    zeroize(&mut prk);
+    Ok(align_prk)
+}
+
+///
+/// rsgx_hkdf_sha256_expand performs the HKDF-Expand step of RFC 5869 with SHA256.
+///
+/// # Parameters
+///
+/// **prk**
+///
+/// The pseudorandom key returned by rsgx_hkdf_sha256_extract.
+///
+/// **info**
+///
+/// The optional context and application specific information. It may be empty.
+///
+/// **okm**
+///
+/// The output keying material. Its whole length is filled, and it must be between 1
+/// and SGX_HKDF_SHA256_MAX_OUTPUT_SIZE bytes.
+///
+/// # Requirements
+///
+/// Library: libsgx_tcrypto.a
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The output length is zero or exceeds SGX_HKDF_SHA256_MAX_OUTPUT_SIZE.
+///
+/// **SGX_ERROR_OUT_OF_MEMORY**
+///
+/// Not enough memory is available to complete this operation.
+///
+/// **SGX_ERROR_UNEXPECTED**
+///
+/// An internal cryptography library failure occurred. The output is zeroed.
+///
+pub fn rsgx_hkdf_sha256_expand<O>(prk: &sgx_hmac_256bit_key_t, info: &[u8], okm: &mut O) -> SgxError
+    where O: KdfOutput + ?Sized {
+
+    let out = okm.kdf_bytes_mut();
+    if out.is_empty() || out.len() > SGX_HKDF_SHA256_MAX_OUTPUT_SIZE {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+
+    let mut block = sgx_hmac_256bit_tag_t::default();
+    let result = hkdf_sha256_expand_blocks(prk, info, out, &mut block);
+    zeroize(&mut block);
+    if result.is_err() {
+        zeroize(out);
+    }
+    result
+}
+
+fn hkdf_sha256_expand_blocks(prk: &sgx_hmac_256bit_key_t,
+                             info: &[u8],
+                             out: &mut [u8],
+                             block: &mut sgx_hmac_256bit_tag_t) -> SgxError {
+
+    // T(i) = HMAC(PRK, T(i - 1) || info || i), with T(0) empty.
+    for (i, chunk) in out.chunks_mut(SGX_SHA256_HASH_SIZE).enumerate() {
+        let handle = SgxHmacHandle::new();
+        handle.init(prk)?;
+        if i > 0 {
+            handle.update_slice(&block[..])?;
+        }
+        if !info.is_empty() {
+            handle.update_slice(info)?;
+        }
+        handle.update_slice(&[(i + 1) as u8])?;
+        *block = handle.get_hash()?;
+        handle.close()?;
+        chunk.copy_from_slice(&block[..chunk.len()]);
+    }
+    Ok(())
+}
+
+///
+/// rsgx_hkdf_sha256 performs HKDF-Extract followed by HKDF-Expand (RFC 5869) with SHA256.
+///
+/// The intermediate PRK is zeroed before returning. See rsgx_hkdf_sha256_extract and
+/// rsgx_hkdf_sha256_expand for the parameters and errors.
+///
+pub fn rsgx_hkdf_sha256<O>(salt: &[u8], ikm: &[u8], info: &[u8], okm: &mut O) -> SgxError
+    where O: KdfOutput + ?Sized {
+
+    let mut prk = rsgx_align_hkdf_sha256_extract(salt, ikm)?;
+    let result = rsgx_hkdf_sha256_expand(&prk.mac, info, okm);
+    zeroize(&mut prk.mac);
+    result
+}
+
+///
+/// rsgx_cmac_kdf_counter performs the NIST SP800-108 KDF in counter mode with AES-CMAC
+/// as the PRF.
+///
+/// # Description
+///
+/// Each 128-bit block of output is computed as
+///
+/// `AES-CMAC(kdk, i || label || 0x00 || context || L)`
+///
+/// where `i` is an 8-bit counter starting at 1 and `L` is the output length in bits,
+/// encoded as a 16-bit little-endian integer. With an empty context this is exactly the
+/// derivation the SGX SDK performs for the SMK, SK, MK and VK keys, for example
+/// `rsgx_cmac_kdf_counter(&kdk, b"SMK", &[], &mut align_key)` with a 128-bit output.
+///
+/// # Parameters
+///
+/// **kdk**
+///
+/// The key derivation key.
+///
+/// **label**
+///
+/// A label identifying the purpose of the derived key. It may be empty.
+///
+/// **context**
+///
+/// Information related to the derived key, such as the identities of the parties. It may be empty.
+///
+/// **okm**
+///
+/// The output keying material. Its whole length is filled, and it must be between 1
+/// and SGX_CMAC_KDF_MAX_OUTPUT_SIZE bytes.
+///
+/// # Requirements
+///
+/// Library: libsgx_tcrypto.a
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The output length is zero or exceeds SGX_CMAC_KDF_MAX_OUTPUT_SIZE.
+///
+/// **SGX_ERROR_OUT_OF_MEMORY**
+///
+/// Not enough memory is available to complete this operation.
+///
+/// **SGX_ERROR_UNEXPECTED**
+///
+/// An internal cryptography library failure occurred. The output is zeroed.
+///
+pub fn rsgx_cmac_kdf_counter<O>(kdk: &sgx_cmac_128bit_key_t, label: &[u8], context: &[u8], okm: &mut O) -> SgxError
+    where O: KdfOutput + ?Sized {
+
+    let out = okm.kdf_bytes_mut();
+    if out.is_empty() || out.len() > SGX_CMAC_KDF_MAX_OUTPUT_SIZE {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+
+    let mut block = sgx_cmac_128bit_tag_t::default();
+    let result = cmac_kdf_counter_blocks(kdk, label, context, out, &mut block);
+    zeroize(&mut block);
+    if result.is_err() {
+        zeroize(out);
+    }
+    result
+}
+
+fn cmac_kdf_counter_blocks(kdk: &sgx_cmac_128bit_key_t,
+                           label: &[u8],
+                           context: &[u8],
+                           out: &mut [u8],
+                           block: &mut sgx_cmac_128bit_tag_t) -> SgxError {
+
+    let out_bits = ((out.len() * 8) as u16).to_le_bytes();
+    for (i, chunk) in out.chunks_mut(SGX_CMAC_MAC_SIZE).enumerate() {
+        let handle = SgxCmacHandle::new();
+        handle.init(kdk)?;
+        handle.update_slice(&[(i + 1) as u8])?;
+        if !label.is_empty() {
+            handle.update_slice(label)?;
+        }
+        handle.update_slice(&[0_u8])?;
+        if !context.is_empty() {
+            handle.update_slice(context)?;
+        }
+        handle.update_slice(&out_bits)?;
+        *block = handle.get_hash()?;
+        handle.close()?;
+        chunk.copy_from_slice(&block[..chunk.len()]);
+    }
+    Ok(())
+}
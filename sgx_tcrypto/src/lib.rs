@@ -30,6 +30,47 @@
 #![allow(clippy::too_many_arguments)]
 
 extern crate sgx_types;
+extern crate sgx_tse;
 
 mod crypto;
 pub use self::crypto::*;
+
+mod internal;
+mod aes;
+mod ghash;
+mod chacha20;
+mod poly1305;
+mod curve25519;
+mod der;
+mod bignum;
+mod rsa;
+
+mod sha2;
+pub use self::sha2::*;
+
+mod aes_gcm;
+pub use self::aes_gcm::*;
+
+mod aes_gcm_siv;
+pub use self::aes_gcm_siv::*;
+
+mod chacha20_poly1305;
+pub use self::chacha20_poly1305::*;
+
+mod kdf;
+pub use self::kdf::*;
+
+mod ed25519;
+pub use self::ed25519::*;
+
+mod x25519;
+pub use self::x25519::*;
+
+mod codec;
+pub use self::codec::*;
+
+mod secret;
+pub use self::secret::*;
+
+mod sealer;
+pub use self::sealer::*;
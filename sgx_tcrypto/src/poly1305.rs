@@ -0,0 +1,224 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! Poly1305 one-time authenticator (RFC 8439), 26-bit limb implementation.
+//!
+use crate::internal::zeroize;
+
+pub(crate) const POLY1305_KEY_SIZE: usize = 32;
+pub(crate) const POLY1305_BLOCK_SIZE: usize = 16;
+pub(crate) const POLY1305_TAG_SIZE: usize = 16;
+
+#[inline]
+fn le32(b: &[u8]) -> u32 {
+    u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+}
+
+#[derive(Clone)]
+pub(crate) struct Poly1305 {
+    r: [u32; 5],
+    h: [u32; 5],
+    pad: [u32; 4],
+    buf: [u8; POLY1305_BLOCK_SIZE],
+    buf_len: usize,
+}
+
+impl Poly1305 {
+    pub(crate) fn new(key: &[u8; POLY1305_KEY_SIZE]) -> Poly1305 {
+        let r = [
+            le32(&key[0..4]) & 0x03ff_ffff,
+            (le32(&key[3..7]) >> 2) & 0x03ff_ff03,
+            (le32(&key[6..10]) >> 4) & 0x03ff_c0ff,
+            (le32(&key[9..13]) >> 6) & 0x03f0_3fff,
+            (le32(&key[12..16]) >> 8) & 0x000f_ffff,
+        ];
+        let pad = [le32(&key[16..20]), le32(&key[20..24]), le32(&key[24..28]), le32(&key[28..32])];
+        Poly1305 {
+            r,
+            h: [0_u32; 5],
+            pad,
+            buf: [0_u8; POLY1305_BLOCK_SIZE],
+            buf_len: 0,
+        }
+    }
+
+    fn block(&mut self, m: &[u8], hibit: u32) {
+        let r0 = u64::from(self.r[0]);
+        let r1 = u64::from(self.r[1]);
+        let r2 = u64::from(self.r[2]);
+        let r3 = u64::from(self.r[3]);
+        let r4 = u64::from(self.r[4]);
+        let s1 = r1 * 5;
+        let s2 = r2 * 5;
+        let s3 = r3 * 5;
+        let s4 = r4 * 5;
+
+        let h0 = u64::from(self.h[0] + (le32(&m[0..4]) & 0x03ff_ffff));
+        let h1 = u64::from(self.h[1] + ((le32(&m[3..7]) >> 2) & 0x03ff_ffff));
+        let h2 = u64::from(self.h[2] + ((le32(&m[6..10]) >> 4) & 0x03ff_ffff));
+        let h3 = u64::from(self.h[3] + ((le32(&m[9..13]) >> 6) & 0x03ff_ffff));
+        let h4 = u64::from(self.h[4] + ((le32(&m[12..16]) >> 8) | hibit));
+
+        let d0 = h0 * r0 + h1 * s4 + h2 * s3 + h3 * s2 + h4 * s1;
+        let mut d1 = h0 * r1 + h1 * r0 + h2 * s4 + h3 * s3 + h4 * s2;
+        let mut d2 = h0 * r2 + h1 * r1 + h2 * r0 + h3 * s4 + h4 * s3;
+        let mut d3 = h0 * r3 + h1 * r2 + h2 * r1 + h3 * r0 + h4 * s4;
+        let mut d4 = h0 * r4 + h1 * r3 + h2 * r2 + h3 * r1 + h4 * r0;
+
+        let mut c = d0 >> 26;
+        let mut h0 = (d0 as u32) & 0x03ff_ffff;
+        d1 += c;
+        c = d1 >> 26;
+        let mut h1 = (d1 as u32) & 0x03ff_ffff;
+        d2 += c;
+        c = d2 >> 26;
+        let h2 = (d2 as u32) & 0x03ff_ffff;
+        d3 += c;
+        c = d3 >> 26;
+        let h3 = (d3 as u32) & 0x03ff_ffff;
+        d4 += c;
+        c = d4 >> 26;
+        let h4 = (d4 as u32) & 0x03ff_ffff;
+        h0 += (c as u32) * 5;
+        let c = h0 >> 26;
+        h0 &= 0x03ff_ffff;
+        h1 += c;
+
+        self.h = [h0, h1, h2, h3, h4];
+    }
+
+    pub(crate) fn update(&mut self, mut data: &[u8]) {
+        if self.buf_len > 0 {
+            let n = core::cmp::min(POLY1305_BLOCK_SIZE - self.buf_len, data.len());
+            self.buf[self.buf_len..self.buf_len + n].copy_from_slice(&data[..n]);
+            self.buf_len += n;
+            data = &data[n..];
+            if self.buf_len < POLY1305_BLOCK_SIZE {
+                return;
+            }
+            let block = self.buf;
+            self.block(&block, 1 << 24);
+            self.buf_len = 0;
+        }
+        while data.len() >= POLY1305_BLOCK_SIZE {
+            let (block, rest) = data.split_at(POLY1305_BLOCK_SIZE);
+            self.block(block, 1 << 24);
+            data = rest;
+        }
+        self.buf[..data.len()].copy_from_slice(data);
+        self.buf_len = data.len();
+    }
+
+    ///
+    /// Zero-pads the input absorbed so far to a multiple of 16 bytes, as
+    /// required between the AAD and the ciphertext of the AEAD construction.
+    ///
+    pub(crate) fn pad16(&mut self) {
+        if self.buf_len > 0 {
+            let zeros = [0_u8; POLY1305_BLOCK_SIZE];
+            let n = POLY1305_BLOCK_SIZE - self.buf_len;
+            self.update(&zeros[..n]);
+        }
+    }
+
+    pub(crate) fn finalize(mut self) -> [u8; POLY1305_TAG_SIZE] {
+        if self.buf_len > 0 {
+            let mut block = [0_u8; POLY1305_BLOCK_SIZE];
+            block[..self.buf_len].copy_from_slice(&self.buf[..self.buf_len]);
+            block[self.buf_len] = 1;
+            self.block(&block, 0);
+        }
+
+        let [mut h0, mut h1, mut h2, mut h3, mut h4] = self.h;
+
+        let mut c = h1 >> 26;
+        h1 &= 0x03ff_ffff;
+        h2 += c;
+        c = h2 >> 26;
+        h2 &= 0x03ff_ffff;
+        h3 += c;
+        c = h3 >> 26;
+        h3 &= 0x03ff_ffff;
+        h4 += c;
+        c = h4 >> 26;
+        h4 &= 0x03ff_ffff;
+        h0 += c * 5;
+        c = h0 >> 26;
+        h0 &= 0x03ff_ffff;
+        h1 += c;
+
+        // Compute h - p and select it in constant time if h >= p.
+        let mut g0 = h0.wrapping_add(5);
+        c = g0 >> 26;
+        g0 &= 0x03ff_ffff;
+        let mut g1 = h1.wrapping_add(c);
+        c = g1 >> 26;
+        g1 &= 0x03ff_ffff;
+        let mut g2 = h2.wrapping_add(c);
+        c = g2 >> 26;
+        g2 &= 0x03ff_ffff;
+        let mut g3 = h3.wrapping_add(c);
+        c = g3 >> 26;
+        g3 &= 0x03ff_ffff;
+        let mut g4 = h4.wrapping_add(c).wrapping_sub(1 << 26);
+
+        let mut mask = (g4 >> 31).wrapping_sub(1);
+        g0 &= mask;
+        g1 &= mask;
+        g2 &= mask;
+        g3 &= mask;
+        g4 &= mask;
+        mask = !mask;
+        h0 = (h0 & mask) | g0;
+        h1 = (h1 & mask) | g1;
+        h2 = (h2 & mask) | g2;
+        h3 = (h3 & mask) | g3;
+        h4 = (h4 & mask) | g4;
+
+        let h0 = h0 | (h1 << 26);
+        let h1 = (h1 >> 6) | (h2 << 20);
+        let h2 = (h2 >> 12) | (h3 << 14);
+        let h3 = (h3 >> 18) | (h4 << 8);
+
+        let mut f = u64::from(h0) + u64::from(self.pad[0]);
+        let t0 = f as u32;
+        f = u64::from(h1) + u64::from(self.pad[1]) + (f >> 32);
+        let t1 = f as u32;
+        f = u64::from(h2) + u64::from(self.pad[2]) + (f >> 32);
+        let t2 = f as u32;
+        f = u64::from(h3) + u64::from(self.pad[3]) + (f >> 32);
+        let t3 = f as u32;
+
+        let mut tag = [0_u8; POLY1305_TAG_SIZE];
+        tag[0..4].copy_from_slice(&t0.to_le_bytes());
+        tag[4..8].copy_from_slice(&t1.to_le_bytes());
+        tag[8..12].copy_from_slice(&t2.to_le_bytes());
+        tag[12..16].copy_from_slice(&t3.to_le_bytes());
+        tag
+    }
+}
+
+impl Drop for Poly1305 {
+    fn drop(&mut self) {
+        zeroize(&mut self.r);
+        zeroize(&mut self.h);
+        zeroize(&mut self.pad);
+        zeroize(&mut self.buf);
+    }
+}
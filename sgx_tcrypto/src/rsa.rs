@@ -0,0 +1,595 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! RSA key material and padding schemes behind `SgxRsaPrivKey` and `SgxRsaPubKey`.
+//!
+//! libsgx_tcrypto.a only exposes RSA-OAEP with SHA-256 on its opaque key handles,
+//! so the handles keep a copy of the key components and the PKCS#1 v2.2 encodings
+//! (RSASSA-PSS, RSASSA-PKCS1-v1_5 and RSAES-OAEP with SHA-1, SHA-256 or SHA-384)
+//! are implemented here on top of bignum.rs.
+//!
+use sgx_types::*;
+use crate::bignum::{self, Limbs, Mont, BN_MAX_LIMBS};
+use crate::crypto::{SgxShaHandle, SgxSha1Handle};
+use crate::sha2::Sha512Context;
+use crate::internal::{zeroize, consttime_eq, read_rand};
+use core::cmp::Ordering;
+
+const MAX_HASH_SIZE: usize = SGX_SHA384_HASH_SIZE;
+
+const SHA1_DIGEST_INFO: [u8; 15] = [
+    0x30, 0x21, 0x30, 0x09, 0x06, 0x05, 0x2b, 0x0e, 0x03, 0x02, 0x1a, 0x05, 0x00, 0x04, 0x14,
+];
+const SHA256_DIGEST_INFO: [u8; 19] = [
+    0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01,
+    0x05, 0x00, 0x04, 0x20,
+];
+const SHA384_DIGEST_INFO: [u8; 19] = [
+    0x30, 0x41, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x02,
+    0x05, 0x00, 0x04, 0x30,
+];
+
+fn hash_size(hash: sgx_rsa_hash_t) -> usize {
+    match hash {
+        sgx_rsa_hash_t::SGX_RSA_HASH_SHA1 => SGX_SHA1_HASH_SIZE,
+        sgx_rsa_hash_t::SGX_RSA_HASH_SHA256 => SGX_SHA256_HASH_SIZE,
+        sgx_rsa_hash_t::SGX_RSA_HASH_SHA384 => SGX_SHA384_HASH_SIZE,
+    }
+}
+
+fn digest_info(hash: sgx_rsa_hash_t) -> &'static [u8] {
+    match hash {
+        sgx_rsa_hash_t::SGX_RSA_HASH_SHA1 => &SHA1_DIGEST_INFO,
+        sgx_rsa_hash_t::SGX_RSA_HASH_SHA256 => &SHA256_DIGEST_INFO,
+        sgx_rsa_hash_t::SGX_RSA_HASH_SHA384 => &SHA384_DIGEST_INFO,
+    }
+}
+
+// Hashes the concatenation of `parts` into `out`, returning the digest size.
+fn digest(hash: sgx_rsa_hash_t, parts: &[&[u8]], out: &mut [u8; MAX_HASH_SIZE]) -> SgxResult<usize> {
+    match hash {
+        sgx_rsa_hash_t::SGX_RSA_HASH_SHA1 => {
+            let handle = SgxSha1Handle::new();
+            handle.init()?;
+            for part in parts.iter().filter(|p| !p.is_empty()) {
+                handle.update_slice(part)?;
+            }
+            out[..SGX_SHA1_HASH_SIZE].copy_from_slice(&handle.get_hash()?);
+            Ok(SGX_SHA1_HASH_SIZE)
+        },
+        sgx_rsa_hash_t::SGX_RSA_HASH_SHA256 => {
+            let handle = SgxShaHandle::new();
+            handle.init()?;
+            for part in parts.iter().filter(|p| !p.is_empty()) {
+                handle.update_slice(part)?;
+            }
+            out[..SGX_SHA256_HASH_SIZE].copy_from_slice(&handle.get_hash()?);
+            Ok(SGX_SHA256_HASH_SIZE)
+        },
+        sgx_rsa_hash_t::SGX_RSA_HASH_SHA384 => {
+            let mut ctx = Sha512Context::new_sha384();
+            for part in parts {
+                ctx.update(part);
+            }
+            out[..SGX_SHA384_HASH_SIZE].copy_from_slice(&ctx.finalize()[..SGX_SHA384_HASH_SIZE]);
+            Ok(SGX_SHA384_HASH_SIZE)
+        },
+    }
+}
+
+// XORs MGF1(seed) into `out`.
+fn mgf1_xor(hash: sgx_rsa_hash_t, seed: &[u8], out: &mut [u8]) -> SgxError {
+    let mut block = [0_u8; MAX_HASH_SIZE];
+    let mut counter: u32 = 0;
+    let mut pos = 0;
+    while pos < out.len() {
+        let size = digest(hash, &[seed, &counter.to_be_bytes()], &mut block)?;
+        let n = core::cmp::min(size, out.len() - pos);
+        for (o, b) in out[pos..pos + n].iter_mut().zip(block.iter()) {
+            *o ^= *b;
+        }
+        pos += n;
+        counter += 1;
+    }
+    zeroize(&mut block);
+    Ok(())
+}
+
+// All-ones if x == 0, zero otherwise.
+#[inline]
+fn zero_mask(x: u8) -> u8 {
+    (u16::from(x).wrapping_sub(1) >> 8) as u8
+}
+
+fn load_exact_le(out: &mut [u64], bytes: &[u8], size: usize) -> SgxError {
+    if bytes.len() < size || !bignum::load_le(out, &bytes[..size]) {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    Ok(())
+}
+
+///
+/// An RSA public key: the modulus as a Montgomery context and the public exponent.
+///
+pub(crate) struct RsaPublicKey {
+    n: Mont,
+    e: Limbs,
+    bits: usize,
+    size: usize,
+}
+
+impl RsaPublicKey {
+    ///
+    /// Reads `n` and `e` as little-endian byte strings of `mod_size` and `exp_size` bytes.
+    ///
+    pub(crate) fn new(mod_size: usize, exp_size: usize, n: &[u8], e: &[u8]) -> SgxResult<RsaPublicKey> {
+        let mut limbs = [0_u64; BN_MAX_LIMBS];
+        load_exact_le(&mut limbs, n, mod_size)?;
+        let n = Mont::new(&limbs).ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+        RsaPublicKey::from_mont(n, mod_size, exp_size, e)
+    }
+
+    fn from_mont(n: Mont, mod_size: usize, exp_size: usize, e: &[u8]) -> SgxResult<RsaPublicKey> {
+        let bits = bignum::bit_len(n.modulus());
+        if (bits + 7) / 8 != mod_size {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let mut exp = [0_u64; BN_MAX_LIMBS];
+        load_exact_le(&mut exp, e, exp_size)?;
+        if bignum::bit_len(&exp) == 0 || bignum::cmp_vartime(&exp, n.modulus()) != Ordering::Less {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        Ok(RsaPublicKey { n, e: exp, bits, size: mod_size })
+    }
+
+    ///
+    /// The modulus size in bytes, k in PKCS#1.
+    ///
+    #[inline]
+    pub(crate) fn size(&self) -> usize {
+        self.size
+    }
+
+    // RSAEP/RSAVP1: out = input^e mod n, both k bytes big-endian.
+    fn public_op(&self, input: &[u8], out: &mut [u8]) -> SgxError {
+        let len = self.n.len();
+        let mut x = [0_u64; BN_MAX_LIMBS];
+        if !bignum::load_be(&mut x[..len], input)
+            || bignum::cmp_vartime(&x[..len], self.n.modulus()) != Ordering::Less {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let mut y = [0_u64; BN_MAX_LIMBS];
+        self.n.exp_vartime(&x[..len], &self.e, &mut y[..len]);
+        bignum::store_be(&y[..len], out);
+        Ok(())
+    }
+
+    pub(crate) fn verify_pkcs1_v15(&self, hash: sgx_rsa_hash_t, data: &[u8], signature: &[u8]) -> SgxResult<bool> {
+        let k = self.size;
+        if signature.len() != k {
+            return Ok(false);
+        }
+        let mut expected = [0_u8; SGX_RSA_MAX_MOD_SIZE];
+        encode_pkcs1_v15(hash, data, &mut expected[..k])?;
+
+        let mut em = [0_u8; SGX_RSA_MAX_MOD_SIZE];
+        if self.public_op(signature, &mut em[..k]).is_err() {
+            return Ok(false);
+        }
+        Ok(consttime_eq(&em[..k], &expected[..k]))
+    }
+
+    ///
+    /// Verifies an RSASSA-PSS signature with MGF1 over the same hash. The salt length
+    /// is recovered from the encoding, so signatures with any salt length are accepted.
+    ///
+    pub(crate) fn verify_pss(&self, hash: sgx_rsa_hash_t, data: &[u8], signature: &[u8]) -> SgxResult<bool> {
+        let k = self.size;
+        let h_len = hash_size(hash);
+        let em_bits = self.bits - 1;
+        let em_len = (em_bits + 7) / 8;
+        if signature.len() != k {
+            return Ok(false);
+        }
+        if em_len < h_len + 2 {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let mut m_hash = [0_u8; MAX_HASH_SIZE];
+        digest(hash, &[data], &mut m_hash)?;
+
+        let mut buf = [0_u8; SGX_RSA_MAX_MOD_SIZE];
+        if self.public_op(signature, &mut buf[..k]).is_err() {
+            return Ok(false);
+        }
+        if k > em_len && buf[0] != 0 {
+            return Ok(false);
+        }
+        let em = &mut buf[k - em_len..k];
+        if em[em_len - 1] != 0xbc {
+            return Ok(false);
+        }
+        let top_mask = 0xff_u8 >> (8 * em_len - em_bits);
+        if em[0] & !top_mask != 0 {
+            return Ok(false);
+        }
+
+        let db_len = em_len - h_len - 1;
+        let (db, rest) = em.split_at_mut(db_len);
+        let h = &rest[..h_len];
+        mgf1_xor(hash, h, db)?;
+        db[0] &= top_mask;
+
+        let sep = match db.iter().position(|b| *b != 0) {
+            Some(i) if db[i] == 0x01 => i,
+            _ => return Ok(false),
+        };
+        let salt = &db[sep + 1..];
+
+        let mut h2 = [0_u8; MAX_HASH_SIZE];
+        digest(hash, &[&[0_u8; 8], &m_hash[..h_len], salt], &mut h2)?;
+        Ok(consttime_eq(&h2[..h_len], h))
+    }
+
+    pub(crate) fn encrypt_oaep(&self, hash: sgx_rsa_hash_t, msg: &[u8], out: &mut [u8]) -> SgxError {
+        let k = self.size;
+        let h_len = hash_size(hash);
+        if k < 2 * h_len + 2 || msg.len() > k - 2 * h_len - 2 || out.len() < k {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let mut em = [0_u8; SGX_RSA_MAX_MOD_SIZE];
+        {
+            let (seed, db) = em[1..k].split_at_mut(h_len);
+            let mut l_hash = [0_u8; MAX_HASH_SIZE];
+            digest(hash, &[], &mut l_hash)?;
+            db[..h_len].copy_from_slice(&l_hash[..h_len]);
+            let db_len = db.len();
+            db[db_len - msg.len() - 1] = 0x01;
+            db[db_len - msg.len()..].copy_from_slice(msg);
+
+            read_rand(seed)?;
+            mgf1_xor(hash, seed, db)?;
+            mgf1_xor(hash, db, seed)?;
+        }
+        let ret = self.public_op(&em[..k], &mut out[..k]);
+        zeroize(&mut em);
+        ret
+    }
+}
+
+// The Chinese Remainder Theorem form of a private key.
+struct RsaCrtKey {
+    p: Mont,
+    q: Mont,
+    dp: Limbs,
+    dq: Limbs,
+    qinv: Limbs,
+}
+
+impl Drop for RsaCrtKey {
+    fn drop(&mut self) {
+        zeroize(&mut self.dp);
+        zeroize(&mut self.dq);
+        zeroize(&mut self.qinv);
+    }
+}
+
+///
+/// An RSA private key, either in CRT form (`create2`) or as the plain
+/// private exponent (`create1`). The public half is kept for the fault check
+/// done after every private operation.
+///
+pub(crate) struct RsaPrivateKey {
+    public: RsaPublicKey,
+    crt: Option<RsaCrtKey>,
+    d: Limbs,
+}
+
+impl RsaPrivateKey {
+    ///
+    /// Reads a CRT private key. `p`, `q`, `dmp1`, `dmq1` and `iqmp` are little-endian
+    /// byte strings of `mod_size / 2` bytes, as for `sgx_create_rsa_priv2_key`.
+    ///
+    pub(crate) fn from_crt(mod_size: usize,
+                           exp_size: usize,
+                           e: &[u8],
+                           p: &[u8],
+                           q: &[u8],
+                           dmp1: &[u8],
+                           dmq1: &[u8],
+                           iqmp: &[u8]) -> SgxResult<RsaPrivateKey> {
+        let half = mod_size / 2;
+        let mut tmp = [0_u64; BN_MAX_LIMBS];
+
+        load_exact_le(&mut tmp, p, half)?;
+        let p = Mont::new(&tmp).ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+        load_exact_le(&mut tmp, q, half)?;
+        let q = Mont::new(&tmp).ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+
+        let mut crt = RsaCrtKey {
+            p,
+            q,
+            dp: [0_u64; BN_MAX_LIMBS],
+            dq: [0_u64; BN_MAX_LIMBS],
+            qinv: [0_u64; BN_MAX_LIMBS],
+        };
+        let (p_len, q_len) = (crt.p.len(), crt.q.len());
+        let ret = load_exact_le(&mut crt.dp[..p_len], dmp1, half)
+            .and(load_exact_le(&mut crt.dq[..q_len], dmq1, half))
+            .and(load_exact_le(&mut tmp, iqmp, half));
+        if ret.is_err() {
+            zeroize(&mut tmp);
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        bignum::reduce(&tmp[..significant_len(half)], crt.p.modulus(), &mut crt.qinv[..p_len]);
+        zeroize(&mut tmp);
+
+        let mut n = [0_u64; 2 * BN_MAX_LIMBS];
+        bignum::mul(crt.p.modulus(), crt.q.modulus(), &mut n[..p_len + q_len]);
+        let n = if bignum::significant_limbs(&n) <= BN_MAX_LIMBS {
+            Mont::new(&n[..BN_MAX_LIMBS]).ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?
+        } else {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        };
+        let public = RsaPublicKey::from_mont(n, mod_size, exp_size, e)?;
+        Ok(RsaPrivateKey { public, crt: Some(crt), d: [0_u64; BN_MAX_LIMBS] })
+    }
+
+    ///
+    /// Reads a private key given as modulus and private exponent, as for
+    /// `sgx_create_rsa_priv1_key`.
+    ///
+    pub(crate) fn from_exponent(mod_size: usize,
+                                exp_size: usize,
+                                priv_exp_size: usize,
+                                n: &[u8],
+                                e: &[u8],
+                                d: &[u8]) -> SgxResult<RsaPrivateKey> {
+        let public = RsaPublicKey::new(mod_size, exp_size, n, e)?;
+        let mut key = RsaPrivateKey { public, crt: None, d: [0_u64; BN_MAX_LIMBS] };
+        let len = key.public.n.len();
+        load_exact_le(&mut key.d[..len], d, priv_exp_size)?;
+        Ok(key)
+    }
+
+    #[inline]
+    pub(crate) fn size(&self) -> usize {
+        self.public.size
+    }
+
+    ///
+    /// The longest message `decrypt_oaep` can return for this key and hash.
+    ///
+    pub(crate) fn oaep_max_message_size(&self, hash: sgx_rsa_hash_t) -> SgxResult<usize> {
+        let h_len = hash_size(hash);
+        if self.size() < 2 * h_len + 2 {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        Ok(self.size() - 2 * h_len - 2)
+    }
+
+    // RSADP/RSASP1: out = input^d mod n, both k bytes big-endian.
+    fn private_op(&self, input: &[u8], out: &mut [u8]) -> SgxError {
+        let n = &self.public.n;
+        let len = n.len();
+        let mut c = [0_u64; BN_MAX_LIMBS];
+        if !bignum::load_be(&mut c[..len], input)
+            || bignum::cmp_vartime(&c[..len], n.modulus()) != Ordering::Less {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let mut m = [0_u64; BN_MAX_LIMBS];
+        match self.crt {
+            Some(ref crt) => crt_exp(crt, &c[..len], &mut m[..len]),
+            None => n.exp(&c[..len], &self.d[..len], &mut m[..len]),
+        }
+
+        // Guard against faults and inconsistent key components before releasing
+        // anything derived from the private key.
+        let mut check = [0_u64; BN_MAX_LIMBS];
+        n.exp_vartime(&m[..len], &self.public.e, &mut check[..len]);
+        let ret = if bignum::cmp_vartime(&check[..len], &c[..len]) == Ordering::Equal {
+            bignum::store_be(&m[..len], out);
+            Ok(())
+        } else {
+            Err(sgx_status_t::SGX_ERROR_UNEXPECTED)
+        };
+        zeroize(&mut m);
+        ret
+    }
+
+    pub(crate) fn sign_pkcs1_v15(&self, hash: sgx_rsa_hash_t, data: &[u8], signature: &mut [u8]) -> SgxError {
+        let k = self.size();
+        if signature.len() != k {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let mut em = [0_u8; SGX_RSA_MAX_MOD_SIZE];
+        encode_pkcs1_v15(hash, data, &mut em[..k])?;
+        self.private_op(&em[..k], signature)
+    }
+
+    ///
+    /// Signs with RSASSA-PSS, MGF1 over the same hash and a random salt as long as the digest.
+    ///
+    pub(crate) fn sign_pss(&self, hash: sgx_rsa_hash_t, data: &[u8], signature: &mut [u8]) -> SgxError {
+        let k = self.size();
+        let h_len = hash_size(hash);
+        let em_bits = self.public.bits - 1;
+        let em_len = (em_bits + 7) / 8;
+        if signature.len() != k || em_len < 2 * h_len + 2 {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let mut m_hash = [0_u8; MAX_HASH_SIZE];
+        digest(hash, &[data], &mut m_hash)?;
+        let mut salt = [0_u8; MAX_HASH_SIZE];
+        read_rand(&mut salt[..h_len])?;
+
+        let mut buf = [0_u8; SGX_RSA_MAX_MOD_SIZE];
+        {
+            let em = &mut buf[k - em_len..k];
+            let db_len = em_len - h_len - 1;
+            let (db, rest) = em.split_at_mut(db_len);
+            let mut h = [0_u8; MAX_HASH_SIZE];
+            digest(hash, &[&[0_u8; 8], &m_hash[..h_len], &salt[..h_len]], &mut h)?;
+
+            db[db_len - h_len - 1] = 0x01;
+            db[db_len - h_len..].copy_from_slice(&salt[..h_len]);
+            mgf1_xor(hash, &h[..h_len], db)?;
+            db[0] &= 0xff_u8 >> (8 * em_len - em_bits);
+            rest[..h_len].copy_from_slice(&h[..h_len]);
+            rest[h_len] = 0xbc;
+        }
+        self.private_op(&buf[..k], signature)
+    }
+
+    ///
+    /// Decrypts an RSAES-OAEP ciphertext with an empty label, returning the message
+    /// length. Padding is checked in constant time and every failure is reported the
+    /// same way.
+    ///
+    pub(crate) fn decrypt_oaep(&self, hash: sgx_rsa_hash_t, input: &[u8], out: &mut [u8]) -> SgxResult<usize> {
+        let k = self.size();
+        let h_len = hash_size(hash);
+        if input.len() != k || k < 2 * h_len + 2 {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let mut em = [0_u8; SGX_RSA_MAX_MOD_SIZE];
+        self.private_op(input, &mut em[..k])?;
+
+        let mut l_hash = [0_u8; MAX_HASH_SIZE];
+        digest(hash, &[], &mut l_hash)?;
+
+        let (y, rest) = em[..k].split_at_mut(1);
+        let (seed, db) = rest.split_at_mut(h_len);
+        mgf1_xor(hash, db, seed)?;
+        mgf1_xor(hash, seed, db)?;
+
+        let mut good = zero_mask(y[0]);
+        let mut diff = 0_u8;
+        for (a, b) in db[..h_len].iter().zip(l_hash[..h_len].iter()) {
+            diff |= a ^ b;
+        }
+        good &= zero_mask(diff);
+
+        // Find the 0x01 separator without branching on the padding bytes.
+        let mut looking = 0xff_u8;
+        let mut invalid = 0_u8;
+        let mut index = 0_usize;
+        for (i, b) in db[h_len..].iter().enumerate() {
+            let is_zero = zero_mask(*b);
+            let is_one = zero_mask(*b ^ 0x01);
+            let found = looking & is_one;
+            index |= i & (usize::from(found) & 1).wrapping_neg();
+            invalid |= looking & !is_zero & !is_one;
+            looking &= !is_one;
+        }
+        good &= !looking & !invalid;
+
+        let ret = if good == 0xff {
+            let msg = &db[h_len + index + 1..];
+            if out.len() < msg.len() {
+                Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+            } else {
+                out[..msg.len()].copy_from_slice(msg);
+                Ok(msg.len())
+            }
+        } else {
+            Err(sgx_status_t::SGX_ERROR_UNEXPECTED)
+        };
+        zeroize(&mut em);
+        ret
+    }
+}
+
+impl Drop for RsaPrivateKey {
+    fn drop(&mut self) {
+        zeroize(&mut self.d);
+    }
+}
+
+fn significant_len(bytes: usize) -> usize {
+    core::cmp::min((bytes + 7) / 8, BN_MAX_LIMBS)
+}
+
+// m = c^d mod n from the CRT components (RFC 8017, 5.1.2).
+fn crt_exp(crt: &RsaCrtKey, c: &[u64], m: &mut [u64]) {
+    let (p_len, q_len) = (crt.p.len(), crt.q.len());
+    let mut cp = [0_u64; BN_MAX_LIMBS];
+    let mut m1 = [0_u64; BN_MAX_LIMBS];
+    let mut m2 = [0_u64; BN_MAX_LIMBS];
+    let mut h = [0_u64; BN_MAX_LIMBS];
+    let mut t = [0_u64; BN_MAX_LIMBS];
+
+    bignum::reduce(c, crt.p.modulus(), &mut cp[..p_len]);
+    crt.p.exp(&cp[..p_len], &crt.dp[..p_len], &mut m1[..p_len]);
+    bignum::reduce(c, crt.q.modulus(), &mut cp[..q_len]);
+    crt.q.exp(&cp[..q_len], &crt.dq[..q_len], &mut m2[..q_len]);
+
+    // h = qinv * (m1 - m2) mod p
+    bignum::reduce(&m2[..q_len], crt.p.modulus(), &mut cp[..p_len]);
+    let borrow = bignum::sub(&m1[..p_len], &cp[..p_len], &mut t[..p_len]);
+    bignum::add(&t[..p_len], crt.p.modulus(), &mut h[..p_len]);
+    bignum::select(borrow.wrapping_neg(), &h[..p_len], &t[..p_len], &mut cp[..p_len]);
+    crt.p.mul_mod(&crt.qinv[..p_len], &cp[..p_len], &mut h[..p_len]);
+
+    // m = m2 + h * q
+    let mut prod = [0_u64; 2 * BN_MAX_LIMBS];
+    bignum::mul(&h[..p_len], crt.q.modulus(), &mut prod[..p_len + q_len]);
+    let mut carry = bignum::add(&prod[..q_len], &m2[..q_len], &mut t[..q_len]);
+    prod[..q_len].copy_from_slice(&t[..q_len]);
+    for v in prod[q_len..p_len + q_len].iter_mut() {
+        let (sum, overflow) = v.overflowing_add(carry);
+        *v = sum;
+        carry = u64::from(overflow);
+    }
+    m.copy_from_slice(&prod[..m.len()]);
+
+    zeroize(&mut cp);
+    zeroize(&mut m1);
+    zeroize(&mut m2);
+    zeroize(&mut h);
+    zeroize(&mut t);
+    zeroize(&mut prod);
+}
+
+// EMSA-PKCS1-v1_5: 0x00 0x01 0xff.. 0x00 DigestInfo H, filling all of `em`.
+fn encode_pkcs1_v15(hash: sgx_rsa_hash_t, data: &[u8], em: &mut [u8]) -> SgxError {
+    let prefix = digest_info(hash);
+    let h_len = hash_size(hash);
+    let t_len = prefix.len() + h_len;
+    let k = em.len();
+    if k < t_len + 11 {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+
+    let mut h = [0_u8; MAX_HASH_SIZE];
+    digest(hash, &[data], &mut h)?;
+    em[0] = 0x00;
+    em[1] = 0x01;
+    for b in em[2..k - t_len - 1].iter_mut() {
+        *b = 0xff;
+    }
+    em[k - t_len - 1] = 0x00;
+    em[k - t_len..k - h_len].copy_from_slice(prefix);
+    em[k - h_len..].copy_from_slice(&h[..h_len]);
+    Ok(())
+}
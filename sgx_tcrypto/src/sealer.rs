@@ -0,0 +1,322 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! AEAD Sealer
+//!
+//! `AeadSealer` takes nonce management away from the caller. Every call to
+//! `seal` draws a fresh 96-bit nonce from sgx_read_rand, so no counter has to
+//! survive an enclave restart, and the sealer stops encrypting once the key has
+//! protected as many messages as is safe with random nonces.
+//!
+//! A sealed message is framed as
+//!
+//! ```text
+//! +-----------------+------------------------------+-----------------+
+//! | nonce (12 bytes)| ciphertext (plaintext len)   | MAC (16 bytes)  |
+//! +-----------------+------------------------------+-----------------+
+//! ```
+//!
+//! so it is exactly `AEAD_SEALER_OVERHEAD` bytes longer than the plaintext. The
+//! algorithm is not recorded in the frame; both sides must agree on it.
+//!
+use sgx_types::*;
+use core::cell::Cell;
+use crate::crypto::{rsgx_rijndael128GCM_encrypt, rsgx_rijndael128GCM_decrypt};
+use crate::aes_gcm_siv::*;
+use crate::secret::{SecretKey128, SecretKey256};
+use crate::internal::{zeroize, read_rand};
+
+/// Size of the nonce at the start of a sealed message.
+pub const AEAD_SEALER_NONCE_SIZE: usize = SGX_AESGCM_IV_SIZE;
+/// Size of the MAC at the end of a sealed message.
+pub const AEAD_SEALER_MAC_SIZE: usize = SGX_AESGCM_MAC_SIZE;
+/// Number of bytes a sealed message adds to the plaintext.
+pub const AEAD_SEALER_OVERHEAD: usize = AEAD_SEALER_NONCE_SIZE + AEAD_SEALER_MAC_SIZE;
+
+///
+/// The AEAD used by an `AeadSealer`.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AeadAlgorithm {
+    /// AES-128-GCM through rsgx_rijndael128GCM_encrypt.
+    Aes128Gcm,
+    /// AES-128-GCM-SIV through rsgx_rijndael128GCMSIV_encrypt.
+    Aes128GcmSiv,
+    /// AES-256-GCM-SIV through rsgx_rijndael256GCMSIV_encrypt.
+    Aes256GcmSiv,
+}
+
+impl AeadAlgorithm {
+    ///
+    /// The number of messages a single key may seal with random nonces.
+    ///
+    /// AES-GCM is held to the 2^32 invocations allowed by NIST SP 800-38D, Section 8.3.
+    /// AES-GCM-SIV tolerates nonce collisions, and a conservative 2^48 is used for it
+    /// (see RFC 8452, Section 9).
+    ///
+    pub fn message_limit(self) -> u64 {
+        match self {
+            AeadAlgorithm::Aes128Gcm => 1_u64 << 32,
+            AeadAlgorithm::Aes128GcmSiv | AeadAlgorithm::Aes256GcmSiv => 1_u64 << 48,
+        }
+    }
+}
+
+enum SealerKey {
+    Key128(SecretKey128),
+    Key256(SecretKey256),
+}
+
+///
+/// Seals and opens messages under one key with randomly generated nonces.
+///
+pub struct AeadSealer {
+    algorithm: AeadAlgorithm,
+    key: SealerKey,
+    sealed: Cell<u64>,
+    limit: u64,
+}
+
+impl AeadSealer {
+    fn with_key(algorithm: AeadAlgorithm, key: SealerKey) -> Self {
+        AeadSealer {
+            algorithm,
+            key,
+            sealed: Cell::new(0),
+            limit: algorithm.message_limit(),
+        }
+    }
+
+    ///
+    /// Creates a sealer using AES-128-GCM.
+    ///
+    /// rsgx_rijndael128GCM_encrypt rejects messages whose plaintext and AAD are both empty,
+    /// and so does this sealer.
+    ///
+    pub fn aes128_gcm(key: SecretKey128) -> Self {
+        Self::with_key(AeadAlgorithm::Aes128Gcm, SealerKey::Key128(key))
+    }
+
+    ///
+    /// Creates a sealer using AES-128-GCM-SIV.
+    ///
+    pub fn aes128_gcm_siv(key: SecretKey128) -> Self {
+        Self::with_key(AeadAlgorithm::Aes128GcmSiv, SealerKey::Key128(key))
+    }
+
+    ///
+    /// Creates a sealer using AES-256-GCM-SIV.
+    ///
+    pub fn aes256_gcm_siv(key: SecretKey256) -> Self {
+        Self::with_key(AeadAlgorithm::Aes256GcmSiv, SealerKey::Key256(key))
+    }
+
+    ///
+    /// Lowers the number of messages this sealer will seal. The algorithm's own limit
+    /// cannot be raised.
+    ///
+    pub fn with_message_limit(mut self, limit: u64) -> Self {
+        if limit < self.limit {
+            self.limit = limit;
+        }
+        self
+    }
+
+    ///
+    /// Resumes the message count of a key that has already been used, for instance by a
+    /// previous instance of the enclave. The caller is responsible for persisting it.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// `count` is below the number of messages this sealer has already sealed. Lowering
+    /// the count would let the key exceed its limit.
+    ///
+    pub fn with_sealed_count(self, count: u64) -> SgxResult<Self> {
+        if count < self.sealed.get() {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        self.sealed.set(count);
+        Ok(self)
+    }
+
+    pub fn algorithm(&self) -> AeadAlgorithm {
+        self.algorithm
+    }
+
+    ///
+    /// The number of messages sealed with this key so far.
+    ///
+    pub fn sealed_count(&self) -> u64 {
+        self.sealed.get()
+    }
+
+    ///
+    /// The number of messages that can still be sealed with this key.
+    ///
+    pub fn remaining(&self) -> u64 {
+        self.limit.saturating_sub(self.sealed.get())
+    }
+
+    ///
+    /// The size of the sealed message for a plaintext of `plaintext_len` bytes.
+    ///
+    pub fn sealed_len(plaintext_len: usize) -> SgxResult<usize> {
+        plaintext_len.checked_add(AEAD_SEALER_OVERHEAD).ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+    }
+
+    ///
+    /// seal encrypts and authenticates plaintext and writes the framed message to dst.
+    ///
+    /// # Parameters
+    ///
+    /// **plaintext**
+    ///
+    /// The data to be encrypted.
+    ///
+    /// **aad**
+    ///
+    /// Additional authenticated data. It is not included in the sealed message and must be
+    /// passed to `open` again.
+    ///
+    /// **dst**
+    ///
+    /// The output buffer. It must hold at least `sealed_len(plaintext.len())` bytes.
+    ///
+    /// # Return value
+    ///
+    /// The number of bytes written to dst.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The destination buffer is too small, or the plaintext or AAD is larger than 4 GB.
+    ///
+    /// **SGX_ERROR_INVALID_STATE**
+    ///
+    /// The key has sealed as many messages as its limit allows and must be replaced.
+    ///
+    pub fn seal(&self, plaintext: &[u8], aad: &[u8], dst: &mut [u8]) -> SgxResult<usize> {
+        let total = Self::sealed_len(plaintext.len())?;
+        if dst.len() < total {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        self.check_limit()?;
+
+        let dst = &mut dst[..total];
+        if let Err(err) = self.seal_into(plaintext, aad, dst) {
+            zeroize(dst);
+            return Err(err);
+        }
+        // A message is only counted once it has been sealed. A failed call hands no
+        // ciphertext back to the caller, so the nonce it drew has not been used.
+        self.sealed.set(self.sealed.get() + 1);
+        Ok(total)
+    }
+
+    ///
+    /// open authenticates and decrypts a message produced by `seal`.
+    ///
+    /// # Parameters
+    ///
+    /// **sealed**
+    ///
+    /// The framed message.
+    ///
+    /// **aad**
+    ///
+    /// The additional authenticated data that was passed to `seal`.
+    ///
+    /// **dst**
+    ///
+    /// The output buffer. It must hold at least `sealed.len() - AEAD_SEALER_OVERHEAD` bytes.
+    ///
+    /// # Return value
+    ///
+    /// The number of plaintext bytes written to dst.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The message is shorter than `AEAD_SEALER_OVERHEAD` or the destination buffer is too small.
+    ///
+    /// **SGX_ERROR_MAC_MISMATCH**
+    ///
+    /// The message or the AAD has been modified. Nothing is left in dst.
+    ///
+    pub fn open(&self, sealed: &[u8], aad: &[u8], dst: &mut [u8]) -> SgxResult<usize> {
+        if sealed.len() < AEAD_SEALER_OVERHEAD {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let len = sealed.len() - AEAD_SEALER_OVERHEAD;
+        if dst.len() < len {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let (nonce, rest) = sealed.split_at(AEAD_SEALER_NONCE_SIZE);
+        let (ciphertext, mac_in) = rest.split_at(len);
+        let mut mac = sgx_aes_gcm_128bit_tag_t::default();
+        mac.copy_from_slice(mac_in);
+        let dst = &mut dst[..len];
+        let result = match (self.algorithm, &self.key) {
+            (AeadAlgorithm::Aes128Gcm, SealerKey::Key128(key)) =>
+                rsgx_rijndael128GCM_decrypt(key, ciphertext, nonce, aad, &mac, dst),
+            (AeadAlgorithm::Aes128GcmSiv, SealerKey::Key128(key)) =>
+                rsgx_rijndael128GCMSIV_decrypt(key, ciphertext, nonce, aad, &mac, dst),
+            (AeadAlgorithm::Aes256GcmSiv, SealerKey::Key256(key)) =>
+                rsgx_rijndael256GCMSIV_decrypt(key, ciphertext, nonce, aad, &mac, dst),
+            _ => Err(sgx_status_t::SGX_ERROR_UNEXPECTED),
+        };
+        match result {
+            Ok(()) => Ok(len),
+            Err(err) => {
+                zeroize(dst);
+                Err(err)
+            },
+        }
+    }
+
+    fn seal_into(&self, plaintext: &[u8], aad: &[u8], dst: &mut [u8]) -> SgxError {
+        let (nonce, rest) = dst.split_at_mut(AEAD_SEALER_NONCE_SIZE);
+        let (ciphertext, mac_out) = rest.split_at_mut(plaintext.len());
+        read_rand(nonce)?;
+        let mut mac = sgx_aes_gcm_128bit_tag_t::default();
+        match (self.algorithm, &self.key) {
+            (AeadAlgorithm::Aes128Gcm, SealerKey::Key128(key)) =>
+                rsgx_rijndael128GCM_encrypt(key, plaintext, nonce, aad, ciphertext, &mut mac)?,
+            (AeadAlgorithm::Aes128GcmSiv, SealerKey::Key128(key)) =>
+                rsgx_rijndael128GCMSIV_encrypt(key, plaintext, nonce, aad, ciphertext, &mut mac)?,
+            (AeadAlgorithm::Aes256GcmSiv, SealerKey::Key256(key)) =>
+                rsgx_rijndael256GCMSIV_encrypt(key, plaintext, nonce, aad, ciphertext, &mut mac)?,
+            _ => return Err(sgx_status_t::SGX_ERROR_UNEXPECTED),
+        }
+        mac_out.copy_from_slice(&mac);
+        Ok(())
+    }
+
+    fn check_limit(&self) -> SgxError {
+        if self.sealed.get() >= self.limit {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_STATE);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,438 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! SHA-384 and SHA-512 Functions
+//!
+//! libsgx_tcrypto.a only provides SHA-1 and SHA-256, so the 64-bit SHA-2
+//! family is implemented here in portable Rust. The API mirrors the
+//! SHA-256 functions and `SgxShaHandle`.
+//!
+use sgx_types::*;
+use sgx_types::marker::ContiguousMemory;
+use core::cell::{Cell, RefCell};
+use crate::internal::{zeroize, check_msg_size, check_slice_size, msg_bytes, slice_bytes};
+
+const SHA512_BLOCK_SIZE: usize = 128;
+
+const SHA384_IV: [u64; 8] = [
+    0xcbbb_9d5d_c105_9ed8, 0x629a_292a_367c_d507, 0x9159_015a_3070_dd17, 0x152f_ecd8_f70e_5939,
+    0x6733_2667_ffc0_0b31, 0x8eb4_4a87_6858_1511, 0xdb0c_2e0d_64f9_8fa7, 0x47b5_481d_befa_4fa4,
+];
+
+const SHA512_IV: [u64; 8] = [
+    0x6a09_e667_f3bc_c908, 0xbb67_ae85_84ca_a73b, 0x3c6e_f372_fe94_f82b, 0xa54f_f53a_5f1d_36f1,
+    0x510e_527f_ade6_82d1, 0x9b05_688c_2b3e_6c1f, 0x1f83_d9ab_fb41_bd6b, 0x5be0_cd19_137e_2179,
+];
+
+const K512: [u64; 80] = [
+    0x428a_2f98_d728_ae22, 0x7137_4491_23ef_65cd, 0xb5c0_fbcf_ec4d_3b2f, 0xe9b5_dba5_8189_dbbc,
+    0x3956_c25b_f348_b538, 0x59f1_11f1_b605_d019, 0x923f_82a4_af19_4f9b, 0xab1c_5ed5_da6d_8118,
+    0xd807_aa98_a303_0242, 0x1283_5b01_4570_6fbe, 0x2431_85be_4ee4_b28c, 0x550c_7dc3_d5ff_b4e2,
+    0x72be_5d74_f27b_896f, 0x80de_b1fe_3b16_96b1, 0x9bdc_06a7_25c7_1235, 0xc19b_f174_cf69_2694,
+    0xe49b_69c1_9ef1_4ad2, 0xefbe_4786_384f_25e3, 0x0fc1_9dc6_8b8c_d5b5, 0x240c_a1cc_77ac_9c65,
+    0x2de9_2c6f_592b_0275, 0x4a74_84aa_6ea6_e483, 0x5cb0_a9dc_bd41_fbd4, 0x76f9_88da_8311_53b5,
+    0x983e_5152_ee66_dfab, 0xa831_c66d_2db4_3210, 0xb003_27c8_98fb_213f, 0xbf59_7fc7_beef_0ee4,
+    0xc6e0_0bf3_3da8_8fc2, 0xd5a7_9147_930a_a725, 0x06ca_6351_e003_826f, 0x1429_2967_0a0e_6e70,
+    0x27b7_0a85_46d2_2ffc, 0x2e1b_2138_5c26_c926, 0x4d2c_6dfc_5ac4_2aed, 0x5338_0d13_9d95_b3df,
+    0x650a_7354_8baf_63de, 0x766a_0abb_3c77_b2a8, 0x81c2_c92e_47ed_aee6, 0x9272_2c85_1482_353b,
+    0xa2bf_e8a1_4cf1_0364, 0xa81a_664b_bc42_3001, 0xc24b_8b70_d0f8_9791, 0xc76c_51a3_0654_be30,
+    0xd192_e819_d6ef_5218, 0xd699_0624_5565_a910, 0xf40e_3585_5771_202a, 0x106a_a070_32bb_d1b8,
+    0x19a4_c116_b8d2_d0c8, 0x1e37_6c08_5141_ab53, 0x2748_774c_df8e_eb99, 0x34b0_bcb5_e19b_48a8,
+    0x391c_0cb3_c5c9_5a63, 0x4ed8_aa4a_e341_8acb, 0x5b9c_ca4f_7763_e373, 0x682e_6ff3_d6b2_b8a3,
+    0x748f_82ee_5def_b2fc, 0x78a5_636f_4317_2f60, 0x84c8_7814_a1f0_ab72, 0x8cc7_0208_1a64_39ec,
+    0x90be_fffa_2363_1e28, 0xa450_6ceb_de82_bde9, 0xbef9_a3f7_b2c6_7915, 0xc671_78f2_e372_532b,
+    0xca27_3ece_ea26_619c, 0xd186_b8c7_21c0_c207, 0xeada_7dd6_cde0_eb1e, 0xf57d_4f7f_ee6e_d178,
+    0x06f0_67aa_7217_6fba, 0x0a63_7dc5_a2c8_98a6, 0x113f_9804_bef9_0dae, 0x1b71_0b35_131c_471b,
+    0x28db_77f5_2304_7d84, 0x32ca_ab7b_40c7_2493, 0x3c9e_be0a_15c9_bebc, 0x431d_67c4_9c10_0d4c,
+    0x4cc5_d4be_cb3e_42b6, 0x597f_299c_fc65_7e2a, 0x5fcb_6fab_3ad6_faec, 0x6c44_198c_4a47_5817,
+];
+
+///
+/// Internal SHA-512 state. SHA-384 uses the same compression function with
+/// a different initial value and a truncated output.
+///
+#[derive(Copy, Clone)]
+pub(crate) struct Sha512Context {
+    h: [u64; 8],
+    buf: [u8; SHA512_BLOCK_SIZE],
+    buf_len: usize,
+    total_len: u128,
+}
+
+impl Sha512Context {
+    pub(crate) fn new_sha384() -> Self {
+        Sha512Context::with_iv(SHA384_IV)
+    }
+
+    pub(crate) fn new_sha512() -> Self {
+        Sha512Context::with_iv(SHA512_IV)
+    }
+
+    fn with_iv(iv: [u64; 8]) -> Self {
+        Sha512Context {
+            h: iv,
+            buf: [0_u8; SHA512_BLOCK_SIZE],
+            buf_len: 0,
+            total_len: 0,
+        }
+    }
+
+    pub(crate) fn update(&mut self, mut data: &[u8]) {
+        self.total_len += data.len() as u128;
+
+        if self.buf_len > 0 {
+            let n = core::cmp::min(SHA512_BLOCK_SIZE - self.buf_len, data.len());
+            self.buf[self.buf_len..self.buf_len + n].copy_from_slice(&data[..n]);
+            self.buf_len += n;
+            data = &data[n..];
+            if self.buf_len < SHA512_BLOCK_SIZE {
+                return;
+            }
+            let block = self.buf;
+            self.compress(&block);
+            self.buf_len = 0;
+        }
+
+        while data.len() >= SHA512_BLOCK_SIZE {
+            let (block, rest) = data.split_at(SHA512_BLOCK_SIZE);
+            self.compress(block);
+            data = rest;
+        }
+
+        self.buf[..data.len()].copy_from_slice(data);
+        self.buf_len = data.len();
+    }
+
+    ///
+    /// Pads and processes the last block, returning the full 512-bit digest.
+    /// The context is consumed by value so callers keep their own copy intact.
+    ///
+    pub(crate) fn finalize(mut self) -> [u8; SGX_SHA512_HASH_SIZE] {
+        let bit_len = self.total_len << 3;
+        let mut pad = [0_u8; SHA512_BLOCK_SIZE * 2];
+        pad[0] = 0x80;
+        let pad_len = if self.buf_len < 112 { 112 - self.buf_len } else { 240 - self.buf_len };
+        pad[pad_len..pad_len + 16].copy_from_slice(&bit_len.to_be_bytes());
+        self.update(&pad[..pad_len + 16]);
+
+        let mut out = [0_u8; SGX_SHA512_HASH_SIZE];
+        for (chunk, v) in out.chunks_mut(8).zip(self.h.iter()) {
+            chunk.copy_from_slice(&v.to_be_bytes());
+        }
+        self.clear();
+        out
+    }
+
+    pub(crate) fn clear(&mut self) {
+        zeroize(&mut self.h);
+        zeroize(&mut self.buf);
+        self.buf_len = 0;
+        self.total_len = 0;
+    }
+
+    #[allow(clippy::many_single_char_names)]
+    fn compress(&mut self, block: &[u8]) {
+        let mut w = [0_u64; 80];
+        for (i, chunk) in block.chunks(8).enumerate() {
+            let mut b = [0_u8; 8];
+            b.copy_from_slice(chunk);
+            w[i] = u64::from_be_bytes(b);
+        }
+        for i in 16..80 {
+            let s0 = w[i - 15].rotate_right(1) ^ w[i - 15].rotate_right(8) ^ (w[i - 15] >> 7);
+            let s1 = w[i - 2].rotate_right(19) ^ w[i - 2].rotate_right(61) ^ (w[i - 2] >> 6);
+            w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+        }
+
+        let mut a = self.h[0];
+        let mut b = self.h[1];
+        let mut c = self.h[2];
+        let mut d = self.h[3];
+        let mut e = self.h[4];
+        let mut f = self.h[5];
+        let mut g = self.h[6];
+        let mut h = self.h[7];
+
+        for i in 0..80 {
+            let s1 = e.rotate_right(14) ^ e.rotate_right(18) ^ e.rotate_right(41);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = h.wrapping_add(s1).wrapping_add(ch).wrapping_add(K512[i]).wrapping_add(w[i]);
+            let s0 = a.rotate_right(28) ^ a.rotate_right(34) ^ a.rotate_right(39);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+
+        self.h[0] = self.h[0].wrapping_add(a);
+        self.h[1] = self.h[1].wrapping_add(b);
+        self.h[2] = self.h[2].wrapping_add(c);
+        self.h[3] = self.h[3].wrapping_add(d);
+        self.h[4] = self.h[4].wrapping_add(e);
+        self.h[5] = self.h[5].wrapping_add(f);
+        self.h[6] = self.h[6].wrapping_add(g);
+        self.h[7] = self.h[7].wrapping_add(h);
+        zeroize(&mut w);
+    }
+}
+
+fn sha384_digest(data: &[u8]) -> sgx_sha384_hash_t {
+    let mut ctx = Sha512Context::new_sha384();
+    ctx.update(data);
+    let mut full = ctx.finalize();
+    let mut hash: sgx_sha384_hash_t = [0_u8; SGX_SHA384_HASH_SIZE];
+    hash.copy_from_slice(&full[..SGX_SHA384_HASH_SIZE]);
+    zeroize(&mut full);
+    hash
+}
+
+fn sha512_digest(data: &[u8]) -> sgx_sha512_hash_t {
+    let mut ctx = Sha512Context::new_sha512();
+    ctx.update(data);
+    ctx.finalize()
+}
+
+///
+/// The rsgx_sha384_msg function performs a standard SHA384 hash over the input data buffer.
+///
+/// # Description
+///
+/// The function should be used if the complete input data stream is available.
+/// Otherwise, the Init, Update… Update, Final procedure of `SgxSha384Handle`
+/// should be used to compute a SHA384 hash over multiple input data sets.
+///
+/// # Parameters
+///
+/// **src**
+///
+/// A pointer to the input data stream to be hashed.
+///
+/// # Return value
+///
+/// The 384-bit hash that has been SHA384 calculated
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The input is empty or larger than 4 GB.
+///
+pub fn rsgx_sha384_msg<T>(src: &T) -> SgxResult<sgx_sha384_hash_t>
+    where T: Copy + ContiguousMemory {
+
+    let size = check_msg_size::<T>()?;
+    Ok(sha384_digest(msg_bytes(src, size)))
+}
+
+///
+/// The rsgx_sha384_slice function performs a standard SHA384 hash over the input data buffer.
+///
+pub fn rsgx_sha384_slice<T>(src: &[T]) -> SgxResult<sgx_sha384_hash_t>
+    where T: Copy + ContiguousMemory {
+
+    let size = check_slice_size(src)?;
+    Ok(sha384_digest(slice_bytes(src, size)))
+}
+
+///
+/// The rsgx_sha512_msg function performs a standard SHA512 hash over the input data buffer.
+///
+/// # Description
+///
+/// The function should be used if the complete input data stream is available.
+/// Otherwise, the Init, Update… Update, Final procedure of `SgxSha512Handle`
+/// should be used to compute a SHA512 hash over multiple input data sets.
+///
+/// # Parameters
+///
+/// **src**
+///
+/// A pointer to the input data stream to be hashed.
+///
+/// # Return value
+///
+/// The 512-bit hash that has been SHA512 calculated
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The input is empty or larger than 4 GB.
+///
+pub fn rsgx_sha512_msg<T>(src: &T) -> SgxResult<sgx_sha512_hash_t>
+    where T: Copy + ContiguousMemory {
+
+    let size = check_msg_size::<T>()?;
+    Ok(sha512_digest(msg_bytes(src, size)))
+}
+
+///
+/// The rsgx_sha512_slice function performs a standard SHA512 hash over the input data buffer.
+///
+pub fn rsgx_sha512_slice<T>(src: &[T]) -> SgxResult<sgx_sha512_hash_t>
+    where T: Copy + ContiguousMemory {
+
+    let size = check_slice_size(src)?;
+    Ok(sha512_digest(slice_bytes(src, size)))
+}
+
+macro_rules! impl_sha512_handle {
+    ($(#[$attr:meta])* $name:ident, $ctor:ident, $hash_t:ty, $hash_size:expr) => {
+        $(#[$attr])*
+        pub struct $name {
+            state: RefCell<Sha512Context>,
+            initflag: Cell<bool>,
+        }
+
+        impl $name {
+            ///
+            /// Constructs a new, empty handle.
+            ///
+            pub fn new() -> Self {
+                $name {
+                    state: RefCell::new(Sha512Context::$ctor()),
+                    initflag: Cell::new(false),
+                }
+            }
+
+            ///
+            /// init resets the hash state. It is the first step of the Init, Update … Update, Final process.
+            ///
+            pub fn init(&self) -> SgxError {
+                if self.initflag.get() {
+                    return Ok(());
+                }
+                *self.state.borrow_mut() = Sha512Context::$ctor();
+                self.initflag.set(true);
+                Ok(())
+            }
+
+            ///
+            /// update_msg hashes the input dataset provided.
+            ///
+            /// # Errors
+            ///
+            /// **SGX_ERROR_INVALID_PARAMETER**
+            ///
+            /// The input is empty or larger than 4 GB.
+            ///
+            /// **SGX_ERROR_INVALID_STATE**
+            ///
+            /// The hash state is not initialized.
+            ///
+            pub fn update_msg<T>(&self, src: &T) -> SgxError
+                where T: Copy + ContiguousMemory {
+
+                if !self.initflag.get() {
+                    return Err(sgx_status_t::SGX_ERROR_INVALID_STATE);
+                }
+                let size = check_msg_size::<T>()?;
+                self.state.borrow_mut().update(msg_bytes(src, size));
+                Ok(())
+            }
+
+            ///
+            /// update_slice hashes the input dataset provided.
+            ///
+            pub fn update_slice<T>(&self, src: &[T]) -> SgxError
+                where T: Copy + ContiguousMemory {
+
+                if !self.initflag.get() {
+                    return Err(sgx_status_t::SGX_ERROR_INVALID_STATE);
+                }
+                let size = check_slice_size(src)?;
+                self.state.borrow_mut().update(slice_bytes(src, size));
+                Ok(())
+            }
+
+            ///
+            /// get_hash obtains the hash of all datasets processed so far.
+            ///
+            /// The handle stays initialized, so more data may be appended afterwards.
+            ///
+            /// # Errors
+            ///
+            /// **SGX_ERROR_INVALID_STATE**
+            ///
+            /// The hash state is not initialized.
+            ///
+            pub fn get_hash(&self) -> SgxResult<$hash_t> {
+                if !self.initflag.get() {
+                    return Err(sgx_status_t::SGX_ERROR_INVALID_STATE);
+                }
+                let mut full = self.state.borrow().finalize();
+                let mut hash: $hash_t = [0_u8; $hash_size];
+                hash.copy_from_slice(&full[..$hash_size]);
+                zeroize(&mut full);
+                Ok(hash)
+            }
+
+            ///
+            /// close wipes the hash state that was set up in function init.
+            ///
+            pub fn close(&self) -> SgxError {
+                if !self.initflag.get() {
+                    return Ok(());
+                }
+                self.state.borrow_mut().clear();
+                self.initflag.set(false);
+                Ok(())
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl Drop for $name {
+            ///
+            /// drop wipes the hash state.
+            ///
+            fn drop(&mut self) {
+                let _ = self.close();
+            }
+        }
+    }
+}
+
+impl_sha512_handle! {
+    ///
+    /// SHA384 algorithm context state.
+    ///
+    /// This is a handle to the context state used to perform an iterative SHA384 hash.
+    ///
+    SgxSha384Handle, new_sha384, sgx_sha384_hash_t, SGX_SHA384_HASH_SIZE
+}
+
+impl_sha512_handle! {
+    ///
+    /// SHA512 algorithm context state.
+    ///
+    /// This is a handle to the context state used to perform an iterative SHA512 hash.
+    ///
+    SgxSha512Handle, new_sha512, sgx_sha512_hash_t, SGX_SHA512_HASH_SIZE
+}
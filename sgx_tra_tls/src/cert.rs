@@ -0,0 +1,293 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! RA-TLS certificates.
+//!
+//! An RA-TLS certificate is a self-signed P-256 certificate whose key is bound
+//! to an enclave: the report data of the attested quote is the public key,
+//! `X || Y` big-endian. The IAS attestation report, its signature and the
+//! report signing certificate are carried in a Netscape comment extension as
+//! `report|base64(signature)|base64(signing certificate)`, the format used by
+//! the RA samples of this SDK.
+//!
+
+use std::prelude::v1::*;
+use sgx_types::*;
+use crypto::*;
+use crate::der::*;
+use crate::error::RaTlsError;
+use crate::time;
+
+/// Netscape comment, 2.16.840.1.113730.1.13.
+pub const OID_NETSCAPE_COMMENT: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x86, 0xf8, 0x42, 0x01, 0x0d];
+
+const OID_ECDSA_WITH_SHA256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02];
+const OID_COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+
+/// Issuer and subject common name of generated certificates.
+pub const CERT_COMMON_NAME: &str = "Teaclave RA-TLS";
+/// Validity period of generated certificates.
+pub const CERT_VALIDITY_DAYS: u64 = 90;
+
+/// An IAS attestation verification report with its signature and signing
+/// certificate, as returned by the IAS report API.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AttestationEvidence {
+    /// The JSON report body.
+    pub report: Vec<u8>,
+    /// The decoded `X-IASReport-Signature` header.
+    pub signature: Vec<u8>,
+    /// The DER report signing certificate, the first certificate of the
+    /// `X-IASReport-Signing-Certificate` header.
+    pub signing_cert: Vec<u8>,
+}
+
+impl AttestationEvidence {
+    pub fn new(report: Vec<u8>, signature: Vec<u8>, signing_cert: Vec<u8>) -> AttestationEvidence {
+        AttestationEvidence { report, signature, signing_cert }
+    }
+
+    /// Encodes the evidence into the certificate extension payload.
+    pub fn to_payload(&self) -> Vec<u8> {
+        let mut payload = self.report.clone();
+        payload.push(b'|');
+        payload.extend_from_slice(base64::encode(&self.signature).as_bytes());
+        payload.push(b'|');
+        payload.extend_from_slice(base64::encode(&self.signing_cert).as_bytes());
+        payload
+    }
+
+    /// Decodes a certificate extension payload.
+    ///
+    /// The report itself is JSON and never contains `|`, so the payload must
+    /// split into exactly three parts.
+    pub fn from_payload(payload: &[u8]) -> Result<AttestationEvidence, RaTlsError> {
+        let mut parts = payload.split(|b| *b == b'|');
+        let (report, signature, signing_cert) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(report), Some(signature), Some(signing_cert), None) => (report, signature, signing_cert),
+            _ => return Err(RaTlsError::BadEvidence),
+        };
+        Ok(AttestationEvidence {
+            report: report.to_vec(),
+            signature: base64::decode(signature).map_err(|_| RaTlsError::BadEvidence)?,
+            signing_cert: base64::decode(signing_cert).map_err(|_| RaTlsError::BadEvidence)?,
+        })
+    }
+}
+
+/// Returns the report data that binds `public` to a quote: the big-endian
+/// X and Y coordinates of the key.
+///
+/// The enclave passes it to `rsgx_create_report` before quoting, and the
+/// verifier compares it with the key of the certificate.
+pub fn key_report_data(public: &sgx_ec256_public_t) -> sgx_report_data_t {
+    let point = rsgx_ec256_public_to_sec1(public);
+    let mut report_data = sgx_report_data_t::default();
+    report_data.d.copy_from_slice(&point[1..]);
+    report_data
+}
+
+/// Generates an RA-TLS certificate for the key pair and evidence, valid for
+/// `CERT_VALIDITY_DAYS` from now.
+///
+/// Returns the PKCS#8 DER private key and the DER certificate, ready for
+/// `rustls::PrivateKey` and `rustls::Certificate`.
+///
+/// # Errors
+///
+/// `RaTlsError::Crypto` if signing or encoding fails.
+pub fn gen_ecc_cert(evidence: &AttestationEvidence,
+                    prv_k: &sgx_ec256_private_t,
+                    pub_k: &sgx_ec256_public_t,
+                    ecc_handle: &SgxEccHandle) -> Result<(Vec<u8>, Vec<u8>), RaTlsError> {
+    gen_ecc_cert_at(evidence, prv_k, pub_k, ecc_handle, time::now_secs())
+}
+
+pub(crate) fn gen_ecc_cert_at(evidence: &AttestationEvidence,
+                              prv_k: &sgx_ec256_private_t,
+                              pub_k: &sgx_ec256_public_t,
+                              ecc_handle: &SgxEccHandle,
+                              not_before: u64) -> Result<(Vec<u8>, Vec<u8>), RaTlsError> {
+    let mut tbs = Vec::new();
+    write_nested(&mut tbs, TAG_SEQUENCE, |out| {
+        // Version: v3
+        write_nested(out, TAG_CONTEXT_0, |out| write_tlv(out, TAG_INTEGER, &[2]));
+        // Serial number
+        write_tlv(out, TAG_INTEGER, &[1]);
+        write_nested(out, TAG_SEQUENCE, |out| write_tlv(out, TAG_OID, OID_ECDSA_WITH_SHA256));
+        write_name(out);
+        write_nested(out, TAG_SEQUENCE, |out| {
+            let (tag, start) = time::x509_time(not_before);
+            write_tlv(out, tag, &start);
+            let (tag, end) = time::x509_time(not_before + CERT_VALIDITY_DAYS * 86_400);
+            write_tlv(out, tag, &end);
+        });
+        write_name(out);
+        out.extend_from_slice(&rsgx_ec256_public_to_spki_der(pub_k));
+        write_nested(out, TAG_CONTEXT_3, |out| {
+            write_nested(out, TAG_SEQUENCE, |out| {
+                write_nested(out, TAG_SEQUENCE, |out| {
+                    write_tlv(out, TAG_OID, OID_NETSCAPE_COMMENT);
+                    write_tlv(out, TAG_OCTET_STRING, &evidence.to_payload());
+                });
+            });
+        });
+    });
+
+    let signature = ecc_handle.ecdsa_sign_slice(&tbs, prv_k).map_err(RaTlsError::Crypto)?;
+    let mut sig_der = [0_u8; SGX_EC256_SIGNATURE_DER_MAX_SIZE];
+    let sig_len = rsgx_ec256_signature_to_der(&signature, &mut sig_der).map_err(RaTlsError::Crypto)?;
+
+    let mut cert_der = Vec::new();
+    write_nested(&mut cert_der, TAG_SEQUENCE, |out| {
+        out.extend_from_slice(&tbs);
+        write_nested(out, TAG_SEQUENCE, |out| write_tlv(out, TAG_OID, OID_ECDSA_WITH_SHA256));
+        write_bit_string(out, &sig_der[..sig_len]);
+    });
+
+    let mut key_der = vec![0_u8; SGX_EC256_PKCS8_DER_MAX_SIZE];
+    let key_len = rsgx_ec256_private_to_pkcs8_der(prv_k, Some(pub_k), &mut key_der).map_err(RaTlsError::Crypto)?;
+    key_der.truncate(key_len);
+
+    Ok((key_der, cert_der))
+}
+
+fn write_name(out: &mut Vec<u8>) {
+    write_nested(out, TAG_SEQUENCE, |out| {
+        write_nested(out, TAG_SET, |out| {
+            write_nested(out, TAG_SEQUENCE, |out| {
+                write_tlv(out, TAG_OID, OID_COMMON_NAME);
+                write_tlv(out, TAG_UTF8_STRING, CERT_COMMON_NAME.as_bytes());
+            });
+        });
+    });
+}
+
+/// Extracts the public key and the attestation evidence from an RA-TLS
+/// certificate.
+///
+/// This only parses the certificate; `RaTlsVerifier` checks the evidence.
+/// The certificate signature is not checked either: anyone can self-sign,
+/// and the TLS handshake already proves possession of the key.
+///
+/// # Errors
+///
+/// `RaTlsError::BadCertificate` if the certificate is malformed, its key is
+/// not a P-256 key, or it has no (or more than one) attestation extension.
+/// `RaTlsError::BadEvidence` if the extension cannot be decoded.
+pub fn extract_evidence(cert_der: &[u8]) -> Result<(sgx_ec256_public_t, AttestationEvidence), RaTlsError> {
+    let (spki, payload) = parse_cert(cert_der).ok_or(RaTlsError::BadCertificate)?;
+    let public = rsgx_ec256_public_from_spki_der(spki).map_err(|_| RaTlsError::BadCertificate)?;
+    let evidence = AttestationEvidence::from_payload(payload)?;
+    Ok((public, evidence))
+}
+
+// Returns the encoded SubjectPublicKeyInfo and the attestation payload.
+fn parse_cert(cert_der: &[u8]) -> Option<(&[u8], &[u8])> {
+    let mut outer = DerReader::new(cert_der);
+    let mut cert = outer.read_sequence()?;
+    outer.expect_empty()?;
+
+    let mut tbs = cert.read_sequence()?;
+    tbs.skip_optional(TAG_CONTEXT_0)?;
+    tbs.read(TAG_INTEGER)?;
+    // signature, issuer, validity, subject
+    for _ in 0..4 {
+        tbs.read(TAG_SEQUENCE)?;
+    }
+    let (_, spki) = tbs.read_with_encoding(TAG_SEQUENCE)?;
+    // issuerUniqueID, subjectUniqueID
+    tbs.skip_optional(0x81)?;
+    tbs.skip_optional(0x82)?;
+    let mut exts = DerReader::new(tbs.read(TAG_CONTEXT_3)?);
+    tbs.expect_empty()?;
+    let mut exts_seq = exts.read_sequence()?;
+    exts.expect_empty()?;
+
+    let mut payload = None;
+    while !exts_seq.is_empty() {
+        let mut ext = exts_seq.read_sequence()?;
+        let oid = ext.read(TAG_OID)?;
+        ext.skip_optional(TAG_BOOLEAN)?;
+        let value = ext.read(TAG_OCTET_STRING)?;
+        ext.expect_empty()?;
+        if oid == OID_NETSCAPE_COMMENT {
+            if payload.is_some() {
+                return None;
+            }
+            payload = Some(value);
+        }
+    }
+
+    // signatureAlgorithm, signatureValue
+    cert.read(TAG_SEQUENCE)?;
+    cert.read(TAG_BIT_STRING)?;
+    cert.expect_empty()?;
+
+    payload.map(|payload| (spki, payload))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATLS_CERT: &[u8] = include_bytes!("../testdata/ratls_cert.der");
+
+    #[test]
+    fn payload_roundtrip() {
+        let evidence = AttestationEvidence::new(b"{\"id\":\"1\"}".to_vec(), vec![1, 2, 3], vec![0x30, 0x00]);
+        assert_eq!(AttestationEvidence::from_payload(&evidence.to_payload()), Ok(evidence));
+        assert_eq!(AttestationEvidence::from_payload(b"{}|AQID"), Err(RaTlsError::BadEvidence));
+        assert_eq!(AttestationEvidence::from_payload(b"{}|AQID|MAA=|MAA="), Err(RaTlsError::BadEvidence));
+        assert_eq!(AttestationEvidence::from_payload(b"{}|!!|MAA="), Err(RaTlsError::BadEvidence));
+    }
+
+    #[test]
+    fn extract_fixture() {
+        let (public, evidence) = extract_evidence(RATLS_CERT).unwrap();
+        assert!(evidence.report.starts_with(b"{\"id\":"));
+        assert_eq!(evidence.signature.len(), 256);
+        assert!(webpki::EndEntityCert::from(&evidence.signing_cert).is_ok());
+        let spki = rsgx_ec256_public_to_spki_der(&public);
+        assert!(RATLS_CERT.windows(spki.len()).any(|w| w == &spki[..]));
+    }
+
+    #[test]
+    fn extract_rejects_malformed() {
+        assert_eq!(extract_evidence(&RATLS_CERT[..RATLS_CERT.len() - 1]).err(), Some(RaTlsError::BadCertificate));
+        assert_eq!(extract_evidence(&[]).err(), Some(RaTlsError::BadCertificate));
+    }
+
+    #[test]
+    fn gen_extract_roundtrip() {
+        let ecc_handle = SgxEccHandle::new();
+        ecc_handle.open().unwrap();
+        let (prv_k, pub_k) = ecc_handle.create_key_pair().unwrap();
+        let evidence = AttestationEvidence::new(b"{}".to_vec(), vec![0xaa; 256], vec![0x30, 0x00]);
+
+        let (key_der, cert_der) = gen_ecc_cert_at(&evidence, &prv_k, &pub_k, &ecc_handle, 1_591_012_800).unwrap();
+        assert_eq!(key_der[0], TAG_SEQUENCE);
+        assert!(webpki::EndEntityCert::from(&cert_der).is_ok());
+
+        let (public, extracted) = extract_evidence(&cert_der).unwrap();
+        assert_eq!(public.gx, pub_k.gx);
+        assert_eq!(public.gy, pub_k.gy);
+        assert_eq!(extracted, evidence);
+    }
+}
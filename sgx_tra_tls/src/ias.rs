@@ -0,0 +1,597 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! IAS attestation verification reports: the report API response, the JSON
+//! report and its signature, and the platform info blob.
+//!
+//! Everything here is plain parsing and sgx_tcrypto RSA, so reports can be
+//! checked inside an enclave without a TLS stack or webpki.
+//!
+
+use std::prelude::v1::*;
+use std::str;
+use sgx_types::*;
+use sgx_types::quote::QuoteView;
+use crypto::*;
+use serde_json::Value;
+use crate::cert::AttestationEvidence;
+use crate::error::RaTlsError;
+use crate::policy::QuoteStatus;
+use crate::time;
+use crate::x509::{self, CertInfo, OID_SHA256_WITH_RSA};
+
+// `epid_group_flags` of the platform info blob.
+pub const QE_EPID_GROUP_REVOKED: u8 = 0x01;
+pub const PERF_REKEY_FOR_QE_EPID_GROUP_AVAILABLE: u8 = 0x02;
+pub const QE_EPID_GROUP_OUT_OF_DATE: u8 = 0x04;
+
+// `tcb_evaluation_flags` of the platform info blob.
+pub const QUOTE_CPUSVN_OUT_OF_DATE: u16 = 0x0001;
+pub const QUOTE_ISVSVN_QE_OUT_OF_DATE: u16 = 0x0002;
+pub const QUOTE_ISVSVN_PCE_OUT_OF_DATE: u16 = 0x0004;
+pub const PLATFORM_CONFIGURATION_NEEDED: u16 = 0x0008;
+
+// `pse_evaluation_flags` of the platform info blob.
+pub const PSE_ISVSVN_OUT_OF_DATE: u16 = 0x0001;
+pub const EPID_GROUP_ID_BY_PS_HW_GID_REVOKED: u16 = 0x0002;
+pub const SVN_FROM_PS_HW_SEC_INFO_OUT_OF_DATE: u16 = 0x0004;
+pub const SIGRL_VER_FROM_PS_HW_SIG_RLVER_OUT_OF_DATE: u16 = 0x0008;
+pub const PRIVRL_VER_FROM_PS_HW_PRV_KEY_RLVER_OUT_OF_DATE: u16 = 0x0010;
+
+// The longest nonce IAS accepts in a report request.
+const IAS_NONCE_MAX_LEN: usize = 32;
+
+// The TLV header IAS puts in front of the platform info: type 21,
+// version 2, big-endian size.
+const PIB_HEADER: [u8; 4] = [0x15, 0x02, 0x00, SGX_PLATFORM_INFO_SIZE as u8];
+
+/// The platform info blob of a report, as returned by IAS for quotes of
+/// platforms that need an update.
+///
+/// The multi-byte fields are big-endian on the wire; the accessors return
+/// native values.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlatformInfoBlob {
+    raw: Vec<u8>,
+}
+
+impl PlatformInfoBlob {
+    /// Parses the hex `platformInfoBlob` field of a report.
+    pub fn parse(hex: &str) -> Option<PlatformInfoBlob> {
+        let bytes = decode_hex(hex)?;
+        if bytes.len() != PIB_HEADER.len() + SGX_PLATFORM_INFO_SIZE || bytes[..4] != PIB_HEADER {
+            return None;
+        }
+        Some(PlatformInfoBlob { raw: bytes[4..].to_vec() })
+    }
+
+    /// The blob without its TLV header.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.raw
+    }
+
+    /// The blob as passed to `sgx_report_attestation_status`.
+    pub fn platform_info(&self) -> sgx_platform_info_t {
+        let mut info = sgx_platform_info_t::default();
+        info.platform_info.copy_from_slice(&self.raw);
+        info
+    }
+
+    pub fn epid_group_flags(&self) -> u8 {
+        self.raw[0]
+    }
+
+    pub fn tcb_evaluation_flags(&self) -> u16 {
+        u16::from_be_bytes([self.raw[1], self.raw[2]])
+    }
+
+    pub fn pse_evaluation_flags(&self) -> u16 {
+        u16::from_be_bytes([self.raw[3], self.raw[4]])
+    }
+
+    /// The CPUSVN and PCE ISVSVN of the latest equivalent TCB.
+    pub fn latest_equivalent_tcb_psvn(&self) -> &[u8] {
+        &self.raw[5..23]
+    }
+
+    pub fn latest_pse_isvsvn(&self) -> &[u8] {
+        &self.raw[23..25]
+    }
+
+    pub fn latest_psda_svn(&self) -> &[u8] {
+        &self.raw[25..29]
+    }
+
+    pub fn xeid(&self) -> u32 {
+        u32::from_be_bytes([self.raw[29], self.raw[30], self.raw[31], self.raw[32]])
+    }
+
+    pub fn gid(&self) -> u32 {
+        u32::from_be_bytes([self.raw[33], self.raw[34], self.raw[35], self.raw[36]])
+    }
+
+    /// The ECDSA signature of the blob by IAS.
+    pub fn signature(&self) -> &[u8] {
+        &self.raw[37..]
+    }
+
+    /// Returns true if the platform needs a microcode or PSW update to reach
+    /// the latest TCB.
+    pub fn tcb_out_of_date(&self) -> bool {
+        self.tcb_evaluation_flags() & (QUOTE_CPUSVN_OUT_OF_DATE |
+                                       QUOTE_ISVSVN_QE_OUT_OF_DATE |
+                                       QUOTE_ISVSVN_PCE_OUT_OF_DATE) != 0
+    }
+
+    pub fn configuration_needed(&self) -> bool {
+        self.tcb_evaluation_flags() & PLATFORM_CONFIGURATION_NEEDED != 0
+    }
+}
+
+/// An IAS attestation verification report.
+///
+/// Parsing only checks the structure. Use `verify_report_signature` first
+/// to make sure the report comes from IAS.
+#[derive(Clone, Debug)]
+pub struct IasReport {
+    id: String,
+    timestamp: u64,
+    version: u64,
+    quote_status: QuoteStatus,
+    quote_body: Vec<u8>,
+    platform_info_blob: Option<PlatformInfoBlob>,
+    advisory_url: Option<String>,
+    advisory_ids: Vec<String>,
+    nonce: Option<String>,
+    epid_pseudonym: Option<Vec<u8>>,
+}
+
+impl IasReport {
+    /// Parses the JSON body of a report.
+    ///
+    /// # Errors
+    ///
+    /// `RaTlsError::BadReport` if a mandatory field is missing or any field
+    /// is malformed, including a quote body that is not 432 bytes.
+    pub fn parse(report: &[u8]) -> Result<IasReport, RaTlsError> {
+        let json: Value = serde_json::from_slice(report).map_err(|_| RaTlsError::BadReport)?;
+        let id = json["id"].as_str().ok_or(RaTlsError::BadReport)?;
+        let timestamp = json["timestamp"].as_str()
+            .and_then(time::parse_ias_timestamp)
+            .ok_or(RaTlsError::BadReport)?;
+        let version = json["version"].as_u64().ok_or(RaTlsError::BadReport)?;
+        let quote_status = json["isvEnclaveQuoteStatus"].as_str()
+            .and_then(QuoteStatus::parse)
+            .ok_or(RaTlsError::BadReport)?;
+        let quote_body = json["isvEnclaveQuoteBody"].as_str()
+            .and_then(|body| base64::decode(body).ok())
+            .ok_or(RaTlsError::BadReport)?;
+        QuoteView::parse_body(&quote_body).map_err(|_| RaTlsError::BadReport)?;
+
+        let platform_info_blob = match json.get("platformInfoBlob") {
+            Some(pib) => Some(pib.as_str().and_then(PlatformInfoBlob::parse).ok_or(RaTlsError::BadReport)?),
+            None => None,
+        };
+        let advisory_url = optional_str(&json, "advisoryURL")?;
+        let advisory_ids = match json.get("advisoryIDs") {
+            Some(ids) => {
+                let ids = ids.as_array().ok_or(RaTlsError::BadReport)?;
+                ids.iter()
+                    .map(|id| id.as_str().map(String::from).ok_or(RaTlsError::BadReport))
+                    .collect::<Result<Vec<String>, RaTlsError>>()?
+            },
+            None => Vec::new(),
+        };
+        let nonce = optional_str(&json, "nonce")?;
+        let epid_pseudonym = match optional_str(&json, "epidPseudonym")? {
+            Some(pseudonym) => Some(base64::decode(&pseudonym).map_err(|_| RaTlsError::BadReport)?),
+            None => None,
+        };
+
+        Ok(IasReport {
+            id: id.to_owned(),
+            timestamp,
+            version,
+            quote_status,
+            quote_body,
+            platform_info_blob,
+            advisory_url,
+            advisory_ids,
+            nonce,
+            epid_pseudonym,
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The time IAS issued the report, in seconds since the Unix epoch.
+    pub fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// The report API version.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn quote_status(&self) -> QuoteStatus {
+        self.quote_status
+    }
+
+    /// The quote body, without the quote signature.
+    pub fn quote(&self) -> QuoteView<'_> {
+        // Checked in parse.
+        QuoteView::parse_body(&self.quote_body).unwrap()
+    }
+
+    pub fn platform_info_blob(&self) -> Option<&PlatformInfoBlob> {
+        self.platform_info_blob.as_ref()
+    }
+
+    pub fn advisory_url(&self) -> Option<&str> {
+        self.advisory_url.as_deref()
+    }
+
+    /// The Intel security advisories that apply to the platform.
+    pub fn advisory_ids(&self) -> &[String] {
+        &self.advisory_ids
+    }
+
+    /// The nonce passed with the quote, if any.
+    pub fn nonce(&self) -> Option<&str> {
+        self.nonce.as_deref()
+    }
+
+    /// The EPID pseudonym, present for linkable quotes.
+    pub fn epid_pseudonym(&self) -> Option<&[u8]> {
+        self.epid_pseudonym.as_deref()
+    }
+
+    /// Checks that the report is at most `max_age` seconds old at `now`.
+    ///
+    /// Reports dated after `now` pass, since the clocks of IAS and the
+    /// verifier are not synchronized.
+    pub fn check_freshness(&self, now: u64, max_age: u64) -> Result<(), RaTlsError> {
+        if now.saturating_sub(self.timestamp) > max_age {
+            return Err(RaTlsError::StaleReport);
+        }
+        Ok(())
+    }
+
+    /// Checks that the report carries the nonce the verifier sent with the
+    /// quote.
+    pub fn check_nonce(&self, nonce: &str) -> Result<(), RaTlsError> {
+        if self.nonce() != Some(nonce) {
+            return Err(RaTlsError::NonceMismatch);
+        }
+        Ok(())
+    }
+}
+
+fn optional_str(json: &Value, key: &str) -> Result<Option<String>, RaTlsError> {
+    match json.get(key) {
+        Some(value) => value.as_str().map(|s| Some(s.to_owned())).ok_or(RaTlsError::BadReport),
+        None => Ok(None),
+    }
+}
+
+/// Builds the HTTP request for the IAS report API.
+///
+/// `nonce` is echoed in the report.
+///
+/// # Errors
+///
+/// `RaTlsError::BadNonce` if `nonce` is longer than the 32 characters IAS
+/// accepts or is not alphanumeric.
+pub fn report_request(host: &str, path: &str, api_key: &str, quote: &[u8], nonce: Option<&str>) -> Result<String, RaTlsError> {
+    let body = match nonce {
+        Some(nonce) => {
+            if nonce.len() > IAS_NONCE_MAX_LEN || !nonce.bytes().all(|b| b.is_ascii_alphanumeric()) {
+                return Err(RaTlsError::BadNonce);
+            }
+            format!("{{\"isvEnclaveQuote\":\"{}\",\"nonce\":\"{}\"}}", base64::encode(quote), nonce)
+        },
+        None => format!("{{\"isvEnclaveQuote\":\"{}\"}}", base64::encode(quote)),
+    };
+    Ok(format!("POST {} HTTP/1.1\r\nHost: {}\r\nOcp-Apim-Subscription-Key: {}\r\nContent-Length: {}\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n{}",
+            path, host, api_key, body.len(), body))
+}
+
+/// Parses an IAS report API response into attestation evidence.
+///
+/// The signing certificate is the first certificate of the
+/// `X-IASReport-Signing-Certificate` chain.
+///
+/// # Errors
+///
+/// `RaTlsError::IasStatus` if IAS did not answer `200 OK`, and
+/// `RaTlsError::BadIasResponse` if the response is not a complete HTTP
+/// response with the report signature headers.
+pub fn parse_report_response(resp: &[u8]) -> Result<AttestationEvidence, RaTlsError> {
+    let header_end = resp.windows(4).position(|w| w == b"\r\n\r\n").ok_or(RaTlsError::BadIasResponse)?;
+    let head = str::from_utf8(&resp[..header_end]).map_err(|_| RaTlsError::BadIasResponse)?;
+    let body = &resp[header_end + 4..];
+
+    let mut lines = head.split("\r\n");
+    let mut status_line = lines.next().unwrap_or("").splitn(3, ' ');
+    if !status_line.next().unwrap_or("").starts_with("HTTP/1.") {
+        return Err(RaTlsError::BadIasResponse);
+    }
+    let status = status_line.next()
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or(RaTlsError::BadIasResponse)?;
+    if status != 200 {
+        return Err(RaTlsError::IasStatus(status));
+    }
+
+    let mut content_length = None;
+    let mut signature = None;
+    let mut signing_cert = None;
+    for line in lines {
+        let colon = line.find(':').ok_or(RaTlsError::BadIasResponse)?;
+        let (name, value) = (&line[..colon], line[colon + 1..].trim());
+        if name.eq_ignore_ascii_case("Content-Length") {
+            content_length = Some(value.parse::<usize>().map_err(|_| RaTlsError::BadIasResponse)?);
+        } else if name.eq_ignore_ascii_case("Transfer-Encoding") {
+            return Err(RaTlsError::BadIasResponse);
+        } else if name.eq_ignore_ascii_case("X-IASReport-Signature") {
+            signature = Some(base64::decode(value).map_err(|_| RaTlsError::BadIasResponse)?);
+        } else if name.eq_ignore_ascii_case("X-IASReport-Signing-Certificate") {
+            let pem = percent_decode(value).ok_or(RaTlsError::BadIasResponse)?;
+            let mut der = vec![0_u8; pem.len()];
+            let len = rsgx_pem_decode("CERTIFICATE", &pem, &mut der).map_err(|_| RaTlsError::BadIasResponse)?;
+            der.truncate(len);
+            signing_cert = Some(der);
+        }
+    }
+
+    let report = match content_length {
+        Some(len) if len <= body.len() => &body[..len],
+        Some(_) => return Err(RaTlsError::BadIasResponse),
+        None => body,
+    };
+    match (signature, signing_cert) {
+        (Some(signature), Some(signing_cert)) => Ok(AttestationEvidence::new(report.to_vec(), signature, signing_cert)),
+        _ => Err(RaTlsError::BadIasResponse),
+    }
+}
+
+/// Verifies the report signature of `evidence`.
+///
+/// The signing certificate must be issued by `root_ca`, given in DER, be
+/// valid at `now`, in seconds since the Unix epoch, and its key must verify
+/// the RSA-SHA256 report signature. IAS signs directly under its root, so
+/// no intermediates are accepted.
+///
+/// # Errors
+///
+/// `RaTlsError::BadSigningCert` if the certificate is malformed, expired or
+/// not issued by the root, and `RaTlsError::BadReportSignature` if the
+/// report signature does not verify.
+pub fn verify_report_signature(evidence: &AttestationEvidence, root_ca: &[u8], now: u64) -> Result<(), RaTlsError> {
+    let root = CertInfo::parse(root_ca).ok_or(RaTlsError::BadSigningCert)?;
+    let cert = CertInfo::parse(&evidence.signing_cert).ok_or(RaTlsError::BadSigningCert)?;
+    if cert.issuer != root.subject || !cert.is_valid_at(now) || cert.signature_algorithm != OID_SHA256_WITH_RSA {
+        return Err(RaTlsError::BadSigningCert);
+    }
+    if !x509::rsa_verify(root.spki, cert.tbs, cert.signature)? {
+        return Err(RaTlsError::BadSigningCert);
+    }
+    if !x509::rsa_verify(cert.spki, &evidence.report, &evidence.signature)? {
+        return Err(RaTlsError::BadReportSignature);
+    }
+    Ok(())
+}
+
+pub(crate) fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    let hex = hex.as_bytes();
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    hex.chunks(2)
+        .map(|pair| Some((hex_value(pair[0])? << 4) | hex_value(pair[1])?))
+        .collect()
+}
+
+fn hex_value(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+fn percent_decode(s: &str) -> Option<Vec<u8>> {
+    let s = s.as_bytes();
+    let mut out = Vec::with_capacity(s.len());
+    let mut i = 0;
+    while i < s.len() {
+        if s[i] == b'%' {
+            if i + 2 >= s.len() {
+                return None;
+            }
+            out.push((hex_value(s[i + 1])? << 4) | hex_value(s[i + 2])?);
+            i += 3;
+        } else {
+            out.push(s[i]);
+            i += 1;
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::der::{TAG_GENERALIZED_TIME, TAG_UTC_TIME};
+
+    // See testdata/gen.sh. The report was issued at 2020-06-01T12:30:00.
+    const IAS_RESPONSE: &[u8] = include_bytes!("../testdata/ias_response.http");
+    const TEST_ROOT_CA: &[u8] = include_bytes!("../testdata/ias_root_ca.der");
+    const ISSUED_AT: u64 = 1_591_014_600;
+
+    fn evidence() -> AttestationEvidence {
+        parse_report_response(IAS_RESPONSE).unwrap()
+    }
+
+    #[test]
+    fn parse_response() {
+        let evidence = evidence();
+        assert!(evidence.report.starts_with(b"{\"id\":"));
+        assert!(evidence.report.ends_with(b"}"));
+        assert_eq!(evidence.signature.len(), 256);
+        let cert = CertInfo::parse(&evidence.signing_cert).unwrap();
+        let root = CertInfo::parse(TEST_ROOT_CA).unwrap();
+        assert_eq!(cert.issuer, root.subject);
+    }
+
+    #[test]
+    fn parse_response_errors() {
+        let unauthorized = b"HTTP/1.1 401 Access Denied\r\nContent-Length: 0\r\n\r\n";
+        assert_eq!(parse_report_response(unauthorized).err(), Some(RaTlsError::IasStatus(401)));
+
+        let no_signature = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}";
+        assert_eq!(parse_report_response(no_signature).err(), Some(RaTlsError::BadIasResponse));
+
+        let truncated = &IAS_RESPONSE[..IAS_RESPONSE.len() - 1];
+        assert_eq!(parse_report_response(truncated).err(), Some(RaTlsError::BadIasResponse));
+
+        let header_end = IAS_RESPONSE.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+        assert_eq!(parse_report_response(&IAS_RESPONSE[..header_end]).err(), Some(RaTlsError::BadIasResponse));
+    }
+
+    #[test]
+    fn verify_signature() {
+        let evidence = evidence();
+        assert_eq!(verify_report_signature(&evidence, TEST_ROOT_CA, ISSUED_AT), Ok(()));
+
+        let mut tampered = evidence.clone();
+        tampered.report[10] ^= 1;
+        assert_eq!(verify_report_signature(&tampered, TEST_ROOT_CA, ISSUED_AT), Err(RaTlsError::BadReportSignature));
+
+        let mut tampered = evidence.clone();
+        let pos = tampered.signing_cert.len() - 1;
+        tampered.signing_cert[pos] ^= 1;
+        assert_eq!(verify_report_signature(&tampered, TEST_ROOT_CA, ISSUED_AT), Err(RaTlsError::BadSigningCert));
+
+        // The signing certificate is valid from 2020 through 2049.
+        assert_eq!(verify_report_signature(&evidence, TEST_ROOT_CA, 1_546_300_800), Err(RaTlsError::BadSigningCert));
+        assert_eq!(verify_report_signature(&evidence, TEST_ROOT_CA, 2_524_608_000), Err(RaTlsError::BadSigningCert));
+    }
+
+    #[test]
+    fn verify_signature_pinned_root() {
+        let mut root = vec![0_u8; crate::IAS_REPORT_CA.len()];
+        let len = rsgx_pem_decode("CERTIFICATE", crate::IAS_REPORT_CA, &mut root).unwrap();
+        root.truncate(len);
+        assert!(CertInfo::parse(&root).is_some());
+        assert_eq!(verify_report_signature(&evidence(), &root, ISSUED_AT), Err(RaTlsError::BadSigningCert));
+    }
+
+    #[test]
+    fn parse_report() {
+        let report = IasReport::parse(&evidence().report).unwrap();
+        assert_eq!(report.id(), "131402127283658917512346497442431582233");
+        assert_eq!(report.timestamp(), ISSUED_AT);
+        assert_eq!(report.version(), 4);
+        assert_eq!(report.quote_status(), QuoteStatus::GroupOutOfDate);
+        assert_eq!(report.advisory_url(), Some("https://security-center.intel.com"));
+        assert_eq!(report.advisory_ids(), &["INTEL-SA-00219".to_string(), "INTEL-SA-00334".to_string()]);
+        assert_eq!(report.nonce(), Some("0123456789abcdef"));
+        assert_eq!(report.epid_pseudonym(), Some(&[0_u8; 64][..]));
+        assert_eq!(report.quote().version(), 2);
+        assert_eq!(report.quote().report_body().isv_svn(), 3);
+
+        assert_eq!(report.check_freshness(ISSUED_AT + 60, 60), Ok(()));
+        assert_eq!(report.check_freshness(ISSUED_AT - 60, 60), Ok(()));
+        assert_eq!(report.check_freshness(ISSUED_AT + 61, 60), Err(RaTlsError::StaleReport));
+        assert_eq!(report.check_nonce("0123456789abcdef"), Ok(()));
+        assert_eq!(report.check_nonce("0123456789abcdee"), Err(RaTlsError::NonceMismatch));
+    }
+
+    #[test]
+    fn parse_report_errors() {
+        let report = String::from_utf8(evidence().report).unwrap();
+        let cases = [
+            ("\"version\":4,", ""),
+            ("\"isvEnclaveQuoteStatus\":\"GROUP_OUT_OF_DATE\"", "\"isvEnclaveQuoteStatus\":\"GROUP_OUT_OF_SYNC\""),
+            ("\"timestamp\":\"2020-06-01T12:30:00.000000\"", "\"timestamp\":\"2020-06-01 12:30:00\""),
+            ("\"platformInfoBlob\":\"1502", "\"platformInfoBlob\":\"1503"),
+            ("\"advisoryIDs\":[\"INTEL-SA-00219\"", "\"advisoryIDs\":[219"),
+            ("\"isvEnclaveQuoteBody\":\"AgAB", "\"isvEnclaveQuoteBody\":\"AB"),
+        ];
+        for (from, to) in cases.iter() {
+            assert!(report.contains(from), "{}", from);
+            let broken = report.replacen(from, to, 1);
+            assert_eq!(IasReport::parse(broken.as_bytes()).err(), Some(RaTlsError::BadReport), "{}", to);
+        }
+    }
+
+    #[test]
+    fn platform_info_blob() {
+        let report = IasReport::parse(&evidence().report).unwrap();
+        let pib = report.platform_info_blob().unwrap();
+        assert_eq!(pib.epid_group_flags(), QE_EPID_GROUP_OUT_OF_DATE);
+        assert_eq!(pib.tcb_evaluation_flags(), QUOTE_CPUSVN_OUT_OF_DATE | QUOTE_ISVSVN_QE_OUT_OF_DATE);
+        assert_eq!(pib.pse_evaluation_flags(), 0);
+        assert!(pib.tcb_out_of_date());
+        assert!(!pib.configuration_needed());
+        assert_eq!(pib.latest_equivalent_tcb_psvn()[..4], [0x0f, 0x0f, 0x02, 0x02]);
+        assert_eq!(pib.latest_pse_isvsvn(), &[0x00, 0x0b]);
+        assert_eq!(pib.latest_psda_svn(), &[0x00, 0x00, 0x00, 0x02]);
+        assert_eq!(pib.xeid(), 0);
+        assert_eq!(pib.gid(), 0x0b0c);
+        assert_eq!(pib.signature()[0], 0x40);
+        assert_eq!(pib.platform_info().platform_info[..], pib.as_bytes()[..]);
+
+        assert!(PlatformInfoBlob::parse("150200650400").is_none());
+        assert!(PlatformInfoBlob::parse("zz").is_none());
+    }
+
+    #[test]
+    fn build_request() {
+        let req = report_request("api.trustedservices.intel.com", "/sgx/dev/attestation/v3/report",
+                                 "key", &[1, 2, 3], Some("abc")).unwrap();
+        let body = "{\"isvEnclaveQuote\":\"AQID\",\"nonce\":\"abc\"}";
+        assert!(req.starts_with("POST /sgx/dev/attestation/v3/report HTTP/1.1\r\nHost: api.trustedservices.intel.com\r\n"));
+        assert!(req.contains(&format!("Content-Length: {}\r\n", body.len())));
+        assert!(req.ends_with(&format!("\r\n\r\n{}", body)));
+
+        let nonce = "0123456789abcdefABCDEF0123456789";
+        assert!(report_request("host", "/", "key", &[1], Some(nonce)).is_ok());
+        for nonce in &["0123456789abcdefABCDEF0123456789a", "abc\",\"x", "a b"] {
+            assert_eq!(report_request("host", "/", "key", &[1], Some(nonce)), Err(RaTlsError::BadNonce));
+        }
+    }
+
+    #[test]
+    fn decode_helpers() {
+        assert_eq!(percent_decode("a%20b%0A"), Some(b"a b\n".to_vec()));
+        assert_eq!(percent_decode("a%2"), None);
+        assert_eq!(percent_decode("a%zz"), None);
+        assert_eq!(decode_hex("0aFf"), Some(vec![0x0a, 0xff]));
+        assert_eq!(decode_hex("0aF"), None);
+        assert_eq!(time::parse_x509_time(TAG_UTC_TIME, b"200601123000Z"), Some(ISSUED_AT));
+        assert_eq!(time::parse_x509_time(TAG_GENERALIZED_TIME, b"20200601123000Z"), Some(ISSUED_AT));
+        assert_eq!(time::parse_x509_time(TAG_UTC_TIME, b"200601123000+"), None);
+    }
+}
@@ -0,0 +1,202 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+use std::prelude::v1::*;
+use sgx_types::*;
+use sgx_types::quote::ReportBodyView;
+use crate::error::RaTlsError;
+
+/// `isvEnclaveQuoteStatus` of an IAS attestation verification report.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum QuoteStatus {
+    Ok,
+    SignatureInvalid,
+    GroupRevoked,
+    SignatureRevoked,
+    KeyRevoked,
+    SigrlVersionMismatch,
+    GroupOutOfDate,
+    ConfigurationNeeded,
+    SwHardeningNeeded,
+    ConfigurationAndSwHardeningNeeded,
+}
+
+impl QuoteStatus {
+    pub fn parse(s: &str) -> Option<QuoteStatus> {
+        match s {
+            "OK" => Some(QuoteStatus::Ok),
+            "SIGNATURE_INVALID" => Some(QuoteStatus::SignatureInvalid),
+            "GROUP_REVOKED" => Some(QuoteStatus::GroupRevoked),
+            "SIGNATURE_REVOKED" => Some(QuoteStatus::SignatureRevoked),
+            "KEY_REVOKED" => Some(QuoteStatus::KeyRevoked),
+            "SIGRL_VERSION_MISMATCH" => Some(QuoteStatus::SigrlVersionMismatch),
+            "GROUP_OUT_OF_DATE" => Some(QuoteStatus::GroupOutOfDate),
+            "CONFIGURATION_NEEDED" => Some(QuoteStatus::ConfigurationNeeded),
+            "SW_HARDENING_NEEDED" => Some(QuoteStatus::SwHardeningNeeded),
+            "CONFIGURATION_AND_SW_HARDENING_NEEDED" => Some(QuoteStatus::ConfigurationAndSwHardeningNeeded),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            QuoteStatus::Ok => "OK",
+            QuoteStatus::SignatureInvalid => "SIGNATURE_INVALID",
+            QuoteStatus::GroupRevoked => "GROUP_REVOKED",
+            QuoteStatus::SignatureRevoked => "SIGNATURE_REVOKED",
+            QuoteStatus::KeyRevoked => "KEY_REVOKED",
+            QuoteStatus::SigrlVersionMismatch => "SIGRL_VERSION_MISMATCH",
+            QuoteStatus::GroupOutOfDate => "GROUP_OUT_OF_DATE",
+            QuoteStatus::ConfigurationNeeded => "CONFIGURATION_NEEDED",
+            QuoteStatus::SwHardeningNeeded => "SW_HARDENING_NEEDED",
+            QuoteStatus::ConfigurationAndSwHardeningNeeded => "CONFIGURATION_AND_SW_HARDENING_NEEDED",
+        }
+    }
+
+    /// Returns true for statuses where the quote is genuine but the platform
+    /// is missing updates or configuration. The report then carries a
+    /// platform info blob.
+    pub fn needs_update(&self) -> bool {
+        match *self {
+            QuoteStatus::GroupOutOfDate |
+            QuoteStatus::ConfigurationNeeded |
+            QuoteStatus::SwHardeningNeeded |
+            QuoteStatus::ConfigurationAndSwHardeningNeeded => true,
+            _ => false,
+        }
+    }
+}
+
+/// What a verifier requires of the attested enclave.
+///
+/// The defaults accept any enclave identity with quote status `OK`, in
+/// production mode, with no age limit on the report. An empty MRENCLAVE or
+/// MRSIGNER list accepts any value; a real deployment should pin at least
+/// one of them.
+#[derive(Clone, Debug)]
+pub struct AttestationPolicy {
+    mr_enclaves: Vec<[u8; SGX_HASH_SIZE]>,
+    mr_signers: Vec<[u8; SGX_HASH_SIZE]>,
+    isv_prod_id: Option<sgx_prod_id_t>,
+    min_isv_svn: sgx_isv_svn_t,
+    accepted_statuses: Vec<QuoteStatus>,
+    allow_debug: bool,
+    max_report_age: Option<u64>,
+}
+
+impl Default for AttestationPolicy {
+    fn default() -> AttestationPolicy {
+        AttestationPolicy::new()
+    }
+}
+
+impl AttestationPolicy {
+    pub fn new() -> AttestationPolicy {
+        AttestationPolicy {
+            mr_enclaves: Vec::new(),
+            mr_signers: Vec::new(),
+            isv_prod_id: None,
+            min_isv_svn: 0,
+            accepted_statuses: vec![QuoteStatus::Ok],
+            allow_debug: false,
+            max_report_age: None,
+        }
+    }
+
+    /// Adds an MRENCLAVE to the allowed list.
+    pub fn allow_mr_enclave(mut self, mr_enclave: &sgx_measurement_t) -> Self {
+        self.mr_enclaves.push(mr_enclave.m);
+        self
+    }
+
+    /// Adds an MRSIGNER to the allowed list.
+    pub fn allow_mr_signer(mut self, mr_signer: &sgx_measurement_t) -> Self {
+        self.mr_signers.push(mr_signer.m);
+        self
+    }
+
+    pub fn with_isv_prod_id(mut self, isv_prod_id: sgx_prod_id_t) -> Self {
+        self.isv_prod_id = Some(isv_prod_id);
+        self
+    }
+
+    pub fn with_min_isv_svn(mut self, min_isv_svn: sgx_isv_svn_t) -> Self {
+        self.min_isv_svn = min_isv_svn;
+        self
+    }
+
+    /// Accepts a quote status besides `OK`, e.g. `GroupOutOfDate` for
+    /// platforms that are behind on microcode or PSW updates.
+    pub fn accept_status(mut self, status: QuoteStatus) -> Self {
+        if !self.accepted_statuses.contains(&status) {
+            self.accepted_statuses.push(status);
+        }
+        self
+    }
+
+    /// Accepts enclaves with the DEBUG attribute set. Their memory can be
+    /// read by the host, so this is for development only.
+    pub fn allow_debug(mut self, allow: bool) -> Self {
+        self.allow_debug = allow;
+        self
+    }
+
+    /// Rejects reports whose IAS timestamp is more than `secs` seconds old.
+    pub fn with_max_report_age(mut self, secs: u64) -> Self {
+        self.max_report_age = Some(secs);
+        self
+    }
+
+    pub fn max_report_age(&self) -> Option<u64> {
+        self.max_report_age
+    }
+
+    /// Checks the quote status and the identity in a report body.
+    pub fn check(&self, status: QuoteStatus, body: &ReportBodyView) -> Result<(), RaTlsError> {
+        self.check_status(status)?;
+        self.check_identity(body)
+    }
+
+    pub fn check_status(&self, status: QuoteStatus) -> Result<(), RaTlsError> {
+        if self.accepted_statuses.contains(&status) {
+            Ok(())
+        } else {
+            Err(RaTlsError::QuoteStatus(status))
+        }
+    }
+
+    /// Checks the enclave identity and attributes in a report body.
+    pub fn check_identity(&self, body: &ReportBodyView) -> Result<(), RaTlsError> {
+        if !self.mr_enclaves.is_empty() && !self.mr_enclaves.contains(&body.mr_enclave().m) {
+            return Err(RaTlsError::MrEnclaveMismatch);
+        }
+        if !self.mr_signers.is_empty() && !self.mr_signers.contains(&body.mr_signer().m) {
+            return Err(RaTlsError::MrSignerMismatch);
+        }
+        if self.isv_prod_id.map_or(false, |id| id != body.isv_prod_id()) {
+            return Err(RaTlsError::IsvProdIdMismatch);
+        }
+        if body.isv_svn() < self.min_isv_svn {
+            return Err(RaTlsError::IsvSvnTooLow);
+        }
+        if !self.allow_debug && body.attributes().flags & SGX_FLAGS_DEBUG != 0 {
+            return Err(RaTlsError::DebugEnclave);
+        }
+        Ok(())
+    }
+}
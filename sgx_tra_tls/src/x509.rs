@@ -0,0 +1,305 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! The parts of X.509 certificates and CRLs needed to check the IAS and DCAP
+//! signing chains, and the signature checks on top of sgx_tcrypto.
+//!
+
+use std::prelude::v1::*;
+use sgx_types::*;
+use crypto::*;
+use crate::der::*;
+use crate::error::RaTlsError;
+use crate::time;
+
+pub(crate) const OID_SHA256_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b];
+pub(crate) const OID_ECDSA_WITH_SHA256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02];
+const OID_COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+const TAG_NULL: u8 = 0x05;
+const TAG_CONTEXT_1: u8 = 0x81;
+const TAG_CONTEXT_2: u8 = 0x82;
+
+pub(crate) struct CertInfo<'a> {
+    pub(crate) tbs: &'a [u8],
+    pub(crate) serial: &'a [u8],
+    pub(crate) issuer: &'a [u8],
+    pub(crate) subject: &'a [u8],
+    pub(crate) not_before: u64,
+    pub(crate) not_after: u64,
+    pub(crate) spki: &'a [u8],
+    extensions: Option<&'a [u8]>,
+    pub(crate) signature_algorithm: &'a [u8],
+    pub(crate) signature: &'a [u8],
+}
+
+impl<'a> CertInfo<'a> {
+    pub(crate) fn parse(der: &'a [u8]) -> Option<CertInfo<'a>> {
+        let (tbs, signature_algorithm, signature) = parse_signed(der)?;
+
+        let mut tbs_reader = DerReader::new(tbs).read_sequence()?;
+        tbs_reader.skip_optional(TAG_CONTEXT_0)?;
+        let serial = tbs_reader.read(TAG_INTEGER)?;
+        tbs_reader.read(TAG_SEQUENCE)?;
+        let (_, issuer) = tbs_reader.read_with_encoding(TAG_SEQUENCE)?;
+        let mut validity = tbs_reader.read_sequence()?;
+        let not_before = read_time(&mut validity)?;
+        let not_after = read_time(&mut validity)?;
+        validity.expect_empty()?;
+        let (_, subject) = tbs_reader.read_with_encoding(TAG_SEQUENCE)?;
+        let (_, spki) = tbs_reader.read_with_encoding(TAG_SEQUENCE)?;
+        tbs_reader.skip_optional(TAG_CONTEXT_1)?;
+        tbs_reader.skip_optional(TAG_CONTEXT_2)?;
+        let extensions = match tbs_reader.peek_tag() {
+            Some(TAG_CONTEXT_3) => Some(DerReader::new(tbs_reader.read(TAG_CONTEXT_3)?).read(TAG_SEQUENCE)?),
+            _ => None,
+        };
+        tbs_reader.expect_empty()?;
+
+        Some(CertInfo {
+            tbs,
+            serial,
+            issuer,
+            subject,
+            not_before,
+            not_after,
+            spki,
+            extensions,
+            signature_algorithm,
+            signature,
+        })
+    }
+
+    pub(crate) fn is_valid_at(&self, now: u64) -> bool {
+        self.not_before <= now && now <= self.not_after
+    }
+
+    ///
+    /// Returns the value of the subject's common name attribute.
+    ///
+    pub(crate) fn common_name(&self) -> Option<&'a [u8]> {
+        let mut rdns = DerReader::new(self.subject).read_sequence()?;
+        while !rdns.is_empty() {
+            let mut rdn = DerReader::new(rdns.read(TAG_SET)?);
+            while !rdn.is_empty() {
+                let mut attribute = rdn.read_sequence()?;
+                let oid = attribute.read(TAG_OID)?;
+                let (_, value, _) = attribute.read_any()?;
+                if oid == OID_COMMON_NAME {
+                    return Some(value);
+                }
+            }
+        }
+        None
+    }
+
+    ///
+    /// Returns the value of the extension with the given OID.
+    ///
+    pub(crate) fn extension(&self, oid: &[u8]) -> Option<&'a [u8]> {
+        let mut extensions = DerReader::new(self.extensions?);
+        while !extensions.is_empty() {
+            let mut ext = extensions.read_sequence()?;
+            let ext_oid = ext.read(TAG_OID)?;
+            ext.skip_optional(TAG_BOOLEAN)?;
+            let value = ext.read(TAG_OCTET_STRING)?;
+            if ext_oid == oid {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    ///
+    /// Checks that `issuer` issued this certificate with an ECDSA-SHA256
+    /// signature.
+    ///
+    pub(crate) fn is_ecdsa_signed_by(&self, issuer: &CertInfo) -> Result<bool, RaTlsError> {
+        if self.issuer != issuer.subject || self.signature_algorithm != OID_ECDSA_WITH_SHA256 {
+            return Ok(false);
+        }
+        ecdsa_verify_der(issuer.spki, self.tbs, self.signature)
+    }
+}
+
+pub(crate) struct CrlInfo<'a> {
+    tbs: &'a [u8],
+    pub(crate) issuer: &'a [u8],
+    pub(crate) this_update: u64,
+    pub(crate) next_update: Option<u64>,
+    revoked: Option<&'a [u8]>,
+    signature_algorithm: &'a [u8],
+    signature: &'a [u8],
+}
+
+impl<'a> CrlInfo<'a> {
+    pub(crate) fn parse(der: &'a [u8]) -> Option<CrlInfo<'a>> {
+        let (tbs, signature_algorithm, signature) = parse_signed(der)?;
+
+        let mut tbs_reader = DerReader::new(tbs).read_sequence()?;
+        tbs_reader.skip_optional(TAG_INTEGER)?;
+        tbs_reader.read(TAG_SEQUENCE)?;
+        let (_, issuer) = tbs_reader.read_with_encoding(TAG_SEQUENCE)?;
+        let this_update = read_time(&mut tbs_reader)?;
+        let next_update = match tbs_reader.peek_tag() {
+            Some(TAG_UTC_TIME) | Some(TAG_GENERALIZED_TIME) => Some(read_time(&mut tbs_reader)?),
+            _ => None,
+        };
+        let revoked = match tbs_reader.peek_tag() {
+            Some(TAG_SEQUENCE) => Some(tbs_reader.read(TAG_SEQUENCE)?),
+            _ => None,
+        };
+        tbs_reader.skip_optional(TAG_CONTEXT_0)?;
+        tbs_reader.expect_empty()?;
+
+        let crl = CrlInfo { tbs, issuer, this_update, next_update, revoked, signature_algorithm, signature };
+        crl.revoked_serials().map(|_| crl)
+    }
+
+    fn revoked_serials(&self) -> Option<Vec<&'a [u8]>> {
+        let mut serials = Vec::new();
+        let mut entries = DerReader::new(self.revoked.unwrap_or(&[]));
+        while !entries.is_empty() {
+            let mut entry = entries.read_sequence()?;
+            serials.push(entry.read(TAG_INTEGER)?);
+        }
+        Some(serials)
+    }
+
+    pub(crate) fn is_revoked(&self, cert: &CertInfo) -> bool {
+        self.revoked_serials().map_or(true, |serials| serials.contains(&cert.serial))
+    }
+
+    ///
+    /// Returns false once the CRL has passed its next update.
+    ///
+    pub(crate) fn is_current_at(&self, now: u64) -> bool {
+        self.this_update <= now && self.next_update.map_or(true, |next| now < next)
+    }
+
+    pub(crate) fn is_ecdsa_signed_by(&self, issuer: &CertInfo) -> Result<bool, RaTlsError> {
+        if self.issuer != issuer.subject || self.signature_algorithm != OID_ECDSA_WITH_SHA256 {
+            return Ok(false);
+        }
+        ecdsa_verify_der(issuer.spki, self.tbs, self.signature)
+    }
+}
+
+// Splits a certificate or CRL into its signed part, with tag and length,
+// its signature algorithm and signature.
+fn parse_signed(der: &[u8]) -> Option<(&[u8], &[u8], &[u8])> {
+    let mut outer = DerReader::new(der);
+    let mut signed = outer.read_sequence()?;
+    outer.expect_empty()?;
+
+    let (_, tbs) = signed.read_with_encoding(TAG_SEQUENCE)?;
+    let mut alg = signed.read_sequence()?;
+    let signature_algorithm = alg.read(TAG_OID)?;
+    alg.skip_optional(TAG_NULL)?;
+    alg.expect_empty()?;
+    let signature = signed.read(TAG_BIT_STRING)?;
+    signed.expect_empty()?;
+    if signature.first() != Some(&0) {
+        return None;
+    }
+    Some((tbs, signature_algorithm, &signature[1..]))
+}
+
+fn read_time(reader: &mut DerReader) -> Option<u64> {
+    let (tag, content, _) = reader.read_any()?;
+    time::parse_x509_time(tag, content)
+}
+
+///
+/// Decodes every `CERTIFICATE` block of a PEM chain, in order.
+///
+pub(crate) fn pem_certificates(pem: &[u8]) -> Option<Vec<Vec<u8>>> {
+    const END: &[u8] = b"-----END CERTIFICATE-----";
+    let mut certs = Vec::new();
+    let mut rest = pem;
+    while let Some(end) = rest.windows(END.len()).position(|w| w == END) {
+        let (block, tail) = rest.split_at(end + END.len());
+        let mut der = vec![0_u8; block.len()];
+        let len = rsgx_pem_decode("CERTIFICATE", block, &mut der).ok()?;
+        der.truncate(len);
+        certs.push(der);
+        rest = tail;
+    }
+    if certs.is_empty() { None } else { Some(certs) }
+}
+
+pub(crate) fn rsa_verify(spki: &[u8], data: &[u8], signature: &[u8]) -> Result<bool, RaTlsError> {
+    let mut n = [0_u8; SGX_RSA_MAX_MOD_SIZE];
+    let mut e = [0_u8; SGX_RSA3072_PUB_EXP_SIZE];
+    let (n_len, _) = rsgx_rsa_public_from_spki_der(spki, &mut n, &mut e).map_err(|_| RaTlsError::BadSigningCert)?;
+    let key = SgxRsaPubKey::new();
+    key.create(n_len as i32, e.len() as i32, &n[..n_len], &e).map_err(RaTlsError::Crypto)?;
+    key.verify_pkcs1_v15(sgx_rsa_hash_t::SGX_RSA_HASH_SHA256, data, signature).map_err(RaTlsError::Crypto)
+}
+
+///
+/// Verifies a DER ECDSA-Sig-Value over `data` with a P-256 SPKI.
+///
+pub(crate) fn ecdsa_verify_der(spki: &[u8], data: &[u8], signature: &[u8]) -> Result<bool, RaTlsError> {
+    match (spki_public(spki), rsgx_ec256_signature_from_der(signature)) {
+        (Some(public), Ok(signature)) => ecdsa_verify(&public, data, &signature),
+        _ => Ok(false),
+    }
+}
+
+pub(crate) fn spki_public(spki: &[u8]) -> Option<sgx_ec256_public_t> {
+    rsgx_ec256_public_from_spki_der(spki).ok()
+}
+
+///
+/// Decodes a raw big-endian `x || y` P-256 public key, the encoding used by
+/// DCAP quotes.
+///
+pub(crate) fn raw_public(xy: &[u8]) -> Option<sgx_ec256_public_t> {
+    if xy.len() != 2 * SGX_ECP256_KEY_SIZE {
+        return None;
+    }
+    let mut point = [0x04_u8; 1 + 2 * SGX_ECP256_KEY_SIZE];
+    point[1..].copy_from_slice(xy);
+    rsgx_ec256_public_from_sec1(&point).ok()
+}
+
+///
+/// Decodes a raw big-endian `r || s` ECDSA signature, the encoding used by
+/// DCAP quotes and collateral.
+///
+pub(crate) fn raw_signature(rs: &[u8]) -> Option<sgx_ec256_signature_t> {
+    if rs.len() != 2 * SGX_ECP256_KEY_SIZE {
+        return None;
+    }
+    let mut signature = sgx_ec256_signature_t::default();
+    for (words, half) in [&mut signature.x, &mut signature.y].iter_mut().zip(rs.chunks(SGX_ECP256_KEY_SIZE)) {
+        for (word, chunk) in words.iter_mut().zip(half.rchunks(4)) {
+            *word = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+    }
+    Some(signature)
+}
+
+pub(crate) fn ecdsa_verify(public: &sgx_ec256_public_t, data: &[u8], signature: &sgx_ec256_signature_t) -> Result<bool, RaTlsError> {
+    let handle = SgxEccHandle::new();
+    handle.open().map_err(RaTlsError::Crypto)?;
+    let valid = handle.ecdsa_verify_slice(data, public, signature).map_err(RaTlsError::Crypto);
+    let _ = handle.close();
+    valid
+}
@@ -21,6 +21,7 @@
 use sgx_types::*;
 use sgx_types::marker::ContiguousMemory;
 use crate::internal::*;
+use crate::policy::SgxSealPolicy;
 use core::mem;
 use core::marker::PhantomData;
 use alloc::slice;
@@ -460,4 +461,93 @@ impl<'a, T: 'a + ?Sized> SgxMacAadata<'a, T> {
     pub fn get_add_mac_txt_len(&self) -> u32 {
         self.inner.get_add_mac_txt_len()
     }
+
+    ///
+    /// Compare the CPUSVN, ISVSVN and CONFIGSVN recorded in the key request of the MAC data
+    /// with those of the running enclave.
+    ///
+    /// # Return value
+    ///
+    /// **SgxSealStatus::Current**
+    ///
+    /// The data was MACed by this version of the enclave on this platform.
+    ///
+    /// **SgxSealStatus::Outdated**
+    ///
+    /// The data was MACed under an older security version. Call `migrate_data` to
+    /// move it to the current one.
+    ///
+    /// **SgxSealStatus::Newer**
+    ///
+    /// The data was MACed by a newer enclave and cannot be processed by this one.
+    ///
+    pub fn get_seal_status(&self) -> SgxSealStatus {
+        self.inner.get_seal_status()
+    }
+
+    ///
+    /// Migrate the MAC data to the security version of the running enclave.
+    ///
+    /// # Description
+    ///
+    /// The data is verified with the seal key derived from the `sgx_key_request_t` stored
+    /// in the blob, i.e. for the CPUSVN and ISVSVN it was MACed under, and then MACed again
+    /// with a fresh key ID under the current CPUSVN, ISVSVN and CONFIGSVN. The key policy,
+    /// attribute mask and misc mask of the blob are kept. After an enclave upgrade, migrated
+    /// blobs no longer depend on the old security version, so that version can be revoked.
+    ///
+    /// Migration requires the key policy to still match: data bound to MRENCLAVE can only
+    /// be migrated by the enclave build that MACed it.
+    ///
+    /// # Errors
+    ///
+    /// The errors of unsealing and sealing. In particular:
+    ///
+    /// **SGX_ERROR_INVALID_CPUSVN**
+    ///
+    /// The CPUSVN in the sealed data blob is beyond the CPUSVN value of the platform.
+    ///
+    /// **SGX_ERROR_INVALID_ISVSVN**
+    ///
+    /// The ISVSVN in the sealed data blob is greater than the ISVSVN value of the enclave.
+    ///
+    /// **SGX_ERROR_MAC_MISMATCH**
+    ///
+    /// The tag verification failed.
+    ///
+    pub fn migrate_data(&self) -> SgxResult<Self> {
+        self.inner.migrate_data().map(|x| {
+            Self {
+                inner: x,
+                marker: PhantomData,
+            }
+        })
+    }
+
+    ///
+    /// Verify the MAC data and seal it again under a new policy and a fresh key ID.
+    ///
+    /// # Description
+    ///
+    /// This rotates the seal key of the blob and moves it to the current security version,
+    /// like `migrate_data`, but with the key policy, attribute mask and misc mask given
+    /// in `policy`. For example, it moves data from `SgxSealPolicy::mrenclave()` to
+    /// `SgxSealPolicy::mrsigner()` before an enclave upgrade.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The policy is not accepted by `mac_aadata_ex`.
+    ///
+    /// The errors of unsealing are returned as for `migrate_data`.
+    ///
+    pub fn reseal_data(&self, policy: &SgxSealPolicy) -> SgxResult<Self> {
+        self.inner.reseal_data(policy.key_policy, policy.attribute_mask, policy.misc_mask).map(|x| {
+            Self {
+                inner: x,
+                marker: PhantomData,
+            }
+        })
+    }
 }
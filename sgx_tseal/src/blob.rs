@@ -0,0 +1,181 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! Owned Sealed Blob
+//!
+//! `SealedBlob` holds a sealed data blob in memory owned by the enclave and
+//! converts it to and from the byte layout of `sgx_sealed_data_t`:
+//!
+//! ```text
+//! offset  size  field
+//! 0       512   key_request
+//! 512     4     plain_text_offset, the length of the encrypted text
+//! 516     12    reserved
+//! 528     4     aes_data.payload_size
+//! 532     12    aes_data.reserved
+//! 544     16    aes_data.payload_tag
+//! 560     ...   encrypted text, then additional MAC text
+//! ```
+//!
+//! Unlike `from_raw_sealed_data_t`, `from_bytes` takes a plain slice, checks
+//! every length and offset against the slice before reading it, and copies the
+//! blob, so a buffer received from outside the enclave can be parsed safely.
+//!
+use sgx_types::*;
+use crate::internal::*;
+use crate::policy::SgxSealPolicy;
+use crate::seal::{SgxSealedData, SgxUnsealedData};
+use alloc::vec::Vec;
+
+/// A sealed data blob owned by the enclave.
+#[derive(Clone, Default)]
+pub struct SealedBlob {
+    inner: SgxInternalSealedData,
+}
+
+impl SealedBlob {
+
+    ///
+    /// Seal a byte payload with the default MRSIGNER policy. See `SgxSealedData::seal_data`.
+    ///
+    pub fn seal_data(additional_text: &[u8], encrypt_text: &[u8]) -> SgxResult<Self> {
+        SgxInternalSealedData::seal_data(additional_text, encrypt_text).map(|x| SealedBlob { inner: x })
+    }
+
+    ///
+    /// Seal a byte payload under the given policy. See `SgxSealedData::seal_data_ex`.
+    ///
+    pub fn seal_data_ex(policy: &SgxSealPolicy, additional_text: &[u8], encrypt_text: &[u8]) -> SgxResult<Self> {
+        SgxInternalSealedData::seal_data_ex(policy.key_policy,
+                                            policy.attribute_mask,
+                                            policy.misc_mask,
+                                            additional_text,
+                                            encrypt_text).map(|x| SealedBlob { inner: x })
+    }
+
+    ///
+    /// Unseal the blob as bytes.
+    ///
+    /// # Errors
+    ///
+    /// The errors of `SgxSealedData::unseal_data`.
+    ///
+    pub fn unseal_data<'a>(&self) -> SgxResult<SgxUnsealedData<'a, [u8]>> {
+        self.inner.unseal_data().map(SgxUnsealedData::from_internal)
+    }
+
+    ///
+    /// Parse a blob from the bytes of an `sgx_sealed_data_t`.
+    ///
+    /// # Parameters
+    ///
+    /// **bytes**
+    ///
+    /// The sealed data, for example as received from the untrusted application.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The slice is shorter than `sgx_sealed_data_t`, `plain_text_offset` is greater than
+    /// `payload_size`, or the length of the slice is not exactly the size computed by
+    /// `calc_raw_sealed_data_size` from the encrypt and additional text lengths.
+    ///
+    pub fn from_bytes(bytes: &[u8]) -> SgxResult<Self> {
+        SgxInternalSealedData::from_bytes(bytes).map(|x| SealedBlob { inner: x })
+    }
+
+    ///
+    /// Write the blob in the layout of `sgx_sealed_data_t`.
+    ///
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.inner.to_bytes()
+    }
+
+    ///
+    /// Convert the blob to a `SgxSealedData` of type `T`. Unsealing it fails with
+    /// **SGX_ERROR_MAC_MISMATCH** if the encrypt text does not have the size of a `T`.
+    ///
+    pub fn into_sealed_data<'a, T: 'a + ?Sized>(self) -> SgxSealedData<'a, T> {
+        SgxSealedData::from_internal(self.inner)
+    }
+
+    ///
+    /// Get the size of payload in SealedBlob.
+    ///
+    pub fn get_payload_size(&self) -> u32 {
+        self.inner.get_payload_size()
+    }
+
+    ///
+    /// Get a slice of payload tag in SealedBlob.
+    ///
+    pub fn get_payload_tag(&self) -> &[u8; SGX_SEAL_TAG_SIZE] {
+        self.inner.get_payload_tag()
+    }
+
+    ///
+    /// Get the sgx_key_request_t in SealedBlob.
+    ///
+    pub fn get_key_request(&self) -> &sgx_key_request_t {
+        self.inner.get_key_request()
+    }
+
+    ///
+    /// Get a slice of encrypt text in SealedBlob.
+    ///
+    pub fn get_encrypt_txt(&self) -> &[u8] {
+        self.inner.get_encrypt_txt()
+    }
+
+    ///
+    /// Get a slice of additional text in SealedBlob.
+    ///
+    pub fn get_additional_txt(&self) -> &[u8] {
+        self.inner.get_additional_txt()
+    }
+
+    ///
+    /// Get the size of the blob as written by `to_bytes`.
+    ///
+    pub fn get_raw_sealed_data_size(&self) -> u32 {
+        SgxInternalSealedData::calc_raw_sealed_data_size(self.inner.get_add_mac_txt_len(),
+                                                         self.inner.get_encrypt_txt_len())
+    }
+
+    ///
+    /// Compare the security versions of the blob with those of the running enclave.
+    ///
+    pub fn get_seal_status(&self) -> SgxSealStatus {
+        self.inner.get_seal_status()
+    }
+
+    ///
+    /// Seal the payload again under the current enclave identity. See `SgxSealedData::migrate_data`.
+    ///
+    pub fn migrate_data(&self) -> SgxResult<Self> {
+        self.inner.migrate_data().map(|x| SealedBlob { inner: x })
+    }
+}
+
+impl<'a, T: 'a + ?Sized> From<SgxSealedData<'a, T>> for SealedBlob {
+    fn from(sealed_data: SgxSealedData<'a, T>) -> SealedBlob {
+        SealedBlob { inner: sealed_data.into_internal() }
+    }
+}
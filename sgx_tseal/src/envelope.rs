@@ -0,0 +1,360 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! Versioned Sealed Envelope
+//!
+//! `SgxSealedEnvelope` seals a byte payload together with a small header that
+//! records the envelope format version and an application-defined schema
+//! version. The header is the first part of the additional MAC text, so it is
+//! authenticated but readable without unsealing:
+//!
+//! ```text
+//! offset  size  field
+//! 0       8     magic "SGXSLENV"
+//! 8       2     envelope format version, little-endian (currently 1)
+//! 10      2     reserved, zero
+//! 12      4     schema version, little-endian
+//! 16      ...   additional text supplied by the caller
+//! ```
+//!
+//! The key policy and security versions are taken from the `sgx_key_request_t`
+//! of the blob. Together with the schema version they let an enclave decide at
+//! load time whether a blob must be migrated to the current enclave identity or
+//! upgraded to a newer payload layout, and do both in one step with `upgrade`.
+//!
+use sgx_types::*;
+use crate::internal::*;
+use crate::policy::SgxSealPolicy;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+const ENVELOPE_MAGIC: [u8; 8] = *b"SGXSLENV";
+const ENVELOPE_HEADER_SIZE: usize = 16;
+
+/// The envelope format version written by this library.
+pub const SGX_SEAL_ENVELOPE_VERSION: u16 = 1;
+
+fn envelope_header(schema_version: u32) -> [u8; ENVELOPE_HEADER_SIZE] {
+    let mut header = [0_u8; ENVELOPE_HEADER_SIZE];
+    header[..8].copy_from_slice(&ENVELOPE_MAGIC);
+    header[8..10].copy_from_slice(&SGX_SEAL_ENVELOPE_VERSION.to_le_bytes());
+    header[12..16].copy_from_slice(&schema_version.to_le_bytes());
+    header
+}
+
+fn parse_envelope_header(additional: &[u8]) -> Option<u32> {
+    if additional.len() < ENVELOPE_HEADER_SIZE || additional[..8] != ENVELOPE_MAGIC {
+        return None;
+    }
+    let version = u16::from_le_bytes([additional[8], additional[9]]);
+    if version != SGX_SEAL_ENVELOPE_VERSION || additional[10] != 0 || additional[11] != 0 {
+        return None;
+    }
+    Some(u32::from_le_bytes([additional[12], additional[13], additional[14], additional[15]]))
+}
+
+/// The decrypted content of an `SgxSealedEnvelope`. The payload is cleared on drop.
+pub struct SgxUnsealedEnvelope {
+    schema_version: u32,
+    decrypt: Box<[u8]>,
+    additional: Box<[u8]>,
+}
+
+impl SgxUnsealedEnvelope {
+    ///
+    /// Get the schema version the payload was sealed with.
+    ///
+    pub fn get_schema_version(&self) -> u32 {
+        self.schema_version
+    }
+    ///
+    /// Get the decrypted payload.
+    ///
+    pub fn get_decrypt_txt(&self) -> &[u8] {
+        &*self.decrypt
+    }
+    ///
+    /// Get the additional text supplied by the caller, without the envelope header.
+    ///
+    pub fn get_additional_txt(&self) -> &[u8] {
+        &*self.additional
+    }
+}
+
+impl Drop for SgxUnsealedEnvelope {
+    fn drop(&mut self) {
+        clear_bytes(&mut self.decrypt);
+    }
+}
+
+/// A sealed payload with an authenticated format and schema version.
+#[derive(Clone, Default)]
+pub struct SgxSealedEnvelope {
+    inner: SgxInternalSealedData,
+}
+
+impl SgxSealedEnvelope {
+
+    ///
+    /// Seal a payload into a new envelope.
+    ///
+    /// # Parameters
+    ///
+    /// **policy**
+    ///
+    /// The key policy and masks used to derive the seal key.
+    ///
+    /// **schema_version**
+    ///
+    /// The version of the payload layout, chosen by the application.
+    ///
+    /// **additional_text**
+    ///
+    /// Optional additional MAC data, stored in clear after the envelope header.
+    ///
+    /// **encrypt_text**
+    ///
+    /// The payload to be encrypted. Must be non-empty and within the enclave.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The parameters or the policy are not accepted by `seal_data_ex`.
+    ///
+    /// **SGX_ERROR_OUT_OF_MEMORY**
+    ///
+    /// The enclave is out of memory.
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// Indicates a crypto library failure or the RDRAND instruction fails to generate a
+    /// random number.
+    ///
+    pub fn seal(policy: &SgxSealPolicy,
+                schema_version: u32,
+                additional_text: &[u8],
+                encrypt_text: &[u8]) -> SgxResult<Self> {
+
+        let mut additional: Vec<u8> = Vec::with_capacity(ENVELOPE_HEADER_SIZE + additional_text.len());
+        additional.extend_from_slice(&envelope_header(schema_version));
+        additional.extend_from_slice(additional_text);
+
+        SgxInternalSealedData::seal_data_ex(policy.key_policy,
+                                            policy.attribute_mask,
+                                            policy.misc_mask,
+                                            &additional,
+                                            encrypt_text).map(|x| SgxSealedEnvelope { inner: x })
+    }
+
+    ///
+    /// Unseal the envelope.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_CPUSVN**
+    ///
+    /// The CPUSVN in the sealed data blob is beyond the CPUSVN value of the platform.
+    ///
+    /// **SGX_ERROR_INVALID_ISVSVN**
+    ///
+    /// The ISVSVN in the sealed data blob is greater than the ISVSVN value of the enclave.
+    ///
+    /// **SGX_ERROR_MAC_MISMATCH**
+    ///
+    /// The tag verification failed or the envelope header is malformed.
+    ///
+    pub fn unseal(&self) -> SgxResult<SgxUnsealedEnvelope> {
+
+        let schema_version = self.checked_schema_version()?;
+        let unsealed_data = self.inner.unseal_data()?;
+        Ok(SgxUnsealedEnvelope {
+            schema_version,
+            decrypt: unsealed_data.decrypt,
+            additional: unsealed_data.additional[ENVELOPE_HEADER_SIZE..].to_vec().into_boxed_slice(),
+        })
+    }
+
+    ///
+    /// Seal the payload again under the current enclave identity and a fresh key ID,
+    /// keeping the policy and schema version. See `SgxSealedData::migrate_data`.
+    ///
+    pub fn migrate(&self) -> SgxResult<Self> {
+        self.checked_schema_version()?;
+        self.inner.migrate_data().map(|x| SgxSealedEnvelope { inner: x })
+    }
+
+    ///
+    /// Seal the payload again under `policy` and a fresh key ID, keeping the schema version.
+    /// See `SgxSealedData::reseal_data`.
+    ///
+    pub fn reseal(&self, policy: &SgxSealPolicy) -> SgxResult<Self> {
+        self.checked_schema_version()?;
+        self.inner.reseal_data(policy.key_policy, policy.attribute_mask, policy.misc_mask)
+            .map(|x| SgxSealedEnvelope { inner: x })
+    }
+
+    ///
+    /// Bring the envelope up to date with the running enclave and the given schema version.
+    ///
+    /// # Description
+    ///
+    /// If the envelope is current and already at `schema_version`, a copy of it is returned.
+    /// Otherwise the payload is unsealed; if its schema version is older than
+    /// `schema_version`, `convert` is called with the old schema version and the old payload
+    /// and returns the payload in the new layout. The result is sealed under the current
+    /// enclave identity with the policy of the envelope.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_VERSION**
+    ///
+    /// The envelope has a newer schema version than `schema_version`.
+    ///
+    /// The errors of `unseal`, `seal` and `convert` are passed through.
+    ///
+    pub fn upgrade<F>(&self, schema_version: u32, convert: F) -> SgxResult<Self>
+        where F: FnOnce(u32, &[u8]) -> SgxResult<Vec<u8>> {
+
+        let current_schema = self.checked_schema_version()?;
+        if current_schema > schema_version {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_VERSION);
+        }
+        if !self.needs_upgrade(schema_version) {
+            return Ok(self.clone());
+        }
+
+        if current_schema == schema_version {
+            return self.migrate();
+        }
+
+        let unsealed = self.unseal()?;
+        let policy = self.get_policy();
+        let mut converted = convert(current_schema, unsealed.get_decrypt_txt())?;
+        let result = Self::seal(&policy, schema_version, unsealed.get_additional_txt(), &converted);
+        clear_bytes(&mut converted);
+        result
+    }
+
+    ///
+    /// Returns true if the envelope was sealed under another security version or with a
+    /// schema version older than `schema_version`.
+    ///
+    pub fn needs_upgrade(&self, schema_version: u32) -> bool {
+        match self.get_schema_version() {
+            Some(version) => version < schema_version || self.get_seal_status() != SgxSealStatus::Current,
+            None => false,
+        }
+    }
+
+    ///
+    /// Get the envelope format version, or None if the blob is not an envelope.
+    ///
+    pub fn get_format_version(&self) -> Option<u16> {
+        parse_envelope_header(self.inner.get_additional_txt()).map(|_| SGX_SEAL_ENVELOPE_VERSION)
+    }
+
+    ///
+    /// Get the schema version, or None if the blob is not an envelope. The value is only
+    /// authenticated once the envelope has been unsealed.
+    ///
+    pub fn get_schema_version(&self) -> Option<u32> {
+        parse_envelope_header(self.inner.get_additional_txt())
+    }
+
+    ///
+    /// Get the key policy and masks the envelope was sealed with.
+    ///
+    pub fn get_policy(&self) -> SgxSealPolicy {
+        SgxSealPolicy::from_key_request(self.inner.get_key_request())
+    }
+
+    ///
+    /// See `SgxSealedData::get_seal_status`.
+    ///
+    pub fn get_seal_status(&self) -> SgxSealStatus {
+        self.inner.get_seal_status()
+    }
+
+    ///
+    /// Get the pointer of sgx_key_request_t in SgxSealedEnvelope.
+    ///
+    pub fn get_key_request(&self) -> &sgx_key_request_t {
+        self.inner.get_key_request()
+    }
+
+    ///
+    /// Get the additional text supplied by the caller, without the envelope header.
+    ///
+    pub fn get_additional_txt(&self) -> &[u8] {
+        let additional = self.inner.get_additional_txt();
+        if additional.len() < ENVELOPE_HEADER_SIZE {
+            &[]
+        } else {
+            &additional[ENVELOPE_HEADER_SIZE..]
+        }
+    }
+
+    ///
+    /// Calculate the size of the sgx_sealed_data_t buffer for an envelope.
+    ///
+    pub fn calc_raw_sealed_data_size(add_mac_txt_size: u32, encrypt_txt_size: u32) -> u32 {
+        match add_mac_txt_size.checked_add(ENVELOPE_HEADER_SIZE as u32) {
+            Some(size) => SgxInternalSealedData::calc_raw_sealed_data_size(size, encrypt_txt_size),
+            None => u32::max_value(),
+        }
+    }
+
+    ///
+    /// Get the size of the sgx_sealed_data_t buffer needed by `to_raw_sealed_data_t`.
+    ///
+    pub fn get_raw_sealed_data_size(&self) -> u32 {
+        SgxInternalSealedData::calc_raw_sealed_data_size(self.inner.get_add_mac_txt_len(),
+                                                         self.inner.get_encrypt_txt_len())
+    }
+
+    ///
+    /// Convert a pointer of sgx_sealed_data_t buffer to SgxSealedEnvelope.
+    ///
+    /// # Return value
+    ///
+    /// **None**
+    ///
+    /// The buffer is malformed or does not contain an envelope of a supported format version.
+    ///
+    pub unsafe fn from_raw_sealed_data_t(p: * mut sgx_sealed_data_t, len: u32) -> Option<Self> {
+        let inner = SgxInternalSealedData::from_raw_sealed_data_t(p, len)?;
+        if inner.get_encrypt_txt_len() == 0 {
+            return None;
+        }
+        parse_envelope_header(inner.get_additional_txt())?;
+        Some(SgxSealedEnvelope { inner })
+    }
+
+    ///
+    /// Convert SgxSealedEnvelope to the pointer of sgx_sealed_data_t.
+    ///
+    pub unsafe fn to_raw_sealed_data_t(&self, p: * mut sgx_sealed_data_t, len: u32) -> Option<* mut sgx_sealed_data_t> {
+        self.inner.to_raw_sealed_data_t(p, len)
+    }
+
+    fn checked_schema_version(&self) -> SgxResult<u32> {
+        self.get_schema_version().ok_or(sgx_status_t::SGX_ERROR_MAC_MISMATCH)
+    }
+}
@@ -21,11 +21,33 @@ use sgx_tcrypto::*;
 use sgx_tse::*;
 use core::mem;
 use core::ptr;
+use core::slice;
 use alloc::boxed::Box;
 use alloc::vec::Vec;
 
 /* intel sgx sdk 2.4 */
-const KEY_POLICY_KSS: uint16_t = (SGX_KEYPOLICY_CONFIGID | SGX_KEYPOLICY_ISVFAMILYID | SGX_KEYPOLICY_ISVEXTPRODID);
+pub(crate) const KEY_POLICY_KSS: uint16_t = (SGX_KEYPOLICY_CONFIGID | SGX_KEYPOLICY_ISVFAMILYID | SGX_KEYPOLICY_ISVEXTPRODID);
+
+///
+/// The state of a sealed blob relative to the security versions of the running enclave.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SgxSealStatus {
+    /// The blob was sealed under the current CPUSVN, ISVSVN and CONFIGSVN.
+    Current,
+    /// The blob was sealed under another CPUSVN or an older ISVSVN or CONFIGSVN. It can still
+    /// be unsealed unless the CPUSVN is newer than the platform's, and should be migrated.
+    Outdated,
+    /// The blob was sealed by an enclave with a higher ISVSVN or CONFIGSVN and cannot be
+    /// unsealed by this one.
+    Newer,
+}
+
+pub(crate) fn clear_bytes(buf: &mut [u8]) {
+    for b in buf.iter_mut() {
+        unsafe { ptr::write_volatile(b, 0) };
+    }
+}
 
 #[derive(Clone, Default)]
 pub struct SgxInternalUnsealedData {
@@ -246,6 +268,67 @@ impl SgxInternalSealedData {
         Some(sealed_data)
     }
 
+    pub fn to_bytes(&self) -> Vec<u8> {
+
+        let key_request_size = mem::size_of::<sgx_key_request_t>();
+        let additional_len = self.payload_data.additional.len();
+        let encrypt_len = self.payload_data.encrypt.len();
+
+        let mut bytes: Vec<u8> = Vec::with_capacity(mem::size_of::<sgx_sealed_data_t>() + encrypt_len + additional_len);
+        let key_request = unsafe {
+            slice::from_raw_parts(&self.key_request as * const sgx_key_request_t as * const u8, key_request_size)
+        };
+        bytes.extend_from_slice(key_request);
+        bytes.extend_from_slice(&(encrypt_len as u32).to_le_bytes());
+        bytes.extend_from_slice(&[0_u8; 12]);
+        bytes.extend_from_slice(&self.payload_data.payload_size.to_le_bytes());
+        bytes.extend_from_slice(&self.payload_data.reserved);
+        bytes.extend_from_slice(&self.payload_data.payload_tag);
+        bytes.extend_from_slice(&self.payload_data.encrypt);
+        bytes.extend_from_slice(&self.payload_data.additional);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> SgxResult<Self> {
+
+        let header_size = mem::size_of::<sgx_sealed_data_t>();
+        let key_request_size = mem::size_of::<sgx_key_request_t>();
+        if bytes.len() < header_size || bytes.len() >= u32::max_value() as usize {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let read_u32 = |offset: usize| -> u32 {
+            u32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]])
+        };
+        // plain_text_offset, reserved, then sgx_aes_gcm_data_t.
+        let plain_text_offset = read_u32(key_request_size);
+        let aes_data = key_request_size + 16;
+        let payload_size = read_u32(aes_data);
+        if plain_text_offset > payload_size {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let encrypt_len = plain_text_offset;
+        let additional_len = payload_size - plain_text_offset;
+        let sealed_data_size = Self::calc_raw_sealed_data_size(additional_len, encrypt_len);
+        if sealed_data_size == u32::max_value() || sealed_data_size as usize != bytes.len() {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+
+        let mut sealed_data = Self::default();
+        sealed_data.key_request = unsafe {
+            ptr::read_unaligned(bytes.as_ptr() as * const sgx_key_request_t)
+        };
+        sealed_data.payload_data.payload_size = payload_size;
+        sealed_data.payload_data.payload_tag.copy_from_slice(&bytes[aes_data + 16..header_size]);
+
+        let (encrypt, additional) = bytes[header_size..].split_at(encrypt_len as usize);
+        sealed_data.payload_data.encrypt = encrypt.to_vec().into_boxed_slice();
+        sealed_data.payload_data.additional = additional.to_vec().into_boxed_slice();
+
+        Ok(sealed_data)
+    }
+
     pub fn seal_data(additional_text: &[u8], encrypt_text: &[u8]) -> SgxResult<Self> {
 
         //let attribute_mask = sgx_attributes_t{flags: SGX_FLAGS_RESERVED | SGX_FLAGS_INITTED | SGX_FLAGS_DEBUG, xfrm: 0};
@@ -482,6 +565,66 @@ impl SgxInternalSealedData {
         self.unseal_data_helper()
     }
 
+    ///
+    /// Compare the security versions recorded in the key request with those of the running enclave.
+    ///
+    pub fn get_seal_status(&self) -> SgxSealStatus {
+
+        let report = rsgx_self_report();
+        let key_request = self.get_key_request();
+
+        if (key_request.isv_svn > report.body.isv_svn) ||
+           (key_request.config_svn > report.body.config_svn) {
+            SgxSealStatus::Newer
+        } else if (key_request.isv_svn == report.body.isv_svn) &&
+                  (key_request.config_svn == report.body.config_svn) &&
+                  (key_request.cpu_svn.svn == report.body.cpu_svn.svn) {
+            SgxSealStatus::Current
+        } else {
+            SgxSealStatus::Outdated
+        }
+    }
+
+    ///
+    /// Unseal with the key request stored in the blob and seal the same payload again with
+    /// a fresh key ID under the current CPUSVN, ISVSVN and CONFIGSVN.
+    ///
+    pub fn reseal_data(&self, key_policy: u16, attribute_mask: sgx_attributes_t, misc_mask: sgx_misc_select_t) -> SgxResult<Self> {
+
+        let mut unsealed_data = if self.get_encrypt_txt_len() == 0 {
+            self.unmac_aadata()?
+        } else {
+            self.unseal_data()?
+        };
+
+        let result = if unsealed_data.decrypt.is_empty() {
+            Self::mac_aadata_ex(key_policy,
+                                attribute_mask,
+                                misc_mask,
+                                unsealed_data.get_additional_txt())
+        } else {
+            Self::seal_data_ex(key_policy,
+                               attribute_mask,
+                               misc_mask,
+                               unsealed_data.get_additional_txt(),
+                               unsealed_data.get_decrypt_txt())
+        };
+
+        clear_bytes(&mut unsealed_data.decrypt);
+        result
+    }
+
+    ///
+    /// Reseal under the current enclave identity, keeping the key policy and masks of the blob.
+    ///
+    pub fn migrate_data(&self) -> SgxResult<Self> {
+
+        let key_request = *self.get_key_request();
+        self.reseal_data(key_request.key_policy,
+                         key_request.attribute_mask,
+                         key_request.misc_mask)
+    }
+
     fn seal_data_iv(additional_text: &[u8],
                     encrypt_text: &[u8],
                     payload_iv: &[u8],
@@ -22,6 +22,9 @@
 //! * Exposes APIs to create sealed data which is both confidentiality andintegrity protected.
 //! * Exposes an API to unseal sealed data inside the enclave.
 //! * Provides APIs to authenticate and verify the input data with AES-GMAC.
+//! * Provides a versioned envelope and APIs to migrate sealed data to a new enclave security version.
+//! * Provides an owned sealed blob that is parsed from untrusted bytes with full bounds checking.
+//! * Provides sealed storage bound to a monotonic counter to detect rollback of sealed data.
 //!
 //! The library also provides APIs to help calculate the sealed data size, encrypt text length, and Message Authentication Code (MAC) text length.
 //!
@@ -83,6 +86,7 @@ extern crate sgx_types;
 extern crate sgx_trts;
 extern crate sgx_tcrypto;
 extern crate sgx_tse;
+extern crate sgx_tservice;
 
 mod seal;
 pub use self::seal::{SgxSealedData, SgxUnsealedData};
@@ -91,3 +95,16 @@ mod aad;
 pub use self::aad::{SgxMacAadata};
 
 mod internal;
+pub use self::internal::SgxSealStatus;
+
+mod policy;
+pub use self::policy::SgxSealPolicy;
+
+mod envelope;
+pub use self::envelope::{SgxSealedEnvelope, SgxUnsealedEnvelope, SGX_SEAL_ENVELOPE_VERSION};
+
+mod blob;
+pub use self::blob::SealedBlob;
+
+mod rollback;
+pub use self::rollback::{CounterBackend, RollbackProtectedStore, SGX_COUNTER_ID_SIZE};
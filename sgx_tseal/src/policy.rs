@@ -0,0 +1,90 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! Seal Key Policy
+//!
+//! `SgxSealPolicy` groups the key policy, attribute mask and misc mask that
+//! `seal_data_ex` and `mac_aadata_ex` take as separate raw values, and can be
+//! recovered from the `sgx_key_request_t` stored in a sealed blob.
+//!
+use sgx_types::*;
+use sgx_tse::*;
+use crate::internal::KEY_POLICY_KSS;
+
+/// The key derivation parameters of a sealed blob.
+#[derive(Clone, Copy)]
+pub struct SgxSealPolicy {
+    pub key_policy: u16,
+    pub attribute_mask: sgx_attributes_t,
+    pub misc_mask: sgx_misc_select_t,
+}
+
+impl SgxSealPolicy {
+    ///
+    /// Create a policy from raw values, as passed to `seal_data_ex`.
+    ///
+    pub fn new(key_policy: u16, attribute_mask: sgx_attributes_t, misc_mask: sgx_misc_select_t) -> Self {
+        SgxSealPolicy {
+            key_policy,
+            attribute_mask,
+            misc_mask,
+        }
+    }
+
+    ///
+    /// The policy used by `seal_data`: MRSIGNER (plus the KSS identities when the
+    /// enclave has KSS enabled) with the default attribute and misc masks. Data sealed
+    /// with it can be unsealed by later versions of the enclave from the same signer.
+    ///
+    pub fn mrsigner() -> Self {
+        Self::with_key_policy(SGX_KEYPOLICY_MRSIGNER)
+    }
+
+    ///
+    /// Like `mrsigner`, but bound to MRENCLAVE. Data sealed with it can only be unsealed
+    /// by exactly this enclave build, so it must be migrated to an MRSIGNER policy
+    /// before the enclave is upgraded.
+    ///
+    pub fn mrenclave() -> Self {
+        Self::with_key_policy(SGX_KEYPOLICY_MRENCLAVE)
+    }
+
+    ///
+    /// Recover the policy from the key request stored in a sealed blob.
+    ///
+    pub fn from_key_request(key_request: &sgx_key_request_t) -> Self {
+        SgxSealPolicy {
+            key_policy: key_request.key_policy,
+            attribute_mask: key_request.attribute_mask,
+            misc_mask: key_request.misc_mask,
+        }
+    }
+
+    fn with_key_policy(mut key_policy: u16) -> Self {
+        let report = rsgx_self_report();
+        if (report.body.attributes.flags & SGX_FLAGS_KSS) != 0 {
+            key_policy |= KEY_POLICY_KSS;
+        }
+        SgxSealPolicy {
+            key_policy,
+            attribute_mask: sgx_attributes_t{flags: TSEAL_DEFAULT_FLAGSMASK, xfrm: 0},
+            misc_mask: TSEAL_DEFAULT_MISCMASK,
+        }
+    }
+}
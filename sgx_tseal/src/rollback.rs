@@ -0,0 +1,232 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! Rollback Protected Sealed Storage
+//!
+//! A sealed blob stays valid forever, so the untrusted application can hand an
+//! enclave an older copy of its state. `RollbackProtectedStore` binds every
+//! sealed version to the value of a monotonic counter and only unseals the
+//! blob that matches the current value. The counter is reached through the
+//! `CounterBackend` trait, implemented here for `SgxMonotonicCounter`, backed
+//! by any counter source of sgx_tservice. sgx_tstd implements it for a counter
+//! kept in a protected file, which stands in for a real counter in simulation
+//! mode.
+//!
+//! The binding is stored in front of the additional MAC text:
+//!
+//! ```text
+//! offset  size  field
+//! 0       8     magic "SGXRBCTR"
+//! 8       16    counter id
+//! 24      4     counter value, little-endian
+//! 28      4     reserved, zero
+//! 32      ...   additional text supplied by the caller
+//! ```
+//!
+use sgx_types::*;
+use sgx_tservice::sgxcounter::SgxMonotonicCounter;
+use crate::policy::SgxSealPolicy;
+use crate::blob::SealedBlob;
+use crate::seal::SgxUnsealedData;
+use alloc::vec::Vec;
+
+const BINDING_MAGIC: [u8; 8] = *b"SGXRBCTR";
+const BINDING_HEADER_SIZE: usize = 32;
+
+/// The size of the identifier returned by `CounterBackend::counter_id`.
+pub const SGX_COUNTER_ID_SIZE: usize = 16;
+
+///
+/// A monotonic counter that a `RollbackProtectedStore` binds its blobs to.
+///
+/// The counter must never go backwards and must be out of reach of the untrusted
+/// application, otherwise it provides no rollback protection.
+///
+pub trait CounterBackend {
+    ///
+    /// Get an identifier that is unique to this counter, so that a blob bound to
+    /// one counter is not accepted by a store using another.
+    ///
+    fn counter_id(&self) -> SgxResult<[u8; SGX_COUNTER_ID_SIZE]>;
+    ///
+    /// Read the current value of the counter.
+    ///
+    fn read(&self) -> SgxResult<u32>;
+    ///
+    /// Increment the counter and return the new value.
+    ///
+    fn increment(&self) -> SgxResult<u32>;
+}
+
+impl CounterBackend for SgxMonotonicCounter {
+    fn counter_id(&self) -> SgxResult<[u8; SGX_COUNTER_ID_SIZE]> {
+        let uuid = self.get_uuid().ok_or(sgx_status_t::SGX_ERROR_MC_NOT_FOUND)?;
+        let mut id = [0_u8; SGX_COUNTER_ID_SIZE];
+        id[..SGX_MC_UUID_COUNTER_ID_SIZE].copy_from_slice(&uuid.counter_id);
+        id[SGX_MC_UUID_COUNTER_ID_SIZE..].copy_from_slice(&uuid.nonce);
+        Ok(id)
+    }
+
+    fn read(&self) -> SgxResult<u32> {
+        SgxMonotonicCounter::read(self)
+    }
+
+    fn increment(&self) -> SgxResult<u32> {
+        SgxMonotonicCounter::increment(self)
+    }
+}
+
+fn binding_header(id: &[u8; SGX_COUNTER_ID_SIZE], value: u32) -> [u8; BINDING_HEADER_SIZE] {
+    let mut header = [0_u8; BINDING_HEADER_SIZE];
+    header[..8].copy_from_slice(&BINDING_MAGIC);
+    header[8..24].copy_from_slice(id);
+    header[24..28].copy_from_slice(&value.to_le_bytes());
+    header
+}
+
+fn parse_binding_header(additional: &[u8]) -> Option<([u8; SGX_COUNTER_ID_SIZE], u32)> {
+    if additional.len() < BINDING_HEADER_SIZE || additional[..8] != BINDING_MAGIC || additional[28..32] != [0_u8; 4] {
+        return None;
+    }
+    let mut id = [0_u8; SGX_COUNTER_ID_SIZE];
+    id.copy_from_slice(&additional[8..24]);
+    let value = u32::from_le_bytes([additional[24], additional[25], additional[26], additional[27]]);
+    Some((id, value))
+}
+
+/// Seals data bound to the value of a monotonic counter, so that only the latest version can be unsealed.
+pub struct RollbackProtectedStore<B: CounterBackend> {
+    backend: B,
+    policy: SgxSealPolicy,
+}
+
+impl<B: CounterBackend> RollbackProtectedStore<B> {
+
+    ///
+    /// Create a store that seals with the default MRSIGNER policy of `SgxSealedData::seal_data`.
+    ///
+    pub fn new(backend: B) -> Self {
+        Self::with_policy(backend, SgxSealPolicy::mrsigner())
+    }
+
+    ///
+    /// Create a store that seals with the given policy.
+    ///
+    pub fn with_policy(backend: B, policy: SgxSealPolicy) -> Self {
+        RollbackProtectedStore { backend, policy }
+    }
+
+    ///
+    /// Get the counter backend of the store.
+    ///
+    pub fn get_backend(&self) -> &B {
+        &self.backend
+    }
+
+    ///
+    /// Consume the store and return its counter backend.
+    ///
+    pub fn into_backend(self) -> B {
+        self.backend
+    }
+
+    ///
+    /// Seal a new version of the data and increment the counter, which makes every
+    /// blob sealed before unusable.
+    ///
+    /// # Description
+    ///
+    /// The data is sealed for the next counter value before the counter is incremented,
+    /// so a failure to seal leaves the previous version valid. Once the counter has been
+    /// incremented, the previous version is gone: the returned blob should be stored
+    /// before anything else depends on the new state.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_MC_USED_UP**
+    ///
+    /// The counter has reached its maximum value.
+    ///
+    /// **SGX_ERROR_UNEXPECTED**
+    ///
+    /// The counter did not move to the expected value, for example because it is shared
+    /// with another writer. The returned error means the new blob was discarded.
+    ///
+    /// The errors of the counter backend and of `SgxSealedData::seal_data_ex` are passed through.
+    ///
+    pub fn seal(&self, additional_text: &[u8], encrypt_text: &[u8]) -> SgxResult<SealedBlob> {
+
+        let id = self.backend.counter_id()?;
+        let current = self.backend.read()?;
+        let next = current.checked_add(1).ok_or(sgx_status_t::SGX_ERROR_MC_USED_UP)?;
+
+        let mut additional: Vec<u8> = Vec::with_capacity(BINDING_HEADER_SIZE + additional_text.len());
+        additional.extend_from_slice(&binding_header(&id, next));
+        additional.extend_from_slice(additional_text);
+        let blob = SealedBlob::seal_data_ex(&self.policy, &additional, encrypt_text)?;
+
+        if self.backend.increment()? != next {
+            return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+        }
+        Ok(blob)
+    }
+
+    ///
+    /// Unseal a blob if it is the latest version.
+    ///
+    /// The additional text of the result is the one passed to `seal`, without the
+    /// counter binding.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_MC_NOT_FOUND**
+    ///
+    /// The blob is bound to another counter, or to a value other than the current one.
+    /// An older value means the blob has been replaced by a newer version.
+    ///
+    /// **SGX_ERROR_MAC_MISMATCH**
+    ///
+    /// The blob was not sealed by a `RollbackProtectedStore`, or the tag verification failed.
+    ///
+    /// The errors of the counter backend and of `SgxSealedData::unseal_data` are passed through.
+    ///
+    pub fn unseal<'a>(&self, blob: &SealedBlob) -> SgxResult<SgxUnsealedData<'a, [u8]>> {
+
+        // The header is checked again by the MAC when the blob is unsealed.
+        let (id, value) = parse_binding_header(blob.get_additional_txt()).ok_or(sgx_status_t::SGX_ERROR_MAC_MISMATCH)?;
+        if id != self.backend.counter_id()? || value != self.backend.read()? {
+            return Err(sgx_status_t::SGX_ERROR_MC_NOT_FOUND);
+        }
+
+        let mut unsealed = blob.unseal_data()?;
+        unsealed.additional = unsealed.additional[BINDING_HEADER_SIZE..].to_vec().into_boxed_slice();
+        Ok(unsealed)
+    }
+
+    ///
+    /// Check whether a blob is the latest version without unsealing it. The binding is
+    /// only authenticated by `unseal`.
+    ///
+    pub fn is_current(&self, blob: &SealedBlob) -> SgxResult<bool> {
+        match parse_binding_header(blob.get_additional_txt()) {
+            Some((id, value)) => Ok(id == self.backend.counter_id()? && value == self.backend.read()?),
+            None => Ok(false),
+        }
+    }
+}
@@ -23,16 +23,32 @@
 //!
 //! **Trust Platform Service Functions**
 //!
+//! These need the Platform Services enclave and the `pse` feature, which is enabled
+//! by default.
+//!
+//! **Trusted Time and Monotonic Counter Sources**
+//!
+//! `SgxTime` and `SgxMonotonicCounter` get their values from a `TrustedTimeSource` and a
+//! `MonotonicCounterSource`. Besides the Platform Services enclave, the `remote` module
+//! provides both from a remote service that shares a key with the enclave, for platforms
+//! where the Platform Services enclave is no longer available.
+//!
 
 #![no_std]
 #![allow(non_camel_case_types)]
 #![cfg_attr(target_env = "sgx", feature(rustc_private))]
 
+extern crate alloc;
 extern crate sgx_types;
 
 pub mod sgxtime;
 pub mod sgxcounter;
+pub mod remote;
+
+mod source;
 
+#[cfg(feature = "pse")]
 mod tae;
+#[cfg(feature = "pse")]
 pub use self::tae::*;
 
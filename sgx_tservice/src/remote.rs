@@ -0,0 +1,483 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! Remote Trusted Time and Counter Service
+//!
+//! Platforms without the Platform Services enclave can get trusted time and
+//! monotonic counters from a remote service instead. The enclave and the
+//! service share a session key, normally the MK of a remote attestation
+//! session (see `rsgx_ra_get_keys` in sgx_tkey_exchange), so the service knows
+//! it is talking to an attested enclave and the enclave knows the answers come
+//! from the service. The untrusted application only forwards opaque frames,
+//! through a `ServiceTransport` supplied by the caller.
+//!
+//! Each frame is authenticated with AES-CMAC under the session key. A request
+//! carries a fresh random nonce that the response must echo, so old responses
+//! cannot be replayed. All integers are little-endian.
+//!
+//! ```text
+//! request                               response
+//! offset  size  field                   offset  size  field
+//! 0       4     magic "SGXQ"            0       4     magic "SGXR"
+//! 4       2     version (1)             4       2     version (1)
+//! 6       2     opcode                  6       2     opcode of the request
+//! 8       4     reserved, zero          8       4     status, 0 on success
+//! 12      16    nonce                   12      16    nonce of the request
+//! 28      n     body                    28      n     body, empty on error
+//! 28+n    16    AES-CMAC of 0..28+n     28+n    16    AES-CMAC of 0..28+n
+//! ```
+//!
+//! | opcode | request               | request body                      | response body              |
+//! |--------|-----------------------|-----------------------------------|----------------------------|
+//! | 1      | get trusted time      | none                              | time u64, time source nonce [32] |
+//! | 2      | create counter        | owner policy u16, reserved u16, attribute flags u64, xfrm u64 | uuid [16], value u32 |
+//! | 3      | destroy counter       | uuid [16]                         | none                       |
+//! | 4      | increment counter     | uuid [16]                         | value u32                  |
+//! | 5      | read counter          | uuid [16]                         | value u32                  |
+//!
+use sgx_types::*;
+use crate::sgxtime::TrustedTimeSource;
+use crate::sgxcounter::MonotonicCounterSource;
+use core::ptr;
+use alloc::vec::Vec;
+
+/// The protocol version implemented by this module.
+pub const SGX_TRUSTED_SERVICE_VERSION: u16 = 1;
+/// The size of the nonce that binds a response to its request.
+pub const SGX_TRUSTED_SERVICE_NONCE_SIZE: usize = 16;
+
+const REQUEST_MAGIC: [u8; 4] = *b"SGXQ";
+const RESPONSE_MAGIC: [u8; 4] = *b"SGXR";
+const FRAME_HEADER_SIZE: usize = 28;
+const FRAME_MAC_SIZE: usize = SGX_CMAC_MAC_SIZE;
+const UUID_SIZE: usize = SGX_MC_UUID_COUNTER_ID_SIZE + SGX_MC_UUID_NONCE_SIZE;
+
+const OPCODE_GET_TRUSTED_TIME: u16 = 1;
+const OPCODE_CREATE_COUNTER: u16 = 2;
+const OPCODE_DESTROY_COUNTER: u16 = 3;
+const OPCODE_INCREMENT_COUNTER: u16 = 4;
+const OPCODE_READ_COUNTER: u16 = 5;
+
+/// A request to the trusted time and counter service.
+#[derive(Clone, Copy)]
+pub enum ServiceRequest {
+    GetTrustedTime,
+    CreateCounter { owner_policy: u16, owner_attribute_mask: sgx_attributes_t },
+    DestroyCounter(sgx_mc_uuid_t),
+    IncrementCounter(sgx_mc_uuid_t),
+    ReadCounter(sgx_mc_uuid_t),
+}
+
+/// A response of the trusted time and counter service.
+#[derive(Clone, Copy)]
+pub enum ServiceResponse {
+    /// Answers `GetTrustedTime`.
+    TrustedTime { timestamp: sgx_time_t, source_nonce: sgx_time_source_nonce_t },
+    /// Answers `CreateCounter`.
+    CounterCreated { counter_uuid: sgx_mc_uuid_t, counter_value: u32 },
+    /// Answers `DestroyCounter`.
+    CounterDestroyed,
+    /// Answers `IncrementCounter` and `ReadCounter`.
+    CounterValue(u32),
+    /// Answers any request that failed.
+    Error(sgx_status_t),
+}
+
+/// Carries frames between the enclave and the service, usually through an OCALL.
+pub trait ServiceTransport {
+    ///
+    /// Send a request frame and return the response frame. The frames are
+    /// authenticated by the caller, so the transport needs no protection.
+    ///
+    fn call(&self, request: &[u8]) -> SgxResult<Vec<u8>>;
+}
+
+fn rsgx_cmac(key: &sgx_cmac_128bit_key_t, msg: &[u8]) -> SgxResult<sgx_cmac_128bit_tag_t> {
+
+    if msg.len() > u32::max_value() as usize {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    let mut mac = sgx_cmac_128bit_tag_t::default();
+    let ret = unsafe {
+        sgx_rijndael128_cmac_msg(key as * const sgx_cmac_128bit_key_t,
+                                 msg.as_ptr(),
+                                 msg.len() as u32,
+                                 &mut mac as * mut sgx_cmac_128bit_tag_t)
+    };
+    match ret {
+        sgx_status_t::SGX_SUCCESS => Ok(mac),
+        _ => Err(ret),
+    }
+}
+
+fn consttime_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b.iter()).fold(0_u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn seal_frame(key: &sgx_cmac_128bit_key_t, mut frame: Vec<u8>) -> SgxResult<Vec<u8>> {
+    let mac = rsgx_cmac(key, &frame)?;
+    frame.extend_from_slice(&mac);
+    Ok(frame)
+}
+
+// Checks the MAC, magic and version of a frame and returns its opcode, status
+// or reserved field, nonce and body.
+fn open_frame<'a>(key: &sgx_cmac_128bit_key_t,
+                  magic: [u8; 4],
+                  frame: &'a [u8]) -> SgxResult<(u16, u32, [u8; SGX_TRUSTED_SERVICE_NONCE_SIZE], &'a [u8])> {
+
+    if frame.len() < FRAME_HEADER_SIZE + FRAME_MAC_SIZE {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    let (data, mac) = frame.split_at(frame.len() - FRAME_MAC_SIZE);
+    if !consttime_eq(&rsgx_cmac(key, data)?, mac) {
+        return Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH);
+    }
+    if data[..4] != magic[..] || read_u16(&data[4..]) != SGX_TRUSTED_SERVICE_VERSION {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    let mut nonce = [0_u8; SGX_TRUSTED_SERVICE_NONCE_SIZE];
+    nonce.copy_from_slice(&data[12..FRAME_HEADER_SIZE]);
+    Ok((read_u16(&data[6..]), read_u32(&data[8..]), nonce, &data[FRAME_HEADER_SIZE..]))
+}
+
+fn read_u16(buf: &[u8]) -> u16 {
+    u16::from_le_bytes([buf[0], buf[1]])
+}
+
+fn read_u32(buf: &[u8]) -> u32 {
+    u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]])
+}
+
+fn read_u64(buf: &[u8]) -> u64 {
+    let mut bytes = [0_u8; 8];
+    bytes.copy_from_slice(&buf[..8]);
+    u64::from_le_bytes(bytes)
+}
+
+fn write_uuid(frame: &mut Vec<u8>, uuid: &sgx_mc_uuid_t) {
+    frame.extend_from_slice(&uuid.counter_id);
+    frame.extend_from_slice(&uuid.nonce);
+}
+
+fn read_uuid(buf: &[u8]) -> sgx_mc_uuid_t {
+    let mut uuid = sgx_mc_uuid_t::default();
+    uuid.counter_id.copy_from_slice(&buf[..SGX_MC_UUID_COUNTER_ID_SIZE]);
+    uuid.nonce.copy_from_slice(&buf[SGX_MC_UUID_COUNTER_ID_SIZE..UUID_SIZE]);
+    uuid
+}
+
+// Only the errors a time or counter service can report are carried over; any
+// other status becomes SGX_ERROR_UNEXPECTED.
+fn status_from_u32(status: u32) -> sgx_status_t {
+    let known = [
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER,
+        sgx_status_t::SGX_ERROR_OUT_OF_MEMORY,
+        sgx_status_t::SGX_ERROR_BUSY,
+        sgx_status_t::SGX_ERROR_MC_NOT_FOUND,
+        sgx_status_t::SGX_ERROR_MC_NO_ACCESS_RIGHT,
+        sgx_status_t::SGX_ERROR_MC_USED_UP,
+        sgx_status_t::SGX_ERROR_MC_OVER_QUOTA,
+        sgx_status_t::SGX_ERROR_SERVICE_UNAVAILABLE,
+        sgx_status_t::SGX_ERROR_SERVICE_TIMEOUT,
+        sgx_status_t::SGX_ERROR_NETWORK_FAILURE,
+    ];
+    known.iter().cloned().find(|s| *s as u32 == status).unwrap_or(sgx_status_t::SGX_ERROR_UNEXPECTED)
+}
+
+impl ServiceRequest {
+
+    fn opcode(&self) -> u16 {
+        match *self {
+            ServiceRequest::GetTrustedTime => OPCODE_GET_TRUSTED_TIME,
+            ServiceRequest::CreateCounter { .. } => OPCODE_CREATE_COUNTER,
+            ServiceRequest::DestroyCounter(_) => OPCODE_DESTROY_COUNTER,
+            ServiceRequest::IncrementCounter(_) => OPCODE_INCREMENT_COUNTER,
+            ServiceRequest::ReadCounter(_) => OPCODE_READ_COUNTER,
+        }
+    }
+
+    ///
+    /// Encode and authenticate the request.
+    ///
+    pub fn encode(&self,
+                  key: &sgx_cmac_128bit_key_t,
+                  nonce: &[u8; SGX_TRUSTED_SERVICE_NONCE_SIZE]) -> SgxResult<Vec<u8>> {
+
+        let mut frame: Vec<u8> = Vec::with_capacity(FRAME_HEADER_SIZE + 20 + FRAME_MAC_SIZE);
+        frame.extend_from_slice(&REQUEST_MAGIC);
+        frame.extend_from_slice(&SGX_TRUSTED_SERVICE_VERSION.to_le_bytes());
+        frame.extend_from_slice(&self.opcode().to_le_bytes());
+        frame.extend_from_slice(&[0_u8; 4]);
+        frame.extend_from_slice(nonce);
+        match *self {
+            ServiceRequest::GetTrustedTime => {},
+            ServiceRequest::CreateCounter { owner_policy, owner_attribute_mask } => {
+                frame.extend_from_slice(&owner_policy.to_le_bytes());
+                frame.extend_from_slice(&[0_u8; 2]);
+                frame.extend_from_slice(&owner_attribute_mask.flags.to_le_bytes());
+                frame.extend_from_slice(&owner_attribute_mask.xfrm.to_le_bytes());
+            },
+            ServiceRequest::DestroyCounter(ref uuid) |
+            ServiceRequest::IncrementCounter(ref uuid) |
+            ServiceRequest::ReadCounter(ref uuid) => write_uuid(&mut frame, uuid),
+        }
+        seal_frame(key, frame)
+    }
+
+    ///
+    /// Authenticate and decode a request frame. This is the service side of the protocol.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_MAC_MISMATCH**
+    ///
+    /// The frame was not produced with `key`.
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The frame is malformed or of another protocol version.
+    ///
+    pub fn decode(key: &sgx_cmac_128bit_key_t,
+                  frame: &[u8]) -> SgxResult<([u8; SGX_TRUSTED_SERVICE_NONCE_SIZE], ServiceRequest)> {
+
+        let (opcode, reserved, nonce, body) = open_frame(key, REQUEST_MAGIC, frame)?;
+        if reserved != 0 {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let request = match (opcode, body.len()) {
+            (OPCODE_GET_TRUSTED_TIME, 0) => ServiceRequest::GetTrustedTime,
+            (OPCODE_CREATE_COUNTER, 20) => ServiceRequest::CreateCounter {
+                owner_policy: read_u16(body),
+                owner_attribute_mask: sgx_attributes_t {
+                    flags: read_u64(&body[4..]),
+                    xfrm: read_u64(&body[12..]),
+                },
+            },
+            (OPCODE_DESTROY_COUNTER, UUID_SIZE) => ServiceRequest::DestroyCounter(read_uuid(body)),
+            (OPCODE_INCREMENT_COUNTER, UUID_SIZE) => ServiceRequest::IncrementCounter(read_uuid(body)),
+            (OPCODE_READ_COUNTER, UUID_SIZE) => ServiceRequest::ReadCounter(read_uuid(body)),
+            _ => return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+        };
+        Ok((nonce, request))
+    }
+}
+
+impl ServiceResponse {
+
+    ///
+    /// Encode and authenticate the response to a request. This is the service side of the protocol.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The response does not answer this kind of request, or it is an `Error` with
+    /// `SGX_SUCCESS`.
+    ///
+    pub fn encode(&self,
+                  key: &sgx_cmac_128bit_key_t,
+                  request: &ServiceRequest,
+                  nonce: &[u8; SGX_TRUSTED_SERVICE_NONCE_SIZE]) -> SgxResult<Vec<u8>> {
+
+        let status = match (*self, *request) {
+            (ServiceResponse::Error(sgx_status_t::SGX_SUCCESS), _) => return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+            (ServiceResponse::Error(status), _) => status as u32,
+            (ServiceResponse::TrustedTime { .. }, ServiceRequest::GetTrustedTime) |
+            (ServiceResponse::CounterCreated { .. }, ServiceRequest::CreateCounter { .. }) |
+            (ServiceResponse::CounterDestroyed, ServiceRequest::DestroyCounter(_)) |
+            (ServiceResponse::CounterValue(_), ServiceRequest::IncrementCounter(_)) |
+            (ServiceResponse::CounterValue(_), ServiceRequest::ReadCounter(_)) => 0,
+            _ => return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+        };
+
+        let mut frame: Vec<u8> = Vec::with_capacity(FRAME_HEADER_SIZE + 40 + FRAME_MAC_SIZE);
+        frame.extend_from_slice(&RESPONSE_MAGIC);
+        frame.extend_from_slice(&SGX_TRUSTED_SERVICE_VERSION.to_le_bytes());
+        frame.extend_from_slice(&request.opcode().to_le_bytes());
+        frame.extend_from_slice(&status.to_le_bytes());
+        frame.extend_from_slice(nonce);
+        match *self {
+            ServiceResponse::TrustedTime { timestamp, ref source_nonce } => {
+                frame.extend_from_slice(&timestamp.to_le_bytes());
+                frame.extend_from_slice(source_nonce);
+            },
+            ServiceResponse::CounterCreated { ref counter_uuid, counter_value } => {
+                write_uuid(&mut frame, counter_uuid);
+                frame.extend_from_slice(&counter_value.to_le_bytes());
+            },
+            ServiceResponse::CounterValue(counter_value) => frame.extend_from_slice(&counter_value.to_le_bytes()),
+            ServiceResponse::CounterDestroyed | ServiceResponse::Error(_) => {},
+        }
+        seal_frame(key, frame)
+    }
+
+    ///
+    /// Authenticate and decode the response to a request sent with `nonce`.
+    ///
+    /// # Errors
+    ///
+    /// **SGX_ERROR_MAC_MISMATCH**
+    ///
+    /// The frame was not produced with `key`, or answers another request.
+    ///
+    /// **SGX_ERROR_INVALID_PARAMETER**
+    ///
+    /// The frame is malformed or of another protocol version.
+    ///
+    pub fn decode(key: &sgx_cmac_128bit_key_t,
+                  request: &ServiceRequest,
+                  nonce: &[u8; SGX_TRUSTED_SERVICE_NONCE_SIZE],
+                  frame: &[u8]) -> SgxResult<ServiceResponse> {
+
+        let (opcode, status, frame_nonce, body) = open_frame(key, RESPONSE_MAGIC, frame)?;
+        if opcode != request.opcode() || !consttime_eq(&frame_nonce, nonce) {
+            return Err(sgx_status_t::SGX_ERROR_MAC_MISMATCH);
+        }
+        if status != 0 {
+            return if body.is_empty() {
+                Ok(ServiceResponse::Error(status_from_u32(status)))
+            } else {
+                Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+            };
+        }
+
+        let response = match (opcode, body.len()) {
+            (OPCODE_GET_TRUSTED_TIME, 40) => {
+                let mut source_nonce = sgx_time_source_nonce_t::default();
+                source_nonce.copy_from_slice(&body[8..]);
+                ServiceResponse::TrustedTime { timestamp: read_u64(body), source_nonce }
+            },
+            (OPCODE_CREATE_COUNTER, 20) => ServiceResponse::CounterCreated {
+                counter_uuid: read_uuid(body),
+                counter_value: read_u32(&body[UUID_SIZE..]),
+            },
+            (OPCODE_DESTROY_COUNTER, 0) => ServiceResponse::CounterDestroyed,
+            (OPCODE_INCREMENT_COUNTER, 4) | (OPCODE_READ_COUNTER, 4) => ServiceResponse::CounterValue(read_u32(body)),
+            _ => return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+        };
+        Ok(response)
+    }
+}
+
+/// Trusted time and monotonic counters from a remote service that shares a session key with the enclave.
+pub struct RemoteTrustedService<T: ServiceTransport> {
+    transport: T,
+    key: sgx_cmac_128bit_key_t,
+}
+
+impl<T: ServiceTransport> RemoteTrustedService<T> {
+
+    ///
+    /// Create a client of the service reachable through `transport`.
+    ///
+    /// # Parameters
+    ///
+    /// **transport**
+    ///
+    /// Forwards frames to the service.
+    ///
+    /// **session_key**
+    ///
+    /// The key shared with the service over an attested channel, such as the MK of
+    /// a remote attestation session. It is wiped when the client is dropped.
+    ///
+    pub fn new(transport: T, session_key: &sgx_cmac_128bit_key_t) -> Self {
+        RemoteTrustedService { transport, key: *session_key }
+    }
+
+    ///
+    /// Get the transport of the client.
+    ///
+    pub fn get_transport(&self) -> &T {
+        &self.transport
+    }
+
+    ///
+    /// Send a request to the service and return its answer.
+    ///
+    /// # Errors
+    ///
+    /// An `Error` response is returned as its status. The errors of the transport,
+    /// of `sgx_read_rand` and of `ServiceResponse::decode` are passed through.
+    ///
+    pub fn request(&self, request: &ServiceRequest) -> SgxResult<ServiceResponse> {
+
+        let mut nonce = [0_u8; SGX_TRUSTED_SERVICE_NONCE_SIZE];
+        let ret = unsafe { sgx_read_rand(nonce.as_mut_ptr(), nonce.len()) };
+        if ret != sgx_status_t::SGX_SUCCESS {
+            return Err(ret);
+        }
+
+        let frame = request.encode(&self.key, &nonce)?;
+        let response = self.transport.call(&frame)?;
+        match ServiceResponse::decode(&self.key, request, &nonce, &response)? {
+            ServiceResponse::Error(status) => Err(status),
+            response => Ok(response),
+        }
+    }
+}
+
+impl<T: ServiceTransport> Drop for RemoteTrustedService<T> {
+    fn drop(&mut self) {
+        for b in self.key.iter_mut() {
+            unsafe { ptr::write_volatile(b, 0) };
+        }
+    }
+}
+
+impl<T: ServiceTransport> TrustedTimeSource for RemoteTrustedService<T> {
+    fn get_trusted_time(&self) -> SgxResult<(sgx_time_t, sgx_time_source_nonce_t)> {
+        match self.request(&ServiceRequest::GetTrustedTime)? {
+            ServiceResponse::TrustedTime { timestamp, source_nonce } => Ok((timestamp, source_nonce)),
+            _ => Err(sgx_status_t::SGX_ERROR_UNEXPECTED),
+        }
+    }
+}
+
+impl<T: ServiceTransport> MonotonicCounterSource for RemoteTrustedService<T> {
+    fn create_counter(&self, owner_policy: u16, owner_attribute_mask: &sgx_attributes_t) -> SgxResult<(sgx_mc_uuid_t, u32)> {
+        let request = ServiceRequest::CreateCounter { owner_policy, owner_attribute_mask: *owner_attribute_mask };
+        match self.request(&request)? {
+            ServiceResponse::CounterCreated { counter_uuid, counter_value } => Ok((counter_uuid, counter_value)),
+            _ => Err(sgx_status_t::SGX_ERROR_UNEXPECTED),
+        }
+    }
+
+    fn destroy_counter(&self, counter_uuid: &sgx_mc_uuid_t) -> SgxError {
+        match self.request(&ServiceRequest::DestroyCounter(*counter_uuid))? {
+            ServiceResponse::CounterDestroyed => Ok(()),
+            _ => Err(sgx_status_t::SGX_ERROR_UNEXPECTED),
+        }
+    }
+
+    fn increment_counter(&self, counter_uuid: &sgx_mc_uuid_t) -> SgxResult<u32> {
+        match self.request(&ServiceRequest::IncrementCounter(*counter_uuid))? {
+            ServiceResponse::CounterValue(value) => Ok(value),
+            _ => Err(sgx_status_t::SGX_ERROR_UNEXPECTED),
+        }
+    }
+
+    fn read_counter(&self, counter_uuid: &sgx_mc_uuid_t) -> SgxResult<u32> {
+        match self.request(&ServiceRequest::ReadCounter(*counter_uuid))? {
+            ServiceResponse::CounterValue(value) => Ok(value),
+            _ => Err(sgx_status_t::SGX_ERROR_UNEXPECTED),
+        }
+    }
+}
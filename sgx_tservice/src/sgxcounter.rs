@@ -17,11 +17,143 @@
 
 use sgx_types::*;
 use core::cell::Cell;
+use core::mem;
+use crate::source::SourceCell;
+
+/// The owner attribute mask used by `SgxMonotonicCounter::new`.
+pub const SGX_MC_DEFAULT_OWNER_ATTRIBUTE_MASK: sgx_attributes_t = sgx_attributes_t {
+    flags: 0xFFFF_FFFF_FFFF_FFCB,
+    xfrm: 0,
+};
+
+/// A provider of monotonic counters, addressed by uuid.
+pub trait MonotonicCounterSource {
+    /// Create a counter and return its uuid and initial value.
+    fn create_counter(&self, owner_policy: u16, owner_attribute_mask: &sgx_attributes_t) -> SgxResult<(sgx_mc_uuid_t, u32)>;
+    /// Destroy a counter.
+    fn destroy_counter(&self, counter_uuid: &sgx_mc_uuid_t) -> SgxError;
+    /// Increment a counter and return the new value.
+    fn increment_counter(&self, counter_uuid: &sgx_mc_uuid_t) -> SgxResult<u32>;
+    /// Read the value of a counter.
+    fn read_counter(&self, counter_uuid: &sgx_mc_uuid_t) -> SgxResult<u32>;
+}
+
+/// Monotonic counters of the Platform Services enclave. A PSE session must be open,
+/// see `rsgx_create_pse_session`.
+#[cfg(feature = "pse")]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PseCounterSource;
+
+#[cfg(feature = "pse")]
+impl MonotonicCounterSource for PseCounterSource {
+    fn create_counter(&self, owner_policy: u16, owner_attribute_mask: &sgx_attributes_t) -> SgxResult<(sgx_mc_uuid_t, u32)> {
+
+        let mut counter_uuid = sgx_mc_uuid_t::default();
+        let mut counter_value: u32 = 0;
+        let ret = rsgx_create_monotonic_counter_ex(owner_policy, owner_attribute_mask, &mut counter_uuid, &mut counter_value);
+        match ret {
+            sgx_status_t::SGX_SUCCESS => Ok((counter_uuid, counter_value)),
+            _ => Err(ret),
+        }
+    }
+
+    fn destroy_counter(&self, counter_uuid: &sgx_mc_uuid_t) -> SgxError {
+
+        let ret = rsgx_destroy_monotonic_counter(counter_uuid);
+        match ret {
+            sgx_status_t::SGX_SUCCESS => Ok(()),
+            _ => Err(ret),
+        }
+    }
+
+    fn increment_counter(&self, counter_uuid: &sgx_mc_uuid_t) -> SgxResult<u32> {
+
+        let mut counter_value: u32 = 0;
+        let ret = rsgx_increment_monotonic_counter(counter_uuid, &mut counter_value);
+        match ret {
+            sgx_status_t::SGX_SUCCESS => Ok(counter_value),
+            _ => Err(ret),
+        }
+    }
+
+    fn read_counter(&self, counter_uuid: &sgx_mc_uuid_t) -> SgxResult<u32> {
+
+        let mut counter_value: u32 = 0;
+        let ret = rsgx_read_monotonic_counter(counter_uuid, &mut counter_value);
+        match ret {
+            sgx_status_t::SGX_SUCCESS => Ok(counter_value),
+            _ => Err(ret),
+        }
+    }
+}
+
+#[cfg(not(feature = "pse"))]
+struct UnavailableCounterSource;
+
+#[cfg(not(feature = "pse"))]
+impl MonotonicCounterSource for UnavailableCounterSource {
+    fn create_counter(&self, _owner_policy: u16, _owner_attribute_mask: &sgx_attributes_t) -> SgxResult<(sgx_mc_uuid_t, u32)> {
+        Err(sgx_status_t::SGX_ERROR_SERVICE_UNAVAILABLE)
+    }
+
+    fn destroy_counter(&self, _counter_uuid: &sgx_mc_uuid_t) -> SgxError {
+        Err(sgx_status_t::SGX_ERROR_SERVICE_UNAVAILABLE)
+    }
+
+    fn increment_counter(&self, _counter_uuid: &sgx_mc_uuid_t) -> SgxResult<u32> {
+        Err(sgx_status_t::SGX_ERROR_SERVICE_UNAVAILABLE)
+    }
+
+    fn read_counter(&self, _counter_uuid: &sgx_mc_uuid_t) -> SgxResult<u32> {
+        Err(sgx_status_t::SGX_ERROR_SERVICE_UNAVAILABLE)
+    }
+}
+
+static COUNTER_SOURCE: SourceCell<dyn MonotonicCounterSource + Sync> = SourceCell::new();
+
+///
+/// Set the counter source used by `SgxMonotonicCounter::new`, `new_ex` and `from_uuid`.
+///
+/// The source can be set once. Until it is set, the Platform Services enclave is used
+/// if the `pse` feature is enabled, and every operation fails with
+/// **SGX_ERROR_SERVICE_UNAVAILABLE** otherwise.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_STATE**
+///
+/// A counter source has already been set.
+///
+pub fn rsgx_set_monotonic_counter_source(source: &'static (dyn MonotonicCounterSource + Sync)) -> SgxError {
+    if COUNTER_SOURCE.set(source) {
+        Ok(())
+    } else {
+        Err(sgx_status_t::SGX_ERROR_INVALID_STATE)
+    }
+}
+
+///
+/// Get the counter source used by `SgxMonotonicCounter::new`, `new_ex` and `from_uuid`.
+///
+pub fn rsgx_get_monotonic_counter_source() -> &'static dyn MonotonicCounterSource {
+    if let Some(source) = COUNTER_SOURCE.get() {
+        return source;
+    }
+    #[cfg(feature = "pse")]
+    {
+        &PseCounterSource
+    }
+    #[cfg(not(feature = "pse"))]
+    {
+        &UnavailableCounterSource
+    }
+}
 
 /// Monotonic counter ID
 pub struct SgxMonotonicCounter {
     counter_uuid: sgx_mc_uuid_t,
     initflag: Cell<bool>,
+    source: &'static dyn MonotonicCounterSource,
 }
 
 impl SgxMonotonicCounter {
@@ -30,12 +162,30 @@ impl SgxMonotonicCounter {
     /// Rebuild a SgxMonotonicCounter from an existing uuid. Caller is responsible for the uuid.
     ///
     pub fn from_uuid(id: sgx_mc_uuid_t) -> Self {
+        SgxMonotonicCounter::from_uuid_with_source(rsgx_get_monotonic_counter_source(), id)
+    }
+
+    ///
+    /// Rebuild a SgxMonotonicCounter from an existing uuid of the given counter source.
+    ///
+    pub fn from_uuid_with_source(source: &'static dyn MonotonicCounterSource, id: sgx_mc_uuid_t) -> Self {
         SgxMonotonicCounter {
             counter_uuid: id,
             initflag: Cell::new(true),
+            source,
         }
     }
 
+    ///
+    /// Release the counter without destroying it and return its uuid, so that it can
+    /// be rebuilt with `from_uuid` later. Dropping a SgxMonotonicCounter destroys the counter.
+    ///
+    pub fn into_uuid(self) -> Option<sgx_mc_uuid_t> {
+        let uuid = self.get_uuid();
+        mem::forget(self);
+        uuid
+    }
+
     ///
     /// Dump the uuid of an existing SGX monotonic counter
     ///
@@ -55,6 +205,9 @@ impl SgxMonotonicCounter {
     /// Call new to create a monotonic counter with the default owner policy 0x1, which means enclaves
     /// with same signing key can access the monotonic counter and default owner_attribute_mask 0xFFFFFFFFFFFFFFCB.
     ///
+    /// The counter is created in the source returned by `rsgx_get_monotonic_counter_source`, which is
+    /// the Platform Services enclave unless another source has been set.
+    ///
     /// The caller should call rsgx_create_pse_session to establish a session with the platform service enclave
     /// before calling this API.
     ///
@@ -133,16 +286,10 @@ impl SgxMonotonicCounter {
     ///
     pub fn new(counter_value: &mut u32) -> SgxResult<Self> {
 
-        let mut counter_uuid = sgx_mc_uuid_t::default();
-        let ret = rsgx_create_monotonic_counter(&mut counter_uuid, counter_value);
-
-        match ret {
-            sgx_status_t::SGX_SUCCESS => Ok(SgxMonotonicCounter{
-                                            counter_uuid,
-                                            initflag: Cell::new(true),
-                                         }),
-            _ => Err(ret),
-        }
+        SgxMonotonicCounter::new_with_source(rsgx_get_monotonic_counter_source(),
+                                             SGX_MC_POLICY_SIGNER,
+                                             &SGX_MC_DEFAULT_OWNER_ATTRIBUTE_MASK,
+                                             counter_value)
     }
 
     ///
@@ -152,6 +299,9 @@ impl SgxMonotonicCounter {
     ///
     /// Call new_ex to create a monotonic counter with the given owner_policy and owner_attribute_mask.
     ///
+    /// The counter is created in the source returned by `rsgx_get_monotonic_counter_source`, which is
+    /// the Platform Services enclave unless another source has been set.
+    ///
     /// The caller should call rsgx_create_pse_session to establish a session with the platform service enclave
     /// before calling this API.
     ///
@@ -243,16 +393,27 @@ impl SgxMonotonicCounter {
     ///
     pub fn new_ex(owner_policy: u16, owner_attribute_mask: &sgx_attributes_t, counter_value: &mut u32) -> SgxResult<Self> {
 
-        let mut counter_uuid = sgx_mc_uuid_t::default();
-        let ret = rsgx_create_monotonic_counter_ex(owner_policy, owner_attribute_mask, &mut counter_uuid, counter_value);
+        SgxMonotonicCounter::new_with_source(rsgx_get_monotonic_counter_source(),
+                                             owner_policy,
+                                             owner_attribute_mask,
+                                             counter_value)
+    }
 
-        match ret {
-            sgx_status_t::SGX_SUCCESS => Ok(SgxMonotonicCounter{
-                                            counter_uuid,
-                                            initflag: Cell::new(true),
-                                         }),
-            _ => Err(ret),
-        }
+    ///
+    /// Create a monotonic counter in the given counter source. See `new_ex`.
+    ///
+    pub fn new_with_source(source: &'static dyn MonotonicCounterSource,
+                           owner_policy: u16,
+                           owner_attribute_mask: &sgx_attributes_t,
+                           counter_value: &mut u32) -> SgxResult<Self> {
+
+        let (counter_uuid, value) = source.create_counter(owner_policy, owner_attribute_mask)?;
+        *counter_value = value;
+        Ok(SgxMonotonicCounter {
+            counter_uuid,
+            initflag: Cell::new(true),
+            source,
+        })
     }
 
     ///
@@ -331,13 +492,9 @@ impl SgxMonotonicCounter {
             return Err(sgx_status_t::SGX_ERROR_MC_NOT_FOUND);
         }
 
-        let ret = rsgx_destroy_monotonic_counter(&self.counter_uuid);
-        if ret == sgx_status_t::SGX_SUCCESS {
-            self.initflag.set(false);
-            Ok(())
-        } else {
-            Err(ret)
-        }
+        self.source.destroy_counter(&self.counter_uuid)?;
+        self.initflag.set(false);
+        Ok(())
     }
 
     ///
@@ -416,12 +573,7 @@ impl SgxMonotonicCounter {
             return Err(sgx_status_t::SGX_ERROR_MC_NOT_FOUND);
         }
 
-        let mut counter_value: u32 = 0;
-        let ret = rsgx_increment_monotonic_counter(&self.counter_uuid, &mut counter_value);
-         match ret {
-            sgx_status_t::SGX_SUCCESS => Ok(counter_value),
-            _ => Err(ret),
-        }
+        self.source.increment_counter(&self.counter_uuid)
     }
 
     ///
@@ -491,12 +643,7 @@ impl SgxMonotonicCounter {
             return Err(sgx_status_t::SGX_ERROR_MC_NOT_FOUND);
         }
 
-        let mut counter_value: u32 = 0;
-        let ret = rsgx_read_monotonic_counter(&self.counter_uuid, &mut counter_value);
-        match ret {
-            sgx_status_t::SGX_SUCCESS => Ok(counter_value),
-            _ => Err(ret),
-        }
+        self.source.read_counter(&self.counter_uuid)
     }
 }
 
@@ -509,6 +656,7 @@ impl Drop for SgxMonotonicCounter {
     }
 }
 
+#[cfg(feature = "pse")]
 fn rsgx_create_monotonic_counter_ex(owner_policy: u16,
                                     owner_attribute_mask: &sgx_attributes_t,
                                     counter_uuid: &mut sgx_mc_uuid_t,
@@ -522,13 +670,7 @@ fn rsgx_create_monotonic_counter_ex(owner_policy: u16,
     }
 }
 
-fn rsgx_create_monotonic_counter(counter_uuid: &mut sgx_mc_uuid_t, counter_value: &mut u32) -> sgx_status_t {
-
-    unsafe {
-        sgx_create_monotonic_counter(counter_uuid as * mut sgx_mc_uuid_t, counter_value as * mut u32)
-    }
-}
-
+#[cfg(feature = "pse")]
 fn rsgx_destroy_monotonic_counter(counter_uuid: &sgx_mc_uuid_t) -> sgx_status_t {
 
     unsafe {
@@ -536,6 +678,7 @@ fn rsgx_destroy_monotonic_counter(counter_uuid: &sgx_mc_uuid_t) -> sgx_status_t
     }
 }
 
+#[cfg(feature = "pse")]
 fn rsgx_increment_monotonic_counter(counter_uuid: &sgx_mc_uuid_t, counter_value: &mut u32) -> sgx_status_t {
 
     unsafe {
@@ -543,6 +686,7 @@ fn rsgx_increment_monotonic_counter(counter_uuid: &sgx_mc_uuid_t, counter_value:
     }
 }
 
+#[cfg(feature = "pse")]
 fn rsgx_read_monotonic_counter(counter_uuid: &sgx_mc_uuid_t, counter_value: &mut u32) -> sgx_status_t {
 
     unsafe {
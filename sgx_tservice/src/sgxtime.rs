@@ -18,6 +18,84 @@
 use sgx_types::*;
 use core::cmp::Ordering;
 use core::fmt;
+use crate::source::SourceCell;
+
+/// A provider of trusted time: a timestamp in seconds and a nonce that identifies
+/// the time source. Timestamps are only comparable if their nonces are equal.
+pub trait TrustedTimeSource {
+    fn get_trusted_time(&self) -> SgxResult<(sgx_time_t, sgx_time_source_nonce_t)>;
+}
+
+/// Trusted time from the Platform Services enclave. A PSE session must be open,
+/// see `rsgx_create_pse_session`.
+#[cfg(feature = "pse")]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PseTimeSource;
+
+#[cfg(feature = "pse")]
+impl TrustedTimeSource for PseTimeSource {
+    fn get_trusted_time(&self) -> SgxResult<(sgx_time_t, sgx_time_source_nonce_t)> {
+
+        let mut timestamp: sgx_time_t = 0;
+        let mut source_nonce: sgx_time_source_nonce_t = Default::default();
+
+        let ret = rsgx_get_trusted_time(&mut timestamp, &mut source_nonce);
+        match ret {
+            sgx_status_t::SGX_SUCCESS => Ok((timestamp, source_nonce)),
+            _ => Err(ret),
+        }
+    }
+}
+
+#[cfg(not(feature = "pse"))]
+struct UnavailableTimeSource;
+
+#[cfg(not(feature = "pse"))]
+impl TrustedTimeSource for UnavailableTimeSource {
+    fn get_trusted_time(&self) -> SgxResult<(sgx_time_t, sgx_time_source_nonce_t)> {
+        Err(sgx_status_t::SGX_ERROR_SERVICE_UNAVAILABLE)
+    }
+}
+
+static TIME_SOURCE: SourceCell<dyn TrustedTimeSource + Sync> = SourceCell::new();
+
+///
+/// Set the time source used by `SgxTime::now`.
+///
+/// The source can be set once. Until it is set, `SgxTime::now` uses the Platform
+/// Services enclave if the `pse` feature is enabled, and fails with
+/// **SGX_ERROR_SERVICE_UNAVAILABLE** otherwise.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_STATE**
+///
+/// A time source has already been set.
+///
+pub fn rsgx_set_trusted_time_source(source: &'static (dyn TrustedTimeSource + Sync)) -> SgxError {
+    if TIME_SOURCE.set(source) {
+        Ok(())
+    } else {
+        Err(sgx_status_t::SGX_ERROR_INVALID_STATE)
+    }
+}
+
+///
+/// Get the time source used by `SgxTime::now`.
+///
+pub fn rsgx_get_trusted_time_source() -> &'static dyn TrustedTimeSource {
+    if let Some(source) = TIME_SOURCE.get() {
+        return source;
+    }
+    #[cfg(feature = "pse")]
+    {
+        &PseTimeSource
+    }
+    #[cfg(not(feature = "pse"))]
+    {
+        &UnavailableTimeSource
+    }
+}
 
 /// timestamp contains time in seconds and source_nonce contains nonce associate with the time.
 #[derive(Copy, Clone, Debug, Default)]
@@ -78,14 +156,14 @@ impl SgxTime {
 
     pub fn now() -> Result<SgxTime, SgxTimeError> {
 
-        let mut timestamp: sgx_time_t = 0;
-        let mut source_nonce: sgx_time_source_nonce_t = Default::default();
+        SgxTime::now_from(rsgx_get_trusted_time_source())
+    }
 
-        let ret = rsgx_get_trusted_time(&mut timestamp, &mut source_nonce);
-        match ret {
-            sgx_status_t::SGX_SUCCESS => Ok(SgxTime{timestamp, source_nonce}),
-            _ => Err(SgxTimeError::SgxStatus(ret)),
-        }
+    pub fn now_from<S: TrustedTimeSource + ?Sized>(source: &S) -> Result<SgxTime, SgxTimeError> {
+
+        source.get_trusted_time()
+              .map(|(timestamp, source_nonce)| SgxTime{timestamp, source_nonce})
+              .map_err(SgxTimeError::SgxStatus)
     }
 
     pub fn duration_since(&self, earlier: &SgxTime) -> Result<Duration, SgxTimeError> {
@@ -191,6 +269,7 @@ impl SgxTime {
 ///
 /// Indicates an unexpected error occurs.
 ///
+#[cfg(feature = "pse")]
 fn rsgx_get_trusted_time(current_time: &mut sgx_time_t,
                          time_source_nonce: &mut sgx_time_source_nonce_t) -> sgx_status_t {
 
@@ -0,0 +1,59 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+const UNSET: usize = 0;
+const SETTING: usize = 1;
+const SET: usize = 2;
+
+// A process-wide provider that can be registered once, before or after it is
+// first looked up.
+pub(crate) struct SourceCell<T: ?Sized + 'static> {
+    state: AtomicUsize,
+    source: UnsafeCell<Option<&'static T>>,
+}
+
+unsafe impl<T: ?Sized + Sync + 'static> Sync for SourceCell<T> {}
+
+impl<T: ?Sized + 'static> SourceCell<T> {
+    pub(crate) const fn new() -> Self {
+        SourceCell {
+            state: AtomicUsize::new(UNSET),
+            source: UnsafeCell::new(None),
+        }
+    }
+
+    pub(crate) fn set(&self, source: &'static T) -> bool {
+        if self.state.compare_and_swap(UNSET, SETTING, Ordering::Acquire) != UNSET {
+            return false;
+        }
+        unsafe { *self.source.get() = Some(source) };
+        self.state.store(SET, Ordering::Release);
+        true
+    }
+
+    pub(crate) fn get(&self) -> Option<&'static T> {
+        if self.state.load(Ordering::Acquire) == SET {
+            unsafe { *self.source.get() }
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,374 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//! A single-threaded executor for futures.
+//!
+//! `LocalExecutor` runs any number of tasks on the enclave thread that calls
+//! it, so one TCS can serve many connections. Tasks waiting for a socket are
+//! parked in a reactor, which waits for all of them with a single `poll`
+//! OCALL once no task can make progress.
+//!
+//! Wakers may be used from other enclave threads. Such a wakeup is noticed
+//! immediately if the executor is idle, but not while it is blocked in the
+//! reactor waiting for sockets.
+
+use core::cell::RefCell;
+use core::future::Future;
+use core::mem::ManuallyDrop;
+use core::pin::Pin;
+use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+use alloc_crate::boxed::Box;
+use alloc_crate::collections::VecDeque;
+use alloc_crate::rc::Rc;
+use alloc_crate::vec::Vec;
+use crate::sync::{Arc, SgxMutex, SgxCondvar};
+
+type LocalTask = Pin<Box<dyn Future<Output = ()>>>;
+
+// The task id used for the future passed to `block_on`.
+const MAIN_TASK: usize = usize::max_value();
+
+struct ReadyQueue {
+    ids: SgxMutex<VecDeque<usize>>,
+    cond: SgxCondvar,
+}
+
+impl ReadyQueue {
+    fn push(&self, id: usize) {
+        if let Ok(mut ids) = self.ids.lock() {
+            ids.push_back(id);
+        }
+        self.cond.signal();
+    }
+
+    fn take(&self) -> VecDeque<usize> {
+        match self.ids.lock() {
+            Ok(mut ids) => ids.split_off(0),
+            Err(_) => VecDeque::new(),
+        }
+    }
+
+    // Puts ids taken but not yet processed back at the front of the queue.
+    fn requeue(&self, mut rest: VecDeque<usize>) {
+        if rest.is_empty() {
+            return;
+        }
+        if let Ok(mut ids) = self.ids.lock() {
+            rest.append(&mut ids);
+            *ids = rest;
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.ids.lock().map(|ids| ids.is_empty()).unwrap_or(true)
+    }
+
+    fn wait(&self) {
+        if let Ok(mut ids) = self.ids.lock() {
+            while ids.is_empty() {
+                ids = match self.cond.wait(ids) {
+                    Ok(ids) => ids,
+                    Err(_) => return,
+                };
+            }
+        }
+    }
+}
+
+struct TaskWaker {
+    id: usize,
+    queue: Arc<ReadyQueue>,
+}
+
+static WAKER_VTABLE: RawWakerVTable = RawWakerVTable::new(clone_waker, wake, wake_by_ref, drop_waker);
+
+fn new_waker(id: usize, queue: &Arc<ReadyQueue>) -> Waker {
+    let waker = Arc::new(TaskWaker { id, queue: queue.clone() });
+    unsafe { Waker::from_raw(RawWaker::new(Arc::into_raw(waker) as *const (), &WAKER_VTABLE)) }
+}
+
+unsafe fn clone_waker(data: *const ()) -> RawWaker {
+    let waker = ManuallyDrop::new(Arc::from_raw(data as *const TaskWaker));
+    let cloned: Arc<TaskWaker> = Arc::clone(&waker);
+    RawWaker::new(Arc::into_raw(cloned) as *const (), &WAKER_VTABLE)
+}
+
+unsafe fn wake(data: *const ()) {
+    let waker = Arc::from_raw(data as *const TaskWaker);
+    waker.queue.push(waker.id);
+}
+
+unsafe fn wake_by_ref(data: *const ()) {
+    let waker = ManuallyDrop::new(Arc::from_raw(data as *const TaskWaker));
+    waker.queue.push(waker.id);
+}
+
+unsafe fn drop_waker(data: *const ()) {
+    drop(Arc::from_raw(data as *const TaskWaker));
+}
+
+struct Inner {
+    tasks: RefCell<Vec<Option<LocalTask>>>,
+    spawned: RefCell<Vec<LocalTask>>,
+    queue: Arc<ReadyQueue>,
+}
+
+/// Runs futures on the current thread.
+///
+/// # Examples
+///
+/// ```
+/// use std::executor::LocalExecutor;
+///
+/// let executor = LocalExecutor::new();
+/// let spawner = executor.spawner();
+/// executor.spawn(async move {
+///     spawner.spawn(async { /* ... */ });
+/// });
+/// executor.run();
+/// ```
+pub struct LocalExecutor {
+    inner: Rc<Inner>,
+}
+
+/// A handle for spawning tasks onto a `LocalExecutor` from inside its tasks.
+#[derive(Clone)]
+pub struct LocalSpawner {
+    inner: Rc<Inner>,
+}
+
+impl LocalExecutor {
+    pub fn new() -> LocalExecutor {
+        LocalExecutor {
+            inner: Rc::new(Inner {
+                tasks: RefCell::new(Vec::new()),
+                spawned: RefCell::new(Vec::new()),
+                queue: Arc::new(ReadyQueue {
+                    ids: SgxMutex::new(VecDeque::new()),
+                    cond: SgxCondvar::new(),
+                }),
+            }),
+        }
+    }
+
+    /// Returns a handle that spawns tasks onto this executor.
+    pub fn spawner(&self) -> LocalSpawner {
+        LocalSpawner { inner: self.inner.clone() }
+    }
+
+    /// Spawns a task. It starts running on the next call to `run` or `block_on`.
+    pub fn spawn<F: Future<Output = ()> + 'static>(&self, future: F) {
+        self.inner.spawn(Box::pin(future));
+    }
+
+    /// Runs spawned tasks until all of them have completed.
+    pub fn run(&self) {
+        self.inner.run::<LocalTask>(None);
+    }
+
+    /// Runs `future` to completion, running spawned tasks alongside it.
+    ///
+    /// Spawned tasks that have not completed when `future` does stay in the
+    /// executor and continue on the next call to `run` or `block_on`.
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        let mut future = Box::pin(future);
+        match self.inner.run(Some(future.as_mut())) {
+            Some(output) => output,
+            None => unreachable!(),
+        }
+    }
+}
+
+impl Default for LocalExecutor {
+    fn default() -> LocalExecutor {
+        LocalExecutor::new()
+    }
+}
+
+impl LocalSpawner {
+    /// Spawns a task onto the executor.
+    pub fn spawn<F: Future<Output = ()> + 'static>(&self, future: F) {
+        self.inner.spawn(Box::pin(future));
+    }
+}
+
+impl Inner {
+    fn spawn(&self, task: LocalTask) {
+        self.spawned.borrow_mut().push(task);
+    }
+
+    // Moves newly spawned tasks into free slots and schedules them.
+    fn adopt_spawned(&self) {
+        let spawned: Vec<LocalTask> = self.spawned.borrow_mut().drain(..).collect();
+        let mut tasks = self.tasks.borrow_mut();
+        for task in spawned {
+            let id = match tasks.iter().position(|t| t.is_none()) {
+                Some(id) => {
+                    tasks[id] = Some(task);
+                    id
+                },
+                None => {
+                    tasks.push(Some(task));
+                    tasks.len() - 1
+                },
+            };
+            self.queue.push(id);
+        }
+    }
+
+    fn poll_task(&self, id: usize) {
+        let task = match self.tasks.borrow_mut().get_mut(id) {
+            Some(slot) => slot.take(),
+            None => None,
+        };
+        if let Some(mut task) = task {
+            let waker = new_waker(id, &self.queue);
+            let mut cx = Context::from_waker(&waker);
+            if task.as_mut().poll(&mut cx).is_pending() {
+                self.tasks.borrow_mut()[id] = Some(task);
+            }
+        }
+    }
+
+    fn has_tasks(&self) -> bool {
+        !self.spawned.borrow().is_empty() || self.tasks.borrow().iter().any(|t| t.is_some())
+    }
+
+    fn run<F: Future>(&self, mut main: Option<Pin<&mut F>>) -> Option<F::Output> {
+        if main.is_some() {
+            self.queue.push(MAIN_TASK);
+        }
+        loop {
+            self.adopt_spawned();
+            let mut batch = self.queue.take();
+            while let Some(id) = batch.pop_front() {
+                if id == MAIN_TASK {
+                    if let Some(ref mut future) = main {
+                        let waker = new_waker(MAIN_TASK, &self.queue);
+                        let mut cx = Context::from_waker(&waker);
+                        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                            // Tasks woken in this batch keep their wakeup
+                            // for the next call to `run` or `block_on`.
+                            self.queue.requeue(batch);
+                            self.adopt_spawned();
+                            return Some(output);
+                        }
+                    }
+                } else {
+                    self.poll_task(id);
+                }
+            }
+            self.adopt_spawned();
+
+            if main.is_none() && !self.has_tasks() {
+                return None;
+            }
+            if self.queue.is_empty() {
+                if reactor::is_idle() {
+                    self.queue.wait();
+                } else {
+                    reactor::wait();
+                }
+            }
+        }
+    }
+}
+
+/// Runs a future to completion on the current thread.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    LocalExecutor::new().block_on(future)
+}
+
+#[cfg(feature = "net")]
+pub(crate) use self::reactor::{poll_io, READABLE, WRITABLE};
+
+#[cfg(feature = "net")]
+mod reactor {
+    use core::cell::RefCell;
+    use core::task::{Context, Poll, Waker};
+    use alloc_crate::vec::Vec;
+    use sgx_trts::libc::{c_int, c_short};
+    use crate::io;
+
+    mod libc {
+        pub use sgx_trts::libc::*;
+        pub use sgx_trts::libc::ocall::poll;
+    }
+
+    pub const READABLE: c_short = libc::POLLIN;
+    pub const WRITABLE: c_short = libc::POLLOUT;
+
+    thread_local! {
+        static WAITERS: RefCell<Vec<(c_int, c_short, Waker)>> = RefCell::new(Vec::new());
+    }
+
+    /// Runs a non-blocking socket operation, parking the task on `fd` if it would block.
+    pub fn poll_io<T, F>(fd: c_int, events: c_short, cx: &mut Context<'_>, mut op: F) -> Poll<io::Result<T>>
+        where F: FnMut() -> io::Result<T>
+    {
+        loop {
+            match op() {
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {},
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    WAITERS.with(|waiters| waiters.borrow_mut().push((fd, events, cx.waker().clone())));
+                    return Poll::Pending;
+                },
+                result => return Poll::Ready(result),
+            }
+        }
+    }
+
+    pub fn is_idle() -> bool {
+        WAITERS.with(|waiters| waiters.borrow().is_empty())
+    }
+
+    // Waits until at least one parked socket is ready and wakes its tasks.
+    // If the host reports an error, every task is woken to retry its
+    // operation and see the error itself.
+    pub fn wait() {
+        let waiters = WAITERS.with(|waiters| waiters.replace(Vec::new()));
+        let mut fds: Vec<libc::pollfd> = waiters.iter()
+            .map(|&(fd, events, _)| libc::pollfd { fd, events, revents: 0 })
+            .collect();
+        let ready = loop {
+            match unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, -1) } {
+                -1 if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted => {},
+                -1 => break None,
+                _ => break Some(fds),
+            }
+        };
+
+        let mut parked = Vec::new();
+        for (i, (fd, events, waker)) in waiters.into_iter().enumerate() {
+            match ready {
+                Some(ref fds) if fds[i].revents == 0 => parked.push((fd, events, waker)),
+                _ => waker.wake(),
+            }
+        }
+        WAITERS.with(|waiters| waiters.borrow_mut().extend(parked));
+    }
+}
+
+#[cfg(not(feature = "net"))]
+mod reactor {
+    pub fn is_idle() -> bool {
+        true
+    }
+
+    pub fn wait() {}
+}
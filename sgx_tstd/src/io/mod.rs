@@ -32,6 +32,8 @@ pub use self::cursor::Cursor;
 pub use self::error::{Result, Error, ErrorKind};
 pub use self::util::{copy, sink, Sink, empty, Empty, repeat, Repeat};
 pub use self::lazy::{Lazy};
+pub use self::async_io::{AsyncRead, AsyncWrite, AsyncReadExt, AsyncWriteExt};
+pub use self::async_io::{ReadFuture, ReadExactFuture, WriteFuture, WriteAllFuture, FlushFuture, CloseFuture};
 
 pub use sys::os::{errno, set_errno, error_string};
 #[cfg(feature = "stdio")]
@@ -43,6 +45,7 @@ pub use self::stdio::{_print, _eprint};
 //pub use self::stdio::{set_panic, set_print};
 
 pub mod prelude;
+mod async_io;
 mod buffered;
 mod cursor;
 mod error;
@@ -134,6 +134,7 @@ pub use sgx_trts::{global_ctors_object, global_dtors_object, is_x86_feature_dete
 extern crate sgx_tprotected_fs;
 extern crate sgx_libc;
 extern crate sgx_tseal;
+extern crate sgx_tcrypto;
 
 // The standard macros that are not built-in to the compiler.
 #[macro_use]
@@ -217,6 +218,7 @@ pub mod task {
 }
 
 pub mod future;
+pub mod executor;
 
 // Platform-abstraction modules
 #[macro_use]
@@ -20,6 +20,13 @@
 //! This module provides networking functionality for the Transmission Control and User
 //! Datagram Protocols, as well as types for IP and socket addresses.
 //!
+//! With the `net_switchless` feature, [`TcpStream`], [`TcpListener`] and
+//! [`UdpSocket`] issue their socket calls through the switchless OCALLs of
+//! `sgx_net_switchless.edl`, which the enclave EDL must then import. The calls
+//! only avoid an enclave exit when the enclave was created with untrusted
+//! workers (`SgxEnclave::create_with_workers`); otherwise the SGX runtime
+//! falls back to regular OCALLs.
+//!
 
 use crate::io::{self, Error, ErrorKind};
 
@@ -31,6 +38,8 @@ pub use self::tcp::TcpStream;
 pub use self::tcp::TcpListener;
 #[cfg(feature = "net")]
 pub use self::udp::UdpSocket;
+#[cfg(feature = "net")]
+pub use self::poll::{Poll, Registry, Events, Event, Token, Interest, Source, SourceFd};
 pub use self::parser::AddrParseError;
 
 mod ip;
@@ -40,6 +49,8 @@ mod parser;
 mod tcp;
 #[cfg(feature = "net")]
 mod udp;
+#[cfg(feature = "net")]
+mod poll;
 
 /// Possible values which can be passed to the [`shutdown`] method of
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
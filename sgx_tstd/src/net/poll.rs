@@ -0,0 +1,428 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//! Readiness polling for non-blocking sockets, built on epoll.
+//!
+//! The API follows the shape of mio: sources are registered with a `Registry`
+//! under a `Token`, and `Poll::poll` fills `Events` with the tokens of the
+//! sources that became ready. Registration is edge-triggered, so a source
+//! should be read or written until it returns `WouldBlock` before waiting again.
+//!
+//! `epoll_wait` runs on the untrusted host, which could report any number of
+//! events for any file descriptor. `Poll::poll` rejects counts larger than the
+//! buffer it passed, and tokens never leave the enclave: the host only sees
+//! file descriptors, which are mapped back to tokens through the registry.
+//! Events for descriptors that are not registered, and event bits that were
+//! not asked for, are dropped.
+
+use sgx_trts::libc::c_int;
+use core::fmt;
+use core::ops::BitOr;
+use core::slice;
+use alloc_crate::collections::BTreeMap;
+use alloc_crate::vec::Vec;
+use crate::io;
+use crate::net::{TcpListener, TcpStream, UdpSocket};
+use crate::os::unix::io::{AsRawFd, RawFd};
+use crate::sync::SgxMutex;
+use crate::sys::cvt;
+use crate::sys::fd::FileDesc;
+use crate::time::Duration;
+
+mod libc {
+    pub use sgx_trts::libc::*;
+    pub use sgx_trts::libc::ocall::{epoll_create1, epoll_ctl, epoll_wait};
+}
+
+/// Associates readiness events with a registered source.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Token(pub usize);
+
+/// The readiness a source is registered for.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Interest(u8);
+
+const READABLE: u8 = 0b01;
+const WRITABLE: u8 = 0b10;
+
+impl Interest {
+    /// Interest in readable events.
+    pub const READABLE: Interest = Interest(READABLE);
+    /// Interest in writable events.
+    pub const WRITABLE: Interest = Interest(WRITABLE);
+
+    /// Combines two interests.
+    pub const fn add(self, other: Interest) -> Interest {
+        Interest(self.0 | other.0)
+    }
+
+    pub const fn is_readable(self) -> bool {
+        self.0 & READABLE != 0
+    }
+
+    pub const fn is_writable(self) -> bool {
+        self.0 & WRITABLE != 0
+    }
+
+    fn epoll_events(self) -> u32 {
+        let mut events = libc::EPOLLET as u32;
+        if self.is_readable() {
+            events |= (libc::EPOLLIN | libc::EPOLLRDHUP | libc::EPOLLPRI) as u32;
+        }
+        if self.is_writable() {
+            events |= libc::EPOLLOUT as u32;
+        }
+        events
+    }
+}
+
+impl BitOr for Interest {
+    type Output = Interest;
+
+    fn bitor(self, other: Interest) -> Interest {
+        self.add(other)
+    }
+}
+
+impl fmt::Debug for Interest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.is_readable(), self.is_writable()) {
+            (true, true) => f.write_str("READABLE | WRITABLE"),
+            (true, false) => f.write_str("READABLE"),
+            _ => f.write_str("WRITABLE"),
+        }
+    }
+}
+
+/// An object that can be registered with a `Registry`.
+pub trait Source {
+    fn register(&mut self, registry: &Registry, token: Token, interests: Interest) -> io::Result<()>;
+    fn reregister(&mut self, registry: &Registry, token: Token, interests: Interest) -> io::Result<()>;
+    fn deregister(&mut self, registry: &Registry) -> io::Result<()>;
+}
+
+/// Registers a raw file descriptor, for sources that have no `Source` implementation.
+#[derive(Debug)]
+pub struct SourceFd<'a>(pub &'a RawFd);
+
+impl Source for SourceFd<'_> {
+    fn register(&mut self, registry: &Registry, token: Token, interests: Interest) -> io::Result<()> {
+        registry.register_fd(*self.0, token, interests)
+    }
+
+    fn reregister(&mut self, registry: &Registry, token: Token, interests: Interest) -> io::Result<()> {
+        registry.reregister_fd(*self.0, token, interests)
+    }
+
+    fn deregister(&mut self, registry: &Registry) -> io::Result<()> {
+        registry.deregister_fd(*self.0)
+    }
+}
+
+macro_rules! impl_source {
+    ($($t:ty),*) => {$(
+        impl Source for $t {
+            fn register(&mut self, registry: &Registry, token: Token, interests: Interest) -> io::Result<()> {
+                registry.register_fd(self.as_raw_fd(), token, interests)
+            }
+
+            fn reregister(&mut self, registry: &Registry, token: Token, interests: Interest) -> io::Result<()> {
+                registry.reregister_fd(self.as_raw_fd(), token, interests)
+            }
+
+            fn deregister(&mut self, registry: &Registry) -> io::Result<()> {
+                registry.deregister_fd(self.as_raw_fd())
+            }
+        }
+    )*}
+}
+
+impl_source!(TcpStream, TcpListener, UdpSocket);
+
+/// Registers sources with a `Poll`.
+pub struct Registry {
+    epfd: FileDesc,
+    sources: SgxMutex<BTreeMap<RawFd, (Token, Interest)>>,
+}
+
+impl Registry {
+    /// Registers `source` for the readiness events in `interests`, reported under `token`.
+    pub fn register<S: Source + ?Sized>(&self, source: &mut S, token: Token, interests: Interest) -> io::Result<()> {
+        source.register(self, token, interests)
+    }
+
+    /// Changes the token or interests of a registered source.
+    pub fn reregister<S: Source + ?Sized>(&self, source: &mut S, token: Token, interests: Interest) -> io::Result<()> {
+        source.reregister(self, token, interests)
+    }
+
+    /// Removes a source. Sources must be deregistered before they are closed.
+    pub fn deregister<S: Source + ?Sized>(&self, source: &mut S) -> io::Result<()> {
+        source.deregister(self)
+    }
+
+    fn lock(&self) -> io::Result<crate::sync::SgxMutexGuard<'_, BTreeMap<RawFd, (Token, Interest)>>> {
+        self.sources.lock().map_err(|_| io::Error::new(io::ErrorKind::Other, "poll registry lock poisoned"))
+    }
+
+    fn ctl(&self, op: c_int, fd: RawFd, interests: Option<Interest>) -> io::Result<()> {
+        let mut event = libc::epoll_event {
+            events: interests.map_or(0, Interest::epoll_events),
+            u64: fd as u64,
+        };
+        cvt(unsafe { libc::epoll_ctl(self.epfd.raw(), op, fd, &mut event) }).map(|_| ())
+    }
+
+    fn register_fd(&self, fd: RawFd, token: Token, interests: Interest) -> io::Result<()> {
+        let mut sources = self.lock()?;
+        if sources.contains_key(&fd) {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, "source is already registered"));
+        }
+        self.ctl(libc::EPOLL_CTL_ADD, fd, Some(interests))?;
+        sources.insert(fd, (token, interests));
+        Ok(())
+    }
+
+    fn reregister_fd(&self, fd: RawFd, token: Token, interests: Interest) -> io::Result<()> {
+        let mut sources = self.lock()?;
+        if !sources.contains_key(&fd) {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "source is not registered"));
+        }
+        self.ctl(libc::EPOLL_CTL_MOD, fd, Some(interests))?;
+        sources.insert(fd, (token, interests));
+        Ok(())
+    }
+
+    fn deregister_fd(&self, fd: RawFd) -> io::Result<()> {
+        let mut sources = self.lock()?;
+        if sources.remove(&fd).is_none() {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "source is not registered"));
+        }
+        // The source is forgotten even if the host fails to remove it, so that
+        // stale events for a reused descriptor cannot be reported under its token.
+        self.ctl(libc::EPOLL_CTL_DEL, fd, None)
+    }
+}
+
+impl fmt::Debug for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Registry").field("epfd", &self.epfd.raw()).finish()
+    }
+}
+
+/// Polls registered sources for readiness events.
+///
+/// # Examples
+///
+/// ```
+/// use std::net::{Events, Interest, Poll, TcpListener, TcpStream, Token};
+/// use std::time::Duration;
+///
+/// # fn main() -> std::io::Result<()> {
+/// let mut poll = Poll::new()?;
+/// let mut events = Events::with_capacity(128);
+/// let mut listener = TcpListener::bind("127.0.0.1:0")?;
+/// listener.set_nonblocking(true)?;
+/// poll.registry().register(&mut listener, Token(0), Interest::READABLE)?;
+///
+/// let _client = TcpStream::connect(listener.local_addr()?)?;
+/// poll.poll(&mut events, Some(Duration::from_secs(1)))?;
+/// for event in events.iter() {
+///     assert_eq!(event.token(), Token(0));
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Poll {
+    registry: Registry,
+}
+
+impl Poll {
+    /// Creates a new epoll instance.
+    pub fn new() -> io::Result<Poll> {
+        let epfd = cvt(unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) })?;
+        Ok(Poll {
+            registry: Registry {
+                epfd: FileDesc::new(epfd),
+                sources: SgxMutex::new(BTreeMap::new()),
+            },
+        })
+    }
+
+    /// Returns the registry used to register sources.
+    pub fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    /// Waits for readiness events, for at most `timeout` if one is given.
+    ///
+    /// `events` is cleared first. It may come back empty when the timeout
+    /// expires, or when the host only reported events that failed validation.
+    pub fn poll(&mut self, events: &mut Events, timeout: Option<Duration>) -> io::Result<()> {
+        events.events.clear();
+        let timeout = timeout.map_or(-1, |to| {
+            let millis = to.as_secs()
+                .saturating_mul(1_000)
+                .saturating_add((u64::from(to.subsec_nanos()) + 999_999) / 1_000_000);
+            millis.min(c_int::max_value() as u64) as c_int
+        });
+
+        let capacity = events.raw.len();
+        let n = cvt(unsafe {
+            libc::epoll_wait(self.registry.epfd.raw(), events.raw.as_mut_ptr(), capacity as c_int, timeout)
+        })?;
+        let sources = self.registry.lock()?;
+        translate_events(&events.raw, n, &sources, &mut events.events)
+    }
+
+    /// Validates `n` raw events as if the host had returned them from
+    /// `epoll_wait`, and fills `events` the way `poll` does. This lets tests
+    /// exercise the checks without a misbehaving host.
+    #[doc(hidden)]
+    pub fn translate_raw_events(&self, raw: &[libc::epoll_event], n: c_int, events: &mut Events) -> io::Result<()> {
+        events.events.clear();
+        let sources = self.registry.lock()?;
+        translate_events(raw, n, &sources, &mut events.events)
+    }
+}
+
+// Maps the first `n` raw events the host reported back to tokens. `n` comes
+// from the host, as do the descriptors and event bits in `raw`.
+fn translate_events(raw: &[libc::epoll_event],
+                    n: c_int,
+                    sources: &BTreeMap<RawFd, (Token, Interest)>,
+                    events: &mut Vec<Event>) -> io::Result<()> {
+    if n < 0 || n as usize > raw.len() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "host returned more events than requested"));
+    }
+    for raw in &raw[..n as usize] {
+        let data = raw.u64;
+        if data > c_int::max_value() as u64 {
+            continue;
+        }
+        let (token, interests) = match sources.get(&(data as RawFd)) {
+            Some(&source) => source,
+            None => continue,
+        };
+        let allowed = interests.epoll_events() | (libc::EPOLLERR | libc::EPOLLHUP) as u32;
+        let bits = raw.events & allowed & !(libc::EPOLLET as u32);
+        if bits != 0 {
+            events.push(Event { token, bits });
+        }
+    }
+    Ok(())
+}
+
+/// A readiness event.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct Event {
+    token: Token,
+    bits: u32,
+}
+
+impl Event {
+    /// Returns the token of the source that became ready.
+    pub fn token(&self) -> Token {
+        self.token
+    }
+
+    pub fn is_readable(&self) -> bool {
+        self.bits & (libc::EPOLLIN | libc::EPOLLPRI) as u32 != 0
+    }
+
+    pub fn is_writable(&self) -> bool {
+        self.bits & libc::EPOLLOUT as u32 != 0
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.bits & libc::EPOLLERR as u32 != 0
+    }
+
+    pub fn is_read_closed(&self) -> bool {
+        self.bits & libc::EPOLLHUP as u32 != 0 ||
+        (self.bits & libc::EPOLLIN as u32 != 0 && self.bits & libc::EPOLLRDHUP as u32 != 0)
+    }
+
+    pub fn is_write_closed(&self) -> bool {
+        self.bits & libc::EPOLLHUP as u32 != 0 ||
+        (self.bits & libc::EPOLLOUT as u32 != 0 && self.bits & libc::EPOLLERR as u32 != 0)
+    }
+}
+
+impl fmt::Debug for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Event")
+            .field("token", &self.token)
+            .field("readable", &self.is_readable())
+            .field("writable", &self.is_writable())
+            .field("error", &self.is_error())
+            .field("read_closed", &self.is_read_closed())
+            .field("write_closed", &self.is_write_closed())
+            .finish()
+    }
+}
+
+/// A buffer of readiness events filled by `Poll::poll`.
+pub struct Events {
+    raw: Vec<libc::epoll_event>,
+    events: Vec<Event>,
+}
+
+impl Events {
+    /// Creates a buffer for up to `capacity` events per call to `Poll::poll`.
+    pub fn with_capacity(capacity: usize) -> Events {
+        let capacity = capacity.max(1).min(c_int::max_value() as usize);
+        Events {
+            raw: (0..capacity).map(|_| libc::epoll_event { events: 0, u64: 0 }).collect(),
+            events: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.raw.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, Event> {
+        self.events.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+}
+
+impl<'a> IntoIterator for &'a Events {
+    type Item = &'a Event;
+    type IntoIter = slice::Iter<'a, Event>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.events.iter()
+    }
+}
+
+impl fmt::Debug for Events {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.events.iter()).finish()
+    }
+}
+
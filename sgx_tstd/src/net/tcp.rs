@@ -19,7 +19,10 @@ use crate::io::prelude::*;
 
 use sgx_trts::libc::c_int;
 use core::fmt;
-use crate::io::{self, Initializer, IoSlice, IoSliceMut};
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use crate::io::{self, Initializer, IoSlice, IoSliceMut, AsyncRead, AsyncWrite};
+use crate::executor;
 use crate::net::{ToSocketAddrs, SocketAddr, Shutdown};
 use crate::sys_common::net as net_imp;
 use crate::sys_common::{AsInner, FromInner, IntoInner};
@@ -377,6 +380,42 @@ impl Write for TcpStream {
     fn flush(&mut self) -> io::Result<()> { Ok(()) }
 }
 
+impl TcpStream {
+    fn raw_fd(&self) -> c_int {
+        *self.0.socket().as_inner()
+    }
+}
+
+/// Asynchronous reads, for use with `std::executor`.
+///
+/// The stream should be put into non-blocking mode with `set_nonblocking`;
+/// otherwise reads block the executor.
+impl AsyncRead for TcpStream {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        let stream = self.get_mut();
+        executor::poll_io(stream.raw_fd(), executor::READABLE, cx, || stream.0.read(buf))
+    }
+}
+
+/// Asynchronous writes, for use with `std::executor`.
+///
+/// The stream should be put into non-blocking mode with `set_nonblocking`;
+/// otherwise writes block the executor.
+impl AsyncWrite for TcpStream {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let stream = self.get_mut();
+        executor::poll_io(stream.raw_fd(), executor::WRITABLE, cx, || stream.0.write(buf))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(self.0.shutdown(Shutdown::Write))
+    }
+}
+
 impl Read for &TcpStream {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> { self.0.read(buf) }
 
@@ -507,6 +546,21 @@ impl TcpListener {
         self.0.accept().map(|(a, b)| (TcpStream(a), b))
     }
 
+    /// Polls for a new incoming connection, for use with `std::executor`.
+    ///
+    /// The listener should be in non-blocking mode. The accepted stream is put
+    /// into non-blocking mode, ready for `AsyncRead` and `AsyncWrite`.
+    ///
+    pub fn poll_accept(&self, cx: &mut Context<'_>) -> Poll<io::Result<(TcpStream, SocketAddr)>> {
+        let fd = *self.0.socket().as_inner();
+        executor::poll_io(fd, executor::READABLE, cx, || self.accept()).map(|result| {
+            result.and_then(|(stream, addr)| {
+                stream.set_nonblocking(true)?;
+                Ok((stream, addr))
+            })
+        })
+    }
+
     /// Returns an iterator over the connections being received on this
     /// listener.
     ///
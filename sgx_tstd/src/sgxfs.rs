@@ -17,12 +17,24 @@
 
 //! Filesystem manipulation operations.
 
-use sgx_types::{sgx_key_128bit_t, sgx_align_key_128bit_t};
-use crate::io::{self, SeekFrom, Seek, Read, Initializer, Write};
-use crate::path::Path;
+use sgx_types::{sgx_key_128bit_t, sgx_align_key_128bit_t, sgx_status_t, SgxResult};
+use sgx_trts::trts::rsgx_read_rand;
+use sgx_tseal::{CounterBackend, SGX_COUNTER_ID_SIZE};
+use crate::io::{self, SeekFrom, Seek, Read, Initializer, Write, AsyncRead, AsyncWrite};
+use crate::path::{Path, PathBuf};
 use crate::sys::sgxfs as fs_imp;
+use crate::sys::fs as fs_sys;
+use crate::ffi::OsString;
+use core::ptr;
+use core::pin::Pin;
+use core::task::{Context, Poll};
 use crate::sys_common::{AsInnerMut, FromInner, AsInner, IntoInner};
 
+mod dir;
+mod integrity;
+pub use self::dir::{SgxDir, SgxDirFile, DirEntry, Metadata};
+pub use self::integrity::{SgxIntegrityFile, SGX_INTEGRITY_BLOCK_SIZE};
+
 /// A reference to an open file on the filesystem.
 ///
 /// An instance of a `File` can be read and/or written depending on what options
@@ -171,6 +183,26 @@ impl Write for SgxFile {
     fn flush(&mut self) -> io::Result<()> { self.inner.flush() }
 }
 
+impl AsyncRead for SgxFile {
+    fn poll_read(self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        Poll::Ready(self.get_mut().inner.read(buf))
+    }
+}
+
+impl AsyncWrite for SgxFile {
+    fn poll_write(self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Poll::Ready(self.get_mut().inner.write(buf))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(self.get_mut().inner.flush())
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(self.get_mut().inner.flush())
+    }
+}
+
 impl Seek for SgxFile {
     fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
         self.inner.seek(pos)
@@ -273,6 +305,21 @@ impl OpenOptions {
         self._open_ex(path.as_ref(), key)
     }
 
+    /// Opens an integrity-only file at `path`, authenticated with `key`.
+    ///
+    /// The data is stored in plaintext with a MAC tree beside it; see
+    /// `SgxIntegrityFile`. The options must set either `read` or `write`, and
+    /// nothing else.
+    pub fn open_integrity<P: AsRef<Path>>(&self, path: P, key: &sgx_key_128bit_t) -> io::Result<SgxIntegrityFile> {
+        if self.0.read_only() {
+            SgxIntegrityFile::open_reader(path.as_ref(), key)
+        } else if self.0.write_only() {
+            SgxIntegrityFile::create_writer(path.as_ref(), key)
+        } else {
+            Err(io::Error::new(io::ErrorKind::InvalidInput, "integrity-only files are opened either for reading or for writing"))
+        }
+    }
+
     fn _open(&self, path: &Path) -> io::Result<SgxFile> {
         let inner = fs_imp::SgxFile::open(path, &self.0)?;
         Ok(SgxFile { inner: inner })
@@ -315,3 +362,175 @@ pub fn import_auto_key<P: AsRef<Path>>(path: P, key: &sgx_key_128bit_t) -> io::R
 pub fn copy<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> io::Result<u64> {
     fs_imp::copy(from.as_ref(), to.as_ref())
 }
+
+const REKEY_BUF_SIZE: usize = 4096;
+
+/// Re-encrypts a protected file under a new key.
+///
+/// The file is streamed through a fixed-size buffer into a new protected file,
+/// which then replaces the original with a single rename. If anything fails
+/// before the rename, the original file is left untouched.
+///
+/// Protected files are bound to their file name, so the new file is written
+/// under the same name in a temporary directory next to the original,
+/// `.<name>.rekey-<random>`, which is removed afterwards. The untrusted
+/// filesystem must support renaming across these two directories.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be opened with `old_key`, for example
+/// when it was written with a different key.
+///
+pub fn rekey<P: AsRef<Path>>(path: P, old_key: &sgx_key_128bit_t, new_key: &sgx_key_128bit_t) -> io::Result<()> {
+    rekey_inner(path.as_ref(), Some(old_key), new_key)
+}
+
+/// Converts a protected file encrypted with the auto key to one encrypted with `new_key`.
+///
+/// This works like `rekey`, opening the original file with the auto key.
+///
+pub fn rekey_from_auto_key<P: AsRef<Path>>(path: P, new_key: &sgx_key_128bit_t) -> io::Result<()> {
+    rekey_inner(path.as_ref(), None, new_key)
+}
+
+fn rekey_inner(path: &Path, old_key: Option<&sgx_key_128bit_t>, new_key: &sgx_key_128bit_t) -> io::Result<()> {
+    let file_name = path.file_name().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    let mut src = match old_key {
+        Some(key) => SgxFile::open_ex(path, key)?,
+        None => SgxFile::open(path)?,
+    };
+
+    let mut suffix = [0_u8; 8];
+    rsgx_read_rand(&mut suffix).map_err(io::Error::from_sgx_error)?;
+    let mut tmp_dir_name = OsString::from(".");
+    tmp_dir_name.push(file_name);
+    tmp_dir_name.push(format!(".rekey-{:016x}", u64::from_le_bytes(suffix)));
+    let tmp_dir = parent.join(tmp_dir_name);
+    let tmp_path = tmp_dir.join(file_name);
+
+    fs_sys::DirBuilder::new().mkdir(&tmp_dir)?;
+    let result = rekey_copy(&mut src, &tmp_path, new_key).and_then(|_| {
+        drop(src);
+        fs_sys::rename(&tmp_path, path)
+    });
+    if result.is_err() {
+        let _ = fs_imp::remove(&tmp_path);
+    }
+    let _ = fs_sys::rmdir(&tmp_dir);
+    result
+}
+
+fn rekey_copy(src: &mut SgxFile, dst_path: &Path, new_key: &sgx_key_128bit_t) -> io::Result<()> {
+    let mut dst = SgxFile::create_ex(dst_path, new_key)?;
+    let mut buf = vec![0_u8; REKEY_BUF_SIZE];
+    let result = loop {
+        match src.read(&mut buf) {
+            Ok(0) => break dst.flush(),
+            Ok(n) => {
+                if let Err(e) = dst.write_all(&buf[..n]) {
+                    break Err(e);
+                }
+            },
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {},
+            Err(e) => break Err(e),
+        }
+    };
+    for b in buf.iter_mut() {
+        unsafe { ptr::write_volatile(b, 0) };
+    }
+    result
+}
+
+const COUNTER_FILE_SIZE: usize = SGX_COUNTER_ID_SIZE + 4;
+
+/// A monotonic counter kept in a protected file, for use with `sgx_tseal::RollbackProtectedStore`.
+///
+/// The file is encrypted and integrity protected with the auto key, but nothing stops
+/// the untrusted application from replacing it with an older copy. It stands in for
+/// `SgxMonotonicCounter` where the platform services are not available, such as in
+/// simulation mode, and provides no rollback protection on its own.
+pub struct SgxFileCounter {
+    path: PathBuf,
+    id: [u8; SGX_COUNTER_ID_SIZE],
+}
+
+impl SgxFileCounter {
+    /// Creates a counter file with a random id and the value zero, replacing any
+    /// existing file at `path`.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<SgxFileCounter> {
+        let mut id = [0_u8; SGX_COUNTER_ID_SIZE];
+        rsgx_read_rand(&mut id).map_err(io::Error::from_sgx_error)?;
+        let counter = SgxFileCounter { path: path.as_ref().to_path_buf(), id };
+        counter.store(0)?;
+        Ok(counter)
+    }
+
+    /// Opens an existing counter file.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<SgxFileCounter> {
+        let buf = Self::load_file(path.as_ref())?;
+        let mut id = [0_u8; SGX_COUNTER_ID_SIZE];
+        id.copy_from_slice(&buf[..SGX_COUNTER_ID_SIZE]);
+        Ok(SgxFileCounter { path: path.as_ref().to_path_buf(), id })
+    }
+
+    /// Returns the path of the counter file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn load_file(path: &Path) -> io::Result<[u8; COUNTER_FILE_SIZE]> {
+        let mut buf = [0_u8; COUNTER_FILE_SIZE];
+        let mut file = SgxFile::open(path)?;
+        file.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn load(&self) -> io::Result<u32> {
+        let buf = Self::load_file(&self.path)?;
+        if buf[..SGX_COUNTER_ID_SIZE] != self.id {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "counter file has been replaced"));
+        }
+        let mut value = [0_u8; 4];
+        value.copy_from_slice(&buf[SGX_COUNTER_ID_SIZE..]);
+        Ok(u32::from_le_bytes(value))
+    }
+
+    fn store(&self, value: u32) -> io::Result<()> {
+        let mut buf = [0_u8; COUNTER_FILE_SIZE];
+        buf[..SGX_COUNTER_ID_SIZE].copy_from_slice(&self.id);
+        buf[SGX_COUNTER_ID_SIZE..].copy_from_slice(&value.to_le_bytes());
+        let mut file = SgxFile::create(&self.path)?;
+        file.write_all(&buf)?;
+        file.flush()
+    }
+}
+
+fn counter_error(error: io::Error) -> sgx_status_t {
+    match error.raw_sgx_error() {
+        Some(status) => status,
+        None if error.kind() == io::ErrorKind::NotFound => sgx_status_t::SGX_ERROR_MC_NOT_FOUND,
+        None => sgx_status_t::SGX_ERROR_UNEXPECTED,
+    }
+}
+
+impl CounterBackend for SgxFileCounter {
+    fn counter_id(&self) -> SgxResult<[u8; SGX_COUNTER_ID_SIZE]> {
+        Ok(self.id)
+    }
+
+    fn read(&self) -> SgxResult<u32> {
+        self.load().map_err(counter_error)
+    }
+
+    fn increment(&self) -> SgxResult<u32> {
+        let value = self.load().map_err(counter_error)?;
+        let value = value.checked_add(1).ok_or(sgx_status_t::SGX_ERROR_MC_USED_UP)?;
+        self.store(value).map_err(counter_error)?;
+        Ok(value)
+    }
+}
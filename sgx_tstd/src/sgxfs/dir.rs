@@ -0,0 +1,679 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//! Encrypted directories of protected files.
+//!
+//! An `SgxDir` keeps a tree of protected files in one untrusted directory. The
+//! files are stored under random names, and their real paths, sizes and
+//! modification times are kept in an index that is itself a protected file,
+//! encrypted with the same key. The host sees how many files there are and
+//! roughly how large they are, but not their names or how they are organized.
+//!
+//! The index is written alternately to two slots, `index.0` and `index.1`, with
+//! an increasing generation number, and the newest slot that can be decrypted
+//! wins when the directory is opened. An interrupted update therefore leaves
+//! the previous index in place, and `rename` is atomic. The host can still
+//! delete the newer slot to roll the directory back to the previous generation;
+//! use `generation` together with a monotonic counter if that matters.
+//!
+//! Directories in the tree are implicit: a directory exists as long as a file
+//! below it does.
+
+use sgx_types::sgx_key_128bit_t;
+use sgx_trts::trts::rsgx_read_rand;
+use crate::io::{self, SeekFrom, Seek, Read, Initializer, Write};
+use crate::path::{Path, PathBuf, Component};
+use crate::sync::{Arc, SgxMutex};
+use crate::sys::fs as fs_imp;
+use crate::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::untrusted::time::SystemTimeEx;
+use core::ptr;
+use super::{SgxFile, OpenOptions, remove};
+
+const INDEX_MAGIC: [u8; 8] = *b"SGXFSDIR";
+const INDEX_VERSION: u16 = 1;
+const INDEX_SLOTS: [&str; 2] = ["index.0", "index.1"];
+const FILE_ID_SIZE: usize = 16;
+
+#[derive(Clone)]
+struct IndexEntry {
+    path: String,
+    id: [u8; FILE_ID_SIZE],
+    len: u64,
+    modified: Duration,
+}
+
+struct Index {
+    generation: u64,
+    entries: Vec<IndexEntry>,
+}
+
+impl Index {
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&INDEX_MAGIC);
+        buf.extend_from_slice(&INDEX_VERSION.to_le_bytes());
+        buf.extend_from_slice(&[0_u8; 2]);
+        buf.extend_from_slice(&self.generation.to_le_bytes());
+        buf.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+        for entry in &self.entries {
+            buf.extend_from_slice(&(entry.path.len() as u16).to_le_bytes());
+            buf.extend_from_slice(entry.path.as_bytes());
+            buf.extend_from_slice(&entry.id);
+            buf.extend_from_slice(&entry.len.to_le_bytes());
+            buf.extend_from_slice(&entry.modified.as_secs().to_le_bytes());
+            buf.extend_from_slice(&entry.modified.subsec_nanos().to_le_bytes());
+        }
+        buf
+    }
+
+    fn decode(buf: &[u8]) -> io::Result<Index> {
+        let mut reader = IndexReader { buf, pos: 0 };
+        if reader.take(8)? != INDEX_MAGIC || reader.u16()? != INDEX_VERSION || reader.u16()? != 0 {
+            return Err(invalid_index());
+        }
+        let generation = reader.u64()?;
+        let count = reader.u32()? as usize;
+        let mut entries = Vec::new();
+        for _ in 0..count {
+            let path_len = reader.u16()? as usize;
+            let path = String::from_utf8(reader.take(path_len)?.to_vec()).map_err(|_| invalid_index())?;
+            let mut id = [0_u8; FILE_ID_SIZE];
+            id.copy_from_slice(reader.take(FILE_ID_SIZE)?);
+            let len = reader.u64()?;
+            let secs = reader.u64()?;
+            let nanos = reader.u32()?;
+            if nanos >= 1_000_000_000 {
+                return Err(invalid_index());
+            }
+            entries.push(IndexEntry { path, id, len, modified: Duration::new(secs, nanos) });
+        }
+        if reader.pos != buf.len() {
+            return Err(invalid_index());
+        }
+        Ok(Index { generation, entries })
+    }
+
+    fn find(&self, path: &str) -> Option<usize> {
+        self.entries.iter().position(|e| e.path == path)
+    }
+
+    fn is_dir(&self, path: &str) -> bool {
+        path.is_empty() || self.entries.iter().any(|e| is_below(&e.path, path))
+    }
+}
+
+struct IndexReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> IndexReader<'a> {
+    fn take(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        if self.buf.len() - self.pos < n {
+            return Err(invalid_index());
+        }
+        let bytes = &self.buf[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        let b = self.take(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        let mut b = [0_u8; 8];
+        b.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(b))
+    }
+}
+
+fn invalid_index() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "malformed directory index")
+}
+
+fn not_found() -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, "no such file in encrypted directory")
+}
+
+// Whether `path` lies strictly below the directory `dir`.
+fn is_below(path: &str, dir: &str) -> bool {
+    dir.is_empty() || (path.len() > dir.len() && path.starts_with(dir) && path.as_bytes()[dir.len()] == b'/')
+}
+
+// Turns a relative path into the '/'-separated form used in the index.
+fn normalize(path: &Path) -> io::Result<String> {
+    let mut parts: Vec<&str> = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => {
+                let part = part.to_str().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path is not valid UTF-8"))?;
+                parts.push(part);
+            },
+            Component::CurDir => {},
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, "path must be relative and must not contain '..'")),
+        }
+    }
+    let path = parts.join("/");
+    if path.len() > u16::max_value() as usize {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "path is too long"));
+    }
+    Ok(path)
+}
+
+fn file_name(id: &[u8; FILE_ID_SIZE]) -> String {
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+    let mut name = String::with_capacity(FILE_ID_SIZE * 2);
+    for b in id.iter() {
+        name.push(HEX[(b >> 4) as usize] as char);
+        name.push(HEX[(b & 0xf) as usize] as char);
+    }
+    name
+}
+
+fn now() -> Duration {
+    <SystemTime as SystemTimeEx>::now().duration_since(UNIX_EPOCH).unwrap_or_default()
+}
+
+struct DirInner {
+    root: PathBuf,
+    key: Option<sgx_key_128bit_t>,
+    index: SgxMutex<Index>,
+}
+
+impl DirInner {
+    fn open_file(&self, id: &[u8; FILE_ID_SIZE], opts: &OpenOptions) -> io::Result<SgxFile> {
+        let path = self.root.join(file_name(id));
+        match self.key {
+            Some(ref key) => opts.open_ex(path, key),
+            None => opts.open(path),
+        }
+    }
+
+    fn read_slot(&self, slot: &str) -> io::Result<Index> {
+        let path = self.root.join(slot);
+        let mut file = match self.key {
+            Some(ref key) => SgxFile::open_ex(path, key)?,
+            None => SgxFile::open(path)?,
+        };
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf)?;
+        Index::decode(&buf)
+    }
+
+    fn load(&self) -> io::Result<Index> {
+        let mut newest: Option<Index> = None;
+        let mut error = None;
+        for slot in INDEX_SLOTS.iter() {
+            match self.read_slot(slot) {
+                Ok(index) => {
+                    if newest.as_ref().map_or(true, |n| index.generation > n.generation) {
+                        newest = Some(index);
+                    }
+                },
+                Err(e) => error = Some(e),
+            }
+        }
+        match (newest, error) {
+            (Some(index), _) => Ok(index),
+            (None, Some(e)) => Err(e),
+            (None, None) => Err(invalid_index()),
+        }
+    }
+
+    // Writes the index to the slot that does not hold the current generation.
+    fn store(&self, index: &mut Index) -> io::Result<()> {
+        let generation = index.generation.checked_add(1).ok_or_else(invalid_index)?;
+        let path = self.root.join(INDEX_SLOTS[(generation % 2) as usize]);
+        let mut file = match self.key {
+            Some(ref key) => SgxFile::create_ex(path, key)?,
+            None => SgxFile::create(path)?,
+        };
+        index.generation = generation;
+        let result = file.write_all(&index.encode()).and_then(|_| file.flush());
+        if result.is_err() {
+            index.generation -= 1;
+        }
+        result
+    }
+
+    fn lock(&self) -> io::Result<crate::sync::SgxMutexGuard<Index>> {
+        self.index.lock().map_err(|_| io::Error::new(io::ErrorKind::Other, "directory index lock poisoned"))
+    }
+}
+
+impl Drop for DirInner {
+    fn drop(&mut self) {
+        if let Some(ref mut key) = self.key {
+            for b in key.iter_mut() {
+                unsafe { ptr::write_volatile(b, 0) };
+            }
+        }
+    }
+}
+
+/// A directory of protected files with encrypted names.
+///
+/// `SgxDir` is cheap to clone; clones share the same index.
+#[derive(Clone)]
+pub struct SgxDir {
+    inner: Arc<DirInner>,
+}
+
+/// A file opened through an `SgxDir`.
+///
+/// Its size and modification time are recorded in the directory index when it
+/// is synced with `sync_all` or dropped after being written to.
+pub struct SgxDirFile {
+    file: SgxFile,
+    dir: Arc<DirInner>,
+    id: [u8; FILE_ID_SIZE],
+    dirty: bool,
+}
+
+/// Metadata of a file or directory in an `SgxDir`.
+#[derive(Clone, Debug)]
+pub struct Metadata {
+    is_dir: bool,
+    len: u64,
+    modified: Duration,
+}
+
+/// An entry returned by `SgxDir::read_dir`.
+#[derive(Clone, Debug)]
+pub struct DirEntry {
+    path: PathBuf,
+    metadata: Metadata,
+}
+
+impl SgxDir {
+    /// Creates an empty encrypted directory protected with the auto key.
+    ///
+    /// The untrusted directory at `path` is created if it does not exist. Any
+    /// index already in it is replaced, which orphans the files it listed.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<SgxDir> {
+        SgxDir::create_inner(path.as_ref(), None)
+    }
+
+    /// Creates an empty encrypted directory protected with `key`.
+    pub fn create_ex<P: AsRef<Path>>(path: P, key: &sgx_key_128bit_t) -> io::Result<SgxDir> {
+        SgxDir::create_inner(path.as_ref(), Some(*key))
+    }
+
+    /// Opens an encrypted directory protected with the auto key.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<SgxDir> {
+        SgxDir::open_inner(path.as_ref(), None)
+    }
+
+    /// Opens an encrypted directory protected with `key`.
+    pub fn open_ex<P: AsRef<Path>>(path: P, key: &sgx_key_128bit_t) -> io::Result<SgxDir> {
+        SgxDir::open_inner(path.as_ref(), Some(*key))
+    }
+
+    fn create_inner(path: &Path, key: Option<sgx_key_128bit_t>) -> io::Result<SgxDir> {
+        match fs_imp::DirBuilder::new().mkdir(path) {
+            Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => {},
+            result => result?,
+        }
+        for slot in INDEX_SLOTS.iter() {
+            if let Err(e) = remove(path.join(slot)) {
+                if e.kind() != io::ErrorKind::NotFound {
+                    return Err(e);
+                }
+            }
+        }
+        let inner = DirInner {
+            root: path.to_path_buf(),
+            key,
+            index: SgxMutex::new(Index { generation: 0, entries: Vec::new() }),
+        };
+        {
+            let mut index = inner.lock()?;
+            inner.store(&mut index)?;
+        }
+        Ok(SgxDir { inner: Arc::new(inner) })
+    }
+
+    fn open_inner(path: &Path, key: Option<sgx_key_128bit_t>) -> io::Result<SgxDir> {
+        let mut inner = DirInner {
+            root: path.to_path_buf(),
+            key,
+            index: SgxMutex::new(Index { generation: 0, entries: Vec::new() }),
+        };
+        let index = inner.load()?;
+        inner.index = SgxMutex::new(index);
+        Ok(SgxDir { inner: Arc::new(inner) })
+    }
+
+    /// Returns the untrusted directory that holds the files.
+    pub fn path(&self) -> &Path {
+        &self.inner.root
+    }
+
+    /// Returns the generation of the index, which grows with every change to the directory.
+    pub fn generation(&self) -> io::Result<u64> {
+        Ok(self.inner.lock()?.generation)
+    }
+
+    /// Opens a file in read-only mode.
+    pub fn open_file<P: AsRef<Path>>(&self, path: P) -> io::Result<SgxDirFile> {
+        self.open_file_with(path, OpenOptions::new().read(true))
+    }
+
+    /// Opens a file in write-only mode, creating it if it does not exist and
+    /// truncating it if it does.
+    pub fn create_file<P: AsRef<Path>>(&self, path: P) -> io::Result<SgxDirFile> {
+        self.open_file_with(path, OpenOptions::new().write(true))
+    }
+
+    /// Opens a file with the given options. Options that write or append create
+    /// the file if it does not exist.
+    pub fn open_file_with<P: AsRef<Path>>(&self, path: P, opts: &OpenOptions) -> io::Result<SgxDirFile> {
+        let path = normalize(path.as_ref())?;
+        let mut index = self.inner.lock()?;
+        let (id, created) = match index.find(&path) {
+            Some(i) => (index.entries[i].id, false),
+            None => {
+                if !opts.0.creates() {
+                    return Err(not_found());
+                }
+                if path.is_empty() || index.is_dir(&path) || index.entries.iter().any(|e| is_below(&path, &e.path)) {
+                    return Err(io::Error::new(io::ErrorKind::AlreadyExists, "a directory or file is in the way"));
+                }
+                let mut id = [0_u8; FILE_ID_SIZE];
+                rsgx_read_rand(&mut id).map_err(io::Error::from_sgx_error)?;
+                index.entries.push(IndexEntry { path, id, len: 0, modified: now() });
+                (id, true)
+            },
+        };
+
+        let file = match self.inner.open_file(&id, opts) {
+            Ok(file) => file,
+            Err(e) => {
+                if created {
+                    index.entries.pop();
+                }
+                return Err(e);
+            },
+        };
+        if created {
+            if let Err(e) = self.inner.store(&mut index) {
+                index.entries.pop();
+                drop(index);
+                drop(file);
+                let _ = remove(self.inner.root.join(file_name(&id)));
+                return Err(e);
+            }
+        }
+        Ok(SgxDirFile { file, dir: self.inner.clone(), id, dirty: opts.0.creates() })
+    }
+
+    /// Reads the entire contents of a file.
+    pub fn read<P: AsRef<Path>>(&self, path: P) -> io::Result<Vec<u8>> {
+        let mut file = self.open_file(path)?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Writes a slice as the entire contents of a file.
+    pub fn write<P: AsRef<Path>, C: AsRef<[u8]>>(&self, path: P, contents: C) -> io::Result<()> {
+        let mut file = self.create_file(path)?;
+        file.write_all(contents.as_ref())?;
+        file.sync_all()
+    }
+
+    /// Returns the metadata of a file or directory.
+    pub fn metadata<P: AsRef<Path>>(&self, path: P) -> io::Result<Metadata> {
+        let path = normalize(path.as_ref())?;
+        let index = self.inner.lock()?;
+        match index.find(&path) {
+            Some(i) => {
+                let entry = &index.entries[i];
+                Ok(Metadata { is_dir: false, len: entry.len, modified: entry.modified })
+            },
+            None if index.is_dir(&path) => {
+                let modified = index.entries.iter()
+                    .filter(|e| is_below(&e.path, &path))
+                    .map(|e| e.modified)
+                    .max()
+                    .unwrap_or_default();
+                Ok(Metadata { is_dir: true, len: 0, modified })
+            },
+            None => Err(not_found()),
+        }
+    }
+
+    /// Returns the entries of a directory, sorted by path. An empty path is the
+    /// root of the tree.
+    pub fn read_dir<P: AsRef<Path>>(&self, path: P) -> io::Result<Vec<DirEntry>> {
+        let dir = normalize(path.as_ref())?;
+        let index = self.inner.lock()?;
+        if !index.is_dir(&dir) {
+            return Err(not_found());
+        }
+
+        let mut entries: Vec<DirEntry> = Vec::new();
+        for entry in index.entries.iter().filter(|e| is_below(&e.path, &dir)) {
+            let rest = if dir.is_empty() { &entry.path[..] } else { &entry.path[dir.len() + 1..] };
+            match rest.find('/') {
+                None => entries.push(DirEntry {
+                    path: PathBuf::from(&entry.path),
+                    metadata: Metadata { is_dir: false, len: entry.len, modified: entry.modified },
+                }),
+                Some(end) => {
+                    let child = PathBuf::from(&entry.path[..entry.path.len() - rest.len() + end]);
+                    match entries.iter_mut().find(|e| e.path == child) {
+                        Some(e) => e.metadata.modified = e.metadata.modified.max(entry.modified),
+                        None => entries.push(DirEntry {
+                            path: child,
+                            metadata: Metadata { is_dir: true, len: 0, modified: entry.modified },
+                        }),
+                    }
+                },
+            }
+        }
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(entries)
+    }
+
+    /// Renames a file or directory, replacing the file at `to` if there is one.
+    ///
+    /// Only the index changes, so the rename is atomic: after a crash the
+    /// directory shows either the old or the new name.
+    pub fn rename<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> io::Result<()> {
+        let from = normalize(from.as_ref())?;
+        let to = normalize(to.as_ref())?;
+        if from.is_empty() || to.is_empty() || is_below(&to, &from) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "cannot move a directory into itself"));
+        }
+        if from == to {
+            return Ok(());
+        }
+
+        let mut index = self.inner.lock()?;
+        let entries = index.entries.clone();
+        let replaced = match index.find(&from) {
+            Some(i) => {
+                if index.is_dir(&to) || index.entries.iter().any(|e| is_below(&to, &e.path)) {
+                    return Err(io::Error::new(io::ErrorKind::AlreadyExists, "a directory or file is in the way"));
+                }
+                let replaced = index.find(&to).map(|j| index.entries.remove(j).id);
+                let i = index.find(&from).unwrap_or(i);
+                index.entries[i].path = to;
+                replaced
+            },
+            None if index.is_dir(&from) => {
+                if index.find(&to).is_some() || index.is_dir(&to) || index.entries.iter().any(|e| is_below(&to, &e.path)) {
+                    return Err(io::Error::new(io::ErrorKind::AlreadyExists, "a directory or file is in the way"));
+                }
+                for entry in index.entries.iter_mut().filter(|e| is_below(&e.path, &from)) {
+                    entry.path = format!("{}{}", to, &entry.path[from.len()..]);
+                }
+                None
+            },
+            None => return Err(not_found()),
+        };
+
+        if let Err(e) = self.inner.store(&mut index) {
+            index.entries = entries;
+            return Err(e);
+        }
+        drop(index);
+        if let Some(id) = replaced {
+            let _ = remove(self.inner.root.join(file_name(&id)));
+        }
+        Ok(())
+    }
+
+    /// Removes a file.
+    pub fn remove_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = normalize(path.as_ref())?;
+        let mut index = self.inner.lock()?;
+        let i = index.find(&path).ok_or_else(not_found)?;
+        let entry = index.entries.remove(i);
+        if let Err(e) = self.inner.store(&mut index) {
+            index.entries.insert(i, entry);
+            return Err(e);
+        }
+        drop(index);
+        match remove(self.inner.root.join(file_name(&entry.id))) {
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
+}
+
+impl SgxDirFile {
+    /// Flushes the file and records its size and modification time in the directory index.
+    pub fn sync_all(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        let pos = self.file.seek(SeekFrom::Current(0))?;
+        let len = self.file.seek(SeekFrom::End(0))?;
+        self.file.seek(SeekFrom::Start(pos))?;
+
+        let mut index = self.dir.lock()?;
+        if let Some(entry) = index.entries.iter_mut().find(|e| e.id == self.id) {
+            let previous = (entry.len, entry.modified);
+            entry.len = len;
+            entry.modified = now();
+            if let Err(e) = self.dir.store(&mut index) {
+                if let Some(entry) = index.entries.iter_mut().find(|e| e.id == self.id) {
+                    entry.len = previous.0;
+                    entry.modified = previous.1;
+                }
+                return Err(e);
+            }
+        }
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Returns the metadata of the file as recorded in the directory index.
+    pub fn metadata(&self) -> io::Result<Metadata> {
+        let index = self.dir.lock()?;
+        index.entries.iter()
+            .find(|e| e.id == self.id)
+            .map(|e| Metadata { is_dir: false, len: e.len, modified: e.modified })
+            .ok_or_else(not_found)
+    }
+}
+
+impl Read for SgxDirFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.read(buf)
+    }
+
+    #[inline]
+    unsafe fn initializer(&self) -> Initializer {
+        Initializer::nop()
+    }
+}
+
+impl Write for SgxDirFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.dirty = true;
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Seek for SgxDirFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.file.seek(pos)
+    }
+}
+
+impl Drop for SgxDirFile {
+    fn drop(&mut self) {
+        if self.dirty {
+            let _ = self.sync_all();
+        }
+    }
+}
+
+impl Metadata {
+    /// Returns `true` if this metadata is for a directory.
+    pub fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+
+    /// Returns `true` if this metadata is for a file.
+    pub fn is_file(&self) -> bool {
+        !self.is_dir
+    }
+
+    /// Returns the plaintext size of the file, in bytes. Directories have size zero.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns the last modification time, as read by the enclave from the
+    /// untrusted clock when the file was last synced. For a directory, this is
+    /// the latest modification time of the files below it.
+    pub fn modified(&self) -> SystemTime {
+        UNIX_EPOCH + self.modified
+    }
+}
+
+impl DirEntry {
+    /// Returns the path of the entry, relative to the root of the `SgxDir`.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the last component of the path of the entry.
+    pub fn file_name(&self) -> &str {
+        self.path.file_name().and_then(|name| name.to_str()).unwrap_or("")
+    }
+
+    /// Returns the metadata of the entry.
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+}
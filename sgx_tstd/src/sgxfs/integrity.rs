@@ -0,0 +1,483 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//! Integrity-only protected files.
+//!
+//! An integrity-only file keeps its data in plaintext, so the untrusted
+//! application can read it like any other file, and authenticates it with a
+//! Merkle tree of AES-CMACs kept in a sidecar file next to it, `<name>.mac`.
+//! The data is split into blocks of `SGX_INTEGRITY_BLOCK_SIZE` bytes; each
+//! block has a leaf MAC bound to its position, each pair of nodes a parent MAC,
+//! and the top of the tree, the file length and the file name are covered by
+//! a MAC in the sidecar header.
+//!
+//! Reads verify only the blocks they touch and the tree nodes on the way up to
+//! the first node already verified, so random access into a large file costs
+//! a few MACs per block. As with confidential protected files, the host can
+//! still substitute an older, complete version of the file and its sidecar.
+
+use sgx_types::{sgx_key_128bit_t, sgx_cmac_128bit_tag_t};
+use sgx_tcrypto::SgxCmacHandle;
+use crate::collections::BTreeMap;
+use crate::ffi::OsString;
+use crate::io::{self, SeekFrom, Seek, Read, Write};
+use crate::path::{Path, PathBuf};
+use crate::sys::fs as fs_sys;
+use core::ptr;
+use super::OpenOptions;
+
+/// The size of the blocks that are authenticated individually.
+pub const SGX_INTEGRITY_BLOCK_SIZE: usize = 4096;
+
+const MAC_FILE_MAGIC: [u8; 8] = *b"SGXFSMAC";
+const MAC_FILE_VERSION: u16 = 1;
+const MAC_SIZE: usize = 16;
+// magic, version, reserved, block size, data length, header tag
+const HEADER_SIZE: usize = 8 + 2 + 2 + 4 + 8 + MAC_SIZE;
+const HEADER_TAGGED_SIZE: usize = HEADER_SIZE - MAC_SIZE;
+
+const DOMAIN_LEAF: u8 = 0;
+const DOMAIN_NODE: u8 = 1;
+const DOMAIN_HEADER: u8 = 2;
+
+fn tampered() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "integrity check failed")
+}
+
+fn cmac(key: &sgx_key_128bit_t, parts: &[&[u8]]) -> io::Result<sgx_cmac_128bit_tag_t> {
+    let handle = SgxCmacHandle::new();
+    handle.init(key).map_err(io::Error::from_sgx_error)?;
+    for part in parts.iter().filter(|part| !part.is_empty()) {
+        handle.update_slice(part).map_err(io::Error::from_sgx_error)?;
+    }
+    handle.get_hash().map_err(io::Error::from_sgx_error)
+}
+
+fn leaf_mac(key: &sgx_key_128bit_t, index: u64, data: &[u8]) -> io::Result<sgx_cmac_128bit_tag_t> {
+    cmac(key, &[&[DOMAIN_LEAF], &index.to_le_bytes(), data])
+}
+
+fn node_mac(key: &sgx_key_128bit_t, level: u32, index: u64, children: &[u8]) -> io::Result<sgx_cmac_128bit_tag_t> {
+    cmac(key, &[&[DOMAIN_NODE], &level.to_le_bytes(), &index.to_le_bytes(), children])
+}
+
+fn header_mac(key: &sgx_key_128bit_t, header: &[u8], name: &[u8], top: &[u8]) -> io::Result<sgx_cmac_128bit_tag_t> {
+    cmac(key, &[&[DOMAIN_HEADER], header, &(name.len() as u64).to_le_bytes(), name, top])
+}
+
+// The number of nodes on each level of the tree, from the leaves up. Readers
+// compute this from the length in the header before its MAC is checked, so
+// a length that overflows is rejected here.
+fn level_sizes(len: u64) -> Option<Vec<u64>> {
+    let block_size = SGX_INTEGRITY_BLOCK_SIZE as u64;
+    let mut sizes = vec![len.checked_add(block_size - 1)? / block_size];
+    while *sizes.last().unwrap() > 1 {
+        let size = (sizes.last().unwrap() + 1) / 2;
+        sizes.push(size);
+    }
+    Some(sizes)
+}
+
+fn mac_path(path: &Path) -> io::Result<(PathBuf, OsString)> {
+    let name = path.file_name().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let mut mac_name = name.to_os_string();
+    mac_name.push(".mac");
+    Ok((path.with_file_name(&mac_name), name.to_os_string()))
+}
+
+fn read_exact_at(file: &fs_sys::File, mut buf: &mut [u8], mut offset: u64) -> io::Result<()> {
+    while !buf.is_empty() {
+        match file.read_at(buf, offset) {
+            Ok(0) => return Err(tampered()),
+            Ok(n) => {
+                let tmp = buf;
+                buf = &mut tmp[n..];
+                offset += n as u64;
+            },
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {},
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+fn write_all_at(file: &fs_sys::File, mut buf: &[u8], mut offset: u64) -> io::Result<()> {
+    while !buf.is_empty() {
+        match file.write_at(buf, offset) {
+            Ok(0) => return Err(io::Error::new(io::ErrorKind::WriteZero, "failed to write whole buffer")),
+            Ok(n) => {
+                buf = &buf[n..];
+                offset += n as u64;
+            },
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {},
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+fn open_untrusted(path: &Path, write: bool) -> io::Result<fs_sys::File> {
+    let mut opts = fs_sys::OpenOptions::new();
+    if write {
+        opts.write(true);
+        opts.create(true);
+        opts.truncate(true);
+    } else {
+        opts.read(true);
+    }
+    fs_sys::File::open(path, &opts)
+}
+
+struct Reader {
+    data: fs_sys::File,
+    macs: fs_sys::File,
+    len: u64,
+    levels: Vec<u64>,
+    verified: BTreeMap<(u32, u64), sgx_cmac_128bit_tag_t>,
+    pos: u64,
+}
+
+struct Writer {
+    data: fs_sys::File,
+    macs: fs_sys::File,
+    name: OsString,
+    block: Vec<u8>,
+    leaves: Vec<sgx_cmac_128bit_tag_t>,
+    len: u64,
+    finished: bool,
+}
+
+enum Mode {
+    Read(Reader),
+    Write(Writer),
+}
+
+/// A file whose contents are stored in plaintext and protected against tampering.
+///
+/// Files opened for writing are written sequentially; the MAC tree is written
+/// when the file is finished with `finish` or dropped. Files opened for reading
+/// support random access with `Seek` and `read_at`.
+pub struct SgxIntegrityFile {
+    key: sgx_key_128bit_t,
+    mode: Mode,
+}
+
+impl SgxIntegrityFile {
+    /// Opens an integrity-only file for reading, verifying the sidecar header.
+    pub fn open<P: AsRef<Path>>(path: P, key: &sgx_key_128bit_t) -> io::Result<SgxIntegrityFile> {
+        OpenOptions::new().read(true).open_integrity(path, key)
+    }
+
+    /// Creates an integrity-only file for writing, truncating it if it exists.
+    pub fn create<P: AsRef<Path>>(path: P, key: &sgx_key_128bit_t) -> io::Result<SgxIntegrityFile> {
+        OpenOptions::new().write(true).open_integrity(path, key)
+    }
+
+    pub(super) fn open_reader(path: &Path, key: &sgx_key_128bit_t) -> io::Result<SgxIntegrityFile> {
+        let (mac_path, name) = mac_path(path)?;
+        let data = open_untrusted(path, false)?;
+        let macs = open_untrusted(&mac_path, false)?;
+
+        let mut header = [0_u8; HEADER_SIZE];
+        read_exact_at(&macs, &mut header, 0)?;
+        if header[..8] != MAC_FILE_MAGIC ||
+           header[8..10] != MAC_FILE_VERSION.to_le_bytes() ||
+           header[12..16] != (SGX_INTEGRITY_BLOCK_SIZE as u32).to_le_bytes() {
+            return Err(tampered());
+        }
+        let mut len = [0_u8; 8];
+        len.copy_from_slice(&header[16..24]);
+        let len = u64::from_le_bytes(len);
+        let levels = level_sizes(len).ok_or_else(tampered)?;
+
+        let mut verified = BTreeMap::new();
+        let top_level = levels.len() - 1;
+        let mut top = [0_u8; MAC_SIZE];
+        let top = if levels[top_level] == 1 {
+            read_exact_at(&macs, &mut top, node_offset(&levels, top_level, 0))?;
+            verified.insert((top_level as u32, 0), top);
+            &top[..]
+        } else {
+            &[][..]
+        };
+        let tag = header_mac(key, &header[..HEADER_TAGGED_SIZE], name.to_string_lossy().as_bytes(), top)?;
+        if !consttime_eq(&tag, &header[HEADER_TAGGED_SIZE..]) {
+            return Err(tampered());
+        }
+
+        let reader = Reader { data, macs, len, levels, verified, pos: 0 };
+        Ok(SgxIntegrityFile { key: *key, mode: Mode::Read(reader) })
+    }
+
+    pub(super) fn create_writer(path: &Path, key: &sgx_key_128bit_t) -> io::Result<SgxIntegrityFile> {
+        let (mac_path, name) = mac_path(path)?;
+        let macs = open_untrusted(&mac_path, true)?;
+        let data = open_untrusted(path, true)?;
+        let writer = Writer {
+            data,
+            macs,
+            name,
+            block: Vec::with_capacity(SGX_INTEGRITY_BLOCK_SIZE),
+            leaves: Vec::new(),
+            len: 0,
+            finished: false,
+        };
+        Ok(SgxIntegrityFile { key: *key, mode: Mode::Write(writer) })
+    }
+
+    /// Returns the length of the file's data, in bytes.
+    pub fn len(&self) -> u64 {
+        match self.mode {
+            Mode::Read(ref reader) => reader.len,
+            Mode::Write(ref writer) => writer.len,
+        }
+    }
+
+    /// Returns `true` if the file holds no data.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reads verified data starting at `offset`, without moving the cursor.
+    ///
+    /// Returns the number of bytes read, which is less than `buf.len()` only at
+    /// the end of the file.
+    pub fn read_at(&mut self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        let key = self.key;
+        match self.mode {
+            Mode::Read(ref mut reader) => reader.read_at(&key, buf, offset),
+            Mode::Write(_) => Err(io::Error::new(io::ErrorKind::PermissionDenied, "file is opened for writing")),
+        }
+    }
+
+    /// Writes the remaining data and the MAC tree and syncs both files.
+    ///
+    /// Dropping a file opened for writing does the same but ignores errors.
+    pub fn finish(&mut self) -> io::Result<()> {
+        let key = self.key;
+        match self.mode {
+            Mode::Write(ref mut writer) => writer.finish(&key),
+            Mode::Read(_) => Ok(()),
+        }
+    }
+}
+
+fn node_offset(levels: &[u64], level: usize, index: u64) -> u64 {
+    let before: u64 = levels[..level].iter().sum();
+    (HEADER_SIZE as u64) + (before + index) * MAC_SIZE as u64
+}
+
+fn consttime_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b.iter()).fold(0_u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+impl Reader {
+    fn read_at(&mut self, key: &sgx_key_128bit_t, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        if offset >= self.len || buf.is_empty() {
+            return Ok(0);
+        }
+        let block_size = SGX_INTEGRITY_BLOCK_SIZE as u64;
+        let end = self.len.min(offset.saturating_add(buf.len() as u64));
+        let mut block = vec![0_u8; SGX_INTEGRITY_BLOCK_SIZE];
+        let mut pos = offset;
+        while pos < end {
+            let index = pos / block_size;
+            let start = index * block_size;
+            let block_len = (self.len - start).min(block_size) as usize;
+            read_exact_at(&self.data, &mut block[..block_len], start)?;
+            self.verify_block(key, index, &block[..block_len])?;
+
+            let from = (pos - start) as usize;
+            let to = ((end - start) as usize).min(block_len);
+            let copied = to - from;
+            let out = (pos - offset) as usize;
+            buf[out..out + copied].copy_from_slice(&block[from..to]);
+            pos += copied as u64;
+        }
+        Ok((end - offset) as usize)
+    }
+
+    // Walks from the leaf towards the top until it meets a verified node.
+    fn verify_block(&mut self, key: &sgx_key_128bit_t, index: u64, data: &[u8]) -> io::Result<()> {
+        let mut level = 0;
+        let mut index = index;
+        let mut mac = leaf_mac(key, index, data)?;
+        let mut path = Vec::new();
+        loop {
+            if let Some(known) = self.verified.get(&(level as u32, index)) {
+                if !consttime_eq(known, &mac) {
+                    return Err(tampered());
+                }
+                break;
+            }
+            if level + 1 == self.levels.len() {
+                return Err(tampered());
+            }
+
+            let left = index & !1;
+            let count = if left + 1 < self.levels[level] { 2 } else { 1 };
+            let mut children = [0_u8; 2 * MAC_SIZE];
+            read_exact_at(&self.macs, &mut children[..count * MAC_SIZE], node_offset(&self.levels, level, left))?;
+            let slot = (index - left) as usize * MAC_SIZE;
+            children[slot..slot + MAC_SIZE].copy_from_slice(&mac);
+            for i in 0..count {
+                let mut child = [0_u8; MAC_SIZE];
+                child.copy_from_slice(&children[i * MAC_SIZE..(i + 1) * MAC_SIZE]);
+                path.push(((level as u32, left + i as u64), child));
+            }
+
+            mac = node_mac(key, level as u32 + 1, left / 2, &children[..count * MAC_SIZE])?;
+            level += 1;
+            index = left / 2;
+        }
+        self.verified.extend(path);
+        Ok(())
+    }
+}
+
+impl Writer {
+    fn write_block(&mut self, key: &sgx_key_128bit_t) -> io::Result<()> {
+        let index = self.leaves.len() as u64;
+        write_all_at(&self.data, &self.block, index * SGX_INTEGRITY_BLOCK_SIZE as u64)?;
+        self.leaves.push(leaf_mac(key, index, &self.block)?);
+        self.block.clear();
+        Ok(())
+    }
+
+    fn write(&mut self, key: &sgx_key_128bit_t, buf: &[u8]) -> io::Result<usize> {
+        if self.finished {
+            return Err(io::Error::new(io::ErrorKind::Other, "file is already finished"));
+        }
+        let n = buf.len().min(SGX_INTEGRITY_BLOCK_SIZE - self.block.len());
+        self.block.extend_from_slice(&buf[..n]);
+        self.len += n as u64;
+        if self.block.len() == SGX_INTEGRITY_BLOCK_SIZE {
+            self.write_block(key)?;
+        }
+        Ok(n)
+    }
+
+    fn finish(&mut self, key: &sgx_key_128bit_t) -> io::Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        if !self.block.is_empty() {
+            self.write_block(key)?;
+        }
+
+        let levels = level_sizes(self.len)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "file is too large"))?;
+        let mut tree: Vec<u8> = self.leaves.iter().flat_map(|mac| mac.iter().cloned()).collect();
+        let mut level_start = 0;
+        for level in 1..levels.len() {
+            let below = levels[level - 1] as usize;
+            let mut next = Vec::with_capacity(levels[level] as usize * MAC_SIZE);
+            for index in 0..levels[level] as usize {
+                let left = level_start + 2 * index * MAC_SIZE;
+                let right = (level_start + below * MAC_SIZE).min(left + 2 * MAC_SIZE);
+                next.extend_from_slice(&node_mac(key, level as u32, index as u64, &tree[left..right])?);
+            }
+            level_start += below * MAC_SIZE;
+            tree.extend_from_slice(&next);
+        }
+        let top = if levels[levels.len() - 1] == 1 { &tree[tree.len() - MAC_SIZE..] } else { &[][..] };
+
+        let mut header = Vec::with_capacity(HEADER_SIZE);
+        header.extend_from_slice(&MAC_FILE_MAGIC);
+        header.extend_from_slice(&MAC_FILE_VERSION.to_le_bytes());
+        header.extend_from_slice(&[0_u8; 2]);
+        header.extend_from_slice(&(SGX_INTEGRITY_BLOCK_SIZE as u32).to_le_bytes());
+        header.extend_from_slice(&self.len.to_le_bytes());
+        let tag = header_mac(key, &header, self.name.to_string_lossy().as_bytes(), top)?;
+        header.extend_from_slice(&tag);
+
+        self.data.fsync()?;
+        write_all_at(&self.macs, &tree, HEADER_SIZE as u64)?;
+        self.macs.fsync()?;
+        write_all_at(&self.macs, &header, 0)?;
+        self.macs.fsync()?;
+        self.finished = true;
+        Ok(())
+    }
+}
+
+impl Read for SgxIntegrityFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let pos = match self.mode {
+            Mode::Read(ref reader) => reader.pos,
+            Mode::Write(_) => return Err(io::Error::new(io::ErrorKind::PermissionDenied, "file is opened for writing")),
+        };
+        let n = self.read_at(buf, pos)?;
+        if let Mode::Read(ref mut reader) = self.mode {
+            reader.pos += n as u64;
+        }
+        Ok(n)
+    }
+}
+
+impl Write for SgxIntegrityFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let key = self.key;
+        match self.mode {
+            Mode::Write(ref mut writer) => writer.write(&key, buf),
+            Mode::Read(_) => Err(io::Error::new(io::ErrorKind::PermissionDenied, "file is opened for reading")),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for SgxIntegrityFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let reader = match self.mode {
+            Mode::Read(ref mut reader) => reader,
+            Mode::Write(_) => return Err(io::Error::new(io::ErrorKind::Other, "files opened for writing cannot seek")),
+        };
+        let new_pos = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => checked_add_signed(reader.len, offset),
+            SeekFrom::Current(offset) => checked_add_signed(reader.pos, offset),
+        };
+        match new_pos {
+            Some(new_pos) => {
+                reader.pos = new_pos;
+                Ok(new_pos)
+            },
+            None => Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid seek to a negative or overflowing position")),
+        }
+    }
+}
+
+fn checked_add_signed(base: u64, offset: i64) -> Option<u64> {
+    if offset >= 0 {
+        base.checked_add(offset as u64)
+    } else {
+        base.checked_sub(offset.wrapping_neg() as u64)
+    }
+}
+
+impl Drop for SgxIntegrityFile {
+    fn drop(&mut self) {
+        let _ = self.finish();
+        for b in self.key.iter_mut() {
+            unsafe { ptr::write_volatile(b, 0) };
+        }
+    }
+}
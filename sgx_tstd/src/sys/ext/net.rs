@@ -99,11 +99,14 @@ impl SocketAddr {
         } else if addr.sun_family != libc::AF_UNIX as libc::sa_family_t {
             return Err(io::Error::new(io::ErrorKind::InvalidInput,
                                       "file descriptor did not correspond to a Unix socket"));
+        } else if (len as usize) < sun_path_offset(&addr) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "invalid Unix socket address length"));
         }
 
+        // The length reported for a truncated address exceeds the buffer.
         Ok(SocketAddr {
             addr,
-            len,
+            len: len.min(mem::size_of::<libc::sockaddr_un>() as libc::socklen_t),
         })
     }
 
@@ -374,6 +374,11 @@ impl IntoInner<c_int> for Socket {
 
 mod libc {
     pub use sgx_trts::libc::*;
+    pub use sgx_trts::libc::ocall::gai_strerror;
+    #[cfg(not(feature = "net_switchless"))]
     pub use sgx_trts::libc::ocall::{socket, socketpair, connect, accept4, recv, recvfrom, shutdown,
-                                    ioctl_arg1, poll, gai_strerror};
+                                    ioctl_arg1, poll};
+    #[cfg(feature = "net_switchless")]
+    pub use sgx_trts::libc::net_switchless::{socket, socketpair, connect, accept4, recv, recvfrom, shutdown,
+                                             ioctl_arg1, poll};
 }
\ No newline at end of file
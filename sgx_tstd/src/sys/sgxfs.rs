@@ -52,6 +52,10 @@ impl OpenOptions {
     pub fn update(&mut self, update: bool) { self.update = update; }
     pub fn binary(&mut self, binary: bool) { self.binary = binary; }
 
+    pub fn creates(&self) -> bool { self.write || self.append }
+    pub fn read_only(&self) -> bool { self.read && !self.write && !self.append && !self.update }
+    pub fn write_only(&self) -> bool { self.write && !self.read && !self.append && !self.update }
+
     fn get_access_mode(&self) -> io::Result<String> {
 
         let mut mode = match (self.read, self.write, self.append) {
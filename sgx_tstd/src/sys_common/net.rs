@@ -716,6 +716,11 @@ impl fmt::Debug for UdpSocket {
 
 mod libc {
     pub use sgx_trts::libc::*;
+    pub use sgx_trts::libc::ocall::{getaddrinfo, freeaddrinfo};
+    #[cfg(not(feature = "net_switchless"))]
     pub use sgx_trts::libc::ocall::{bind, listen, connect, setsockopt, getsockopt, send, sendto,
-                                    getpeername, getsockname, getaddrinfo, freeaddrinfo};
+                                    getpeername, getsockname};
+    #[cfg(feature = "net_switchless")]
+    pub use sgx_trts::libc::net_switchless::{bind, listen, connect, setsockopt, getsockopt, send, sendto,
+                                             getpeername, getsockname};
 }
\ No newline at end of file
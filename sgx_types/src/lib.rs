@@ -78,3 +78,4 @@ pub use self::function::*;
 pub mod marker;
 pub mod metadata;
 pub mod cpu_feature;
+pub mod quote;
@@ -0,0 +1,403 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! Zero-copy views over quote and report body bytes.
+//!
+//! `sgx_quote_t` and `sgx_quote3_t` end in variable-length signature data,
+//! so a quote received from the QE or an attestation service cannot simply be
+//! cast to them. [`QuoteView::parse`] checks every length field against the
+//! input before handing out borrowed views, and never reads past it.
+//!
+//! Supported layouts are EPID quotes (version 1 and 2) and ECDSA-256 quotes
+//! (version 3). [`QuoteView::parse_body`] accepts the 432 bytes of a quote
+//! without signature, which is what IAS returns in `isvEnclaveQuoteBody`.
+//!
+
+use crate::*;
+use core::fmt;
+use core::ptr;
+
+macro_rules! read_array {
+    ($bytes:expr, $offset:expr, $len:expr) => {{
+        let mut array = [0_u8; $len];
+        array.copy_from_slice(&$bytes[$offset..$offset + $len]);
+        array
+    }}
+}
+
+/// Size of the quote header shared by all supported versions.
+pub const QUOTE_HEADER_SIZE: usize = 48;
+/// Size of `sgx_report_body_t`.
+pub const REPORT_BODY_SIZE: usize = 384;
+/// Size of a quote up to and excluding the signature length field.
+pub const QUOTE_BODY_SIZE: usize = QUOTE_HEADER_SIZE + REPORT_BODY_SIZE;
+
+const QUOTE_SIG_OFFSET: usize = QUOTE_BODY_SIZE + 4;
+
+const ECDSA_SIG_SIZE: usize = 64;
+const ECDSA_PUB_KEY_SIZE: usize = 64;
+const ECDSA_SIG_DATA_SIZE: usize = ECDSA_SIG_SIZE + ECDSA_PUB_KEY_SIZE + REPORT_BODY_SIZE + ECDSA_SIG_SIZE;
+
+/// Attestation key type of ECDSA-256-with-P-256 quotes.
+pub const SGX_QL_ALG_ECDSA_P256: u16 = 2;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum QuoteParseError {
+    /// The input ends before a fixed-size part of the layout.
+    Truncated,
+    /// A length field does not match the remaining input.
+    LengthMismatch,
+    UnsupportedVersion(u16),
+    UnsupportedKeyType(u16),
+}
+
+impl fmt::Display for QuoteParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            QuoteParseError::Truncated => write!(f, "quote is truncated"),
+            QuoteParseError::LengthMismatch => write!(f, "quote length field does not match its data"),
+            QuoteParseError::UnsupportedVersion(v) => write!(f, "unsupported quote version {}", v),
+            QuoteParseError::UnsupportedKeyType(t) => write!(f, "unsupported attestation key type {}", t),
+        }
+    }
+}
+
+impl From<QuoteParseError> for sgx_status_t {
+    fn from(_: QuoteParseError) -> sgx_status_t {
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    }
+}
+
+/// A validated quote.
+#[derive(Copy, Clone, Debug)]
+pub struct QuoteView<'a> {
+    bytes: &'a [u8],
+    signature: QuoteSignature<'a>,
+}
+
+/// The signature part of a quote.
+#[derive(Copy, Clone, Debug)]
+pub enum QuoteSignature<'a> {
+    /// The quote was parsed with `parse_body` and carries no signature.
+    None,
+    /// EPID signature, opaque outside of the attestation service.
+    Epid(&'a [u8]),
+    Ecdsa(EcdsaSignatureView<'a>),
+}
+
+impl<'a> QuoteView<'a> {
+    /// Parses a complete quote, including its signature.
+    ///
+    /// The signature length field must account for exactly the bytes that
+    /// follow it. For version 3 quotes the signature data is parsed as well.
+    pub fn parse(bytes: &'a [u8]) -> Result<QuoteView<'a>, QuoteParseError> {
+        let version = Self::check_version(bytes)?;
+        if bytes.len() < QUOTE_SIG_OFFSET {
+            return Err(QuoteParseError::Truncated);
+        }
+        let sig_len = read_u32(bytes, QUOTE_BODY_SIZE) as usize;
+        let sig = &bytes[QUOTE_SIG_OFFSET..];
+        if sig.len() != sig_len {
+            return Err(QuoteParseError::LengthMismatch);
+        }
+        let signature = if version == 3 {
+            QuoteSignature::Ecdsa(EcdsaSignatureView::parse(sig)?)
+        } else {
+            QuoteSignature::Epid(sig)
+        };
+        Ok(QuoteView { bytes, signature })
+    }
+
+    /// Parses a quote body of exactly `QUOTE_BODY_SIZE` bytes.
+    pub fn parse_body(bytes: &'a [u8]) -> Result<QuoteView<'a>, QuoteParseError> {
+        Self::check_version(bytes)?;
+        if bytes.len() != QUOTE_BODY_SIZE {
+            return Err(QuoteParseError::LengthMismatch);
+        }
+        Ok(QuoteView { bytes, signature: QuoteSignature::None })
+    }
+
+    fn check_version(bytes: &[u8]) -> Result<u16, QuoteParseError> {
+        if bytes.len() < QUOTE_HEADER_SIZE {
+            return Err(QuoteParseError::Truncated);
+        }
+        match read_u16(bytes, 0) {
+            1 | 2 => Ok(read_u16(bytes, 0)),
+            3 => {
+                let key_type = read_u16(bytes, 2);
+                if key_type != SGX_QL_ALG_ECDSA_P256 {
+                    return Err(QuoteParseError::UnsupportedKeyType(key_type));
+                }
+                Ok(3)
+            },
+            v => Err(QuoteParseError::UnsupportedVersion(v)),
+        }
+    }
+
+    /// Returns the parsed bytes.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    pub fn version(&self) -> u16 {
+        read_u16(self.bytes, 0)
+    }
+
+    pub fn is_epid(&self) -> bool {
+        self.version() != 3
+    }
+
+    /// Returns the EPID signature type (`sgx_quote_sign_type_t` as u16), or
+    /// `None` for ECDSA quotes.
+    pub fn sign_type(&self) -> Option<u16> {
+        if self.is_epid() { Some(read_u16(self.bytes, 2)) } else { None }
+    }
+
+    /// Returns the EPID group ID, or `None` for ECDSA quotes.
+    pub fn epid_group_id(&self) -> Option<sgx_epid_group_id_t> {
+        if self.is_epid() { Some(read_array!(self.bytes, 4, 4)) } else { None }
+    }
+
+    /// Returns the attestation key type, or `None` for EPID quotes.
+    pub fn att_key_type(&self) -> Option<u16> {
+        if self.is_epid() { None } else { Some(read_u16(self.bytes, 2)) }
+    }
+
+    pub fn qe_svn(&self) -> sgx_isv_svn_t {
+        read_u16(self.bytes, 8)
+    }
+
+    pub fn pce_svn(&self) -> sgx_isv_svn_t {
+        read_u16(self.bytes, 10)
+    }
+
+    /// Returns the extended EPID group ID, or `None` for ECDSA quotes.
+    pub fn xeid(&self) -> Option<u32> {
+        if self.is_epid() { Some(read_u32(self.bytes, 12)) } else { None }
+    }
+
+    /// Returns the EPID basename, or `None` for ECDSA quotes.
+    pub fn basename(&self) -> Option<&'a [u8]> {
+        if self.is_epid() { Some(&self.bytes[16..48]) } else { None }
+    }
+
+    /// Returns the QE vendor ID, or `None` for EPID quotes.
+    pub fn qe_vendor_id(&self) -> Option<&'a [u8]> {
+        if self.is_epid() { None } else { Some(&self.bytes[12..28]) }
+    }
+
+    /// Returns the header user data, or `None` for EPID quotes.
+    pub fn user_data(&self) -> Option<&'a [u8]> {
+        if self.is_epid() { None } else { Some(&self.bytes[28..48]) }
+    }
+
+    pub fn report_body(&self) -> ReportBodyView<'a> {
+        ReportBodyView { bytes: &self.bytes[QUOTE_HEADER_SIZE..QUOTE_BODY_SIZE] }
+    }
+
+    /// Returns the header and report body, the part covered by the quote
+    /// signature.
+    pub fn signed_bytes(&self) -> &'a [u8] {
+        &self.bytes[..QUOTE_BODY_SIZE]
+    }
+
+    pub fn signature(&self) -> QuoteSignature<'a> {
+        self.signature
+    }
+}
+
+/// The signature data of a version 3 quote (`sgx_ql_ecdsa_sig_data_t`
+/// followed by its QE authentication and certification data).
+#[derive(Copy, Clone, Debug)]
+pub struct EcdsaSignatureView<'a> {
+    bytes: &'a [u8],
+    auth_data: &'a [u8],
+    certification_data: CertificationDataView<'a>,
+}
+
+impl<'a> EcdsaSignatureView<'a> {
+    pub fn parse(bytes: &'a [u8]) -> Result<EcdsaSignatureView<'a>, QuoteParseError> {
+        if bytes.len() < ECDSA_SIG_DATA_SIZE + 2 {
+            return Err(QuoteParseError::Truncated);
+        }
+        let auth_len = read_u16(bytes, ECDSA_SIG_DATA_SIZE) as usize;
+        let rest = &bytes[ECDSA_SIG_DATA_SIZE + 2..];
+        if rest.len() < auth_len {
+            return Err(QuoteParseError::Truncated);
+        }
+        let (auth_data, rest) = rest.split_at(auth_len);
+        let certification_data = CertificationDataView::parse(rest)?;
+        Ok(EcdsaSignatureView { bytes, auth_data, certification_data })
+    }
+
+    /// ECDSA signature over the quote header and report body, as `r || s`.
+    pub fn signature(&self) -> &'a [u8] {
+        &self.bytes[..ECDSA_SIG_SIZE]
+    }
+
+    /// The attestation public key, as `x || y`.
+    pub fn attest_pub_key(&self) -> &'a [u8] {
+        &self.bytes[ECDSA_SIG_SIZE..ECDSA_SIG_SIZE + ECDSA_PUB_KEY_SIZE]
+    }
+
+    /// The report of the QE that produced the quote.
+    pub fn qe_report(&self) -> ReportBodyView<'a> {
+        let start = ECDSA_SIG_SIZE + ECDSA_PUB_KEY_SIZE;
+        ReportBodyView { bytes: &self.bytes[start..start + REPORT_BODY_SIZE] }
+    }
+
+    /// ECDSA signature of the PCK over the QE report.
+    pub fn qe_report_signature(&self) -> &'a [u8] {
+        let start = ECDSA_SIG_DATA_SIZE - ECDSA_SIG_SIZE;
+        &self.bytes[start..ECDSA_SIG_DATA_SIZE]
+    }
+
+    pub fn qe_auth_data(&self) -> &'a [u8] {
+        self.auth_data
+    }
+
+    pub fn certification_data(&self) -> CertificationDataView<'a> {
+        self.certification_data
+    }
+}
+
+/// QE certification data (`sgx_ql_certification_data_t`).
+#[derive(Copy, Clone, Debug)]
+pub struct CertificationDataView<'a> {
+    cert_key_type: u16,
+    data: &'a [u8],
+}
+
+impl<'a> CertificationDataView<'a> {
+    /// Parses certification data that must end exactly at the end of `bytes`.
+    pub fn parse(bytes: &'a [u8]) -> Result<CertificationDataView<'a>, QuoteParseError> {
+        if bytes.len() < 6 {
+            return Err(QuoteParseError::Truncated);
+        }
+        let len = read_u32(bytes, 2) as usize;
+        let data = &bytes[6..];
+        if data.len() != len {
+            return Err(QuoteParseError::LengthMismatch);
+        }
+        Ok(CertificationDataView { cert_key_type: read_u16(bytes, 0), data })
+    }
+
+    /// The certification data type, e.g. 5 for a PEM PCK certificate chain.
+    pub fn cert_key_type(&self) -> u16 {
+        self.cert_key_type
+    }
+
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+}
+
+/// A borrowed `sgx_report_body_t`.
+#[derive(Copy, Clone, Debug)]
+pub struct ReportBodyView<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> ReportBodyView<'a> {
+    /// Parses exactly `REPORT_BODY_SIZE` bytes.
+    pub fn parse(bytes: &'a [u8]) -> Result<ReportBodyView<'a>, QuoteParseError> {
+        if bytes.len() != REPORT_BODY_SIZE {
+            return Err(QuoteParseError::LengthMismatch);
+        }
+        Ok(ReportBodyView { bytes })
+    }
+
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    pub fn cpu_svn(&self) -> sgx_cpu_svn_t {
+        sgx_cpu_svn_t { svn: read_array!(self.bytes, 0, SGX_CPUSVN_SIZE) }
+    }
+
+    pub fn misc_select(&self) -> sgx_misc_select_t {
+        read_u32(self.bytes, 16)
+    }
+
+    pub fn isv_ext_prod_id(&self) -> sgx_isvext_prod_id_t {
+        read_array!(self.bytes, 32, SGX_ISVEXT_PROD_ID_SIZE)
+    }
+
+    pub fn attributes(&self) -> sgx_attributes_t {
+        sgx_attributes_t {
+            flags: read_u64(self.bytes, 48),
+            xfrm: read_u64(self.bytes, 56),
+        }
+    }
+
+    pub fn mr_enclave(&self) -> sgx_measurement_t {
+        sgx_measurement_t { m: read_array!(self.bytes, 64, SGX_HASH_SIZE) }
+    }
+
+    pub fn mr_signer(&self) -> sgx_measurement_t {
+        sgx_measurement_t { m: read_array!(self.bytes, 128, SGX_HASH_SIZE) }
+    }
+
+    pub fn config_id(&self) -> &'a [u8] {
+        &self.bytes[192..192 + SGX_CONFIGID_SIZE]
+    }
+
+    pub fn isv_prod_id(&self) -> sgx_prod_id_t {
+        read_u16(self.bytes, 256)
+    }
+
+    pub fn isv_svn(&self) -> sgx_isv_svn_t {
+        read_u16(self.bytes, 258)
+    }
+
+    pub fn config_svn(&self) -> sgx_config_svn_t {
+        read_u16(self.bytes, 260)
+    }
+
+    pub fn isv_family_id(&self) -> sgx_isvfamily_id_t {
+        read_array!(self.bytes, 304, SGX_ISV_FAMILY_ID_SIZE)
+    }
+
+    pub fn report_data(&self) -> &'a [u8] {
+        &self.bytes[320..320 + SGX_REPORT_DATA_SIZE]
+    }
+
+    /// Copies the view into an `sgx_report_body_t`.
+    pub fn to_report_body(&self) -> sgx_report_body_t {
+        let mut body = sgx_report_body_t::default();
+        unsafe {
+            ptr::copy_nonoverlapping(self.bytes.as_ptr(),
+                                     &mut body as *mut sgx_report_body_t as *mut u8,
+                                     REPORT_BODY_SIZE);
+        }
+        body
+    }
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(read_array!(bytes, offset, 2))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(read_array!(bytes, offset, 4))
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(read_array!(bytes, offset, 8))
+}
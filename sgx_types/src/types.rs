@@ -542,11 +542,19 @@ pub const SGX_MC_POLICY_ENCLAVE: uint16_t  = 0x02;
 
 pub const SGX_SHA1_HASH_SIZE: size_t         = 20;
 pub const SGX_SHA256_HASH_SIZE: size_t       = 32;
+pub const SGX_SHA384_HASH_SIZE: size_t       = 48;
+pub const SGX_SHA512_HASH_SIZE: size_t       = 64;
 pub const SGX_ECP256_KEY_SIZE: size_t        = 32;
 pub const SGX_NISTP_ECP256_KEY_SIZE: size_t  = (SGX_ECP256_KEY_SIZE / 4);
 pub const SGX_AESGCM_IV_SIZE: size_t         = 12;
 pub const SGX_AESGCM_KEY_SIZE: size_t        = 16;
 pub const SGX_AESGCM_MAC_SIZE: size_t        = 16;
+pub const SGX_AESGCM256_KEY_SIZE: size_t     = 32;
+pub const SGX_AESGCMSIV_NONCE_SIZE: size_t   = 12;
+pub const SGX_CHACHA20_KEY_SIZE: size_t      = 32;
+pub const SGX_CHACHA20POLY1305_NONCE_SIZE: size_t = 12;
+pub const SGX_XCHACHA20POLY1305_NONCE_SIZE: size_t = 24;
+pub const SGX_POLY1305_MAC_SIZE: size_t      = 16;
 pub const SGX_HMAC256_KEY_SIZE: size_t       = 32;
 pub const SGX_HMAC256_MAC_SIZE: size_t       = 32;
 pub const SGX_CMAC_KEY_SIZE: size_t          = 16;
@@ -555,6 +563,9 @@ pub const SGX_AESCTR_KEY_SIZE: size_t        = 16;
 pub const SGX_RSA3072_KEY_SIZE: size_t       = 384;
 pub const SGX_RSA3072_PRI_EXP_SIZE: size_t   = 384;
 pub const SGX_RSA3072_PUB_EXP_SIZE: size_t   = 4;
+pub const SGX_ED25519_KEY_SIZE: size_t       = 32;
+pub const SGX_ED25519_SIGNATURE_SIZE: size_t = 64;
+pub const SGX_X25519_KEY_SIZE: size_t        = 32;
 
 impl_struct! {
     pub struct sgx_ec256_dh_shared_t {
@@ -581,6 +592,31 @@ impl_struct! {
         pub x: [uint32_t; SGX_NISTP_ECP256_KEY_SIZE],
         pub y: [uint32_t; SGX_NISTP_ECP256_KEY_SIZE],
     }
+
+    pub struct sgx_ed25519_private_t {
+        pub seed: [uint8_t; SGX_ED25519_KEY_SIZE],
+    }
+
+    pub struct sgx_ed25519_public_t {
+        pub key: [uint8_t; SGX_ED25519_KEY_SIZE],
+    }
+
+    pub struct sgx_ed25519_signature_t {
+        pub r: [uint8_t; SGX_ED25519_KEY_SIZE],
+        pub s: [uint8_t; SGX_ED25519_KEY_SIZE],
+    }
+
+    pub struct sgx_x25519_private_t {
+        pub scalar: [uint8_t; SGX_X25519_KEY_SIZE],
+    }
+
+    pub struct sgx_x25519_public_t {
+        pub key: [uint8_t; SGX_X25519_KEY_SIZE],
+    }
+
+    pub struct sgx_x25519_dh_shared_t {
+        pub s: [uint8_t; SGX_X25519_KEY_SIZE],
+    }
 }
 
 impl_copy_clone! {
@@ -631,9 +667,14 @@ pub type sgx_aes_state_handle_t     = * mut c_void;
 
 pub type sgx_sha1_hash_t = [uint8_t; SGX_SHA1_HASH_SIZE];
 pub type sgx_sha256_hash_t = [uint8_t; SGX_SHA256_HASH_SIZE];
+pub type sgx_sha384_hash_t = [uint8_t; SGX_SHA384_HASH_SIZE];
+pub type sgx_sha512_hash_t = [uint8_t; SGX_SHA512_HASH_SIZE];
 
 pub type sgx_aes_gcm_128bit_key_t   = [uint8_t; SGX_AESGCM_KEY_SIZE];
 pub type sgx_aes_gcm_128bit_tag_t   = [uint8_t; SGX_AESGCM_MAC_SIZE];
+pub type sgx_aes_gcm_256bit_key_t   = [uint8_t; SGX_AESGCM256_KEY_SIZE];
+pub type sgx_chacha20_256bit_key_t  = [uint8_t; SGX_CHACHA20_KEY_SIZE];
+pub type sgx_poly1305_128bit_tag_t  = [uint8_t; SGX_POLY1305_MAC_SIZE];
 pub type sgx_hmac_256bit_key_t      = [uint8_t; SGX_HMAC256_KEY_SIZE];
 pub type sgx_hmac_256bit_tag_t      = [uint8_t; SGX_HMAC256_MAC_SIZE];
 pub type sgx_cmac_128bit_key_t      = [uint8_t; SGX_CMAC_KEY_SIZE];
@@ -711,6 +752,19 @@ pub const IQMP_SIZE_IN_UINT: size_t   = (IQMP_SIZE_IN_BYTES / 4);
 
 pub type sgx_rsa_key_t = * mut c_void;
 
+/* largest modulus, in bytes, accepted by the SgxRsaPrivKey/SgxRsaPubKey padding modes */
+pub const SGX_RSA_MAX_MOD_SIZE: size_t = 512;
+
+impl_enum! {
+    #[repr(u32)]
+    #[derive(Copy, Clone, PartialEq, Eq)]
+    pub enum sgx_rsa_hash_t {
+        SGX_RSA_HASH_SHA1       = 0,
+        SGX_RSA_HASH_SHA256     = 1,
+        SGX_RSA_HASH_SHA384     = 2,
+    }
+}
+
 
 /* intel sgx sdk 2.1.3 */
 impl_copy_clone! {
@@ -1430,6 +1484,8 @@ impl_enum! {
         SGX_QL_QV_RESULT_INVALID_SIGNATURE          = 0x0000_A004,
         SGX_QL_QV_RESULT_REVOKED                    = 0x0000_A005,
         SGX_QL_QV_RESULT_UNSPECIFIED                = 0x0000_A006,
+        SGX_QL_QV_RESULT_SW_HARDENING_NEEDED        = 0x0000_A007,
+        SGX_QL_QV_RESULT_CONFIG_AND_SW_HARDENING_NEEDED = 0x0000_A008,
         SGX_QL_QV_RESULT_MAX                        = 0x0000_A0FF,
     }
 }
@@ -1491,9 +1547,33 @@ pub struct sgx_align_ec256_private_t {
     pub key: sgx_ec256_private_t,
 }
 
+#[repr(C, align(64))]
+#[derive(Copy, Clone, Default)]
+pub struct sgx_align_ed25519_private_t {
+    _pad: [uint8_t; 8],
+    pub key: sgx_ed25519_private_t,
+}
+
+#[repr(C, align(64))]
+#[derive(Copy, Clone, Default)]
+pub struct sgx_align_x25519_private_t {
+    _pad: [uint8_t; 8],
+    pub key: sgx_x25519_private_t,
+}
+
+#[repr(C, align(64))]
+#[derive(Copy, Clone, Default)]
+pub struct sgx_align_x25519_dh_shared_t {
+    _pad: [uint8_t; 8],
+    pub key: sgx_x25519_dh_shared_t,
+}
+
 unsafe impl ContiguousMemory for sgx_align_key_128bit_t {}
 unsafe impl ContiguousMemory for sgx_align_mac_128bit_t {}
 unsafe impl ContiguousMemory for sgx_align_key_256bit_t {}
 unsafe impl ContiguousMemory for sgx_align_mac_256bit_t {}
 unsafe impl ContiguousMemory for sgx_align_ec256_dh_shared_t {}
-unsafe impl ContiguousMemory for sgx_align_ec256_private_t {}
\ No newline at end of file
+unsafe impl ContiguousMemory for sgx_align_ec256_private_t {}
+unsafe impl ContiguousMemory for sgx_align_ed25519_private_t {}
+unsafe impl ContiguousMemory for sgx_align_x25519_private_t {}
+unsafe impl ContiguousMemory for sgx_align_x25519_dh_shared_t {}
\ No newline at end of file
@@ -0,0 +1,311 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! Constant-time AES block cipher.
+//!
+//! SubBytes is evaluated with the Boyar-Peralta bitsliced S-box circuit, so no
+//! secret-dependent table lookups or branches are performed. It is only used by
+//! the modes that libsgx_tcrypto.a does not provide (e.g. 256-bit keys).
+//!
+use crate::internal::zeroize;
+
+pub(crate) const AES_BLOCK_SIZE: usize = 16;
+
+const AES_MAX_ROUNDS: usize = 14;
+
+const RCON: [u8; 10] = [0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x1b, 0x36];
+
+pub(crate) struct Aes {
+    round_keys: [[u8; AES_BLOCK_SIZE]; AES_MAX_ROUNDS + 1],
+    rounds: usize,
+}
+
+impl Aes {
+    ///
+    /// Expands a 128, 192 or 256-bit key. Returns None for any other key size.
+    ///
+    pub(crate) fn new(key: &[u8]) -> Option<Aes> {
+        let nk = match key.len() {
+            16 | 24 | 32 => key.len() / 4,
+            _ => return None,
+        };
+        let rounds = nk + 6;
+        let total = 4 * (rounds + 1);
+
+        let mut w = [[0_u8; 4]; 4 * (AES_MAX_ROUNDS + 1)];
+        for (i, chunk) in key.chunks(4).enumerate() {
+            w[i].copy_from_slice(chunk);
+        }
+        for i in nk..total {
+            let mut temp = w[i - 1];
+            if i % nk == 0 {
+                temp.rotate_left(1);
+                sub_word(&mut temp);
+                temp[0] ^= RCON[i / nk - 1];
+            } else if nk > 6 && i % nk == 4 {
+                sub_word(&mut temp);
+            }
+            let prev = w[i - nk];
+            for (dst, (p, t)) in w[i].iter_mut().zip(prev.iter().zip(temp.iter())) {
+                *dst = *p ^ *t;
+            }
+        }
+
+        let mut aes = Aes {
+            round_keys: [[0_u8; AES_BLOCK_SIZE]; AES_MAX_ROUNDS + 1],
+            rounds,
+        };
+        for (i, word) in w[..total].iter().enumerate() {
+            aes.round_keys[i / 4][(i % 4) * 4..(i % 4) * 4 + 4].copy_from_slice(word);
+        }
+        for word in w.iter_mut() {
+            zeroize(word);
+        }
+        Some(aes)
+    }
+
+    pub(crate) fn encrypt_block(&self, block: &mut [u8; AES_BLOCK_SIZE]) {
+        add_round_key(block, &self.round_keys[0]);
+        for r in 1..self.rounds {
+            sub_bytes(block);
+            shift_rows(block);
+            mix_columns(block);
+            add_round_key(block, &self.round_keys[r]);
+        }
+        sub_bytes(block);
+        shift_rows(block);
+        add_round_key(block, &self.round_keys[self.rounds]);
+    }
+}
+
+impl Drop for Aes {
+    fn drop(&mut self) {
+        for rk in self.round_keys.iter_mut() {
+            zeroize(rk);
+        }
+    }
+}
+
+fn add_round_key(block: &mut [u8; AES_BLOCK_SIZE], rk: &[u8; AES_BLOCK_SIZE]) {
+    for (b, k) in block.iter_mut().zip(rk.iter()) {
+        *b ^= *k;
+    }
+}
+
+fn sub_word(word: &mut [u8; 4]) {
+    let mut block = [0_u8; AES_BLOCK_SIZE];
+    block[..4].copy_from_slice(word);
+    sub_bytes(&mut block);
+    word.copy_from_slice(&block[..4]);
+    zeroize(&mut block);
+}
+
+fn sub_bytes(block: &mut [u8; AES_BLOCK_SIZE]) {
+    let mut q = [0_u16; 8];
+    for (i, b) in block.iter().enumerate() {
+        for (j, plane) in q.iter_mut().enumerate() {
+            *plane |= u16::from((*b >> j) & 1) << i;
+        }
+    }
+    bitslice_sbox(&mut q);
+    for (i, b) in block.iter_mut().enumerate() {
+        let mut v = 0_u8;
+        for (j, plane) in q.iter().enumerate() {
+            v |= (((*plane >> i) & 1) as u8) << j;
+        }
+        *b = v;
+    }
+    zeroize(&mut q);
+}
+
+fn shift_rows(block: &mut [u8; AES_BLOCK_SIZE]) {
+    let s = *block;
+    for c in 0..4 {
+        for r in 1..4 {
+            block[r + 4 * c] = s[r + 4 * ((c + r) % 4)];
+        }
+    }
+}
+
+#[inline]
+fn xtime(x: u8) -> u8 {
+    (x << 1) ^ ((x >> 7) * 0x1b)
+}
+
+fn mix_columns(block: &mut [u8; AES_BLOCK_SIZE]) {
+    for col in block.chunks_mut(4) {
+        let a0 = col[0];
+        let a1 = col[1];
+        let a2 = col[2];
+        let a3 = col[3];
+        let t = a0 ^ a1 ^ a2 ^ a3;
+        col[0] = a0 ^ t ^ xtime(a0 ^ a1);
+        col[1] = a1 ^ t ^ xtime(a1 ^ a2);
+        col[2] = a2 ^ t ^ xtime(a2 ^ a3);
+        col[3] = a3 ^ t ^ xtime(a3 ^ a0);
+    }
+}
+
+///
+/// Boyar-Peralta S-box circuit. q[0] holds the least significant bit of
+/// every byte, q[7] the most significant one.
+///
+#[allow(clippy::many_single_char_names)]
+fn bitslice_sbox(q: &mut [u16; 8]) {
+    let x0 = q[7];
+    let x1 = q[6];
+    let x2 = q[5];
+    let x3 = q[4];
+    let x4 = q[3];
+    let x5 = q[2];
+    let x6 = q[1];
+    let x7 = q[0];
+
+    // Top linear transformation.
+    let y14 = x3 ^ x5;
+    let y13 = x0 ^ x6;
+    let y9 = x0 ^ x3;
+    let y8 = x0 ^ x5;
+    let t0 = x1 ^ x2;
+    let y1 = t0 ^ x7;
+    let y4 = y1 ^ x3;
+    let y12 = y13 ^ y14;
+    let y2 = y1 ^ x0;
+    let y5 = y1 ^ x6;
+    let y3 = y5 ^ y8;
+    let t1 = x4 ^ y12;
+    let y15 = t1 ^ x5;
+    let y20 = t1 ^ x1;
+    let y6 = y15 ^ x7;
+    let y10 = y15 ^ t0;
+    let y11 = y20 ^ y9;
+    let y7 = x7 ^ y11;
+    let y17 = y10 ^ y11;
+    let y19 = y10 ^ y8;
+    let y16 = t0 ^ y11;
+    let y21 = y13 ^ y16;
+    let y18 = x0 ^ y16;
+
+    // Non-linear section.
+    let t2 = y12 & y15;
+    let t3 = y3 & y6;
+    let t4 = t3 ^ t2;
+    let t5 = y4 & x7;
+    let t6 = t5 ^ t2;
+    let t7 = y13 & y16;
+    let t8 = y5 & y1;
+    let t9 = t8 ^ t7;
+    let t10 = y2 & y7;
+    let t11 = t10 ^ t7;
+    let t12 = y9 & y11;
+    let t13 = y14 & y17;
+    let t14 = t13 ^ t12;
+    let t15 = y8 & y10;
+    let t16 = t15 ^ t12;
+    let t17 = t4 ^ t14;
+    let t18 = t6 ^ t16;
+    let t19 = t9 ^ t14;
+    let t20 = t11 ^ t16;
+    let t21 = t17 ^ y20;
+    let t22 = t18 ^ y19;
+    let t23 = t19 ^ y21;
+    let t24 = t20 ^ y18;
+
+    let t25 = t21 ^ t22;
+    let t26 = t21 & t23;
+    let t27 = t24 ^ t26;
+    let t28 = t25 & t27;
+    let t29 = t28 ^ t22;
+    let t30 = t23 ^ t24;
+    let t31 = t22 ^ t26;
+    let t32 = t31 & t30;
+    let t33 = t32 ^ t24;
+    let t34 = t23 ^ t33;
+    let t35 = t27 ^ t33;
+    let t36 = t24 & t35;
+    let t37 = t36 ^ t34;
+    let t38 = t27 ^ t36;
+    let t39 = t29 & t38;
+    let t40 = t25 ^ t39;
+
+    let t41 = t40 ^ t37;
+    let t42 = t29 ^ t33;
+    let t43 = t29 ^ t40;
+    let t44 = t33 ^ t37;
+    let t45 = t42 ^ t41;
+    let z0 = t44 & y15;
+    let z1 = t37 & y6;
+    let z2 = t33 & x7;
+    let z3 = t43 & y16;
+    let z4 = t40 & y1;
+    let z5 = t29 & y7;
+    let z6 = t42 & y11;
+    let z7 = t45 & y17;
+    let z8 = t41 & y10;
+    let z9 = t44 & y12;
+    let z10 = t37 & y3;
+    let z11 = t33 & y4;
+    let z12 = t43 & y13;
+    let z13 = t40 & y5;
+    let z14 = t29 & y2;
+    let z15 = t42 & y9;
+    let z16 = t45 & y14;
+    let z17 = t41 & y8;
+
+    // Bottom linear transformation.
+    let t46 = z15 ^ z16;
+    let t47 = z10 ^ z11;
+    let t48 = z5 ^ z13;
+    let t49 = z9 ^ z10;
+    let t50 = z2 ^ z12;
+    let t51 = z2 ^ z5;
+    let t52 = z7 ^ z8;
+    let t53 = z0 ^ z3;
+    let t54 = z6 ^ z7;
+    let t55 = z16 ^ z17;
+    let t56 = z12 ^ t48;
+    let t57 = t50 ^ t53;
+    let t58 = z4 ^ t46;
+    let t59 = z3 ^ t54;
+    let t60 = t46 ^ t57;
+    let t61 = z14 ^ t57;
+    let t62 = t52 ^ t58;
+    let t63 = t49 ^ t58;
+    let t64 = z4 ^ t59;
+    let t65 = t61 ^ t62;
+    let t66 = z1 ^ t63;
+    let s0 = t59 ^ t63;
+    let s6 = t56 ^ !t62;
+    let s7 = t48 ^ !t60;
+    let t67 = t64 ^ t65;
+    let s3 = t53 ^ t66;
+    let s4 = t51 ^ t66;
+    let s5 = t47 ^ t65;
+    let s1 = t64 ^ !s3;
+    let s2 = t55 ^ !t67;
+
+    q[7] = s0;
+    q[6] = s1;
+    q[5] = s2;
+    q[4] = s3;
+    q[3] = s4;
+    q[2] = s5;
+    q[1] = s6;
+    q[0] = s7;
+}
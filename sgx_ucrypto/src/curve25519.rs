@@ -0,0 +1,563 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! Curve25519 arithmetic shared by Ed25519 and X25519.
+//!
+//! Field elements use five 51-bit limbs and 128-bit products. Everything that
+//! may touch secret data (field arithmetic, scalar multiplication, scalar
+//! reduction) runs in constant time; there are no secret-dependent branches or
+//! table indices.
+//!
+#![allow(clippy::unreadable_literal)]
+
+use std::ops::{Add, Sub, Mul, Neg};
+use crate::internal::zeroize;
+
+const MASK51: u64 = (1_u64 << 51) - 1;
+
+#[derive(Clone, Copy)]
+pub(crate) struct Fe(pub(crate) [u64; 5]);
+
+// d = -121665 / 121666
+const EDWARDS_D: Fe = Fe([0x34dca135978a3, 0x1a8283b156ebd, 0x5e7a26001c029, 0x739c663a03cbb, 0x52036cee2b6ff]);
+const EDWARDS_D2: Fe = Fe([0x69b9426b2f159, 0x35050762add7a, 0x3cf44c0038052, 0x6738cc7407977, 0x2406d9dc56dff]);
+const SQRT_M1: Fe = Fe([0x61b274a0ea0b0, 0x0d5a5fc8f189d, 0x7ef5e9cbd0c60, 0x78595a6804c9e, 0x2b8324804fc1d]);
+
+fn load8(b: &[u8]) -> u64 {
+    let mut w = [0_u8; 8];
+    w.copy_from_slice(&b[..8]);
+    u64::from_le_bytes(w)
+}
+
+#[inline]
+fn m(a: u64, b: u64) -> u128 {
+    u128::from(a) * u128::from(b)
+}
+
+impl Fe {
+    pub(crate) const ZERO: Fe = Fe([0, 0, 0, 0, 0]);
+    pub(crate) const ONE: Fe = Fe([1, 0, 0, 0, 0]);
+
+    ///
+    /// Loads a little-endian encoding, ignoring the top bit.
+    ///
+    pub(crate) fn from_bytes(b: &[u8; 32]) -> Fe {
+        Fe([load8(&b[0..]) & MASK51,
+            (load8(&b[6..]) >> 3) & MASK51,
+            (load8(&b[12..]) >> 6) & MASK51,
+            (load8(&b[19..]) >> 1) & MASK51,
+            (load8(&b[24..]) >> 12) & MASK51])
+    }
+
+    ///
+    /// Returns the canonical little-endian encoding.
+    ///
+    pub(crate) fn to_bytes(&self) -> [u8; 32] {
+        let mut l = Fe::weak_reduce(self.0);
+
+        // Compute q = 1 if l >= p, then add 19q and drop bit 255.
+        let mut q = (l[0] + 19) >> 51;
+        q = (l[1] + q) >> 51;
+        q = (l[2] + q) >> 51;
+        q = (l[3] + q) >> 51;
+        q = (l[4] + q) >> 51;
+        l[0] += 19 * q;
+        l[1] += l[0] >> 51;
+        l[0] &= MASK51;
+        l[2] += l[1] >> 51;
+        l[1] &= MASK51;
+        l[3] += l[2] >> 51;
+        l[2] &= MASK51;
+        l[4] += l[3] >> 51;
+        l[3] &= MASK51;
+        l[4] &= MASK51;
+
+        let words = [l[0] | (l[1] << 51),
+                     (l[1] >> 13) | (l[2] << 38),
+                     (l[2] >> 26) | (l[3] << 25),
+                     (l[3] >> 39) | (l[4] << 12)];
+        let mut out = [0_u8; 32];
+        for (chunk, w) in out.chunks_mut(8).zip(words.iter()) {
+            chunk.copy_from_slice(&w.to_le_bytes());
+        }
+        out
+    }
+
+    fn weak_reduce(mut l: [u64; 5]) -> [u64; 5] {
+        let c0 = l[0] >> 51;
+        let c1 = l[1] >> 51;
+        let c2 = l[2] >> 51;
+        let c3 = l[3] >> 51;
+        let c4 = l[4] >> 51;
+        l[0] &= MASK51;
+        l[1] &= MASK51;
+        l[2] &= MASK51;
+        l[3] &= MASK51;
+        l[4] &= MASK51;
+        l[0] += c4 * 19;
+        l[1] += c0;
+        l[2] += c1;
+        l[3] += c2;
+        l[4] += c3;
+        l
+    }
+
+    pub(crate) fn square(&self) -> Fe {
+        *self * *self
+    }
+
+    fn pow2k(&self, k: u32) -> Fe {
+        let mut t = self.square();
+        for _ in 1..k {
+            t = t.square();
+        }
+        t
+    }
+
+    // Returns (self^(2^250 - 1), self^11).
+    fn pow22501(&self) -> (Fe, Fe) {
+        let t0 = self.square();
+        let t1 = t0.pow2k(2);
+        let t2 = *self * t1;
+        let t3 = t0 * t2;
+        let t4 = t3.square();
+        let t5 = t2 * t4;
+        let t6 = t5.pow2k(5);
+        let t7 = t6 * t5;
+        let t8 = t7.pow2k(10);
+        let t9 = t8 * t7;
+        let t10 = t9.pow2k(20);
+        let t11 = t10 * t9;
+        let t12 = t11.pow2k(10);
+        let t13 = t12 * t7;
+        let t14 = t13.pow2k(50);
+        let t15 = t14 * t13;
+        let t16 = t15.pow2k(100);
+        let t17 = t16 * t15;
+        let t18 = t17.pow2k(50);
+        let t19 = t18 * t13;
+        (t19, t3)
+    }
+
+    ///
+    /// Computes self^(p - 2), which is the inverse for non-zero elements and zero otherwise.
+    ///
+    pub(crate) fn invert(&self) -> Fe {
+        let (t19, t3) = self.pow22501();
+        t19.pow2k(5) * t3
+    }
+
+    // self^((p - 5) / 8)
+    fn pow_p58(&self) -> Fe {
+        let (t19, _) = self.pow22501();
+        *self * t19.pow2k(2)
+    }
+
+    pub(crate) fn is_zero(&self) -> bool {
+        self.to_bytes().iter().fold(0_u8, |acc, b| acc | b) == 0
+    }
+
+    pub(crate) fn is_negative(&self) -> u8 {
+        self.to_bytes()[0] & 1
+    }
+
+    fn ct_eq(&self, other: &Fe) -> bool {
+        (*self - *other).is_zero()
+    }
+
+    ///
+    /// Replaces self with other when choice is 1, in constant time.
+    ///
+    pub(crate) fn cmov(&mut self, other: &Fe, choice: u8) {
+        let mask = 0_u64.wrapping_sub(u64::from(choice));
+        for (a, b) in self.0.iter_mut().zip(other.0.iter()) {
+            *a ^= mask & (*a ^ *b);
+        }
+    }
+
+    ///
+    /// Swaps a and b when choice is 1, in constant time.
+    ///
+    pub(crate) fn cswap(a: &mut Fe, b: &mut Fe, choice: u8) {
+        let mask = 0_u64.wrapping_sub(u64::from(choice));
+        for (x, y) in a.0.iter_mut().zip(b.0.iter_mut()) {
+            let t = mask & (*x ^ *y);
+            *x ^= t;
+            *y ^= t;
+        }
+    }
+
+    pub(crate) fn mul_small(&self, k: u32) -> Fe {
+        let k = u64::from(k);
+        let c0 = m(self.0[0], k);
+        let c1 = m(self.0[1], k) + (c0 >> 51);
+        let c2 = m(self.0[2], k) + (c1 >> 51);
+        let c3 = m(self.0[3], k) + (c2 >> 51);
+        let c4 = m(self.0[4], k) + (c3 >> 51);
+        let mut l = [c0 as u64 & MASK51,
+                     c1 as u64 & MASK51,
+                     c2 as u64 & MASK51,
+                     c3 as u64 & MASK51,
+                     c4 as u64 & MASK51];
+        l[0] += ((c4 >> 51) as u64) * 19;
+        Fe(l)
+    }
+
+    pub(crate) fn clear(&mut self) {
+        zeroize(&mut self.0);
+    }
+}
+
+impl Add for Fe {
+    type Output = Fe;
+
+    fn add(self, rhs: Fe) -> Fe {
+        let mut l = self.0;
+        for (a, b) in l.iter_mut().zip(rhs.0.iter()) {
+            *a += *b;
+        }
+        Fe(Fe::weak_reduce(l))
+    }
+}
+
+impl Sub for Fe {
+    type Output = Fe;
+
+    fn sub(self, rhs: Fe) -> Fe {
+        // Add 16p first so that no limb underflows.
+        let a = Fe::weak_reduce(self.0);
+        let b = Fe::weak_reduce(rhs.0);
+        Fe(Fe::weak_reduce([(a[0] + 0x007f_ffff_ffff_fed0) - b[0],
+                            (a[1] + 0x007f_ffff_ffff_fff0) - b[1],
+                            (a[2] + 0x007f_ffff_ffff_fff0) - b[2],
+                            (a[3] + 0x007f_ffff_ffff_fff0) - b[3],
+                            (a[4] + 0x007f_ffff_ffff_fff0) - b[4]]))
+    }
+}
+
+impl Neg for Fe {
+    type Output = Fe;
+
+    fn neg(self) -> Fe {
+        Fe::ZERO - self
+    }
+}
+
+impl Mul for Fe {
+    type Output = Fe;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn mul(self, rhs: Fe) -> Fe {
+        let a = &self.0;
+        let b = &rhs.0;
+        let b1_19 = b[1] * 19;
+        let b2_19 = b[2] * 19;
+        let b3_19 = b[3] * 19;
+        let b4_19 = b[4] * 19;
+
+        let c0 = m(a[0], b[0]) + m(a[4], b1_19) + m(a[3], b2_19) + m(a[2], b3_19) + m(a[1], b4_19);
+        let mut c1 = m(a[1], b[0]) + m(a[0], b[1]) + m(a[4], b2_19) + m(a[3], b3_19) + m(a[2], b4_19);
+        let mut c2 = m(a[2], b[0]) + m(a[1], b[1]) + m(a[0], b[2]) + m(a[4], b3_19) + m(a[3], b4_19);
+        let mut c3 = m(a[3], b[0]) + m(a[2], b[1]) + m(a[1], b[2]) + m(a[0], b[3]) + m(a[4], b4_19);
+        let mut c4 = m(a[4], b[0]) + m(a[3], b[1]) + m(a[2], b[2]) + m(a[1], b[3]) + m(a[0], b[4]);
+
+        c1 += c0 >> 51;
+        let mut l0 = (c0 as u64) & MASK51;
+        c2 += c1 >> 51;
+        let l1 = (c1 as u64) & MASK51;
+        c3 += c2 >> 51;
+        let l2 = (c2 as u64) & MASK51;
+        c4 += c3 >> 51;
+        let l3 = (c3 as u64) & MASK51;
+        let carry = (c4 >> 51) as u64;
+        let l4 = (c4 as u64) & MASK51;
+
+        l0 += carry * 19;
+        let l1 = l1 + (l0 >> 51);
+        l0 &= MASK51;
+        Fe([l0, l1, l2, l3, l4])
+    }
+}
+
+///
+/// A point on the twisted Edwards curve -x^2 + y^2 = 1 + d x^2 y^2, in extended
+/// coordinates (X : Y : Z : T) with x = X/Z, y = Y/Z and xy = T/Z.
+///
+#[derive(Clone, Copy)]
+pub(crate) struct EdwardsPoint {
+    x: Fe,
+    y: Fe,
+    z: Fe,
+    t: Fe,
+}
+
+impl EdwardsPoint {
+    pub(crate) const IDENTITY: EdwardsPoint = EdwardsPoint { x: Fe::ZERO, y: Fe::ONE, z: Fe::ONE, t: Fe::ZERO };
+
+    pub(crate) const BASEPOINT: EdwardsPoint = EdwardsPoint {
+        x: Fe([0x62d608f25d51a, 0x412a4b4f6592a, 0x75b7171a4b31d, 0x1ff60527118fe, 0x216936d3cd6e5]),
+        y: Fe([0x6666666666658, 0x4cccccccccccc, 0x1999999999999, 0x3333333333333, 0x6666666666666]),
+        z: Fe::ONE,
+        t: Fe([0x68ab3a5b7dda3, 0x00eea2a5eadbb, 0x2af8df483c27e, 0x332b375274732, 0x67875f0fd78b7]),
+    };
+
+    ///
+    /// Decodes a point as specified in RFC 8032, section 5.1.3. Non-canonical
+    /// encodings of y and encodings that are not on the curve are rejected.
+    ///
+    pub(crate) fn decompress(bytes: &[u8; 32]) -> Option<EdwardsPoint> {
+        let sign = bytes[31] >> 7;
+        let y = Fe::from_bytes(bytes);
+        let mut canonical = *bytes;
+        canonical[31] &= 0x7f;
+        if y.to_bytes() != canonical {
+            return None;
+        }
+
+        // x^2 = (y^2 - 1) / (d y^2 + 1)
+        let yy = y.square();
+        let u = yy - Fe::ONE;
+        let v = EDWARDS_D * yy + Fe::ONE;
+        let v3 = v.square() * v;
+        let v7 = v3.square() * v;
+        let mut x = u * v3 * (u * v7).pow_p58();
+
+        let vxx = v * x.square();
+        if !vxx.ct_eq(&u) {
+            if vxx.ct_eq(&-u) {
+                x = x * SQRT_M1;
+            } else {
+                return None;
+            }
+        }
+        if x.is_zero() && sign == 1 {
+            return None;
+        }
+        if x.is_negative() != sign {
+            x = -x;
+        }
+
+        Some(EdwardsPoint { x, y, z: Fe::ONE, t: x * y })
+    }
+
+    pub(crate) fn compress(&self) -> [u8; 32] {
+        let zinv = self.z.invert();
+        let x = self.x * zinv;
+        let y = self.y * zinv;
+        let mut s = y.to_bytes();
+        s[31] ^= x.is_negative() << 7;
+        s
+    }
+
+    // Unified addition (add-2008-hwcd-3), which is also valid for doubling.
+    #[allow(clippy::many_single_char_names)]
+    pub(crate) fn add(&self, other: &EdwardsPoint) -> EdwardsPoint {
+        let a = (self.y - self.x) * (other.y - other.x);
+        let b = (self.y + self.x) * (other.y + other.x);
+        let c = self.t * EDWARDS_D2 * other.t;
+        let d = (self.z + self.z) * other.z;
+        let e = b - a;
+        let f = d - c;
+        let g = d + c;
+        let h = b + a;
+        EdwardsPoint { x: e * f, y: g * h, z: f * g, t: e * h }
+    }
+
+    pub(crate) fn neg(&self) -> EdwardsPoint {
+        EdwardsPoint { x: -self.x, y: self.y, z: self.z, t: -self.t }
+    }
+
+    fn cmov(&mut self, other: &EdwardsPoint, choice: u8) {
+        self.x.cmov(&other.x, choice);
+        self.y.cmov(&other.y, choice);
+        self.z.cmov(&other.z, choice);
+        self.t.cmov(&other.t, choice);
+    }
+
+    ///
+    /// Computes [scalar]self for a 256-bit little-endian scalar, in constant time.
+    ///
+    pub(crate) fn mul(&self, scalar: &[u8; 32]) -> EdwardsPoint {
+        let mut acc = EdwardsPoint::IDENTITY;
+        for i in (0..256).rev() {
+            acc = acc.add(&acc);
+            let sum = acc.add(self);
+            acc.cmov(&sum, (scalar[i >> 3] >> (i & 7)) & 1);
+        }
+        acc
+    }
+
+    pub(crate) fn mul_base(scalar: &[u8; 32]) -> EdwardsPoint {
+        EdwardsPoint::BASEPOINT.mul(scalar)
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.x.clear();
+        self.y.clear();
+        self.z.clear();
+        self.t.clear();
+    }
+}
+
+// The group order L = 2^252 + 27742317777372353535851937790883648493.
+const ORDER: [u64; 4] = [0x5812_631a_5cf5_d3ed, 0x14de_f9de_a2f7_9cd6, 0, 0x1000_0000_0000_0000];
+
+///
+/// Reduces a little-endian integer of at most 512 bits modulo L, in constant time.
+///
+pub(crate) fn scalar_reduce(input: &[u8]) -> [u8; 32] {
+    let mut acc = [0_u64; 4];
+    for i in (0..input.len() * 8).rev() {
+        // acc < L < 2^253, so shifting in one bit cannot overflow 256 bits.
+        let bit = u64::from((input[i >> 3] >> (i & 7)) & 1);
+        acc[3] = (acc[3] << 1) | (acc[2] >> 63);
+        acc[2] = (acc[2] << 1) | (acc[1] >> 63);
+        acc[1] = (acc[1] << 1) | (acc[0] >> 63);
+        acc[0] = (acc[0] << 1) | bit;
+        scalar_cond_sub_order(&mut acc);
+    }
+
+    let mut out = [0_u8; 32];
+    for (chunk, w) in out.chunks_mut(8).zip(acc.iter()) {
+        chunk.copy_from_slice(&w.to_le_bytes());
+    }
+    zeroize(&mut acc);
+    out
+}
+
+// Subtracts L when acc >= L.
+fn scalar_cond_sub_order(acc: &mut [u64; 4]) {
+    let mut diff = [0_u64; 4];
+    let mut borrow = 0_u64;
+    for i in 0..4 {
+        let (d1, b1) = acc[i].overflowing_sub(ORDER[i]);
+        let (d2, b2) = d1.overflowing_sub(borrow);
+        diff[i] = d2;
+        borrow = u64::from(b1 | b2);
+    }
+    // Keep acc when the subtraction borrowed.
+    let mask = borrow.wrapping_sub(1);
+    for (a, d) in acc.iter_mut().zip(diff.iter()) {
+        *a ^= mask & (*a ^ *d);
+    }
+}
+
+///
+/// Computes (a * b + c) mod L for 256-bit little-endian inputs, in constant time.
+///
+pub(crate) fn scalar_muladd(a: &[u8; 32], b: &[u8; 32], c: &[u8; 32]) -> [u8; 32] {
+    let aw = load_words(a);
+    let bw = load_words(b);
+    let cw = load_words(c);
+
+    let mut product = [0_u64; 9];
+    for (i, x) in aw.iter().enumerate() {
+        let mut carry = 0_u128;
+        for (j, y) in bw.iter().enumerate() {
+            let t = m(*x, *y) + u128::from(product[i + j]) + carry;
+            product[i + j] = t as u64;
+            carry = t >> 64;
+        }
+        product[i + 4] = carry as u64;
+    }
+    let mut carry = 0_u128;
+    for (i, p) in product.iter_mut().enumerate() {
+        let t = u128::from(*p) + u128::from(if i < 4 { cw[i] } else { 0 }) + carry;
+        *p = t as u64;
+        carry = t >> 64;
+    }
+
+    let mut wide = [0_u8; 72];
+    for (chunk, w) in wide.chunks_mut(8).zip(product.iter()) {
+        chunk.copy_from_slice(&w.to_le_bytes());
+    }
+    let out = scalar_reduce(&wide);
+    zeroize(&mut wide);
+    zeroize(&mut product);
+    out
+}
+
+fn load_words(b: &[u8; 32]) -> [u64; 4] {
+    [load8(&b[0..]), load8(&b[8..]), load8(&b[16..]), load8(&b[24..])]
+}
+
+///
+/// Returns whether a little-endian scalar is canonical, i.e. less than L.
+///
+pub(crate) fn scalar_is_canonical(s: &[u8; 32]) -> bool {
+    let w = load_words(s);
+    let mut borrow = false;
+    for (x, l) in w.iter().zip(ORDER.iter()) {
+        let (d1, b1) = x.overflowing_sub(*l);
+        let (_, b2) = d1.overflowing_sub(u64::from(borrow));
+        borrow = b1 | b2;
+    }
+    borrow
+}
+
+///
+/// The X25519 function of RFC 7748. The scalar is clamped here, and the top bit
+/// of u is ignored.
+///
+#[allow(clippy::many_single_char_names)]
+pub(crate) fn x25519(scalar: &[u8; 32], u: &[u8; 32]) -> [u8; 32] {
+    let mut k = *scalar;
+    k[0] &= 248;
+    k[31] &= 127;
+    k[31] |= 64;
+
+    let x1 = Fe::from_bytes(u);
+    let mut x2 = Fe::ONE;
+    let mut z2 = Fe::ZERO;
+    let mut x3 = x1;
+    let mut z3 = Fe::ONE;
+    let mut swap = 0_u8;
+
+    for t in (0..255).rev() {
+        let k_t = (k[t >> 3] >> (t & 7)) & 1;
+        swap ^= k_t;
+        Fe::cswap(&mut x2, &mut x3, swap);
+        Fe::cswap(&mut z2, &mut z3, swap);
+        swap = k_t;
+
+        let a = x2 + z2;
+        let aa = a.square();
+        let b = x2 - z2;
+        let bb = b.square();
+        let e = aa - bb;
+        let c = x3 + z3;
+        let d = x3 - z3;
+        let da = d * a;
+        let cb = c * b;
+        x3 = (da + cb).square();
+        z3 = x1 * (da - cb).square();
+        x2 = aa * bb;
+        z2 = e * (aa + e.mul_small(121_665));
+    }
+    Fe::cswap(&mut x2, &mut x3, swap);
+    Fe::cswap(&mut z2, &mut z3, swap);
+
+    let out = (x2 * z2.invert()).to_bytes();
+    zeroize(&mut k);
+    x2.clear();
+    z2.clear();
+    x3.clear();
+    z3.clear();
+    out
+}
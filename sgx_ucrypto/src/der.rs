@@ -0,0 +1,237 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! A minimal DER reader and writer for the fixed structures in codec.rs.
+//!
+//! The reader only accepts DER: definite, minimally encoded lengths and
+//! minimally encoded INTEGERs. The writer works on a caller-provided buffer,
+//! since this crate does not allocate.
+//!
+use sgx_types::*;
+
+pub(crate) const TAG_INTEGER: u8 = 0x02;
+pub(crate) const TAG_BIT_STRING: u8 = 0x03;
+pub(crate) const TAG_OCTET_STRING: u8 = 0x04;
+pub(crate) const TAG_NULL: u8 = 0x05;
+pub(crate) const TAG_OID: u8 = 0x06;
+pub(crate) const TAG_SEQUENCE: u8 = 0x30;
+pub(crate) const TAG_CONTEXT_0: u8 = 0xa0;
+pub(crate) const TAG_CONTEXT_1: u8 = 0xa1;
+
+fn length_size(len: usize) -> usize {
+    match len {
+        0..=0x7f => 1,
+        0x80..=0xff => 2,
+        0x100..=0xffff => 3,
+        _ => 4,
+    }
+}
+
+///
+/// The encoded size of a TLV with content_len bytes of content.
+///
+pub(crate) fn tlv_size(content_len: usize) -> usize {
+    1 + length_size(content_len) + content_len
+}
+
+///
+/// The content size of an INTEGER holding the unsigned little-endian value.
+///
+pub(crate) fn uint_le_content_size(value_le: &[u8]) -> usize {
+    let value_le = strip_high_zeros(value_le);
+    match value_le.last() {
+        None => 1,
+        Some(top) if top & 0x80 != 0 => value_le.len() + 1,
+        Some(_) => value_le.len(),
+    }
+}
+
+// Drops the most significant zero bytes of a little-endian value.
+fn strip_high_zeros(value_le: &[u8]) -> &[u8] {
+    let zeros = value_le.iter().rev().take_while(|b| **b == 0).count();
+    &value_le[..value_le.len() - zeros]
+}
+
+pub(crate) struct DerWriter<'a> {
+    buf: &'a mut [u8],
+    pos: usize,
+}
+
+impl<'a> DerWriter<'a> {
+    pub(crate) fn new(buf: &'a mut [u8]) -> DerWriter<'a> {
+        DerWriter { buf, pos: 0 }
+    }
+
+    pub(crate) fn position(&self) -> usize {
+        self.pos
+    }
+
+    pub(crate) fn write_raw(&mut self, data: &[u8]) -> SgxError {
+        let end = self.pos.checked_add(data.len()).ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+        if end > self.buf.len() {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        self.buf[self.pos..end].copy_from_slice(data);
+        self.pos = end;
+        Ok(())
+    }
+
+    ///
+    /// Writes a tag and length. The caller then writes exactly len bytes of content.
+    ///
+    pub(crate) fn write_header(&mut self, tag: u8, len: usize) -> SgxError {
+        self.write_raw(&[tag])?;
+        match length_size(len) {
+            1 => self.write_raw(&[len as u8]),
+            2 => self.write_raw(&[0x81, len as u8]),
+            3 => self.write_raw(&[0x82, (len >> 8) as u8, len as u8]),
+            _ => self.write_raw(&[0x83, (len >> 16) as u8, (len >> 8) as u8, len as u8]),
+        }
+    }
+
+    pub(crate) fn write_tlv(&mut self, tag: u8, content: &[u8]) -> SgxError {
+        self.write_header(tag, content.len())?;
+        self.write_raw(content)
+    }
+
+    ///
+    /// Writes an unsigned little-endian value as a minimal INTEGER.
+    ///
+    pub(crate) fn write_uint_le(&mut self, value_le: &[u8]) -> SgxError {
+        let stripped = strip_high_zeros(value_le);
+        self.write_header(TAG_INTEGER, uint_le_content_size(value_le))?;
+        if stripped.last().map_or(true, |top| top & 0x80 != 0) {
+            self.write_raw(&[0])?;
+        }
+        for b in stripped.iter().rev() {
+            self.write_raw(&[*b])?;
+        }
+        Ok(())
+    }
+
+    ///
+    /// Writes a BIT STRING with no unused bits.
+    ///
+    pub(crate) fn write_bit_string(&mut self, content: &[u8]) -> SgxError {
+        self.write_header(TAG_BIT_STRING, content.len() + 1)?;
+        self.write_raw(&[0])?;
+        self.write_raw(content)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct DerReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> DerReader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> DerReader<'a> {
+        DerReader { data }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub(crate) fn peek_tag(&self) -> Option<u8> {
+        self.data.first().copied()
+    }
+
+    ///
+    /// Reads a TLV with the expected tag and returns its content.
+    ///
+    pub(crate) fn read_tlv(&mut self, tag: u8) -> SgxResult<&'a [u8]> {
+        let invalid = sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+        if self.data.len() < 2 || self.data[0] != tag {
+            return Err(invalid);
+        }
+        let first = self.data[1] as usize;
+        let (len, header) = if first < 0x80 {
+            (first, 2)
+        } else {
+            let n = first & 0x7f;
+            if n == 0 || n > 3 || self.data.len() < 2 + n {
+                return Err(invalid);
+            }
+            let len = self.data[2..2 + n].iter().fold(0_usize, |acc, b| (acc << 8) | *b as usize);
+            // The long form must be minimal.
+            if len < 0x80 || length_size(len) != n + 1 {
+                return Err(invalid);
+            }
+            (len, 2 + n)
+        };
+        if self.data.len() - header < len {
+            return Err(invalid);
+        }
+        let content = &self.data[header..header + len];
+        self.data = &self.data[header + len..];
+        Ok(content)
+    }
+
+    pub(crate) fn read_sequence(&mut self) -> SgxResult<DerReader<'a>> {
+        self.read_tlv(TAG_SEQUENCE).map(DerReader::new)
+    }
+
+    ///
+    /// Reads a non-negative INTEGER and returns its big-endian magnitude without
+    /// leading zeros.
+    ///
+    pub(crate) fn read_uint(&mut self) -> SgxResult<&'a [u8]> {
+        let invalid = sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+        let content = self.read_tlv(TAG_INTEGER)?;
+        if content.is_empty() || content[0] & 0x80 != 0 {
+            return Err(invalid);
+        }
+        if content[0] == 0 {
+            // A leading zero is only allowed when the next byte has its top bit set.
+            if content.len() > 1 && content[1] & 0x80 == 0 {
+                return Err(invalid);
+            }
+            return Ok(&content[1..]);
+        }
+        Ok(content)
+    }
+
+    pub(crate) fn read_small_uint(&mut self) -> SgxResult<u32> {
+        let value = self.read_uint()?;
+        if value.len() > 4 {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        Ok(value.iter().fold(0_u32, |acc, b| (acc << 8) | u32::from(*b)))
+    }
+
+    ///
+    /// Reads a BIT STRING with no unused bits and returns its content.
+    ///
+    pub(crate) fn read_bit_string(&mut self) -> SgxResult<&'a [u8]> {
+        let content = self.read_tlv(TAG_BIT_STRING)?;
+        match content.split_first() {
+            Some((0, rest)) => Ok(rest),
+            _ => Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+        }
+    }
+
+    pub(crate) fn expect_empty(&self) -> SgxError {
+        if self.data.is_empty() {
+            Ok(())
+        } else {
+            Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)
+        }
+    }
+}
@@ -0,0 +1,277 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! Zeroizing Key and Nonce Wrappers
+//!
+//! The key types in sgx_types are plain arrays and structures that are `Copy`, so
+//! every assignment or by-value return leaves another copy of the key on the enclave
+//! stack and nothing ever wipes it. `SecretKey` keeps a key in a single `Box`
+//! allocation, is neither `Copy` nor `Clone`, and zeroes the key when dropped.
+//! The enclave side uses `AlignBox` instead; outside the enclave there is no
+//! alignment requirement to meet.
+//!
+//! `SecretKey<T>` dereferences to the sgx_types key `T`, so `&key` can be passed
+//! wherever the rsgx_* functions take `&T`, and it implements `KdfOutput` so keys
+//! can be derived straight into it.
+//!
+use sgx_types::*;
+use sgx_types::marker::ContiguousMemory;
+use std::boxed::Box;
+use crate::crypto::sgx_aes_ctr_128bit_ctr_t;
+use crate::internal::{zeroize, read_rand};
+use std::ops::{Deref, DerefMut};
+use std::borrow::{Borrow, BorrowMut};
+use std::fmt;
+use std::mem;
+use std::ptr;
+use std::slice;
+
+///
+/// Key types that can be held by a `SecretKey`.
+///
+/// Implementors must be plain bytes without padding, whose all-zero value is
+/// a valid, wiped state.
+///
+pub unsafe trait SecretMaterial: Copy + Default + ContiguousMemory {}
+
+unsafe impl SecretMaterial for sgx_key_128bit_t {}
+unsafe impl SecretMaterial for sgx_key_256bit_t {}
+unsafe impl SecretMaterial for sgx_ec256_private_t {}
+unsafe impl SecretMaterial for sgx_ec256_dh_shared_t {}
+unsafe impl SecretMaterial for sgx_ed25519_private_t {}
+unsafe impl SecretMaterial for sgx_x25519_private_t {}
+unsafe impl SecretMaterial for sgx_x25519_dh_shared_t {}
+
+///
+/// A key that lives in one heap allocation and is zeroed on drop.
+///
+pub struct SecretKey<T: SecretMaterial> {
+    inner: Box<T>,
+}
+
+/// AES-128, CMAC and AES-CTR keys.
+pub type SecretKey128 = SecretKey<sgx_key_128bit_t>;
+/// AES-256 and ChaCha20 keys.
+pub type SecretKey256 = SecretKey<sgx_key_256bit_t>;
+/// NIST P-256 private keys.
+pub type EcPrivateKey = SecretKey<sgx_ec256_private_t>;
+/// NIST P-256 Diffie-Hellman shared secrets.
+pub type EcSharedKey = SecretKey<sgx_ec256_dh_shared_t>;
+/// Ed25519 private keys.
+pub type Ed25519PrivateKey = SecretKey<sgx_ed25519_private_t>;
+/// X25519 private keys.
+pub type X25519PrivateKey = SecretKey<sgx_x25519_private_t>;
+/// X25519 shared secrets.
+pub type X25519SharedKey = SecretKey<sgx_x25519_dh_shared_t>;
+
+impl<T: SecretMaterial> SecretKey<T> {
+    ///
+    /// Allocates an all-zero key.
+    ///
+    /// The result is always Ok; the signature matches sgx_tcrypto, where the
+    /// aligned allocation can fail.
+    ///
+    pub fn new() -> SgxResult<Self> {
+        Ok(SecretKey { inner: Box::new(T::default()) })
+    }
+
+    ///
+    /// Allocates a key filled from sgx_read_rand.
+    ///
+    /// This is suitable for symmetric keys and for the Ed25519 and X25519 private
+    /// keys. NIST P-256 private keys must come from `SgxEccHandle::create_secret_key_pair`.
+    ///
+    pub fn random() -> SgxResult<Self> {
+        let mut key = Self::new()?;
+        read_rand(key.as_bytes_mut())?;
+        Ok(key)
+    }
+
+    ///
+    /// Copies a key from a byte slice, which must be exactly as long as `T`.
+    ///
+    pub fn from_slice(src: &[u8]) -> SgxResult<Self> {
+        if src.len() != mem::size_of::<T>() {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let mut key = Self::new()?;
+        key.as_bytes_mut().copy_from_slice(src);
+        Ok(key)
+    }
+
+    ///
+    /// Moves a key into a new `SecretKey` and zeroes `src`.
+    ///
+    pub fn take(src: &mut T) -> SgxResult<Self> {
+        let mut key = Self::new()?;
+        unsafe {
+            ptr::copy_nonoverlapping(src as * const T, &mut *key.inner as * mut T, 1);
+            zeroize(slice::from_raw_parts_mut(src as * mut T as * mut u8, mem::size_of::<T>()));
+        }
+        Ok(key)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(&*self.inner as * const T as * const u8, mem::size_of::<T>()) }
+    }
+
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(&mut *self.inner as * mut T as * mut u8, mem::size_of::<T>()) }
+    }
+}
+
+impl<T: SecretMaterial> Deref for SecretKey<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: SecretMaterial> DerefMut for SecretKey<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
+
+impl<T: SecretMaterial> AsRef<T> for SecretKey<T> {
+    fn as_ref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: SecretMaterial> AsMut<T> for SecretKey<T> {
+    fn as_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
+
+impl<T: SecretMaterial> Borrow<T> for SecretKey<T> {
+    fn borrow(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: SecretMaterial> BorrowMut<T> for SecretKey<T> {
+    fn borrow_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
+
+impl<T: SecretMaterial> fmt::Debug for SecretKey<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SecretKey { .. }")
+    }
+}
+
+impl<T: SecretMaterial> Drop for SecretKey<T> {
+    fn drop(&mut self) {
+        zeroize(self.as_bytes_mut());
+    }
+}
+
+///
+/// Nonce types that can be held by a `Nonce`.
+///
+pub unsafe trait NonceMaterial: Copy + Default + ContiguousMemory {}
+
+unsafe impl NonceMaterial for [u8; 12] {}
+unsafe impl NonceMaterial for [u8; 16] {}
+unsafe impl NonceMaterial for [u8; 24] {}
+
+///
+/// A typed nonce or IV.
+///
+/// Nonces are not secret, so a `Nonce` stays on the stack and is not wiped. It is
+/// neither `Copy` nor `Clone`, which makes reusing one for a second message a
+/// deliberate act rather than an accidental copy.
+///
+pub struct Nonce<T: NonceMaterial> {
+    value: T,
+}
+
+/// AES-GCM IVs.
+pub type AesGcmNonce = Nonce<[u8; SGX_AESGCM_IV_SIZE]>;
+/// AES-GCM-SIV nonces.
+pub type AesGcmSivNonce = Nonce<[u8; SGX_AESGCMSIV_NONCE_SIZE]>;
+/// ChaCha20-Poly1305 nonces.
+pub type ChaCha20Poly1305Nonce = Nonce<[u8; SGX_CHACHA20POLY1305_NONCE_SIZE]>;
+/// XChaCha20-Poly1305 nonces.
+pub type XChaCha20Poly1305Nonce = Nonce<[u8; SGX_XCHACHA20POLY1305_NONCE_SIZE]>;
+/// AES-CTR initial counter blocks.
+pub type AesCtrNonce = Nonce<sgx_aes_ctr_128bit_ctr_t>;
+
+impl<T: NonceMaterial> Nonce<T> {
+    pub fn new(value: T) -> Self {
+        Nonce { value }
+    }
+
+    ///
+    /// Draws a nonce from sgx_read_rand.
+    ///
+    pub fn random() -> SgxResult<Self> {
+        let mut nonce = Nonce { value: T::default() };
+        read_rand(nonce.as_bytes_mut())?;
+        Ok(nonce)
+    }
+
+    ///
+    /// Copies a nonce from a byte slice, which must be exactly as long as `T`.
+    ///
+    pub fn from_slice(src: &[u8]) -> SgxResult<Self> {
+        if src.len() != mem::size_of::<T>() {
+            return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+        }
+        let mut nonce = Nonce { value: T::default() };
+        nonce.as_bytes_mut().copy_from_slice(src);
+        Ok(nonce)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(&self.value as * const T as * const u8, mem::size_of::<T>()) }
+    }
+
+    fn as_bytes_mut(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(&mut self.value as * mut T as * mut u8, mem::size_of::<T>()) }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T: NonceMaterial> Deref for Nonce<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: NonceMaterial> AsRef<[u8]> for Nonce<T> {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl<T: NonceMaterial> fmt::Debug for Nonce<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Nonce").field(&self.as_bytes()).finish()
+    }
+}
@@ -0,0 +1,133 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! X25519 Key Agreement Functions
+//!
+//! Elliptic curve Diffie-Hellman over Curve25519 (RFC 7748), the Curve25519
+//! counterpart of `SgxEccHandle::compute_shared_dhkey`.
+//!
+use sgx_types::*;
+use crate::curve25519::x25519;
+use crate::internal::read_rand;
+
+// The u-coordinate of the base point.
+const X25519_BASEPOINT: [u8; 32] = [9, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                                    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+///
+/// rsgx_x25519_create_key_pair generates an X25519 key pair.
+///
+/// # Description
+///
+/// The private key is 32 random bytes from sgx_read_rand. It is stored unclamped;
+/// clamping is applied whenever the key is used, as RFC 7748 specifies.
+///
+/// # Requirements
+///
+/// Library: libsgx_tcrypto.a, libsgx_trts.a
+///
+/// # Return value
+///
+/// The private key and the public key.
+///
+/// # Errors
+///
+/// **SGX_ERROR_UNEXPECTED**
+///
+/// Random number generation failed.
+///
+pub fn rsgx_x25519_create_key_pair() -> SgxResult<(sgx_x25519_private_t, sgx_x25519_public_t)> {
+    let mut private = sgx_x25519_private_t::default();
+    read_rand(&mut private.scalar)?;
+    let public = rsgx_x25519_public_from_private(&private)?;
+    Ok((private, public))
+}
+
+///
+/// rsgx_align_x25519_create_key_pair is the same as rsgx_x25519_create_key_pair, but
+/// returns the private key in an aligned structure.
+///
+pub fn rsgx_align_x25519_create_key_pair() -> SgxResult<(sgx_align_x25519_private_t, sgx_x25519_public_t)> {
+    let mut private = sgx_align_x25519_private_t::default();
+    read_rand(&mut private.key.scalar)?;
+    let public = rsgx_x25519_public_from_private(&private.key)?;
+    Ok((private, public))
+}
+
+///
+/// rsgx_x25519_public_from_private derives the public key that belongs to an X25519 private key.
+///
+pub fn rsgx_x25519_public_from_private(private: &sgx_x25519_private_t) -> SgxResult<sgx_x25519_public_t> {
+    Ok(sgx_x25519_public_t { key: x25519(&private.scalar, &X25519_BASEPOINT) })
+}
+
+///
+/// rsgx_x25519_compute_shared_dhkey computes the X25519 shared secret.
+///
+/// # Description
+///
+/// The shared secret is the raw output of the X25519 function. It should not be used as a
+/// key directly; pass it through a KDF such as rsgx_hkdf_sha256 first.
+///
+/// # Parameters
+///
+/// **private**
+///
+/// The local private key.
+///
+/// **peer_public**
+///
+/// The public key of the peer.
+///
+/// # Requirements
+///
+/// Library: libsgx_tcrypto.a
+///
+/// # Return value
+///
+/// The shared secret.
+///
+/// # Errors
+///
+/// **SGX_ERROR_INVALID_PARAMETER**
+///
+/// The peer public key is a low-order point, so the shared secret would be all zeros and
+/// independent of the private key.
+///
+pub fn rsgx_x25519_compute_shared_dhkey(private: &sgx_x25519_private_t,
+                                        peer_public: &sgx_x25519_public_t) -> SgxResult<sgx_x25519_dh_shared_t> {
+
+    let shared = sgx_x25519_dh_shared_t { s: x25519(&private.scalar, &peer_public.key) };
+    if shared.s.iter().fold(0_u8, |acc, b| acc | b) == 0 {
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    Ok(shared)
+}
+
+///
+/// rsgx_x25519_compute_align_shared_dhkey is the same as rsgx_x25519_compute_shared_dhkey,
+/// but returns the shared secret in an aligned structure.
+///
+pub fn rsgx_x25519_compute_align_shared_dhkey(private: &sgx_x25519_private_t,
+                                              peer_public: &sgx_x25519_public_t) -> SgxResult<sgx_align_x25519_dh_shared_t> {
+
+    let mut align_shared = sgx_align_x25519_dh_shared_t::default();
+    align_shared.key = rsgx_x25519_compute_shared_dhkey(private, peer_public)?;
+    Ok(align_shared)
+}
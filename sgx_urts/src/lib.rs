@@ -33,5 +33,7 @@ pub mod pipe;
 pub mod event;
 pub mod thread;
 pub mod net;
+pub mod net_switchless;
+pub mod quote;
 pub use enclave::*;
 
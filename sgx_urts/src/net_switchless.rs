@@ -0,0 +1,195 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+//!
+//! Untrusted side of `sgx_net_switchless.edl`.
+//!
+//! The switchless socket OCALLs share their implementation with the regular
+//! ones; only the EDL attribute differs.
+//!
+
+use libc::{c_int, c_void, size_t, ssize_t, sockaddr, socklen_t, msghdr, pollfd, nfds_t};
+use crate::{asyncio, fd, socket};
+
+#[no_mangle]
+pub extern "C" fn u_net_socket_ocall(error: * mut c_int,
+                                     domain: c_int,
+                                     ty: c_int,
+                                     protocol: c_int) -> c_int {
+    socket::u_socket_ocall(error, domain, ty, protocol)
+}
+
+#[no_mangle]
+pub extern "C" fn u_net_socketpair_ocall(error: * mut c_int,
+                                         domain: c_int,
+                                         ty: c_int,
+                                         protocol: c_int,
+                                         sv: * mut c_int) -> c_int {
+    socket::u_socketpair_ocall(error, domain, ty, protocol, sv)
+}
+
+#[no_mangle]
+pub extern "C" fn u_net_bind_ocall(error: * mut c_int,
+                                   sockfd: c_int,
+                                   address: * const sockaddr,
+                                   addrlen: socklen_t) -> c_int {
+    socket::u_bind_ocall(error, sockfd, address, addrlen)
+}
+
+#[no_mangle]
+pub extern "C" fn u_net_listen_ocall(error: * mut c_int,
+                                     sockfd: c_int,
+                                     backlog: c_int) -> c_int {
+    socket::u_listen_ocall(error, sockfd, backlog)
+}
+
+#[no_mangle]
+pub extern "C" fn u_net_accept4_ocall(error: * mut c_int,
+                                      sockfd: c_int,
+                                      addr: * mut sockaddr,
+                                      addrlen_in: socklen_t,
+                                      addrlen_out: * mut socklen_t,
+                                      flags: c_int) -> c_int {
+    socket::u_accept4_ocall(error, sockfd, addr, addrlen_in, addrlen_out, flags)
+}
+
+#[no_mangle]
+pub extern "C" fn u_net_connect_ocall(error: * mut c_int,
+                                      sockfd: c_int,
+                                      address: * const sockaddr,
+                                      addrlen: socklen_t) -> c_int {
+    socket::u_connect_ocall(error, sockfd, address, addrlen)
+}
+
+#[no_mangle]
+pub extern "C" fn u_net_recv_ocall(error: * mut c_int,
+                                   sockfd: c_int,
+                                   buf: * mut c_void,
+                                   len: size_t,
+                                   flags: c_int) -> ssize_t {
+    socket::u_recv_ocall(error, sockfd, buf, len, flags)
+}
+
+#[no_mangle]
+pub extern "C" fn u_net_recvfrom_ocall(error: * mut c_int,
+                                       sockfd: c_int,
+                                       buf: * mut c_void,
+                                       len: size_t,
+                                       flags: c_int,
+                                       src_addr: * mut sockaddr,
+                                       addrlen_in: socklen_t,
+                                       addrlen_out: * mut socklen_t) -> ssize_t {
+    socket::u_recvfrom_ocall(error, sockfd, buf, len, flags, src_addr, addrlen_in, addrlen_out)
+}
+
+#[no_mangle]
+pub extern "C" fn u_net_recvmsg_ocall(error: * mut c_int,
+                                      sockfd: c_int,
+                                      msg: * mut msghdr,
+                                      flags: c_int) -> ssize_t {
+    socket::u_recvmsg_ocall(error, sockfd, msg, flags)
+}
+
+#[no_mangle]
+pub extern "C" fn u_net_send_ocall(error: * mut c_int,
+                                   sockfd: c_int,
+                                   buf: * const c_void,
+                                   len: size_t,
+                                   flags: c_int) -> ssize_t {
+    socket::u_send_ocall(error, sockfd, buf, len, flags)
+}
+
+#[no_mangle]
+pub extern "C" fn u_net_sendto_ocall(error: * mut c_int,
+                                     sockfd: c_int,
+                                     buf: * const c_void,
+                                     len: size_t,
+                                     flags: c_int,
+                                     dest_addr: * const sockaddr,
+                                     addrlen: socklen_t) -> ssize_t {
+    socket::u_sendto_ocall(error, sockfd, buf, len, flags, dest_addr, addrlen)
+}
+
+#[no_mangle]
+pub extern "C" fn u_net_sendmsg_ocall(error: * mut c_int,
+                                      sockfd: c_int,
+                                      msg: * const msghdr,
+                                      flags: c_int) -> ssize_t {
+    socket::u_sendmsg_ocall(error, sockfd, msg, flags)
+}
+
+#[no_mangle]
+pub extern "C" fn u_net_getsockopt_ocall(error: * mut c_int,
+                                         sockfd: c_int,
+                                         level: c_int,
+                                         optname: c_int,
+                                         optval: * mut c_void,
+                                         optlen_in: socklen_t,
+                                         optlen_out: * mut socklen_t) -> c_int {
+    socket::u_getsockopt_ocall(error, sockfd, level, optname, optval, optlen_in, optlen_out)
+}
+
+#[no_mangle]
+pub extern "C" fn u_net_setsockopt_ocall(error: * mut c_int,
+                                         sockfd: c_int,
+                                         level: c_int,
+                                         optname: c_int,
+                                         optval: * const c_void,
+                                         optlen: socklen_t) -> c_int {
+    socket::u_setsockopt_ocall(error, sockfd, level, optname, optval, optlen)
+}
+
+#[no_mangle]
+pub extern "C" fn u_net_getsockname_ocall(error: * mut c_int,
+                                          sockfd: c_int,
+                                          addr: * mut sockaddr,
+                                          addrlen_in: socklen_t,
+                                          addrlen_out: * mut socklen_t) -> c_int {
+    socket::u_getsockname_ocall(error, sockfd, addr, addrlen_in, addrlen_out)
+}
+
+#[no_mangle]
+pub extern "C" fn u_net_getpeername_ocall(error: * mut c_int,
+                                          sockfd: c_int,
+                                          addr: * mut sockaddr,
+                                          addrlen_in: socklen_t,
+                                          addrlen_out: * mut socklen_t) -> c_int {
+    socket::u_getpeername_ocall(error, sockfd, addr, addrlen_in, addrlen_out)
+}
+
+#[no_mangle]
+pub extern "C" fn u_net_shutdown_ocall(error: * mut c_int,
+                                       sockfd: c_int,
+                                       how: c_int) -> c_int {
+    socket::u_shutdown_ocall(error, sockfd, how)
+}
+
+#[no_mangle]
+pub extern "C" fn u_net_ioctl_ocall(error: * mut c_int,
+                                    fd: c_int,
+                                    request: c_int,
+                                    arg: * mut c_int) -> c_int {
+    fd::u_ioctl_arg1_ocall(error, fd, request, arg)
+}
+
+#[no_mangle]
+pub extern "C" fn u_net_poll_ocall(error: * mut c_int,
+                                   fds: * mut pollfd,
+                                   nfds: nfds_t,
+                                   timeout: c_int) -> c_int {
+    asyncio::u_poll_ocall(error, fds, nfds, timeout)
+}
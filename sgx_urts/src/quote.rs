@@ -0,0 +1,274 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License..
+
+
+//!
+//! Untrusted-side quote generation.
+//!
+//! [`EpidQuoter`] wraps the EPID quoting enclave (`libsgx_uae_service` or
+//! `libsgx_epid`), and [`DcapQuoter`] wraps the ECDSA quoting enclave of the
+//! DCAP quote library (`libsgx_dcap_ql`). The application remains responsible
+//! for linking the library of the quoter it uses.
+//!
+//! Both implement [`Quoter`]: fetch the QE target info, hand it to the
+//! enclave so that it can create a report targeting the QE, then turn the
+//! report into a quote.
+//!
+
+use sgx_types::*;
+use std::error::Error;
+use std::fmt;
+use std::ptr;
+
+/// The error type for quote generation.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum QuoteError {
+    /// The EPID quoting library returned an error.
+    Epid(sgx_status_t),
+    /// The DCAP quote library returned an error.
+    Dcap(sgx_quote3_error_t),
+    /// The revocation list does not fit in the 32-bit size of the API.
+    SigRlTooLarge,
+}
+
+impl fmt::Display for QuoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            QuoteError::Epid(status) => write!(f, "EPID quoting failed: {}", status),
+            QuoteError::Dcap(status) => write!(f, "DCAP quoting failed: {}", status),
+            QuoteError::SigRlTooLarge => write!(f, "signature revocation list is too large"),
+        }
+    }
+}
+
+impl Error for QuoteError {}
+
+impl From<QuoteError> for sgx_status_t {
+    fn from(err: QuoteError) -> sgx_status_t {
+        match err {
+            QuoteError::Epid(status) => status,
+            QuoteError::Dcap(sgx_quote3_error_t::SGX_QL_ERROR_INVALID_PARAMETER) |
+            QuoteError::SigRlTooLarge => sgx_status_t::SGX_ERROR_INVALID_PARAMETER,
+            QuoteError::Dcap(sgx_quote3_error_t::SGX_QL_ERROR_OUT_OF_MEMORY) => sgx_status_t::SGX_ERROR_OUT_OF_MEMORY,
+            QuoteError::Dcap(_) => sgx_status_t::SGX_ERROR_UNEXPECTED,
+        }
+    }
+}
+
+pub type QuoteResult<T> = Result<T, QuoteError>;
+
+/// A source of quotes for reports targeting its quoting enclave.
+pub trait Quoter {
+    /// Returns the target info of the quoting enclave.
+    ///
+    /// The enclave to be attested passes it to `sgx_create_report` so that
+    /// the resulting report can be verified by the quoting enclave.
+    fn target_info(&self) -> QuoteResult<sgx_target_info_t>;
+
+    /// Converts `report` into a quote.
+    fn quote(&self, report: &sgx_report_t) -> QuoteResult<Vec<u8>>;
+}
+
+/// Generates EPID quotes.
+///
+/// The quote embeds the service provider ID and is signed with the given
+/// signature type. A signature revocation list obtained from the attestation
+/// service for the platform's group can be installed with `set_sigrl`; it is
+/// empty by default.
+#[derive(Clone)]
+pub struct EpidQuoter {
+    spid: sgx_spid_t,
+    quote_type: sgx_quote_sign_type_t,
+    sigrl: Vec<u8>,
+}
+
+impl EpidQuoter {
+    pub fn new(spid: sgx_spid_t, quote_type: sgx_quote_sign_type_t) -> EpidQuoter {
+        EpidQuoter {
+            spid,
+            quote_type,
+            sigrl: Vec::new(),
+        }
+    }
+
+    /// Initializes quoting and returns the QE target info together with the
+    /// EPID group ID of the platform.
+    ///
+    /// The group ID is what the attestation service needs to look up the
+    /// signature revocation list.
+    ///
+    /// # Errors
+    ///
+    /// Returns `QuoteError::Epid` with the status of `sgx_init_quote`.
+    pub fn init() -> QuoteResult<(sgx_target_info_t, sgx_epid_group_id_t)> {
+        let mut target_info = sgx_target_info_t::default();
+        let mut gid: sgx_epid_group_id_t = [0; 4];
+        let ret = unsafe { sgx_init_quote(&mut target_info, &mut gid) };
+        match ret {
+            sgx_status_t::SGX_SUCCESS => Ok((target_info, gid)),
+            _ => Err(QuoteError::Epid(ret)),
+        }
+    }
+
+    pub fn spid(&self) -> &sgx_spid_t {
+        &self.spid
+    }
+
+    pub fn quote_type(&self) -> sgx_quote_sign_type_t {
+        self.quote_type
+    }
+
+    pub fn sigrl(&self) -> &[u8] {
+        &self.sigrl
+    }
+
+    /// Installs the signature revocation list to check the platform against.
+    pub fn set_sigrl(&mut self, sigrl: Vec<u8>) {
+        self.sigrl = sigrl;
+    }
+
+    /// Returns the size of a quote produced with the current revocation list.
+    pub fn quote_size(&self) -> QuoteResult<u32> {
+        let (p_sigrl, sigrl_size) = self.sigrl_raw()?;
+        let mut size: u32 = 0;
+        let ret = unsafe { sgx_calc_quote_size(p_sigrl, sigrl_size, &mut size) };
+        match ret {
+            sgx_status_t::SGX_SUCCESS => Ok(size),
+            _ => Err(QuoteError::Epid(ret)),
+        }
+    }
+
+    /// Converts `report` into a quote and also returns a report of the QE
+    /// over `nonce` and the quote.
+    ///
+    /// The enclave that requested the quote can verify the QE report to make
+    /// sure the quote came from the genuine quoting enclave and is fresh.
+    pub fn quote_with_nonce(&self,
+                            report: &sgx_report_t,
+                            nonce: &sgx_quote_nonce_t) -> QuoteResult<(Vec<u8>, sgx_report_t)> {
+        let mut qe_report = sgx_report_t::default();
+        let quote = self.get_quote(report, nonce, &mut qe_report)?;
+        Ok((quote, qe_report))
+    }
+
+    fn get_quote(&self,
+                 report: &sgx_report_t,
+                 p_nonce: * const sgx_quote_nonce_t,
+                 p_qe_report: * mut sgx_report_t) -> QuoteResult<Vec<u8>> {
+        let (p_sigrl, sigrl_size) = self.sigrl_raw()?;
+        let size = self.quote_size()?;
+        let mut quote = vec![0_u8; size as usize];
+        let ret = unsafe {
+            sgx_get_quote(report,
+                          self.quote_type,
+                          &self.spid,
+                          p_nonce,
+                          p_sigrl,
+                          sigrl_size,
+                          p_qe_report,
+                          quote.as_mut_ptr() as * mut sgx_quote_t,
+                          size)
+        };
+        match ret {
+            sgx_status_t::SGX_SUCCESS => Ok(quote),
+            _ => Err(QuoteError::Epid(ret)),
+        }
+    }
+
+    fn sigrl_raw(&self) -> QuoteResult<(* const u8, u32)> {
+        if self.sigrl.is_empty() {
+            return Ok((ptr::null(), 0));
+        }
+        if self.sigrl.len() > u32::max_value() as usize {
+            return Err(QuoteError::SigRlTooLarge);
+        }
+        Ok((self.sigrl.as_ptr(), self.sigrl.len() as u32))
+    }
+}
+
+impl Quoter for EpidQuoter {
+    fn target_info(&self) -> QuoteResult<sgx_target_info_t> {
+        EpidQuoter::init().map(|(target_info, _)| target_info)
+    }
+
+    fn quote(&self, report: &sgx_report_t) -> QuoteResult<Vec<u8>> {
+        self.get_quote(report, ptr::null(), ptr::null_mut())
+    }
+}
+
+/// Generates ECDSA quotes through the DCAP quote library.
+pub struct DcapQuoter {
+    _priv: (),
+}
+
+impl DcapQuoter {
+    /// Uses the default (persistent) QE load policy.
+    pub fn new() -> DcapQuoter {
+        DcapQuoter { _priv: () }
+    }
+
+    /// Sets the load policy of the quoting enclaves for this process.
+    ///
+    /// # Errors
+    ///
+    /// Returns `QuoteError::Dcap` with the status of
+    /// `sgx_qe_set_enclave_load_policy`.
+    pub fn with_load_policy(policy: sgx_ql_request_policy_t) -> QuoteResult<DcapQuoter> {
+        dcap_result(unsafe { sgx_qe_set_enclave_load_policy(policy) })?;
+        Ok(DcapQuoter::new())
+    }
+
+    /// Returns the size of a quote produced by the QE.
+    pub fn quote_size(&self) -> QuoteResult<u32> {
+        let mut size: u32 = 0;
+        dcap_result(unsafe { sgx_qe_get_quote_size(&mut size) })?;
+        Ok(size)
+    }
+
+    /// Unloads the quoting enclaves if the load policy allows it.
+    pub fn cleanup(&self) -> QuoteResult<()> {
+        dcap_result(unsafe { sgx_qe_cleanup_by_policy() })
+    }
+}
+
+impl Default for DcapQuoter {
+    fn default() -> DcapQuoter {
+        DcapQuoter::new()
+    }
+}
+
+impl Quoter for DcapQuoter {
+    fn target_info(&self) -> QuoteResult<sgx_target_info_t> {
+        let mut target_info = sgx_target_info_t::default();
+        dcap_result(unsafe { sgx_qe_get_target_info(&mut target_info) })?;
+        Ok(target_info)
+    }
+
+    fn quote(&self, report: &sgx_report_t) -> QuoteResult<Vec<u8>> {
+        let size = self.quote_size()?;
+        let mut quote = vec![0_u8; size as usize];
+        dcap_result(unsafe { sgx_qe_get_quote(report, size, quote.as_mut_ptr()) })?;
+        Ok(quote)
+    }
+}
+
+fn dcap_result(ret: sgx_quote3_error_t) -> QuoteResult<()> {
+    match ret {
+        sgx_quote3_error_t::SGX_QL_SUCCESS => Ok(()),
+        _ => Err(QuoteError::Dcap(ret)),
+    }
+}